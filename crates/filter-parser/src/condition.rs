@@ -1,20 +1,32 @@
 //! BNF grammar:
 //!
 //! ```text
-//! condition      = value ("==" | ">" ...) value
-//! to             = value value TO value
+//! condition      = field ("==" | ">" ...) (value | fieldReference)
+//! fieldReference = "_field(" WS* value WS* ")"
+//! field          = length | strlen | value
+//! length         = "_length(" WS* value WS* ")"
+//! strlen         = "_strlen(" WS* value WS* ")"
+//! to             = field value TO value
+//! approximately  = value ("NEAR" value "TOLERANCE" | "≈" value "±") value
+//! fuzzyEqual     = value "~=" value ("DISTANCE" value)?
+//! matches        = value "MATCHES" value
+//! modulo         = value "MOD" value "=" value
 //! ```
 
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::multispace1;
-use nom::combinator::cut;
-use nom::sequence::{terminated, tuple};
+use nom::character::complete::{char, multispace0, multispace1};
+use nom::combinator::{cut, map, opt, peek};
+use nom::sequence::{delimited, preceded, terminated, tuple};
 use Condition::*;
 
-use super::{parse_value, FilterCondition, IResult, Span, Token};
+use super::value::word_exact;
+use super::{
+    parse_bracketed_value_list, parse_value, ws, Error, ErrorKind, FilterCondition, IResult, Span,
+    Token,
+};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Condition<'a> {
     GreaterThan(Token<'a>),
     GreaterThanOrEqual(Token<'a>),
@@ -23,11 +35,53 @@ pub enum Condition<'a> {
     Null,
     Empty,
     Exists,
+    IsTrue,
+    IsFalse,
+    IsDate,
     LowerThan(Token<'a>),
     LowerThanOrEqual(Token<'a>),
-    Between { from: Token<'a>, to: Token<'a> },
-    Contains { keyword: Token<'a>, word: Token<'a> },
-    StartsWith { keyword: Token<'a>, word: Token<'a> },
+    Between {
+        from: Token<'a>,
+        to: Token<'a>,
+    },
+    Contains {
+        keyword: Token<'a>,
+        word: Token<'a>,
+    },
+    StartsWith {
+        keyword: Token<'a>,
+        word: Token<'a>,
+    },
+    StartsWithAny {
+        keyword: Token<'a>,
+        words: Vec<Token<'a>>,
+    },
+    Approximately {
+        value: Token<'a>,
+        tolerance: Token<'a>,
+    },
+    /// Matches the string values within `max_distance` typos of the `value`,
+    /// the distance depends on the length of the value when not given.
+    FuzzyEqual {
+        value: Token<'a>,
+        max_distance: Option<Token<'a>>,
+    },
+    /// Matches the string values found by the regex `pattern`, which is checked when parsing.
+    Regex {
+        pattern: Token<'a>,
+    },
+    /// Matches the integer values whose remainder of the division by the `divisor`
+    /// is the `remainder`, both are integers checked when parsing, see [`modulo_operands`].
+    Modulo {
+        divisor: Token<'a>,
+        remainder: Token<'a>,
+    },
+    /// Compares the field to the `other` field of the same document with the `operator`,
+    /// one of `=`, `!=`, `<`, `<=`, `>` or `>=`.
+    CompareFields {
+        operator: Token<'a>,
+        other: Token<'a>,
+    },
 }
 
 impl Condition<'_> {
@@ -40,31 +94,104 @@ impl Condition<'_> {
             Condition::Null => "IS NULL",
             Condition::Empty => "IS EMPTY",
             Condition::Exists => "EXISTS",
+            Condition::IsTrue => "IS TRUE",
+            Condition::IsFalse => "IS FALSE",
+            Condition::IsDate => "IS DATE",
             Condition::LowerThan(_) => "<",
             Condition::LowerThanOrEqual(_) => "<=",
             Condition::Between { .. } => "TO",
             Condition::Contains { .. } => "CONTAINS",
             Condition::StartsWith { .. } => "STARTS WITH",
+            Condition::StartsWithAny { .. } => "STARTS WITH ANY",
+            Condition::Approximately { .. } => "NEAR",
+            Condition::FuzzyEqual { .. } => "~=",
+            Condition::Regex { .. } => "MATCHES",
+            Condition::Modulo { .. } => "MOD",
+            Condition::CompareFields { .. } => "_field",
         }
     }
 }
 
-/// condition      = value ("==" | ">" ...) value
+/// The function selecting the number of distinct values of a field instead of its values.
+pub const LENGTH_FUNCTION: &str = "_length";
+
+/// The function selecting the number of characters of the string values of a field instead of its values.
+pub const STRLEN_FUNCTION: &str = "_strlen";
+
+/// Builds the condition on a field or on the value selected by a function of the field.
+type ConditionBuilder = for<'a> fn(Token<'a>, Condition<'a>) -> FilterCondition<'a>;
+
+fn field_condition<'a>(fid: Token<'a>, op: Condition<'a>) -> FilterCondition<'a> {
+    FilterCondition::Condition { fid, op }
+}
+
+fn length_condition<'a>(fid: Token<'a>, op: Condition<'a>) -> FilterCondition<'a> {
+    FilterCondition::Length { fid, op }
+}
+
+fn string_length_condition<'a>(fid: Token<'a>, op: Condition<'a>) -> FilterCondition<'a> {
+    FilterCondition::StringLength { fid, op }
+}
+
+/// field          = length | strlen | value
+fn parse_field(input: Span) -> IResult<(Token, ConditionBuilder)> {
+    alt((
+        map(parse_field_function(LENGTH_FUNCTION), |fid| {
+            (fid, length_condition as ConditionBuilder)
+        }),
+        map(parse_field_function(STRLEN_FUNCTION), |fid| {
+            (fid, string_length_condition as ConditionBuilder)
+        }),
+        map(parse_value, |fid| (fid, field_condition as ConditionBuilder)),
+    ))(input)
+}
+
+/// length         = "_length(" WS* value WS* ")"
+/// strlen         = "_strlen(" WS* value WS* ")"
+///
+/// Only fails once the opening parenthesis is found, so a field named like the function is still a field.
+fn parse_field_function<'a>(name: &'static str) -> impl Fn(Span<'a>) -> IResult<'a, Token<'a>> {
+    move |input| {
+        preceded(
+            tuple((multispace0, word_exact(name), char('('))),
+            cut(terminated(terminated(ws(parse_value), char(')')), multispace0)),
+        )(input)
+        .map_err(|e| e.map(|_| Error::new_from_kind(input, ErrorKind::FieldFunction(name))))
+    }
+}
+
+/// condition      = field ("==" | ">" ...) (value | fieldReference)
 pub fn parse_condition(input: Span) -> IResult<FilterCondition> {
     let operator = alt((tag("<="), tag(">="), tag("!="), tag("<"), tag(">"), tag("=")));
-    let (input, (fid, op, value)) = tuple((parse_value, operator, cut(parse_value)))(input)?;
-
-    let condition = match *op.fragment() {
-        "<=" => FilterCondition::Condition { fid, op: LowerThanOrEqual(value) },
-        ">=" => FilterCondition::Condition { fid, op: GreaterThanOrEqual(value) },
-        "!=" => FilterCondition::Condition { fid, op: NotEqual(value) },
-        "<" => FilterCondition::Condition { fid, op: LowerThan(value) },
-        ">" => FilterCondition::Condition { fid, op: GreaterThan(value) },
-        "=" => FilterCondition::Condition { fid, op: Equal(value) },
+    let (input, ((fid, condition), op, other)) =
+        tuple((parse_field, operator, opt(parse_field_reference)))(input)?;
+    if let Some(other) = other {
+        let operator = Token::new(op, None);
+        return Ok((input, condition(fid, CompareFields { operator, other })));
+    }
+    let (input, value) = cut(parse_value)(input)?;
+
+    let op = match *op.fragment() {
+        "<=" => LowerThanOrEqual(value),
+        ">=" => GreaterThanOrEqual(value),
+        "!=" => NotEqual(value),
+        "<" => LowerThan(value),
+        ">" => GreaterThan(value),
+        "=" => Equal(value),
         _ => unreachable!(),
     };
 
-    Ok((input, condition))
+    Ok((input, condition(fid, op)))
+}
+
+/// fieldReference = WS* "_field(" WS* value WS* ")"
+/// If we parse `_field` we MUST parse the rest of the expression.
+fn parse_field_reference(input: Span) -> IResult<Token> {
+    preceded(
+        tuple((multispace0, word_exact("_field"))),
+        cut(terminated(delimited(char('('), ws(parse_value), char(')')), multispace0)),
+    )(input)
+    .map_err(|e| e.map(|_| Error::new_from_kind(input, ErrorKind::FieldReference)))
 }
 
 /// null          = value "IS" WS+ "NULL"
@@ -99,6 +226,32 @@ pub fn parse_is_not_empty(input: Span) -> IResult<FilterCondition> {
     Ok((input, FilterCondition::Not(Box::new(FilterCondition::Condition { fid: key, op: Empty }))))
 }
 
+/// boolean        = value "IS" WS+ ("TRUE" | "FALSE")
+pub fn parse_is_boolean(input: Span) -> IResult<FilterCondition> {
+    let (input, key) = parse_value(input)?;
+
+    let (input, (_, _, boolean)) =
+        tuple((tag("IS"), multispace1, alt((tag("TRUE"), tag("FALSE")))))(input)?;
+    let op = if *boolean.fragment() == "TRUE" { IsTrue } else { IsFalse };
+    Ok((input, FilterCondition::Condition { fid: key, op }))
+}
+
+/// date           = value "IS" WS+ "DATE"
+pub fn parse_is_date(input: Span) -> IResult<FilterCondition> {
+    let (input, key) = parse_value(input)?;
+
+    let (input, _) = tuple((tag("IS"), multispace1, tag("DATE")))(input)?;
+    Ok((input, FilterCondition::Condition { fid: key, op: IsDate }))
+}
+
+/// date           = value "IS" WS+ "NOT" WS+ "DATE"
+pub fn parse_is_not_date(input: Span) -> IResult<FilterCondition> {
+    let (input, key) = parse_value(input)?;
+
+    let (input, _) = tuple((tag("IS"), multispace1, tag("NOT"), multispace1, tag("DATE")))(input)?;
+    Ok((input, FilterCondition::Not(Box::new(FilterCondition::Condition { fid: key, op: IsDate }))))
+}
+
 /// exist          = value "EXISTS"
 pub fn parse_exists(input: Span) -> IResult<FilterCondition> {
     let (input, key) = terminated(parse_value, tag("EXISTS"))(input)?;
@@ -154,6 +307,24 @@ pub fn parse_starts_with(input: Span) -> IResult<FilterCondition> {
     ))
 }
 
+/// starts with any    = value "STARTS WITH" WS+ "ANY" WS* "[" value_list "]"
+pub fn parse_starts_with_any(input: Span) -> IResult<FilterCondition> {
+    let (input, (fid, starts_with, _, _, _)) =
+        tuple((parse_value, tag("STARTS WITH"), multispace1, word_exact("ANY"), multispace0))(
+            input,
+        )?;
+    // without a bracket, `ANY` is the prefix of a simple `STARTS WITH`
+    let (input, _) = peek(tag("["))(input)?;
+    let (input, words) = parse_bracketed_value_list(input)?;
+    Ok((
+        input,
+        FilterCondition::Condition {
+            fid,
+            op: StartsWithAny { keyword: Token { span: starts_with, value: None }, words },
+        },
+    ))
+}
+
 /// starts with        = value "NOT" WS+ "CONTAINS" value
 pub fn parse_not_starts_with(input: Span) -> IResult<FilterCondition> {
     let keyword = tuple((tag("NOT"), multispace1, tag("STARTS WITH")));
@@ -169,10 +340,112 @@ pub fn parse_not_starts_with(input: Span) -> IResult<FilterCondition> {
     ))
 }
 
-/// to             = value value "TO" WS+ value
+/// approximately  = value ("NEAR" value "TOLERANCE" | "≈" value "±") value
+pub fn parse_approximately(input: Span) -> IResult<FilterCondition> {
+    let near =
+        tuple((map(word_exact("NEAR"), |_| ()), parse_value, map(word_exact("TOLERANCE"), |_| ())));
+    let symbol = tuple((map(tag("≈"), |_| ()), parse_value, map(tag("±"), |_| ())));
+    let (input, (fid, (_, value, _), tolerance)) =
+        tuple((parse_value, alt((near, symbol)), cut(parse_value)))(input)?;
+
+    // both bounds are computed from the value and the tolerance, they must be numbers
+    value.parse_finite_float().map_err(nom::Err::Failure)?;
+    if tolerance.parse_finite_float().map_err(nom::Err::Failure)? < 0.0 {
+        return Err(nom::Err::Failure(Error::new_from_kind(
+            tolerance.original_span(),
+            ErrorKind::NegativeTolerance,
+        )));
+    }
+
+    Ok((input, FilterCondition::Condition { fid, op: Approximately { value, tolerance } }))
+}
+
+/// fuzzyEqual     = value "~=" value ("DISTANCE" value)?
+pub fn parse_fuzzy_equal(input: Span) -> IResult<FilterCondition> {
+    let (input, (fid, _, value)) = tuple((parse_value, tag("~="), cut(parse_value)))(input)?;
+    let (input, max_distance) = opt(preceded(word_exact("DISTANCE"), cut(parse_value)))(input)?;
+
+    if let Some(max_distance) = &max_distance {
+        if max_distance.value().parse::<u8>().is_err() {
+            return Err(nom::Err::Failure(Error::new_from_kind(
+                max_distance.original_span(),
+                ErrorKind::InvalidFuzzyDistance,
+            )));
+        }
+    }
+
+    Ok((input, FilterCondition::Condition { fid, op: FuzzyEqual { value, max_distance } }))
+}
+
+/// matches        = value "MATCHES" value
+pub fn parse_matches(input: Span) -> IResult<FilterCondition> {
+    let (input, (fid, _, pattern)) =
+        tuple((parse_value, word_exact("MATCHES"), cut(parse_value)))(input)?;
+
+    if let Err(error) = compile_regex(pattern.value()) {
+        return Err(nom::Err::Failure(Error::new_from_kind(
+            pattern.original_span(),
+            ErrorKind::InvalidRegex(error.to_string()),
+        )));
+    }
+
+    Ok((input, FilterCondition::Condition { fid, op: Regex { pattern } }))
+}
+
+/// The maximum size in bytes of the compiled regex of a `MATCHES` filter.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Compiles the pattern of a `MATCHES` filter, ignoring the case as the facet values are
+/// normalized, and rejecting the patterns compiling to more than [`REGEX_SIZE_LIMIT`] bytes.
+///
+/// The parser validates the pattern with this same function so that a parsed filter always
+/// compiles when it is evaluated.
+pub fn compile_regex(pattern: &str) -> Result<regex::Regex, regex::Error> {
+    regex::RegexBuilder::new(pattern).case_insensitive(true).size_limit(REGEX_SIZE_LIMIT).build()
+}
+
+/// modulo         = value "MOD" value "=" value
+pub fn parse_modulo(input: Span) -> IResult<FilterCondition> {
+    let (input, (fid, _, divisor, _, remainder)) = tuple((
+        parse_value,
+        word_exact("MOD"),
+        cut(parse_value),
+        |input| {
+            cut(ws(char('=')))(input).map_err(|e: nom::Err<Error>| {
+                e.map(|e| Error::new_from_kind(*e.context(), ErrorKind::ExpectedModuloEqual))
+            })
+        },
+        cut(parse_value),
+    ))(input)?;
+
+    modulo_operands(&divisor, &remainder).map_err(nom::Err::Failure)?;
+
+    Ok((input, FilterCondition::Condition { fid, op: Modulo { divisor, remainder } }))
+}
+
+/// Returns the divisor and the remainder of a `MOD` condition, the divisor
+/// must be a strictly positive integer and the remainder an integer.
+pub fn modulo_operands<'a>(
+    divisor: &Token<'a>,
+    remainder: &Token<'a>,
+) -> Result<(i64, i64), Error<'a>> {
+    let divisor_value = divisor.value().parse::<i64>().ok().filter(|divisor| *divisor > 0);
+    let Some(divisor_value) = divisor_value else {
+        return Err(Error::new_from_kind(divisor.original_span(), ErrorKind::InvalidModuloDivisor));
+    };
+    let Ok(remainder_value) = remainder.value().parse::<i64>() else {
+        return Err(Error::new_from_kind(
+            remainder.original_span(),
+            ErrorKind::InvalidModuloRemainder,
+        ));
+    };
+    Ok((divisor_value, remainder_value))
+}
+
+/// to             = field value "TO" WS+ value
 pub fn parse_to(input: Span) -> IResult<FilterCondition> {
-    let (input, (key, from, _, _, to)) =
-        tuple((parse_value, parse_value, tag("TO"), multispace1, cut(parse_value)))(input)?;
+    let (input, ((key, condition), from, _, _, to)) =
+        tuple((parse_field, parse_value, tag("TO"), multispace1, cut(parse_value)))(input)?;
 
-    Ok((input, FilterCondition::Condition { fid: key, op: Between { from, to } }))
+    Ok((input, condition(key, Between { from, to })))
 }
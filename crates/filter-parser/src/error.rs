@@ -59,6 +59,9 @@ pub enum ErrorKind<'a> {
     ReservedGeo(&'a str),
     GeoRadius,
     GeoBoundingBox,
+    GeoInRegion,
+    FieldReference,
+    FieldFunction(&'a str),
     MisusedGeoRadius,
     MisusedGeoBoundingBox,
     InvalidPrimary,
@@ -69,6 +72,12 @@ pub enum ErrorKind<'a> {
     InOpeningBracket,
     InClosingBracket,
     NonFiniteFloat,
+    NegativeTolerance,
+    InvalidFuzzyDistance,
+    InvalidRegex(String),
+    InvalidModuloDivisor,
+    InvalidModuloRemainder,
+    ExpectedModuloEqual,
     InExpectedValue(ExpectedValueKind),
     ReservedKeyword(String),
     MissingClosingDelimiter(char),
@@ -146,7 +155,7 @@ impl Display for Error<'_> {
             }
             ErrorKind::InvalidPrimary => {
                 let text = if input.trim().is_empty() { "but instead got nothing.".to_string() } else { format!("at `{}`.", escaped_input) };
-                writeln!(f, "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` {}", text)?
+                writeln!(f, "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` {}", text)?
             }
             ErrorKind::InvalidEscapedNumber => {
                 writeln!(f, "Found an invalid escaped sequence number: `{}`.", escaped_input)?
@@ -160,6 +169,15 @@ impl Display for Error<'_> {
             ErrorKind::GeoBoundingBox => {
                 writeln!(f, "The `_geoBoundingBox` filter expects two pairs of arguments: `_geoBoundingBox([latitude, longitude], [latitude, longitude])`.")?
             }
+            ErrorKind::GeoInRegion => {
+                writeln!(f, "The `_geoInRegion` filter expects the name of a region: `_geoInRegion(name)`.")?
+            }
+            ErrorKind::FieldReference => {
+                writeln!(f, "The `_field` reference expects the name of a field: `field < _field(other)`.")?
+            }
+            ErrorKind::FieldFunction(name) => {
+                writeln!(f, "The `{name}` function expects the name of a field: `{name}(field) > 1`.")?
+            }
             ErrorKind::ReservedGeo(name) => {
                 writeln!(f, "`{}` is a reserved keyword and thus can't be used as a filter expression. Use the `_geoRadius(latitude, longitude, distance)` or `_geoBoundingBox([latitude, longitude], [latitude, longitude])` built-in rules to filter on `_geo` coordinates.", name.escape_debug())?
             }
@@ -181,6 +199,24 @@ impl Display for Error<'_> {
             ErrorKind::NonFiniteFloat => {
                 writeln!(f, "Non finite floats are not supported")?
             }
+            ErrorKind::NegativeTolerance => {
+                writeln!(f, "The tolerance of a `NEAR` filter must be a positive number but `{escaped_input}` was given.")?
+            }
+            ErrorKind::InvalidFuzzyDistance => {
+                writeln!(f, "The distance of a `~=` filter must be an integer between 0 and 255 but `{escaped_input}` was given.")?
+            }
+            ErrorKind::InvalidRegex(error) => {
+                writeln!(f, "The pattern of a `MATCHES` filter must be a valid regex but `{escaped_input}` was given.\n{error}")?
+            }
+            ErrorKind::InvalidModuloDivisor => {
+                writeln!(f, "The divisor of a `MOD` filter must be a strictly positive integer but `{escaped_input}` was given.")?
+            }
+            ErrorKind::InvalidModuloRemainder => {
+                writeln!(f, "The remainder of a `MOD` filter must be an integer but `{escaped_input}` was given.")?
+            }
+            ErrorKind::ExpectedModuloEqual => {
+                writeln!(f, "Was expecting a `=` followed by the remainder of the `MOD` filter at `{escaped_input}`.")?
+            }
             ErrorKind::InExpectedValue(ExpectedValueKind::ReservedKeyword) => {
                 writeln!(f, "Expected only comma-separated field names inside `IN[..]` but instead found `{escaped_input}`, which is a keyword. To use `{escaped_input}` as a field name or a value, surround it by quotes.")?
             }
@@ -8,17 +8,25 @@
 //! not            = ("NOT" WS+ not) | primary
 //! primary        = (WS* "(" WS* expression WS* ")" WS*) | geoRadius | in | condition | exists | not_exists | to
 //! in             = value "IN" WS* "[" value_list "]"
-//! condition      = value ("=" | "!=" | ">" | ">=" | "<" | "<=") value
+//! condition      = field ("=" | "!=" | ">" | ">=" | "<" | "<=") (value | fieldReference)
+//! fieldReference = "_field(" WS* value WS* ")", the value of another field of the document
 //! exists         = value "EXISTS"
 //! not_exists     = value "NOT" WS+ "EXISTS"
-//! to             = value value "TO" WS+ value
+//! to             = field value "TO" WS+ value
+//! field          = length | strlen | value
+//! length         = "_length(" WS* value WS* ")", the number of distinct values of the field
+//! strlen         = "_strlen(" WS* value WS* ")", the number of characters of the string values of the field
+//! fuzzyEqual     = value "~=" value ("DISTANCE" value)?, the string values within the distance
+//! matches        = value "MATCHES" value, the string values matching the regex
+//! modulo         = value "MOD" value "=" value, the integers with this remainder
 //! value          = WS* ( word | singleQuoted | doubleQuoted) WS+
 //! value_list     = (value ("," value)* ","?)?
 //! singleQuoted   = "'" .* all but quotes "'"
 //! doubleQuoted   = "\"" .* all but double quotes "\""
 //! word           = (alphanumeric | _ | - | .)+
-//! geoRadius      = "_geoRadius(" WS* float WS* "," WS* float WS* "," float WS* ")"
+//! geoRadius      = ("_geoRadius(" | "_geoRadiusExclusive(" | "_geoRadiusApprox(" | "_geoNotRadius(") WS* float WS* "," WS* float WS* "," float WS* ")"
 //! geoBoundingBox = "_geoBoundingBox([" WS * float WS* "," WS* float WS* "], [" WS* float WS* "," WS* float WS* "]")
+//! geoInRegion    = "_geoInRegion(" WS* value WS* ")"
 //! ```
 //!
 //! Other BNF grammar used to handle some specific errors:
@@ -44,19 +52,23 @@ mod condition;
 mod error;
 mod value;
 
+use std::collections::HashSet;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 
-pub use condition::{parse_condition, parse_to, Condition};
+pub use condition::{compile_regex, modulo_operands, parse_condition, parse_to, Condition};
 use condition::{
-    parse_contains, parse_exists, parse_is_empty, parse_is_not_empty, parse_is_not_null,
-    parse_is_null, parse_not_contains, parse_not_exists, parse_not_starts_with, parse_starts_with,
+    parse_approximately, parse_contains, parse_exists, parse_fuzzy_equal, parse_is_boolean,
+    parse_is_date, parse_is_empty, parse_is_not_date, parse_is_not_empty, parse_is_not_null,
+    parse_is_null, parse_matches, parse_modulo, parse_not_contains, parse_not_exists,
+    parse_not_starts_with, parse_starts_with, parse_starts_with_any,
 };
 use error::{cut_with_err, ExpectedValueKind, NomErrorExt};
 pub use error::{Error, ErrorKind};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::{char, multispace0};
-use nom::combinator::{cut, eof, map, opt};
+use nom::character::complete::{char, multispace0, multispace1};
+use nom::combinator::{cut, eof, map, opt, peek};
 use nom::multi::{many0, separated_list1};
 use nom::number::complete::recognize_float;
 use nom::sequence::{delimited, preceded, terminated, tuple};
@@ -86,6 +98,12 @@ impl PartialEq for Token<'_> {
     }
 }
 
+impl Hash for Token<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.span.fragment().hash(state)
+    }
+}
+
 impl<'a> Token<'a> {
     pub fn new(span: Span<'a>, value: Option<String>) -> Self {
         Self { span, value }
@@ -113,7 +131,7 @@ impl<'a> Token<'a> {
         self.span
     }
 
-    pub fn parse_finite_float(&self) -> Result<f64, Error> {
+    pub fn parse_finite_float(&self) -> Result<f64, Error<'a>> {
         let value: f64 = self.value().parse().map_err(|e| self.as_external_error(e))?;
         if value.is_finite() {
             Ok(value)
@@ -136,15 +154,80 @@ impl<'a> From<&'a str> for Token<'a> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum FilterCondition<'a> {
     Not(Box<Self>),
-    Condition { fid: Token<'a>, op: Condition<'a> },
-    In { fid: Token<'a>, els: Vec<Token<'a>> },
+    Condition {
+        fid: Token<'a>,
+        op: Condition<'a>,
+    },
+    In {
+        fid: Token<'a>,
+        els: Vec<Token<'a>>,
+    },
+    /// The documents containing every one of the values, parsed from `field CONTAINS ALL [a, b]`.
+    ContainsAll {
+        fid: Token<'a>,
+        els: Vec<Token<'a>>,
+    },
+    /// The documents whose number of distinct values of the field matches the comparison,
+    /// parsed from `_length(field) > 3`.
+    Length {
+        fid: Token<'a>,
+        op: Condition<'a>,
+    },
+    /// The documents having a string value of the field whose number of characters matches
+    /// the comparison, parsed from `_strlen(field) = 8`.
+    StringLength {
+        fid: Token<'a>,
+        op: Condition<'a>,
+    },
     Or(Vec<Self>),
     And(Vec<Self>),
-    GeoLowerThan { point: [Token<'a>; 2], radius: Token<'a> },
-    GeoBoundingBox { top_right_point: [Token<'a>; 2], bottom_left_point: [Token<'a>; 2] },
+    /// The documents matching `left` but not `right`, parsed from `left AND NOT right`.
+    Difference {
+        left: Box<Self>,
+        right: Box<Self>,
+    },
+    GeoLowerThan {
+        point: [Token<'a>; 2],
+        radius: Token<'a>,
+        kind: GeoRadiusKind,
+    },
+    GeoBoundingBox {
+        top_right_point: [Token<'a>; 2],
+        bottom_left_point: [Token<'a>; 2],
+    },
+    /// The documents whose geo point is inside of the polygon of the region stored under
+    /// `name`, parsed from `_geoInRegion("downtown")`.
+    GeoInRegion {
+        name: Token<'a>,
+    },
+}
+
+/// The documents a [`FilterCondition::GeoLowerThan`] selects around its circle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeoRadiusKind {
+    /// The points within the circle or on its edge, parsed from `_geoRadius`.
+    Inclusive,
+    /// The points strictly within the circle, parsed from `_geoRadiusExclusive`.
+    Exclusive,
+    /// The points of the bounding box of the circle, parsed from `_geoRadiusApprox`.
+    Approximate,
+    /// The geo faceted documents outside of the circle, parsed from `_geoNotRadius`.
+    Outside,
+}
+
+impl GeoRadiusKind {
+    /// The name of the filter function selecting this kind of documents.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            GeoRadiusKind::Inclusive => "_geoRadius",
+            GeoRadiusKind::Exclusive => "_geoRadiusExclusive",
+            GeoRadiusKind::Approximate => "_geoRadiusApprox",
+            GeoRadiusKind::Outside => "_geoNotRadius",
+        }
+    }
 }
 
 pub enum TraversedElement<'a> {
@@ -163,19 +246,35 @@ impl<'a> FilterCondition<'a> {
                 | Condition::Null
                 | Condition::Empty
                 | Condition::Exists
+                | Condition::IsTrue
+                | Condition::IsFalse
+                | Condition::IsDate
                 | Condition::LowerThan(_)
                 | Condition::LowerThanOrEqual(_)
-                | Condition::Between { .. } => None,
+                | Condition::Between { .. }
+                | Condition::Approximately { .. }
+                | Condition::FuzzyEqual { .. }
+                | Condition::Regex { .. }
+                | Condition::Modulo { .. }
+                | Condition::CompareFields { .. } => None,
                 Condition::Contains { keyword, word: _ }
-                | Condition::StartsWith { keyword, word: _ } => Some(keyword),
+                | Condition::StartsWith { keyword, word: _ }
+                | Condition::StartsWithAny { keyword, words: _ } => Some(keyword),
             },
             FilterCondition::Not(this) => this.use_contains_operator(),
             FilterCondition::Or(seq) | FilterCondition::And(seq) => {
                 seq.iter().find_map(|filter| filter.use_contains_operator())
             }
+            FilterCondition::Difference { left, right } => {
+                left.use_contains_operator().or_else(|| right.use_contains_operator())
+            }
             FilterCondition::GeoLowerThan { .. }
             | FilterCondition::GeoBoundingBox { .. }
-            | FilterCondition::In { .. } => None,
+            | FilterCondition::GeoInRegion { .. }
+            | FilterCondition::In { .. }
+            | FilterCondition::ContainsAll { .. }
+            | FilterCondition::Length { .. }
+            | FilterCondition::StringLength { .. } => None,
         }
     }
 
@@ -184,9 +283,14 @@ impl<'a> FilterCondition<'a> {
             return Box::new(std::iter::empty());
         }
         match self {
-            FilterCondition::Condition { fid, .. } | FilterCondition::In { fid, .. } => {
-                Box::new(std::iter::once(fid))
+            FilterCondition::Condition { fid, op: Condition::CompareFields { other, .. } } => {
+                Box::new([fid, other].into_iter())
             }
+            FilterCondition::Condition { fid, .. }
+            | FilterCondition::In { fid, .. }
+            | FilterCondition::ContainsAll { fid, .. }
+            | FilterCondition::Length { fid, .. }
+            | FilterCondition::StringLength { fid, .. } => Box::new(std::iter::once(fid)),
             FilterCondition::Not(filter) => {
                 let depth = depth.saturating_sub(1);
                 filter.fids(depth)
@@ -195,6 +299,10 @@ impl<'a> FilterCondition<'a> {
                 let depth = depth.saturating_sub(1);
                 Box::new(subfilters.iter().flat_map(move |f| f.fids(depth)))
             }
+            FilterCondition::Difference { left, right } => {
+                let depth = depth.saturating_sub(1);
+                Box::new(left.fids(depth).chain(right.fids(depth)))
+            }
             _ => Box::new(std::iter::empty()),
         }
     }
@@ -202,7 +310,13 @@ impl<'a> FilterCondition<'a> {
     /// Returns the first token found at the specified depth, `None` if no token at this depth.
     pub fn token_at_depth(&self, depth: usize) -> Option<&Token> {
         match self {
-            FilterCondition::Condition { fid, .. } if depth == 0 => Some(fid),
+            FilterCondition::Condition { fid, .. }
+            | FilterCondition::Length { fid, .. }
+            | FilterCondition::StringLength { fid, .. }
+                if depth == 0 =>
+            {
+                Some(fid)
+            }
             FilterCondition::Or(subfilters) => {
                 let depth = depth.saturating_sub(1);
                 for f in subfilters.iter() {
@@ -221,11 +335,136 @@ impl<'a> FilterCondition<'a> {
                 }
                 None
             }
+            FilterCondition::Difference { left, right } => {
+                let depth = depth.saturating_sub(1);
+                left.token_at_depth(depth).or_else(|| right.token_at_depth(depth))
+            }
             FilterCondition::GeoLowerThan { point: [point, _], .. } if depth == 0 => Some(point),
             _ => None,
         }
     }
 
+    /// Returns the number of nodes of the filter AST, counting every element of the `IN` lists.
+    pub fn node_count(&self) -> usize {
+        match self {
+            FilterCondition::Not(filter) => 1 + filter.node_count(),
+            FilterCondition::In { els, .. } | FilterCondition::ContainsAll { els, .. } => {
+                1 + els.len()
+            }
+            FilterCondition::Or(subfilters) | FilterCondition::And(subfilters) => {
+                1 + subfilters.iter().map(|f| f.node_count()).sum::<usize>()
+            }
+            FilterCondition::Difference { left, right } => {
+                1 + left.node_count() + right.node_count()
+            }
+            FilterCondition::Condition { .. }
+            | FilterCondition::Length { .. }
+            | FilterCondition::StringLength { .. }
+            | FilterCondition::GeoLowerThan { .. }
+            | FilterCondition::GeoBoundingBox { .. }
+            | FilterCondition::GeoInRegion { .. } => 1,
+        }
+    }
+
+    /// Returns a logically equivalent filter where the `AND` and `OR` nested in a node of the
+    /// same type are merged into their parent, the duplicated children of a node are removed,
+    /// and the double negations are dropped.
+    ///
+    /// The `AND` and `OR` left with a single child are replaced by this child.
+    pub fn simplify(self) -> Self {
+        match self {
+            FilterCondition::Not(filter) => match filter.simplify() {
+                FilterCondition::Not(filter) => *filter,
+                filter => FilterCondition::Not(Box::new(filter)),
+            },
+            FilterCondition::And(subfilters) => {
+                let subfilters = flatten_and_dedup(subfilters, |filter| match filter {
+                    FilterCondition::And(subfilters) => Ok(subfilters),
+                    filter => Err(filter),
+                });
+                match <[_; 1]>::try_from(subfilters) {
+                    Ok([filter]) => filter,
+                    Err(subfilters) => FilterCondition::And(subfilters),
+                }
+            }
+            FilterCondition::Or(subfilters) => {
+                let subfilters = flatten_and_dedup(subfilters, |filter| match filter {
+                    FilterCondition::Or(subfilters) => Ok(subfilters),
+                    filter => Err(filter),
+                });
+                match <[_; 1]>::try_from(subfilters) {
+                    Ok([filter]) => filter,
+                    Err(subfilters) => FilterCondition::Or(subfilters),
+                }
+            }
+            FilterCondition::Difference { left, right } => FilterCondition::Difference {
+                left: Box::new(left.simplify()),
+                right: Box::new(right.simplify()),
+            },
+            filter => filter,
+        }
+    }
+
+    /// Returns the first token found past the `max_nodes` first nodes, `None` if the filter is small enough.
+    pub fn token_past_node_limit(&self, max_nodes: usize) -> Option<&Token> {
+        let mut remaining = max_nodes;
+        self.inner_token_past_node_limit(&mut remaining)
+    }
+
+    fn inner_token_past_node_limit(&self, remaining: &mut usize) -> Option<&Token> {
+        match self {
+            FilterCondition::Not(filter) => {
+                *remaining = remaining.saturating_sub(1);
+                filter.inner_token_past_node_limit(remaining)
+            }
+            FilterCondition::Or(subfilters) | FilterCondition::And(subfilters) => {
+                *remaining = remaining.saturating_sub(1);
+                subfilters.iter().find_map(|f| f.inner_token_past_node_limit(remaining))
+            }
+            FilterCondition::Difference { left, right } => {
+                *remaining = remaining.saturating_sub(1);
+                left.inner_token_past_node_limit(remaining)
+                    .or_else(|| right.inner_token_past_node_limit(remaining))
+            }
+            FilterCondition::In { fid, els } | FilterCondition::ContainsAll { fid, els } => {
+                std::iter::once(fid).chain(els).find(|_| match remaining.checked_sub(1) {
+                    Some(rest) => {
+                        *remaining = rest;
+                        false
+                    }
+                    None => true,
+                })
+            }
+            FilterCondition::Condition { fid: token, .. }
+            | FilterCondition::Length { fid: token, .. }
+            | FilterCondition::StringLength { fid: token, .. }
+            | FilterCondition::GeoLowerThan { point: [token, _], .. }
+            | FilterCondition::GeoBoundingBox { top_right_point: [token, _], .. }
+            | FilterCondition::GeoInRegion { name: token } => match remaining.checked_sub(1) {
+                Some(rest) => {
+                    *remaining = rest;
+                    None
+                }
+                None => Some(token),
+            },
+        }
+    }
+
+    /// Returns `true` if the filter is a single condition, without any logical operator.
+    pub fn is_simple(&self) -> bool {
+        matches!(
+            self,
+            FilterCondition::Condition { .. }
+                | FilterCondition::In { .. }
+                | FilterCondition::ContainsAll { .. }
+                | FilterCondition::Length { .. }
+                | FilterCondition::StringLength { .. }
+                | FilterCondition::GeoLowerThan { .. }
+                | FilterCondition::GeoBoundingBox { .. }
+                | FilterCondition::GeoInRegion { .. }
+        )
+    }
+
     pub fn parse(input: &'a str) -> Result<Option<Self>, Error<'a>> {
         if input.trim().is_empty() {
             return Ok(None);
@@ -263,6 +502,11 @@ fn parse_in_body(input: Span) -> IResult<Vec<Token>> {
     let (input, _) = ws(word_exact("IN"))(input)?;
 
     // everything after `IN` can be a failure
+    parse_bracketed_value_list(input)
+}
+
+/// "[" value_list "]"
+fn parse_bracketed_value_list(input: Span) -> IResult<Vec<Token>> {
     let (input, _) =
         cut_with_err(tag("["), |_| Error::new_from_kind(input, ErrorKind::InOpeningBracket))(
             input,
@@ -308,6 +552,18 @@ fn parse_not_in(input: Span) -> IResult<FilterCondition> {
     Ok((input, filter))
 }
 
+/// contains_all = value "CONTAINS" WS+ "ALL" WS* "[" value_list "]"
+fn parse_contains_all(input: Span) -> IResult<FilterCondition> {
+    let (input, value) = parse_value(input)?;
+    let (input, _) = tuple((tag("CONTAINS"), multispace1, word_exact("ALL"), multispace0))(input)?;
+    // without a bracket, `ALL` is the word of a simple `CONTAINS`
+    let (input, _) = peek(tag("["))(input)?;
+    let (input, content) = parse_bracketed_value_list(input)?;
+
+    let filter = FilterCondition::ContainsAll { fid: value, els: content };
+    Ok((input, filter))
+}
+
 /// or             = and ("OR" and)
 fn parse_or(input: Span, depth: usize) -> IResult<FilterCondition> {
     if depth > MAX_FILTER_DEPTH {
@@ -335,15 +591,27 @@ fn parse_and(input: Span, depth: usize) -> IResult<FilterCondition> {
     }
     let (input, first_filter) = parse_not(input, depth + 1)?;
     // if we found a `AND` then we MUST find something next
-    let (input, mut ands) =
+    let (input, ands) =
         many0(preceded(ws(word_exact("AND")), cut(|input| parse_not(input, depth + 1))))(input)?;
 
-    let filter = if ands.is_empty() {
-        first_filter
-    } else {
-        ands.insert(0, first_filter);
-        FilterCondition::And(ands)
-    };
+    // `left AND NOT right` is normalized into a difference when `right` is a simple condition
+    let mut filters = vec![first_filter];
+    for filter in ands {
+        match filter {
+            FilterCondition::Not(right) if right.is_simple() => {
+                let left = if filters.len() == 1 {
+                    filters.pop().unwrap()
+                } else {
+                    FilterCondition::And(std::mem::take(&mut filters))
+                };
+                filters.push(FilterCondition::Difference { left: Box::new(left), right });
+            }
+            filter => filters.push(filter),
+        }
+    }
+
+    let filter =
+        if filters.len() == 1 { filters.pop().unwrap() } else { FilterCondition::And(filters) };
 
     Ok((input, filter))
 }
@@ -367,18 +635,26 @@ fn parse_not(input: Span, depth: usize) -> IResult<FilterCondition> {
     ))(input)
 }
 
-/// geoRadius      = WS* "_geoRadius(float WS* "," WS* float WS* "," WS* float)
+/// geoRadius      = WS* ("_geoRadius(" | "_geoRadiusExclusive(" | "_geoRadiusApprox(" | "_geoNotRadius(") float WS* "," WS* float WS* "," WS* float)
 /// If we parse `_geoRadius` we MUST parse the rest of the expression.
 fn parse_geo_radius(input: Span) -> IResult<FilterCondition> {
     // we want to allow space BEFORE the _geoRadius but not after
-    let parsed = preceded(
-        tuple((multispace0, word_exact("_geoRadius"))),
+    let parsed = tuple((
+        preceded(
+            multispace0,
+            alt((
+                word_exact("_geoRadius"),
+                word_exact("_geoRadiusExclusive"),
+                word_exact("_geoRadiusApprox"),
+                word_exact("_geoNotRadius"),
+            )),
+        ),
         // if we were able to parse `_geoRadius` and can't parse the rest of the input we return a failure
         cut(delimited(char('('), separated_list1(tag(","), ws(recognize_float)), char(')'))),
-    )(input)
+    ))(input)
     .map_err(|e| e.map(|_| Error::new_from_kind(input, ErrorKind::GeoRadius)));
 
-    let (input, args) = parsed?;
+    let (input, (keyword, args)) = parsed?;
 
     if args.len() != 3 {
         return Err(nom::Err::Failure(Error::new_from_kind(input, ErrorKind::GeoRadius)));
@@ -387,6 +663,12 @@ fn parse_geo_radius(input: Span) -> IResult<FilterCondition> {
     let res = FilterCondition::GeoLowerThan {
         point: [args[0].into(), args[1].into()],
         radius: args[2].into(),
+        kind: match keyword.value() {
+            "_geoRadiusExclusive" => GeoRadiusKind::Exclusive,
+            "_geoRadiusApprox" => GeoRadiusKind::Approximate,
+            "_geoNotRadius" => GeoRadiusKind::Outside,
+            _ => GeoRadiusKind::Inclusive,
+        },
     };
     Ok((input, res))
 }
@@ -422,6 +704,22 @@ fn parse_geo_bounding_box(input: Span) -> IResult<FilterCondition> {
     Ok((input, res))
 }
 
+/// geoInRegion      = WS* "_geoInRegion(" WS* value WS* ")"
+/// If we parse `_geoInRegion` we MUST parse the rest of the expression.
+fn parse_geo_in_region(input: Span) -> IResult<FilterCondition> {
+    // we want to allow space BEFORE the _geoInRegion but not after
+    let parsed = preceded(
+        tuple((multispace0, word_exact("_geoInRegion"))),
+        // if we were able to parse `_geoInRegion` and can't parse the rest of the input we return a failure
+        cut(delimited(char('('), ws(parse_value), char(')'))),
+    )(input)
+    .map_err(|e| e.map(|_| Error::new_from_kind(input, ErrorKind::GeoInRegion)));
+
+    let (input, name) = parsed?;
+
+    Ok((input, FilterCondition::GeoInRegion { name }))
+}
+
 /// geoPoint      = WS* "_geoPoint(float WS* "," WS* float WS* "," WS* float)
 fn parse_geo_point(input: Span) -> IResult<FilterCondition> {
     // we want to forbid space BEFORE the _geoPoint but not after
@@ -491,22 +789,29 @@ fn parse_primary(input: Span, depth: usize) -> IResult<FilterCondition> {
                 Error::new_from_kind(input, ErrorKind::MissingClosingDelimiter(c.char()))
             }),
         ),
-        parse_geo_radius,
-        parse_geo_bounding_box,
+        alt((parse_geo_radius, parse_geo_bounding_box, parse_geo_in_region)),
         parse_in,
         parse_not_in,
         parse_condition,
-        parse_is_null,
-        parse_is_not_null,
-        parse_is_empty,
-        parse_is_not_empty,
+        alt((
+            parse_is_null,
+            parse_is_not_null,
+            parse_is_empty,
+            parse_is_not_empty,
+            parse_is_boolean,
+            parse_is_date,
+            parse_is_not_date,
+        )),
         parse_exists,
         parse_not_exists,
         parse_to,
+        parse_contains_all,
         parse_contains,
         parse_not_contains,
+        parse_starts_with_any,
         parse_starts_with,
         parse_not_starts_with,
+        alt((parse_approximately, parse_fuzzy_equal, parse_matches, parse_modulo)),
         // the next lines are only for error handling and are written at the end to have the less possible performance impact
         parse_geo,
         parse_geo_distance,
@@ -536,6 +841,12 @@ impl std::fmt::Display for FilterCondition<'_> {
             FilterCondition::Condition { fid, op } => {
                 write!(f, "{fid} {op}")
             }
+            FilterCondition::Length { fid, op } => {
+                write!(f, "_length({fid}) {op}")
+            }
+            FilterCondition::StringLength { fid, op } => {
+                write!(f, "_strlen({fid}) {op}")
+            }
             FilterCondition::In { fid, els } => {
                 write!(f, "{fid} IN[")?;
                 for el in els {
@@ -543,6 +854,13 @@ impl std::fmt::Display for FilterCondition<'_> {
                 }
                 write!(f, "]")
             }
+            FilterCondition::ContainsAll { fid, els } => {
+                write!(f, "{fid} CONTAINS ALL[")?;
+                for el in els {
+                    write!(f, "{el}, ")?;
+                }
+                write!(f, "]")
+            }
             FilterCondition::Or(els) => {
                 write!(f, "OR[")?;
                 for el in els {
@@ -557,8 +875,11 @@ impl std::fmt::Display for FilterCondition<'_> {
                 }
                 write!(f, "]")
             }
-            FilterCondition::GeoLowerThan { point, radius } => {
-                write!(f, "_geoRadius({}, {}, {})", point[0], point[1], radius)
+            FilterCondition::Difference { left, right } => {
+                write!(f, "AND[{left}, NOT ({right}), ]")
+            }
+            FilterCondition::GeoLowerThan { point, radius, kind } => {
+                write!(f, "{}({}, {}, {})", kind.keyword(), point[0], point[1], radius)
             }
             FilterCondition::GeoBoundingBox {
                 top_right_point: top_left_point,
@@ -573,6 +894,7 @@ impl std::fmt::Display for FilterCondition<'_> {
                     bottom_right_point[1]
                 )
             }
+            FilterCondition::GeoInRegion { name } => write!(f, "_geoInRegion({name})"),
         }
     }
 }
@@ -587,11 +909,33 @@ impl std::fmt::Display for Condition<'_> {
             Condition::Null => write!(f, "IS NULL"),
             Condition::Empty => write!(f, "IS EMPTY"),
             Condition::Exists => write!(f, "EXISTS"),
+            Condition::IsTrue => write!(f, "IS TRUE"),
+            Condition::IsFalse => write!(f, "IS FALSE"),
+            Condition::IsDate => write!(f, "IS DATE"),
             Condition::LowerThan(token) => write!(f, "< {token}"),
             Condition::LowerThanOrEqual(token) => write!(f, "<= {token}"),
             Condition::Between { from, to } => write!(f, "{from} TO {to}"),
             Condition::Contains { word, keyword: _ } => write!(f, "CONTAINS {word}"),
             Condition::StartsWith { word, keyword: _ } => write!(f, "STARTS WITH {word}"),
+            Condition::StartsWithAny { words, keyword: _ } => {
+                write!(f, "STARTS WITH ANY[")?;
+                for word in words {
+                    write!(f, "{word}, ")?;
+                }
+                write!(f, "]")
+            }
+            Condition::Approximately { value, tolerance } => {
+                write!(f, "NEAR {value} TOLERANCE {tolerance}")
+            }
+            Condition::FuzzyEqual { value, max_distance: None } => write!(f, "~= {value}"),
+            Condition::FuzzyEqual { value, max_distance: Some(max_distance) } => {
+                write!(f, "~= {value} DISTANCE {max_distance}")
+            }
+            Condition::Regex { pattern } => write!(f, "MATCHES {pattern}"),
+            Condition::Modulo { divisor, remainder } => write!(f, "MOD {divisor} = {remainder}"),
+            Condition::CompareFields { operator, other } => {
+                write!(f, "{} _field({other})", operator.value())
+            }
         }
     }
 }
@@ -602,6 +946,31 @@ impl std::fmt::Display for Token<'_> {
     }
 }
 
+/// Simplifies the children of a node, merging the children of the nested nodes of the same
+/// type returned by `nested` into the node, and keeping the first of the equal children.
+fn flatten_and_dedup<'a>(
+    subfilters: Vec<FilterCondition<'a>>,
+    nested: impl Fn(FilterCondition<'a>) -> Result<Vec<FilterCondition<'a>>, FilterCondition<'a>>,
+) -> Vec<FilterCondition<'a>> {
+    let mut flattened: Vec<FilterCondition<'a>> = Vec::with_capacity(subfilters.len());
+    for filter in subfilters {
+        // a simplified nested node has no nested node of its own type left
+        match nested(filter.simplify()) {
+            Ok(subfilters) => flattened.extend(subfilters),
+            Err(filter) => flattened.push(filter),
+        }
+    }
+
+    let mut seen = HashSet::with_capacity(flattened.len());
+    let first_seen: Vec<bool> = flattened.iter().map(|filter| seen.insert(filter)).collect();
+    drop(seen);
+    flattened
+        .into_iter()
+        .zip(first_seen)
+        .filter_map(|(filter, first)| first.then_some(filter))
+        .collect()
+}
+
 #[cfg(test)]
 pub mod tests {
     use FilterCondition as Fc;
@@ -692,6 +1061,18 @@ pub mod tests {
         insta::assert_snapshot!(p("NOT subscribers IS NOT EMPTY"), @"{subscribers} IS EMPTY");
         insta::assert_snapshot!(p("subscribers  IS   NOT   EMPTY"), @"NOT ({subscribers} IS EMPTY)");
 
+        // Test TRUE + FALSE
+        insta::assert_snapshot!(p("active IS TRUE"), @"{active} IS TRUE");
+        insta::assert_snapshot!(p("active IS FALSE"), @"{active} IS FALSE");
+        insta::assert_snapshot!(p("NOT active IS TRUE"), @"NOT ({active} IS TRUE)");
+        insta::assert_snapshot!(p("active  IS   FALSE"), @"{active} IS FALSE");
+
+        // Test DATE + NOT DATE
+        insta::assert_snapshot!(p("release_date IS DATE"), @"{release_date} IS DATE");
+        insta::assert_snapshot!(p("NOT release_date IS DATE"), @"NOT ({release_date} IS DATE)");
+        insta::assert_snapshot!(p("release_date IS NOT DATE"), @"NOT ({release_date} IS DATE)");
+        insta::assert_snapshot!(p("release_date  IS   NOT   DATE"), @"NOT ({release_date} IS DATE)");
+
         // Test EXISTS + NOT EXITS
         insta::assert_snapshot!(p("subscribers EXISTS"), @"{subscribers} EXISTS");
         insta::assert_snapshot!(p("NOT subscribers EXISTS"), @"NOT ({subscribers} EXISTS)");
@@ -706,6 +1087,34 @@ pub mod tests {
         insta::assert_snapshot!(p("NOT subscribers NOT CONTAINS 'hello'"), @"{subscribers} CONTAINS {hello}");
         insta::assert_snapshot!(p("subscribers NOT   CONTAINS 'hello'"), @"NOT ({subscribers} CONTAINS {hello})");
 
+        // Test the number of values
+        insta::assert_snapshot!(p("_length(tags) = 0"), @"_length({tags}) = {0}");
+        insta::assert_snapshot!(p("_length(tags) != 1"), @"_length({tags}) != {1}");
+        insta::assert_snapshot!(p("_length( tags ) >= 2"), @"_length({tags}) >= {2}");
+        insta::assert_snapshot!(p("_length(doc.tags) 1 TO 3"), @"_length({doc.tags}) {1} TO {3}");
+        insta::assert_snapshot!(p("NOT _length(tags) < 2"), @"NOT (_length({tags}) < {2})");
+        insta::assert_snapshot!(p("_length('tag list') > 2"), @"_length({tag list}) > {2}");
+        insta::assert_snapshot!(p("tags.length = 2"), @"{tags.length} = {2}");
+        insta::assert_snapshot!(p("length = 2"), @"{length} = {2}");
+        insta::assert_snapshot!(p("_length = 2"), @"{_length} = {2}");
+        insta::assert_snapshot!(p("_strlen(sku) = 8"), @"_strlen({sku}) = {8}");
+        insta::assert_snapshot!(p("_strlen(sku) 4 TO 8"), @"_strlen({sku}) {4} TO {8}");
+        insta::assert_snapshot!(p("NOT _strlen(doc.sku) < 2"), @"NOT (_strlen({doc.sku}) < {2})");
+        insta::assert_snapshot!(p("sku.strlen = 8"), @"{sku.strlen} = {8}");
+        insta::assert_snapshot!(p("_strlen = 8"), @"{_strlen} = {8}");
+
+        // Test the comparisons between two fields
+        insta::assert_snapshot!(p("sale_price < _field(list_price)"), @"{sale_price} < _field({list_price})");
+        insta::assert_snapshot!(p("sale_price>=_field( 'list price' )"), @"{sale_price} >= _field({list price})");
+        insta::assert_snapshot!(p("NOT a = _field(b) AND c != 3"), @"AND[NOT ({a} = _field({b})), {c} != {3}, ]");
+        insta::assert_snapshot!(p("a = '_field(b)'"), @"{a} = {_field(b)}");
+
+        // Test CONTAINS ALL
+        insta::assert_snapshot!(p("tags CONTAINS ALL [a, b]"), @"{tags} CONTAINS ALL[{a}, {b}, ]");
+        insta::assert_snapshot!(p("tags CONTAINS ALL[a, 'b c',]"), @"{tags} CONTAINS ALL[{a}, {b c}, ]");
+        insta::assert_snapshot!(p("NOT tags CONTAINS ALL [a]"), @"NOT ({tags} CONTAINS ALL[{a}, ])");
+        insta::assert_snapshot!(p("tags CONTAINS ALL"), @"{tags} CONTAINS {ALL}");
+
         // Test STARTS WITH + NOT STARTS WITH
         insta::assert_snapshot!(p("subscribers STARTS WITH 'hel'"), @"{subscribers} STARTS WITH {hel}");
         insta::assert_snapshot!(p("NOT subscribers STARTS WITH 'hel'"), @"NOT ({subscribers} STARTS WITH {hel})");
@@ -713,6 +1122,32 @@ pub mod tests {
         insta::assert_snapshot!(p("NOT subscribers NOT STARTS WITH 'hel'"), @"{subscribers} STARTS WITH {hel}");
         insta::assert_snapshot!(p("subscribers NOT   STARTS WITH 'hel'"), @"NOT ({subscribers} STARTS WITH {hel})");
 
+        // Test STARTS WITH ANY
+        insta::assert_snapshot!(p("title STARTS WITH ANY [hel, 'wor']"), @"{title} STARTS WITH ANY[{hel}, {wor}, ]");
+        insta::assert_snapshot!(p("NOT title STARTS WITH ANY[hel]"), @"NOT ({title} STARTS WITH ANY[{hel}, ])");
+        insta::assert_snapshot!(p("title STARTS WITH ANY"), @"{title} STARTS WITH {ANY}");
+
+        // Test NEAR
+        insta::assert_snapshot!(p("price NEAR 19.99 TOLERANCE 0.01"), @"{price} NEAR {19.99} TOLERANCE {0.01}");
+        insta::assert_snapshot!(p("price ≈ 19.99 ± 0.01"), @"{price} NEAR {19.99} TOLERANCE {0.01}");
+        insta::assert_snapshot!(p("price≈-3±0"), @"{price} NEAR {-3} TOLERANCE {0}");
+        insta::assert_snapshot!(p("NOT price NEAR 10 TOLERANCE 1 AND color = NEAR"), @"AND[NOT ({price} NEAR {10} TOLERANCE {1}), {color} = {NEAR}, ]");
+
+        // Test fuzzy equal
+        insta::assert_snapshot!(p("brand ~= samsng"), @"{brand} ~= {samsng}");
+        insta::assert_snapshot!(p("brand~='samsng'"), @"{brand} ~= {samsng}");
+        insta::assert_snapshot!(p("brand ~= samsng DISTANCE 2"), @"{brand} ~= {samsng} DISTANCE {2}");
+        insta::assert_snapshot!(p("brand ~= samsng AND brand ~= DISTANCE DISTANCE 0"), @"AND[{brand} ~= {samsng}, {brand} ~= {DISTANCE} DISTANCE {0}, ]");
+
+        // Test matches
+        insta::assert_snapshot!(p("sku MATCHES \"^A[0-9]{4}$\""), @"{sku} MATCHES {^A[0-9]{4}$}");
+        insta::assert_snapshot!(p("sku MATCHES 'a.c' OR sku = MATCHES"), @"OR[{sku} MATCHES {a.c}, {sku} = {MATCHES}, ]");
+
+        // Test modulo
+        insta::assert_snapshot!(p("id MOD 10 = 0"), @"{id} MOD {10} = {0}");
+        insta::assert_snapshot!(p("id MOD 2=1 AND id MOD '3' = -1"), @"AND[{id} MOD {2} = {1}, {id} MOD {3} = {-1}, ]");
+        insta::assert_snapshot!(p("NOT id MOD 2 = 0"), @"NOT ({id} MOD {2} = {0})");
+
         // Test nested NOT
         insta::assert_snapshot!(p("NOT NOT NOT NOT x = 5"), @"{x} = {5}");
         insta::assert_snapshot!(p("NOT NOT (NOT NOT x = 5)"), @"{x} = {5}");
@@ -721,11 +1156,20 @@ pub mod tests {
         insta::assert_snapshot!(p("_geoRadius(12, 13, 14)"), @"_geoRadius({12}, {13}, {14})");
         insta::assert_snapshot!(p("NOT _geoRadius(12, 13, 14)"), @"NOT (_geoRadius({12}, {13}, {14}))");
         insta::assert_snapshot!(p("_geoRadius(12,13,14)"), @"_geoRadius({12}, {13}, {14})");
+        insta::assert_snapshot!(p("_geoRadiusExclusive(12, 13, 14)"), @"_geoRadiusExclusive({12}, {13}, {14})");
+        insta::assert_snapshot!(p("NOT _geoRadiusExclusive(12, 13, 14)"), @"NOT (_geoRadiusExclusive({12}, {13}, {14}))");
+        insta::assert_snapshot!(p("_geoRadiusApprox(12, 13, 14)"), @"_geoRadiusApprox({12}, {13}, {14})");
+        insta::assert_snapshot!(p("NOT _geoRadiusApprox(12, 13, 14)"), @"NOT (_geoRadiusApprox({12}, {13}, {14}))");
+        insta::assert_snapshot!(p("_geoNotRadius(12, 13, 14)"), @"_geoNotRadius({12}, {13}, {14})");
+        insta::assert_snapshot!(p("NOT _geoNotRadius(12, 13, 14)"), @"NOT (_geoNotRadius({12}, {13}, {14}))");
 
         // Test geo bounding box
         insta::assert_snapshot!(p("_geoBoundingBox([12, 13], [14, 15])"), @"_geoBoundingBox([{12}, {13}], [{14}, {15}])");
         insta::assert_snapshot!(p("NOT _geoBoundingBox([12, 13], [14, 15])"), @"NOT (_geoBoundingBox([{12}, {13}], [{14}, {15}]))");
         insta::assert_snapshot!(p("_geoBoundingBox([12,13],[14,15])"), @"_geoBoundingBox([{12}, {13}], [{14}, {15}])");
+        insta::assert_snapshot!(p("_geoInRegion(downtown)"), @"_geoInRegion({downtown})");
+        insta::assert_snapshot!(p("_geoInRegion( \"old town\" )"), @"_geoInRegion({old town})");
+        insta::assert_snapshot!(p("NOT _geoInRegion('downtown')"), @"NOT (_geoInRegion({downtown}))");
 
         // Test OR + AND
         insta::assert_snapshot!(p("channel = ponce AND 'dog race' != 'bernese mountain'"), @"AND[{channel} = {ponce}, {dog race} != {bernese mountain}, ]");
@@ -784,7 +1228,7 @@ pub mod tests {
         "###);
 
         insta::assert_snapshot!(p("'OR'"), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `\'OR\'`.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `\'OR\'`.
         1:5 'OR'
         "###);
 
@@ -794,12 +1238,12 @@ pub mod tests {
         "###);
 
         insta::assert_snapshot!(p("channel Ponce"), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `channel Ponce`.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `channel Ponce`.
         1:14 channel Ponce
         "###);
 
         insta::assert_snapshot!(p("channel = Ponce OR"), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` but instead got nothing.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` but instead got nothing.
         19:19 channel = Ponce OR
         "###);
 
@@ -823,6 +1267,36 @@ pub mod tests {
         1:21 _geoBoundingBox = 12
         "###);
 
+        insta::assert_snapshot!(p("price < _field()"), @r###"
+        The `_field` reference expects the name of a field: `field < _field(other)`.
+        8:17 price < _field()
+        "###);
+        insta::assert_snapshot!(p("price < _field(list_price"), @r###"
+        The `_field` reference expects the name of a field: `field < _field(other)`.
+        8:26 price < _field(list_price
+        "###);
+        insta::assert_snapshot!(p("_length() > 2"), @r###"
+        The `_length` function expects the name of a field: `_length(field) > 1`.
+        1:14 _length() > 2
+        "###);
+        insta::assert_snapshot!(p("_strlen(sku"), @r###"
+        The `_strlen` function expects the name of a field: `_strlen(field) > 1`.
+        1:12 _strlen(sku
+        "###);
+        insta::assert_snapshot!(p("_length(tags > 2"), @r###"
+        The `_length` function expects the name of a field: `_length(field) > 1`.
+        1:17 _length(tags > 2
+        "###);
+        insta::assert_snapshot!(p("_length(tags) EXISTS"), @r###"
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `_length(tags) EXISTS`.
+        1:21 _length(tags) EXISTS
+        "###);
+
+        insta::assert_snapshot!(p("_geoInRegion()"), @r###"
+        The `_geoInRegion` filter expects the name of a region: `_geoInRegion(name)`.
+        1:15 _geoInRegion()
+        "###);
+
         insta::assert_snapshot!(p("_geoBoundingBox(1.0, 1.0)"), @r###"
         The `_geoBoundingBox` filter expects two pairs of arguments: `_geoBoundingBox([latitude, longitude], [latitude, longitude])`.
         1:26 _geoBoundingBox(1.0, 1.0)
@@ -884,12 +1358,12 @@ pub mod tests {
         "###);
 
         insta::assert_snapshot!(p("colour NOT EXIST"), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `colour NOT EXIST`.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `colour NOT EXIST`.
         1:17 colour NOT EXIST
         "###);
 
         insta::assert_snapshot!(p("subscribers 100 TO1000"), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `subscribers 100 TO1000`.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `subscribers 100 TO1000`.
         1:23 subscribers 100 TO1000
         "###);
 
@@ -952,37 +1426,99 @@ pub mod tests {
         "###);
 
         insta::assert_snapshot!(p(r#"value NULL"#), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `value NULL`.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `value NULL`.
         1:11 value NULL
         "###);
         insta::assert_snapshot!(p(r#"value NOT NULL"#), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `value NOT NULL`.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `value NOT NULL`.
         1:15 value NOT NULL
         "###);
         insta::assert_snapshot!(p(r#"value EMPTY"#), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `value EMPTY`.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `value EMPTY`.
         1:12 value EMPTY
         "###);
         insta::assert_snapshot!(p(r#"value NOT EMPTY"#), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `value NOT EMPTY`.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `value NOT EMPTY`.
         1:16 value NOT EMPTY
         "###);
         insta::assert_snapshot!(p(r#"value IS"#), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `value IS`.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `value IS`.
         1:9 value IS
         "###);
         insta::assert_snapshot!(p(r#"value IS NOT"#), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `value IS NOT`.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `value IS NOT`.
         1:13 value IS NOT
         "###);
         insta::assert_snapshot!(p(r#"value IS EXISTS"#), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `value IS EXISTS`.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `value IS EXISTS`.
         1:16 value IS EXISTS
         "###);
         insta::assert_snapshot!(p(r#"value IS NOT EXISTS"#), @r###"
-        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `value IS NOT EXISTS`.
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `value IS NOT EXISTS`.
         1:20 value IS NOT EXISTS
         "###);
+
+        insta::assert_snapshot!(p("price NEAR 19.99"), @r###"
+        Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `price NEAR 19.99`.
+        1:17 price NEAR 19.99
+        "###);
+        insta::assert_snapshot!(p("price NEAR cheap TOLERANCE 1"), @r###"
+        invalid float literal
+        12:17 price NEAR cheap TOLERANCE 1
+        "###);
+        insta::assert_snapshot!(p("price NEAR 19.99 TOLERANCE inf"), @r###"
+        Non finite floats are not supported
+        28:31 price NEAR 19.99 TOLERANCE inf
+        "###);
+        insta::assert_snapshot!(p("price ≈ 19.99 ± -0.01"), @r###"
+        The tolerance of a `NEAR` filter must be a positive number but `-0.01` was given.
+        17:22 price ≈ 19.99 ± -0.01
+        "###);
+        insta::assert_snapshot!(p("brand ~= samsng DISTANCE -1"), @r###"
+        The distance of a `~=` filter must be an integer between 0 and 255 but `-1` was given.
+        26:28 brand ~= samsng DISTANCE -1
+        "###);
+        insta::assert_snapshot!(p("brand ~= samsng DISTANCE two"), @r###"
+        The distance of a `~=` filter must be an integer between 0 and 255 but `two` was given.
+        26:29 brand ~= samsng DISTANCE two
+        "###);
+        insta::assert_snapshot!(p("brand ~="), @r###"
+        Was expecting a value but instead got nothing.
+        9:9 brand ~=
+        "###);
+        insta::assert_snapshot!(p("sku MATCHES \"A[0-9\""), @r###"
+        The pattern of a `MATCHES` filter must be a valid regex but `A[0-9` was given.
+        regex parse error:
+            A[0-9
+             ^
+        error: unclosed character class
+        14:19 sku MATCHES "A[0-9"
+        "###);
+        insta::assert_snapshot!(p("sku MATCHES \"[a-z]{100000}\""), @r###"
+        The pattern of a `MATCHES` filter must be a valid regex but `[a-z]{100000}` was given.
+        Compiled regex exceeds size limit of 1048576 bytes.
+        14:27 sku MATCHES "[a-z]{100000}"
+        "###);
+        insta::assert_snapshot!(p("sku MATCHES"), @r###"
+        Was expecting a value but instead got nothing.
+        12:12 sku MATCHES
+        "###);
+        insta::assert_snapshot!(p("id MOD 0 = 0"), @r###"
+        The divisor of a `MOD` filter must be a strictly positive integer but `0` was given.
+        8:9 id MOD 0 = 0
+        "###);
+        insta::assert_snapshot!(p("id MOD 2.5 = 1"), @r###"
+        The divisor of a `MOD` filter must be a strictly positive integer but `2.5` was given.
+        8:11 id MOD 2.5 = 1
+        "###);
+        insta::assert_snapshot!(p("id MOD 2 = one"), @r###"
+        The remainder of a `MOD` filter must be an integer but `one` was given.
+        12:15 id MOD 2 = one
+        "###);
+        insta::assert_snapshot!(p("id MOD 2 0"), @r###"
+        Was expecting a `=` followed by the remainder of the `MOD` filter at `0`.
+        10:11 id MOD 2 0
+        "###);
     }
 
     #[test]
@@ -1000,6 +1536,32 @@ pub mod tests {
         assert!(filter.token_at_depth(3).is_none());
     }
 
+    #[test]
+    fn simplify() {
+        let p = |s| Fc::parse(s).unwrap().unwrap();
+
+        let filter = p("a = 1 AND (b = 2 AND (c = 3 AND (d = 4 AND a = 1)))");
+        insta::assert_snapshot!(filter, @"AND[{a} = {1}, AND[{b} = {2}, AND[{c} = {3}, AND[{d} = {4}, {a} = {1}, ], ], ], ]");
+        insta::assert_snapshot!(filter.simplify(), @"AND[{a} = {1}, {b} = {2}, {c} = {3}, {d} = {4}, ]");
+
+        let filter = p("a = 1 OR (b = 2 OR (a = 1 OR c = 3)) OR (d = 4 AND (e = 5 AND f = 6))");
+        insta::assert_snapshot!(filter.simplify(), @"OR[{a} = {1}, {b} = {2}, {c} = {3}, AND[{d} = {4}, {e} = {5}, {f} = {6}, ], ]");
+
+        // the parser already drops the double negations, but not the built filters
+        let not = |filter| Fc::Not(Box::new(filter));
+        let filter = Fc::And(vec![not(not(p("a = 1"))), not(not(not(p("b = 2"))))]);
+        insta::assert_snapshot!(filter.simplify(), @"AND[{a} = {1}, NOT ({b} = {2}), ]");
+
+        // the nodes left with a single child are replaced by it
+        let filter = p("(a = 1 OR a = 1) AND (a = 1 AND a = 1)");
+        insta::assert_snapshot!(filter.simplify(), @"{a} = {1}");
+
+        // the different kinds of nodes are kept nested
+        let filter =
+            p("a = 1 AND (b = 2 OR (c = 3 OR d = 4)) AND NOT (e = 5 AND (f = 6 AND g = 7))");
+        insta::assert_snapshot!(filter.simplify(), @"AND[{a} = {1}, OR[{b} = {2}, {c} = {3}, {d} = {4}, ], NOT (AND[{e} = {5}, {f} = {6}, {g} = {7}, ]), ]");
+    }
+
     #[test]
     fn fids() {
         let filter = Fc::parse("field = value").unwrap().unwrap();
@@ -1037,6 +1599,27 @@ pub mod tests {
         assert_eq!(fids[0].value(), "field1");
     }
 
+    #[test]
+    fn difference() {
+        let filter = Fc::parse("a = 1 AND NOT b = 2").unwrap().unwrap();
+        assert!(
+            matches!(&filter, Fc::Difference { left, right } if left.is_simple() && right.is_simple())
+        );
+        insta::assert_snapshot!(filter, @"AND[{a} = {1}, NOT ({b} = {2}), ]");
+
+        // the conditions before the negation are kept together on the left side
+        let filter = Fc::parse("a = 1 AND b = 2 AND NOT c = 3 AND d = 4").unwrap().unwrap();
+        insta::assert_snapshot!(filter, @"AND[AND[AND[{a} = {1}, {b} = {2}, ], NOT ({c} = {3}), ], {d} = {4}, ]");
+
+        // only the negations of simple conditions are normalized
+        let filter = Fc::parse("a = 1 AND NOT (b = 2 OR c = 3)").unwrap().unwrap();
+        assert!(matches!(filter, Fc::And(_)));
+        let filter = Fc::parse("NOT a = 1 AND b = 2").unwrap().unwrap();
+        assert!(matches!(filter, Fc::And(_)));
+        let filter = Fc::parse("a = 1 AND b NOT IN [2, 3]").unwrap().unwrap();
+        assert!(matches!(filter, Fc::Difference { .. }));
+    }
+
     #[test]
     fn token_from_str() {
         let s = "test string that should not be parsed";
@@ -215,6 +215,9 @@ fn is_keyword(s: &str) -> bool {
             | "STARTS"
             | "WITH"
             | "_geoRadius"
+            | "_geoRadiusExclusive"
+            | "_geoRadiusApprox"
+            | "_geoNotRadius"
             | "_geoBoundingBox"
     )
 }
@@ -822,6 +822,10 @@ impl IndexScheduler {
                      config: milli::vector::EmbeddingConfig { embedder_options, prompt, quantized },
                      ..
                  }| {
+                    let prompt = embedder_options
+                        .document_prompt(prompt)
+                        .map_err(meilisearch_types::milli::vector::Error::from)
+                        .map_err(|err| Error::from_milli(err.into(), Some(index_uid.clone())))?;
                     let prompt = Arc::new(
                         prompt
                             .try_into()
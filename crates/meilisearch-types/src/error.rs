@@ -401,7 +401,7 @@ impl ErrorCode for milli_core::Error {
         use milli_core::{Error, UserError};
 
         match self {
-            Error::InternalError(_) => Code::Internal,
+            Error::InternalError(_) | Error::Interrupted => Code::Internal,
             Error::IoError(e) => e.error_code(),
             Error::UserError(ref error) => {
                 match error {
@@ -409,7 +409,9 @@ impl ErrorCode for milli_core::Error {
                     UserError::SerdeJson(_)
                     | UserError::EnvAlreadyOpened
                     | UserError::DocumentLimitReached
-                    | UserError::UnknownInternalDocumentId { .. } => Code::Internal,
+                    | UserError::UnknownInternalDocumentId { .. }
+                    | UserError::ChunkCompressionLevelUnsupported { .. }
+                    | UserError::InvalidChunkCompressionLevel { .. } => Code::Internal,
                     UserError::InvalidStoreFile => Code::InvalidStoreFile,
                     UserError::NoSpaceLeftOnDevice => Code::NoSpaceLeftOnDevice,
                     UserError::MaxDatabaseSizeReached => Code::DatabaseSizeLimitReached,
@@ -465,6 +467,9 @@ impl ErrorCode for milli_core::Error {
                     UserError::InvalidMinTypoWordLenSetting(_, _) => {
                         Code::InvalidSettingsTypoTolerance
                     }
+                    UserError::InvalidGeoRegion { .. } => Code::BadRequest,
+                    UserError::InvalidGeoPoint(_) => Code::BadRequest,
+                    UserError::InvalidPercentile(_) => Code::BadRequest,
                     UserError::InvalidSearchEmbedder(_) => Code::InvalidSearchEmbedder,
                     UserError::InvalidSimilarEmbedder(_) => Code::InvalidSimilarEmbedder,
                     UserError::VectorEmbeddingError(_) | UserError::DocumentEmbeddingError(_) => {
@@ -645,7 +645,7 @@ async fn filter_invalid_syntax_object() {
         |response, code| {
             snapshot!(response, @r###"
             {
-              "message": "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `title & Glass`.\n1:14 title & Glass",
+              "message": "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `title & Glass`.\n1:14 title & Glass",
               "code": "invalid_search_filter",
               "type": "invalid_request",
               "link": "https://docs.meilisearch.com/errors#invalid_search_filter"
@@ -666,7 +666,7 @@ async fn filter_invalid_syntax_array() {
         |response, code| {
             snapshot!(response, @r###"
             {
-              "message": "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `CONTAINS`, `NOT CONTAINS`, `STARTS WITH`, `NOT STARTS WITH`, `_geoRadius`, or `_geoBoundingBox` at `title & Glass`.\n1:14 title & Glass",
+              "message": "Was expecting an operation `=`, `!=`, `>=`, `>`, `<=`, `<`, `IN`, `NOT IN`, `TO`, `EXISTS`, `NOT EXISTS`, `IS NULL`, `IS NOT NULL`, `IS EMPTY`, `IS NOT EMPTY`, `IS TRUE`, `IS FALSE`, `IS DATE`, `IS NOT DATE`, `CONTAINS`, `CONTAINS ALL`, `NOT CONTAINS`, `STARTS WITH`, `STARTS WITH ANY`, `NOT STARTS WITH`, `NEAR`, `~=`, `MATCHES`, `MOD`, `_length`, `_strlen`, `_geoRadius`, `_geoRadiusExclusive`, `_geoRadiusApprox`, `_geoNotRadius`, `_geoInRegion`, or `_geoBoundingBox` at `title & Glass`.\n1:14 title & Glass",
               "code": "invalid_search_filter",
               "type": "invalid_request",
               "link": "https://docs.meilisearch.com/errors#invalid_search_filter"
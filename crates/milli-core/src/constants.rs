@@ -11,3 +11,7 @@ const fn parse_u32(s: &str) -> u32 {
 
 pub const RESERVED_VECTORS_FIELD_NAME: &str = "_vectors";
 pub const RESERVED_GEO_FIELD_NAME: &str = "_geo";
+/// The latitude of the `_geo` field, faceted as a number.
+pub const RESERVED_GEO_LAT_FIELD_NAME: &str = "_geo.lat";
+/// The longitude of the `_geo` field, faceted as a number.
+pub const RESERVED_GEO_LNG_FIELD_NAME: &str = "_geo.lng";
@@ -13,6 +13,7 @@ use thiserror::Error;
 
 use crate::constants::RESERVED_GEO_FIELD_NAME;
 use crate::documents::{self, DocumentsBatchCursorError};
+use crate::search::facet::BadGeoError;
 use crate::thread_pool_no_abort::PanicCatched;
 use crate::vector::settings::EmbeddingSettings;
 use crate::{CriterionError, DocumentId, FieldId, Object, SortError};
@@ -30,6 +31,8 @@ pub enum Error {
     IoError(#[from] io::Error),
     #[error(transparent)]
     UserError(#[from] UserError),
+    #[error("The operation was interrupted.")]
+    Interrupted,
 }
 
 #[derive(Error, Debug)]
@@ -174,6 +177,10 @@ and can not be more than 511 bytes.", .document_id.to_string()
         allowed_operators.join(", "),
         if operator == "=" || operator == "!=" || operator == "IN" {"equality"}
         else if operator == "<" || operator == ">" || operator == "<=" || operator == ">=" || operator == "TO" {"comparison"}
+        else if operator == "_field" {"fieldComparison"}
+        else if operator == "~=" {"fuzzyEqual"}
+        else if operator == "MATCHES" {"regex"}
+        else if operator == "MOD" {"modulo"}
         else {"the appropriate filter operators"}
     )]
     FilterOperatorNotAllowed {
@@ -279,6 +286,21 @@ and can not be more than 511 bytes.", .document_id.to_string()
     UnknownInternalDocumentId { document_id: DocumentId },
     #[error("`minWordSizeForTypos` setting is invalid. `oneTypo` and `twoTypos` fields should be between `0` and `255`, and `twoTypos` should be greater or equals to `oneTypo` but found `oneTypo: {0}` and twoTypos: {1}`.")]
     InvalidMinTypoWordLenSetting(u8, u8),
+    #[error("The geo region `{name}` is invalid: {reason}.")]
+    InvalidGeoRegion { name: String, reason: String },
+    #[error(transparent)]
+    InvalidGeoPoint(#[from] BadGeoError),
+    #[error("The percentile must be between `0` and `100` but `{0}` was given.")]
+    InvalidPercentile(f64),
+    #[error("The `{compression_type:?}` chunk compression doesn't use a compression level but the level `{level}` was given.")]
+    ChunkCompressionLevelUnsupported { compression_type: grenad::CompressionType, level: u32 },
+    #[error("The `{compression_type:?}` chunk compression level must be between `{min}` and `{max}` but `{level}` was given.")]
+    InvalidChunkCompressionLevel {
+        compression_type: grenad::CompressionType,
+        level: u32,
+        min: u32,
+        max: u32,
+    },
     #[error(transparent)]
     VectorEmbeddingError(#[from] crate::vector::Error),
     #[error(transparent)]
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Which original value represents a facet value when several distinct original
+/// values, like `Café` and `cafe`, are normalized into the same facet value.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum FacetRepresentative {
+    /// The original value of the first document found with this facet value.
+    #[default]
+    FirstSeen,
+    /// The smallest original value in lexicographic order.
+    Lexicographic,
+    /// The original value shared by the most documents, ties are won by the first seen.
+    MostFrequent,
+}
+
+impl FacetRepresentative {
+    /// Returns `true` if the representative can be chosen without looking at all the originals.
+    pub fn is_first_seen(&self) -> bool {
+        matches!(self, FacetRepresentative::FirstSeen)
+    }
+
+    /// Chooses the representative among the original values of the documents sharing
+    /// a facet value, the originals must be given by increasing internal document id.
+    pub fn choose<'a, I>(&self, originals: I) -> Option<&'a str>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut originals = originals.into_iter();
+        match self {
+            FacetRepresentative::FirstSeen => originals.next(),
+            FacetRepresentative::Lexicographic => originals.min(),
+            FacetRepresentative::MostFrequent => {
+                // the rank of the first occurrence breaks the ties between the counts
+                let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+                for (rank, original) in originals.enumerate() {
+                    counts.entry(original).or_insert((0, rank)).0 += 1;
+                }
+                counts
+                    .into_iter()
+                    .max_by(|(_, (lcount, lrank)), (_, (rcount, rrank))| {
+                        lcount.cmp(rcount).then(rrank.cmp(lrank))
+                    })
+                    .map(|(original, _)| original)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FacetRepresentative;
+
+    #[test]
+    fn choose_representative() {
+        let originals = ["cafe", "Café", "CAFE", "Café", "cafe"];
+
+        assert_eq!(FacetRepresentative::FirstSeen.choose(originals), Some("cafe"));
+        assert_eq!(FacetRepresentative::Lexicographic.choose(originals), Some("CAFE"));
+        // `cafe` and `Café` both appear twice, `cafe` is seen first
+        assert_eq!(FacetRepresentative::MostFrequent.choose(originals), Some("cafe"));
+        assert_eq!(FacetRepresentative::MostFrequent.choose(["a", "B", "B"]), Some("B"));
+        assert_eq!(FacetRepresentative::MostFrequent.choose([]), None);
+    }
+}
@@ -1,6 +1,8 @@
+mod facet_representative;
 mod facet_type;
 mod facet_value;
 pub mod value_encoding;
 
+pub use self::facet_representative::FacetRepresentative;
 pub use self::facet_type::FacetType;
 pub use self::facet_value::FacetValue;
@@ -13,6 +13,17 @@ pub fn f64_into_bytes(float: f64) -> Option<[u8; 8]> {
     None
 }
 
+/// Returns the float with a negative zero replaced by the positive zero, so that both zeros
+/// are stored under the same facet value.
+#[inline]
+pub fn canonicalize_zero(float: f64) -> f64 {
+    if float == 0.0 {
+        0.0
+    } else {
+        float
+    }
+}
+
 #[inline]
 fn xor_first_bit(mut x: [u8; 8]) -> [u8; 8] {
     x[0] ^= 0x80;
@@ -127,6 +127,51 @@ impl FilterableAttributesFeatures {
         self.facet_search
     }
 
+    /// The value the documents missing the field take in the range filters
+    pub fn missing_as(&self) -> Option<f64> {
+        self.filter.missing_as()
+    }
+
+    /// Check if the range filters ignore the string values of the field
+    pub fn is_numeric_only(&self) -> bool {
+        self.filter.is_numeric_only()
+    }
+
+    /// Check if the integers of the field are stored exactly
+    pub fn is_integer(&self) -> bool {
+        self.filter.is_integer()
+    }
+
+    /// Check if the numbers of the field are also stored as strings
+    pub fn is_prefixable_numbers(&self) -> bool {
+        self.filter.is_prefixable_numbers()
+    }
+
+    /// Check if `_strlen` is allowed
+    pub fn is_string_length(&self) -> bool {
+        self.filter.is_string_length()
+    }
+
+    /// Check if comparing the field to another field with `_field` is allowed
+    pub fn is_field_comparison(&self) -> bool {
+        self.filter.is_field_comparison()
+    }
+
+    /// Check if `~=` is allowed
+    pub fn is_fuzzy_equal(&self) -> bool {
+        self.filter.is_fuzzy_equal()
+    }
+
+    /// Check if `MATCHES` is allowed
+    pub fn is_regex(&self) -> bool {
+        self.filter.is_regex()
+    }
+
+    /// Check if `MOD` is allowed
+    pub fn is_modulo(&self) -> bool {
+        self.filter.is_modulo()
+    }
+
     pub fn allowed_filter_operators(&self) -> Vec<String> {
         self.filter.allowed_operators()
     }
@@ -147,7 +192,7 @@ impl<E: DeserializeError> Deserr<E> for FilterableAttributesRule {
     }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Deserr, ToSchema)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug, Deserr, ToSchema)]
 #[serde(deny_unknown_fields, rename_all = "camelCase")]
 #[deserr(rename_all = camelCase, deny_unknown_fields)]
 pub struct FilterFeatures {
@@ -157,8 +202,62 @@ pub struct FilterFeatures {
     #[serde(default)]
     #[deserr(default)]
     comparison: bool,
+    /// The value taken by the documents missing the field in the range filters,
+    /// so that `price >= 0` also returns the documents without a price when it is `0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[deserr(default)]
+    missing_as: Option<f64>,
+    /// Only compare the numbers of the field in the range filters, ignoring its strings,
+    /// so that `price > 10` doesn't also match the documents with a `"ten"` price.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[deserr(default)]
+    numeric_only: bool,
+    /// Store the integers of the field exactly, so that the filters on integer identifiers
+    /// beyond 2^53 don't match the neighbouring integers sharing the same `f64`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[deserr(default)]
+    integer: bool,
+    /// Also store the numbers of the field as strings, so that `zip STARTS WITH "900"`
+    /// matches the documents with a `90001` zip number like the ones with a `"90001"` string.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[deserr(default)]
+    prefixable_numbers: bool,
+    /// Allow filtering on the number of characters of the string values of the field,
+    /// as in `_strlen(sku) = 8`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[deserr(default)]
+    string_length: bool,
+    /// Allow comparing the numbers of the field to the ones of another field of the same document,
+    /// as in `sale_price < _field(list_price)`. Both fields must allow it.
+    ///
+    /// These comparisons read the values of every document containing both fields,
+    /// they are much more expensive than the comparisons to a value.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[deserr(default)]
+    field_comparison: bool,
+    /// Allow the `~=` operator matching the string values within a number of typos of a value.
+    ///
+    /// These filters scan every string value of the field.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[deserr(default)]
+    fuzzy_equal: bool,
+    /// Allow the `MATCHES` operator matching the string values against a regex.
+    ///
+    /// These filters scan every string value of the field.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[deserr(default)]
+    regex: bool,
+    /// Allow the `MOD` operator selecting the integers with a given remainder, as in `id MOD 10 = 0`.
+    ///
+    /// These filters scan every number of the field.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[deserr(default)]
+    modulo: bool,
 }
 
+// `missing_as` comes from a JSON number and can never be NaN
+impl Eq for FilterFeatures {}
+
 fn default_true() -> bool {
     true
 }
@@ -186,6 +285,21 @@ impl FilterFeatures {
         if self.is_filterable_exists() {
             operators.push("EXISTS");
         }
+        if self.is_string_length() {
+            operators.push("_strlen");
+        }
+        if self.is_field_comparison() {
+            operators.push("_field");
+        }
+        if self.is_fuzzy_equal() {
+            operators.push("~=");
+        }
+        if self.is_regex() {
+            operators.push("MATCHES");
+        }
+        if self.is_modulo() {
+            operators.push("MOD");
+        }
 
         operators.into_iter().map(String::from).collect()
     }
@@ -218,23 +332,104 @@ impl FilterFeatures {
         self.is_filterable()
     }
 
+    /// The value the documents missing the field take in the range filters
+    pub fn missing_as(&self) -> Option<f64> {
+        self.missing_as
+    }
+
+    /// Check if the range filters ignore the string values of the field
+    pub fn is_numeric_only(&self) -> bool {
+        self.numeric_only
+    }
+
+    /// Check if the integers of the field are stored exactly
+    pub fn is_integer(&self) -> bool {
+        self.integer
+    }
+
+    /// Check if the numbers of the field are also stored as strings
+    pub fn is_prefixable_numbers(&self) -> bool {
+        self.prefixable_numbers
+    }
+
+    /// Check if `_strlen` is allowed
+    pub fn is_string_length(&self) -> bool {
+        self.string_length
+    }
+
+    /// Check if comparing the field to another field with `_field` is allowed
+    pub fn is_field_comparison(&self) -> bool {
+        self.field_comparison
+    }
+
+    /// Check if `~=` is allowed
+    pub fn is_fuzzy_equal(&self) -> bool {
+        self.fuzzy_equal
+    }
+
+    /// Check if `MATCHES` is allowed
+    pub fn is_regex(&self) -> bool {
+        self.regex
+    }
+
+    /// Check if `MOD` is allowed
+    pub fn is_modulo(&self) -> bool {
+        self.modulo
+    }
+
     /// Create a new `FilterFeatures` with the legacy default features.
     ///
     /// This is the default behavior for `FilterableAttributesRule::Field`.
     /// This will set the equality and comparison to true.
     pub fn legacy_default() -> Self {
-        Self { equality: true, comparison: true }
+        Self {
+            equality: true,
+            comparison: true,
+            missing_as: None,
+            numeric_only: false,
+            integer: false,
+            prefixable_numbers: false,
+            string_length: false,
+            field_comparison: false,
+            fuzzy_equal: false,
+            regex: false,
+            modulo: false,
+        }
     }
 
     /// Create a new `FilterFeatures` with no features.
     pub fn no_features() -> Self {
-        Self { equality: false, comparison: false }
+        Self {
+            equality: false,
+            comparison: false,
+            missing_as: None,
+            numeric_only: false,
+            integer: false,
+            prefixable_numbers: false,
+            string_length: false,
+            field_comparison: false,
+            fuzzy_equal: false,
+            regex: false,
+            modulo: false,
+        }
     }
 }
 
 impl Default for FilterFeatures {
     fn default() -> Self {
-        Self { equality: true, comparison: false }
+        Self {
+            equality: true,
+            comparison: false,
+            missing_as: None,
+            numeric_only: false,
+            integer: false,
+            prefixable_numbers: false,
+            string_length: false,
+            field_comparison: false,
+            fuzzy_equal: false,
+            regex: false,
+            modulo: false,
+        }
     }
 }
 
@@ -0,0 +1,63 @@
+use std::borrow::Cow;
+
+use heed::{BoxedError, BytesDecode, BytesEncode};
+
+use crate::heed_codec::SliceTooShortError;
+use crate::{try_split_array_at, FieldId};
+
+/// The key of the [`facet_id_i64_docids`][`crate::Index::facet_id_i64_docids`] database,
+/// the field id followed by the integer encoded so that the bytes are ordered like the integers.
+pub struct FieldIdI64Codec;
+
+impl FieldIdI64Codec {
+    /// Encodes the integer into big-endian bytes ordered like the integers.
+    pub fn ordered_bytes(integer: i64) -> [u8; 8] {
+        // flipping the sign bit moves the negative integers before the positive ones
+        ((integer as u64) ^ (1 << 63)).to_be_bytes()
+    }
+
+    fn from_ordered_bytes(bytes: [u8; 8]) -> i64 {
+        (u64::from_be_bytes(bytes) ^ (1 << 63)) as i64
+    }
+}
+
+impl<'a> BytesDecode<'a> for FieldIdI64Codec {
+    type DItem = (FieldId, i64);
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, BoxedError> {
+        let (field_id_bytes, bytes) = try_split_array_at(bytes).ok_or(SliceTooShortError)?;
+        let field_id = u16::from_be_bytes(field_id_bytes);
+        let (integer_bytes, _) = try_split_array_at(bytes).ok_or(SliceTooShortError)?;
+        Ok((field_id, Self::from_ordered_bytes(integer_bytes)))
+    }
+}
+
+impl<'a> BytesEncode<'a> for FieldIdI64Codec {
+    type EItem = (FieldId, i64);
+
+    fn bytes_encode((field_id, integer): &Self::EItem) -> Result<Cow<'a, [u8]>, BoxedError> {
+        let mut bytes = Vec::with_capacity(2 + 8);
+        bytes.extend_from_slice(&field_id.to_be_bytes());
+        bytes.extend_from_slice(&Self::ordered_bytes(*integer));
+        Ok(Cow::Owned(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordered_like_the_integers() {
+        let integers = [i64::MIN, -(1 << 53) - 1, -1, 0, 1, (1 << 53) + 1, i64::MAX];
+        let encoded: Vec<_> = integers
+            .iter()
+            .map(|integer| FieldIdI64Codec::bytes_encode(&(3, *integer)).unwrap().into_owned())
+            .collect();
+
+        assert!(encoded.windows(2).all(|pair| pair[0] < pair[1]));
+        for (bytes, integer) in encoded.iter().zip(integers) {
+            assert_eq!(FieldIdI64Codec::bytes_decode(bytes).unwrap(), (3, integer));
+        }
+    }
+}
@@ -1,4 +1,5 @@
 mod field_doc_id_facet_codec;
+mod field_id_i64_codec;
 mod ordered_f64_codec;
 
 use std::borrow::Cow;
@@ -10,6 +11,7 @@ use heed::{BoxedError, BytesDecode, BytesEncode};
 use roaring::RoaringBitmap;
 
 pub use self::field_doc_id_facet_codec::FieldDocIdFacetCodec;
+pub use self::field_id_i64_codec::FieldIdI64Codec;
 pub use self::ordered_f64_codec::OrderedF64Codec;
 use super::StrRefCodec;
 use crate::{CboRoaringBitmapCodec, BEU16};
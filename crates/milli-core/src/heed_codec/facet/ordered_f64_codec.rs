@@ -4,7 +4,7 @@ use std::convert::TryInto;
 use heed::{BoxedError, BytesDecode};
 use thiserror::Error;
 
-use crate::facet::value_encoding::f64_into_bytes;
+use crate::facet::value_encoding::{canonicalize_zero, f64_into_bytes};
 use crate::heed_codec::SliceTooShortError;
 
 pub struct OrderedF64Codec;
@@ -46,6 +46,7 @@ fn encode_f64_into_ordered_bytes(
     f: f64,
     buffer: &mut [u8; 16],
 ) -> Result<(), InvalidGloballyOrderedFloatError> {
+    let f = canonicalize_zero(f);
     // write the globally ordered float
     let bytes = f64_into_bytes(f).ok_or(InvalidGloballyOrderedFloatError { float: f })?;
     buffer[..8].copy_from_slice(&bytes[..]);
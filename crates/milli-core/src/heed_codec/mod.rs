@@ -9,6 +9,7 @@ mod roaring_bitmap;
 mod roaring_bitmap_length;
 mod str_beu32_codec;
 mod str_ref;
+mod str_str_str_codec;
 mod str_str_u8_codec;
 pub mod version;
 
@@ -27,6 +28,7 @@ pub use self::roaring_bitmap_length::{
     BoRoaringBitmapLenCodec, CboRoaringBitmapLenCodec, RoaringBitmapLenCodec,
 };
 pub use self::str_beu32_codec::{StrBEU16Codec, StrBEU32Codec};
+pub use self::str_str_str_codec::StrStrStrCodec;
 pub use self::str_str_u8_codec::{U8StrStrCodec, UncheckedU8StrStrCodec};
 
 pub trait BytesDecodeOwned {
@@ -0,0 +1,35 @@
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::str;
+
+use heed::BoxedError;
+
+/// Three strings separated by `\0` bytes, the key of the word triples.
+pub struct StrStrStrCodec;
+
+impl<'a> heed::BytesDecode<'a> for StrStrStrCodec {
+    type DItem = (&'a str, &'a str, &'a str);
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, BoxedError> {
+        let s1 = CStr::from_bytes_until_nul(bytes)?.to_str()?;
+        // skip '\0' byte between the strings.
+        let bytes = &bytes[s1.len() + 1..];
+        let s2 = CStr::from_bytes_until_nul(bytes)?.to_str()?;
+        let s3 = str::from_utf8(&bytes[s2.len() + 1..])?;
+        Ok((s1, s2, s3))
+    }
+}
+
+impl<'a> heed::BytesEncode<'a> for StrStrStrCodec {
+    type EItem = (&'a str, &'a str, &'a str);
+
+    fn bytes_encode((s1, s2, s3): &Self::EItem) -> Result<Cow<'a, [u8]>, BoxedError> {
+        let mut bytes = Vec::with_capacity(s1.len() + s2.len() + s3.len() + 2);
+        bytes.extend_from_slice(s1.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(s2.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(s3.as_bytes());
+        Ok(Cow::Owned(bytes))
+    }
+}
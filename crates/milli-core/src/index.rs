@@ -1,36 +1,45 @@
 use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
 use std::fs::File;
+use std::ops::Bound;
 use std::path::Path;
 
+use charabia::Language;
 use heed::types::*;
-use heed::{CompactionOption, Database, DatabaseStat, RoTxn, RwTxn, Unspecified, WithoutTls};
+use heed::{
+    BytesDecode, CompactionOption, Database, DatabaseStat, RoTxn, RwTxn, Unspecified, WithoutTls,
+};
 use indexmap::IndexMap;
 use roaring::RoaringBitmap;
-use rstar::RTree;
+use rstar::{RTree, AABB};
 use serde::{Deserialize, Serialize};
 
 use crate::constants::{self, RESERVED_GEO_FIELD_NAME, RESERVED_VECTORS_FIELD_NAME};
 use crate::database_stats::DatabaseStats;
 use crate::documents::PrimaryKey;
 use crate::error::{InternalError, UserError};
+use crate::facet::FacetRepresentative;
 use crate::fields_ids_map::metadata::{FieldIdMapWithMetadata, MetadataBuilder};
 use crate::fields_ids_map::FieldsIdsMap;
 use crate::heed_codec::facet::{
-    FacetGroupKeyCodec, FacetGroupValueCodec, FieldDocIdFacetF64Codec, FieldDocIdFacetStringCodec,
-    FieldIdCodec, OrderedF64Codec,
+    FacetGroupKey, FacetGroupKeyCodec, FacetGroupValueCodec, FieldDocIdFacetF64Codec,
+    FieldDocIdFacetStringCodec, FieldIdCodec, FieldIdI64Codec, OrderedF64Codec,
 };
 use crate::heed_codec::version::VersionCodec;
-use crate::heed_codec::{BEU16StrCodec, FstSetCodec, StrBEU16Codec, StrRefCodec};
+use crate::heed_codec::{
+    BEU16StrCodec, BytesRefCodec, FstSetCodec, StrBEU16Codec, StrRefCodec, StrStrStrCodec,
+};
 use crate::order_by_map::OrderByMap;
 use crate::proximity::ProximityPrecision;
+use crate::search::facet::{ascending_facet_sort, get_highest_level, BadGeoError};
 use crate::vector::{ArroyStats, ArroyWrapper, Embedding, EmbeddingConfig};
 use crate::{
-    default_criteria, CboRoaringBitmapCodec, Criterion, DocumentId, ExternalDocumentsIds,
-    FacetDistribution, FieldDistribution, FieldId, FieldIdMapMissingEntry, FieldIdWordCountCodec,
-    FieldidsWeightsMap, FilterableAttributesRule, GeoPoint, LocalizedAttributesRule, ObkvCodec,
-    Result, RoaringBitmapCodec, RoaringBitmapLenCodec, Search, U8StrStrCodec, Weight, BEU16, BEU32,
-    BEU64,
+    default_criteria, distance_between_two_points, lat_lng_to_xyz, CboRoaringBitmapCodec,
+    Criterion, DocumentId, ExternalDocumentsIds, FacetDistribution, FieldDistribution, FieldId,
+    FieldIdMapMissingEntry, FieldIdWordCountCodec, FieldidsWeightsMap, FilterableAttributesRule,
+    GeoPoint, LocalizedAttributesRule, ObkvCodec, Result, RoaringBitmapCodec,
+    RoaringBitmapLenCodec, Search, U8StrStrCodec, Weight, BEU16, BEU32, BEU64,
 };
 
 pub const DEFAULT_MIN_WORD_LEN_ONE_TYPO: u8 = 5;
@@ -70,12 +79,21 @@ pub mod main_key {
     pub const EXACT_ATTRIBUTES: &str = "exact-attributes";
     pub const MAX_VALUES_PER_FACET: &str = "max-values-per-facet";
     pub const SORT_FACET_VALUES_BY: &str = "sort-facet-values-by";
+    pub const GEO_REGIONS: &str = "geo-regions";
     pub const PAGINATION_MAX_TOTAL_HITS: &str = "pagination-max-total-hits";
     pub const PROXIMITY_PRECISION: &str = "proximity-precision";
     pub const EMBEDDING_CONFIGS: &str = "embedding_configs";
     pub const SEARCH_CUTOFF: &str = "search_cutoff";
+    pub const FILTER_SCAN_LIMIT: &str = "filter_scan_limit";
+    pub const FACET_CARDINALITY_BUDGET: &str = "facet_cardinality_budget";
     pub const LOCALIZED_ATTRIBUTES_RULES: &str = "localized_attributes_rules";
     pub const FACET_SEARCH: &str = "facet_search";
+    pub const RECORD_WORD_TRIPLES: &str = "record_word_triples";
+    pub const RECORD_WORD_PAIR_FREQUENCIES: &str = "record_word_pair_frequencies";
+    pub const PROXIMITY_EXCLUDED_FIELDS: &str = "proximity_excluded_fields";
+    pub const FACET_NORMALIZATION_LOCALE: &str = "facet_normalization_locale";
+    pub const FACET_REPRESENTATIVE: &str = "facet_representative";
+    pub const DOCUMENTS_VERSION: &str = "documents-version";
     pub const PREFIX_SEARCH: &str = "prefix_search";
     pub const DOCUMENTS_STATS: &str = "documents_stats";
     pub const DISABLED_TYPOS_TERMS: &str = "disabled_typos_terms";
@@ -90,25 +108,33 @@ pub mod db_name {
     pub const EXTERNAL_DOCUMENTS_IDS: &str = "external-documents-ids";
     pub const DOCID_WORD_POSITIONS: &str = "docid-word-positions";
     pub const WORD_PAIR_PROXIMITY_DOCIDS: &str = "word-pair-proximity-docids";
+    pub const WORD_TRIPLE_DOCIDS: &str = "word-triple-docids";
+    pub const WORD_PAIR_FREQUENCY_DOCIDS: &str = "word-pair-frequency-docids";
     pub const WORD_POSITION_DOCIDS: &str = "word-position-docids";
     pub const WORD_FIELD_ID_DOCIDS: &str = "word-field-id-docids";
     pub const WORD_PREFIX_POSITION_DOCIDS: &str = "word-prefix-position-docids";
     pub const WORD_PREFIX_FIELD_ID_DOCIDS: &str = "word-prefix-field-id-docids";
     pub const FIELD_ID_WORD_COUNT_DOCIDS: &str = "field-id-word-count-docids";
     pub const FACET_ID_F64_DOCIDS: &str = "facet-id-f64-docids";
+    pub const FACET_ID_I64_DOCIDS: &str = "facet-id-i64-docids";
     pub const FACET_ID_EXISTS_DOCIDS: &str = "facet-id-exists-docids";
     pub const FACET_ID_IS_NULL_DOCIDS: &str = "facet-id-is-null-docids";
     pub const FACET_ID_IS_EMPTY_DOCIDS: &str = "facet-id-is-empty-docids";
+    pub const FACET_ID_IS_DATE_DOCIDS: &str = "facet-id-is-date-docids";
+    pub const FACET_ID_IS_TRUE_DOCIDS: &str = "facet-id-is-true-docids";
+    pub const FACET_ID_IS_FALSE_DOCIDS: &str = "facet-id-is-false-docids";
     pub const FACET_ID_STRING_DOCIDS: &str = "facet-id-string-docids";
     pub const FACET_ID_NORMALIZED_STRING_STRINGS: &str = "facet-id-normalized-string-strings";
+    pub const FACET_ID_STRING_REPRESENTATIVES: &str = "facet-id-string-representatives";
     pub const FACET_ID_STRING_FST: &str = "facet-id-string-fst";
     pub const FIELD_ID_DOCID_FACET_F64S: &str = "field-id-docid-facet-f64s";
     pub const FIELD_ID_DOCID_FACET_STRINGS: &str = "field-id-docid-facet-strings";
     pub const VECTOR_EMBEDDER_CATEGORY_ID: &str = "vector-embedder-category-id";
     pub const VECTOR_ARROY: &str = "vector-arroy";
     pub const DOCUMENTS: &str = "documents";
+    pub const VERSION_DOCIDS: &str = "version-docids";
 }
-const NUMBER_OF_DBS: u32 = 25;
+const NUMBER_OF_DBS: u32 = 33;
 
 #[derive(Clone)]
 pub struct Index {
@@ -136,6 +162,13 @@ pub struct Index {
     /// Maps the proximity between a pair of words with all the docids where this relation appears.
     pub word_pair_proximity_docids: Database<U8StrStrCodec, CboRoaringBitmapCodec>,
 
+    /// Maps three words following each other with all the docids where they appear in this order.
+    pub word_triple_docids: Database<StrStrStrCodec, CboRoaringBitmapCodec>,
+
+    /// Maps the number of times a word directly follows another one in a document,
+    /// capped to [`crate::proximity::MAX_WORD_PAIR_FREQUENCY`], with all the docids where this number is reached.
+    pub word_pair_frequency_docids: Database<U8StrStrCodec, CboRoaringBitmapCodec>,
+
     /// Maps the word and the position with the docids that corresponds to it.
     pub word_position_docids: Database<StrBEU16Codec, CboRoaringBitmapCodec>,
     /// Maps the word and the field id with the docids that corresponds to it.
@@ -154,13 +187,24 @@ pub struct Index {
     pub facet_id_is_null_docids: Database<FieldIdCodec, CboRoaringBitmapCodec>,
     /// Maps the facet field id and the docids for which this field is considered empty
     pub facet_id_is_empty_docids: Database<FieldIdCodec, CboRoaringBitmapCodec>,
+    /// Maps the facet field id and the docids for which this field contains a valid date
+    pub facet_id_is_date_docids: Database<FieldIdCodec, CboRoaringBitmapCodec>,
+    /// Maps the facet field id and the docids for which this field contains the boolean `true`
+    pub facet_id_is_true_docids: Database<FieldIdCodec, CboRoaringBitmapCodec>,
+    /// Maps the facet field id and the docids for which this field contains the boolean `false`
+    pub facet_id_is_false_docids: Database<FieldIdCodec, CboRoaringBitmapCodec>,
 
     /// Maps the facet field id and ranges of numbers with the docids that corresponds to them.
     pub facet_id_f64_docids: Database<FacetGroupKeyCodec<OrderedF64Codec>, FacetGroupValueCodec>,
+    /// Maps the facet field id and the exact integers of the integer fields with the docids that corresponds to them.
+    pub facet_id_i64_docids: Database<FieldIdI64Codec, CboRoaringBitmapCodec>,
     /// Maps the facet field id and ranges of strings with the docids that corresponds to them.
     pub facet_id_string_docids: Database<FacetGroupKeyCodec<StrRefCodec>, FacetGroupValueCodec>,
     /// Maps the facet field id of the normalized-for-search string facets with their original versions.
     pub facet_id_normalized_string_strings: Database<BEU16StrCodec, SerdeJson<BTreeSet<String>>>,
+    /// Maps the facet field id and the normalized string facets with the original representing them,
+    /// as chosen by the facet representative setting.
+    pub facet_id_string_representatives: Database<BEU16StrCodec, Str>,
     /// Maps the facet field id of the string facets with an FST containing all the facets values.
     pub facet_id_string_fst: Database<BEU16, FstSetCodec>,
 
@@ -176,6 +220,9 @@ pub struct Index {
 
     /// Maps the document id to the document as an obkv store.
     pub(crate) documents: Database<BEU32, ObkvCodec>,
+
+    /// Maps a version of the documents to the ids of the documents added or updated in this version.
+    pub version_docids: Database<BEU64, CboRoaringBitmapCodec>,
 }
 
 impl Index {
@@ -202,6 +249,9 @@ impl Index {
             env.create_database(&mut wtxn, Some(EXACT_WORD_PREFIX_DOCIDS))?;
         let word_pair_proximity_docids =
             env.create_database(&mut wtxn, Some(WORD_PAIR_PROXIMITY_DOCIDS))?;
+        let word_triple_docids = env.create_database(&mut wtxn, Some(WORD_TRIPLE_DOCIDS))?;
+        let word_pair_frequency_docids =
+            env.create_database(&mut wtxn, Some(WORD_PAIR_FREQUENCY_DOCIDS))?;
         let word_position_docids = env.create_database(&mut wtxn, Some(WORD_POSITION_DOCIDS))?;
         let word_fid_docids = env.create_database(&mut wtxn, Some(WORD_FIELD_ID_DOCIDS))?;
         let field_id_word_count_docids =
@@ -211,10 +261,13 @@ impl Index {
         let word_prefix_fid_docids =
             env.create_database(&mut wtxn, Some(WORD_PREFIX_FIELD_ID_DOCIDS))?;
         let facet_id_f64_docids = env.create_database(&mut wtxn, Some(FACET_ID_F64_DOCIDS))?;
+        let facet_id_i64_docids = env.create_database(&mut wtxn, Some(FACET_ID_I64_DOCIDS))?;
         let facet_id_string_docids =
             env.create_database(&mut wtxn, Some(FACET_ID_STRING_DOCIDS))?;
         let facet_id_normalized_string_strings =
             env.create_database(&mut wtxn, Some(FACET_ID_NORMALIZED_STRING_STRINGS))?;
+        let facet_id_string_representatives =
+            env.create_database(&mut wtxn, Some(FACET_ID_STRING_REPRESENTATIVES))?;
         let facet_id_string_fst = env.create_database(&mut wtxn, Some(FACET_ID_STRING_FST))?;
         let facet_id_exists_docids =
             env.create_database(&mut wtxn, Some(FACET_ID_EXISTS_DOCIDS))?;
@@ -222,6 +275,12 @@ impl Index {
             env.create_database(&mut wtxn, Some(FACET_ID_IS_NULL_DOCIDS))?;
         let facet_id_is_empty_docids =
             env.create_database(&mut wtxn, Some(FACET_ID_IS_EMPTY_DOCIDS))?;
+        let facet_id_is_date_docids =
+            env.create_database(&mut wtxn, Some(FACET_ID_IS_DATE_DOCIDS))?;
+        let facet_id_is_true_docids =
+            env.create_database(&mut wtxn, Some(FACET_ID_IS_TRUE_DOCIDS))?;
+        let facet_id_is_false_docids =
+            env.create_database(&mut wtxn, Some(FACET_ID_IS_FALSE_DOCIDS))?;
         let field_id_docid_facet_f64s =
             env.create_database(&mut wtxn, Some(FIELD_ID_DOCID_FACET_F64S))?;
         let field_id_docid_facet_strings =
@@ -232,6 +291,7 @@ impl Index {
         let vector_arroy = env.create_database(&mut wtxn, Some(VECTOR_ARROY))?;
 
         let documents = env.create_database(&mut wtxn, Some(DOCUMENTS))?;
+        let version_docids = env.create_database(&mut wtxn, Some(VERSION_DOCIDS))?;
 
         let this = Index {
             env: env.clone(),
@@ -242,23 +302,31 @@ impl Index {
             word_prefix_docids,
             exact_word_prefix_docids,
             word_pair_proximity_docids,
+            word_triple_docids,
+            word_pair_frequency_docids,
             word_position_docids,
             word_fid_docids,
             word_prefix_position_docids,
             word_prefix_fid_docids,
             field_id_word_count_docids,
             facet_id_f64_docids,
+            facet_id_i64_docids,
             facet_id_string_docids,
             facet_id_normalized_string_strings,
+            facet_id_string_representatives,
             facet_id_string_fst,
             facet_id_exists_docids,
             facet_id_is_null_docids,
             facet_id_is_empty_docids,
+            facet_id_is_date_docids,
+            facet_id_is_true_docids,
+            facet_id_is_false_docids,
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
             vector_arroy,
             embedder_category_id,
             documents,
+            version_docids,
         };
         if this.get_version(&wtxn)?.is_none() && creation {
             this.put_version(
@@ -470,6 +538,68 @@ impl Index {
             .unwrap_or_default())
     }
 
+    /* documents version */
+
+    /// Returns the version of the documents, increased every time documents are added or updated.
+    pub fn documents_version(&self, rtxn: &RoTxn<'_>) -> heed::Result<u64> {
+        Ok(self
+            .main
+            .remap_types::<Str, BEU64>()
+            .get(rtxn, main_key::DOCUMENTS_VERSION)?
+            .unwrap_or_default())
+    }
+
+    /// Records a new version of the documents for the given changed documents.
+    ///
+    /// The deleted documents are not recorded and the version is left
+    /// as is when none of the changed documents remain.
+    ///
+    /// The previous versions are compacted: a document is only kept in the last version it
+    /// changed in and the deleted documents are removed, the emptied versions being deleted.
+    /// There are therefore never more versions stored than documents in the index.
+    pub(crate) fn put_documents_version(
+        &self,
+        wtxn: &mut RwTxn<'_>,
+        changed_docids: &RoaringBitmap,
+    ) -> heed::Result<()> {
+        let documents_ids = self.documents_ids(wtxn)?;
+        let changed_docids = changed_docids & &documents_ids;
+
+        let mut iter = self.version_docids.iter_mut(wtxn)?;
+        while let Some((version, docids)) = iter.next().transpose()? {
+            let remaining = (&docids & &documents_ids) - &changed_docids;
+            if remaining.is_empty() {
+                unsafe { iter.del_current()? };
+            } else if remaining.len() != docids.len() {
+                unsafe { iter.put_current(&version, &remaining)? };
+            }
+        }
+        drop(iter);
+
+        if changed_docids.is_empty() {
+            return Ok(());
+        }
+
+        let version = self.documents_version(wtxn)? + 1;
+        self.version_docids.put(wtxn, &version, &changed_docids)?;
+        self.main.remap_types::<Str, BEU64>().put(wtxn, main_key::DOCUMENTS_VERSION, &version)
+    }
+
+    /// Returns the ids of the documents added or updated after the given version
+    /// that are still in the index.
+    pub fn documents_changed_since(
+        &self,
+        rtxn: &RoTxn<'_>,
+        version: u64,
+    ) -> heed::Result<RoaringBitmap> {
+        let mut docids = RoaringBitmap::new();
+        for result in self.version_docids.range(rtxn, &(version.saturating_add(1)..))? {
+            let (_, version_docids) = result?;
+            docids |= version_docids;
+        }
+        Ok(docids & self.documents_ids(rtxn)?)
+    }
+
     /// Returns the number of documents indexed in the database.
     pub fn number_of_documents(&self, rtxn: &RoTxn<'_>) -> Result<u64> {
         let count = self
@@ -667,6 +797,124 @@ impl Index {
         }
     }
 
+    /// Returns the `k` documents nearest to the `[latitude, longitude]` point with their
+    /// distance to it in meters, from the nearest to the furthest.
+    ///
+    /// The documents further than `max_distance` meters, when specified, are ignored.
+    pub fn geo_nearest(
+        &self,
+        rtxn: &RoTxn<'_>,
+        point: [f64; 2],
+        k: usize,
+        max_distance: Option<f64>,
+    ) -> Result<Vec<(DocumentId, f64)>> {
+        let [lat, lng] = point;
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(UserError::InvalidGeoPoint(BadGeoError::Lat(lat)))?;
+        }
+        if !(-180.0..=180.0).contains(&lng) {
+            return Err(UserError::InvalidGeoPoint(BadGeoError::Lng(lng)))?;
+        }
+
+        let Some(rtree) = self.geo_rtree(rtxn)? else {
+            return Ok(Vec::new());
+        };
+
+        Ok(rtree
+            .nearest_neighbor_iter(&lat_lng_to_xyz(&point))
+            .map(|geo_point| {
+                let (docid, coords) = geo_point.data;
+                (docid, distance_between_two_points(&point, &coords))
+            })
+            .take_while(|(_, distance)| max_distance.is_none_or(|max| *distance <= max))
+            .take(k)
+            .collect())
+    }
+
+    /// Returns the number of points in the `rtree`, one per document with a geo point.
+    pub fn geo_point_count(&self, rtxn: &RoTxn<'_>) -> Result<usize> {
+        Ok(self.geo_rtree(rtxn)?.map_or(0, |rtree| rtree.size()))
+    }
+
+    /// Returns the documents whose geo point is in the box between the `top_right` and the
+    /// `bottom_left` `[latitude, longitude]` corners, included.
+    ///
+    /// Unlike the `_geoBoundingBox` filter, that goes through the facet databases, the points
+    /// are only looked up in the `rtree`. The box wraps around the earth when its right
+    /// longitude is below its left longitude.
+    pub fn geo_bounding_box(
+        &self,
+        rtxn: &RoTxn<'_>,
+        top_right: [f64; 2],
+        bottom_left: [f64; 2],
+    ) -> Result<RoaringBitmap> {
+        let [top, right] = top_right;
+        let [bottom, left] = bottom_left;
+        for lat in [top, bottom] {
+            if !(-90.0..=90.0).contains(&lat) {
+                return Err(UserError::InvalidGeoPoint(BadGeoError::Lat(lat)))?;
+            }
+        }
+        for lng in [right, left] {
+            if !(-180.0..=180.0).contains(&lng) {
+                return Err(UserError::InvalidGeoPoint(BadGeoError::Lng(lng)))?;
+            }
+        }
+        if top < bottom {
+            return Err(UserError::InvalidGeoPoint(BadGeoError::BoundingBoxTopIsBelowBottom(
+                top, bottom,
+            )))?;
+        }
+
+        let Some(rtree) = self.geo_rtree(rtxn)? else {
+            return Ok(RoaringBitmap::new());
+        };
+
+        let lng_ranges =
+            if right < left { vec![[left, 180.0], [-180.0, right]] } else { vec![[left, right]] };
+
+        let mut docids = RoaringBitmap::new();
+        for [left, right] in lng_ranges {
+            let envelope = geo_box_envelope([bottom, top], [left, right]);
+            for point in rtree.locate_in_envelope_intersecting(&envelope) {
+                let (docid, [lat, lng]) = point.data;
+                if (bottom..=top).contains(&lat) && (left..=right).contains(&lng) {
+                    docids.insert(docid);
+                }
+            }
+        }
+        Ok(docids)
+    }
+
+    /// Returns the documents whose geo point is inside of the polygon of `[latitude, longitude]`
+    /// vertices, the polygon is closed by joining its last vertex to its first one.
+    ///
+    /// The points are first looked up in the `rtree` with the bounding box of the polygon and
+    /// then checked one by one by casting a ray from them. The edges of the polygon are straight
+    /// lines in the latitude and longitude plane and must not cross the antimeridian.
+    pub fn geo_in_polygon(&self, rtxn: &RoTxn<'_>, polygon: &[[f64; 2]]) -> Result<RoaringBitmap> {
+        let Some(rtree) = self.geo_rtree(rtxn)? else {
+            return Ok(RoaringBitmap::new());
+        };
+        if polygon.is_empty() {
+            return Ok(RoaringBitmap::new());
+        }
+
+        let [bottom, top, left, right] = polygon.iter().fold(
+            [f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY],
+            |[bottom, top, left, right], &[lat, lng]| {
+                [bottom.min(lat), top.max(lat), left.min(lng), right.max(lng)]
+            },
+        );
+        let envelope = geo_box_envelope([bottom, top], [left, right]);
+
+        Ok(rtree
+            .locate_in_envelope_intersecting(&envelope)
+            .filter(|point| is_point_in_polygon(point.data.1, polygon))
+            .map(|point| point.data.0)
+            .collect())
+    }
+
     /* geo faceted */
 
     /// Writes the documents ids that are faceted with a _geo field.
@@ -1054,6 +1302,32 @@ impl Index {
         }
     }
 
+    /// Retrieve all the documents which contain this field id set as a valid date
+    pub fn date_faceted_documents_ids(
+        &self,
+        rtxn: &RoTxn<'_>,
+        field_id: FieldId,
+    ) -> heed::Result<RoaringBitmap> {
+        match self.facet_id_is_date_docids.get(rtxn, &field_id)? {
+            Some(docids) => Ok(docids),
+            None => Ok(RoaringBitmap::new()),
+        }
+    }
+
+    /// Retrieve all the documents which contain this field id set as the boolean `value`
+    pub fn boolean_faceted_documents_ids(
+        &self,
+        rtxn: &RoTxn<'_>,
+        field_id: FieldId,
+        value: bool,
+    ) -> heed::Result<RoaringBitmap> {
+        let db = if value { self.facet_id_is_true_docids } else { self.facet_id_is_false_docids };
+        match db.get(rtxn, &field_id)? {
+            Some(docids) => Ok(docids),
+            None => Ok(RoaringBitmap::new()),
+        }
+    }
+
     /// Retrieve all the documents which contain this field id
     pub fn exists_faceted_documents_ids(
         &self,
@@ -1066,6 +1340,229 @@ impl Index {
         }
     }
 
+    /// Retrieve all the documents which do not contain this field id, not even as null or empty
+    pub fn missing_faceted_documents_ids(
+        &self,
+        rtxn: &RoTxn<'_>,
+        field_id: FieldId,
+    ) -> heed::Result<RoaringBitmap> {
+        Ok(self.documents_ids(rtxn)? - self.exists_faceted_documents_ids(rtxn, field_id)?)
+    }
+
+    /// Retrieve the documents containing this field id, as null or as empty, and the documents
+    /// missing it, reading every set once.
+    pub fn field_presence(
+        &self,
+        rtxn: &RoTxn<'_>,
+        field_id: FieldId,
+    ) -> heed::Result<FieldPresence> {
+        let exists = self.exists_faceted_documents_ids(rtxn, field_id)?;
+        let missing = self.documents_ids(rtxn)? - &exists;
+        Ok(FieldPresence {
+            null: self.null_faceted_documents_ids(rtxn, field_id)?,
+            empty: self.empty_faceted_documents_ids(rtxn, field_id)?,
+            exists,
+            missing,
+        })
+    }
+
+    /* facet values */
+
+    /// Returns at most `limit` normalized string values of the field with their number of
+    /// documents, in lexicographic order, starting after the `after` value.
+    ///
+    /// The last value returned can be used as the `after` value of the next page.
+    pub fn facet_values(
+        &self,
+        rtxn: &RoTxn<'_>,
+        field: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<(String, u64)>> {
+        let Some(field_id) = self.fields_ids_map(rtxn)?.id(field) else {
+            return Ok(Vec::new());
+        };
+
+        let start = match after {
+            Some(left_bound) => Bound::Excluded(FacetGroupKey { field_id, level: 0, left_bound }),
+            None => Bound::Included(FacetGroupKey { field_id, level: 0, left_bound: "" }),
+        };
+
+        let mut values = Vec::new();
+        for result in self.facet_id_string_docids.range(rtxn, &(start, Bound::Unbounded))? {
+            if values.len() >= limit {
+                break;
+            }
+            let (key, value) = result?;
+            if key.field_id != field_id || key.level != 0 {
+                break;
+            }
+            values.push((key.left_bound.to_string(), value.bitmap.len()));
+        }
+
+        Ok(values)
+    }
+
+    /// Returns at most `limit` number values of the field with their number of documents,
+    /// in ascending order, starting after the `after` value.
+    ///
+    /// The last value returned can be used as the `after` value of the next page.
+    pub fn facet_number_values(
+        &self,
+        rtxn: &RoTxn<'_>,
+        field: &str,
+        after: Option<f64>,
+        limit: usize,
+    ) -> Result<Vec<(f64, u64)>> {
+        let Some(field_id) = self.fields_ids_map(rtxn)?.id(field) else {
+            return Ok(Vec::new());
+        };
+
+        let start = match after {
+            Some(left_bound) => Bound::Excluded(FacetGroupKey { field_id, level: 0, left_bound }),
+            None => Bound::Included(FacetGroupKey { field_id, level: 0, left_bound: f64::MIN }),
+        };
+
+        let mut values = Vec::new();
+        for result in self.facet_id_f64_docids.range(rtxn, &(start, Bound::Unbounded))? {
+            if values.len() >= limit {
+                break;
+            }
+            let (key, value) = result?;
+            if key.field_id != field_id || key.level != 0 {
+                break;
+            }
+            values.push((key.left_bound, value.bitmap.len()));
+        }
+
+        Ok(values)
+    }
+
+    /// Streams the normalized string values of the field found in the documents of the
+    /// `universe`, in lexicographic order, with the documents of the universe having them.
+    ///
+    /// The values are read one by one while iterating, the values of the field that no
+    /// document of the universe has are skipped.
+    pub fn facet_values_in_universe<'t>(
+        &self,
+        rtxn: &'t RoTxn<'_>,
+        field: &str,
+        universe: &'t RoaringBitmap,
+    ) -> Result<impl Iterator<Item = Result<(String, RoaringBitmap)>> + 't> {
+        let iter = match self.fields_ids_map(rtxn)?.id(field) {
+            Some(field_id) => {
+                let mut level_0_prefix = field_id.to_be_bytes().to_vec();
+                level_0_prefix.push(0);
+                let iter = self
+                    .facet_id_string_docids
+                    .remap_key_type::<Bytes>()
+                    .prefix_iter(rtxn, &level_0_prefix)?
+                    .remap_key_type::<FacetGroupKeyCodec<StrRefCodec>>();
+                Some(iter)
+            }
+            None => None,
+        };
+
+        Ok(iter.into_iter().flatten().filter_map(move |result| match result {
+            Ok((key, value)) => {
+                let docids = value.bitmap & universe;
+                (!docids.is_empty()).then(|| Ok((key.left_bound.to_string(), docids)))
+            }
+            Err(error) => Some(Err(error.into())),
+        }))
+    }
+
+    /// Returns the number value of the field under which lie the given `percentile`,
+    /// between `0` and `100`, of the documents of the `universe` having a number for it.
+    ///
+    /// The documents are ranked by their lowest value of the field, the threshold is the
+    /// value of the document at the nearest rank. `None` is returned when no document
+    /// of the universe has a number value for the field.
+    pub fn facet_percentile_threshold(
+        &self,
+        rtxn: &RoTxn<'_>,
+        field: &str,
+        percentile: f64,
+        universe: &RoaringBitmap,
+    ) -> Result<Option<f64>> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(UserError::InvalidPercentile(percentile).into());
+        }
+        let Some(field_id) = self.fields_ids_map(rtxn)?.id(field) else {
+            return Ok(None);
+        };
+
+        // the groups of the highest level cover all the values of the field
+        let db = self.facet_id_f64_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>();
+        let highest_level = get_highest_level(rtxn, db, field_id)?;
+        let mut highest_level_prefix = field_id.to_be_bytes().to_vec();
+        highest_level_prefix.push(highest_level);
+        let mut candidates = RoaringBitmap::new();
+        for result in db.remap_key_type::<Bytes>().prefix_iter(rtxn, &highest_level_prefix)? {
+            let (_, value) = result?;
+            candidates |= value.bitmap & universe;
+        }
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        let rank = (percentile / 100.0 * candidates.len() as f64).ceil().max(1.0) as u64;
+        let mut ranked = 0;
+        for result in ascending_facet_sort(rtxn, db, field_id, candidates)? {
+            let (docids, value) = result?;
+            ranked += docids.len();
+            if ranked >= rank {
+                let value = OrderedF64Codec::bytes_decode(value).map_err(heed::Error::Decoding)?;
+                return Ok(Some(value));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the number of distinct string and number values of every faceted field.
+    ///
+    /// Every facet value of the index is read, it is meant for diagnostics.
+    pub fn facet_cardinalities(&self, rtxn: &RoTxn<'_>) -> Result<BTreeMap<FieldId, u64>> {
+        let mut cardinalities = BTreeMap::new();
+        for db in [
+            self.facet_id_string_docids.remap_types::<Bytes, DecodeIgnore>(),
+            self.facet_id_f64_docids.remap_types::<Bytes, DecodeIgnore>(),
+        ] {
+            for result in db.iter(rtxn)? {
+                let (key, ()) = result?;
+                // the keys start with the field id and the level of the facet value
+                if let &[high, low, 0, ..] = key {
+                    *cardinalities.entry(FieldId::from_be_bytes([high, low])).or_default() += 1;
+                }
+            }
+        }
+        Ok(cardinalities)
+    }
+
+    /// Returns the faceted fields having more distinct values than the
+    /// [facet cardinality budget](Index::facet_cardinality_budget), none without a budget.
+    pub fn facet_cardinality_warnings(
+        &self,
+        rtxn: &RoTxn<'_>,
+    ) -> Result<Vec<FacetCardinalityWarning>> {
+        let Some(budget) = self.facet_cardinality_budget(rtxn)? else {
+            return Ok(Vec::new());
+        };
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        Ok(self
+            .facet_cardinalities(rtxn)?
+            .into_iter()
+            .filter(|&(_, cardinality)| cardinality > budget)
+            .map(|(field_id, cardinality)| FacetCardinalityWarning {
+                field_id,
+                field: fields_ids_map.name(field_id).unwrap_or_default().to_string(),
+                cardinality,
+                budget,
+            })
+            .collect())
+    }
+
     /* distinct field */
 
     pub(crate) fn put_distinct_field(
@@ -1615,6 +2112,28 @@ impl Index {
         self.main.remap_key_type::<Str>().delete(txn, main_key::SORT_FACET_VALUES_BY)
     }
 
+    /// Returns the polygons of `[latitude, longitude]` vertices of the named geo regions.
+    pub fn geo_regions(&self, txn: &RoTxn<'_>) -> heed::Result<BTreeMap<String, Vec<[f64; 2]>>> {
+        let regions = self
+            .main
+            .remap_types::<Str, SerdeJson<BTreeMap<String, Vec<[f64; 2]>>>>()
+            .get(txn, main_key::GEO_REGIONS)?
+            .unwrap_or_default();
+        Ok(regions)
+    }
+
+    pub(crate) fn put_geo_regions(
+        &self,
+        txn: &mut RwTxn<'_>,
+        val: &BTreeMap<String, Vec<[f64; 2]>>,
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, SerdeJson<_>>().put(txn, main_key::GEO_REGIONS, &val)
+    }
+
+    pub(crate) fn delete_geo_regions(&self, txn: &mut RwTxn<'_>) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(txn, main_key::GEO_REGIONS)
+    }
+
     pub fn pagination_max_total_hits(&self, txn: &RoTxn<'_>) -> heed::Result<Option<u64>> {
         self.main.remap_types::<Str, BEU64>().get(txn, main_key::PAGINATION_MAX_TOTAL_HITS)
     }
@@ -1691,6 +2210,133 @@ impl Index {
         self.main.remap_key_type::<Str>().delete(txn, main_key::FACET_SEARCH)
     }
 
+    pub fn record_word_triples(&self, txn: &RoTxn<'_>) -> heed::Result<bool> {
+        self.main
+            .remap_types::<Str, SerdeBincode<bool>>()
+            .get(txn, main_key::RECORD_WORD_TRIPLES)
+            .map(|v| v.unwrap_or(false))
+    }
+
+    pub(crate) fn put_record_word_triples(
+        &self,
+        txn: &mut RwTxn<'_>,
+        val: bool,
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, SerdeBincode<bool>>().put(
+            txn,
+            main_key::RECORD_WORD_TRIPLES,
+            &val,
+        )
+    }
+
+    pub(crate) fn delete_record_word_triples(&self, txn: &mut RwTxn<'_>) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(txn, main_key::RECORD_WORD_TRIPLES)
+    }
+
+    pub fn record_word_pair_frequencies(&self, txn: &RoTxn<'_>) -> heed::Result<bool> {
+        self.main
+            .remap_types::<Str, SerdeBincode<bool>>()
+            .get(txn, main_key::RECORD_WORD_PAIR_FREQUENCIES)
+            .map(|v| v.unwrap_or(false))
+    }
+
+    pub(crate) fn put_record_word_pair_frequencies(
+        &self,
+        txn: &mut RwTxn<'_>,
+        val: bool,
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, SerdeBincode<bool>>().put(
+            txn,
+            main_key::RECORD_WORD_PAIR_FREQUENCIES,
+            &val,
+        )
+    }
+
+    pub(crate) fn delete_record_word_pair_frequencies(
+        &self,
+        txn: &mut RwTxn<'_>,
+    ) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(txn, main_key::RECORD_WORD_PAIR_FREQUENCIES)
+    }
+
+    /// The fields whose words are not paired in the word pair proximity database,
+    /// for the long fields with little ranking value.
+    pub fn proximity_excluded_fields(&self, txn: &RoTxn<'_>) -> heed::Result<BTreeSet<FieldId>> {
+        self.main
+            .remap_types::<Str, SerdeBincode<BTreeSet<FieldId>>>()
+            .get(txn, main_key::PROXIMITY_EXCLUDED_FIELDS)
+            .map(Option::unwrap_or_default)
+    }
+
+    pub(crate) fn put_proximity_excluded_fields(
+        &self,
+        txn: &mut RwTxn<'_>,
+        fields: &BTreeSet<FieldId>,
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, SerdeBincode<_>>().put(
+            txn,
+            main_key::PROXIMITY_EXCLUDED_FIELDS,
+            fields,
+        )
+    }
+
+    pub(crate) fn delete_proximity_excluded_fields(
+        &self,
+        txn: &mut RwTxn<'_>,
+    ) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(txn, main_key::PROXIMITY_EXCLUDED_FIELDS)
+    }
+
+    /// The locale whose casing rules are followed when normalizing the string facet values.
+    pub fn facet_normalization_locale(&self, rtxn: &RoTxn<'_>) -> heed::Result<Option<Language>> {
+        self.main
+            .remap_types::<Str, SerdeJson<Language>>()
+            .get(rtxn, main_key::FACET_NORMALIZATION_LOCALE)
+    }
+
+    pub(crate) fn put_facet_normalization_locale(
+        &self,
+        txn: &mut RwTxn<'_>,
+        val: Language,
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, SerdeJson<Language>>().put(
+            txn,
+            main_key::FACET_NORMALIZATION_LOCALE,
+            &val,
+        )
+    }
+
+    pub(crate) fn delete_facet_normalization_locale(
+        &self,
+        txn: &mut RwTxn<'_>,
+    ) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(txn, main_key::FACET_NORMALIZATION_LOCALE)
+    }
+
+    /// Which original value represents the string facet values shared by distinct originals.
+    pub fn facet_representative(&self, rtxn: &RoTxn<'_>) -> heed::Result<FacetRepresentative> {
+        self.main
+            .remap_types::<Str, SerdeBincode<FacetRepresentative>>()
+            .get(rtxn, main_key::FACET_REPRESENTATIVE)
+            .map(Option::unwrap_or_default)
+    }
+
+    pub(crate) fn put_facet_representative(
+        &self,
+        txn: &mut RwTxn<'_>,
+        val: FacetRepresentative,
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, SerdeBincode<FacetRepresentative>>().put(
+            txn,
+            main_key::FACET_REPRESENTATIVE,
+            &val,
+        )
+    }
+
+    pub(crate) fn delete_facet_representative(&self, txn: &mut RwTxn<'_>) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(txn, main_key::FACET_REPRESENTATIVE)
+    }
+
     pub fn localized_attributes_rules(
         &self,
         rtxn: &RoTxn<'_>,
@@ -1759,6 +2405,45 @@ impl Index {
         self.main.remap_key_type::<Str>().delete(wtxn, main_key::SEARCH_CUTOFF)
     }
 
+    pub(crate) fn put_filter_scan_limit(
+        &self,
+        wtxn: &mut RwTxn<'_>,
+        limit: u64,
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, BEU64>().put(wtxn, main_key::FILTER_SCAN_LIMIT, &limit)
+    }
+
+    /// The maximum number of facet values a single `CONTAINS`, `STARTS WITH`, `~=`, `MATCHES` or
+    /// `MOD` filter can scan, unlimited when `None`.
+    pub fn filter_scan_limit(&self, rtxn: &RoTxn<'_>) -> Result<Option<u64>> {
+        Ok(self.main.remap_types::<Str, BEU64>().get(rtxn, main_key::FILTER_SCAN_LIMIT)?)
+    }
+
+    pub(crate) fn delete_filter_scan_limit(&self, wtxn: &mut RwTxn<'_>) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(wtxn, main_key::FILTER_SCAN_LIMIT)
+    }
+
+    pub(crate) fn put_facet_cardinality_budget(
+        &self,
+        wtxn: &mut RwTxn<'_>,
+        budget: u64,
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, BEU64>().put(wtxn, main_key::FACET_CARDINALITY_BUDGET, &budget)
+    }
+
+    /// The number of distinct values above which the indexing warns about a facet field,
+    /// see [`Index::facet_cardinality_warnings`].
+    pub fn facet_cardinality_budget(&self, rtxn: &RoTxn<'_>) -> heed::Result<Option<u64>> {
+        self.main.remap_types::<Str, BEU64>().get(rtxn, main_key::FACET_CARDINALITY_BUDGET)
+    }
+
+    pub(crate) fn delete_facet_cardinality_budget(
+        &self,
+        wtxn: &mut RwTxn<'_>,
+    ) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(wtxn, main_key::FACET_CARDINALITY_BUDGET)
+    }
+
     pub fn embeddings(
         &self,
         rtxn: &RoTxn<'_>,
@@ -1776,6 +2461,27 @@ impl Index {
         Ok(res)
     }
 
+    /// Returns the documents having at least one embedding for the `embedder_name` embedder,
+    /// `None` if there is no such embedder.
+    pub fn embedded_documents_ids(
+        &self,
+        rtxn: &RoTxn<'_>,
+        embedder_name: &str,
+    ) -> Result<Option<RoaringBitmap>> {
+        let embedding_configs = self.embedding_configs(rtxn)?;
+        let Some(config) = embedding_configs.iter().find(|config| config.name == embedder_name)
+        else {
+            return Ok(None);
+        };
+        let Some(embedder_id) = self.embedder_category_id.get(rtxn, embedder_name)? else {
+            return Ok(None);
+        };
+        let reader = ArroyWrapper::new(self.vector_arroy, embedder_id, config.config.quantized());
+        let mut stats = ArroyStats::default();
+        reader.aggregate_stats(rtxn, &mut stats)?;
+        Ok(Some(stats.documents))
+    }
+
     pub fn prefix_settings(&self, rtxn: &RoTxn<'_>) -> Result<PrefixSettings> {
         let compute_prefixes = self.prefix_search(rtxn)?.unwrap_or_default();
         Ok(PrefixSettings { compute_prefixes, max_prefix_length: 4, prefix_count_threshold: 100 })
@@ -1817,23 +2523,31 @@ impl Index {
             word_prefix_docids,
             exact_word_prefix_docids,
             word_pair_proximity_docids,
+            word_triple_docids,
+            word_pair_frequency_docids,
             word_position_docids,
             word_fid_docids,
             word_prefix_position_docids,
             word_prefix_fid_docids,
             field_id_word_count_docids,
             facet_id_f64_docids,
+            facet_id_i64_docids,
             facet_id_string_docids,
             facet_id_normalized_string_strings,
+            facet_id_string_representatives,
             facet_id_string_fst,
             facet_id_exists_docids,
             facet_id_is_null_docids,
             facet_id_is_empty_docids,
+            facet_id_is_date_docids,
+            facet_id_is_true_docids,
+            facet_id_is_false_docids,
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
             vector_arroy,
             embedder_category_id,
             documents,
+            version_docids,
         } = self;
 
         fn compute_size(stats: DatabaseStat) -> usize {
@@ -1864,6 +2578,11 @@ impl Index {
             "word_pair_proximity_docids",
             word_pair_proximity_docids.stat(rtxn).map(compute_size)?,
         );
+        sizes.insert("word_triple_docids", word_triple_docids.stat(rtxn).map(compute_size)?);
+        sizes.insert(
+            "word_pair_frequency_docids",
+            word_pair_frequency_docids.stat(rtxn).map(compute_size)?,
+        );
         sizes.insert("word_position_docids", word_position_docids.stat(rtxn).map(compute_size)?);
         sizes.insert("word_fid_docids", word_fid_docids.stat(rtxn).map(compute_size)?);
         sizes.insert(
@@ -1877,12 +2596,17 @@ impl Index {
             field_id_word_count_docids.stat(rtxn).map(compute_size)?,
         );
         sizes.insert("facet_id_f64_docids", facet_id_f64_docids.stat(rtxn).map(compute_size)?);
+        sizes.insert("facet_id_i64_docids", facet_id_i64_docids.stat(rtxn).map(compute_size)?);
         sizes
             .insert("facet_id_string_docids", facet_id_string_docids.stat(rtxn).map(compute_size)?);
         sizes.insert(
             "facet_id_normalized_string_strings",
             facet_id_normalized_string_strings.stat(rtxn).map(compute_size)?,
         );
+        sizes.insert(
+            "facet_id_string_representatives",
+            facet_id_string_representatives.stat(rtxn).map(compute_size)?,
+        );
         sizes.insert("facet_id_string_fst", facet_id_string_fst.stat(rtxn).map(compute_size)?);
         sizes
             .insert("facet_id_exists_docids", facet_id_exists_docids.stat(rtxn).map(compute_size)?);
@@ -1894,6 +2618,18 @@ impl Index {
             "facet_id_is_empty_docids",
             facet_id_is_empty_docids.stat(rtxn).map(compute_size)?,
         );
+        sizes.insert(
+            "facet_id_is_date_docids",
+            facet_id_is_date_docids.stat(rtxn).map(compute_size)?,
+        );
+        sizes.insert(
+            "facet_id_is_true_docids",
+            facet_id_is_true_docids.stat(rtxn).map(compute_size)?,
+        );
+        sizes.insert(
+            "facet_id_is_false_docids",
+            facet_id_is_false_docids.stat(rtxn).map(compute_size)?,
+        );
         sizes.insert(
             "field_id_docid_facet_f64s",
             field_id_docid_facet_f64s.stat(rtxn).map(compute_size)?,
@@ -1905,6 +2641,7 @@ impl Index {
         sizes.insert("vector_arroy", vector_arroy.stat(rtxn).map(compute_size)?);
         sizes.insert("embedder_category_id", embedder_category_id.stat(rtxn).map(compute_size)?);
         sizes.insert("documents", documents.stat(rtxn).map(compute_size)?);
+        sizes.insert("version_docids", version_docids.stat(rtxn).map(compute_size)?);
 
         Ok(sizes)
     }
@@ -1917,6 +2654,41 @@ pub struct IndexEmbeddingConfig {
     pub user_provided: RoaringBitmap,
 }
 
+/// The documents of an index by presence of a field, see [`Index::field_presence`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldPresence {
+    /// The documents containing the field, including the null and empty ones.
+    pub exists: RoaringBitmap,
+    /// The documents where the field is null.
+    pub null: RoaringBitmap,
+    /// The documents where the field is an empty string, array or object.
+    pub empty: RoaringBitmap,
+    /// The documents not containing the field at all.
+    pub missing: RoaringBitmap,
+}
+
+/// A faceted field with more distinct values than the facet cardinality budget of the index,
+/// see [`Index::facet_cardinality_warnings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FacetCardinalityWarning {
+    pub field_id: FieldId,
+    pub field: String,
+    /// The number of distinct string and number values of the field.
+    pub cardinality: u64,
+    pub budget: u64,
+}
+
+impl fmt::Display for FacetCardinalityWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { field, cardinality, budget, .. } = self;
+        write!(
+            f,
+            "The facet field `{field}` has {cardinality} distinct values, more than the budget of {budget}. \
+            Check that the field is meant to be filterable."
+        )
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PrefixSettings {
     pub prefix_count_threshold: usize,
@@ -1961,6 +2733,66 @@ impl std::fmt::Display for RollbackOutcome {
     }
 }
 
+/// Returns the smallest box of the `rtree` space containing the points of the sphere
+/// between the `[bottom, top]` latitudes and the `[left, right]` longitudes.
+fn geo_box_envelope([bottom, top]: [f64; 2], [left, right]: [f64; 2]) -> AABB<[f64; 3]> {
+    // the rounding errors must not exclude the points on the edges of the box
+    const MARGIN: f64 = 1e-9;
+
+    // over a range of degrees, the extremes of a cosine or sine are at the
+    // ends of the range or at the multiples of 90 degrees inside of it
+    let extremes = |from: f64, to: f64, f: fn(f64) -> f64| {
+        let mut angle = (from / 90.0).ceil() * 90.0;
+        let mut values = vec![f(from.to_radians()), f(to.to_radians())];
+        while angle <= to {
+            values.push(f(angle.to_radians()));
+            angle += 90.0;
+        }
+        values.into_iter().fold([f64::INFINITY, f64::NEG_INFINITY], |[min, max], value| {
+            [min.min(value), max.max(value)]
+        })
+    };
+    let product = |[a, b]: [f64; 2], [c, d]: [f64; 2]| {
+        [a * c, a * d, b * c, b * d]
+            .into_iter()
+            .fold([f64::INFINITY, f64::NEG_INFINITY], |[min, max], value| {
+                [min.min(value), max.max(value)]
+            })
+    };
+
+    let cos_lat = extremes(bottom, top, f64::cos);
+    let [x_min, x_max] = product(cos_lat, extremes(left, right, f64::cos));
+    let [y_min, y_max] = product(cos_lat, extremes(left, right, f64::sin));
+    let [z_min, z_max] = extremes(bottom, top, f64::sin);
+
+    AABB::from_corners(
+        [x_min - MARGIN, y_min - MARGIN, z_min - MARGIN],
+        [x_max + MARGIN, y_max + MARGIN, z_max + MARGIN],
+    )
+}
+
+/// Casts a ray from the `[latitude, longitude]` point towards the growing longitudes and
+/// counts the edges of the polygon it crosses, the point is inside when this count is odd.
+fn is_point_in_polygon([lat, lng]: [f64; 2], polygon: &[[f64; 2]]) -> bool {
+    let mut inside = false;
+    let mut previous = match polygon.last() {
+        Some(&last) => last,
+        None => return false,
+    };
+    for &current in polygon {
+        let [lat_a, lng_a] = previous;
+        let [lat_b, lng_b] = current;
+        if (lat_a > lat) != (lat_b > lat) {
+            let crossing_lng = lng_a + (lat - lat_a) / (lat_b - lat_a) * (lng_b - lng_a);
+            if lng < crossing_lng {
+                inside = !inside;
+            }
+        }
+        previous = current;
+    }
+    inside
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(transparent)]
 struct OffsetDateTime(#[serde(with = "time::serde::rfc3339")] time::OffsetDateTime);
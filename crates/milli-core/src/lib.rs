@@ -40,13 +40,15 @@ pub mod constants;
 mod fieldids_weights_map;
 pub mod progress;
 
+use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap};
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use std::hash::BuildHasherDefault;
 
 use charabia::normalizer::{CharNormalizer, CompatibilityDecompositionNormalizer};
-pub use filter_parser::{Condition, FilterCondition, Span, Token};
+use charabia::Language;
+pub use filter_parser::{Condition, FilterCondition, GeoRadiusKind, Span, Token};
 use fxhash::{FxHasher32, FxHasher64};
 pub use grenad::CompressionType;
 pub use search::new::{
@@ -82,8 +84,10 @@ pub use self::localized_attributes_rules::LocalizedAttributesRule;
 pub use self::search::facet::{FacetValueHit, SearchForFacetValues};
 pub use self::search::similar::Similar;
 pub use self::search::{
-    FacetDistribution, Filter, FormatOptions, MatchBounds, MatcherBuilder, MatchingWords, OrderBy,
-    Search, SearchResult, SemanticSearch, TermsMatchingStrategy, DEFAULT_VALUES_PER_FACET,
+    BitmapFilter, DocumentMatchExplanation, FacetDistribution, Filter, FilterAnalysis,
+    FilterOperator, FilterWarning, FormatOptions, MatchBounds, MatcherBuilder, MatchingWords,
+    OrderBy, OwnedFilter, Search, SearchResult, SemanticSearch, TermsMatchingStrategy,
+    DEFAULT_BROAD_CONTAINS_THRESHOLD, DEFAULT_VALUES_PER_FACET,
 };
 pub use self::update::ChannelCongestion;
 
@@ -379,8 +383,61 @@ pub fn is_faceted_by(field: &str, facet: &str) -> bool {
     field.starts_with(facet) && field[facet.len()..].chars().next().is_none_or(|c| c == '.')
 }
 
-pub fn normalize_facet(original: &str) -> String {
-    CompatibilityDecompositionNormalizer.normalize_str(original.trim()).to_lowercase()
+/// Normalizes a facet value, borrowing it when it only needs to be trimmed.
+pub fn normalize_facet(original: &str) -> Cow<'_, str> {
+    normalize_facet_with_locale(original, None)
+}
+
+/// Normalizes a facet value following the casing rules of the locale,
+/// without a locale the normalization is the same for all the languages.
+pub fn normalize_facet_with_locale(original: &str, locale: Option<Language>) -> Cow<'_, str> {
+    let trimmed = original.trim();
+    // the compatibility decomposition leaves the ASCII characters untouched
+    if trimmed.bytes().all(|b| b.is_ascii() && !b.is_ascii_uppercase()) {
+        return Cow::Borrowed(trimmed);
+    }
+
+    let normalized = match locale {
+        // the dotted and dotless i are two letters, `İ` and `I` being their capitals
+        Some(Language::Tur | Language::Aze) => {
+            let lowercased: String = trimmed
+                .chars()
+                .map(|c| match c {
+                    'İ' => 'i',
+                    'I' => 'ı',
+                    c => c,
+                })
+                .collect();
+            CompatibilityDecompositionNormalizer.normalize_str(&lowercased).to_lowercase()
+        }
+        // the sharp s is written `SS` in capitals
+        Some(Language::Deu) => CompatibilityDecompositionNormalizer
+            .normalize_str(trimmed)
+            .to_lowercase()
+            .replace('ß', "ss"),
+        _ => CompatibilityDecompositionNormalizer.normalize_str(trimmed).to_lowercase(),
+    };
+    Cow::Owned(normalized)
+}
+
+/// The string facet value a boolean is indexed as.
+pub fn boolean_facet_value(value: bool) -> &'static str {
+    if value {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+/// Returns `true` if the string facet value is a valid date, an RFC 3339 date-time
+/// like `2024-03-17T10:00:00Z` or a full date like `2024-03-17`.
+pub fn is_date_facet_value(value: &str) -> bool {
+    use time::format_description::well_known::Rfc3339;
+    use time::macros::format_description;
+
+    let value = value.trim();
+    time::OffsetDateTime::parse(value, &Rfc3339).is_ok()
+        || time::Date::parse(value, format_description!("[year]-[month]-[day]")).is_ok()
 }
 
 #[cfg(test)]
@@ -460,4 +517,46 @@ mod tests {
 
         assert_eq!(&actual, expected);
     }
+
+    #[test]
+    fn normalize_facet_borrows_normalized_values() {
+        assert!(matches!(normalize_facet("kefir"), Cow::Borrowed("kefir")));
+        assert!(matches!(normalize_facet("  kefir 42 "), Cow::Borrowed("kefir 42")));
+
+        assert!(matches!(normalize_facet("Kefir"), Cow::Owned(s) if s == "kefir"));
+        assert!(matches!(normalize_facet("ｷｪﬁr"), Cow::Owned(s) if s == "キェfir"));
+        assert!(matches!(normalize_facet(" Élan "), Cow::Owned(s) if s == "e\u{301}lan"));
+    }
+
+    #[test]
+    fn normalize_facet_with_locale_casing() {
+        let turkish = Some(Language::Tur);
+        assert_eq!(normalize_facet_with_locale("İSTANBUL", turkish), "istanbul");
+        assert_eq!(normalize_facet_with_locale("ISPARTA", turkish), "ısparta");
+        assert_eq!(normalize_facet_with_locale("istanbul", turkish), "istanbul");
+        assert_eq!(normalize_facet("İSTANBUL"), "i\u{307}stanbul");
+        assert_eq!(normalize_facet("ISPARTA"), "isparta");
+
+        let german = Some(Language::Deu);
+        assert_eq!(normalize_facet_with_locale("Straße", german), "strasse");
+        assert_eq!(normalize_facet_with_locale("STRASSE", german), "strasse");
+        assert_eq!(normalize_facet("Straße"), "straße");
+
+        // the other locales are normalized like without a locale
+        assert_eq!(normalize_facet_with_locale(" Élan ", Some(Language::Fra)), "e\u{301}lan");
+    }
+
+    #[test]
+    fn date_facet_values() {
+        for date in
+            ["2024-03-17", "2024-03-17T10:00:00Z", "2024-03-17T10:00:00.5+02:00", " 2024-02-29 "]
+        {
+            assert!(is_date_facet_value(date), "{date:?} should be a date");
+        }
+        for not_a_date in
+            ["", "2023-02-29", "2024-13-01", "17/03/2024", "2024-03-17T10:00", "tomorrow"]
+        {
+            assert!(!is_date_facet_value(not_a_date), "{not_a_date:?} should not be a date");
+        }
+    }
 }
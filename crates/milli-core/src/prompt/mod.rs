@@ -8,17 +8,22 @@ use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::num::NonZeroUsize;
+use std::sync::RwLock;
 
 use bumpalo::Bump;
+use bumparaw_collections::RawMap;
 use document::ParseableDocument;
 use error::{NewPromptError, RenderPromptError};
 use fields::{BorrowedFields, OwnedFields};
+use rustc_hash::FxBuildHasher;
+use serde_json::value::RawValue;
 
 use self::context::Context;
 use self::document::Document;
-use crate::fields_ids_map::metadata::FieldIdMapWithMetadata;
+use crate::fields_ids_map::metadata::{FieldIdMapWithMetadata, MetadataBuilder};
 use crate::update::del_add::DelAdd;
-use crate::GlobalFieldsIdsMap;
+use crate::update::new::document::{DocumentFromVersions, Versions};
+use crate::{FieldsIdsMap, GlobalFieldsIdsMap, Object};
 
 pub struct Prompt {
     template: liquid::Template,
@@ -157,6 +162,43 @@ impl Prompt {
         }
         Ok(rendered)
     }
+
+    /// Renders the template for a document that is not indexed, truncated to the max bytes.
+    ///
+    /// Also returns whether the rendered text had to be truncated.
+    pub fn render_object(&self, document: &Object) -> Result<(String, bool), RenderPromptError> {
+        let doc_alloc = Bump::new();
+        let document = serde_json::to_string(document).expect("a JSON object is serializable");
+        let document: &RawValue =
+            serde_json::from_str(doc_alloc.alloc_str(&document)).expect("serialized JSON is valid");
+        let document = RawMap::from_raw_value_and_hasher(document, FxBuildHasher, &doc_alloc)
+            .expect("the document is a JSON object");
+        let document = Versions::single(document);
+        let document = ParseableDocument::new(DocumentFromVersions::new(&document), &doc_alloc);
+
+        // the fields of the document are all considered searchable
+        let builder = MetadataBuilder::new(
+            None,
+            Default::default(),
+            Default::default(),
+            None,
+            None,
+            Default::default(),
+        );
+        let fields_ids_map = RwLock::new(FieldIdMapWithMetadata::new(FieldsIdsMap::new(), builder));
+        let field_id_map = RefCell::new(GlobalFieldsIdsMap::new(&fields_ids_map));
+        let fields = BorrowedFields::new(&document, &field_id_map, &doc_alloc);
+        let context = Context::new(&document, &fields);
+
+        let mut rendered =
+            self.template.render(&context).map_err(RenderPromptError::missing_context)?;
+        let rendered_len = rendered.len();
+        if let Some(max_bytes) = self.max_bytes {
+            truncate(&mut rendered, max_bytes.get());
+        }
+        let truncated = rendered.len() < rendered_len;
+        Ok((rendered, truncated))
+    }
 }
 
 fn truncate(s: &mut String, max_bytes: usize) {
@@ -6,6 +6,10 @@ use crate::{relative_from_absolute_position, Position};
 
 pub const MAX_DISTANCE: u32 = 4;
 
+/// The number of times a word directly follows another one in a document
+/// over which the word pair frequencies are no longer distinguished.
+pub const MAX_WORD_PAIR_FREQUENCY: u8 = 15;
+
 pub fn index_proximity(lhs: u32, rhs: u32) -> u32 {
     if lhs <= rhs {
         cmp::min(rhs - lhs, MAX_DISTANCE)
@@ -24,6 +28,42 @@ pub fn positions_proximity(lhs: Position, rhs: Position) -> u32 {
     }
 }
 
+/// Returns the proximity of the word at `pos_b` when it follows the word at `pos_a`, both
+/// being absolute positions made of a field id and of a position in this field.
+///
+/// The proximity is the number of positions to move forward from `pos_a` to reach `pos_b`,
+/// two adjacent words in the order `pos_a` then `pos_b` have a proximity of `1`. When `pos_b`
+/// comes before `pos_a` the words are in the reversed order, which costs one more. The
+/// proximity is capped to [`MAX_DISTANCE`], which is also the proximity of two words in
+/// different fields whatever their positions are.
+///
+/// ```
+/// use milli_core::absolute_from_relative_position as position;
+/// use milli_core::proximity::{distance, MAX_DISTANCE};
+///
+/// // -- the same position
+/// assert_eq!(distance(position(0, 3), position(0, 3)), 0);
+///
+/// // -- the adjacent words
+/// assert_eq!(distance(position(0, 3), position(0, 4)), 1);
+/// assert_eq!(distance(position(0, 3), position(0, 5)), 2);
+///
+/// // -- the reversed order
+/// assert_eq!(distance(position(0, 4), position(0, 3)), 2);
+/// assert_eq!(distance(position(0, 5), position(0, 3)), 3);
+///
+/// // -- beyond the max distance
+/// assert_eq!(distance(position(0, 0), position(0, 100)), MAX_DISTANCE as u8);
+/// assert_eq!(distance(position(0, 100), position(0, 0)), MAX_DISTANCE as u8);
+///
+/// // -- across the field boundaries
+/// assert_eq!(distance(position(0, 3), position(1, 4)), MAX_DISTANCE as u8);
+/// assert_eq!(distance(position(1, 0), position(0, 0)), MAX_DISTANCE as u8);
+/// ```
+pub fn distance(pos_a: Position, pos_b: Position) -> u8 {
+    positions_proximity(pos_a, pos_b) as u8
+}
+
 pub fn path_proximity(path: &[Position]) -> u32 {
     path.windows(2).map(|w| positions_proximity(w[0], w[1])).sum::<u32>()
 }
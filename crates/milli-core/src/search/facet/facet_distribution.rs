@@ -159,9 +159,14 @@ impl<'a> FacetDistribution<'a> {
 
                 let iter = normalized_distribution
                     .into_iter()
-                    .take(self.max_values_per_facet.saturating_sub(distribution.len()))
-                    .map(|(_normalized, (original, count))| (original.to_string(), count));
-                distribution.extend(iter);
+                    .take(self.max_values_per_facet.saturating_sub(distribution.len()));
+                for (normalized, (original, count)) in iter {
+                    let original = match self.representative_facet_string(field_id, normalized)? {
+                        Some(representative) => representative.to_string(),
+                        None => original.to_string(),
+                    };
+                    distribution.insert(original, count);
+                }
             }
         }
 
@@ -210,7 +215,6 @@ impl<'a> FacetDistribution<'a> {
             OrderBy::Lexicographic => lexicographically_iterate_over_facet_distribution,
             OrderBy::Count => count_iterate_over_facet_distribution,
         };
-
         search_function(
             self.rtxn,
             self.index.facet_id_string_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>(),
@@ -219,12 +223,17 @@ impl<'a> FacetDistribution<'a> {
             |facet_key, nbr_docids, any_docid| {
                 let facet_key = StrRefCodec::bytes_decode(facet_key).unwrap();
 
-                let key: (FieldId, _, &str) = (field_id, any_docid, facet_key);
                 let optional_original_string =
-                    self.index.field_id_docid_facet_strings.get(self.rtxn, &key)?;
+                    match self.representative_facet_string(field_id, facet_key)? {
+                        Some(representative) => Some(representative),
+                        None => {
+                            let key: (FieldId, _, &str) = (field_id, any_docid, facet_key);
+                            self.index.field_id_docid_facet_strings.get(self.rtxn, &key)?
+                        }
+                    };
 
                 let original_string = match optional_original_string {
-                    Some(original_string) => original_string.to_owned(),
+                    Some(original_string) => original_string.to_string(),
                     None => {
                         tracing::error!(
                             "Missing original facet string. Using the normalized facet {} instead",
@@ -244,6 +253,16 @@ impl<'a> FacetDistribution<'a> {
         )
     }
 
+    /// Returns the original string stored to represent a normalized facet value
+    /// in place of the first seen one, see [`crate::facet::FacetRepresentative`].
+    fn representative_facet_string(
+        &self,
+        field_id: FieldId,
+        normalized: &str,
+    ) -> heed::Result<Option<&'a str>> {
+        self.index.facet_id_string_representatives.get(self.rtxn, &(field_id, normalized))
+    }
+
     fn facet_values(
         &self,
         field_id: FieldId,
@@ -443,6 +462,7 @@ mod tests {
     use big_s::S;
 
     use crate::documents::mmap_from_objects;
+    use crate::facet::FacetRepresentative;
     use crate::index::tests::TempIndex;
     use crate::{milli_snap, FacetDistribution, FilterableAttributesRule, OrderBy};
 
@@ -886,4 +906,74 @@ mod tests {
 
         milli_snap!(format!("{map:?}"), "candidates_217_777", @r###"{"colour": (218.0, 1776.0)}"###);
     }
+
+    #[test]
+    fn facet_representative() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("colour"))])
+            })
+            .unwrap();
+
+        let documents = documents!([
+            { "id": 0, "colour": "blue" },
+            { "id": 1, "colour": "Blue" },
+            { "id": 2, "colour": "BLUE" },
+            { "id": 3, "colour": "Blue" },
+            { "id": 4, "colour": "RED" }
+        ]);
+
+        index.add_documents(documents).unwrap();
+
+        for (representative, expected) in [
+            (FacetRepresentative::FirstSeen, r#"{"colour": {"blue": 4, "RED": 1}}"#),
+            (FacetRepresentative::Lexicographic, r#"{"colour": {"BLUE": 4, "RED": 1}}"#),
+            (FacetRepresentative::MostFrequent, r#"{"colour": {"Blue": 4, "RED": 1}}"#),
+        ] {
+            index
+                .update_settings(|settings| settings.set_facet_representative(representative))
+                .unwrap();
+
+            let txn = index.read_txn().unwrap();
+            assert_eq!(index.facet_representative(&txn).unwrap(), representative);
+
+            // through the facet levels
+            let map = FacetDistribution::new(&txn, &index)
+                .facets(iter::once(("colour", OrderBy::default())))
+                .execute()
+                .unwrap();
+            assert_eq!(format!("{map:?}"), expected, "{representative:?}");
+
+            // through the documents, the representative doesn't depend on the candidates
+            let map = FacetDistribution::new(&txn, &index)
+                .facets(iter::once(("colour", OrderBy::default())))
+                .candidates([0, 2, 4].into_iter().collect())
+                .execute()
+                .unwrap();
+            let expected = expected.replace(": 4", ": 2");
+            assert_eq!(format!("{map:?}"), expected, "{representative:?}");
+        }
+
+        // the representatives are kept up to date while indexing
+        let documents = documents!([
+            { "id": 5, "colour": "Red" },
+            { "id": 6, "colour": "Red" }
+        ]);
+        index.add_documents(documents).unwrap();
+
+        let txn = index.read_txn().unwrap();
+        let map = FacetDistribution::new(&txn, &index)
+            .facets(iter::once(("colour", OrderBy::default())))
+            .execute()
+            .unwrap();
+        assert_eq!(format!("{map:?}"), r#"{"colour": {"Blue": 4, "Red": 3}}"#);
+        drop(txn);
+
+        index.update_settings(|settings| settings.reset_facet_representative()).unwrap();
+        let txn = index.read_txn().unwrap();
+        assert_eq!(index.facet_representative(&txn).unwrap(), FacetRepresentative::FirstSeen);
+        assert!(index.facet_id_string_representatives.is_empty(&txn).unwrap());
+    }
 }
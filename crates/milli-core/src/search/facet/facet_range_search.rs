@@ -1,6 +1,6 @@
 use std::ops::{Bound, RangeBounds};
 
-use heed::BytesEncode;
+use heed::{BytesDecode, BytesEncode};
 use roaring::RoaringBitmap;
 
 use super::{get_first_facet_value, get_highest_level, get_last_facet_value};
@@ -8,7 +8,7 @@ use crate::heed_codec::facet::{
     FacetGroupKey, FacetGroupKeyCodec, FacetGroupLazyValueCodec, FacetGroupValueCodec,
 };
 use crate::heed_codec::BytesRefCodec;
-use crate::{CboRoaringBitmapCodec, Result};
+use crate::{CboRoaringBitmapCodec, CboRoaringBitmapLenCodec, Result};
 
 /// Find all the document ids for which the given field contains a value contained within
 /// the two bounds.
@@ -21,6 +21,43 @@ pub fn find_docids_of_facet_within_bounds<'t, BoundCodec>(
     universe: Option<&RoaringBitmap>,
     docids: &mut RoaringBitmap,
 ) -> Result<()>
+where
+    BoundCodec: for<'a> BytesEncode<'a>,
+    for<'a> <BoundCodec as BytesEncode<'a>>::EItem: Sized,
+{
+    search_within_bounds(rtxn, db, field_id, left, right, universe, RangeOutput::Docids(docids))
+}
+
+/// Sum the number of documents of the values of the given field contained within the two bounds,
+/// using the largest groups fully contained within the bounds and without decoding any bitmap.
+///
+/// A document with several values within the bounds is counted once per group containing it,
+/// the sum is then an upper bound of the number of documents matching the range.
+pub fn count_docids_of_facet_within_bounds<'t, BoundCodec>(
+    rtxn: &'t heed::RoTxn<'t>,
+    db: heed::Database<FacetGroupKeyCodec<BoundCodec>, FacetGroupValueCodec>,
+    field_id: u16,
+    left: &'t Bound<<BoundCodec as BytesEncode<'t>>::EItem>,
+    right: &'t Bound<<BoundCodec as BytesEncode<'t>>::EItem>,
+) -> Result<u64>
+where
+    BoundCodec: for<'a> BytesEncode<'a>,
+    for<'a> <BoundCodec as BytesEncode<'a>>::EItem: Sized,
+{
+    let mut count = 0;
+    search_within_bounds(rtxn, db, field_id, left, right, None, RangeOutput::Count(&mut count))?;
+    Ok(count)
+}
+
+fn search_within_bounds<'t, BoundCodec>(
+    rtxn: &'t heed::RoTxn<'t>,
+    db: heed::Database<FacetGroupKeyCodec<BoundCodec>, FacetGroupValueCodec>,
+    field_id: u16,
+    left: &'t Bound<<BoundCodec as BytesEncode<'t>>::EItem>,
+    right: &'t Bound<<BoundCodec as BytesEncode<'t>>::EItem>,
+    universe: Option<&RoaringBitmap>,
+    output: RangeOutput<'_>,
+) -> Result<()>
 where
     BoundCodec: for<'a> BytesEncode<'a>,
     for<'a> <BoundCodec as BytesEncode<'a>>::EItem: Sized,
@@ -50,7 +87,7 @@ where
         Bound::Unbounded => Bound::Unbounded,
     };
     let db = db.remap_types::<FacetGroupKeyCodec<BytesRefCodec>, FacetGroupLazyValueCodec>();
-    let mut f = FacetRangeSearch { rtxn, db, field_id, left, right, universe, docids };
+    let mut f = FacetRangeSearch { rtxn, db, field_id, left, right, universe, output };
     let highest_level = get_highest_level(rtxn, db, field_id)?;
 
     if let Some(starting_left_bound) =
@@ -66,6 +103,14 @@ where
     }
 }
 
+/// What is accumulated from the groups contained within the bounds.
+enum RangeOutput<'a> {
+    /// The union of the document ids of the groups.
+    Docids(&'a mut RoaringBitmap),
+    /// The sum of the lengths of the groups.
+    Count(&'a mut u64),
+}
+
 /// Fetch the document ids that have a facet with a value between the two given bounds
 struct FacetRangeSearch<'t, 'b, 'bitmap> {
     rtxn: &'t heed::RoTxn<'t>,
@@ -76,10 +121,28 @@ struct FacetRangeSearch<'t, 'b, 'bitmap> {
     /// The subset of documents ids that are useful for this search.
     /// Great performance optimizations can be achieved by only fetching values matching this subset.
     universe: Option<&'bitmap RoaringBitmap>,
-    docids: &'bitmap mut RoaringBitmap,
+    output: RangeOutput<'bitmap>,
 }
 
 impl<'t> FacetRangeSearch<'t, '_, '_> {
+    fn add_group(&mut self, bitmap_bytes: &[u8]) -> Result<()> {
+        match &mut self.output {
+            RangeOutput::Docids(docids) => {
+                **docids |= match self.universe {
+                    Some(universe) => {
+                        CboRoaringBitmapCodec::intersection_with_serialized(bitmap_bytes, universe)?
+                    }
+                    None => CboRoaringBitmapCodec::deserialize_from(bitmap_bytes)?,
+                };
+            }
+            RangeOutput::Count(count) => {
+                **count += CboRoaringBitmapLenCodec::bytes_decode(bitmap_bytes)
+                    .map_err(heed::Error::Decoding)?;
+            }
+        }
+        Ok(())
+    }
+
     fn run_level_0(&mut self, starting_left_bound: &'t [u8], group_size: usize) -> Result<()> {
         let left_key =
             FacetGroupKey { field_id: self.field_id, level: 0, left_bound: starting_left_bound };
@@ -113,13 +176,7 @@ impl<'t> FacetRangeSearch<'t, '_, '_> {
             }
 
             if RangeBounds::<&[u8]>::contains(&(self.left, self.right), &key.left_bound) {
-                *self.docids |= match self.universe {
-                    Some(universe) => CboRoaringBitmapCodec::intersection_with_serialized(
-                        value.bitmap_bytes,
-                        universe,
-                    )?,
-                    None => CboRoaringBitmapCodec::deserialize_from(value.bitmap_bytes)?,
-                };
+                self.add_group(value.bitmap_bytes)?;
             }
         }
         Ok(())
@@ -210,13 +267,7 @@ impl<'t> FacetRangeSearch<'t, '_, '_> {
                 left_condition && right_condition
             };
             if should_take_whole_group {
-                *self.docids |= match self.universe {
-                    Some(universe) => CboRoaringBitmapCodec::intersection_with_serialized(
-                        previous_value.bitmap_bytes,
-                        universe,
-                    )?,
-                    None => CboRoaringBitmapCodec::deserialize_from(previous_value.bitmap_bytes)?,
-                };
+                self.add_group(previous_value.bitmap_bytes)?;
                 previous_key = next_key;
                 previous_value = next_value;
                 continue;
@@ -312,13 +363,7 @@ impl<'t> FacetRangeSearch<'t, '_, '_> {
             left_condition && right_condition
         };
         if should_take_whole_group {
-            *self.docids |= match self.universe {
-                Some(universe) => CboRoaringBitmapCodec::intersection_with_serialized(
-                    previous_value.bitmap_bytes,
-                    universe,
-                )?,
-                None => CboRoaringBitmapCodec::deserialize_from(previous_value.bitmap_bytes)?,
-            };
+            self.add_group(previous_value.bitmap_bytes)?;
         } else {
             let level = level - 1;
             let starting_left_bound = previous_key.left_bound;
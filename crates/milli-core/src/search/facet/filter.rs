@@ -1,37 +1,206 @@
-use std::collections::BTreeSet;
+use std::cell::{OnceCell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::{Debug, Display};
 use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use std::ops::RangeBounds;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-pub use crate::filter_parser::{Condition, Error as FPError, FilterCondition, Token};
+use crate::filter_parser::{compile_regex, modulo_operands, Span};
+pub use crate::filter_parser::{
+    Condition, Error as FPError, FilterCondition, GeoRadiusKind, Token,
+};
+use charabia::Language;
 use either::Either;
-use heed::types::LazyDecode;
-use heed::BytesEncode;
+use heed::types::{Bytes, DecodeIgnore, LazyDecode};
+use heed::{BytesDecode, BytesEncode};
 use memchr::memmem::Finder;
+use regex::Regex;
 use roaring::{MultiOps, RoaringBitmap};
+use rstar::AABB;
 use serde_json::Value;
+use yoke::{Yoke, Yokeable};
 
 use super::facet_range_search;
-use crate::constants::RESERVED_GEO_FIELD_NAME;
+use crate::constants::{
+    RESERVED_GEO_FIELD_NAME, RESERVED_GEO_LAT_FIELD_NAME, RESERVED_GEO_LNG_FIELD_NAME,
+    RESERVED_VECTORS_FIELD_NAME,
+};
 use crate::error::{Error, UserError};
 use crate::filterable_attributes_rules::{filtered_matching_patterns, matching_features};
 use crate::heed_codec::facet::{
-    FacetGroupKey, FacetGroupKeyCodec, FacetGroupValue, FacetGroupValueCodec,
+    FacetGroupKey, FacetGroupKeyCodec, FacetGroupLazyValue, FacetGroupLazyValueCodec,
+    FacetGroupValue, FacetGroupValueCodec, FieldDocIdFacetF64Codec,
 };
 use crate::index::db_name::FACET_ID_STRING_DOCIDS;
 use crate::{
-    distance_between_two_points, lat_lng_to_xyz, FieldId, FieldsIdsMap,
-    FilterableAttributesFeatures, FilterableAttributesRule, Index, InternalError, Result,
-    SerializationError,
+    distance_between_two_points, lat_lng_to_xyz, CboRoaringBitmapCodec, CboRoaringBitmapLenCodec,
+    DocumentId, FieldId, FieldsIdsMap, FilterableAttributesFeatures, FilterableAttributesRule,
+    Index, InternalError, Result, SerializationError,
 };
 
 /// The maximum number of filters the filter AST can process.
 const MAX_FILTER_DEPTH: usize = 2000;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The shape of the object rules of the array form.
+const OBJECT_RULE: &[&str] = &["{ \"field\": String, \"value\": String | Number | Boolean }"];
+
+/// The earth radius used by the haversine distance of [`distance_between_two_points`].
+const EARTH_RADIUS_METERS: f64 = 6371e3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Yokeable)]
 pub struct Filter<'a> {
     condition: FilterCondition<'a>,
 }
 
+/// A filter restricted to a precomputed bitmap of documents, e.g. computed by an external
+/// access control service, as returned by [`Filter::from_bitmap`].
+///
+/// The bitmap can't be written in a filter expression, it is kept out of the parsed
+/// [`FilterCondition`] and intersected with the documents matching the filter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitmapFilter<'a> {
+    bitmap: RoaringBitmap,
+    filter: Option<Filter<'a>>,
+}
+
+/// A [`Filter`] owning the expression it was parsed from, as returned by
+/// [`Filter::from_str_borrowed_owned`].
+///
+/// It can be kept around, in a cache of the parsed filters for example,
+/// without keeping the source of the expression alive.
+#[derive(Debug)]
+pub struct OwnedFilter {
+    filter: Yoke<Filter<'static>, String>,
+}
+
+impl OwnedFilter {
+    /// The filter, borrowing the owned expression.
+    pub fn filter(&self) -> &Filter<'_> {
+        self.filter.get()
+    }
+
+    /// The expression the filter was parsed from.
+    pub fn expression(&self) -> &str {
+        self.filter.backing_cart()
+    }
+}
+
+/// The operators a filter can apply to a field, as reported by [`Filter::field_usage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FilterOperator {
+    Equal,
+    NotEqual,
+    In,
+    ContainsAll,
+    /// Any comparison on the number of values of the field, as in `_length(tags) > 3`.
+    Length,
+    /// Any comparison on the number of characters of the string values of the field,
+    /// as in `_strlen(sku) = 8`.
+    StringLength,
+    /// Any comparison between two fields of a document, as in `sale_price < _field(list_price)`.
+    CompareFields,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LowerThan,
+    LowerThanOrEqual,
+    Between,
+    Null,
+    Empty,
+    Exists,
+    Date,
+    Contains,
+    StartsWith,
+    FuzzyEqual,
+    Matches,
+    Modulo,
+    GeoRadius,
+    GeoBoundingBox,
+    GeoInRegion,
+}
+
+impl FilterOperator {
+    fn from_condition(condition: &Condition<'_>) -> Self {
+        match condition {
+            Condition::GreaterThan(_) => FilterOperator::GreaterThan,
+            Condition::GreaterThanOrEqual(_) => FilterOperator::GreaterThanOrEqual,
+            Condition::Equal(_) | Condition::IsTrue | Condition::IsFalse => FilterOperator::Equal,
+            Condition::NotEqual(_) => FilterOperator::NotEqual,
+            Condition::Null => FilterOperator::Null,
+            Condition::Empty => FilterOperator::Empty,
+            Condition::Exists => FilterOperator::Exists,
+            Condition::IsDate => FilterOperator::Date,
+            Condition::LowerThan(_) => FilterOperator::LowerThan,
+            Condition::LowerThanOrEqual(_) => FilterOperator::LowerThanOrEqual,
+            Condition::Between { .. } | Condition::Approximately { .. } => FilterOperator::Between,
+            Condition::Contains { .. } => FilterOperator::Contains,
+            Condition::StartsWith { .. } | Condition::StartsWithAny { .. } => {
+                FilterOperator::StartsWith
+            }
+            Condition::FuzzyEqual { .. } => FilterOperator::FuzzyEqual,
+            Condition::Regex { .. } => FilterOperator::Matches,
+            Condition::Modulo { .. } => FilterOperator::Modulo,
+            Condition::CompareFields { .. } => FilterOperator::CompareFields,
+        }
+    }
+
+    /// Returns `true` if the operator requires the comparison feature to be enabled on the field.
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            FilterOperator::GreaterThan
+                | FilterOperator::GreaterThanOrEqual
+                | FilterOperator::LowerThan
+                | FilterOperator::LowerThanOrEqual
+                | FilterOperator::Between
+        )
+    }
+}
+
+/// The length, in characters, under which the `CONTAINS` and `STARTS WITH` terms
+/// are reported as [`FilterWarning::BroadContains`] by default.
+pub const DEFAULT_BROAD_CONTAINS_THRESHOLD: usize = 3;
+
+/// A diagnostic about a filter, reported without changing the documents it matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterWarning {
+    /// A `CONTAINS` or `STARTS WITH` term so short that it matches most of the facet values
+    /// of the field, while the whole facet database of the field is scanned.
+    BroadContains { field: String, term: String },
+}
+
+/// What [`Filter::static_analysis`] knows about a filter without looking at the index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilterAnalysis {
+    /// The filter never matches any document.
+    pub always_empty: bool,
+    /// The filter always matches all the documents.
+    pub always_all: bool,
+}
+
+/// Why a document matches a filter or not, as returned by [`Filter::matches_document`].
+///
+/// Mirrors the tree of the filter, as it is displayed: the `AND`, `OR` and `NOT` conditions hold
+/// the explanations of their sub-conditions, the other conditions are leaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentMatchExplanation {
+    /// The condition, displayed like in the parsed filter.
+    pub condition: String,
+    /// Whether the document matches the condition.
+    pub matches: bool,
+    /// The explanations of the sub-conditions, empty for the leaves.
+    pub children: Vec<DocumentMatchExplanation>,
+}
+
+impl DocumentMatchExplanation {
+    /// The explanations of the leaf conditions, in the order of the filter.
+    pub fn leaves(&self) -> Vec<&DocumentMatchExplanation> {
+        if self.children.is_empty() {
+            return vec![self];
+        }
+        self.children.iter().flat_map(DocumentMatchExplanation::leaves).collect()
+    }
+}
+
 #[derive(Debug)]
 pub enum BadGeoError {
     Lat(f64),
@@ -65,7 +234,17 @@ impl Display for BadGeoError {
 enum FilterError<'a> {
     AttributeNotFilterable { attribute: &'a str, filterable_patterns: BTreeSet<&'a str> },
     ParseGeoError(BadGeoError),
-    TooDeep,
+    TooDeep(usize),
+    TooManyNodes(usize),
+    LengthOperatorNotSupported(&'a str),
+    StringLengthOperatorNotSupported(&'a str),
+    StringLengthNotEnabled(&'a str),
+    UnknownGeoRegion(&'a str),
+    UnknownEmbedder(&'a str),
+    AttributeNotInDocuments(&'a str),
+    ScanLimitExceeded { field: &'a str, limit: u64 },
+    InvalidRegex(regex::Error),
+    ModuloNotNumeric(&'a str),
 }
 impl std::error::Error for FilterError<'_> {}
 
@@ -96,12 +275,46 @@ impl Display for FilterError<'_> {
                     write!(f, ".")
                 }
             }
-            Self::TooDeep => write!(
+            Self::TooDeep(max_depth) => write!(
                 f,
                 "Too many filter conditions, can't process more than {} filters.",
-                MAX_FILTER_DEPTH
+                max_depth
             ),
+            Self::TooManyNodes(max_nodes) => {
+                write!(f, "Too many filter nodes, can't process more than {} nodes.", max_nodes)
+            }
             Self::ParseGeoError(error) => write!(f, "{}", error),
+            Self::LengthOperatorNotSupported(operator) => write!(
+                f,
+                "The `{operator}` operator can't be applied to the number of values of a field."
+            ),
+            Self::StringLengthOperatorNotSupported(operator) => write!(
+                f,
+                "The `{operator}` operator can't be applied to the length of the strings of a field."
+            ),
+            Self::StringLengthNotEnabled(attribute) => write!(
+                f,
+                "Filtering on the length of the strings of `{attribute}` is not allowed. Enable the `stringLength` filter feature of the attribute to use `_strlen`."
+            ),
+            Self::UnknownGeoRegion(name) => {
+                write!(f, "The geo region `{name}` does not exist.")
+            }
+            Self::UnknownEmbedder(name) => write!(f, "The embedder `{name}` does not exist."),
+            Self::ScanLimitExceeded { field, limit } => write!(
+                f,
+                "The filter scanned more than the {limit} facet values allowed on the attribute `{field}`."
+            ),
+            Self::AttributeNotInDocuments(attribute) => write!(
+                f,
+                "Attribute `{attribute}` is filterable but is not present in any document of the index."
+            ),
+            Self::InvalidRegex(error) => {
+                write!(f, "The pattern of a `MATCHES` filter must be a valid regex.\n{error}")
+            }
+            Self::ModuloNotNumeric(attribute) => write!(
+                f,
+                "The `MOD` operator can only be applied to numbers but the attribute `{attribute}` only contains strings."
+            ),
         }
     }
 }
@@ -137,32 +350,86 @@ impl<'a> Filter<'a> {
         let mut ands = Vec::new();
         for value in arr {
             match value {
-                Value::String(s) => ands.push(Either::Right(s.as_str())),
                 Value::Array(arr) => {
                     let mut ors = Vec::new();
                     for value in arr {
-                        match value {
-                            Value::String(s) => ors.push(s.as_str()),
-                            v => {
-                                return Err(Error::UserError(UserError::InvalidFilterExpression(
-                                    &["String"],
-                                    v.clone(),
-                                )))
-                            }
-                        }
+                        ors.extend(Self::parse_filter_array_rule(value, &["String", "Object"])?);
+                    }
+
+                    match ors.len() {
+                        0 => (),
+                        1 => ands.push(ors.pop().unwrap()),
+                        _ => ands.push(FilterCondition::Or(ors)),
                     }
-                    ands.push(Either::Left(ors));
                 }
                 v => {
-                    return Err(Error::UserError(UserError::InvalidFilterExpression(
-                        &["String", "[String]"],
-                        v.clone(),
-                    )))
+                    let expected = &["String", "[String]", "Object"];
+                    ands.extend(Self::parse_filter_array_rule(v, expected)?);
                 }
             }
         }
 
-        Filter::from_array(ands)
+        Self::from_and_conditions(ands)
+    }
+
+    /// Parses a rule of the array form, either a filter expression or an object of
+    /// the `{ "field": "price", "value": 10 }` form.
+    ///
+    /// The numbers and booleans of the object form are lowered into canonical tokens,
+    /// so that `1` and `1.0` select the same documents.
+    fn parse_filter_array_rule(
+        value: &'a Value,
+        expected: &'static [&'static str],
+    ) -> Result<Option<FilterCondition<'a>>> {
+        let object = match value {
+            Value::String(s) => return Ok(Self::from_str(s)?.map(|filter| filter.condition)),
+            Value::Object(object) => object,
+            v => {
+                return Err(Error::UserError(UserError::InvalidFilterExpression(
+                    expected,
+                    v.clone(),
+                )))
+            }
+        };
+
+        let fid = match object.get("field") {
+            Some(Value::String(field)) if object.len() == 2 => field.as_str(),
+            _ => {
+                return Err(Error::UserError(UserError::InvalidFilterExpression(
+                    OBJECT_RULE,
+                    value.clone(),
+                )))
+            }
+        };
+        let op = match object.get("value") {
+            Some(Value::String(s)) => Condition::Equal(Token::from(s.as_str())),
+            Some(Value::Number(number)) => {
+                // the integral floats are displayed without their fractional part
+                let canonical = match (number.as_u64(), number.as_i64(), number.as_f64()) {
+                    (Some(n), _, _) => n.to_string(),
+                    (None, Some(n), _) => n.to_string(),
+                    (None, None, Some(n)) => n.to_string(),
+                    (None, None, None) => number.to_string(),
+                };
+                Condition::Equal(Token::new(Span::new_extra(fid, fid), Some(canonical)))
+            }
+            Some(Value::Bool(true)) => Condition::IsTrue,
+            Some(Value::Bool(false)) => Condition::IsFalse,
+            Some(v) => {
+                return Err(Error::UserError(UserError::InvalidFilterExpression(
+                    &["String", "Number", "Boolean"],
+                    v.clone(),
+                )))
+            }
+            None => {
+                return Err(Error::UserError(UserError::InvalidFilterExpression(
+                    OBJECT_RULE,
+                    value.clone(),
+                )))
+            }
+        };
+
+        Ok(Some(FilterCondition::Condition { fid: Token::from(fid), op }))
     }
 
     pub fn from_array<I, J>(array: I) -> Result<Option<Self>>
@@ -195,6 +462,11 @@ impl<'a> Filter<'a> {
                 }
             }
         }
+
+        Self::from_and_conditions(ands)
+    }
+
+    fn from_and_conditions(mut ands: Vec<FilterCondition<'a>>) -> Result<Option<Self>> {
         let and = if ands.is_empty() {
             return Ok(None);
         } else if ands.len() == 1 {
@@ -204,1192 +476,6124 @@ impl<'a> Filter<'a> {
         };
 
         if let Some(token) = and.token_at_depth(MAX_FILTER_DEPTH) {
-            return Err(token.as_external_error(FilterError::TooDeep).into());
+            return Err(token.as_external_error(FilterError::TooDeep(MAX_FILTER_DEPTH)).into());
         }
 
         Ok(Some(Self { condition: and }))
     }
 
+    /// Creates a filter selecting the documents of the bitmap.
+    ///
+    /// Combine it with other filters using [`BitmapFilter::and`].
+    pub fn from_bitmap(bitmap: RoaringBitmap) -> BitmapFilter<'a> {
+        BitmapFilter { bitmap, filter: None }
+    }
+
+    /// Creates a filter selecting the documents with the given external ids, resolved
+    /// through the primary key of the index.
+    ///
+    /// The ids unknown to the index are ignored. Returns `None` when no id is given.
+    pub fn from_external_ids(
+        ids: &[&str],
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+    ) -> Result<Option<BitmapFilter<'a>>> {
+        if ids.is_empty() {
+            return Ok(None);
+        }
+        let external_documents_ids = index.external_documents_ids();
+        let mut bitmap = RoaringBitmap::new();
+        for id in ids {
+            if let Some(docid) = external_documents_ids.get(rtxn, id)? {
+                bitmap.insert(docid);
+            }
+        }
+        Ok(Some(Self::from_bitmap(bitmap)))
+    }
+
+    /// Creates a filter selecting the documents matching both filters.
+    pub fn and(self, other: Filter<'a>) -> Self {
+        let condition = match self.condition {
+            FilterCondition::And(mut ands) => {
+                ands.push(other.condition);
+                FilterCondition::And(ands)
+            }
+            condition => FilterCondition::And(vec![condition, other.condition]),
+        };
+        Self { condition }
+    }
+
+    /// Returns a logically equivalent filter with the nested `AND` and `OR` of the same type
+    /// merged into a single node, without duplicated children or double negations.
+    ///
+    /// The machine-generated filters often nest their conditions deeper than needed,
+    /// simplifying them reduces the depth and the number of nodes to evaluate.
+    pub fn simplify(self) -> Self {
+        Self { condition: self.condition.simplify() }
+    }
+
     #[allow(clippy::should_implement_trait)]
     pub fn from_str(expression: &'a str) -> Result<Option<Self>> {
+        Self::from_str_with_limits(expression, MAX_FILTER_DEPTH, usize::MAX)
+    }
+
+    /// Parses the filter like [`Filter::from_str`] but takes the ownership of the expression,
+    /// the returned filter doesn't borrow the caller's string.
+    pub fn from_str_borrowed_owned(expression: impl Into<String>) -> Result<Option<OwnedFilter>> {
+        let filter: Yoke<Option<Filter<'static>>, String> =
+            Yoke::try_attach_to_cart(expression.into(), |expression: &str| {
+                Filter::from_str(expression)
+            })?;
+        // an empty expression has no filter
+        let filter = filter.try_map_project(|filter, _| filter.ok_or(())).ok();
+        Ok(filter.map(|filter| OwnedFilter { filter }))
+    }
+
+    /// Parses the filter, rejecting it when it is deeper than `max_depth`
+    /// or contains more than `max_nodes` nodes, see [`Filter::node_count`].
+    pub fn from_str_with_limits(
+        expression: &'a str,
+        max_depth: usize,
+        max_nodes: usize,
+    ) -> Result<Option<Self>> {
         let condition = match FilterCondition::parse(expression) {
             Ok(Some(fc)) => Ok(fc),
             Ok(None) => return Ok(None),
             Err(e) => Err(Error::UserError(UserError::InvalidFilter(e.to_string()))),
         }?;
 
-        if let Some(token) = condition.token_at_depth(MAX_FILTER_DEPTH) {
-            return Err(token.as_external_error(FilterError::TooDeep).into());
+        if let Some(token) = condition.token_at_depth(max_depth) {
+            return Err(token.as_external_error(FilterError::TooDeep(max_depth)).into());
+        }
+
+        if let Some(token) = condition.token_past_node_limit(max_nodes) {
+            return Err(token.as_external_error(FilterError::TooManyNodes(max_nodes)).into());
         }
 
         Ok(Some(Self { condition }))
     }
 
+    /// Creates a filter from an already parsed condition, rejecting it when it is too deep
+    /// like [`Filter::from_str`] does, unlike the unchecked `From<FilterCondition>` conversion.
+    ///
+    /// Use it to rehydrate the conditions cached after parsing without parsing them again.
+    pub fn from_condition_checked(condition: FilterCondition<'a>) -> Result<Self> {
+        if let Some(token) = condition.token_at_depth(MAX_FILTER_DEPTH) {
+            return Err(token.as_external_error(FilterError::TooDeep(MAX_FILTER_DEPTH)).into());
+        }
+
+        Ok(Self { condition })
+    }
+
+    /// The AST of the filter, borrowed so that the filter can still be evaluated.
+    pub fn condition(&self) -> &FilterCondition<'a> {
+        &self.condition
+    }
+
+    /// Returns the number of nodes of the filter AST, including every element of the `IN` lists.
+    pub fn node_count(&self) -> usize {
+        self.condition.node_count()
+    }
+
     pub fn use_contains_operator(&self) -> Option<&Token> {
         self.condition.use_contains_operator()
     }
-}
 
-impl<'a> Filter<'a> {
-    pub fn evaluate(&self, rtxn: &heed::RoTxn<'_>, index: &Index) -> Result<RoaringBitmap> {
-        // to avoid doing this for each recursive call we're going to do it ONCE ahead of time
-        let fields_ids_map = index.fields_ids_map(rtxn)?;
-        let filterable_attributes_rules = index.filterable_attributes_rules(rtxn)?;
-        for fid in self.condition.fids(MAX_FILTER_DEPTH) {
-            let attribute = fid.value();
-            if matching_features(attribute, &filterable_attributes_rules)
-                .is_some_and(|(_, features)| features.is_filterable())
-            {
-                continue;
-            }
+    /// Returns, for every field referenced by the filter, the set of operators applied to it.
+    ///
+    /// Negations are transparent: `NOT price > 10` reports `GreaterThan` for `price`.
+    /// The geo filters are reported under the `_geo` field.
+    pub fn field_usage(&self) -> BTreeMap<String, BTreeSet<FilterOperator>> {
+        let mut usage = BTreeMap::new();
+        collect_field_usage(&self.condition, &mut usage);
+        usage
+    }
 
-            // If the field is not filterable, return an error
-            return Err(fid.as_external_error(FilterError::AttributeNotFilterable {
-                attribute,
-                filterable_patterns: filtered_matching_patterns(
-                    &filterable_attributes_rules,
-                    &|features| features.is_filterable(),
-                ),
-            }))?;
-        }
+    /// Returns the `(field, operator, value)` of every literal value of the filter, in order.
+    ///
+    /// Every element of the `IN` lists and both bounds of the `TO` ranges are reported, the
+    /// coordinates and radius of the geo filters are reported under the `_geo` field.
+    /// The conditions without any value, like `EXISTS` or `IS NULL`, are not reported.
+    pub fn referenced_values(&self) -> Vec<(String, FilterOperator, String)> {
+        let mut values = Vec::new();
+        collect_referenced_values(&self.condition, &mut values);
+        values
+    }
 
-        self.inner_evaluate(rtxn, index, &fields_ids_map, &filterable_attributes_rules, None)
+    /// Returns the [`FilterWarning`]s of the filter, in order.
+    ///
+    /// The `CONTAINS` and `STARTS WITH` terms shorter than `broad_contains_threshold`
+    /// characters are reported as [`FilterWarning::BroadContains`].
+    pub fn warnings(&self, broad_contains_threshold: usize) -> Vec<FilterWarning> {
+        self.referenced_values()
+            .into_iter()
+            .filter(|(_, operator, term)| {
+                matches!(operator, FilterOperator::Contains | FilterOperator::StartsWith)
+                    && term.chars().count() < broad_contains_threshold
+            })
+            .map(|(field, _, term)| FilterWarning::BroadContains { field, term })
+            .collect()
     }
 
-    fn evaluate_operator(
-        rtxn: &heed::RoTxn<'_>,
-        index: &Index,
-        field_id: FieldId,
-        universe: Option<&RoaringBitmap>,
-        operator: &Condition<'a>,
-        features: &FilterableAttributesFeatures,
-        rule_index: usize,
-    ) -> Result<RoaringBitmap> {
-        let numbers_db = index.facet_id_f64_docids;
-        let strings_db = index.facet_id_string_docids;
+    /// Simplifies the filter syntactically, without touching the index, to tell whether
+    /// it is known to match no document or all of them.
+    ///
+    /// Only the length ranges with inverted bounds like `_length(tags) 3 TO 1`, the empty `IN`
+    /// and `OR` lists and the conditions combined with their own negation like
+    /// `x AND NOT x` or `x OR NOT x` are simplified, both fields stay `false` otherwise.
+    pub fn static_analysis(&self) -> FilterAnalysis {
+        let outcome = static_outcome(&self.condition);
+        FilterAnalysis { always_empty: outcome == Some(false), always_all: outcome == Some(true) }
+    }
+}
 
-        // Make sure we always bound the ranges with the field id and the level,
-        // as the facets values are all in the same database and prefixed by the
-        // field id and the level.
+/// Returns `Some(false)` if the condition never matches any document,
+/// `Some(true)` if it always matches all of them and `None` if it depends on the index.
+fn static_outcome(condition: &FilterCondition<'_>) -> Option<bool> {
+    /// Returns `true` if one of the conditions is the negation of another one.
+    fn contains_negated_pair(conditions: &[FilterCondition<'_>]) -> bool {
+        conditions.iter().any(|condition| match condition {
+            FilterCondition::Not(negated) => conditions.contains(negated),
+            _ => false,
+        })
+    }
 
-        let (number_bounds, (left_str, right_str)) = match operator {
-            // return an error if the filter is not allowed for this field
-            Condition::GreaterThan(_)
-            | Condition::GreaterThanOrEqual(_)
-            | Condition::LowerThan(_)
-            | Condition::LowerThanOrEqual(_)
-            | Condition::Between { .. }
-                if !features.is_filterable_comparison() =>
-            {
-                return Err(generate_filter_error(
-                    rtxn, index, field_id, operator, features, rule_index,
-                ));
-            }
-            Condition::Empty if !features.is_filterable_empty() => {
-                return Err(generate_filter_error(
-                    rtxn, index, field_id, operator, features, rule_index,
-                ));
-            }
-            Condition::Null if !features.is_filterable_null() => {
-                return Err(generate_filter_error(
-                    rtxn, index, field_id, operator, features, rule_index,
-                ));
-            }
-            Condition::Exists if !features.is_filterable_exists() => {
-                return Err(generate_filter_error(
-                    rtxn, index, field_id, operator, features, rule_index,
-                ));
-            }
-            Condition::Equal(_) | Condition::NotEqual(_) if !features.is_filterable_equality() => {
-                return Err(generate_filter_error(
-                    rtxn, index, field_id, operator, features, rule_index,
-                ));
-            }
-            Condition::GreaterThan(val) => {
-                let number = val.parse_finite_float().ok();
-                let number_bounds = number.map(|number| (Excluded(number), Included(f64::MAX)));
-                let str_bounds = (Excluded(val.value()), Unbounded);
-                (number_bounds, str_bounds)
+    /// Returns `true` if the number bounds of the range are inverted.
+    ///
+    /// Only meaningful for the lengths, which are always numbers: the range of a field
+    /// like `price "10" TO "5"` also compares its string values, where `"3"` lies in between.
+    fn is_inverted_range(op: &Condition<'_>) -> bool {
+        match op {
+            Condition::Between { from, to } => from
+                .parse_finite_float()
+                .ok()
+                .zip(to.parse_finite_float().ok())
+                .is_some_and(|(from, to)| from > to),
+            _ => false,
+        }
+    }
+
+    match condition {
+        FilterCondition::Not(condition) => static_outcome(condition).map(|outcome| !outcome),
+        FilterCondition::And(conditions) => {
+            let outcomes: Vec<_> = conditions.iter().map(static_outcome).collect();
+            if outcomes.contains(&Some(false)) || contains_negated_pair(conditions) {
+                Some(false)
+            } else if outcomes.iter().all(|outcome| *outcome == Some(true)) {
+                Some(true)
+            } else {
+                None
             }
-            Condition::GreaterThanOrEqual(val) => {
-                let number = val.parse_finite_float().ok();
-                let number_bounds = number.map(|number| (Included(number), Included(f64::MAX)));
-                let str_bounds = (Included(val.value()), Unbounded);
-                (number_bounds, str_bounds)
+        }
+        FilterCondition::Or(conditions) => {
+            let outcomes: Vec<_> = conditions.iter().map(static_outcome).collect();
+            if outcomes.contains(&Some(true)) || contains_negated_pair(conditions) {
+                Some(true)
+            } else if outcomes.iter().all(|outcome| *outcome == Some(false)) {
+                Some(false)
+            } else {
+                None
             }
-            Condition::LowerThan(val) => {
-                let number = val.parse_finite_float().ok();
-                let number_bounds = number.map(|number| (Included(f64::MIN), Excluded(number)));
-                let str_bounds = (Unbounded, Excluded(val.value()));
-                (number_bounds, str_bounds)
+        }
+        FilterCondition::Difference { left, right } => {
+            if left == right {
+                return Some(false);
             }
-            Condition::LowerThanOrEqual(val) => {
-                let number = val.parse_finite_float().ok();
-                let number_bounds = number.map(|number| (Included(f64::MIN), Included(number)));
-                let str_bounds = (Unbounded, Included(val.value()));
-                (number_bounds, str_bounds)
+            match (static_outcome(left), static_outcome(right)) {
+                (Some(false), _) | (_, Some(true)) => Some(false),
+                (Some(true), Some(false)) => Some(true),
+                _ => None,
             }
-            Condition::Between { from, to } => {
-                let from_number = from.parse_finite_float().ok();
-                let to_number = to.parse_finite_float().ok();
+        }
+        FilterCondition::Length { op, .. } | FilterCondition::StringLength { op, .. } => {
+            is_inverted_range(op).then_some(false)
+        }
+        FilterCondition::In { els, .. } => els.is_empty().then_some(false),
+        FilterCondition::Condition { .. }
+        | FilterCondition::ContainsAll { .. }
+        | FilterCondition::GeoLowerThan { .. }
+        | FilterCondition::GeoBoundingBox { .. }
+        | FilterCondition::GeoInRegion { .. } => None,
+    }
+}
+
+/// Returns the name of the embedder of a `_vectors.{embedder} EXISTS` condition, which selects
+/// the documents having an embedding for this embedder whatever the filterable attributes are.
+fn embedder_presence<'b>(fid: &'b Token<'_>, op: &Condition<'_>) -> Option<&'b str> {
+    match op {
+        Condition::Exists => fid
+            .value()
+            .strip_prefix(RESERVED_VECTORS_FIELD_NAME)?
+            .strip_prefix('.')
+            .filter(|name| !name.is_empty()),
+        _ => None,
+    }
+}
+
+/// Collects the field tokens of the `_vectors.{embedder} EXISTS` conditions.
+fn collect_embedder_presence_fids<'b, 'a>(
+    condition: &'b FilterCondition<'a>,
+    fids: &mut Vec<&'b Token<'a>>,
+) {
+    match condition {
+        FilterCondition::Not(condition) => collect_embedder_presence_fids(condition, fids),
+        FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+            conditions.iter().for_each(|condition| collect_embedder_presence_fids(condition, fids))
+        }
+        FilterCondition::Difference { left, right } => {
+            collect_embedder_presence_fids(left, fids);
+            collect_embedder_presence_fids(right, fids);
+        }
+        FilterCondition::Condition { fid, op } if embedder_presence(fid, op).is_some() => {
+            fids.push(fid)
+        }
+        _ => (),
+    }
+}
+
+/// Whether the field is the `_geo.lat` or `_geo.lng` coordinate of the `_geo` field.
+fn is_geo_coordinate(field: &str) -> bool {
+    field == RESERVED_GEO_LAT_FIELD_NAME || field == RESERVED_GEO_LNG_FIELD_NAME
+}
+
+/// Returns the index and features of the filterable attributes rule applying to the field.
+///
+/// The `_geo.lat` and `_geo.lng` coordinates are filterable as numbers when the geo filtering
+/// is enabled, with the features of the `_geo` rule whatever the other rules matching them.
+fn filter_features(
+    field: &str,
+    filterable_attributes_rules: &[FilterableAttributesRule],
+) -> Option<(usize, FilterableAttributesFeatures)> {
+    if is_geo_coordinate(field) {
+        let rule_index = filterable_attributes_rules.iter().position(|rule| rule.has_geo())?;
+        return Some((rule_index, filterable_attributes_rules[rule_index].features()));
+    }
+    matching_features(field, filterable_attributes_rules)
+}
+
+/// Returns `true` if the negation of the condition only selects geo documents.
+///
+/// The documents without coordinates are neither inside nor outside of a bounding box,
+/// so its negation and the negations of its negations stay within the geo documents.
+fn negated_within_geo(condition: &FilterCondition<'_>) -> bool {
+    match condition {
+        FilterCondition::GeoBoundingBox { .. } => true,
+        FilterCondition::Not(condition) => negated_within_geo(condition),
+        _ => false,
+    }
+}
+
+/// Returns the first point of the first geo filter, if any.
+fn first_geo_token<'a, 'b>(condition: &'b FilterCondition<'a>) -> Option<&'b Token<'a>> {
+    match condition {
+        FilterCondition::Not(condition) => first_geo_token(condition),
+        FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+            conditions.iter().find_map(first_geo_token)
+        }
+        FilterCondition::Difference { left, right } => {
+            first_geo_token(left).or_else(|| first_geo_token(right))
+        }
+        FilterCondition::GeoLowerThan { point: [token, _], .. }
+        | FilterCondition::GeoBoundingBox { top_right_point: [token, _], .. }
+        | FilterCondition::GeoInRegion { name: token } => Some(token),
+        FilterCondition::Condition { .. }
+        | FilterCondition::In { .. }
+        | FilterCondition::ContainsAll { .. }
+        | FilterCondition::Length { .. }
+        | FilterCondition::StringLength { .. } => None,
+    }
+}
+
+fn collect_referenced_values(
+    condition: &FilterCondition<'_>,
+    values: &mut Vec<(String, FilterOperator, String)>,
+) {
+    fn push(
+        values: &mut Vec<(String, FilterOperator, String)>,
+        field: &str,
+        operator: FilterOperator,
+        tokens: &[&Token<'_>],
+    ) {
+        values.extend(
+            tokens.iter().map(|token| (field.to_string(), operator, token.value().to_string())),
+        )
+    }
 
-                let number_bounds =
-                    from_number.zip(to_number).map(|(from, to)| (Included(from), Included(to)));
-                let str_bounds = (Included(from.value()), Included(to.value()));
-                (number_bounds, str_bounds)
+    match condition {
+        FilterCondition::Not(condition) => collect_referenced_values(condition, values),
+        FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+            conditions.iter().for_each(|condition| collect_referenced_values(condition, values))
+        }
+        FilterCondition::Difference { left, right } => {
+            collect_referenced_values(left, values);
+            collect_referenced_values(right, values);
+        }
+        FilterCondition::Condition { fid, op } => {
+            let operator = FilterOperator::from_condition(op);
+            match op {
+                Condition::GreaterThan(value)
+                | Condition::GreaterThanOrEqual(value)
+                | Condition::Equal(value)
+                | Condition::NotEqual(value)
+                | Condition::LowerThan(value)
+                | Condition::LowerThanOrEqual(value)
+                | Condition::Contains { word: value, .. }
+                | Condition::StartsWith { word: value, .. }
+                | Condition::FuzzyEqual { value, .. }
+                | Condition::Regex { pattern: value } => {
+                    push(values, fid.value(), operator, &[value])
+                }
+                Condition::Between { from, to } => push(values, fid.value(), operator, &[from, to]),
+                Condition::Modulo { divisor, remainder } => {
+                    push(values, fid.value(), operator, &[divisor, remainder])
+                }
+                Condition::Approximately { value, tolerance } => {
+                    push(values, fid.value(), operator, &[value, tolerance])
+                }
+                Condition::StartsWithAny { words, .. } => {
+                    push(values, fid.value(), operator, &words.iter().collect::<Vec<_>>())
+                }
+                Condition::IsTrue | Condition::IsFalse => values.push((
+                    fid.value().to_string(),
+                    operator,
+                    crate::boolean_facet_value(matches!(op, Condition::IsTrue)).to_string(),
+                )),
+                Condition::Null
+                | Condition::Empty
+                | Condition::Exists
+                | Condition::IsDate
+                | Condition::CompareFields { .. } => (),
             }
-            Condition::Null => {
-                let is_null = index.null_faceted_documents_ids(rtxn, field_id)?;
-                return Ok(is_null);
+        }
+        FilterCondition::In { fid, els } => {
+            push(values, fid.value(), FilterOperator::In, &els.iter().collect::<Vec<_>>())
+        }
+        FilterCondition::ContainsAll { fid, els } => {
+            push(values, fid.value(), FilterOperator::ContainsAll, &els.iter().collect::<Vec<_>>())
+        }
+        FilterCondition::Length { fid, op } => match op {
+            Condition::Between { from, to } => {
+                push(values, fid.value(), FilterOperator::Length, &[from, to])
             }
-            Condition::Empty => {
-                let is_empty = index.empty_faceted_documents_ids(rtxn, field_id)?;
-                return Ok(is_empty);
+            Condition::GreaterThan(value)
+            | Condition::GreaterThanOrEqual(value)
+            | Condition::Equal(value)
+            | Condition::NotEqual(value)
+            | Condition::LowerThan(value)
+            | Condition::LowerThanOrEqual(value) => {
+                push(values, fid.value(), FilterOperator::Length, &[value])
             }
-            Condition::Exists => {
-                let exist = index.exists_faceted_documents_ids(rtxn, field_id)?;
-                return Ok(exist);
+            _ => (),
+        },
+        FilterCondition::StringLength { fid, op } => match op {
+            Condition::Between { from, to } => {
+                push(values, fid.value(), FilterOperator::StringLength, &[from, to])
             }
-            Condition::Equal(val) => {
-                let string_docids = strings_db
-                    .get(
-                        rtxn,
-                        &FacetGroupKey {
-                            field_id,
-                            level: 0,
-                            left_bound: &crate::normalize_facet(val.value()),
-                        },
-                    )?
-                    .map(|v| v.bitmap)
-                    .unwrap_or_default();
-                let number = val.parse_finite_float().ok();
-                let number_docids = match number {
-                    Some(n) => numbers_db
-                        .get(rtxn, &FacetGroupKey { field_id, level: 0, left_bound: n })?
-                        .map(|v| v.bitmap)
-                        .unwrap_or_default(),
-                    None => RoaringBitmap::new(),
-                };
-                return Ok(string_docids | number_docids);
+            Condition::GreaterThan(value)
+            | Condition::GreaterThanOrEqual(value)
+            | Condition::Equal(value)
+            | Condition::NotEqual(value)
+            | Condition::LowerThan(value)
+            | Condition::LowerThanOrEqual(value) => {
+                push(values, fid.value(), FilterOperator::StringLength, &[value])
             }
-            Condition::NotEqual(val) => {
-                let operator = Condition::Equal(val.clone());
-                let docids = Self::evaluate_operator(
-                    rtxn, index, field_id, None, &operator, features, rule_index,
-                )?;
-                let all_ids = index.documents_ids(rtxn)?;
-                return Ok(all_ids - docids);
+            _ => (),
+        },
+        FilterCondition::GeoLowerThan { point: [lat, lng], radius, .. } => {
+            push(values, RESERVED_GEO_FIELD_NAME, FilterOperator::GeoRadius, &[lat, lng, radius])
+        }
+        FilterCondition::GeoBoundingBox {
+            top_right_point: [top, right],
+            bottom_left_point: [bottom, left],
+        } => push(
+            values,
+            RESERVED_GEO_FIELD_NAME,
+            FilterOperator::GeoBoundingBox,
+            &[top, right, bottom, left],
+        ),
+        FilterCondition::GeoInRegion { name } => {
+            push(values, RESERVED_GEO_FIELD_NAME, FilterOperator::GeoInRegion, &[name])
+        }
+    }
+}
+
+fn collect_field_usage(
+    condition: &FilterCondition<'_>,
+    usage: &mut BTreeMap<String, BTreeSet<FilterOperator>>,
+) {
+    let (field, operator) = match condition {
+        FilterCondition::Not(condition) => return collect_field_usage(condition, usage),
+        FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+            conditions.iter().for_each(|condition| collect_field_usage(condition, usage));
+            return;
+        }
+        FilterCondition::Difference { left, right } => {
+            collect_field_usage(left, usage);
+            return collect_field_usage(right, usage);
+        }
+        FilterCondition::Condition { fid, op: Condition::CompareFields { other, .. } } => {
+            for field in [fid, other] {
+                usage
+                    .entry(field.value().to_string())
+                    .or_default()
+                    .insert(FilterOperator::CompareFields);
             }
-            Condition::Contains { keyword: _, word } => {
-                let value = crate::normalize_facet(word.value());
-                let finder = Finder::new(&value);
-                let base = FacetGroupKey { field_id, level: 0, left_bound: "" };
-                let docids = strings_db
-                    .prefix_iter(rtxn, &base)?
-                    .remap_data_type::<LazyDecode<FacetGroupValueCodec>>()
-                    .filter_map(|result| -> Option<Result<RoaringBitmap>> {
-                        match result {
-                            Ok((FacetGroupKey { left_bound, .. }, lazy_group_value)) => {
-                                if finder.find(left_bound.as_bytes()).is_some() {
-                                    Some(lazy_group_value.decode().map(|gv| gv.bitmap).map_err(
-                                        |_| {
-                                            InternalError::from(SerializationError::Decoding {
-                                                db_name: Some(FACET_ID_STRING_DOCIDS),
-                                            })
-                                            .into()
-                                        },
-                                    ))
-                                } else {
-                                    None
-                                }
-                            }
-                            Err(_e) => {
-                                Some(Err(InternalError::from(SerializationError::Decoding {
-                                    db_name: Some(FACET_ID_STRING_DOCIDS),
-                                })
-                                .into()))
-                            }
-                        }
-                    })
-                    .union()?;
+            return;
+        }
+        FilterCondition::Condition { fid, op } => (fid.value(), FilterOperator::from_condition(op)),
+        FilterCondition::In { fid, .. } => (fid.value(), FilterOperator::In),
+        FilterCondition::ContainsAll { fid, .. } => (fid.value(), FilterOperator::ContainsAll),
+        FilterCondition::Length { fid, .. } => (fid.value(), FilterOperator::Length),
+        FilterCondition::StringLength { fid, .. } => (fid.value(), FilterOperator::StringLength),
+        FilterCondition::GeoLowerThan { .. } => {
+            (RESERVED_GEO_FIELD_NAME, FilterOperator::GeoRadius)
+        }
+        FilterCondition::GeoBoundingBox { .. } => {
+            (RESERVED_GEO_FIELD_NAME, FilterOperator::GeoBoundingBox)
+        }
+        FilterCondition::GeoInRegion { .. } => {
+            (RESERVED_GEO_FIELD_NAME, FilterOperator::GeoInRegion)
+        }
+    };
+    usage.entry(field.to_string()).or_default().insert(operator);
+}
 
-                return Ok(docids);
+impl<'a> Filter<'a> {
+    pub fn evaluate(&self, rtxn: &heed::RoTxn<'_>, index: &Index) -> Result<RoaringBitmap> {
+        self.evaluate_cancellable(rtxn, index, &AtomicBool::new(false))
+    }
+
+    /// Evaluates the filter like [`Self::evaluate`], returning [`Error::Interrupted`]
+    /// as soon as `should_stop` is set.
+    pub fn evaluate_cancellable(
+        &self,
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        should_stop: &AtomicBool,
+    ) -> Result<RoaringBitmap> {
+        // to avoid doing this for each recursive call we're going to do it ONCE ahead of time
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let filterable_attributes_rules = index.filterable_attributes_rules(rtxn)?;
+        self.check_filterable_fields(&filterable_attributes_rules)?;
+        // the negations all complement their selection with the documents ids
+        let cache = EvaluationCache::new(rtxn, index);
+
+        self.inner_evaluate(
+            rtxn,
+            index,
+            &fields_ids_map,
+            &filterable_attributes_rules,
+            None,
+            &cache,
+            should_stop,
+        )
+    }
+
+    /// Splits the `universe`, all the documents of the index by default, into the documents
+    /// matching the filter and the ones that don't, in a single evaluation.
+    ///
+    /// Both halves are derived from the same universe: they are disjoint and their union
+    /// is the universe.
+    pub fn partition(
+        &self,
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        universe: Option<&RoaringBitmap>,
+    ) -> Result<(RoaringBitmap, RoaringBitmap)> {
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let filterable_attributes_rules = index.filterable_attributes_rules(rtxn)?;
+        self.check_filterable_fields(&filterable_attributes_rules)?;
+        let cache = EvaluationCache::new(rtxn, index);
+        let universe = match universe {
+            Some(universe) => universe,
+            None => cache.documents_ids()?,
+        };
+
+        let mut matched = self.inner_evaluate(
+            rtxn,
+            index,
+            &fields_ids_map,
+            &filterable_attributes_rules,
+            Some(universe),
+            &cache,
+            &AtomicBool::new(false),
+        )?;
+        // the negations complement their selection with the documents of the index
+        matched &= universe;
+        let unmatched = universe - &matched;
+        Ok((matched, unmatched))
+    }
+
+    /// Explains whether the document matches the filter, condition by condition.
+    ///
+    /// Each leaf condition is evaluated on the document alone, and the `AND`, `OR`, `NOT`
+    /// and difference conditions combine the results of their sub-conditions.
+    pub fn matches_document(
+        &self,
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        docid: DocumentId,
+    ) -> Result<DocumentMatchExplanation> {
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let filterable_attributes_rules = index.filterable_attributes_rules(rtxn)?;
+        self.check_filterable_fields(&filterable_attributes_rules)?;
+        let cache = EvaluationCache::new(rtxn, index);
+        if !cache.documents_ids()?.contains(docid) {
+            return Err(UserError::UnknownInternalDocumentId { document_id: docid }.into());
+        }
+
+        self.explain_document(
+            rtxn,
+            index,
+            &fields_ids_map,
+            &filterable_attributes_rules,
+            docid,
+            &cache,
+        )
+    }
+
+    fn explain_document(
+        &self,
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        field_ids_map: &FieldsIdsMap,
+        filterable_attribute_rules: &[FilterableAttributesRule],
+        docid: DocumentId,
+        cache: &EvaluationCache<'_>,
+    ) -> Result<DocumentMatchExplanation> {
+        let children: Vec<&FilterCondition> = match &self.condition {
+            FilterCondition::Not(condition) => vec![condition],
+            FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+                conditions.iter().collect()
             }
-            Condition::StartsWith { keyword: _, word } => {
-                let value = crate::normalize_facet(word.value());
-                let base = FacetGroupKey { field_id, level: 0, left_bound: value.as_str() };
-                let docids = strings_db
-                    .prefix_iter(rtxn, &base)?
-                    .map(|result| -> Result<RoaringBitmap> {
-                        match result {
-                            Ok((_facet_group_key, FacetGroupValue { bitmap, .. })) => Ok(bitmap),
-                            Err(_e) => Err(InternalError::from(SerializationError::Decoding {
-                                db_name: Some(FACET_ID_STRING_DOCIDS),
-                            })
-                            .into()),
-                        }
-                    })
-                    .union()?;
+            FilterCondition::Difference { left, right } => vec![left, right],
+            _ => Vec::new(),
+        };
+        let children = children
+            .into_iter()
+            .map(|condition| {
+                Filter::from(condition.clone()).explain_document(
+                    rtxn,
+                    index,
+                    field_ids_map,
+                    filterable_attribute_rules,
+                    docid,
+                    cache,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-                return Ok(docids);
+        let (matches, children) = match &self.condition {
+            FilterCondition::Not(condition) => {
+                let matches = if negated_within_geo(condition) {
+                    !children[0].matches && index.geo_faceted_documents_ids(rtxn)?.contains(docid)
+                } else {
+                    !children[0].matches
+                };
+                (matches, children)
             }
+            FilterCondition::Or(_) => (children.iter().any(|child| child.matches), children),
+            FilterCondition::And(_) => (children.iter().all(|child| child.matches), children),
+            // explained like it is displayed, as an `AND` with the negation of its right side
+            FilterCondition::Difference { right, .. } => {
+                let [left, right_explanation] = <[_; 2]>::try_from(children).unwrap();
+                let not_right_matches = if negated_within_geo(right) {
+                    !right_explanation.matches
+                        && index.geo_faceted_documents_ids(rtxn)?.contains(docid)
+                } else {
+                    !right_explanation.matches
+                };
+                let not_right = DocumentMatchExplanation {
+                    condition: FilterCondition::Not(right.clone()).to_string(),
+                    matches: not_right_matches,
+                    children: vec![right_explanation],
+                };
+                (left.matches && not_right.matches, vec![left, not_right])
+            }
+            _ => (
+                self.inner_evaluate(
+                    rtxn,
+                    index,
+                    field_ids_map,
+                    filterable_attribute_rules,
+                    Some(&RoaringBitmap::from_iter([docid])),
+                    cache,
+                    &AtomicBool::new(false),
+                )?
+                .contains(docid),
+                children,
+            ),
         };
 
-        let mut output = RoaringBitmap::new();
+        Ok(DocumentMatchExplanation { condition: self.condition.to_string(), matches, children })
+    }
 
-        if let Some((left_number, right_number)) = number_bounds {
-            Self::explore_facet_levels(
-                rtxn,
-                numbers_db,
-                field_id,
-                &left_number,
-                &right_number,
-                universe,
-                &mut output,
-            )?;
+    /// Evaluates the filter like [`Self::evaluate`], along with its [`Self::warnings`].
+    pub fn evaluate_with_warnings(
+        &self,
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        broad_contains_threshold: usize,
+    ) -> Result<(RoaringBitmap, Vec<FilterWarning>)> {
+        let docids = self.evaluate(rtxn, index)?;
+        Ok((docids, self.warnings(broad_contains_threshold)))
+    }
+
+    /// Evaluates the filter like [`Self::evaluate`], only allowing it to reference the `allowed` fields.
+    ///
+    /// The fields outside of `allowed` are rejected with the same `AttributeNotFilterable` error
+    /// as the fields that are not filterable, even when the index allows filtering on them.
+    /// The geo filters, and the conditions on `_geo.lat` and `_geo.lng`, require the `_geo`
+    /// field to be allowed.
+    pub fn evaluate_with_allowed_fields(
+        &self,
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        allowed: &BTreeSet<&str>,
+    ) -> Result<RoaringBitmap> {
+        self.check_allowed_fields(allowed)?;
+        self.evaluate(rtxn, index)
+    }
+
+    /// Evaluates the filter like [`Self::evaluate`], returning an error instead of
+    /// an empty selection when a filterable field is not present in any document.
+    ///
+    /// This surfaces the mismatches between the filter and the fields of the documents, like a
+    /// filter on `a.b` when the documents store it under another flattened name.
+    pub fn evaluate_strict(&self, rtxn: &heed::RoTxn<'_>, index: &Index) -> Result<RoaringBitmap> {
+        let filterable_attributes_rules = index.filterable_attributes_rules(rtxn)?;
+        self.check_filterable_fields(&filterable_attributes_rules)?;
+        self.check_known_fields(&index.fields_ids_map(rtxn)?)?;
+        self.evaluate(rtxn, index)
+    }
+
+    fn check_known_fields(&self, fields_ids_map: &FieldsIdsMap) -> Result<()> {
+        let mut embedder_presence_fids = Vec::new();
+        collect_embedder_presence_fids(&self.condition, &mut embedder_presence_fids);
+
+        for fid in self.condition.fids(MAX_FILTER_DEPTH) {
+            if embedder_presence_fids.iter().any(|presence| std::ptr::eq(*presence, fid)) {
+                continue;
+            }
+            if fields_ids_map.id(fid.value()).is_none() {
+                return Err(
+                    fid.as_external_error(FilterError::AttributeNotInDocuments(fid.value()))
+                )?;
+            }
         }
 
-        Self::explore_facet_levels(
+        Ok(())
+    }
+
+    fn check_allowed_fields(&self, allowed: &BTreeSet<&str>) -> Result<()> {
+        let geo = first_geo_token(&self.condition).map(|token| (RESERVED_GEO_FIELD_NAME, token));
+        // the geo coordinates are allowed along with the `_geo` field
+        let fields = self.condition.fids(MAX_FILTER_DEPTH).map(|fid| match fid.value() {
+            attribute if is_geo_coordinate(attribute) => (RESERVED_GEO_FIELD_NAME, fid),
+            attribute => (attribute, fid),
+        });
+        match fields.chain(geo).find(|(attribute, _)| !allowed.contains(attribute)) {
+            Some((attribute, token)) => {
+                Err(token.as_external_error(FilterError::AttributeNotFilterable {
+                    attribute,
+                    filterable_patterns: allowed.clone(),
+                }))?
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Estimates the number of documents matching the filter, without evaluating it.
+    ///
+    /// The conditions are estimated from the number of documents of the facet values, and of the
+    /// largest facet groups contained within the ranges, without decoding their bitmaps.
+    /// A document having several values matching a condition is counted once per value.
+    ///
+    /// The conditions are considered independent: the fraction of the documents matched by an
+    /// `AND` is the product of the fractions of its members, while an `OR` is bounded by the sum
+    /// of its members. The geo conditions are bounded by the number of geo faceted documents.
+    /// The estimate never exceeds the number of documents of the index, but can be far
+    /// from [`Self::evaluate`] when the conditions are correlated.
+    pub fn estimate_cardinality(&self, rtxn: &heed::RoTxn<'_>, index: &Index) -> Result<u64> {
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let filterable_attributes_rules = index.filterable_attributes_rules(rtxn)?;
+        self.check_filterable_fields(&filterable_attributes_rules)?;
+
+        let documents = index.number_of_documents(rtxn)? as f64;
+        let cache = EvaluationCache::new(rtxn, index);
+        let estimate = Self::inner_estimate(
+            &self.condition,
             rtxn,
-            strings_db,
-            field_id,
-            &left_str,
-            &right_str,
-            universe,
-            &mut output,
+            index,
+            &fields_ids_map,
+            &filterable_attributes_rules,
+            &cache,
+            documents,
         )?;
+        Ok(estimate.round() as u64)
+    }
 
-        Ok(output)
+    /// Checks that the filter only applies allowed operators on filterable attributes,
+    /// without reading the facet databases.
+    ///
+    /// Returns the same `AttributeNotFilterable` and `FilterOperatorNotAllowed` errors
+    /// as [`Self::evaluate`].
+    pub fn validate(&self, rtxn: &heed::RoTxn<'_>, index: &Index) -> Result<()> {
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let filterable_attributes_rules = index.filterable_attributes_rules(rtxn)?;
+        self.check_filterable_fields(&filterable_attributes_rules)?;
+
+        self.inner_validate(rtxn, index, &fields_ids_map, &filterable_attributes_rules)
     }
 
-    /// Aggregates the documents ids that are part of the specified range automatically
-    /// going deeper through the levels.
-    fn explore_facet_levels<'data, BoundCodec>(
-        rtxn: &'data heed::RoTxn<'data>,
-        db: heed::Database<FacetGroupKeyCodec<BoundCodec>, FacetGroupValueCodec>,
-        field_id: FieldId,
-        left: &'data Bound<<BoundCodec as heed::BytesEncode<'data>>::EItem>,
-        right: &'data Bound<<BoundCodec as heed::BytesEncode<'data>>::EItem>,
-        universe: Option<&RoaringBitmap>,
-        output: &mut RoaringBitmap,
-    ) -> Result<()>
-    where
-        BoundCodec: for<'b> BytesEncode<'b>,
-        for<'b> <BoundCodec as BytesEncode<'b>>::EItem: Sized + PartialOrd,
-    {
-        match (left, right) {
-            // lower TO upper when lower > upper must return no result
-            (Included(l), Included(r)) if l > r => return Ok(()),
-            (Included(l), Excluded(r)) if l >= r => return Ok(()),
-            (Excluded(l), Excluded(r)) if l >= r => return Ok(()),
-            (Excluded(l), Included(r)) if l >= r => return Ok(()),
-            (_, _) => (),
+    fn check_filterable_fields(
+        &self,
+        filterable_attributes_rules: &[FilterableAttributesRule],
+    ) -> Result<()> {
+        let mut embedder_presence_fids = Vec::new();
+        collect_embedder_presence_fids(&self.condition, &mut embedder_presence_fids);
+
+        for fid in self.condition.fids(MAX_FILTER_DEPTH) {
+            if embedder_presence_fids.iter().any(|presence| std::ptr::eq(*presence, fid)) {
+                continue;
+            }
+            let attribute = fid.value();
+            if filter_features(attribute, filterable_attributes_rules)
+                .is_some_and(|(_, features)| features.is_filterable())
+            {
+                continue;
+            }
+
+            // If the field is not filterable, return an error
+            return Err(fid.as_external_error(FilterError::AttributeNotFilterable {
+                attribute,
+                filterable_patterns: filtered_matching_patterns(
+                    filterable_attributes_rules,
+                    &|features| features.is_filterable(),
+                ),
+            }))?;
         }
-        facet_range_search::find_docids_of_facet_within_bounds::<BoundCodec>(
-            rtxn, db, field_id, left, right, universe, output,
-        )?;
 
         Ok(())
     }
 
-    fn inner_evaluate(
+    fn inner_validate(
         &self,
         rtxn: &heed::RoTxn<'_>,
         index: &Index,
         field_ids_map: &FieldsIdsMap,
         filterable_attribute_rules: &[FilterableAttributesRule],
-        universe: Option<&RoaringBitmap>,
-    ) -> Result<RoaringBitmap> {
-        if universe.is_some_and(|u| u.is_empty()) {
-            return Ok(RoaringBitmap::new());
-        }
-
-        match &self.condition {
+    ) -> Result<()> {
+        let (fid, operators) = match &self.condition {
             FilterCondition::Not(f) => {
-                let selected = Self::inner_evaluate(
+                return Self::inner_validate(
                     &(f.as_ref().clone()).into(),
                     rtxn,
                     index,
                     field_ids_map,
                     filterable_attribute_rules,
-                    universe,
-                )?;
-                match universe {
-                    Some(universe) => Ok(universe - selected),
-                    None => {
-                        let all_ids = index.documents_ids(rtxn)?;
-                        Ok(all_ids - selected)
-                    }
-                }
-            }
-            FilterCondition::In { fid, els } => {
-                let Some(field_id) = field_ids_map.id(fid.value()) else {
-                    return Ok(RoaringBitmap::new());
-                };
-                let Some((rule_index, features)) =
-                    matching_features(fid.value(), filterable_attribute_rules)
-                else {
-                    return Ok(RoaringBitmap::new());
-                };
-
-                els.iter()
-                    .map(|el| Condition::Equal(el.clone()))
-                    .map(|op| {
-                        Self::evaluate_operator(
-                            rtxn, index, field_id, universe, &op, &features, rule_index,
-                        )
-                    })
-                    .union()
-            }
-            FilterCondition::Condition { fid, op } => {
-                let Some(field_id) = field_ids_map.id(fid.value()) else {
-                    return Ok(RoaringBitmap::new());
-                };
-                let Some((rule_index, features)) =
-                    matching_features(fid.value(), filterable_attribute_rules)
-                else {
-                    return Ok(RoaringBitmap::new());
-                };
-
-                Self::evaluate_operator(rtxn, index, field_id, universe, op, &features, rule_index)
+                );
             }
-            FilterCondition::Or(subfilters) => subfilters
-                .iter()
-                .cloned()
-                .map(|f| {
-                    Self::inner_evaluate(
-                        &f.into(),
+            FilterCondition::Or(subfilters) | FilterCondition::And(subfilters) => {
+                for f in subfilters {
+                    Self::inner_validate(
+                        &(f.clone()).into(),
                         rtxn,
                         index,
                         field_ids_map,
                         filterable_attribute_rules,
-                        universe,
-                    )
-                })
-                .union(),
-            FilterCondition::And(subfilters) => {
-                let mut subfilters_iter = subfilters.iter();
-                if let Some(first_subfilter) = subfilters_iter.next() {
-                    let mut bitmap = Self::inner_evaluate(
-                        &(first_subfilter.clone()).into(),
+                    )?;
+                }
+                return Ok(());
+            }
+            FilterCondition::Difference { left, right } => {
+                for f in [left, right] {
+                    Self::inner_validate(
+                        &(f.as_ref().clone()).into(),
                         rtxn,
                         index,
                         field_ids_map,
                         filterable_attribute_rules,
-                        universe,
                     )?;
-                    for f in subfilters_iter {
-                        if bitmap.is_empty() {
-                            return Ok(bitmap);
-                        }
-                        // TODO We are doing the intersections two times,
-                        //      it could be more efficient
-                        //      Can't I just replace this `&=` by an `=`?
-                        bitmap &= Self::inner_evaluate(
-                            &(f.clone()).into(),
-                            rtxn,
-                            index,
-                            field_ids_map,
-                            filterable_attribute_rules,
-                            Some(&bitmap),
-                        )?;
-                    }
-                    Ok(bitmap)
-                } else {
-                    Ok(RoaringBitmap::new())
                 }
+                return Ok(());
             }
-            FilterCondition::GeoLowerThan { point, radius } => {
-                if index.is_geo_filtering_enabled(rtxn)? {
-                    let base_point: [f64; 2] =
-                        [point[0].parse_finite_float()?, point[1].parse_finite_float()?];
-                    if !(-90.0..=90.0).contains(&base_point[0]) {
-                        return Err(point[0].as_external_error(BadGeoError::Lat(base_point[0])))?;
-                    }
-                    if !(-180.0..=180.0).contains(&base_point[1]) {
-                        return Err(point[1].as_external_error(BadGeoError::Lng(base_point[1])))?;
-                    }
-                    let radius = radius.parse_finite_float()?;
-                    let rtree = match index.geo_rtree(rtxn)? {
-                        Some(rtree) => rtree,
-                        None => return Ok(RoaringBitmap::new()),
-                    };
+            FilterCondition::In { fid, els } | FilterCondition::ContainsAll { fid, els } => {
+                (fid, els.iter().map(|el| Condition::Equal(el.clone())).collect())
+            }
+            FilterCondition::Condition { fid, op } if embedder_presence(fid, op).is_some() => {
+                let embedder_name = embedder_presence(fid, op).unwrap_or_default();
+                if index.embedding_configs(rtxn)?.iter().any(|config| config.name == embedder_name)
+                {
+                    return Ok(());
+                }
+                return Err(fid.as_external_error(FilterError::UnknownEmbedder(embedder_name)))?;
+            }
+            FilterCondition::Condition { fid, op }
+            | FilterCondition::Length { fid, op }
+            | FilterCondition::StringLength { fid, op } => (fid, vec![op.clone()]),
+            FilterCondition::GeoLowerThan { point, .. } => {
+                return Self::validate_geo(rtxn, index, &point[0], filterable_attribute_rules);
+            }
+            FilterCondition::GeoBoundingBox { top_right_point, .. } => {
+                return Self::validate_geo(
+                    rtxn,
+                    index,
+                    &top_right_point[0],
+                    filterable_attribute_rules,
+                );
+            }
+            FilterCondition::GeoInRegion { name } => {
+                return Self::validate_geo(rtxn, index, name, filterable_attribute_rules);
+            }
+        };
 
-                    let xyz_base_point = lat_lng_to_xyz(&base_point);
+        let Some(field_id) = field_ids_map.id(fid.value()) else {
+            return Ok(());
+        };
+        let Some((rule_index, features)) = filter_features(fid.value(), filterable_attribute_rules)
+        else {
+            return Ok(());
+        };
 
-                    let result = rtree
-                        .nearest_neighbor_iter(&xyz_base_point)
-                        .take_while(|point| {
-                            distance_between_two_points(&base_point, &point.data.1)
-                                <= radius + f64::EPSILON
-                        })
-                        .map(|point| point.data.0)
-                        .collect();
+        for operator in &operators {
+            check_operator_allowed(rtxn, index, field_id, operator, &features, rule_index)?;
+        }
 
-                    Ok(result)
-                } else {
-                    Err(point[0].as_external_error(FilterError::AttributeNotFilterable {
-                        attribute: RESERVED_GEO_FIELD_NAME,
-                        filterable_patterns: filtered_matching_patterns(
-                            filterable_attribute_rules,
-                            &|features| features.is_filterable(),
-                        ),
-                    }))?
-                }
-            }
-            FilterCondition::GeoBoundingBox { top_right_point, bottom_left_point } => {
-                if index.is_geo_filtering_enabled(rtxn)? {
-                    let top_right: [f64; 2] = [
-                        top_right_point[0].parse_finite_float()?,
-                        top_right_point[1].parse_finite_float()?,
-                    ];
-                    let bottom_left: [f64; 2] = [
-                        bottom_left_point[0].parse_finite_float()?,
-                        bottom_left_point[1].parse_finite_float()?,
-                    ];
-                    if !(-90.0..=90.0).contains(&top_right[0]) {
-                        return Err(
-                            top_right_point[0].as_external_error(BadGeoError::Lat(top_right[0]))
-                        )?;
-                    }
-                    if !(-180.0..=180.0).contains(&top_right[1]) {
-                        return Err(
-                            top_right_point[1].as_external_error(BadGeoError::Lng(top_right[1]))
-                        )?;
-                    }
-                    if !(-90.0..=90.0).contains(&bottom_left[0]) {
-                        return Err(bottom_left_point[0]
-                            .as_external_error(BadGeoError::Lat(bottom_left[0])))?;
-                    }
-                    if !(-180.0..=180.0).contains(&bottom_left[1]) {
-                        return Err(bottom_left_point[1]
-                            .as_external_error(BadGeoError::Lng(bottom_left[1])))?;
-                    }
-                    if top_right[0] < bottom_left[0] {
-                        return Err(bottom_left_point[1].as_external_error(
-                            BadGeoError::BoundingBoxTopIsBelowBottom(top_right[0], bottom_left[0]),
-                        ))?;
-                    }
+        Ok(())
+    }
 
-                    // Instead of writing a custom `GeoBoundingBox` filter we're simply going to re-use the range
-                    // filter to create the following filter;
-                    // `_geo.lat {top_right[0]} TO {bottom_left[0]} AND _geo.lng {top_right[1]} TO {bottom_left[1]}`
-                    // As we can see, we need to use a bunch of tokens that don't exist in the original filter,
-                    // thus we're going to create tokens that point to a random span but contain our text.
+    /// Returns the documents having an embedding for the `embedder_name` embedder.
+    fn embedded_docids(
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        fid: &Token<'_>,
+        embedder_name: &str,
+    ) -> Result<RoaringBitmap> {
+        match index.embedded_documents_ids(rtxn, embedder_name)? {
+            Some(docids) => Ok(docids),
+            None => Err(fid.as_external_error(FilterError::UnknownEmbedder(embedder_name)))?,
+        }
+    }
 
-                    let geo_lat_token = Token::new(
-                        top_right_point[0].original_span(),
-                        Some("_geo.lat".to_string()),
-                    );
+    fn validate_geo(
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        token: &Token<'_>,
+        filterable_attribute_rules: &[FilterableAttributesRule],
+    ) -> Result<()> {
+        if index.is_geo_filtering_enabled(rtxn)? {
+            Ok(())
+        } else {
+            Err(token.as_external_error(FilterError::AttributeNotFilterable {
+                attribute: RESERVED_GEO_FIELD_NAME,
+                filterable_patterns: filtered_matching_patterns(
+                    filterable_attribute_rules,
+                    &|features| features.is_filterable(),
+                ),
+            }))?
+        }
+    }
 
-                    let condition_lat = FilterCondition::Condition {
-                        fid: geo_lat_token,
-                        op: Condition::Between {
-                            from: bottom_left_point[0].clone(),
-                            to: top_right_point[0].clone(),
-                        },
-                    };
+    /// Returns the documents of the `universe` whose number of distinct values of the field
+    /// is within the `bounds`.
+    ///
+    /// The documents where the field exists without any value, like an empty array, have
+    /// zero values while the documents without the field are never returned. The values are
+    /// counted for every candidate document, making the cost linear in their number.
+    fn length_docids(
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        field_id: FieldId,
+        bounds: (Bound<f64>, Bound<f64>),
+        universe: Option<&RoaringBitmap>,
+        should_stop: &AtomicBool,
+    ) -> Result<RoaringBitmap> {
+        let numbers = index.field_id_docid_facet_f64s.remap_types::<Bytes, DecodeIgnore>();
+        let strings = index.field_id_docid_facet_strings.remap_types::<Bytes, DecodeIgnore>();
 
-                    let selected_lat = Filter { condition: condition_lat }.inner_evaluate(
-                        rtxn,
-                        index,
-                        field_ids_map,
-                        filterable_attribute_rules,
-                        universe,
-                    )?;
+        let mut candidates = index.exists_faceted_documents_ids(rtxn, field_id)?;
+        if let Some(universe) = universe {
+            candidates &= universe;
+        }
 
-                    let geo_lng_token = Token::new(
-                        top_right_point[1].original_span(),
-                        Some("_geo.lng".to_string()),
-                    );
-                    let selected_lng = if top_right[1] < bottom_left[1] {
-                        // In this case the bounding box is wrapping around the earth (going from 180 to -180).
-                        // We need to update the lng part of the filter from;
-                        // `_geo.lng {top_right[1]} TO {bottom_left[1]}` to
-                        // `_geo.lng {bottom_left[1]} TO 180 AND _geo.lng -180 TO {top_right[1]}`
+        let mut docids = RoaringBitmap::new();
+        let mut prefix = field_id.to_be_bytes().to_vec();
+        for docid in candidates {
+            check_interrupted(should_stop)?;
+            prefix.truncate(std::mem::size_of::<FieldId>());
+            prefix.extend_from_slice(&docid.to_be_bytes());
+            // the facet values of a document are deduplicated, every entry is a distinct value
+            let count = numbers.prefix_iter(rtxn, &prefix)?.count()
+                + strings.prefix_iter(rtxn, &prefix)?.count();
+            if bounds.contains(&(count as f64)) {
+                docids.insert(docid);
+            }
+        }
 
-                        let min_lng_token = Token::new(
-                            top_right_point[1].original_span(),
-                            Some("-180.0".to_string()),
-                        );
-                        let max_lng_token = Token::new(
-                            top_right_point[1].original_span(),
-                            Some("180.0".to_string()),
-                        );
+        Ok(docids)
+    }
 
-                        let condition_left = FilterCondition::Condition {
-                            fid: geo_lng_token.clone(),
-                            op: Condition::Between {
-                                from: bottom_left_point[1].clone(),
-                                to: max_lng_token,
-                            },
-                        };
-                        let left = Filter { condition: condition_left }.inner_evaluate(
-                            rtxn,
-                            index,
-                            field_ids_map,
-                            filterable_attribute_rules,
-                            universe,
-                        )?;
+    /// Returns the documents where a number of the field compares to a number of the `other_id`
+    /// field with the `comparison` operator, one of `=`, `<`, `<=`, `>` or `>=`.
+    ///
+    /// Unlike the comparisons to a value answered by the facet databases, the numbers of both
+    /// fields are read for every document containing them, making the cost linear in the number
+    /// of such documents.
+    fn compare_fields_docids(
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        field_id: FieldId,
+        other_id: FieldId,
+        comparison: &str,
+        universe: Option<&RoaringBitmap>,
+        should_stop: &AtomicBool,
+    ) -> Result<RoaringBitmap> {
+        let compare: fn(&f64, &f64) -> bool = match comparison {
+            "<" => f64::lt,
+            "<=" => f64::le,
+            ">" => f64::gt,
+            ">=" => f64::ge,
+            _ => f64::eq,
+        };
 
-                        let condition_right = FilterCondition::Condition {
-                            fid: geo_lng_token,
-                            op: Condition::Between {
-                                from: min_lng_token,
-                                to: top_right_point[1].clone(),
-                            },
-                        };
-                        let right = Filter { condition: condition_right }.inner_evaluate(
-                            rtxn,
-                            index,
-                            field_ids_map,
-                            filterable_attribute_rules,
-                            universe,
-                        )?;
+        let mut candidates = index.exists_faceted_documents_ids(rtxn, field_id)?
+            & index.exists_faceted_documents_ids(rtxn, other_id)?;
+        if let Some(universe) = universe {
+            candidates &= universe;
+        }
 
-                        left | right
-                    } else {
-                        let condition_lng = FilterCondition::Condition {
-                            fid: geo_lng_token,
-                            op: Condition::Between {
-                                from: bottom_left_point[1].clone(),
-                                to: top_right_point[1].clone(),
-                            },
-                        };
-                        Filter { condition: condition_lng }.inner_evaluate(
-                            rtxn,
-                            index,
-                            field_ids_map,
-                            filterable_attribute_rules,
-                            universe,
-                        )?
-                    };
+        let mut docids = RoaringBitmap::new();
+        let mut values = Vec::new();
+        let mut other_values = Vec::new();
+        for docid in candidates {
+            check_interrupted(should_stop)?;
+            document_numbers(rtxn, index, field_id, docid, &mut values)?;
+            if values.is_empty() {
+                continue;
+            }
+            document_numbers(rtxn, index, other_id, docid, &mut other_values)?;
+            if values.iter().any(|value| other_values.iter().any(|other| compare(value, other))) {
+                docids.insert(docid);
+            }
+        }
 
-                    Ok(selected_lat & selected_lng)
-                } else {
-                    Err(top_right_point[0].as_external_error(
-                        FilterError::AttributeNotFilterable {
-                            attribute: RESERVED_GEO_FIELD_NAME,
-                            filterable_patterns: filtered_matching_patterns(
-                                filterable_attribute_rules,
-                                &|features| features.is_filterable(),
-                            ),
-                        },
-                    ))?
+        Ok(docids)
+    }
+
+    /// Returns the documents of the `universe` having a string value of the field whose
+    /// number of characters is within the `bounds`.
+    ///
+    /// The length is the one of the original string, before its normalization. The empty
+    /// strings are not facet values and are never returned. The strings are read for every
+    /// candidate document, making the cost linear in their number.
+    fn string_length_docids(
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        field_id: FieldId,
+        bounds: (Bound<f64>, Bound<f64>),
+        universe: Option<&RoaringBitmap>,
+        should_stop: &AtomicBool,
+    ) -> Result<RoaringBitmap> {
+        let strings = index.field_id_docid_facet_strings.remap_key_type::<Bytes>();
+
+        let mut candidates = index.exists_faceted_documents_ids(rtxn, field_id)?;
+        if let Some(universe) = universe {
+            candidates &= universe;
+        }
+
+        let mut docids = RoaringBitmap::new();
+        let mut prefix = field_id.to_be_bytes().to_vec();
+        for docid in candidates {
+            check_interrupted(should_stop)?;
+            prefix.truncate(std::mem::size_of::<FieldId>());
+            prefix.extend_from_slice(&docid.to_be_bytes());
+            for result in strings.prefix_iter(rtxn, &prefix)? {
+                let (_key, original) = result?;
+                if bounds.contains(&(original.chars().count() as f64)) {
+                    docids.insert(docid);
+                    break;
                 }
             }
         }
+
+        Ok(docids)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn evaluate_operator(
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        field_id: FieldId,
+        universe: Option<&RoaringBitmap>,
+        operator: &Condition<'a>,
+        features: &FilterableAttributesFeatures,
+        rule_index: usize,
+        cache: &EvaluationCache<'_>,
+        should_stop: &AtomicBool,
+    ) -> Result<RoaringBitmap> {
+        let numbers_db = index.facet_id_f64_docids;
+        let strings_db = index.facet_id_string_docids;
+        let locale = cache.locale()?;
+
+        // return an error if the filter is not allowed for this field
+        check_operator_allowed(rtxn, index, field_id, operator, features, rule_index)?;
+
+        // the integer fields compare their integers exactly instead of as `f64`s
+        let integer_bounds = integer_bounds(operator).filter(|_| features.is_integer());
+
+        // Make sure we always bound the ranges with the field id and the level,
+        // as the facets values are all in the same database and prefixed by the
+        // field id and the level.
+
+        let (number_bounds, str_bounds) = match operator {
+            Condition::GreaterThan(_)
+            | Condition::GreaterThanOrEqual(_)
+            | Condition::LowerThan(_)
+            | Condition::LowerThanOrEqual(_)
+            | Condition::Between { .. }
+            | Condition::Approximately { .. } => range_bounds(operator),
+            Condition::CompareFields { operator: comparison, other } => {
+                let Some((other_id, other_features, other_rule_index)) =
+                    other_field(rtxn, index, other)?
+                else {
+                    return match comparison.value() {
+                        "!=" => Ok(cache.documents_ids()?.clone()),
+                        _ => Ok(RoaringBitmap::new()),
+                    };
+                };
+                check_operator_allowed(
+                    rtxn,
+                    index,
+                    other_id,
+                    operator,
+                    &other_features,
+                    other_rule_index,
+                )?;
+                return match comparison.value() {
+                    "!=" => {
+                        let equal = Self::compare_fields_docids(
+                            rtxn,
+                            index,
+                            field_id,
+                            other_id,
+                            "=",
+                            None,
+                            should_stop,
+                        )?;
+                        Ok(cache.documents_ids()? - equal)
+                    }
+                    comparison => Self::compare_fields_docids(
+                        rtxn,
+                        index,
+                        field_id,
+                        other_id,
+                        comparison,
+                        universe,
+                        should_stop,
+                    ),
+                };
+            }
+            Condition::Null => {
+                let is_null = index.null_faceted_documents_ids(rtxn, field_id)?;
+                return Ok(is_null);
+            }
+            Condition::Empty => {
+                let is_empty = index.empty_faceted_documents_ids(rtxn, field_id)?;
+                return Ok(is_empty);
+            }
+            Condition::Exists => {
+                let exist = index.exists_faceted_documents_ids(rtxn, field_id)?;
+                return Ok(exist);
+            }
+            Condition::IsDate => {
+                let is_date = index.date_faceted_documents_ids(rtxn, field_id)?;
+                return Ok(is_date);
+            }
+            Condition::Equal(val) => {
+                let string_docids = strings_db
+                    .get(
+                        rtxn,
+                        &FacetGroupKey {
+                            field_id,
+                            level: 0,
+                            left_bound: &crate::normalize_facet_with_locale(val.value(), locale),
+                        },
+                    )?
+                    .map(|v| v.bitmap)
+                    .unwrap_or_default();
+                if let Some(bounds) = integer_bounds {
+                    let integer_docids = integer_docids(rtxn, index, field_id, bounds)?;
+                    return Ok(string_docids | integer_docids);
+                }
+                let number = val.parse_finite_float().ok();
+                let number_docids = match number {
+                    Some(n) => numbers_db
+                        .get(rtxn, &FacetGroupKey { field_id, level: 0, left_bound: n })?
+                        .map(|v| v.bitmap)
+                        .unwrap_or_default(),
+                    None => RoaringBitmap::new(),
+                };
+                return Ok(string_docids | number_docids);
+            }
+            Condition::IsTrue | Condition::IsFalse => {
+                let value = matches!(operator, Condition::IsTrue);
+                let is_boolean = index.boolean_faceted_documents_ids(rtxn, field_id, value)?;
+                return Ok(is_boolean);
+            }
+            Condition::NotEqual(val) => {
+                let operator = Condition::Equal(val.clone());
+                let docids = Self::evaluate_operator(
+                    rtxn,
+                    index,
+                    field_id,
+                    None,
+                    &operator,
+                    features,
+                    rule_index,
+                    cache,
+                    should_stop,
+                )?;
+                return Ok(cache.documents_ids()? - docids);
+            }
+            Condition::Contains { keyword: _, word } => {
+                let value = crate::normalize_facet_with_locale(word.value(), locale);
+                let finder = Finder::new(value.as_bytes());
+                let mut scan_limit = ScanLimit::new(rtxn, index, field_id, word)?;
+
+                // The facet values are scanned even when the field is searchable: the words of
+                // the field can't narrow down the candidates as the tokenizer segments, drops
+                // or localizes parts of the values, the words would miss matching values.
+                let base = FacetGroupKey { field_id, level: 0, left_bound: "" };
+                let docids = strings_db
+                    .prefix_iter(rtxn, &base)?
+                    .remap_data_type::<LazyDecode<FacetGroupValueCodec>>()
+                    .filter_map(|result| -> Option<Result<RoaringBitmap>> {
+                        if let Err(error) =
+                            check_interrupted(should_stop).and_then(|()| scan_limit.scan())
+                        {
+                            return Some(Err(error));
+                        }
+                        match result {
+                            Ok((FacetGroupKey { left_bound, .. }, lazy_group_value)) => {
+                                if finder.find(left_bound.as_bytes()).is_some() {
+                                    Some(lazy_group_value.decode().map(|gv| gv.bitmap).map_err(
+                                        |_| {
+                                            InternalError::from(SerializationError::Decoding {
+                                                db_name: Some(FACET_ID_STRING_DOCIDS),
+                                            })
+                                            .into()
+                                        },
+                                    ))
+                                } else {
+                                    None
+                                }
+                            }
+                            Err(_e) => {
+                                Some(Err(InternalError::from(SerializationError::Decoding {
+                                    db_name: Some(FACET_ID_STRING_DOCIDS),
+                                })
+                                .into()))
+                            }
+                        }
+                    })
+                    .union()?;
+
+                return Ok(docids);
+            }
+            Condition::StartsWith { keyword: _, word } => {
+                let value = crate::normalize_facet_with_locale(word.value(), locale);
+                let base = FacetGroupKey { field_id, level: 0, left_bound: value.as_ref() };
+                let mut scan_limit = ScanLimit::new(rtxn, index, field_id, word)?;
+                let docids = strings_db
+                    .prefix_iter(rtxn, &base)?
+                    .map(|result| -> Result<RoaringBitmap> {
+                        check_interrupted(should_stop)?;
+                        scan_limit.scan()?;
+                        match result {
+                            Ok((_facet_group_key, FacetGroupValue { bitmap, .. })) => Ok(bitmap),
+                            Err(_e) => Err(InternalError::from(SerializationError::Decoding {
+                                db_name: Some(FACET_ID_STRING_DOCIDS),
+                            })
+                            .into()),
+                        }
+                    })
+                    .union()?;
+
+                return Ok(docids);
+            }
+            Condition::FuzzyEqual { value, max_distance } => {
+                let mut matcher = FuzzyMatcher::new(rtxn, index, value, max_distance, locale)?;
+                let mut scan_limit = ScanLimit::new(rtxn, index, field_id, value)?;
+                let base = FacetGroupKey { field_id, level: 0, left_bound: "" };
+                let mut docids = RoaringBitmap::new();
+                for result in strings_db
+                    .prefix_iter(rtxn, &base)?
+                    .remap_data_type::<FacetGroupLazyValueCodec>()
+                {
+                    check_interrupted(should_stop)?;
+                    scan_limit.scan()?;
+                    let (FacetGroupKey { left_bound, .. }, value) = result?;
+                    if matcher.matches(left_bound) {
+                        docids |= CboRoaringBitmapCodec::deserialize_from(value.bitmap_bytes)?;
+                    }
+                }
+
+                return Ok(docids);
+            }
+            Condition::Regex { pattern } => {
+                let regex = cache.regex(pattern)?;
+                let mut scan_limit = ScanLimit::new(rtxn, index, field_id, pattern)?;
+                let base = FacetGroupKey { field_id, level: 0, left_bound: "" };
+                let mut docids = RoaringBitmap::new();
+                for result in strings_db
+                    .prefix_iter(rtxn, &base)?
+                    .remap_data_type::<FacetGroupLazyValueCodec>()
+                {
+                    check_interrupted(should_stop)?;
+                    scan_limit.scan()?;
+                    let (FacetGroupKey { left_bound, .. }, value) = result?;
+                    if regex.is_match(left_bound) {
+                        docids |= CboRoaringBitmapCodec::deserialize_from(value.bitmap_bytes)?;
+                    }
+                }
+
+                return Ok(docids);
+            }
+            Condition::Modulo { divisor, remainder } => {
+                let (divisor_value, remainder_value) = modulo_operands(divisor, remainder)?;
+                check_numeric_field(rtxn, index, field_id, divisor)?;
+                let mut scan_limit = ScanLimit::new(rtxn, index, field_id, divisor)?;
+                let mut docids = RoaringBitmap::new();
+                for_each_number_value(rtxn, index, field_id, |number, value| {
+                    check_interrupted(should_stop)?;
+                    scan_limit.scan()?;
+                    if modulo_matches(number, divisor_value, remainder_value) {
+                        docids |= CboRoaringBitmapCodec::deserialize_from(value.bitmap_bytes)?;
+                    }
+                    Ok(())
+                })?;
+
+                return Ok(docids);
+            }
+            Condition::StartsWithAny { keyword, words } => {
+                let prefixes = minimal_sorted_prefixes(words, locale);
+                let mut scan_limit = ScanLimit::new(rtxn, index, field_id, keyword)?;
+                let mut docids = RoaringBitmap::new();
+                for_each_prefixed_value(rtxn, index, field_id, &prefixes, |value| {
+                    check_interrupted(should_stop)?;
+                    scan_limit.scan()?;
+                    docids |= CboRoaringBitmapCodec::deserialize_from(value.bitmap_bytes)?;
+                    Ok(())
+                })?;
+
+                return Ok(docids);
+            }
+        };
+
+        let mut output = RoaringBitmap::new();
+
+        // the documents missing the field are considered to have the `missing_as` value
+        if let Some(missing_as) = features.missing_as() {
+            if number_bounds.is_some_and(|bounds| bounds.contains(&missing_as)) {
+                let mut missing =
+                    cache.documents_ids()? - index.exists_faceted_documents_ids(rtxn, field_id)?;
+                if let Some(universe) = universe {
+                    missing &= universe;
+                }
+                output |= missing;
+            }
+        }
+
+        if let Some(bounds) = integer_bounds {
+            let mut docids = integer_docids(rtxn, index, field_id, bounds)?;
+            if let Some(universe) = universe {
+                docids &= universe;
+            }
+            output |= docids;
+        } else if let Some((left_number, right_number)) = number_bounds {
+            Self::explore_facet_levels(
+                rtxn,
+                numbers_db,
+                field_id,
+                &left_number,
+                &right_number,
+                universe,
+                &mut output,
+            )?;
+        }
+
+        // the numeric only fields never fall back to comparing their strings
+        if let Some((left_str, right_str)) = str_bounds.filter(|_| !features.is_numeric_only()) {
+            Self::explore_facet_levels(
+                rtxn,
+                strings_db,
+                field_id,
+                &left_str,
+                &right_str,
+                universe,
+                &mut output,
+            )?;
+        }
+
+        Ok(output)
+    }
+
+    /// Aggregates the documents ids that are part of the specified range automatically
+    /// going deeper through the levels.
+    fn explore_facet_levels<'data, BoundCodec>(
+        rtxn: &'data heed::RoTxn<'data>,
+        db: heed::Database<FacetGroupKeyCodec<BoundCodec>, FacetGroupValueCodec>,
+        field_id: FieldId,
+        left: &'data Bound<<BoundCodec as heed::BytesEncode<'data>>::EItem>,
+        right: &'data Bound<<BoundCodec as heed::BytesEncode<'data>>::EItem>,
+        universe: Option<&RoaringBitmap>,
+        output: &mut RoaringBitmap,
+    ) -> Result<()>
+    where
+        BoundCodec: for<'b> BytesEncode<'b>,
+        for<'b> <BoundCodec as BytesEncode<'b>>::EItem: Sized + PartialOrd,
+    {
+        match (left, right) {
+            // lower TO upper when lower > upper must return no result
+            (Included(l), Included(r)) if l > r => return Ok(()),
+            (Included(l), Excluded(r)) if l >= r => return Ok(()),
+            (Excluded(l), Excluded(r)) if l >= r => return Ok(()),
+            (Excluded(l), Included(r)) if l >= r => return Ok(()),
+            (_, _) => (),
+        }
+        facet_range_search::find_docids_of_facet_within_bounds::<BoundCodec>(
+            rtxn, db, field_id, left, right, universe, output,
+        )?;
+
+        Ok(())
+    }
+
+    fn count_facet_levels<'data, BoundCodec>(
+        rtxn: &'data heed::RoTxn<'data>,
+        db: heed::Database<FacetGroupKeyCodec<BoundCodec>, FacetGroupValueCodec>,
+        field_id: FieldId,
+        left: &'data Bound<<BoundCodec as heed::BytesEncode<'data>>::EItem>,
+        right: &'data Bound<<BoundCodec as heed::BytesEncode<'data>>::EItem>,
+    ) -> Result<u64>
+    where
+        BoundCodec: for<'b> BytesEncode<'b>,
+        for<'b> <BoundCodec as BytesEncode<'b>>::EItem: Sized + PartialOrd,
+    {
+        match (left, right) {
+            (Included(l), Included(r)) if l > r => return Ok(0),
+            (Included(l), Excluded(r)) if l >= r => return Ok(0),
+            (Excluded(l), Excluded(r)) if l >= r => return Ok(0),
+            (Excluded(l), Included(r)) if l >= r => return Ok(0),
+            (_, _) => (),
+        }
+        facet_range_search::count_docids_of_facet_within_bounds::<BoundCodec>(
+            rtxn, db, field_id, left, right,
+        )
+    }
+
+    fn inner_estimate(
+        condition: &FilterCondition<'_>,
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        field_ids_map: &FieldsIdsMap,
+        filterable_attribute_rules: &[FilterableAttributesRule],
+        cache: &EvaluationCache<'_>,
+        documents: f64,
+    ) -> Result<f64> {
+        let estimate = |condition: &FilterCondition<'_>| {
+            Self::inner_estimate(
+                condition,
+                rtxn,
+                index,
+                field_ids_map,
+                filterable_attribute_rules,
+                cache,
+                documents,
+            )
+        };
+        let fraction = |estimate: f64| if documents == 0.0 { 0.0 } else { estimate / documents };
+        let estimate_operators = |fid: &Token, operators: &mut dyn Iterator<Item = Condition>| {
+            let Some(field_id) = field_ids_map.id(fid.value()) else {
+                return Ok(Vec::new());
+            };
+            let Some((rule_index, features)) =
+                filter_features(fid.value(), filterable_attribute_rules)
+            else {
+                return Ok(Vec::new());
+            };
+            operators
+                .map(|operator| {
+                    check_operator_allowed(
+                        rtxn, index, field_id, &operator, &features, rule_index,
+                    )?;
+                    Self::estimate_operator(
+                        rtxn, index, field_id, &operator, &features, cache, documents,
+                    )
+                })
+                .collect::<Result<Vec<f64>>>()
+        };
+
+        let estimate = match condition {
+            FilterCondition::Not(condition) => documents - estimate(condition)?,
+            FilterCondition::Or(conditions) => {
+                conditions.iter().map(estimate).sum::<Result<f64>>()?
+            }
+            FilterCondition::And(conditions) => {
+                conditions.iter().try_fold(documents, |acc, condition| {
+                    Ok::<_, Error>(acc * fraction(estimate(condition)?))
+                })?
+            }
+            FilterCondition::Difference { left, right } => {
+                estimate(left)? * (1.0 - fraction(estimate(right)?))
+            }
+            FilterCondition::Condition { fid, op } => match embedder_presence(fid, op) {
+                Some(embedder_name) => {
+                    Self::embedded_docids(rtxn, index, fid, embedder_name)?.len() as f64
+                }
+                None => {
+                    estimate_operators(fid, &mut std::iter::once(op.clone()))?.into_iter().sum()
+                }
+            },
+            FilterCondition::In { fid, els } => {
+                let locale = cache.locale()?;
+                let mut operators = distinct_equalities(els, locale).into_iter();
+                estimate_operators(fid, &mut operators)?.into_iter().sum()
+            }
+            FilterCondition::ContainsAll { fid, els } => {
+                let mut operators = els.iter().map(|el| Condition::Equal(el.clone()));
+                let estimates = estimate_operators(fid, &mut operators)?;
+                if estimates.is_empty() {
+                    0.0
+                } else {
+                    estimates.into_iter().fold(documents, |acc, estimate| acc * fraction(estimate))
+                }
+            }
+            FilterCondition::Length { fid, op } => {
+                let Some(field_id) = field_ids_map.id(fid.value()) else {
+                    return Ok(0.0);
+                };
+                let Some((rule_index, features)) =
+                    filter_features(fid.value(), filterable_attribute_rules)
+                else {
+                    return Ok(0.0);
+                };
+                check_operator_allowed(rtxn, index, field_id, op, &features, rule_index)?;
+                let bounds =
+                    length_bounds(fid, op, |op| FilterError::LengthOperatorNotSupported(op))?;
+                let matching = Self::length_docids(
+                    rtxn,
+                    index,
+                    field_id,
+                    bounds,
+                    None,
+                    &AtomicBool::new(false),
+                )?
+                .len() as f64;
+                match op {
+                    Condition::NotEqual(_) => documents - matching,
+                    _ => matching,
+                }
+            }
+            FilterCondition::StringLength { fid, op } => {
+                let Some(field_id) = field_ids_map.id(fid.value()) else {
+                    return Ok(0.0);
+                };
+                let Some(bounds) = string_length_bounds(fid, op, filterable_attribute_rules)?
+                else {
+                    return Ok(0.0);
+                };
+                let matching = Self::string_length_docids(
+                    rtxn,
+                    index,
+                    field_id,
+                    bounds,
+                    None,
+                    &AtomicBool::new(false),
+                )?
+                .len() as f64;
+                match op {
+                    Condition::NotEqual(_) => documents - matching,
+                    _ => matching,
+                }
+            }
+            FilterCondition::GeoLowerThan { .. }
+            | FilterCondition::GeoBoundingBox { .. }
+            | FilterCondition::GeoInRegion { .. } => {
+                index.geo_faceted_documents_ids(rtxn)?.len() as f64
+            }
+        };
+
+        Ok(estimate.clamp(0.0, documents))
+    }
+
+    fn estimate_operator(
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        field_id: FieldId,
+        operator: &Condition<'_>,
+        features: &FilterableAttributesFeatures,
+        cache: &EvaluationCache<'_>,
+        documents: f64,
+    ) -> Result<f64> {
+        let numbers_db = index.facet_id_f64_docids;
+        let strings_db = index.facet_id_string_docids;
+        let locale = cache.locale()?;
+        let integer_bounds = integer_bounds(operator).filter(|_| features.is_integer());
+
+        let (number_bounds, str_bounds) = match operator {
+            Condition::GreaterThan(_)
+            | Condition::GreaterThanOrEqual(_)
+            | Condition::LowerThan(_)
+            | Condition::LowerThanOrEqual(_)
+            | Condition::Between { .. }
+            | Condition::Approximately { .. } => range_bounds(operator),
+            // bounded by the documents containing both fields
+            Condition::CompareFields { operator: comparison, other } => {
+                if comparison.value() == "!=" {
+                    return Ok(documents);
+                }
+                let Some((other_id, _, _)) = other_field(rtxn, index, other)? else {
+                    return Ok(0.0);
+                };
+                let both = index.exists_faceted_documents_ids(rtxn, field_id)?
+                    & index.exists_faceted_documents_ids(rtxn, other_id)?;
+                return Ok(both.len() as f64);
+            }
+            Condition::Null => {
+                return Ok(index.null_faceted_documents_ids(rtxn, field_id)?.len() as f64)
+            }
+            Condition::Empty => {
+                return Ok(index.empty_faceted_documents_ids(rtxn, field_id)?.len() as f64)
+            }
+            Condition::Exists => {
+                return Ok(index.exists_faceted_documents_ids(rtxn, field_id)?.len() as f64)
+            }
+            Condition::IsDate => {
+                return Ok(index.date_faceted_documents_ids(rtxn, field_id)?.len() as f64)
+            }
+            Condition::Equal(val) => {
+                let value = crate::normalize_facet_with_locale(val.value(), locale);
+                let key = FacetGroupKey { field_id, level: 0, left_bound: value.as_ref() };
+                let mut count = facet_value_len(
+                    strings_db.remap_data_type::<FacetGroupLazyValueCodec>().get(rtxn, &key)?,
+                )?;
+                if let Some(bounds) = integer_bounds {
+                    count += count_integer_docids(rtxn, index, field_id, bounds)?;
+                } else if let Ok(number) = val.parse_finite_float() {
+                    let key = FacetGroupKey { field_id, level: 0, left_bound: number };
+                    count += facet_value_len(
+                        numbers_db.remap_data_type::<FacetGroupLazyValueCodec>().get(rtxn, &key)?,
+                    )?;
+                }
+                return Ok(count as f64);
+            }
+            Condition::IsTrue | Condition::IsFalse => {
+                let value = matches!(operator, Condition::IsTrue);
+                return Ok(index.boolean_faceted_documents_ids(rtxn, field_id, value)?.len() as f64);
+            }
+            Condition::NotEqual(val) => {
+                let operator = Condition::Equal(val.clone());
+                let equal = Self::estimate_operator(
+                    rtxn, index, field_id, &operator, features, cache, documents,
+                )?;
+                return Ok(documents - equal);
+            }
+            Condition::Contains { keyword: _, word } => {
+                let value = crate::normalize_facet_with_locale(word.value(), locale);
+                let finder = Finder::new(value.as_bytes());
+                let base = FacetGroupKey { field_id, level: 0, left_bound: "" };
+                let mut count = 0;
+                for result in strings_db
+                    .prefix_iter(rtxn, &base)?
+                    .remap_data_type::<FacetGroupLazyValueCodec>()
+                {
+                    let (FacetGroupKey { left_bound, .. }, value) = result?;
+                    if finder.find(left_bound.as_bytes()).is_some() {
+                        count += facet_value_len(Some(value))?;
+                    }
+                }
+                return Ok(count as f64);
+            }
+            Condition::StartsWith { keyword: _, word } => {
+                let value = crate::normalize_facet_with_locale(word.value(), locale);
+                let base = FacetGroupKey { field_id, level: 0, left_bound: value.as_ref() };
+                let mut count = 0;
+                for result in strings_db
+                    .prefix_iter(rtxn, &base)?
+                    .remap_data_type::<FacetGroupLazyValueCodec>()
+                {
+                    let (_key, value) = result?;
+                    count += facet_value_len(Some(value))?;
+                }
+                return Ok(count as f64);
+            }
+            Condition::FuzzyEqual { value, max_distance } => {
+                let mut matcher = FuzzyMatcher::new(rtxn, index, value, max_distance, locale)?;
+                let base = FacetGroupKey { field_id, level: 0, left_bound: "" };
+                let mut count = 0;
+                for result in strings_db
+                    .prefix_iter(rtxn, &base)?
+                    .remap_data_type::<FacetGroupLazyValueCodec>()
+                {
+                    let (FacetGroupKey { left_bound, .. }, value) = result?;
+                    if matcher.matches(left_bound) {
+                        count += facet_value_len(Some(value))?;
+                    }
+                }
+                return Ok(count as f64);
+            }
+            Condition::Regex { pattern } => {
+                let regex = cache.regex(pattern)?;
+                let base = FacetGroupKey { field_id, level: 0, left_bound: "" };
+                let mut count = 0;
+                for result in strings_db
+                    .prefix_iter(rtxn, &base)?
+                    .remap_data_type::<FacetGroupLazyValueCodec>()
+                {
+                    let (FacetGroupKey { left_bound, .. }, value) = result?;
+                    if regex.is_match(left_bound) {
+                        count += facet_value_len(Some(value))?;
+                    }
+                }
+                return Ok(count as f64);
+            }
+            Condition::Modulo { divisor, remainder } => {
+                let (divisor, remainder) = modulo_operands(divisor, remainder)?;
+                let mut count = 0;
+                for_each_number_value(rtxn, index, field_id, |number, value| {
+                    if modulo_matches(number, divisor, remainder) {
+                        count += facet_value_len(Some(value))?;
+                    }
+                    Ok(())
+                })?;
+                return Ok(count as f64);
+            }
+            Condition::StartsWithAny { keyword: _, words } => {
+                let prefixes = minimal_sorted_prefixes(words, locale);
+                let mut count = 0;
+                for_each_prefixed_value(rtxn, index, field_id, &prefixes, |value| {
+                    count += facet_value_len(Some(value))?;
+                    Ok(())
+                })?;
+                return Ok(count as f64);
+            }
+        };
+
+        let mut count = 0;
+
+        // the documents missing the field are considered to have the `missing_as` value
+        if let Some(missing_as) = features.missing_as() {
+            if number_bounds.is_some_and(|bounds| bounds.contains(&missing_as)) {
+                count += index.number_of_documents(rtxn)?
+                    - index.exists_faceted_documents_ids(rtxn, field_id)?.len();
+            }
+        }
+
+        if let Some(bounds) = integer_bounds {
+            count += count_integer_docids(rtxn, index, field_id, bounds)?;
+        } else if let Some((left_number, right_number)) = number_bounds {
+            count +=
+                Self::count_facet_levels(rtxn, numbers_db, field_id, &left_number, &right_number)?;
+        }
+        if let Some((left_str, right_str)) = str_bounds.filter(|_| !features.is_numeric_only()) {
+            count += Self::count_facet_levels(rtxn, strings_db, field_id, &left_str, &right_str)?;
+        }
+
+        Ok(count as f64)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn inner_evaluate(
+        &self,
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        field_ids_map: &FieldsIdsMap,
+        filterable_attribute_rules: &[FilterableAttributesRule],
+        universe: Option<&RoaringBitmap>,
+        cache: &EvaluationCache<'_>,
+        should_stop: &AtomicBool,
+    ) -> Result<RoaringBitmap> {
+        check_interrupted(should_stop)?;
+        if universe.is_some_and(|u| u.is_empty()) {
+            return Ok(RoaringBitmap::new());
+        }
+
+        match &self.condition {
+            FilterCondition::Not(f) => {
+                let selected = Self::inner_evaluate(
+                    &(f.as_ref().clone()).into(),
+                    rtxn,
+                    index,
+                    field_ids_map,
+                    filterable_attribute_rules,
+                    universe,
+                    cache,
+                    should_stop,
+                )?;
+                let complement = match universe {
+                    Some(universe) => universe - selected,
+                    None => cache.documents_ids()? - selected,
+                };
+                if negated_within_geo(f) {
+                    Ok(complement & index.geo_faceted_documents_ids(rtxn)?)
+                } else {
+                    Ok(complement)
+                }
+            }
+            FilterCondition::In { fid, els } => {
+                let Some(field_id) = field_ids_map.id(fid.value()) else {
+                    return Ok(RoaringBitmap::new());
+                };
+                let Some((rule_index, features)) =
+                    filter_features(fid.value(), filterable_attribute_rules)
+                else {
+                    return Ok(RoaringBitmap::new());
+                };
+
+                let locale = cache.locale()?;
+                distinct_equalities(els, locale)
+                    .into_iter()
+                    .map(|op| {
+                        check_interrupted(should_stop)?;
+                        Self::evaluate_operator(
+                            rtxn,
+                            index,
+                            field_id,
+                            universe,
+                            &op,
+                            &features,
+                            rule_index,
+                            cache,
+                            should_stop,
+                        )
+                    })
+                    .union()
+            }
+            FilterCondition::ContainsAll { fid, els } => {
+                let Some(field_id) = field_ids_map.id(fid.value()) else {
+                    return Ok(RoaringBitmap::new());
+                };
+                let Some((rule_index, features)) =
+                    filter_features(fid.value(), filterable_attribute_rules)
+                else {
+                    return Ok(RoaringBitmap::new());
+                };
+
+                let mut selected: Option<RoaringBitmap> = None;
+                for el in els {
+                    check_interrupted(should_stop)?;
+                    let docids = Self::evaluate_operator(
+                        rtxn,
+                        index,
+                        field_id,
+                        universe,
+                        &Condition::Equal(el.clone()),
+                        &features,
+                        rule_index,
+                        cache,
+                        should_stop,
+                    )?;
+                    let docids = match selected {
+                        Some(selected) => selected & docids,
+                        None => docids,
+                    };
+                    // no document can contain the remaining values
+                    if docids.is_empty() {
+                        return Ok(docids);
+                    }
+                    selected = Some(docids);
+                }
+                Ok(selected.unwrap_or_default())
+            }
+            FilterCondition::Length { fid, op } => {
+                let Some(field_id) = field_ids_map.id(fid.value()) else {
+                    return Ok(RoaringBitmap::new());
+                };
+                let Some((rule_index, features)) =
+                    filter_features(fid.value(), filterable_attribute_rules)
+                else {
+                    return Ok(RoaringBitmap::new());
+                };
+
+                check_operator_allowed(rtxn, index, field_id, op, &features, rule_index)?;
+                let bounds =
+                    length_bounds(fid, op, |op| FilterError::LengthOperatorNotSupported(op))?;
+                let docids =
+                    Self::length_docids(rtxn, index, field_id, bounds, universe, should_stop)?;
+                match (op, universe) {
+                    (Condition::NotEqual(_), Some(universe)) => Ok(universe - docids),
+                    (Condition::NotEqual(_), None) => Ok(cache.documents_ids()? - docids),
+                    _ => Ok(docids),
+                }
+            }
+            FilterCondition::StringLength { fid, op } => {
+                let Some(field_id) = field_ids_map.id(fid.value()) else {
+                    return Ok(RoaringBitmap::new());
+                };
+                let Some(bounds) = string_length_bounds(fid, op, filterable_attribute_rules)?
+                else {
+                    return Ok(RoaringBitmap::new());
+                };
+
+                let docids = Self::string_length_docids(
+                    rtxn,
+                    index,
+                    field_id,
+                    bounds,
+                    universe,
+                    should_stop,
+                )?;
+                match (op, universe) {
+                    (Condition::NotEqual(_), Some(universe)) => Ok(universe - docids),
+                    (Condition::NotEqual(_), None) => Ok(cache.documents_ids()? - docids),
+                    _ => Ok(docids),
+                }
+            }
+            FilterCondition::Condition { fid, op } => {
+                if let Some(embedder_name) = embedder_presence(fid, op) {
+                    let docids = Self::embedded_docids(rtxn, index, fid, embedder_name)?;
+                    return match universe {
+                        Some(universe) => Ok(docids & universe),
+                        None => Ok(docids),
+                    };
+                }
+                let Some(field_id) = field_ids_map.id(fid.value()) else {
+                    return Ok(RoaringBitmap::new());
+                };
+                let Some((rule_index, features)) =
+                    filter_features(fid.value(), filterable_attribute_rules)
+                else {
+                    return Ok(RoaringBitmap::new());
+                };
+
+                Self::evaluate_operator(
+                    rtxn,
+                    index,
+                    field_id,
+                    universe,
+                    op,
+                    &features,
+                    rule_index,
+                    cache,
+                    should_stop,
+                )
+            }
+            FilterCondition::Or(subfilters) => subfilters
+                .iter()
+                .cloned()
+                .map(|f| {
+                    Self::inner_evaluate(
+                        &f.into(),
+                        rtxn,
+                        index,
+                        field_ids_map,
+                        filterable_attribute_rules,
+                        universe,
+                        cache,
+                        should_stop,
+                    )
+                })
+                .union(),
+            FilterCondition::And(subfilters) => {
+                let mut subfilters_iter = subfilters.iter();
+                if let Some(first_subfilter) = subfilters_iter.next() {
+                    let mut bitmap = Self::inner_evaluate(
+                        &(first_subfilter.clone()).into(),
+                        rtxn,
+                        index,
+                        field_ids_map,
+                        filterable_attribute_rules,
+                        universe,
+                        cache,
+                        should_stop,
+                    )?;
+                    for f in subfilters_iter {
+                        if bitmap.is_empty() {
+                            return Ok(bitmap);
+                        }
+                        // TODO We are doing the intersections two times,
+                        //      it could be more efficient
+                        //      Can't I just replace this `&=` by an `=`?
+                        bitmap &= Self::inner_evaluate(
+                            &(f.clone()).into(),
+                            rtxn,
+                            index,
+                            field_ids_map,
+                            filterable_attribute_rules,
+                            Some(&bitmap),
+                            cache,
+                            should_stop,
+                        )?;
+                    }
+                    Ok(bitmap)
+                } else {
+                    Ok(RoaringBitmap::new())
+                }
+            }
+            FilterCondition::Difference { left, right } => {
+                let left = Self::inner_evaluate(
+                    &(left.as_ref().clone()).into(),
+                    rtxn,
+                    index,
+                    field_ids_map,
+                    filterable_attribute_rules,
+                    universe,
+                    cache,
+                    should_stop,
+                )?;
+                // the right side only has to be evaluated on the documents of the left side
+                let selected = Self::inner_evaluate(
+                    &(right.as_ref().clone()).into(),
+                    rtxn,
+                    index,
+                    field_ids_map,
+                    filterable_attribute_rules,
+                    Some(&left),
+                    cache,
+                    should_stop,
+                )?;
+                if negated_within_geo(right) {
+                    Ok((left & index.geo_faceted_documents_ids(rtxn)?) - selected)
+                } else {
+                    Ok(left - selected)
+                }
+            }
+            FilterCondition::GeoLowerThan { point, radius, kind: GeoRadiusKind::Outside } => {
+                // the documents without coordinates are neither within nor outside of the circle
+                let within = FilterCondition::GeoLowerThan {
+                    point: point.clone(),
+                    radius: radius.clone(),
+                    kind: GeoRadiusKind::Inclusive,
+                };
+                let within = Filter { condition: within }.inner_evaluate(
+                    rtxn,
+                    index,
+                    field_ids_map,
+                    filterable_attribute_rules,
+                    universe,
+                    cache,
+                    should_stop,
+                )?;
+                let outside = index.geo_faceted_documents_ids(rtxn)? - within;
+                match universe {
+                    Some(universe) => Ok(outside & universe),
+                    None => Ok(outside),
+                }
+            }
+            FilterCondition::GeoLowerThan { point, radius, kind } => {
+                if index.is_geo_filtering_enabled(rtxn)? {
+                    let base_point: [f64; 2] =
+                        [point[0].parse_finite_float()?, point[1].parse_finite_float()?];
+                    if !(-90.0..=90.0).contains(&base_point[0]) {
+                        return Err(point[0].as_external_error(BadGeoError::Lat(base_point[0])))?;
+                    }
+                    if !(-180.0..=180.0).contains(&base_point[1]) {
+                        return Err(point[1].as_external_error(BadGeoError::Lng(base_point[1])))?;
+                    }
+                    let radius = radius.parse_finite_float()?;
+                    let rtree = match index.geo_rtree(rtxn)? {
+                        Some(rtree) => rtree,
+                        None => return Ok(RoaringBitmap::new()),
+                    };
+
+                    let xyz_base_point = lat_lng_to_xyz(&base_point);
+
+                    if *kind == GeoRadiusKind::Approximate {
+                        // The points within the radius are all closer than the chord of the
+                        // radius angle to the base point on the unit sphere. The box of this chord
+                        // contains the whole circle but also the points near its corners.
+                        let angle = (radius / EARTH_RADIUS_METERS).min(std::f64::consts::PI);
+                        let chord = 2.0 * (angle / 2.0).sin();
+                        let envelope = AABB::from_corners(
+                            xyz_base_point.map(|coord| coord - chord),
+                            xyz_base_point.map(|coord| coord + chord),
+                        );
+                        return Ok(rtree
+                            .locate_in_envelope(&envelope)
+                            .map(|point| point.data.0)
+                            .collect());
+                    }
+
+                    let result = rtree
+                        .nearest_neighbor_iter(&xyz_base_point)
+                        .take_while(|point| {
+                            let distance = distance_between_two_points(&base_point, &point.data.1);
+                            if *kind == GeoRadiusKind::Inclusive {
+                                distance <= radius + f64::EPSILON
+                            } else {
+                                distance < radius
+                            }
+                        })
+                        .map(|point| point.data.0)
+                        .collect();
+
+                    Ok(result)
+                } else {
+                    Err(point[0].as_external_error(FilterError::AttributeNotFilterable {
+                        attribute: RESERVED_GEO_FIELD_NAME,
+                        filterable_patterns: filtered_matching_patterns(
+                            filterable_attribute_rules,
+                            &|features| features.is_filterable(),
+                        ),
+                    }))?
+                }
+            }
+            FilterCondition::GeoBoundingBox { top_right_point, bottom_left_point } => {
+                if index.is_geo_filtering_enabled(rtxn)? {
+                    let top_right: [f64; 2] = [
+                        top_right_point[0].parse_finite_float()?,
+                        top_right_point[1].parse_finite_float()?,
+                    ];
+                    let bottom_left: [f64; 2] = [
+                        bottom_left_point[0].parse_finite_float()?,
+                        bottom_left_point[1].parse_finite_float()?,
+                    ];
+                    if !(-90.0..=90.0).contains(&top_right[0]) {
+                        return Err(
+                            top_right_point[0].as_external_error(BadGeoError::Lat(top_right[0]))
+                        )?;
+                    }
+                    if !(-180.0..=180.0).contains(&top_right[1]) {
+                        return Err(
+                            top_right_point[1].as_external_error(BadGeoError::Lng(top_right[1]))
+                        )?;
+                    }
+                    if !(-90.0..=90.0).contains(&bottom_left[0]) {
+                        return Err(bottom_left_point[0]
+                            .as_external_error(BadGeoError::Lat(bottom_left[0])))?;
+                    }
+                    if !(-180.0..=180.0).contains(&bottom_left[1]) {
+                        return Err(bottom_left_point[1]
+                            .as_external_error(BadGeoError::Lng(bottom_left[1])))?;
+                    }
+                    if top_right[0] < bottom_left[0] {
+                        return Err(bottom_left_point[1].as_external_error(
+                            BadGeoError::BoundingBoxTopIsBelowBottom(top_right[0], bottom_left[0]),
+                        ))?;
+                    }
+
+                    // Instead of writing a custom `GeoBoundingBox` filter we're simply going to re-use the range
+                    // filter to create the following filter;
+                    // `_geo.lat {top_right[0]} TO {bottom_left[0]} AND _geo.lng {top_right[1]} TO {bottom_left[1]}`
+                    // As we can see, we need to use a bunch of tokens that don't exist in the original filter,
+                    // thus we're going to create tokens that point to a random span but contain our text.
+
+                    let geo_lat_token = Token::new(
+                        top_right_point[0].original_span(),
+                        Some(RESERVED_GEO_LAT_FIELD_NAME.to_string()),
+                    );
+
+                    let condition_lat = FilterCondition::Condition {
+                        fid: geo_lat_token,
+                        op: Condition::Between {
+                            from: bottom_left_point[0].clone(),
+                            to: top_right_point[0].clone(),
+                        },
+                    };
+
+                    let selected_lat = Filter { condition: condition_lat }.inner_evaluate(
+                        rtxn,
+                        index,
+                        field_ids_map,
+                        filterable_attribute_rules,
+                        universe,
+                        cache,
+                        should_stop,
+                    )?;
+
+                    let geo_lng_token = Token::new(
+                        top_right_point[1].original_span(),
+                        Some(RESERVED_GEO_LNG_FIELD_NAME.to_string()),
+                    );
+                    let selected_lng = if top_right[1] < bottom_left[1] {
+                        // In this case the bounding box is wrapping around the earth (going from 180 to -180).
+                        // We need to update the lng part of the filter from;
+                        // `_geo.lng {top_right[1]} TO {bottom_left[1]}` to
+                        // `_geo.lng {bottom_left[1]} TO 180 AND _geo.lng -180 TO {top_right[1]}`
+
+                        let min_lng_token = Token::new(
+                            top_right_point[1].original_span(),
+                            Some("-180.0".to_string()),
+                        );
+                        let max_lng_token = Token::new(
+                            top_right_point[1].original_span(),
+                            Some("180.0".to_string()),
+                        );
+
+                        let condition_left = FilterCondition::Condition {
+                            fid: geo_lng_token.clone(),
+                            op: Condition::Between {
+                                from: bottom_left_point[1].clone(),
+                                to: max_lng_token,
+                            },
+                        };
+                        let left = Filter { condition: condition_left }.inner_evaluate(
+                            rtxn,
+                            index,
+                            field_ids_map,
+                            filterable_attribute_rules,
+                            universe,
+                            cache,
+                            should_stop,
+                        )?;
+
+                        let condition_right = FilterCondition::Condition {
+                            fid: geo_lng_token,
+                            op: Condition::Between {
+                                from: min_lng_token,
+                                to: top_right_point[1].clone(),
+                            },
+                        };
+                        let right = Filter { condition: condition_right }.inner_evaluate(
+                            rtxn,
+                            index,
+                            field_ids_map,
+                            filterable_attribute_rules,
+                            universe,
+                            cache,
+                            should_stop,
+                        )?;
+
+                        left | right
+                    } else {
+                        let condition_lng = FilterCondition::Condition {
+                            fid: geo_lng_token,
+                            op: Condition::Between {
+                                from: bottom_left_point[1].clone(),
+                                to: top_right_point[1].clone(),
+                            },
+                        };
+                        Filter { condition: condition_lng }.inner_evaluate(
+                            rtxn,
+                            index,
+                            field_ids_map,
+                            filterable_attribute_rules,
+                            universe,
+                            cache,
+                            should_stop,
+                        )?
+                    };
+
+                    Ok(selected_lat & selected_lng)
+                } else {
+                    Err(top_right_point[0].as_external_error(
+                        FilterError::AttributeNotFilterable {
+                            attribute: RESERVED_GEO_FIELD_NAME,
+                            filterable_patterns: filtered_matching_patterns(
+                                filterable_attribute_rules,
+                                &|features| features.is_filterable(),
+                            ),
+                        },
+                    ))?
+                }
+            }
+            FilterCondition::GeoInRegion { name } => {
+                Self::validate_geo(rtxn, index, name, filterable_attribute_rules)?;
+                let regions = index.geo_regions(rtxn)?;
+                let Some(polygon) = regions.get(name.value()) else {
+                    return Err(
+                        name.as_external_error(FilterError::UnknownGeoRegion(name.value()))
+                    )?;
+                };
+                let docids = index.geo_in_polygon(rtxn, polygon)?;
+                match universe {
+                    Some(universe) => Ok(docids & universe),
+                    None => Ok(docids),
+                }
+            }
+        }
+    }
+}
+
+/// What the evaluation or the estimation of a filter computes at most once: the documents ids
+/// and the facet normalization locale of the index, and the regexes of the `MATCHES` conditions,
+/// shared by the conditions with the same pattern.
+struct EvaluationCache<'t> {
+    rtxn: &'t heed::RoTxn<'t>,
+    index: &'t Index,
+    documents_ids: OnceCell<RoaringBitmap>,
+    locale: OnceCell<Option<Language>>,
+    regexes: RefCell<HashMap<String, Regex>>,
+}
+
+impl<'t> EvaluationCache<'t> {
+    fn new(rtxn: &'t heed::RoTxn<'t>, index: &'t Index) -> Self {
+        EvaluationCache {
+            rtxn,
+            index,
+            documents_ids: OnceCell::new(),
+            locale: OnceCell::new(),
+            regexes: RefCell::default(),
+        }
+    }
+
+    fn documents_ids(&self) -> Result<&RoaringBitmap> {
+        if let Some(documents_ids) = self.documents_ids.get() {
+            return Ok(documents_ids);
+        }
+        let documents_ids = self.index.documents_ids(self.rtxn)?;
+        Ok(self.documents_ids.get_or_init(|| documents_ids))
+    }
+
+    /// Returns the [`Index::facet_normalization_locale`], read on its first use.
+    fn locale(&self) -> Result<Option<Language>> {
+        if let Some(locale) = self.locale.get() {
+            return Ok(*locale);
+        }
+        let locale = self.index.facet_normalization_locale(self.rtxn)?;
+        Ok(*self.locale.get_or_init(|| locale))
+    }
+
+    /// Returns the regex of the pattern, compiled on its first use.
+    fn regex(&self, pattern: &Token<'_>) -> Result<Regex> {
+        if let Some(regex) = self.regexes.borrow().get(pattern.value()) {
+            return Ok(regex.clone());
+        }
+        let regex = compile_regex(pattern.value())
+            .map_err(|error| pattern.as_external_error(FilterError::InvalidRegex(error)))?;
+        self.regexes.borrow_mut().insert(pattern.value().to_string(), regex.clone());
+        Ok(regex)
+    }
+}
+
+/// The number bounds, when the values are numbers, and the string bounds of a range condition.
+type RangeConditionBounds<'c> =
+    (Option<(Bound<f64>, Bound<f64>)>, Option<(Bound<&'c str>, Bound<&'c str>)>);
+
+/// Returns the bounds of the `>`, `>=`, `<`, `<=`, `TO` and `NEAR` conditions.
+///
+/// # Panics
+///
+/// If the condition is not one of the range conditions.
+fn range_bounds<'c>(operator: &'c Condition<'_>) -> RangeConditionBounds<'c> {
+    match operator {
+        Condition::GreaterThan(val) => {
+            let number = val.parse_finite_float().ok();
+            let number_bounds = number.map(|number| (Excluded(number), Included(f64::MAX)));
+            let str_bounds = Some((Excluded(val.value()), Unbounded));
+            (number_bounds, str_bounds)
+        }
+        Condition::GreaterThanOrEqual(val) => {
+            let number = val.parse_finite_float().ok();
+            let number_bounds = number.map(|number| (Included(number), Included(f64::MAX)));
+            let str_bounds = Some((Included(val.value()), Unbounded));
+            (number_bounds, str_bounds)
+        }
+        Condition::LowerThan(val) => {
+            let number = val.parse_finite_float().ok();
+            let number_bounds = number.map(|number| (Included(f64::MIN), Excluded(number)));
+            let str_bounds = Some((Unbounded, Excluded(val.value())));
+            (number_bounds, str_bounds)
+        }
+        Condition::LowerThanOrEqual(val) => {
+            let number = val.parse_finite_float().ok();
+            let number_bounds = number.map(|number| (Included(f64::MIN), Included(number)));
+            let str_bounds = Some((Unbounded, Included(val.value())));
+            (number_bounds, str_bounds)
+        }
+        Condition::Between { from, to } => {
+            let from_number = from.parse_finite_float().ok();
+            let to_number = to.parse_finite_float().ok();
+
+            let number_bounds =
+                from_number.zip(to_number).map(|(from, to)| (Included(from), Included(to)));
+            let str_bounds = Some((Included(from.value()), Included(to.value())));
+            (number_bounds, str_bounds)
+        }
+        Condition::Approximately { value, tolerance } => {
+            // the parser only accepts finite numbers and positive tolerances
+            let value = value.parse_finite_float().ok();
+            let tolerance = tolerance.parse_finite_float().ok();
+            let number_bounds = value.zip(tolerance).map(|(value, tolerance)| {
+                let from = (value - tolerance).max(f64::MIN);
+                let to = (value + tolerance).min(f64::MAX);
+                (Included(from), Included(to))
+            });
+            // only the numbers can be near a value
+            (number_bounds, None)
+        }
+        _ => unreachable!("`{operator}` is not a range condition"),
+    }
+}
+
+/// Returns the bounds of the `=`, `>`, `>=`, `<`, `<=` and `TO` conditions
+/// when all their values are integers.
+fn integer_bounds(operator: &Condition<'_>) -> Option<(Bound<i64>, Bound<i64>)> {
+    let integer = |token: &Token<'_>| token.value().parse::<i64>().ok();
+    match operator {
+        Condition::Equal(val) => integer(val).map(|n| (Included(n), Included(n))),
+        Condition::GreaterThan(val) => integer(val).map(|n| (Excluded(n), Unbounded)),
+        Condition::GreaterThanOrEqual(val) => integer(val).map(|n| (Included(n), Unbounded)),
+        Condition::LowerThan(val) => integer(val).map(|n| (Unbounded, Excluded(n))),
+        Condition::LowerThanOrEqual(val) => integer(val).map(|n| (Unbounded, Included(n))),
+        Condition::Between { from, to } => {
+            integer(from).zip(integer(to)).map(|(from, to)| (Included(from), Included(to)))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the range of the `facet_id_i64_docids` keys of the field within the integer bounds.
+fn integer_keys_range(
+    field_id: FieldId,
+    (left, right): (Bound<i64>, Bound<i64>),
+) -> (Bound<(FieldId, i64)>, Bound<(FieldId, i64)>) {
+    let left = match left {
+        Unbounded => Included((field_id, i64::MIN)),
+        left => left.map(|n| (field_id, n)),
+    };
+    let right = match right {
+        Unbounded => Included((field_id, i64::MAX)),
+        right => right.map(|n| (field_id, n)),
+    };
+    (left, right)
+}
+
+/// Returns the documents ids with an integer of the field within the bounds.
+fn integer_docids(
+    rtxn: &heed::RoTxn<'_>,
+    index: &Index,
+    field_id: FieldId,
+    bounds: (Bound<i64>, Bound<i64>),
+) -> Result<RoaringBitmap> {
+    let range = integer_keys_range(field_id, bounds);
+    let mut docids = RoaringBitmap::new();
+    for result in index.facet_id_i64_docids.range(rtxn, &range)? {
+        let (_key, bitmap) = result?;
+        docids |= bitmap;
+    }
+    Ok(docids)
+}
+
+/// Returns the sum of the number of documents ids of the integers of the field within the bounds.
+fn count_integer_docids(
+    rtxn: &heed::RoTxn<'_>,
+    index: &Index,
+    field_id: FieldId,
+    bounds: (Bound<i64>, Bound<i64>),
+) -> Result<u64> {
+    let range = integer_keys_range(field_id, bounds);
+    let mut count = 0;
+    for result in index
+        .facet_id_i64_docids
+        .remap_data_type::<CboRoaringBitmapLenCodec>()
+        .range(rtxn, &range)?
+    {
+        let (_key, len) = result?;
+        count += len;
+    }
+    Ok(count)
+}
+
+/// Returns the equalities with the elements of an `IN`, without the elements that would be
+/// looked up exactly like a previous one: with the same normalized string and the same number.
+fn distinct_equalities<'a>(els: &[Token<'a>], locale: Option<Language>) -> Vec<Condition<'a>> {
+    let mut seen = HashSet::new();
+    els.iter()
+        .filter(|el| {
+            let string = crate::normalize_facet_with_locale(el.value(), locale).into_owned();
+            let integer = el.value().parse::<i64>().ok();
+            let number = el.parse_finite_float().ok().map(f64::to_bits);
+            seen.insert((string, integer, number))
+        })
+        .map(|el| Condition::Equal(el.clone()))
+        .collect()
+}
+
+/// Returns the normalized prefixes sorted and deduplicated,
+/// without the prefixes starting with another prefix that already matches all their values.
+fn minimal_sorted_prefixes(words: &[Token<'_>], locale: Option<Language>) -> Vec<String> {
+    let mut prefixes: Vec<_> = words
+        .iter()
+        .map(|word| crate::normalize_facet_with_locale(word.value(), locale).into_owned())
+        .collect();
+    prefixes.sort_unstable();
+    prefixes.dedup();
+
+    let mut minimal_prefixes: Vec<String> = Vec::with_capacity(prefixes.len());
+    for prefix in prefixes {
+        if !minimal_prefixes.last().is_some_and(|shorter| prefix.starts_with(shorter.as_str())) {
+            minimal_prefixes.push(prefix);
+        }
+    }
+    minimal_prefixes
+}
+
+/// Calls `f` on the string facet values of the field starting with any of the `prefixes`.
+///
+/// The `prefixes` must not start with one another, see [`minimal_sorted_prefixes`], so that
+/// every value is visited once. The database is seeked to each prefix: only the values starting
+/// with a prefix are read, whatever the number of values between two prefixes.
+fn for_each_prefixed_value<'t>(
+    rtxn: &'t heed::RoTxn<'_>,
+    index: &Index,
+    field_id: FieldId,
+    prefixes: &[String],
+    mut f: impl FnMut(FacetGroupLazyValue<'t>) -> Result<()>,
+) -> Result<()> {
+    let strings = index.facet_id_string_docids.remap_data_type::<FacetGroupLazyValueCodec>();
+    for prefix in prefixes {
+        let prefix = FacetGroupKey { field_id, level: 0, left_bound: prefix.as_str() };
+        for result in strings.prefix_iter(rtxn, &prefix)? {
+            let (_key, value) = result?;
+            f(value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Calls `f` with every number of the level 0 of the facet of the field, in ascending order.
+fn for_each_number_value<'t>(
+    rtxn: &'t heed::RoTxn<'_>,
+    index: &Index,
+    field_id: FieldId,
+    mut f: impl FnMut(f64, FacetGroupLazyValue<'t>) -> Result<()>,
+) -> Result<()> {
+    let start = FacetGroupKey { field_id, level: 0, left_bound: f64::MIN };
+    // the level 1 starts right after the last value of the level 0
+    let end = FacetGroupKey { field_id, level: 1, left_bound: f64::MIN };
+    for result in index
+        .facet_id_f64_docids
+        .remap_data_type::<FacetGroupLazyValueCodec>()
+        .range(rtxn, &(start..end))?
+    {
+        let (FacetGroupKey { left_bound, .. }, value) = result?;
+        f(left_bound, value)?;
+    }
+    Ok(())
+}
+
+/// Returns `true` if the number is an integer whose remainder of the division by the
+/// `divisor` is the `remainder`, the remainder of a negative number being negative.
+fn modulo_matches(number: f64, divisor: i64, remainder: i64) -> bool {
+    number.fract() == 0.0 && (number as i64) % divisor == remainder
+}
+
+/// Returns an error if the field has string values but no number, a `MOD` selects numbers only.
+fn check_numeric_field(
+    rtxn: &heed::RoTxn<'_>,
+    index: &Index,
+    field_id: FieldId,
+    token: &Token<'_>,
+) -> Result<()> {
+    let start = FacetGroupKey { field_id, level: 0, left_bound: f64::MIN };
+    let end = FacetGroupKey { field_id, level: 1, left_bound: f64::MIN };
+    let has_numbers = index
+        .facet_id_f64_docids
+        .remap_data_type::<DecodeIgnore>()
+        .range(rtxn, &(start..end))?
+        .next()
+        .transpose()?
+        .is_some();
+    if has_numbers {
+        return Ok(());
+    }
+
+    let base = FacetGroupKey { field_id, level: 0, left_bound: "" };
+    let has_strings = index
+        .facet_id_string_docids
+        .remap_data_type::<DecodeIgnore>()
+        .prefix_iter(rtxn, &base)?
+        .next()
+        .transpose()?
+        .is_some();
+    if has_strings {
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let field = fields_ids_map.name(field_id).unwrap_or_default();
+        Err(token.as_external_error(FilterError::ModuloNotNumeric(field)))?
+    }
+    Ok(())
+}
+
+/// Returns the number of documents of a facet value, without decoding its bitmap.
+fn facet_value_len(value: Option<FacetGroupLazyValue<'_>>) -> Result<u64> {
+    match value {
+        Some(value) => Ok(CboRoaringBitmapLenCodec::bytes_decode(value.bitmap_bytes)
+            .map_err(heed::Error::Decoding)?),
+        None => Ok(0),
+    }
+}
+
+/// Returns the id, features and rule index of the `other` field of a `_field` comparison,
+/// `None` when no document contains it.
+fn other_field(
+    rtxn: &heed::RoTxn<'_>,
+    index: &Index,
+    other: &Token<'_>,
+) -> Result<Option<(FieldId, FilterableAttributesFeatures, usize)>> {
+    let Some(other_id) = index.fields_ids_map(rtxn)?.id(other.value()) else {
+        return Ok(None);
+    };
+    let filterable_attributes_rules = index.filterable_attributes_rules(rtxn)?;
+    Ok(filter_features(other.value(), &filterable_attributes_rules)
+        .map(|(rule_index, features)| (other_id, features, rule_index)))
+}
+
+/// Replaces the `numbers` with the numbers of the field of the document.
+fn document_numbers(
+    rtxn: &heed::RoTxn<'_>,
+    index: &Index,
+    field_id: FieldId,
+    docid: DocumentId,
+    numbers: &mut Vec<f64>,
+) -> Result<()> {
+    let mut prefix = field_id.to_be_bytes().to_vec();
+    prefix.extend_from_slice(&docid.to_be_bytes());
+
+    numbers.clear();
+    let iter = index
+        .field_id_docid_facet_f64s
+        .remap_key_type::<Bytes>()
+        .prefix_iter(rtxn, &prefix)?
+        .remap_key_type::<FieldDocIdFacetF64Codec>();
+    for result in iter {
+        let ((_, _, number), ()) = result?;
+        numbers.push(number);
+    }
+    Ok(())
+}
+
+/// Matches the normalized facet values within a number of typos of the value of a `~=` filter.
+struct FuzzyMatcher {
+    query: Vec<char>,
+    max_distance: usize,
+    /// The last row of the edit distances, reused between the values.
+    row: Vec<usize>,
+}
+
+impl FuzzyMatcher {
+    /// Without an explicit distance, the value is allowed as many typos as a query word
+    /// of the same length according to the typo tolerance settings of the index.
+    fn new(
+        rtxn: &heed::RoTxn<'_>,
+        index: &Index,
+        value: &Token<'_>,
+        max_distance: &Option<Token<'_>>,
+        locale: Option<Language>,
+    ) -> Result<Self> {
+        let query: Vec<char> =
+            crate::normalize_facet_with_locale(value.value(), locale).chars().collect();
+        let max_distance = match max_distance {
+            // the parser only accepts the distances fitting in a `u8`
+            Some(max_distance) => max_distance.value().parse::<u8>().unwrap_or_default(),
+            None if !index.authorize_typos(rtxn)? => 0,
+            None if query.len() >= index.min_word_len_two_typos(rtxn)? as usize => 2,
+            None if query.len() >= index.min_word_len_one_typo(rtxn)? as usize => 1,
+            None => 0,
+        };
+        Ok(Self { query, max_distance: max_distance as usize, row: Vec::new() })
+    }
+
+    /// Returns whether the Levenshtein distance between the query and the `value` is at most
+    /// the max distance, giving up as soon as every distance of a row exceeds it.
+    fn matches(&mut self, value: &str) -> bool {
+        let Self { query, max_distance, row } = self;
+        if value.chars().count().abs_diff(query.len()) > *max_distance {
+            return false;
+        }
+
+        row.clear();
+        row.extend(0..=query.len());
+        for (i, value_char) in value.chars().enumerate() {
+            let mut diagonal = row[0];
+            row[0] = i + 1;
+            let mut row_min = row[0];
+            for (j, query_char) in query.iter().enumerate() {
+                let above = row[j + 1];
+                let substitution = diagonal + usize::from(value_char != *query_char);
+                row[j + 1] = substitution.min(above + 1).min(row[j] + 1);
+                diagonal = above;
+                row_min = row_min.min(row[j + 1]);
+            }
+            if row_min > *max_distance {
+                return false;
+            }
+        }
+
+        row[query.len()] <= *max_distance
+    }
+}
+
+/// Counts the facet values scanned by a `CONTAINS`, `STARTS WITH`, `~=`, `MATCHES` or `MOD`
+/// filter, erroring once they exceed the [`Index::filter_scan_limit`].
+struct ScanLimit<'t, 'a> {
+    rtxn: &'t heed::RoTxn<'t>,
+    index: &'t Index,
+    field_id: FieldId,
+    token: &'t Token<'a>,
+    limit: Option<u64>,
+    scanned: u64,
+}
+
+impl<'t, 'a> ScanLimit<'t, 'a> {
+    fn new(
+        rtxn: &'t heed::RoTxn<'t>,
+        index: &'t Index,
+        field_id: FieldId,
+        token: &'t Token<'a>,
+    ) -> Result<Self> {
+        let limit = index.filter_scan_limit(rtxn)?;
+        Ok(Self { rtxn, index, field_id, token, limit, scanned: 0 })
+    }
+
+    fn scan(&mut self) -> Result<()> {
+        self.scanned += 1;
+        match self.limit {
+            Some(limit) if self.scanned > limit => {
+                let fields_ids_map = self.index.fields_ids_map(self.rtxn)?;
+                let field = fields_ids_map.name(self.field_id).unwrap_or_default();
+                Err(self.token.as_external_error(FilterError::ScanLimitExceeded { field, limit }))?
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns [`Error::Interrupted`] if the evaluation must stop.
+fn check_interrupted(should_stop: &AtomicBool) -> Result<()> {
+    if should_stop.load(Ordering::Relaxed) {
+        Err(Error::Interrupted)
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the bounds of the number of values selected by the `operator` of a `_length` condition,
+/// the bounds of the equality for a `!=`.
+fn length_bounds<'a>(
+    fid: &Token<'a>,
+    operator: &Condition<'a>,
+    not_supported: fn(&str) -> FilterError<'_>,
+) -> Result<(Bound<f64>, Bound<f64>)> {
+    Ok(match operator {
+        Condition::Equal(value) | Condition::NotEqual(value) => {
+            let value = value.parse_finite_float()?;
+            (Included(value), Included(value))
+        }
+        Condition::GreaterThan(value) => (Excluded(value.parse_finite_float()?), Unbounded),
+        Condition::GreaterThanOrEqual(value) => (Included(value.parse_finite_float()?), Unbounded),
+        Condition::LowerThan(value) => (Unbounded, Excluded(value.parse_finite_float()?)),
+        Condition::LowerThanOrEqual(value) => (Unbounded, Included(value.parse_finite_float()?)),
+        Condition::Between { from, to } => {
+            (Included(from.parse_finite_float()?), Included(to.parse_finite_float()?))
+        }
+        _ => return Err(fid.as_external_error(not_supported(operator.operator())).into()),
+    })
+}
+
+/// Returns the bounds of the number of characters selected by the `operator` of a `_strlen`
+/// condition, or `None` when the field isn't filterable.
+fn string_length_bounds<'a>(
+    fid: &Token<'a>,
+    operator: &Condition<'a>,
+    filterable_attribute_rules: &[FilterableAttributesRule],
+) -> Result<Option<(Bound<f64>, Bound<f64>)>> {
+    let Some((_, features)) = filter_features(fid.value(), filterable_attribute_rules) else {
+        return Ok(None);
+    };
+    if !features.is_string_length() {
+        return Err(fid.as_external_error(FilterError::StringLengthNotEnabled(fid.value())).into());
+    }
+    length_bounds(fid, operator, |op| FilterError::StringLengthOperatorNotSupported(op)).map(Some)
+}
+
+/// Returns an error if the operator is not allowed by the features of the field.
+fn check_operator_allowed(
+    rtxn: &heed::RoTxn<'_>,
+    index: &Index,
+    field_id: FieldId,
+    operator: &Condition<'_>,
+    features: &FilterableAttributesFeatures,
+    rule_index: usize,
+) -> Result<()> {
+    let allowed = match operator {
+        Condition::GreaterThan(_)
+        | Condition::GreaterThanOrEqual(_)
+        | Condition::LowerThan(_)
+        | Condition::LowerThanOrEqual(_)
+        | Condition::Between { .. }
+        | Condition::Approximately { .. } => features.is_filterable_comparison(),
+        Condition::Empty => features.is_filterable_empty(),
+        Condition::Null => features.is_filterable_null(),
+        Condition::Exists => features.is_filterable_exists(),
+        Condition::Equal(_)
+        | Condition::NotEqual(_)
+        | Condition::IsTrue
+        | Condition::IsFalse
+        | Condition::IsDate => features.is_filterable_equality(),
+        Condition::Contains { .. }
+        | Condition::StartsWith { .. }
+        | Condition::StartsWithAny { .. } => true,
+        Condition::FuzzyEqual { .. } => features.is_fuzzy_equal(),
+        Condition::Regex { .. } => features.is_regex(),
+        Condition::Modulo { .. } => features.is_modulo(),
+        Condition::CompareFields { .. } => features.is_field_comparison(),
+    };
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(generate_filter_error(rtxn, index, field_id, operator, features, rule_index))
+    }
+}
+
+fn generate_filter_error(
+    rtxn: &heed::RoTxn<'_>,
+    index: &Index,
+    field_id: FieldId,
+    operator: &Condition<'_>,
+    features: &FilterableAttributesFeatures,
+    rule_index: usize,
+) -> Error {
+    match index.fields_ids_map(rtxn) {
+        Ok(fields_ids_map) => {
+            let field = fields_ids_map.name(field_id).unwrap_or_default();
+            Error::UserError(UserError::FilterOperatorNotAllowed {
+                field: field.to_string(),
+                allowed_operators: features.allowed_filter_operators(),
+                operator: operator.operator().to_string(),
+                rule_index,
+            })
+        }
+        Err(e) => e.into(),
+    }
+}
+
+impl<'a> From<FilterCondition<'a>> for Filter<'a> {
+    fn from(fc: FilterCondition<'a>) -> Self {
+        Self { condition: fc }
+    }
+}
+
+impl<'a> BitmapFilter<'a> {
+    /// Restricts the filter to the documents also matching `other`.
+    pub fn and(self, other: Filter<'a>) -> Self {
+        let filter = match self.filter {
+            Some(filter) => filter.and(other),
+            None => other,
+        };
+        Self { bitmap: self.bitmap, filter: Some(filter) }
+    }
+
+    /// The documents the filter is restricted to.
+    pub fn bitmap(&self) -> &RoaringBitmap {
+        &self.bitmap
+    }
+
+    /// The filter the documents of the bitmap must match, if any.
+    pub fn filter(&self) -> Option<&Filter<'a>> {
+        self.filter.as_ref()
+    }
+
+    /// Returns the documents of the bitmap that are in the index and match the filter.
+    pub fn evaluate(&self, rtxn: &heed::RoTxn<'_>, index: &Index) -> Result<RoaringBitmap> {
+        // the bitmap may contain documents that are not in the index
+        let universe = index.documents_ids(rtxn)? & &self.bitmap;
+        match &self.filter {
+            Some(filter) => {
+                filter.partition(rtxn, index, Some(&universe)).map(|(matched, _)| matched)
+            }
+            None => Ok(universe),
+        }
+    }
+
+    /// Checks the filter like [`Filter::validate`].
+    pub fn validate(&self, rtxn: &heed::RoTxn<'_>, index: &Index) -> Result<()> {
+        match &self.filter {
+            Some(filter) => filter.validate(rtxn, index),
+            None => Ok(()),
+        }
+    }
+
+    /// Lists the fields of the filter like [`Filter::field_usage`].
+    pub fn field_usage(&self) -> BTreeMap<String, BTreeSet<FilterOperator>> {
+        self.filter.as_ref().map(Filter::field_usage).unwrap_or_default()
+    }
+
+    /// Analyzes the filter like [`Filter::static_analysis`], an empty bitmap never matches
+    /// any document.
+    pub fn static_analysis(&self) -> FilterAnalysis {
+        let analysis = self.filter.as_ref().map(Filter::static_analysis).unwrap_or_default();
+        let always_empty = self.bitmap.is_empty() || analysis.always_empty;
+        // whether the bitmap holds all the documents depends on the index
+        FilterAnalysis { always_empty, always_all: false }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::fmt::Write;
+    use std::iter::FromIterator;
+    use std::sync::atomic::AtomicBool;
+
+    use big_s::S;
+    use charabia::Language;
+    use either::Either;
+    use heed::types::Bytes;
+    use meili_snap::snapshot;
+    use roaring::RoaringBitmap;
+
+    use super::{
+        distinct_equalities, minimal_sorted_prefixes, EvaluationCache, FilterAnalysis,
+        FilterOperator, FilterWarning, DEFAULT_BROAD_CONTAINS_THRESHOLD, MAX_FILTER_DEPTH,
+    };
+    use crate::constants::RESERVED_GEO_FIELD_NAME;
+    use crate::index::tests::TempIndex;
+    use crate::update::Settings;
+    use crate::{Condition, Filter, FilterCondition, FilterableAttributesRule, Token};
+
+    #[test]
+    fn empty_db() {
+        let index = TempIndex::new();
+        //Set the filterable fields to be the channel.
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(
+                    "PrIcE".to_string(),
+                )]);
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("PrIcE < 1000").unwrap().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(bitmap.is_empty());
+
+        let filter = Filter::from_str("NOT PrIcE >= 1000").unwrap().unwrap();
+        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(bitmap.is_empty());
+    }
+
+    #[test]
+    fn from_array() {
+        // Simple array with Left
+        let condition = Filter::from_array(vec![Either::Left(["channel = mv"])]).unwrap().unwrap();
+        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Simple array with Right
+        let condition = Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = mv")])
+            .unwrap()
+            .unwrap();
+        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with Left and escaped quote
+        let condition =
+            Filter::from_array(vec![Either::Left(["channel = \"Mister Mv\""])]).unwrap().unwrap();
+        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with Right and escaped quote
+        let condition =
+            Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = \"Mister Mv\"")])
+                .unwrap()
+                .unwrap();
+        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with Left and escaped simple quote
+        let condition =
+            Filter::from_array(vec![Either::Left(["channel = 'Mister Mv'"])]).unwrap().unwrap();
+        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Array with Right and escaped simple quote
+        let condition =
+            Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = 'Mister Mv'")])
+                .unwrap()
+                .unwrap();
+        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Simple with parenthesis
+        let condition =
+            Filter::from_array(vec![Either::Left(["(channel = mv)"])]).unwrap().unwrap();
+        let expected = Filter::from_str("(channel = mv)").unwrap().unwrap();
+        assert_eq!(condition, expected);
+
+        // Test that the facet condition is correctly generated.
+        let condition = Filter::from_array(vec![
+            Either::Right("channel = gotaga"),
+            Either::Left(vec!["timestamp = 44", "channel != ponce"]),
+        ])
+        .unwrap()
+        .unwrap();
+        let expected =
+            Filter::from_str("channel = gotaga AND (timestamp = 44 OR channel != ponce)")
+                .unwrap()
+                .unwrap();
+        assert_eq!(condition, expected);
+    }
+
+    #[test]
+    fn not_filterable() {
+        let index = TempIndex::new();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("_geoRadius(42, 150, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Attribute `_geo` is not filterable. This index does not have configured filterable attributes.
+        12:14 _geoRadius(42, 150, 10)
+        "###);
+
+        let filter = Filter::from_str("_geoBoundingBox([42, 150], [30, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Attribute `_geo` is not filterable. This index does not have configured filterable attributes.
+        18:20 _geoBoundingBox([42, 150], [30, 10])
+        "###);
+
+        let filter = Filter::from_str("dog = \"bernese mountain\"").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Attribute `dog` is not filterable. This index does not have configured filterable attributes.
+        1:4 dog = "bernese mountain"
+        "###);
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title")]);
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(
+                    "title".to_string(),
+                )]);
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("_geoRadius(-100, 150, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Attribute `_geo` is not filterable. Available filterable attribute patterns are: `title`.
+        12:16 _geoRadius(-100, 150, 10)
+        "###);
+
+        let filter = Filter::from_str("_geoBoundingBox([42, 150], [30, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Attribute `_geo` is not filterable. Available filterable attribute patterns are: `title`.
+        18:20 _geoBoundingBox([42, 150], [30, 10])
+        "###);
+
+        let filter = Filter::from_str("name = 12").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Attribute `name` is not filterable. Available filterable attribute patterns are: `title`.
+        1:5 name = 12
+        "###);
+
+        let filter = Filter::from_str("title = \"test\" AND name = 12").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Attribute `name` is not filterable. Available filterable attribute patterns are: `title`.
+        20:24 title = "test" AND name = 12
+        "###);
+
+        let filter = Filter::from_str("title = \"test\" AND name IN [12]").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Attribute `name` is not filterable. Available filterable attribute patterns are: `title`.
+        20:24 title = "test" AND name IN [12]
+        "###);
+
+        let filter = Filter::from_str("title = \"test\" AND name != 12").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Attribute `name` is not filterable. Available filterable attribute patterns are: `title`.
+        20:24 title = "test" AND name != 12
+        "###);
+    }
+
+    #[test]
+    fn escaped_quote_in_filter_value_2380() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                {
+                    "id": "test_1",
+                    "monitor_diagonal": "27' to 30'"
+                },
+                {
+                    "id": "test_2",
+                    "monitor_diagonal": "27\" to 30\""
+                },
+                {
+                    "id": "test_3",
+                    "monitor_diagonal": "27\" to 30'"
+                },
+            ]))
+            .unwrap();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(
+                    "monitor_diagonal".to_string(),
+                )]);
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let mut search = crate::Search::new(&rtxn, &index);
+        // this filter is copy pasted from #2380 with the exact same espace sequence
+        search.filter(Filter::from_str("monitor_diagonal = '27\" to 30\\''").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![2]);
+
+        search.filter(Filter::from_str(r#"monitor_diagonal = "27' to 30'" "#).unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0]);
+
+        search.filter(Filter::from_str(r#"monitor_diagonal = "27\" to 30\"" "#).unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![1]);
+
+        search.filter(Filter::from_str(r#"monitor_diagonal = "27\" to 30'" "#).unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![2]);
+    }
+
+    #[test]
+    fn zero_radius() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S(
+                    RESERVED_GEO_FIELD_NAME,
+                ))]);
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+              {
+                "id": 1,
+                "name": "Nàpiz' Milano",
+                "address": "Viale Vittorio Veneto, 30, 20124, Milan, Italy",
+                "type": "pizza",
+                "rating": 9,
+                RESERVED_GEO_FIELD_NAME: {
+                  "lat": 45.4777599,
+                  "lng": 9.1967508
+                }
+              },
+              {
+                "id": 2,
+                "name": "Artico Gelateria Tradizionale",
+                "address": "Via Dogana, 1, 20123 Milan, Italy",
+                "type": "ice cream",
+                "rating": 10,
+                RESERVED_GEO_FIELD_NAME: {
+                  "lat": 45.4632046,
+                  "lng": 9.1719421
+                }
+              },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let mut search = crate::Search::new(&rtxn, &index);
+
+        search.filter(Filter::from_str("_geoRadius(45.4777599, 9.1967508, 0)").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0]);
+    }
+
+    #[test]
+    fn geo_radius_exclusive() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S(
+                    RESERVED_GEO_FIELD_NAME,
+                ))]);
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+              { "id": 1, RESERVED_GEO_FIELD_NAME: { "lat": 45.4777599, "lng": 9.1967508 } },
+              { "id": 2, RESERVED_GEO_FIELD_NAME: { "lat": 45.4632046, "lng": 9.1719421 } },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // the second document is exactly at the radius
+        let radius =
+            crate::distance_between_two_points(&[45.4777599, 9.1967508], &[45.4632046, 9.1719421]);
+        let filter = format!("_geoRadius(45.4777599, 9.1967508, {radius})");
+        let filter = Filter::from_str(&filter).unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0, 1]));
+
+        let filter = format!("_geoRadiusExclusive(45.4777599, 9.1967508, {radius})");
+        let filter = Filter::from_str(&filter).unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0]));
+
+        let filter = Filter::from_str("_geoRadiusExclusive(45.4777599, 9.1967508, 0)").unwrap();
+        assert!(filter.unwrap().evaluate(&rtxn, &index).unwrap().is_empty());
+    }
+
+    #[test]
+    fn geo_radius_approx() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S(
+                    RESERVED_GEO_FIELD_NAME,
+                ))]);
+            })
+            .unwrap();
+
+        // a grid of points around (45, 9), roughly a kilometer apart
+        let mut docs = vec![];
+        let mut points = vec![];
+        for i in 0..21 {
+            for j in 0..21 {
+                let point = [44.9 + i as f64 * 0.01, 8.9 + j as f64 * 0.01];
+                docs.push(serde_json::json!({
+                    "id": points.len(),
+                    RESERVED_GEO_FIELD_NAME: { "lat": point[0], "lng": point[1] },
+                }));
+                points.push(point);
+            }
+        }
+        index.add_documents(documents!(docs)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for radius in [0.0, 1000.0, 5000.0, 8000.0] {
+            let exact = format!("_geoRadius(45, 9, {radius})");
+            let exact = Filter::from_str(&exact).unwrap().unwrap().evaluate(&rtxn, &index).unwrap();
+            let approx = format!("_geoRadiusApprox(45, 9, {radius})");
+            let approx =
+                Filter::from_str(&approx).unwrap().unwrap().evaluate(&rtxn, &index).unwrap();
+
+            assert!(approx.is_superset(&exact), "{radius}");
+            // the false positives are in the corners of the bounding box of the circle,
+            // never further than its diagonal
+            for docid in approx {
+                let distance =
+                    crate::distance_between_two_points(&[45.0, 9.0], &points[docid as usize]);
+                assert!(distance <= radius * 3.0f64.sqrt() + 1.0, "{radius} {distance}");
+            }
+        }
+
+        // the corners of the box around a 5km radius are included by the approximation only
+        let filter = Filter::from_str("_geoRadiusApprox(45, 9, 5000)").unwrap().unwrap();
+        let approx = filter.evaluate(&rtxn, &index).unwrap();
+        let filter = Filter::from_str("_geoRadius(45, 9, 5000)").unwrap().unwrap();
+        let exact = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(approx.len() > exact.len());
+
+        // a radius larger than the earth selects all the points
+        let filter = Filter::from_str("_geoRadiusApprox(45, 9, 30000000)").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap().len(), 441);
+    }
+
+    #[test]
+    fn geo_not_radius() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S(RESERVED_GEO_FIELD_NAME)),
+                    FilterableAttributesRule::Field(S("kind")),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "kind": "food", RESERVED_GEO_FIELD_NAME: { "lat": 45.0, "lng": 9.0 } },
+                { "id": 1, "kind": "food", RESERVED_GEO_FIELD_NAME: { "lat": 45.001, "lng": 9.0 } },
+                { "id": 2, "kind": "food", RESERVED_GEO_FIELD_NAME: { "lat": 45.1, "lng": 9.0 } },
+                { "id": 3, "kind": "bar", RESERVED_GEO_FIELD_NAME: { "lat": 46.0, "lng": 9.0 } },
+                { "id": 4, "kind": "food" },
+                { "id": 5, "kind": "bar" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in [
+            ("_geoRadius(45, 9, 500)", vec![0, 1]),
+            // the documents without coordinates are not outside of the circle
+            ("_geoNotRadius(45, 9, 500)", vec![2, 3]),
+            ("NOT _geoRadius(45, 9, 500)", vec![2, 3, 4, 5]),
+            ("kind = food AND _geoNotRadius(45, 9, 500)", vec![2]),
+            ("_geoNotRadius(45, 9, 500) AND kind = food", vec![2]),
+            ("kind = bar OR _geoNotRadius(45, 9, 500)", vec![2, 3, 5]),
+            ("NOT _geoNotRadius(45, 9, 500)", vec![0, 1, 4, 5]),
+            ("_geoNotRadius(45, 9, 0)", vec![1, 2, 3]),
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(docids, RoaringBitmap::from_iter(expected), "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn negated_geo_bounding_box() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S(RESERVED_GEO_FIELD_NAME)),
+                    FilterableAttributesRule::Field(S("kind")),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "kind": "food", RESERVED_GEO_FIELD_NAME: { "lat": 45.0, "lng": 9.0 } },
+                { "id": 1, "kind": "food", RESERVED_GEO_FIELD_NAME: { "lat": 45.5, "lng": 9.5 } },
+                { "id": 2, "kind": "food", RESERVED_GEO_FIELD_NAME: { "lat": 47.0, "lng": 9.0 } },
+                { "id": 3, "kind": "bar", RESERVED_GEO_FIELD_NAME: { "lat": 45.5, "lng": 12.0 } },
+                { "id": 4, "kind": "food" },
+                { "id": 5, "kind": "bar" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in [
+            ("_geoBoundingBox([46, 10], [44, 8])", vec![0, 1]),
+            // the documents without coordinates are not outside of the box
+            ("NOT _geoBoundingBox([46, 10], [44, 8])", vec![2, 3]),
+            ("kind = food AND NOT _geoBoundingBox([46, 10], [44, 8])", vec![2]),
+            ("kind = bar OR NOT _geoBoundingBox([46, 10], [44, 8])", vec![2, 3, 5]),
+            ("kind = food AND NOT NOT _geoBoundingBox([46, 10], [44, 8])", vec![0, 1]),
+            ("kind = food AND NOT NOT NOT _geoBoundingBox([46, 10], [44, 8])", vec![2]),
+            // the double negation is the box itself, like once simplified
+            ("NOT NOT _geoBoundingBox([46, 10], [44, 8])", vec![0, 1]),
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(docids, RoaringBitmap::from_iter(expected), "{filter:?}");
+            let simplified = filter.clone().simplify().evaluate(&rtxn, &index).unwrap();
+            assert_eq!(simplified, docids, "{filter:?}");
+        }
+
+        let filter = Filter::from_str("kind = food AND NOT _geoBoundingBox([46, 10], [44, 8])")
+            .unwrap()
+            .unwrap();
+        for docid in 0..6 {
+            let explanation = filter.matches_document(&rtxn, &index, docid).unwrap();
+            assert_eq!(explanation.matches, docid == 2, "{docid}");
+        }
+
+        let filter = Filter::from_str("NOT _geoBoundingBox([46, 10], [44, 8])").unwrap().unwrap();
+        let (matched, unmatched) =
+            filter.partition(&rtxn, &index, Some(&RoaringBitmap::from_iter([0, 2, 4]))).unwrap();
+        assert_eq!(matched, RoaringBitmap::from_iter([2]));
+        assert_eq!(unmatched, RoaringBitmap::from_iter([0, 4]));
+        for docid in 0..6 {
+            let explanation = filter.matches_document(&rtxn, &index, docid).unwrap();
+            assert_eq!(explanation.matches, [2, 3].contains(&docid), "{docid}");
+        }
+    }
+
+    #[test]
+    fn geo_in_region() {
+        let index = TempIndex::new();
+
+        // an L shaped downtown, the notch of the L is inside of its bounding box
+        let downtown =
+            vec![[45.0, 9.0], [46.0, 9.0], [46.0, 9.5], [45.5, 9.5], [45.5, 10.0], [45.0, 10.0]];
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S(RESERVED_GEO_FIELD_NAME)),
+                    FilterableAttributesRule::Field(S("kind")),
+                ]);
+                settings.set_geo_regions(BTreeMap::from([(S("downtown"), downtown.clone())]));
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "kind": "food", RESERVED_GEO_FIELD_NAME: { "lat": 45.25, "lng": 9.25 } },
+                { "id": 1, "kind": "food", RESERVED_GEO_FIELD_NAME: { "lat": 45.75, "lng": 9.75 } },
+                { "id": 2, "kind": "bar", RESERVED_GEO_FIELD_NAME: { "lat": 45.75, "lng": 9.25 } },
+                { "id": 3, "kind": "bar", RESERVED_GEO_FIELD_NAME: { "lat": 47.0, "lng": 9.0 } },
+                { "id": 4, "kind": "food", RESERVED_GEO_FIELD_NAME: { "lat": 45.25, "lng": 9.75 } },
+                { "id": 5, "kind": "food" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in [
+            ("_geoInRegion(downtown)", vec![0, 2, 4]),
+            ("_geoInRegion(\"downtown\")", vec![0, 2, 4]),
+            ("NOT _geoInRegion(downtown)", vec![1, 3, 5]),
+            ("kind = food AND _geoInRegion(downtown)", vec![0, 4]),
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(docids, RoaringBitmap::from_iter(expected), "{filter:?}");
+        }
+
+        let filter = Filter::from_str("_geoInRegion(uptown)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        The geo region `uptown` does not exist.
+        14:20 _geoInRegion(uptown)
+        "###);
+        drop(rtxn);
+
+        // the regions are validated when they are stored
+        let error = index
+            .update_settings(|settings| {
+                settings
+                    .set_geo_regions(BTreeMap::from([(S("line"), vec![[45.0, 9.0], [46.0, 9.0]])]));
+            })
+            .unwrap_err();
+        snapshot!(error.to_string(), @"The geo region `line` is invalid: a region needs at least 3 points but only 2 were given.");
+        let error = index
+            .update_settings(|settings| {
+                let mut polygon = downtown.clone();
+                polygon[1] = [91.0, 9.0];
+                settings.set_geo_regions(BTreeMap::from([(S("downtown"), polygon)]));
+            })
+            .unwrap_err();
+        snapshot!(error.to_string(), @"The geo region `downtown` is invalid: the point `[91, 9]` is not a valid `[latitude, longitude]` pair.");
+
+        // the regions are forgotten when they are reset
+        index.update_settings(|settings| settings.reset_geo_regions()).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("_geoInRegion(downtown)").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).is_err());
+    }
+
+    #[test]
+    fn geo_radius_error() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S(RESERVED_GEO_FIELD_NAME), S("price")]); // to keep the fields order
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S(RESERVED_GEO_FIELD_NAME)),
+                    FilterableAttributesRule::Field("price".to_string()),
+                ]);
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // georadius have a bad latitude
+        let filter = Filter::from_str("_geoRadius(-100, 150, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error.to_string().starts_with(
+                "Bad latitude `-100`. Latitude must be contained between -90 and 90 degrees."
+            ),
+            "{}",
+            error.to_string()
+        );
+
+        // georadius have a bad latitude
+        let filter = Filter::from_str("_geoRadius(-90.0000001, 150, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
+        ));
+
+        // georadius have a bad longitude
+        let filter = Filter::from_str("_geoRadius(-10, 250, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error.to_string().contains(
+                "Bad longitude `250`. Longitude must be contained between -180 and 180 degrees."
+            ),
+            "{}",
+            error.to_string(),
+        );
+
+        // georadius have a bad longitude
+        let filter = Filter::from_str("_geoRadius(-10, 180.000001, 10)").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
+        ));
+    }
+
+    #[test]
+    fn geo_bounding_box_error() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S(RESERVED_GEO_FIELD_NAME), S("price")]); // to keep the fields order
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S(RESERVED_GEO_FIELD_NAME)),
+                    FilterableAttributesRule::Field("price".to_string()),
+                ]);
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // geoboundingbox top left coord have a bad latitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([-90.0000001, 150], [30, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error.to_string().starts_with(
+                "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
+            ),
+            "{}",
+            error.to_string()
+        );
+
+        // geoboundingbox top left coord have a bad latitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([90.0000001, 150], [30, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error.to_string().starts_with(
+                "Bad latitude `90.0000001`. Latitude must be contained between -90 and 90 degrees."
+            ),
+            "{}",
+            error.to_string()
+        );
+
+        // geoboundingbox bottom right coord have a bad latitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([30, 10], [-90.0000001, 150])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
+        ));
+
+        // geoboundingbox bottom right coord have a bad latitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([30, 10], [90.0000001, 150])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad latitude `90.0000001`. Latitude must be contained between -90 and 90 degrees."
+        ));
+
+        // geoboundingbox top left coord have a bad longitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([-10, 180.000001], [30, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
+        ));
+
+        // geoboundingbox top left coord have a bad longitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([-10, -180.000001], [30, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad longitude `-180.000001`. Longitude must be contained between -180 and 180 degrees."
+        ));
+
+        // geoboundingbox bottom right coord have a bad longitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([30, 10], [-10, -180.000001])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad longitude `-180.000001`. Longitude must be contained between -180 and 180 degrees."
+        ));
+
+        // geoboundingbox bottom right coord have a bad longitude
+        let filter =
+            Filter::from_str("_geoBoundingBox([30, 10], [-10, 180.000001])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().contains(
+            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
+        ));
+    }
+
+    #[test]
+    fn filter_depth() {
+        // generates a big (2 MiB) filter with too much of ORs.
+        let tipic_filter = "account_ids=14361 OR ";
+        let mut filter_string = String::with_capacity(tipic_filter.len() * 14360);
+        for i in 1..=14361 {
+            let _ = write!(&mut filter_string, "account_ids={}", i);
+            if i != 14361 {
+                let _ = write!(&mut filter_string, " OR ");
+            }
+        }
+
+        // Note: the filter used to be rejected for being too deep, but that is
+        // no longer the case
+        let filter = Filter::from_str(&filter_string).unwrap();
+        assert!(filter.is_some());
+
+        // the node count limit catches it instead
+        assert_eq!(filter.unwrap().node_count(), 14362);
+        Filter::from_str_with_limits(&filter_string, MAX_FILTER_DEPTH, 14362).unwrap().unwrap();
+        let error =
+            Filter::from_str_with_limits(&filter_string, MAX_FILTER_DEPTH, 10_000).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Too many filter nodes, can't process more than 10000 nodes."));
+    }
+
+    #[test]
+    fn from_condition_checked() {
+        let condition = FilterCondition::parse("account_ids = 1").unwrap().unwrap();
+        let shallow = Filter::from_condition_checked(condition.clone()).unwrap();
+        assert_eq!(shallow, Filter::from(condition.clone()));
+
+        let too_deep = || {
+            (0..MAX_FILTER_DEPTH)
+                .fold(condition.clone(), |condition, _| FilterCondition::Or(vec![condition]))
+        };
+        let error = Filter::from_condition_checked(too_deep()).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Too many filter conditions, can't process more than 2000 filters."));
+
+        // the unchecked conversion accepts it
+        let filter = Filter::from(too_deep());
+        assert!(filter.use_contains_operator().is_none());
+    }
+
+    #[test]
+    fn owned_filter() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("color"))]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "color": "red" },
+                { "id": 1, "color": "blue" },
+                { "id": 2, "color": "red" },
+            ]))
+            .unwrap();
+
+        let mut cache = std::collections::HashMap::new();
+        for color in ["red", "blue"] {
+            let source = format!("color = {color}");
+            let filter = Filter::from_str_borrowed_owned(source.clone()).unwrap().unwrap();
+            drop(source);
+            cache.insert(color, filter);
+        }
+        // the cached filters can be moved to another thread
+        let cache = std::thread::spawn(move || cache).join().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let red = &cache["red"];
+        assert_eq!(red.expression(), "color = red");
+        assert_eq!(red.filter(), &Filter::from_str("color = red").unwrap().unwrap());
+        assert_eq!(red.filter().evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0, 2]));
+        let blue = cache["blue"].filter().clone();
+        assert_eq!(blue.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1]));
+
+        assert!(Filter::from_str_borrowed_owned("   ").unwrap().is_none());
+        let error = Filter::from_str_borrowed_owned(String::from("color =")).unwrap_err();
+        assert!(error.to_string().starts_with("Was expecting a value but instead got nothing."));
+    }
+
+    #[test]
+    fn node_count() {
+        let filter = Filter::from_str("NOT (a = 1 OR b IN [1, 2, 3]) AND _geoRadius(0, 0, 10)")
+            .unwrap()
+            .unwrap();
+        // AND, NOT, OR, `a = 1`, IN with its 3 elements, _geoRadius
+        assert_eq!(filter.node_count(), 9);
+
+        Filter::from_str_with_limits("a IN [1, 2, 3]", MAX_FILTER_DEPTH, 4).unwrap().unwrap();
+        let error =
+            Filter::from_str_with_limits("a IN [1, 2, 3]", MAX_FILTER_DEPTH, 3).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Too many filter nodes, can't process more than 3 nodes.
+        13:14 a IN [1, 2, 3]
+        "###);
+    }
+
+    #[test]
+    fn empty_filter() {
+        let option = Filter::from_str("     ").unwrap();
+        assert_eq!(option, None);
+    }
+
+    #[test]
+    fn non_finite_float() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("price")]); // to keep the fields order
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(
+                    "price".to_string(),
+                )]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                {
+                    "id": "test_1",
+                    "price": "inf"
+                },
+                {
+                    "id": "test_2",
+                    "price": "2000"
+                },
+                {
+                    "id": "test_3",
+                    "price": "infinity"
+                },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("price = inf").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(result.contains(0));
+        let filter = Filter::from_str("price < inf").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        // this is allowed due to filters with strings
+        assert!(result.contains(1));
+
+        let filter = Filter::from_str("price = NaN").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(result.is_empty());
+        let filter = Filter::from_str("price < NaN").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(result.contains(1));
+
+        let filter = Filter::from_str("price = infinity").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(result.contains(2));
+        let filter = Filter::from_str("price < infinity").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert!(result.contains(0));
+        assert!(result.contains(1));
+    }
+
+    #[test]
+    fn filter_number() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field("id".to_string()),
+                    FilterableAttributesRule::Field("one".to_string()),
+                    FilterableAttributesRule::Field("two".to_string()),
+                ]);
+            })
+            .unwrap();
+
+        let mut docs = vec![];
+        for i in 0..100 {
+            docs.push(serde_json::json!({ "id": i, "two": i % 10 }));
+        }
+
+        index.add_documents(documents!(docs)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for i in 0..100 {
+            let filter_str = format!("id = {i}");
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(result, RoaringBitmap::from_iter([i]));
+        }
+        for i in 0..100 {
+            let filter_str = format!("id > {i}");
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(result, RoaringBitmap::from_iter((i + 1)..100));
+        }
+        for i in 0..100 {
+            let filter_str = format!("id < {i}");
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(result, RoaringBitmap::from_iter(0..i));
+        }
+        for i in 0..100 {
+            let filter_str = format!("id <= {i}");
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(result, RoaringBitmap::from_iter(0..=i));
+        }
+        for i in 0..100 {
+            let filter_str = format!("id >= {i}");
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(result, RoaringBitmap::from_iter(i..100));
+        }
+        for i in 0..100 {
+            for j in i..100 {
+                let filter_str = format!("id {i} TO {j}");
+                let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+                let result = filter.evaluate(&rtxn, &index).unwrap();
+                assert_eq!(result, RoaringBitmap::from_iter(i..=j));
+            }
+        }
+        let filter = Filter::from_str("one >= 0 OR one <= 0").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::default());
+
+        let filter = Filter::from_str("one = 0").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::default());
+
+        for i in 0..10 {
+            for j in i..10 {
+                let filter_str = format!("two {i} TO {j}");
+                let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+                let result = filter.evaluate(&rtxn, &index).unwrap();
+                assert_eq!(
+                    result,
+                    RoaringBitmap::from_iter((0..100).filter(|x| (i..=j).contains(&(x % 10))))
+                );
+            }
+        }
+        let filter = Filter::from_str("two != 0").unwrap().unwrap();
+        let result = filter.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(result, RoaringBitmap::from_iter((0..100).filter(|x| x % 10 != 0)));
+    }
+
+    #[test]
+    fn simplify() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field("id".to_string()),
+                    FilterableAttributesRule::Field("two".to_string()),
+                ]);
+            })
+            .unwrap();
+
+        let mut docs = vec![];
+        for i in 0..100 {
+            docs.push(serde_json::json!({ "id": i, "two": i % 10 }));
+        }
+
+        index.add_documents(documents!(docs)).unwrap();
+
+        let filter = Filter::from_str("id > 10 AND (id < 90 AND (two != 3 AND two != 4))");
+        match FilterCondition::from(filter.unwrap().unwrap().simplify()) {
+            FilterCondition::And(subfilters) => {
+                assert_eq!(subfilters.len(), 4);
+                assert!(subfilters.iter().all(|f| !matches!(f, FilterCondition::And(_))));
+            }
+            condition => panic!("expected a single AND node, got {condition}"),
+        }
+
+        let rtxn = index.read_txn().unwrap();
+        for filter_str in [
+            "id > 10 AND (id < 90 AND (two != 3 AND two != 4))",
+            "id > 10 AND (id < 90 AND (two != 3 AND id > 10))",
+            "two = 1 OR (two = 2 OR (two = 1 OR id 40 TO 60))",
+            "(id < 50 OR (id > 80 OR id < 50)) AND (two = 0 AND (two = 0 OR id = 3))",
+            "NOT (two = 0 OR (two = 1 OR id >= 95))",
+            "id >= 20 AND NOT (two = 5 AND (id < 70 AND id < 70))",
+        ] {
+            let filter = Filter::from_str(filter_str).unwrap().unwrap();
+            let expected = filter.evaluate(&rtxn, &index).unwrap();
+            let simplified = filter.simplify();
+            assert_eq!(simplified.evaluate(&rtxn, &index).unwrap(), expected, "{filter_str}");
+        }
+
+        let not = |condition| FilterCondition::Not(Box::new(condition));
+        let filter = Filter::from_str("two = 3").unwrap().unwrap();
+        let double_negation = Filter::from(not(not(filter.clone().into())));
+        assert_eq!(double_negation.clone().simplify(), filter);
+        assert_eq!(
+            double_negation.evaluate(&rtxn, &index).unwrap(),
+            filter.evaluate(&rtxn, &index).unwrap()
+        );
+    }
+
+    #[test]
+    fn estimate_cardinality() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field("id".to_string()),
+                    FilterableAttributesRule::Field("one".to_string()),
+                    FilterableAttributesRule::Field("two".to_string()),
+                ]);
+            })
+            .unwrap();
+
+        let mut docs = vec![];
+        for i in 0..100 {
+            docs.push(serde_json::json!({ "id": i, "two": i % 10 }));
+        }
+
+        index.add_documents(documents!(docs)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        // the estimate must be within 5% of the documents of the index, as `id` and `two` are
+        // single valued and independent enough for the `AND`s to be multiplied
+        let tolerance = 5;
+        let mut filters = vec![
+            S("id = 42"),
+            S("id != 42"),
+            S("one = 0"),
+            S("one >= 0 OR one <= 0"),
+            S("id EXISTS"),
+            S("two IN [1, 2, 3]"),
+            S("id < 50 AND two = 3"),
+            S("id >= 20 AND two 2 TO 5"),
+            S("id < 50 AND NOT two = 3"),
+            S("id < 10 OR id >= 90"),
+            S("NOT id 10 TO 89"),
+        ];
+        for i in (0..100).step_by(7) {
+            filters.push(format!("id > {i}"));
+            filters.push(format!("id <= {i}"));
+            for j in (i..100).step_by(13) {
+                filters.push(format!("id {i} TO {j}"));
+            }
+        }
+        for filter_str in filters {
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let actual = filter.evaluate(&rtxn, &index).unwrap().len();
+            let estimate = filter.estimate_cardinality(&rtxn, &index).unwrap();
+            assert!(
+                actual.abs_diff(estimate) <= tolerance,
+                "{filter_str}: estimated {estimate} documents but matched {actual}"
+            );
+        }
+
+        // the ranges and equalities on single valued fields are exact
+        let filter = Filter::from_str("id 12 TO 87").unwrap().unwrap();
+        assert_eq!(filter.estimate_cardinality(&rtxn, &index).unwrap(), 76);
+        let filter = Filter::from_str("two = 4").unwrap().unwrap();
+        assert_eq!(filter.estimate_cardinality(&rtxn, &index).unwrap(), 10);
+    }
+
+    #[test]
+    fn contains_ignores_searchable_words() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_searchable_fields(vec![S("brand"), S("name")]);
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(
+                    "brand".to_string(),
+                )]);
+            })
+            .unwrap();
+
+        let brands = ["Jackson", "Sony-Ericsson", "Samsung", "Apple", "Sonos", "LG"];
+        let mut docs = vec![];
+        for i in 0..1000 {
+            let brand = format!("{} {}", brands[i % brands.len()], i % 37);
+            docs.push(serde_json::json!({ "id": i, "brand": brand, "name": "phone" }));
+        }
+        index.add_documents(documents!(docs)).unwrap();
+
+        let terms = ["son", "SON", "on", "apple", "sung", "12", "xyz", "son-e", "g 1"];
+
+        let rtxn = index.read_txn().unwrap();
+        let mut expected = Vec::new();
+        for term in terms {
+            let filter_str = format!("brand CONTAINS {term:?}");
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+
+            let value = crate::normalize_facet(term);
+            let matching = (0..1000u32).filter(|&i| {
+                let brand = format!("{} {}", brands[i as usize % brands.len()], i % 37);
+                crate::normalize_facet(&brand).contains(value.as_ref())
+            });
+            assert_eq!(result, RoaringBitmap::from_iter(matching), "{term}");
+            expected.push(result);
+        }
+
+        drop(rtxn);
+
+        // the words of the searchable fields have no impact on the matching facet values
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("name")]);
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for (term, expected) in terms.iter().zip(expected) {
+            let filter_str = format!("brand CONTAINS {term:?}");
+            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
+            let result = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(result, expected, "{term}");
+        }
+    }
+
+    #[test]
+    fn validate_matches_evaluate_errors() {
+        let index = TempIndex::new();
+
+        let price_rule: FilterableAttributesRule = serde_json::from_value(serde_json::json!({
+            "attributePatterns": ["price"],
+            "features": { "filter": { "equality": true, "comparison": false } }
+        }))
+        .unwrap();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("title")),
+                    price_rule.clone(),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "kefir", "price": 10, "color": "white" },
+                { "id": 1, "title": "milk", "price": 5, "color": "white" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for filter in [
+            "color = white",
+            "title = kefir AND color = white",
+            "NOT color EXISTS",
+            "price > 5",
+            "price 1 TO 10",
+            "title = milk OR price >= 5",
+            "_geoRadius(42, 150, 10)",
+            "_geoBoundingBox([42, 150], [30, 10])",
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let validation = filter.validate(&rtxn, &index).unwrap_err();
+            let evaluation = filter.evaluate(&rtxn, &index).unwrap_err();
+            assert_eq!(validation.to_string(), evaluation.to_string());
+        }
+
+        let filter = Filter::from_str("price > 5").unwrap().unwrap();
+        let error = filter.validate(&rtxn, &index).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Filter operator `>` is not allowed for the attribute `price`.
+          - Note: allowed operators: OR, AND, NOT, =, !=, IN, IS EMPTY, IS NULL, EXISTS.
+          - Note: field `price` matched rule #1 in `filterableAttributes`
+          - Hint: enable comparison in rule #1 by modifying the features.filter object
+          - Hint: prepend another rule matching `price` with appropriate filter features before rule #1
+        "###);
+
+        for filter in [
+            "title = kefir",
+            "price = 10 AND title IN [kefir, milk]",
+            "NOT price = 5",
+            "price IS NULL",
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.validate(&rtxn, &index).unwrap();
+            filter.evaluate(&rtxn, &index).unwrap();
+        }
+    }
+
+    #[test]
+    fn field_usage() {
+        let filter = Filter::from_str(
+            "(price > 10 AND price 20 TO 30) OR NOT (brand = apple OR brand IN [sony, lg]) \
+             AND (title CONTAINS phone OR _geoRadius(45.4, 9.1, 1000)) AND NOT price IS NULL",
+        )
+        .unwrap()
+        .unwrap();
+
+        let expected: BTreeMap<String, BTreeSet<FilterOperator>> = BTreeMap::from([
+            (S(RESERVED_GEO_FIELD_NAME), BTreeSet::from([FilterOperator::GeoRadius])),
+            (S("brand"), BTreeSet::from([FilterOperator::Equal, FilterOperator::In])),
+            (
+                S("price"),
+                BTreeSet::from([
+                    FilterOperator::GreaterThan,
+                    FilterOperator::Between,
+                    FilterOperator::Null,
+                ]),
+            ),
+            (S("title"), BTreeSet::from([FilterOperator::Contains])),
+        ]);
+        assert_eq!(filter.field_usage(), expected);
+        assert!(filter.field_usage()["price"].iter().any(FilterOperator::is_comparison));
+        assert!(!filter.field_usage()["brand"].iter().any(FilterOperator::is_comparison));
+    }
+
+    #[test]
+    fn referenced_values() {
+        let filter = Filter::from_str(
+            "(price > 10 AND price 20 TO 30) OR NOT (brand = apple OR brand IN [sony, lg]) \
+             AND (title CONTAINS phone OR _geoRadius(45.4, 9.1, 1000)) AND NOT price IS NULL \
+             AND _geoBoundingBox([1, 2], [-1, -2]) AND active IS TRUE",
+        )
+        .unwrap()
+        .unwrap();
+
+        let value =
+            |field: &'static str, operator, value: &'static str| (S(field), operator, S(value));
+        assert_eq!(
+            filter.referenced_values(),
+            vec![
+                value("price", FilterOperator::GreaterThan, "10"),
+                value("price", FilterOperator::Between, "20"),
+                value("price", FilterOperator::Between, "30"),
+                value("brand", FilterOperator::Equal, "apple"),
+                value("brand", FilterOperator::In, "sony"),
+                value("brand", FilterOperator::In, "lg"),
+                value("title", FilterOperator::Contains, "phone"),
+                value(RESERVED_GEO_FIELD_NAME, FilterOperator::GeoRadius, "45.4"),
+                value(RESERVED_GEO_FIELD_NAME, FilterOperator::GeoRadius, "9.1"),
+                value(RESERVED_GEO_FIELD_NAME, FilterOperator::GeoRadius, "1000"),
+                value(RESERVED_GEO_FIELD_NAME, FilterOperator::GeoBoundingBox, "1"),
+                value(RESERVED_GEO_FIELD_NAME, FilterOperator::GeoBoundingBox, "2"),
+                value(RESERVED_GEO_FIELD_NAME, FilterOperator::GeoBoundingBox, "-1"),
+                value(RESERVED_GEO_FIELD_NAME, FilterOperator::GeoBoundingBox, "-2"),
+                value("active", FilterOperator::Equal, "true"),
+            ]
+        );
+    }
+
+    #[test]
+    fn static_analysis() {
+        let analysis = |filter: &str| Filter::from_str(filter).unwrap().unwrap().static_analysis();
+        let empty = FilterAnalysis { always_empty: true, always_all: false };
+        let all = FilterAnalysis { always_empty: false, always_all: true };
+        let unknown = FilterAnalysis::default();
+
+        // the string values of the field may lie between inverted number bounds
+        assert_eq!(analysis("price 10 TO 5"), unknown);
+        assert_eq!(analysis("_length(tags) 3 TO 1"), empty);
+        assert_eq!(analysis("_length(tags) 1 TO 3"), unknown);
+        assert_eq!(analysis("_length(tags) 1 TO 1"), unknown);
+        assert_eq!(analysis("NOT _length(tags) 3 TO 1"), all);
+        assert_eq!(analysis("field EXISTS OR field NOT EXISTS"), all);
+        assert_eq!(analysis("field EXISTS AND field NOT EXISTS"), empty);
+        assert_eq!(analysis("field EXISTS AND NOT field EXISTS"), empty);
+        assert_eq!(analysis("field EXISTS OR other NOT EXISTS"), unknown);
+        assert_eq!(
+            analysis("brand = apple AND (_length(tags) 3 TO 1 OR _strlen(name) 20 TO 1)"),
+            empty
+        );
+        assert_eq!(analysis("brand = apple OR _length(tags) 3 TO 1"), unknown);
+        assert_eq!(analysis("brand = apple AND price > 5"), unknown);
+        assert_eq!(analysis("brand IN []"), empty);
+        assert_eq!(analysis("brand NOT IN []"), all);
+        assert_eq!(analysis("_geoRadius(45.4, 9.1, 1000)"), unknown);
+
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("price"))]);
+            })
+            .unwrap();
+        index.add_documents(documents!([{ "id": 0, "price": "3" }])).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("price 10 TO 5").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0]));
+        drop(rtxn);
+
+        let condition = FilterCondition::Or(Vec::new());
+        assert_eq!(Filter::from(condition).static_analysis(), empty);
+        let filter = Filter::from_bitmap(RoaringBitmap::new());
+        assert_eq!(filter.static_analysis(), empty);
+        assert_eq!(
+            filter.and(Filter::from_str("price > 5").unwrap().unwrap()).static_analysis(),
+            empty
+        );
+        let filter = Filter::from_bitmap(RoaringBitmap::from_iter([0]));
+        assert_eq!(
+            filter.and(Filter::from_str("price 10 TO 5").unwrap().unwrap()).static_analysis(),
+            FilterAnalysis { always_empty: false, always_all: false }
+        );
+    }
+
+    #[test]
+    fn broad_contains_warnings() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("brand"))]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "brand": "Apple" },
+                { "id": 1, "brand": "Samsung" },
+                { "id": 2, "brand": "Sony" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let (docids, warnings) = filter
+                .evaluate_with_warnings(&rtxn, &index, DEFAULT_BROAD_CONTAINS_THRESHOLD)
+                .unwrap();
+            // the warnings never change the documents
+            assert_eq!(docids, filter.evaluate(&rtxn, &index).unwrap());
+            (docids, warnings)
+        };
+        let broad =
+            |term: &str| FilterWarning::BroadContains { field: S("brand"), term: term.to_string() };
+
+        let (docids, warnings) = evaluate("brand CONTAINS a");
+        assert_eq!(docids, RoaringBitmap::from_iter([0, 1]));
+        assert_eq!(warnings, vec![broad("a")]);
+
+        let (docids, warnings) = evaluate("brand CONTAINS sung");
+        assert_eq!(docids, RoaringBitmap::from_iter([1]));
+        assert!(warnings.is_empty());
+
+        let (_, warnings) = evaluate("NOT brand STARTS WITH s OR brand = a");
+        assert_eq!(warnings, vec![broad("s")]);
+
+        // the threshold is counted in characters
+        let filter = Filter::from_str("brand CONTAINS \"éé\"").unwrap().unwrap();
+        assert_eq!(filter.warnings(3), vec![broad("éé")]);
+        assert!(filter.warnings(2).is_empty());
+    }
+
+    #[test]
+    fn missing_as_in_range_filters() {
+        let index = TempIndex::new();
+
+        let price_rule: FilterableAttributesRule = serde_json::from_value(serde_json::json!({
+            "attributePatterns": ["price"],
+            "features": { "filter": { "equality": true, "comparison": true, "missingAs": 0 } }
+        }))
+        .unwrap();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("weight")),
+                    price_rule.clone(),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": -5, "weight": -5 },
+                { "id": 1, "price": 10, "weight": 10 },
+                { "id": 2 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in [
+            ("price >= 0", vec![1, 2]),
+            ("price > 0", vec![1]),
+            ("price < 0", vec![0]),
+            ("price <= 0", vec![0, 2]),
+            ("price -1 TO 1", vec![2]),
+            ("NOT price >= 0", vec![0]),
+            ("price = 0", vec![]),
+            // fields without `missingAs` ignore the missing documents
+            ("weight >= 0", vec![1]),
+            ("weight <= 0", vec![0]),
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(docids, RoaringBitmap::from_iter(expected), "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn numeric_only_range_filters() {
+        let index = TempIndex::new();
+
+        let price_rule: FilterableAttributesRule = serde_json::from_value(serde_json::json!({
+            "attributePatterns": ["price"],
+            "features": { "filter": { "equality": true, "comparison": true, "numericOnly": true } }
+        }))
+        .unwrap();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("weight")),
+                    price_rule.clone(),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 5, "weight": 5 },
+                { "id": 1, "price": 500, "weight": 500 },
+                { "id": 2, "price": "2000", "weight": "2000" },
+                { "id": 3, "price": "ten", "weight": "ten" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in [
+            ("price > 10", vec![1]),
+            ("price <= 10", vec![0]),
+            ("price 1 TO 1000", vec![0, 1]),
+            ("price < inf", vec![]),
+            ("price = ten", vec![3]),
+            // the other fields still fall back to comparing their strings
+            ("weight > 10", vec![1, 2, 3]),
+            ("weight < inf", vec![2]),
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(docids, RoaringBitmap::from_iter(expected), "{filter:?}");
+            let estimate = filter.estimate_cardinality(&rtxn, &index).unwrap();
+            assert_eq!(estimate, docids.len(), "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn integer_filters() {
+        let index = TempIndex::new();
+
+        let integer_rule = |field: &str| -> FilterableAttributesRule {
+            serde_json::from_value(serde_json::json!({
+                "attributePatterns": [field],
+                "features": { "filter": { "equality": true, "comparison": true, "integer": true } }
+            }))
+            .unwrap()
+        };
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("float_ref")),
+                    integer_rule("int_ref"),
+                ]);
+            })
+            .unwrap();
+        // 2^53 + 1 can't be represented as an `f64` and is rounded to 2^53
+        index
+            .add_documents(documents!([
+                { "id": 0, "int_ref": 9007199254740992_u64, "float_ref": 9007199254740992_u64 },
+                { "id": 1, "int_ref": 9007199254740993_u64, "float_ref": 9007199254740993_u64 },
+                { "id": 2, "int_ref": 9007199254740994_u64, "float_ref": 9007199254740994_u64 },
+                { "id": 3, "int_ref": [-9007199254740993_i64, 12], "float_ref": [-9007199254740993_i64, 12] },
+                { "id": 4, "int_ref": 12.5, "float_ref": 12.5 },
+            ]))
+            .unwrap();
+
+        let cases = [
+            ("int_ref = 9007199254740993", vec![1]),
+            ("int_ref != 9007199254740993", vec![0, 2, 3, 4]),
+            ("int_ref IN [9007199254740992, -9007199254740993]", vec![0, 3]),
+            ("int_ref > 9007199254740992", vec![1, 2]),
+            ("int_ref >= 9007199254740993", vec![1, 2]),
+            ("int_ref < -9007199254740992", vec![3]),
+            ("int_ref 9007199254740993 TO 9007199254740994", vec![1, 2]),
+            ("int_ref 12 TO 13", vec![3]),
+            // the floats still compare as `f64`s
+            ("int_ref 12 TO 12.5", vec![3, 4]),
+        ];
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in cases.clone() {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(docids, RoaringBitmap::from_iter(expected), "{filter:?}");
+            let estimate = filter.estimate_cardinality(&rtxn, &index).unwrap();
+            assert_eq!(estimate, docids.len(), "{filter:?}");
+        }
+
+        // the `f64`s can't tell the integers beyond 2^53 apart
+        let filter = Filter::from_str("float_ref = 9007199254740993").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0, 1]));
+        drop(rtxn);
+
+        // declaring the field as integer in the settings stores its integers exactly
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    integer_rule("float_ref"),
+                    integer_rule("int_ref"),
+                ]);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in cases {
+            let filter = filter.replace("int_ref", "float_ref");
+            let filter = Filter::from_str(&filter).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(docids, RoaringBitmap::from_iter(expected), "{filter:?}");
+        }
+        drop(rtxn);
+
+        index.delete_documents(vec![S("1")]);
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("int_ref = 9007199254740993").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).unwrap().is_empty());
+    }
+
+    #[test]
+    fn prefixable_numbers() {
+        let index = TempIndex::new();
+
+        let prefixable_rule = |prefixable_numbers: bool| -> FilterableAttributesRule {
+            serde_json::from_value(serde_json::json!({
+                "attributePatterns": ["zip"],
+                "features": { "filter": { "equality": true, "comparison": true, "prefixableNumbers": prefixable_numbers } }
+            }))
+            .unwrap()
+        };
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![prefixable_rule(false)]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "zip": 90001 },
+                { "id": 1, "zip": "90010" },
+                { "id": 2, "zip": [75001, 90002] },
+                { "id": 3, "zip": 19000 },
+                { "id": 4, "zip": 900.5 },
+            ]))
+            .unwrap();
+
+        let cases = [
+            (r#"zip STARTS WITH "900""#, vec![0, 1, 2, 4]),
+            (r#"zip STARTS WITH "9000""#, vec![0, 2]),
+            (r#"zip STARTS WITH "750""#, vec![2]),
+            (r#"zip STARTS WITH "900.""#, vec![4]),
+            (r#"zip STARTS WITH "190""#, vec![3]),
+            // the numbers still compare as numbers, and the strings as strings
+            ("zip > 90000", vec![0, 1, 2]),
+            ("zip = 90001", vec![0]),
+        ];
+
+        // the numbers are not stored as strings by default
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str(r#"zip STARTS WITH "900""#).unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1]));
+        drop(rtxn);
+
+        // declaring the numbers as prefixable in the settings stores them as strings
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![prefixable_rule(true)]);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in cases.clone() {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(docids, RoaringBitmap::from_iter(expected), "{filter:?}");
+        }
+        drop(rtxn);
+
+        // the documents added afterwards are stored as strings too
+        index.add_documents(documents!([{ "id": 5, "zip": 90003 }])).unwrap();
+        index.delete_documents(vec![S("0")]);
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str(r#"zip STARTS WITH "9000""#).unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([2, 5]));
+        drop(rtxn);
+
+        // and removed when the numbers are no longer prefixable
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![prefixable_rule(false)]);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str(r#"zip STARTS WITH "900""#).unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1]));
+    }
+
+    #[test]
+    fn is_date() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S(
+                    "release_date",
+                ))]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "release_date": "2024-03-17" },
+                { "id": 1, "release_date": "2024-03-17T10:00:00Z" },
+                { "id": 2, "release_date": "2023-02-29" },
+                { "id": 3, "release_date": "next week" },
+                { "id": 4, "release_date": ["soon", "2024-12-25"] },
+                { "id": 5, "release_date": 20240317 },
+                { "id": 6, "release_date": null },
+                { "id": 7 },
+                { "id": 8, "published": "2024-01-01" },
+            ]))
+            .unwrap();
+
+        let check = |filter: &str, expected: &[u32]| {
+            let rtxn = index.read_txn().unwrap();
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(docids, RoaringBitmap::from_iter(expected.iter().copied()), "{filter:?}");
+        };
+
+        check("release_date IS DATE", &[0, 1, 4]);
+        check("release_date IS NOT DATE", &[2, 3, 5, 6, 7, 8]);
+        check("NOT release_date IS DATE", &[2, 3, 5, 6, 7, 8]);
+        check("release_date EXISTS AND release_date IS NOT DATE", &[2, 3, 5, 6]);
+
+        // the dates follow the updates and deletions of the documents
+        index
+            .add_documents(documents!([
+                { "id": 0, "release_date": "unknown" },
+                { "id": 3, "release_date": "2025-01-01T00:00:00+02:00" },
+            ]))
+            .unwrap();
+        index.delete_documents(vec![S("1")]);
+        check("release_date IS DATE", &[3, 4]);
+
+        // the fields made filterable afterwards get their dates too
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("release_date")),
+                    FilterableAttributesRule::Field(S("published")),
+                ]);
+            })
+            .unwrap();
+        check("published IS DATE", &[8]);
+        check("release_date IS DATE", &[3, 4]);
+
+        // `IS DATE` is an equality operator
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![serde_json::from_value(serde_json::json!({
+                    "attributePatterns": ["release_date"],
+                    "features": { "filter": { "equality": false, "comparison": true } }
+                }))
+                .unwrap()]);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("release_date IS DATE").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).is_err());
+    }
+
+    #[test]
+    fn approximately() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("price")),
+                    serde_json::from_value(serde_json::json!({
+                        "attributePatterns": ["size"],
+                        "features": { "filter": { "equality": true, "comparison": false } }
+                    }))
+                    .unwrap(),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 19.99, "size": 10 },
+                { "id": 1, "price": 19.990000001 },
+                { "id": 2, "price": 19.980001 },
+                { "id": 3, "price": 19.979 },
+                { "id": 4, "price": 20.001 },
+                { "id": 5, "price": 9.5 },
+                { "id": 6, "price": 10.5 },
+                { "id": 7, "price": 9.4999999 },
+                { "id": 8, "price": 10.5000001 },
+                { "id": 9, "price": "19.99" },
+                { "id": 10, "price": [3, 19.995] },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in [
+            ("price NEAR 19.99 TOLERANCE 0.01", vec![0, 1, 2, 10]),
+            ("price ≈ 19.99 ± 0.01", vec![0, 1, 2, 10]),
+            ("price NEAR 19.99 TOLERANCE 0", vec![0]),
+            // the bounds are included
+            ("price NEAR 10 TOLERANCE 0.5", vec![5, 6]),
+            ("price NEAR 10 TOLERANCE 0.4999999", vec![]),
+            ("price NEAR 10 TOLERANCE 0.5000001", vec![5, 6, 7, 8]),
+            ("NOT price NEAR 19.99 TOLERANCE 0.01", vec![3, 4, 5, 6, 7, 8, 9]),
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(docids, RoaringBitmap::from_iter(expected), "{filter:?}");
+            let estimate = filter.estimate_cardinality(&rtxn, &index).unwrap();
+            assert_eq!(estimate, docids.len(), "{filter:?}");
+        }
+
+        // it is a comparison
+        let filter = Filter::from_str("size NEAR 10 TOLERANCE 1").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err().to_string();
+        assert!(error.starts_with("Filter operator `NEAR` is not allowed"), "{error}");
+    }
+
+    #[test]
+    fn disjoint_ranges_on_multi_valued_fields() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("price"))]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 3 },
+                { "id": 1, "price": 20 },
+                { "id": 2, "price": [1, 20] },
+                { "id": 3, "price": "3" },
+            ]))
+            .unwrap();
+
+        // A conjunction of disjoint ranges on the same field is not always empty:
+        // a document matches as soon as one of its values is in each range, and the
+        // string values are compared lexicographically.
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("price > 10 AND price < 5").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([2, 3]));
+    }
+
+    #[test]
+    fn evaluate_cancellable() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("id"))]);
+            })
+            .unwrap();
+        let documents: Vec<_> = (0..100).map(|id| serde_json::json!({ "id": id })).collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let expression = (0..1000).map(|id| format!("id = {id}")).collect::<Vec<_>>().join(" OR ");
+        let filter = Filter::from_str(&expression).unwrap().unwrap();
+
+        // an unset flag does not change the result
+        let should_stop = Arc::new(AtomicBool::new(false));
+        assert_eq!(
+            filter.evaluate_cancellable(&rtxn, &index, &should_stop).unwrap(),
+            filter.evaluate(&rtxn, &index).unwrap()
+        );
+
+        // setting the flag interrupts an evaluation in progress
+        let handle = std::thread::spawn({
+            let should_stop = should_stop.clone();
+            move || {
+                std::thread::sleep(Duration::from_millis(10));
+                should_stop.store(true, Ordering::Relaxed);
+            }
+        });
+        let error = loop {
+            match filter.evaluate_cancellable(&rtxn, &index, &should_stop) {
+                Ok(docids) => assert_eq!(docids.len(), 100),
+                Err(error) => break error,
+            }
+        };
+        handle.join().unwrap();
+        assert!(matches!(error, crate::Error::Interrupted), "{error:?}");
+    }
+
+    #[test]
+    fn partition() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("price")),
+                    FilterableAttributesRule::Field(S("brand")),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 20, "brand": "apple" },
+                { "id": 1, "price": 20, "brand": "nokia" },
+                { "id": 2, "price": 5, "brand": "samsung" },
+                { "id": 3, "price": 12 },
+                { "id": 4, "brand": "apple" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let all = index.documents_ids(&rtxn).unwrap();
+        let universes =
+            [None, Some(RoaringBitmap::from_iter([0, 2, 4])), Some(RoaringBitmap::new())];
+        for expression in [
+            "price > 10",
+            "NOT brand = apple",
+            "price > 10 AND brand EXISTS",
+            "brand = samsung OR price = 12",
+            "price NOT EXISTS",
+            "brand = unknown",
+        ] {
+            let filter = Filter::from_str(expression).unwrap().unwrap();
+            for universe in &universes {
+                let (matched, unmatched) =
+                    filter.partition(&rtxn, &index, universe.as_ref()).unwrap();
+                let universe = universe.as_ref().unwrap_or(&all);
+                assert!(matched.is_disjoint(&unmatched), "{expression}");
+                assert_eq!(&(&matched | &unmatched), universe, "{expression}");
+                assert_eq!(
+                    matched,
+                    filter.evaluate(&rtxn, &index).unwrap() & universe,
+                    "{expression}"
+                );
+            }
+        }
+
+        let filter = Filter::from_str("NOT brand = apple").unwrap().unwrap();
+        let (matched, unmatched) =
+            filter.partition(&rtxn, &index, Some(&RoaringBitmap::from_iter([0, 1, 3]))).unwrap();
+        assert_eq!(matched, RoaringBitmap::from_iter([1, 3]));
+        assert_eq!(unmatched, RoaringBitmap::from_iter([0]));
+    }
+
+    #[test]
+    fn borrow_condition() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("price"))]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 20 },
+                { "id": 1, "price": 5 },
+                { "id": 2, "price": 12 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("NOT price < 10").unwrap().unwrap();
+        let FilterCondition::Not(negated) = filter.condition() else {
+            panic!("{:?}", filter.condition());
+        };
+        let FilterCondition::Condition { fid, op: Condition::LowerThan(value) } = negated.as_ref()
+        else {
+            panic!("{negated:?}");
+        };
+        assert_eq!((fid.value(), value.value()), ("price", "10"));
+
+        // the filter is still usable after borrowing its condition
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0, 2]));
+        assert_eq!(FilterCondition::from(filter.clone()), *filter.condition());
+    }
+
+    #[test]
+    fn boolean_fields() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("active")),
+                    FilterableAttributesRule::Field(S("user.verified")),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "active": true },
+                { "id": 1, "active": false },
+                { "id": 2, "active": [true, false] },
+                { "id": 3 },
+                { "id": 4, "active": null },
+                { "id": 5, "active": "TRUE" },
+                { "id": 6, "active": [true, "false"], "user": { "verified": true } },
+                { "id": 7, "active": "true", "user": [{ "verified": "true" }, { "verified": false }] },
+                { "id": 8, "user.verified": true, "enabled": [false, true] },
+            ]))
+            .unwrap();
+
+        let evaluate = |filter: &str| {
+            let rtxn = index.read_txn().unwrap();
+            Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+        };
+
+        // the documents missing a value are never matched
+        assert_eq!(evaluate("active = true"), RoaringBitmap::from_iter([0, 2, 5, 6, 7]));
+        assert_eq!(evaluate("active = false"), RoaringBitmap::from_iter([1, 2, 6]));
+        // unlike the equality, only the booleans are true or false
+        assert_eq!(evaluate("active IS TRUE"), RoaringBitmap::from_iter([0, 2, 6]));
+        assert_eq!(evaluate("active IS FALSE"), RoaringBitmap::from_iter([1, 2]));
+        assert_eq!(evaluate("NOT active IS TRUE"), RoaringBitmap::from_iter([1, 3, 4, 5, 7, 8]));
+        // the dotted fields are either nested or top-level keys
+        assert_eq!(evaluate("user.verified IS TRUE"), RoaringBitmap::from_iter([6, 8]));
+        assert_eq!(evaluate("user.verified IS FALSE"), RoaringBitmap::from_iter([7]));
+
+        // the updated and deleted documents leave the booleans they held
+        index
+            .add_documents(documents!([
+                { "id": 0, "active": false },
+                { "id": 2, "active": "true" },
+            ]))
+            .unwrap();
+        index.delete_document("1");
+        assert_eq!(evaluate("active IS TRUE"), RoaringBitmap::from_iter([6]));
+        assert_eq!(evaluate("active IS FALSE"), RoaringBitmap::from_iter([0]));
+
+        // so are the booleans of the fields made filterable afterward
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("active")),
+                    FilterableAttributesRule::Field(S("enabled")),
+                ]);
+            })
+            .unwrap();
+        assert_eq!(evaluate("enabled IS TRUE"), RoaringBitmap::from_iter([8]));
+        assert_eq!(evaluate("enabled IS FALSE"), RoaringBitmap::from_iter([8]));
+        assert_eq!(evaluate("active IS TRUE"), RoaringBitmap::from_iter([6]));
+    }
+
+    #[test]
+    fn contains_all() {
+        let index = TempIndex::new();
+
+        let color_rule: FilterableAttributesRule = serde_json::from_value(serde_json::json!({
+            "attributePatterns": ["color"],
+            "features": { "filter": { "equality": false, "comparison": true } }
+        }))
+        .unwrap();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("tags")),
+                    color_rule.clone(),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "tags": ["a", "b", "c"], "color": "red" },
+                { "id": 1, "tags": ["a", "b"] },
+                { "id": 2, "tags": ["a"] },
+                { "id": 3, "tags": ["b", "c"] },
+                { "id": 4, "tags": [] },
+                { "id": 5 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate =
+            |filter: &str| Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index);
+
+        // `IN` matches the documents containing any of the values, `CONTAINS ALL` every one of them
+        assert_eq!(evaluate("tags IN [a, b]").unwrap(), RoaringBitmap::from_iter([0, 1, 2, 3]));
+        assert_eq!(evaluate("tags CONTAINS ALL [a, b]").unwrap(), RoaringBitmap::from_iter([0, 1]));
+        assert_eq!(evaluate("tags CONTAINS ALL [a, b, c]").unwrap(), RoaringBitmap::from_iter([0]));
+        assert_eq!(evaluate("tags CONTAINS ALL [b]").unwrap(), RoaringBitmap::from_iter([0, 1, 3]));
+        assert!(evaluate("tags CONTAINS ALL [a, d]").unwrap().is_empty());
+        assert!(evaluate("tags CONTAINS ALL []").unwrap().is_empty());
+        assert_eq!(
+            evaluate("NOT tags CONTAINS ALL [a, b]").unwrap(),
+            RoaringBitmap::from_iter([2, 3, 4, 5])
+        );
+
+        // the values are compared like the equality filter
+        let error = evaluate("color CONTAINS ALL [red]").unwrap_err();
+        assert!(error.to_string().starts_with("Filter operator `=` is not allowed"), "{error}");
+    }
+
+    #[test]
+    fn signed_zero() {
+        let documents = || {
+            documents!([
+                { "id": 0, "price": -0.0 },
+                { "id": 1, "price": 0.0 },
+                { "id": 2, "price": 0 },
+                { "id": 3, "price": -1.5 },
+                { "id": 4, "price": 1.5 },
+            ])
+        };
+        let set_filterable = |settings: &mut Settings<'_, '_, '_>| {
+            settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("price"))]);
+        };
+
+        // the documents are extracted while indexing them and while reindexing the settings
+        let indexed = TempIndex::new();
+        indexed.update_settings(set_filterable).unwrap();
+        indexed.add_documents(documents()).unwrap();
+        let reindexed = TempIndex::new();
+        reindexed.add_documents(documents()).unwrap();
+        reindexed.update_settings(set_filterable).unwrap();
+
+        for index in [indexed, reindexed] {
+            let rtxn = index.read_txn().unwrap();
+            let evaluate = |filter: &str| {
+                Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+            };
+
+            assert_eq!(evaluate("price = 0"), RoaringBitmap::from_iter([0, 1, 2]));
+            assert_eq!(evaluate("price = -0"), RoaringBitmap::from_iter([0, 1, 2]));
+            assert_eq!(evaluate("price != 0"), RoaringBitmap::from_iter([3, 4]));
+            assert_eq!(evaluate("price IN [-0.0, 1.5]"), RoaringBitmap::from_iter([0, 1, 2, 4]));
+            assert_eq!(evaluate("price >= 0"), RoaringBitmap::from_iter([0, 1, 2, 4]));
+            assert_eq!(evaluate("price <= -0"), RoaringBitmap::from_iter([0, 1, 2, 3]));
+            assert_eq!(evaluate("price > 0"), RoaringBitmap::from_iter([4]));
+            assert_eq!(evaluate("price < -0"), RoaringBitmap::from_iter([3]));
+            assert_eq!(evaluate("price -0 TO 0"), RoaringBitmap::from_iter([0, 1, 2]));
+            assert_eq!(evaluate("price -1 TO 1"), RoaringBitmap::from_iter([0, 1, 2]));
+
+            // both zeros are a single facet value
+            let field_id = index.fields_ids_map(&rtxn).unwrap().id("price").unwrap();
+            let mut level_zero = field_id.to_be_bytes().to_vec();
+            level_zero.push(0);
+            let values = index
+                .facet_id_f64_docids
+                .remap_key_type::<Bytes>()
+                .prefix_iter(&rtxn, &level_zero)
+                .unwrap()
+                .count();
+            assert_eq!(values, 3);
+        }
+    }
+
+    #[test]
+    fn facet_normalization_locale() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("city"))]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "city": "İSTANBUL" },
+                { "id": 1, "city": "ISPARTA" },
+                { "id": 2, "city": "istanbul" },
+            ]))
+            .unwrap();
+
+        let evaluate = |filter: &str| {
+            let rtxn = index.read_txn().unwrap();
+            Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+        };
+
+        // without a locale the capital dotted i keeps its dot
+        assert_eq!(evaluate("city = istanbul"), RoaringBitmap::from_iter([2]));
+        assert_eq!(evaluate("city = ISTANBUL"), RoaringBitmap::from_iter([2]));
+        assert_eq!(evaluate("city = isparta"), RoaringBitmap::from_iter([1]));
+        assert!(evaluate("city = ısparta").is_empty());
+
+        // the documents are reindexed with the Turkish casing rules
+        index
+            .update_settings(|settings| settings.set_facet_normalization_locale(Language::Tur))
+            .unwrap();
+        assert_eq!(evaluate("city = istanbul"), RoaringBitmap::from_iter([0, 2]));
+        assert_eq!(evaluate("city = İstanbul"), RoaringBitmap::from_iter([0, 2]));
+        assert_eq!(evaluate("city = ısparta"), RoaringBitmap::from_iter([1]));
+        assert_eq!(evaluate("city = ISPARTA"), RoaringBitmap::from_iter([1]));
+        assert!(evaluate("city = isparta").is_empty());
+        assert_eq!(evaluate("city != istanbul"), RoaringBitmap::from_iter([1]));
+
+        // and so are the new documents
+        index.add_documents(documents!([{ "id": 3, "city": "IZMIR" }])).unwrap();
+        assert_eq!(evaluate("city IN [ızmır, istanbul]"), RoaringBitmap::from_iter([0, 2, 3]));
+
+        index.update_settings(|settings| settings.reset_facet_normalization_locale()).unwrap();
+        assert_eq!(evaluate("city = istanbul"), RoaringBitmap::from_iter([2]));
+        assert_eq!(evaluate("city = izmir"), RoaringBitmap::from_iter([3]));
     }
-}
 
-fn generate_filter_error(
-    rtxn: &heed::RoTxn<'_>,
-    index: &Index,
-    field_id: FieldId,
-    operator: &Condition<'_>,
-    features: &FilterableAttributesFeatures,
-    rule_index: usize,
-) -> Error {
-    match index.fields_ids_map(rtxn) {
-        Ok(fields_ids_map) => {
-            let field = fields_ids_map.name(field_id).unwrap_or_default();
-            Error::UserError(UserError::FilterOperatorNotAllowed {
-                field: field.to_string(),
-                allowed_operators: features.allowed_filter_operators(),
-                operator: operator.operator().to_string(),
-                rule_index,
+    #[test]
+    fn in_deduplicates_elements() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("status"))]);
             })
-        }
-        Err(e) => e.into(),
-    }
-}
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "status": "active" },
+                { "id": 1, "status": "Open" },
+                { "id": 2, "status": "closed" },
+                { "id": 3, "status": 1 },
+            ]))
+            .unwrap();
 
-impl<'a> From<FilterCondition<'a>> for Filter<'a> {
-    fn from(fc: FilterCondition<'a>) -> Self {
-        Self { condition: fc }
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+        };
+        let equalities = |els: &[&str]| -> Vec<String> {
+            let els: Vec<Token> = els.iter().copied().map(Token::from).collect();
+            distinct_equalities(&els, None).iter().map(|condition| condition.to_string()).collect()
+        };
+
+        assert_eq!(
+            evaluate("status IN [active, Active, open, ' ACTIVE ', OPEN, active]"),
+            RoaringBitmap::from_iter([0, 1])
+        );
+        assert_eq!(
+            equalities(&["active", "Active", "open", " ACTIVE ", "OPEN", "active"]),
+            ["= {active}", "= {open}"]
+        );
+        assert_eq!(evaluate("status IN [1, 1, closed]"), RoaringBitmap::from_iter([2, 3]));
+        assert_eq!(equalities(&["1", "1", "closed"]), ["= {1}", "= {closed}"]);
+        // the same number written differently is looked up as different strings
+        assert_eq!(evaluate("status IN [1, 1.0, 01]"), RoaringBitmap::from_iter([3]));
+        assert_eq!(equalities(&["1", "1.0", "01"]), ["= {1}", "= {1.0}", "= {01}"]);
+        assert_eq!(evaluate("NOT status IN [active, ACTIVE]"), RoaringBitmap::from_iter([1, 2, 3]));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::fmt::Write;
-    use std::iter::FromIterator;
+    #[test]
+    fn allowed_fields() {
+        let index = TempIndex::new();
 
-    use big_s::S;
-    use either::Either;
-    use meili_snap::snapshot;
-    use roaring::RoaringBitmap;
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("tenant")),
+                    FilterableAttributesRule::Field(S("price")),
+                    FilterableAttributesRule::Field(S(RESERVED_GEO_FIELD_NAME)),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "tenant": "a", "price": 10, "_geo": { "lat": 0.0, "lng": 0.0 } },
+                { "id": 1, "tenant": "a", "price": 20 },
+                { "id": 2, "tenant": "b", "price": 10 },
+            ]))
+            .unwrap();
 
-    use crate::constants::RESERVED_GEO_FIELD_NAME;
-    use crate::index::tests::TempIndex;
-    use crate::{Filter, FilterableAttributesRule};
+        let rtxn = index.read_txn().unwrap();
+        let allowed = BTreeSet::from(["tenant"]);
+        let evaluate = |filter: &str| {
+            Filter::from_str(filter)
+                .unwrap()
+                .unwrap()
+                .evaluate_with_allowed_fields(&rtxn, &index, &allowed)
+        };
+
+        assert_eq!(evaluate("tenant = a").unwrap(), RoaringBitmap::from_iter([0, 1]));
+
+        // `price` is filterable but not allowed
+        let error = evaluate("tenant = a AND NOT price > 15").unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Attribute `price` is not filterable. Available filterable attribute patterns are: `tenant`.
+        20:25 tenant = a AND NOT price > 15
+        "###);
+        assert!(Filter::from_str("price > 15").unwrap().unwrap().evaluate(&rtxn, &index).is_ok());
+
+        let error = evaluate("_geoRadius(0, 0, 10)").unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `_geo` is not filterable."), "{error}");
+
+        // the allowed fields must still be filterable in the index
+        let allowed = BTreeSet::from(["tenant", "title"]);
+        let filter = Filter::from_str("title = a").unwrap().unwrap();
+        let error = filter.evaluate_with_allowed_fields(&rtxn, &index, &allowed).unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `title` is not filterable."), "{error}");
+    }
 
     #[test]
-    fn empty_db() {
+    fn evaluate_strict() {
         let index = TempIndex::new();
-        //Set the filterable fields to be the channel.
+
         index
             .update_settings(|settings| {
-                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(
-                    "PrIcE".to_string(),
-                )]);
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("dimensions.width")),
+                    FilterableAttributesRule::Field(S("size.width")),
+                ]);
             })
             .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "size": { "width": 10 } },
+                { "id": 1, "size": { "width": 20 } },
+            ]))
+            .unwrap();
 
         let rtxn = index.read_txn().unwrap();
 
-        let filter = Filter::from_str("PrIcE < 1000").unwrap().unwrap();
-        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(bitmap.is_empty());
+        let filter = Filter::from_str("size.width = 10").unwrap().unwrap();
+        assert_eq!(filter.evaluate_strict(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0]));
 
-        let filter = Filter::from_str("NOT PrIcE >= 1000").unwrap().unwrap();
-        let bitmap = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(bitmap.is_empty());
+        // no document has a `dimensions` object
+        let filter = Filter::from_str("size.width = 10 OR dimensions.width = 10").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0]));
+        let error = filter.evaluate_strict(&rtxn, &index).unwrap_err();
+        snapshot!(error.to_string(), @r###"
+        Attribute `dimensions.width` is filterable but is not present in any document of the index.
+        20:36 size.width = 10 OR dimensions.width = 10
+        "###);
+
+        // the fields that are not filterable are still rejected first
+        let filter = Filter::from_str("depth = 10").unwrap().unwrap();
+        let error = filter.evaluate_strict(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `depth` is not filterable."), "{error}");
     }
 
     #[test]
-    fn from_array() {
-        // Simple array with Left
-        let condition = Filter::from_array(vec![Either::Left(["channel = mv"])]).unwrap().unwrap();
-        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
-        assert_eq!(condition, expected);
+    fn length() {
+        let index = TempIndex::new();
 
-        // Simple array with Right
-        let condition = Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = mv")])
-            .unwrap()
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("tags")),
+                    FilterableAttributesRule::Field(S("length")),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "tags": [] },
+                { "id": 1, "tags": ["a"], "length": 2 },
+                { "id": 2, "tags": ["a", 2] },
+                { "id": 3, "tags": ["a", "b", "c"] },
+                { "id": 4, "tags": [1, 2, 3, "d"] },
+                { "id": 5, "tags": ["a", "b", "c", "d", "e"] },
+                // the facet values are deduplicated
+                { "id": 6, "tags": ["a", "A", "b"] },
+                { "id": 7, "title": "no tags" },
+            ]))
             .unwrap();
-        let expected = Filter::from_str("channel = mv").unwrap().unwrap();
-        assert_eq!(condition, expected);
 
-        // Array with Left and escaped quote
-        let condition =
-            Filter::from_array(vec![Either::Left(["channel = \"Mister Mv\""])]).unwrap().unwrap();
-        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+        };
 
-        // Array with Right and escaped quote
-        let condition =
-            Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = \"Mister Mv\"")])
-                .unwrap()
-                .unwrap();
-        let expected = Filter::from_str("channel = \"Mister Mv\"").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        assert_eq!(evaluate("_length(tags) = 0"), RoaringBitmap::from_iter([0]));
+        assert_eq!(evaluate("_length(tags) = 2"), RoaringBitmap::from_iter([2, 6]));
+        assert_eq!(evaluate("_length(tags) != 1"), RoaringBitmap::from_iter([0, 2, 3, 4, 5, 6, 7]));
+        assert_eq!(evaluate("_length(tags) < 2"), RoaringBitmap::from_iter([0, 1]));
+        assert_eq!(evaluate("_length(tags) <= 2"), RoaringBitmap::from_iter([0, 1, 2, 6]));
+        assert_eq!(evaluate("_length(tags) > 3"), RoaringBitmap::from_iter([4, 5]));
+        assert_eq!(evaluate("_length(tags) >= 3"), RoaringBitmap::from_iter([3, 4, 5]));
+        assert_eq!(evaluate("_length(tags) 1 TO 3"), RoaringBitmap::from_iter([1, 2, 3, 6]));
+        assert_eq!(evaluate("NOT _length(tags) > 0"), RoaringBitmap::from_iter([0, 7]));
+        assert!(evaluate("_length(tags) > 5").is_empty());
+        // a field named like the function is still a field
+        assert_eq!(evaluate("length = 2"), RoaringBitmap::from_iter([1]));
+
+        // only the documents of the universe are counted
+        let filter = Filter::from_str("_length(tags) != 1").unwrap().unwrap();
+        let universe = RoaringBitmap::from_iter([1, 2, 3]);
+        let (matched, unmatched) = filter.partition(&rtxn, &index, Some(&universe)).unwrap();
+        assert_eq!(matched, RoaringBitmap::from_iter([2, 3]));
+        assert_eq!(unmatched, RoaringBitmap::from_iter([1]));
+
+        let filter = Filter::from_str("_length(tags) = many").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).is_err());
+        let filter = Filter::from_str("_length(title) = 2").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `title` is not filterable."), "{error}");
+    }
 
-        // Array with Left and escaped simple quote
-        let condition =
-            Filter::from_array(vec![Either::Left(["channel = 'Mister Mv'"])]).unwrap().unwrap();
-        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
-        assert_eq!(condition, expected);
+    #[test]
+    fn string_length() {
+        let index = TempIndex::new();
 
-        // Array with Right and escaped simple quote
-        let condition =
-            Filter::from_array::<_, Option<&str>>(vec![Either::Right("channel = 'Mister Mv'")])
-                .unwrap()
-                .unwrap();
-        let expected = Filter::from_str("channel = 'Mister Mv'").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        let rule = |string_length: bool| -> FilterableAttributesRule {
+            serde_json::from_value(serde_json::json!({
+                "attributePatterns": ["sku"],
+                "features": { "filter": { "equality": true, "stringLength": string_length } }
+            }))
+            .unwrap()
+        };
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![rule(true)]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "sku": "" },
+                { "id": 1, "sku": "AB-12345" },
+                { "id": 2, "sku": "ab-1234" },
+                // the length is counted in characters, not in bytes
+                { "id": 3, "sku": "ÉCRAN-01" },
+                { "id": 4, "sku": ["abc", "ABCDEFGHIJ"] },
+                { "id": 5, "sku": 12345678 },
+                { "id": 6, "title": "no sku" },
+            ]))
+            .unwrap();
 
-        // Simple with parenthesis
-        let condition =
-            Filter::from_array(vec![Either::Left(["(channel = mv)"])]).unwrap().unwrap();
-        let expected = Filter::from_str("(channel = mv)").unwrap().unwrap();
-        assert_eq!(condition, expected);
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+        };
 
-        // Test that the facet condition is correctly generated.
-        let condition = Filter::from_array(vec![
-            Either::Right("channel = gotaga"),
-            Either::Left(vec!["timestamp = 44", "channel != ponce"]),
-        ])
-        .unwrap()
-        .unwrap();
-        let expected =
-            Filter::from_str("channel = gotaga AND (timestamp = 44 OR channel != ponce)")
-                .unwrap()
-                .unwrap();
-        assert_eq!(condition, expected);
+        assert_eq!(evaluate("_strlen(sku) = 8"), RoaringBitmap::from_iter([1, 3]));
+        // the empty strings aren't facet values, `sku IS EMPTY` selects them
+        assert!(evaluate("_strlen(sku) = 0").is_empty());
+        assert_eq!(evaluate("_strlen(sku) != 8"), RoaringBitmap::from_iter([0, 2, 4, 5, 6]));
+        assert_eq!(evaluate("_strlen(sku) < 7"), RoaringBitmap::from_iter([4]));
+        assert_eq!(evaluate("_strlen(sku) >= 8"), RoaringBitmap::from_iter([1, 3, 4]));
+        assert_eq!(evaluate("_strlen(sku) 3 TO 7"), RoaringBitmap::from_iter([2, 4]));
+        assert!(evaluate("_strlen(sku) > 10").is_empty());
+
+        // only the documents of the universe are read
+        let filter = Filter::from_str("_strlen(sku) = 8").unwrap().unwrap();
+        let universe = RoaringBitmap::from_iter([0, 1, 2]);
+        let (matched, unmatched) = filter.partition(&rtxn, &index, Some(&universe)).unwrap();
+        assert_eq!(matched, RoaringBitmap::from_iter([1]));
+        assert_eq!(unmatched, RoaringBitmap::from_iter([0, 2]));
+
+        let filter = Filter::from_str("_strlen(sku) IS EMPTY");
+        assert!(filter.is_err() || filter.unwrap().unwrap().evaluate(&rtxn, &index).is_err());
+        let filter = Filter::from_str("_strlen(sku) = long").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).is_err());
+
+        drop(rtxn);
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![rule(false)]);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("_strlen(sku) = 8").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("Filtering on the length of the strings of `sku` is not allowed."),
+            "{error}"
+        );
     }
 
     #[test]
-    fn not_filterable() {
+    fn fuzzy_equal() {
         let index = TempIndex::new();
 
+        let rule = |fuzzy_equal: bool| -> FilterableAttributesRule {
+            serde_json::from_value(serde_json::json!({
+                "attributePatterns": ["brand"],
+                "features": { "filter": { "equality": true, "fuzzyEqual": fuzzy_equal } }
+            }))
+            .unwrap()
+        };
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![rule(true)]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "brand": "samsung" },
+                { "id": 1, "brand": "SAMSONG" },
+                { "id": 2, "brand": "sansung" },
+                { "id": 3, "brand": ["sony", "samsng"] },
+                { "id": 4, "brand": "samsung electronics" },
+                { "id": 5, "brand": "sam" },
+                { "id": 6, "title": "no brand" },
+            ]))
+            .unwrap();
+
         let rtxn = index.read_txn().unwrap();
-        let filter = Filter::from_str("_geoRadius(42, 150, 10)").unwrap().unwrap();
+        let evaluate = |filter: &str| {
+            Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+        };
+
+        // six characters are allowed a single typo by default
+        assert_eq!(evaluate("brand ~= samsng"), RoaringBitmap::from_iter([0, 1, 3]));
+        assert_eq!(evaluate("brand ~= Samsng DISTANCE 0"), RoaringBitmap::from_iter([3]));
+        assert_eq!(evaluate("brand ~= samsng DISTANCE 2"), RoaringBitmap::from_iter([0, 1, 2, 3]));
+        // too short for a typo by default
+        assert_eq!(evaluate("brand ~= sim"), RoaringBitmap::new());
+        assert_eq!(evaluate("brand ~= sim DISTANCE 1"), RoaringBitmap::from_iter([5]));
+        assert_eq!(evaluate("NOT brand ~= samsng"), RoaringBitmap::from_iter([2, 4, 5, 6]));
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.set_filter_scan_limit(3);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("brand ~= samsng").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("The filter scanned more than the 3 facet values"));
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.reset_filter_scan_limit();
+                settings.set_filterable_fields(vec![rule(false)]);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("brand ~= samsng").unwrap().unwrap();
         let error = filter.evaluate(&rtxn, &index).unwrap_err();
         snapshot!(error.to_string(), @r###"
-        Attribute `_geo` is not filterable. This index does not have configured filterable attributes.
-        12:14 _geoRadius(42, 150, 10)
+        Filter operator `~=` is not allowed for the attribute `brand`.
+          - Note: allowed operators: OR, AND, NOT, =, !=, IN, IS EMPTY, IS NULL, EXISTS.
+          - Note: field `brand` matched rule #0 in `filterableAttributes`
+          - Hint: enable fuzzyEqual in rule #0 by modifying the features.filter object
+          - Hint: prepend another rule matching `brand` with appropriate filter features before rule #0
         "###);
+    }
 
-        let filter = Filter::from_str("_geoBoundingBox([42, 150], [30, 10])").unwrap().unwrap();
+    #[test]
+    fn regex() {
+        let index = TempIndex::new();
+
+        let rule = |regex: bool| -> FilterableAttributesRule {
+            serde_json::from_value(serde_json::json!({
+                "attributePatterns": ["sku"],
+                "features": { "filter": { "equality": true, "regex": regex } }
+            }))
+            .unwrap()
+        };
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![rule(true)]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "sku": "A1234" },
+                { "id": 1, "sku": "a9876" },
+                { "id": 2, "sku": "A12345" },
+                { "id": 3, "sku": ["B1234", "XA1234"] },
+                { "id": 4, "sku": 1234 },
+                { "id": 5, "title": "no sku" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+        };
+
+        assert_eq!(evaluate(r#"sku MATCHES "^A[0-9]{4}$""#), RoaringBitmap::from_iter([0, 1]));
+        // an unanchored pattern matches anywhere in the value
+        assert_eq!(evaluate("sku MATCHES 'A[0-9]{4}'"), RoaringBitmap::from_iter([0, 1, 2, 3]));
+        assert_eq!(evaluate("sku MATCHES '^[0-9]+$'"), RoaringBitmap::new());
+        assert_eq!(
+            evaluate(r#"NOT sku MATCHES "^A[0-9]{4}$""#),
+            RoaringBitmap::from_iter([2, 3, 4, 5])
+        );
+
+        // the patterns are compiled once per evaluation
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let filterable_attributes_rules = index.filterable_attributes_rules(&rtxn).unwrap();
+        let filter =
+            Filter::from_str("sku MATCHES '^a' OR (sku MATCHES '^a' AND NOT sku MATCHES '5$')")
+                .unwrap()
+                .unwrap();
+        let cache = EvaluationCache::new(&rtxn, &index);
+        let docids = filter
+            .inner_evaluate(
+                &rtxn,
+                &index,
+                &fields_ids_map,
+                &filterable_attributes_rules,
+                None,
+                &cache,
+                &AtomicBool::new(false),
+            )
+            .unwrap();
+        assert_eq!(docids, RoaringBitmap::from_iter([0, 1, 2]));
+        assert_eq!(cache.regexes.borrow().len(), 2);
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.set_filter_scan_limit(3);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("sku MATCHES '^a'").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("The filter scanned more than the 3 facet values"));
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.reset_filter_scan_limit();
+                settings.set_filterable_fields(vec![rule(false)]);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("sku MATCHES '^a'").unwrap().unwrap();
         let error = filter.evaluate(&rtxn, &index).unwrap_err();
         snapshot!(error.to_string(), @r###"
-        Attribute `_geo` is not filterable. This index does not have configured filterable attributes.
-        18:20 _geoBoundingBox([42, 150], [30, 10])
+        Filter operator `MATCHES` is not allowed for the attribute `sku`.
+          - Note: allowed operators: OR, AND, NOT, =, !=, IN, IS EMPTY, IS NULL, EXISTS.
+          - Note: field `sku` matched rule #0 in `filterableAttributes`
+          - Hint: enable regex in rule #0 by modifying the features.filter object
+          - Hint: prepend another rule matching `sku` with appropriate filter features before rule #0
         "###);
 
-        let filter = Filter::from_str("dog = \"bernese mountain\"").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        let error = Filter::from_str("sku MATCHES '(a'").unwrap_err();
         snapshot!(error.to_string(), @r###"
-        Attribute `dog` is not filterable. This index does not have configured filterable attributes.
-        1:4 dog = "bernese mountain"
+        The pattern of a `MATCHES` filter must be a valid regex but `(a` was given.
+        regex parse error:
+            (a
+            ^
+        error: unclosed group
+        14:16 sku MATCHES '(a'
         "###);
-        drop(rtxn);
+    }
+
+    #[test]
+    fn modulo() {
+        let index = TempIndex::new();
 
         index
             .update_settings(|settings| {
-                settings.set_searchable_fields(vec![S("title")]);
-                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(
-                    "title".to_string(),
-                )]);
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(vec![
+                    serde_json::from_value(serde_json::json!({
+                        "attributePatterns": ["id", "price", "name"],
+                        "features": { "filter": { "comparison": true, "modulo": true } }
+                    }))
+                    .unwrap(),
+                    FilterableAttributesRule::Field("title".to_string()),
+                ]);
             })
             .unwrap();
 
+        let mut docs = vec![];
+        for i in 0..100 {
+            docs.push(serde_json::json!({ "id": i, "name": format!("doc{i}"), "title": "t" }));
+        }
+        docs.push(serde_json::json!({ "id": 100, "price": -3 }));
+        docs.push(serde_json::json!({ "id": 101, "price": [4.5, 6] }));
+        docs.push(serde_json::json!({ "id": 102, "price": "7" }));
+        index.add_documents(documents!(docs)).unwrap();
+
         let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+        };
 
-        let filter = Filter::from_str("_geoRadius(-100, 150, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        snapshot!(error.to_string(), @r###"
-        Attribute `_geo` is not filterable. Available filterable attribute patterns are: `title`.
-        12:16 _geoRadius(-100, 150, 10)
+        assert_eq!(evaluate("id MOD 10 = 0"), RoaringBitmap::from_iter((0..=100).step_by(10)));
+        assert_eq!(evaluate("id MOD 2 = 1 AND id < 10"), RoaringBitmap::from_iter([1, 3, 5, 7, 9]));
+        assert_eq!(evaluate("id MOD 25 = 30"), RoaringBitmap::new());
+        assert_eq!(
+            evaluate("NOT id MOD 3 = 0 AND id < 10"),
+            RoaringBitmap::from_iter([1, 2, 4, 5, 7, 8])
+        );
+        // only the integers are selected, a negative number has a negative remainder
+        assert_eq!(evaluate("price MOD 2 = 0"), RoaringBitmap::from_iter([101]));
+        assert_eq!(evaluate("price MOD 2 = -1"), RoaringBitmap::from_iter([100]));
+        assert_eq!(evaluate("price MOD 7 = 0"), RoaringBitmap::new());
+
+        let error = Filter::from_str("name MOD 2 = 0").unwrap().unwrap().evaluate(&rtxn, &index);
+        snapshot!(error.unwrap_err().to_string(), @r###"
+        The `MOD` operator can only be applied to numbers but the attribute `name` only contains strings.
+        10:11 name MOD 2 = 0
         "###);
 
-        let filter = Filter::from_str("_geoBoundingBox([42, 150], [30, 10])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        snapshot!(error.to_string(), @r###"
-        Attribute `_geo` is not filterable. Available filterable attribute patterns are: `title`.
-        18:20 _geoBoundingBox([42, 150], [30, 10])
+        // the `MOD` operator must be enabled on the field
+        let error = Filter::from_str("title MOD 2 = 0").unwrap().unwrap().evaluate(&rtxn, &index);
+        snapshot!(error.unwrap_err().to_string(), @r###"
+        Filter operator `MOD` is not allowed for the attribute `title`.
+          - Note: allowed operators: OR, AND, NOT, =, !=, IN, <, >, <=, >=, TO, IS EMPTY, IS NULL, EXISTS.
+          - Note: field `title` matched rule #1 in `filterableAttributes`
+          - Hint: enable modulo in rule #1 by modifying the features.filter object
+          - Hint: prepend another rule matching `title` with appropriate filter features before rule #1
         "###);
 
-        let filter = Filter::from_str("name = 12").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        let error = Filter::from_str("id MOD 0 = 0").unwrap_err();
         snapshot!(error.to_string(), @r###"
-        Attribute `name` is not filterable. Available filterable attribute patterns are: `title`.
-        1:5 name = 12
+        The divisor of a `MOD` filter must be a strictly positive integer but `0` was given.
+        8:9 id MOD 0 = 0
         "###);
+    }
 
-        let filter = Filter::from_str("title = \"test\" AND name = 12").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        snapshot!(error.to_string(), @r###"
-        Attribute `name` is not filterable. Available filterable attribute patterns are: `title`.
-        20:24 title = "test" AND name = 12
-        "###);
+    #[test]
+    fn compare_fields() {
+        let index = TempIndex::new();
 
-        let filter = Filter::from_str("title = \"test\" AND name IN [12]").unwrap().unwrap();
+        let rule = |field_comparison: bool| -> FilterableAttributesRule {
+            serde_json::from_value(serde_json::json!({
+                "attributePatterns": ["sale_price", "list_price"],
+                "features": { "filter": { "comparison": true, "fieldComparison": field_comparison } }
+            }))
+            .unwrap()
+        };
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![rule(true)]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "sale_price": 80, "list_price": 100 },
+                { "id": 1, "sale_price": 100, "list_price": 100 },
+                { "id": 2, "sale_price": 120.5, "list_price": 100 },
+                { "id": 3, "sale_price": [150, 90], "list_price": 100 },
+                { "id": 4, "sale_price": 50 },
+                { "id": 5, "sale_price": "cheap", "list_price": 100 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+        };
+
+        assert_eq!(evaluate("sale_price < _field(list_price)"), RoaringBitmap::from_iter([0, 3]));
+        assert_eq!(
+            evaluate("sale_price <= _field(list_price)"),
+            RoaringBitmap::from_iter([0, 1, 3])
+        );
+        assert_eq!(evaluate("sale_price > _field(list_price)"), RoaringBitmap::from_iter([2, 3]));
+        assert_eq!(
+            evaluate("list_price >= _field(sale_price)"),
+            RoaringBitmap::from_iter([0, 1, 3])
+        );
+        assert_eq!(evaluate("sale_price = _field(list_price)"), RoaringBitmap::from_iter([1]));
+        assert_eq!(
+            evaluate("sale_price != _field(list_price)"),
+            RoaringBitmap::from_iter([0, 2, 3, 4, 5])
+        );
+        assert_eq!(
+            evaluate("sale_price < _field(list_price) AND sale_price > 85"),
+            RoaringBitmap::from_iter([3])
+        );
+        // the other field must be filterable too
+        let filter = Filter::from_str("sale_price < _field(title)").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).is_err());
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![rule(false)]);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("sale_price < _field(list_price)").unwrap().unwrap();
         let error = filter.evaluate(&rtxn, &index).unwrap_err();
         snapshot!(error.to_string(), @r###"
-        Attribute `name` is not filterable. Available filterable attribute patterns are: `title`.
-        20:24 title = "test" AND name IN [12]
+        Filter operator `_field` is not allowed for the attribute `sale_price`.
+          - Note: allowed operators: OR, AND, NOT, =, !=, IN, <, >, <=, >=, TO, IS EMPTY, IS NULL, EXISTS.
+          - Note: field `sale_price` matched rule #0 in `filterableAttributes`
+          - Hint: enable fieldComparison in rule #0 by modifying the features.filter object
+          - Hint: prepend another rule matching `sale_price` with appropriate filter features before rule #0
         "###);
+    }
 
-        let filter = Filter::from_str("title = \"test\" AND name != 12").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+    #[test]
+    fn filter_scan_limit() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("sku"))]);
+            })
+            .unwrap();
+        let documents: Vec<_> = (0..100)
+            .map(|id| serde_json::json!({ "id": id, "sku": format!("sku-{id:03}") }))
+            .collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate =
+            |filter: &str| Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index);
+
+        // unlimited by default
+        assert_eq!(evaluate("sku CONTAINS 01").unwrap().len(), 11);
+        assert_eq!(evaluate("sku STARTS WITH sku").unwrap().len(), 100);
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.set_filter_scan_limit(10);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let evaluate =
+            |filter: &str| Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index);
+
+        let error = evaluate("sku CONTAINS 01").unwrap_err();
         snapshot!(error.to_string(), @r###"
-        Attribute `name` is not filterable. Available filterable attribute patterns are: `title`.
-        20:24 title = "test" AND name != 12
+        The filter scanned more than the 10 facet values allowed on the attribute `sku`.
+        14:16 sku CONTAINS 01
         "###);
+        let error = evaluate("sku STARTS WITH sku").unwrap_err();
+        assert!(error.to_string().starts_with("The filter scanned more than the 10 facet values"));
+        let error = evaluate("sku STARTS WITH ANY [sku-0, sku-1]").unwrap_err();
+        assert!(error.to_string().starts_with("The filter scanned more than the 10 facet values"));
+        // the scans within the limit still succeed
+        assert_eq!(evaluate("sku STARTS WITH sku-00").unwrap().len(), 10);
+        assert_eq!(evaluate("sku = sku-001").unwrap().len(), 1);
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.reset_filter_scan_limit();
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("sku CONTAINS 01").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap().len(), 11);
     }
 
     #[test]
-    fn escaped_quote_in_filter_value_2380() {
+    fn starts_with_any() {
         let index = TempIndex::new();
 
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("title"))]);
+            })
+            .unwrap();
         index
             .add_documents(documents!([
-                {
-                    "id": "test_1",
-                    "monitor_diagonal": "27' to 30'"
-                },
-                {
-                    "id": "test_2",
-                    "monitor_diagonal": "27\" to 30\""
-                },
-                {
-                    "id": "test_3",
-                    "monitor_diagonal": "27\" to 30'"
-                },
+                { "id": 0, "title": "Kefir" },
+                { "id": 1, "title": "kebab" },
+                { "id": 2, "title": "milk" },
+                { "id": 3, "title": ["echo", "mint"] },
+                { "id": 4, "title": "tea" },
+                { "id": 5, "title": "zucchini" },
+                { "id": 6 },
             ]))
             .unwrap();
 
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+        };
+
+        for prefixes in [
+            &["ke"][..],
+            &["mi", "ke"],
+            &["kef", "ke", "KE"],
+            &["a", "t", "zu", "zz"],
+            &["nothing"],
+            &[""],
+        ] {
+            let quoted: Vec<_> = prefixes.iter().map(|prefix| format!("'{prefix}'")).collect();
+            let any = format!("title STARTS WITH ANY [{}]", quoted.join(", "));
+            let or = quoted
+                .iter()
+                .map(|prefix| format!("title STARTS WITH {prefix}"))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            assert_eq!(evaluate(&any), evaluate(&or), "{any}");
+        }
+        assert_eq!(
+            evaluate("title STARTS WITH ANY [mi, ke]"),
+            RoaringBitmap::from_iter([0, 1, 2, 3])
+        );
+        assert_eq!(evaluate("title STARTS WITH ANY []"), RoaringBitmap::new());
+    }
+
+    #[test]
+    fn starts_with_any_minimal_prefixes() {
+        let words: Vec<Token> =
+            ["mil", "ke", "a", "milk", "Ke", "kefir", "m"].into_iter().map(Token::from).collect();
+        // every value starting with `milk` or `kefir` already starts with `m` or `ke`
+        assert_eq!(minimal_sorted_prefixes(&words, None), ["a", "ke", "m"]);
+    }
+
+    #[test]
+    fn difference() {
+        let index = TempIndex::new();
+
         index
             .update_settings(|settings| {
-                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(
-                    "monitor_diagonal".to_string(),
-                )]);
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("color")),
+                    FilterableAttributesRule::Field(S("price")),
+                ]);
             })
             .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "color": "red", "price": 10 },
+                { "id": 1, "color": "red", "price": 20 },
+                { "id": 2, "color": "blue", "price": 10 },
+                { "id": 3, "color": "red" },
+            ]))
+            .unwrap();
 
-        let rtxn = index.read_txn().unwrap();
+        let mut wtxn = index.write_txn().unwrap();
+        let evaluate = |wtxn: &heed::RwTxn<'_>, filter: &str| {
+            Filter::from_str(filter).unwrap().unwrap().evaluate(wtxn, &index).unwrap()
+        };
 
-        let mut search = crate::Search::new(&rtxn, &index);
-        // this filter is copy pasted from #2380 with the exact same espace sequence
-        search.filter(Filter::from_str("monitor_diagonal = '27\" to 30\\''").unwrap().unwrap());
-        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
-        assert_eq!(documents_ids, vec![2]);
+        for (difference, and_not) in [
+            ("color = red AND NOT price = 10", "NOT price = 10 AND color = red"),
+            ("color = red AND NOT price EXISTS", "NOT price EXISTS AND color = red"),
+            ("color = red AND price NOT IN [10, 30]", "price NOT IN [10, 30] AND color = red"),
+        ] {
+            assert_eq!(evaluate(&wtxn, difference), evaluate(&wtxn, and_not), "{difference}");
+        }
+        assert_eq!(
+            evaluate(&wtxn, "color = red AND NOT price = 10"),
+            RoaringBitmap::from_iter([1, 3])
+        );
 
-        search.filter(Filter::from_str(r#"monitor_diagonal = "27' to 30'" "#).unwrap().unwrap());
-        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
-        assert_eq!(documents_ids, vec![0]);
+        // the difference never computes the complement of its right side among all the
+        // documents, which a `NOT` at the start of a conjunction has to do
+        index.put_documents_ids(&mut wtxn, &RoaringBitmap::new()).unwrap();
+        assert_eq!(
+            evaluate(&wtxn, "color = red AND NOT price = 10"),
+            RoaringBitmap::from_iter([1, 3])
+        );
+        assert!(evaluate(&wtxn, "NOT price = 10 AND color = red").is_empty());
+    }
 
-        search.filter(Filter::from_str(r#"monitor_diagonal = "27\" to 30\"" "#).unwrap().unwrap());
-        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
-        assert_eq!(documents_ids, vec![1]);
+    #[test]
+    fn documents_ids_read_once() {
+        let index = TempIndex::new();
 
-        search.filter(Filter::from_str(r#"monitor_diagonal = "27\" to 30'" "#).unwrap().unwrap());
-        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
-        assert_eq!(documents_ids, vec![2]);
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("color")),
+                    FilterableAttributesRule::Field(S("price")),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "color": "red", "price": 10 },
+                { "id": 1, "color": "red", "price": 20 },
+                { "id": 2, "color": "blue", "price": 10 },
+                { "id": 3, "color": "green" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let filterable_attributes_rules = index.filterable_attributes_rules(&rtxn).unwrap();
+        // the filters without negation never read them
+        for (filter, expected, loaded) in [
+            ("color != red OR price != 10", vec![1, 2, 3], true),
+            ("(color != red OR price != 10) AND NOT color = green", vec![1, 2], true),
+            ("NOT color = red OR NOT price = 10", vec![1, 2, 3], true),
+            ("price = 20", vec![1], false),
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            let cache = EvaluationCache::new(&rtxn, &index);
+            let docids = filter
+                .inner_evaluate(
+                    &rtxn,
+                    &index,
+                    &fields_ids_map,
+                    &filterable_attributes_rules,
+                    None,
+                    &cache,
+                    &AtomicBool::new(false),
+                )
+                .unwrap();
+            assert_eq!(docids, RoaringBitmap::from_iter(expected), "{filter:?}");
+            assert_eq!(cache.documents_ids.get().is_some(), loaded, "{filter:?}");
+            // the conditions share the locale read by the first one
+            assert_eq!(cache.locale.get(), Some(&None), "{filter:?}");
+        }
     }
 
     #[test]
-    fn zero_radius() {
+    fn embedder_presence() {
+        use crate::update::Setting;
+        use crate::vector::settings::{EmbedderSource, EmbeddingSettings};
+
         let index = TempIndex::new();
 
         index
             .update_settings(|settings| {
-                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S(
-                    RESERVED_GEO_FIELD_NAME,
-                ))]);
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("kind"))]);
+                settings.set_embedder_settings(BTreeMap::from([(
+                    S("manual"),
+                    Setting::Set(EmbeddingSettings {
+                        source: Setting::Set(EmbedderSource::UserProvided),
+                        dimensions: Setting::Set(2),
+                        ..Default::default()
+                    }),
+                )]));
             })
             .unwrap();
-
         index
             .add_documents(documents!([
-              {
-                "id": 1,
-                "name": "Nàpiz' Milano",
-                "address": "Viale Vittorio Veneto, 30, 20124, Milan, Italy",
-                "type": "pizza",
-                "rating": 9,
-                RESERVED_GEO_FIELD_NAME: {
-                  "lat": 45.4777599,
-                  "lng": 9.1967508
-                }
-              },
-              {
-                "id": 2,
-                "name": "Artico Gelateria Tradizionale",
-                "address": "Via Dogana, 1, 20123 Milan, Italy",
-                "type": "ice cream",
-                "rating": 10,
-                RESERVED_GEO_FIELD_NAME: {
-                  "lat": 45.4632046,
-                  "lng": 9.1719421
-                }
-              },
+                { "id": 0, "kind": "food", "_vectors": { "manual": [0.1, 0.2] } },
+                { "id": 1, "kind": "food", "_vectors": { "manual": null } },
+                { "id": 2, "kind": "bar", "_vectors": { "manual": [[0.3, 0.4], [0.5, 0.6]] } },
+                { "id": 3, "kind": "bar", "_vectors": { "manual": null } },
             ]))
             .unwrap();
 
         let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in [
+            ("_vectors.manual EXISTS", vec![0, 2]),
+            ("_vectors.manual NOT EXISTS", vec![1, 3]),
+            ("NOT _vectors.manual EXISTS", vec![1, 3]),
+            ("kind = food AND _vectors.manual NOT EXISTS", vec![1]),
+        ] {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.validate(&rtxn, &index).unwrap();
+            let docids = filter.evaluate(&rtxn, &index).unwrap();
+            assert_eq!(docids, RoaringBitmap::from_iter(expected), "{filter:?}");
+            assert_eq!(
+                filter.estimate_cardinality(&rtxn, &index).unwrap(),
+                docids.len(),
+                "{filter:?}"
+            );
+        }
 
-        let mut search = crate::Search::new(&rtxn, &index);
+        let filter = Filter::from_str("_vectors.unknown EXISTS").unwrap().unwrap();
+        snapshot!(filter.evaluate(&rtxn, &index).unwrap_err().to_string(), @r###"
+        The embedder `unknown` does not exist.
+        1:17 _vectors.unknown EXISTS
+        "###);
+        assert!(filter.validate(&rtxn, &index).is_err());
 
-        search.filter(Filter::from_str("_geoRadius(45.4777599, 9.1967508, 0)").unwrap().unwrap());
-        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
-        assert_eq!(documents_ids, vec![0]);
+        // only the presence of the embeddings can be filtered
+        let filter = Filter::from_str("_vectors.manual = 0.1").unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).is_err());
+        drop(rtxn);
+
+        // the embeddings follow the updates of the documents
+        index
+            .add_documents(documents!([
+                { "id": 0, "kind": "food", "_vectors": { "manual": null } },
+                { "id": 3, "kind": "bar", "_vectors": { "manual": [0.7, 0.8] } },
+            ]))
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("_vectors.manual EXISTS").unwrap().unwrap();
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([2, 3]));
     }
 
     #[test]
-    fn geo_radius_error() {
+    fn bitmap_filter() {
         let index = TempIndex::new();
 
         index
             .update_settings(|settings| {
-                settings.set_searchable_fields(vec![S(RESERVED_GEO_FIELD_NAME), S("price")]); // to keep the fields order
-                settings.set_filterable_fields(vec![
-                    FilterableAttributesRule::Field(S(RESERVED_GEO_FIELD_NAME)),
-                    FilterableAttributesRule::Field("price".to_string()),
-                ]);
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("price"))]);
             })
             .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 10 },
+                { "id": 1, "price": 20 },
+                { "id": 2, "price": 30 },
+                { "id": 3, "price": 40 },
+            ]))
+            .unwrap();
 
         let rtxn = index.read_txn().unwrap();
+        let acl = || Filter::from_bitmap(RoaringBitmap::from_iter([0, 1, 2, 42]));
+
+        // the documents that are not in the index are never returned
+        assert_eq!(acl().evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0, 1, 2]));
+
+        let user_filter = || Filter::from_str("price >= 20").unwrap().unwrap();
+        let filter = acl().and(user_filter());
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([1, 2]));
+
+        // the negations only select documents of the bitmap
+        let filter = acl().and(Filter::from_str("NOT price = 20").unwrap().unwrap());
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0, 2]));
+
+        let filter = acl()
+            .and(Filter::from_str("price = 10 OR price = 40").unwrap().unwrap())
+            .and(Filter::from_str("price < 40").unwrap().unwrap());
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter([0]));
+        filter.validate(&rtxn, &index).unwrap();
+        assert_eq!(filter.field_usage().keys().collect::<Vec<_>>(), vec!["price"]);
+        // the bitmap stays out of the filter expression
+        insta::assert_snapshot!(filter.filter().unwrap().condition(), @"AND[OR[{price} = {10}, {price} = {40}, ], {price} < {40}, ]");
+    }
 
-        // georadius have a bad latitude
-        let filter = Filter::from_str("_geoRadius(-100, 150, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(
-            error.to_string().starts_with(
-                "Bad latitude `-100`. Latitude must be contained between -90 and 90 degrees."
-            ),
-            "{}",
-            error.to_string()
-        );
+    #[test]
+    fn external_ids_filter() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("price"))]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": "kefir", "price": 10 },
+                { "id": "echo", "price": 20 },
+                { "id": "intel", "price": 30 },
+                { "id": "bobby", "price": 40 },
+            ]))
+            .unwrap();
+        index.delete_documents(vec![S("bobby")]);
 
-        // georadius have a bad latitude
-        let filter = Filter::from_str("_geoRadius(-90.0000001, 150, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
-        ));
+        let rtxn = index.read_txn().unwrap();
+        let external_ids = index.external_documents_ids();
+        let docid = |id: &str| external_ids.get(&rtxn, id).unwrap().unwrap();
 
-        // georadius have a bad longitude
-        let filter = Filter::from_str("_geoRadius(-10, 250, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(
-            error.to_string().contains(
-                "Bad longitude `250`. Longitude must be contained between -180 and 180 degrees."
-            ),
-            "{}",
-            error.to_string(),
+        // the unknown and deleted ids are ignored
+        let filter =
+            Filter::from_external_ids(&["intel", "unknown", "kefir", "bobby"], &rtxn, &index)
+                .unwrap()
+                .unwrap();
+        let expected = RoaringBitmap::from_iter([docid("kefir"), docid("intel")]);
+        assert_eq!(filter.bitmap(), &expected);
+        assert_eq!(filter.filter(), None);
+        assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), expected);
+
+        let filter = filter.and(Filter::from_str("price > 10").unwrap().unwrap());
+        assert_eq!(
+            filter.evaluate(&rtxn, &index).unwrap(),
+            RoaringBitmap::from_iter([docid("intel")])
         );
 
-        // georadius have a bad longitude
-        let filter = Filter::from_str("_geoRadius(-10, 180.000001, 10)").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
-        ));
+        let filter = Filter::from_external_ids(&["unknown"], &rtxn, &index).unwrap().unwrap();
+        assert!(filter.evaluate(&rtxn, &index).unwrap().is_empty());
+        assert!(Filter::from_external_ids(&[], &rtxn, &index).unwrap().is_none());
     }
 
     #[test]
-    fn geo_bounding_box_error() {
+    fn geo_coordinates_filters() {
         let index = TempIndex::new();
 
+        // the coordinates are filterable with the features of the `_geo` rule
+        let no_comparison: FilterableAttributesRule = serde_json::from_value(serde_json::json!({
+            "attributePatterns": ["*"],
+            "features": { "filter": { "equality": true, "comparison": false } }
+        }))
+        .unwrap();
         index
             .update_settings(|settings| {
-                settings.set_searchable_fields(vec![S(RESERVED_GEO_FIELD_NAME), S("price")]); // to keep the fields order
                 settings.set_filterable_fields(vec![
+                    no_comparison.clone(),
                     FilterableAttributesRule::Field(S(RESERVED_GEO_FIELD_NAME)),
-                    FilterableAttributesRule::Field("price".to_string()),
                 ]);
             })
             .unwrap();
+        index
+            .add_documents(documents!([
+              { "id": 0, "name": "Nàpiz' Milano", RESERVED_GEO_FIELD_NAME: { "lat": 45.4777599, "lng": 9.1967508 } },
+              { "id": 1, "name": "Artico Gelateria Tradizionale", RESERVED_GEO_FIELD_NAME: { "lat": 45.4632046, "lng": 9.1719421 } },
+              { "id": 2, "name": "Duomo di Milano", RESERVED_GEO_FIELD_NAME: { "lat": 45.4641943, "lng": 9.1896346 } },
+              { "id": 3, "name": "Castello Sforzesco", RESERVED_GEO_FIELD_NAME: { "lat": 45.4704762, "lng": 9.179329 } },
+              { "id": 4, "name": "Navigli", RESERVED_GEO_FIELD_NAME: { "lat": 45.4507, "lng": 9.1757 } },
+              { "id": 5, "name": "Milano Centrale", RESERVED_GEO_FIELD_NAME: { "lat": 45.4858, "lng": 9.204 } },
+              { "id": 6, "name": "San Siro", RESERVED_GEO_FIELD_NAME: { "lat": 45.4781, "lng": 9.124 } },
+            ]))
+            .unwrap();
 
         let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index).unwrap()
+        };
 
-        // geoboundingbox top left coord have a bad latitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([-90.0000001, 150], [30, 10])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(
-            error.to_string().starts_with(
-                "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
-            ),
-            "{}",
-            error.to_string()
+        assert_eq!(
+            evaluate("_geoBoundingBox([45.48, 9.2], [45.46, 9.17])"),
+            RoaringBitmap::from_iter([0, 1, 2, 3])
         );
-
-        // geoboundingbox top left coord have a bad latitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([90.0000001, 150], [30, 10])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(
-            error.to_string().starts_with(
-                "Bad latitude `90.0000001`. Latitude must be contained between -90 and 90 degrees."
-            ),
-            "{}",
-            error.to_string()
+        for (top_right, bottom_left) in [
+            ([45.48, 9.2], [45.46, 9.17]),
+            ([45.49, 9.21], [45.45, 9.18]),
+            ([46.0, 10.0], [45.0, 9.0]),
+        ] {
+            let [top, right] = top_right;
+            let [bottom, left] = bottom_left;
+            let expected =
+                evaluate(&format!("_geoBoundingBox([{top}, {right}], [{bottom}, {left}])"));
+            let ranges = format!("_geo.lat {bottom} TO {top} AND _geo.lng {left} TO {right}");
+            assert_eq!(evaluate(&ranges), expected, "{ranges}");
+            let comparisons = format!(
+                "_geo.lat >= {bottom} AND _geo.lat <= {top} AND _geo.lng >= {left} AND _geo.lng <= {right}"
+            );
+            assert_eq!(evaluate(&comparisons), expected, "{comparisons}");
+        }
+        assert_eq!(evaluate("_geo.lat > 45.48"), RoaringBitmap::from_iter([5]));
+        assert_eq!(evaluate("_geo.lng < 9.15"), RoaringBitmap::from_iter([6]));
+
+        let filter = Filter::from_str("_geo.lat > 45.47 AND _geo.lng < 9.18").unwrap().unwrap();
+        filter.validate(&rtxn, &index).unwrap();
+        let allowed = BTreeSet::from([RESERVED_GEO_FIELD_NAME]);
+        assert_eq!(
+            filter.evaluate_with_allowed_fields(&rtxn, &index, &allowed).unwrap(),
+            RoaringBitmap::from_iter([3, 6])
         );
+        drop(rtxn);
 
-        // geoboundingbox bottom right coord have a bad latitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([30, 10], [-90.0000001, 150])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad latitude `-90.0000001`. Latitude must be contained between -90 and 90 degrees."
-        ));
-
-        // geoboundingbox bottom right coord have a bad latitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([30, 10], [90.0000001, 150])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad latitude `90.0000001`. Latitude must be contained between -90 and 90 degrees."
-        ));
-
-        // geoboundingbox top left coord have a bad longitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([-10, 180.000001], [30, 10])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
-        ));
-
-        // geoboundingbox top left coord have a bad longitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([-10, -180.000001], [30, 10])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad longitude `-180.000001`. Longitude must be contained between -180 and 180 degrees."
-        ));
-
-        // geoboundingbox bottom right coord have a bad longitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([30, 10], [-10, -180.000001])").unwrap().unwrap();
-        let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad longitude `-180.000001`. Longitude must be contained between -180 and 180 degrees."
-        ));
-
-        // geoboundingbox bottom right coord have a bad longitude
-        let filter =
-            Filter::from_str("_geoBoundingBox([30, 10], [-10, 180.000001])").unwrap().unwrap();
+        // the coordinates aren't filterable without the geo filtering
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![no_comparison.clone()]);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("_geo.lat > 45.47").unwrap().unwrap();
         let error = filter.evaluate(&rtxn, &index).unwrap_err();
-        assert!(error.to_string().contains(
-            "Bad longitude `180.000001`. Longitude must be contained between -180 and 180 degrees."
-        ));
-    }
-
-    #[test]
-    fn filter_depth() {
-        // generates a big (2 MiB) filter with too much of ORs.
-        let tipic_filter = "account_ids=14361 OR ";
-        let mut filter_string = String::with_capacity(tipic_filter.len() * 14360);
-        for i in 1..=14361 {
-            let _ = write!(&mut filter_string, "account_ids={}", i);
-            if i != 14361 {
-                let _ = write!(&mut filter_string, " OR ");
-            }
-        }
-
-        // Note: the filter used to be rejected for being too deep, but that is
-        // no longer the case
-        let filter = Filter::from_str(&filter_string).unwrap();
-        assert!(filter.is_some());
-    }
-
-    #[test]
-    fn empty_filter() {
-        let option = Filter::from_str("     ").unwrap();
-        assert_eq!(option, None);
+        snapshot!(error.to_string(), @r###"
+        Attribute `_geo.lat` is not filterable. Available filterable attribute patterns are: `*`.
+        1:9 _geo.lat > 45.47
+        "###);
     }
 
     #[test]
-    fn non_finite_float() {
+    fn matches_document() {
         let index = TempIndex::new();
 
         index
             .update_settings(|settings| {
-                settings.set_searchable_fields(vec![S("price")]); // to keep the fields order
-                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(
-                    "price".to_string(),
-                )]);
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("price")),
+                    FilterableAttributesRule::Field(S("brand")),
+                    FilterableAttributesRule::Field(S("in_stock")),
+                ]);
             })
             .unwrap();
         index
             .add_documents(documents!([
-                {
-                    "id": "test_1",
-                    "price": "inf"
-                },
-                {
-                    "id": "test_2",
-                    "price": "2000"
-                },
-                {
-                    "id": "test_3",
-                    "price": "infinity"
-                },
+                { "id": 0, "price": 20, "brand": "apple", "in_stock": true },
+                { "id": 1, "price": 20, "brand": "nokia", "in_stock": true },
+                { "id": 2, "price": 5, "brand": "samsung", "in_stock": false },
             ]))
             .unwrap();
 
         let rtxn = index.read_txn().unwrap();
-        let filter = Filter::from_str("price = inf").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(result.contains(0));
-        let filter = Filter::from_str("price < inf").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        // this is allowed due to filters with strings
-        assert!(result.contains(1));
+        let filter = Filter::from_str(
+            "price > 10 AND (brand = apple OR brand = samsung) AND NOT in_stock = false",
+        )
+        .unwrap()
+        .unwrap();
+        let leaves = |docid| {
+            let explanation = filter.matches_document(&rtxn, &index, docid).unwrap();
+            assert_eq!(
+                explanation.matches,
+                filter.evaluate(&rtxn, &index).unwrap().contains(docid)
+            );
+            explanation
+                .leaves()
+                .into_iter()
+                .map(|leaf| format!("{} {}", leaf.matches, leaf.condition))
+                .collect::<Vec<_>>()
+        };
 
-        let filter = Filter::from_str("price = NaN").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(result.is_empty());
-        let filter = Filter::from_str("price < NaN").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(result.contains(1));
+        snapshot!(format!("{:#?}", leaves(0)), @r###"
+        [
+            "true {price} > {10}",
+            "true {brand} = {apple}",
+            "false {brand} = {samsung}",
+            "false {in_stock} = {false}",
+        ]
+        "###);
+        // the document of another brand only fails the brand conditions
+        snapshot!(format!("{:#?}", leaves(1)), @r###"
+        [
+            "true {price} > {10}",
+            "false {brand} = {apple}",
+            "false {brand} = {samsung}",
+            "false {in_stock} = {false}",
+        ]
+        "###);
+        snapshot!(format!("{:#?}", leaves(2)), @r###"
+        [
+            "false {price} > {10}",
+            "false {brand} = {apple}",
+            "true {brand} = {samsung}",
+            "true {in_stock} = {false}",
+        ]
+        "###);
 
-        let filter = Filter::from_str("price = infinity").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(result.contains(2));
-        let filter = Filter::from_str("price < infinity").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert!(result.contains(0));
-        assert!(result.contains(1));
+        let explanation = filter.matches_document(&rtxn, &index, 1).unwrap();
+        assert!(!explanation.matches);
+        // the parser nests the first two conditions in their own `AND`
+        let [and, in_stock] = &explanation.children[..] else { panic!("{explanation:?}") };
+        let [price, brand] = &and.children[..] else { panic!("{and:?}") };
+        assert!(!and.matches && in_stock.matches);
+        assert!(price.matches && !brand.matches);
+        assert!(!in_stock.children[0].matches);
+
+        let error = filter.matches_document(&rtxn, &index, 42).unwrap_err();
+        snapshot!(error.to_string(), @"An unknown internal document id have been used: `42`.");
     }
 
     #[test]
-    fn filter_number() {
+    fn from_json_object_rules() {
         let index = TempIndex::new();
 
         index
             .update_settings(|settings| {
-                settings.set_primary_key("id".to_owned());
                 settings.set_filterable_fields(vec![
-                    FilterableAttributesRule::Field("id".to_string()),
-                    FilterableAttributesRule::Field("one".to_string()),
-                    FilterableAttributesRule::Field("two".to_string()),
+                    FilterableAttributesRule::Field(S("price")),
+                    FilterableAttributesRule::Field(S("available")),
+                    FilterableAttributesRule::Field(S("brand")),
                 ]);
             })
             .unwrap();
-
-        let mut docs = vec![];
-        for i in 0..100 {
-            docs.push(serde_json::json!({ "id": i, "two": i % 10 }));
-        }
-
-        index.add_documents(documents!(docs)).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 1, "available": true, "brand": "apple" },
+                { "id": 1, "price": 1.0, "available": false, "brand": "nokia" },
+                { "id": 2, "price": 10, "available": true, "brand": "samsung" },
+                { "id": 3, "price": 1.5 },
+            ]))
+            .unwrap();
 
         let rtxn = index.read_txn().unwrap();
-        for i in 0..100 {
-            let filter_str = format!("id = {i}");
-            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-            let result = filter.evaluate(&rtxn, &index).unwrap();
-            assert_eq!(result, RoaringBitmap::from_iter([i]));
-        }
-        for i in 0..100 {
-            let filter_str = format!("id > {i}");
-            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-            let result = filter.evaluate(&rtxn, &index).unwrap();
-            assert_eq!(result, RoaringBitmap::from_iter((i + 1)..100));
-        }
-        for i in 0..100 {
-            let filter_str = format!("id < {i}");
-            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-            let result = filter.evaluate(&rtxn, &index).unwrap();
-            assert_eq!(result, RoaringBitmap::from_iter(0..i));
-        }
-        for i in 0..100 {
-            let filter_str = format!("id <= {i}");
-            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-            let result = filter.evaluate(&rtxn, &index).unwrap();
-            assert_eq!(result, RoaringBitmap::from_iter(0..=i));
-        }
-        for i in 0..100 {
-            let filter_str = format!("id >= {i}");
-            let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-            let result = filter.evaluate(&rtxn, &index).unwrap();
-            assert_eq!(result, RoaringBitmap::from_iter(i..100));
-        }
-        for i in 0..100 {
-            for j in i..100 {
-                let filter_str = format!("id {i} TO {j}");
-                let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-                let result = filter.evaluate(&rtxn, &index).unwrap();
-                assert_eq!(result, RoaringBitmap::from_iter(i..=j));
-            }
-        }
-        let filter = Filter::from_str("one >= 0 OR one <= 0").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert_eq!(result, RoaringBitmap::default());
+        let evaluate = |filter: serde_json::Value| -> Vec<u32> {
+            let filter = Filter::from_json(&filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect()
+        };
 
-        let filter = Filter::from_str("one = 0").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert_eq!(result, RoaringBitmap::default());
+        // the integers and the integral floats are the same token
+        for price in [serde_json::json!(1), serde_json::json!(1.0)] {
+            let filter = serde_json::json!([{ "field": "price", "value": price }]);
+            let condition = Filter::from_json(&filter).unwrap().unwrap();
+            let FilterCondition::Condition { fid, op: Condition::Equal(value) } =
+                condition.condition()
+            else {
+                panic!("{condition:?}")
+            };
+            assert_eq!((fid.value(), value.value()), ("price", "1"));
+            assert_eq!(evaluate(filter), vec![0, 1]);
+        }
+        assert_eq!(evaluate(serde_json::json!([{ "field": "price", "value": 10 }])), vec![2]);
+        assert_eq!(evaluate(serde_json::json!([{ "field": "price", "value": 1.5 }])), vec![3]);
+        assert_eq!(
+            evaluate(serde_json::json!([{ "field": "available", "value": true }])),
+            vec![0, 2]
+        );
+        assert_eq!(
+            evaluate(serde_json::json!([{ "field": "available", "value": false }])),
+            vec![1]
+        );
 
-        for i in 0..10 {
-            for j in i..10 {
-                let filter_str = format!("two {i} TO {j}");
-                let filter = Filter::from_str(&filter_str).unwrap().unwrap();
-                let result = filter.evaluate(&rtxn, &index).unwrap();
-                assert_eq!(
-                    result,
-                    RoaringBitmap::from_iter((0..100).filter(|x| (i..=j).contains(&(x % 10))))
-                );
-            }
+        // the object rules mix with the expressions in the AND and OR positions
+        let filter = serde_json::json!([
+            [{ "field": "brand", "value": "apple" }, "brand = samsung"],
+            { "field": "available", "value": true },
+            "price > 1",
+        ]);
+        assert_eq!(evaluate(filter), vec![2]);
+
+        for filter in [
+            serde_json::json!([1]),
+            serde_json::json!([[true]]),
+            serde_json::json!([{ "field": "price" }]),
+            serde_json::json!([{ "value": 10 }]),
+            serde_json::json!([{ "field": "price", "value": 10, "other": 1 }]),
+            serde_json::json!([{ "field": "price", "value": [10] }]),
+        ] {
+            assert!(Filter::from_json(&filter).is_err(), "{filter}");
         }
-        let filter = Filter::from_str("two != 0").unwrap().unwrap();
-        let result = filter.evaluate(&rtxn, &index).unwrap();
-        assert_eq!(result, RoaringBitmap::from_iter((0..100).filter(|x| x % 10 != 0)));
     }
 }
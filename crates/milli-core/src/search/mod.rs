@@ -6,7 +6,11 @@ use levenshtein_automata::{LevenshteinAutomatonBuilder as LevBuilder, DFA};
 use once_cell::sync::Lazy;
 use roaring::bitmap::RoaringBitmap;
 
-pub use self::facet::{FacetDistribution, Filter, OrderBy, DEFAULT_VALUES_PER_FACET};
+pub use self::facet::{
+    BitmapFilter, DocumentMatchExplanation, FacetDistribution, Filter, FilterAnalysis,
+    FilterOperator, FilterWarning, OrderBy, OwnedFilter, DEFAULT_BROAD_CONTAINS_THRESHOLD,
+    DEFAULT_VALUES_PER_FACET,
+};
 pub use self::new::matches::{FormatOptions, MatchBounds, MatcherBuilder, MatchingWords};
 use self::new::{execute_vector_search, PartialSearchResult, VectorStoreStats};
 use crate::filterable_attributes_rules::{filtered_matching_patterns, matching_features};
@@ -6,6 +6,7 @@ use bumpalo::Bump;
 use heed::{EnvOpenOptions, RwTxn};
 use maplit::btreemap;
 use memmap2::Mmap;
+use roaring::RoaringBitmap;
 use tempfile::TempDir;
 
 use crate::constants::RESERVED_GEO_FIELD_NAME;
@@ -447,6 +448,327 @@ fn set_searchable_fields_and_add_documents() {
     assert_eq!(user_defined, &["doggo", "name"]);
 }
 
+#[test]
+fn geo_nearest() {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|settings| {
+            settings.set_filterable_fields(vec![FilterableAttributesRule::Field(
+                RESERVED_GEO_FIELD_NAME.to_string(),
+            )]);
+        })
+        .unwrap();
+    index
+        .add_documents(documents!([
+            { "id": 0, "name": "Nàpiz' Milano", RESERVED_GEO_FIELD_NAME: { "lat": 45.4777599, "lng": 9.1967508 } },
+            { "id": 1, "name": "Artico Gelateria Tradizionale", RESERVED_GEO_FIELD_NAME: { "lat": 45.4632046, "lng": 9.1719421 } },
+            { "id": 2, "name": "Il Duomo", RESERVED_GEO_FIELD_NAME: { "lat": 45.4641013, "lng": 9.1897325 } },
+            { "id": 3, "name": "Gino Sorbillo", RESERVED_GEO_FIELD_NAME: { "lat": 40.8507327, "lng": 14.2554474 } },
+        ]))
+        .unwrap();
+
+    let rtxn = index.read_txn().unwrap();
+    let point = [45.4777599, 9.1967508];
+
+    let nearest = index.geo_nearest(&rtxn, point, 10, None).unwrap();
+    let docids: Vec<_> = nearest.iter().map(|(docid, _)| *docid).collect();
+    assert_eq!(docids, vec![0, 2, 1, 3]);
+    assert_eq!(nearest[0].1, 0.0);
+    assert!(nearest.windows(2).all(|w| w[0].1 <= w[1].1));
+    for (docid, distance) in &nearest {
+        let coords = [
+            [45.4777599, 9.1967508],
+            [45.4632046, 9.1719421],
+            [45.4641013, 9.1897325],
+            [40.8507327, 14.2554474],
+        ][*docid as usize];
+        assert_eq!(*distance, crate::distance_between_two_points(&point, &coords));
+    }
+
+    // `k` truncates the nearest documents
+    let nearest = index.geo_nearest(&rtxn, point, 2, None).unwrap();
+    assert_eq!(nearest.iter().map(|(docid, _)| *docid).collect::<Vec<_>>(), vec![0, 2]);
+    assert!(index.geo_nearest(&rtxn, point, 0, None).unwrap().is_empty());
+
+    // the documents in Naples are too far away
+    let nearest = index.geo_nearest(&rtxn, point, 10, Some(10_000.0)).unwrap();
+    assert_eq!(nearest.iter().map(|(docid, _)| *docid).collect::<Vec<_>>(), vec![0, 2, 1]);
+
+    let error = index.geo_nearest(&rtxn, [91.0, 9.0], 10, None).unwrap_err();
+    assert!(matches!(error, Error::UserError(crate::error::UserError::InvalidGeoPoint(_))));
+    insta::assert_snapshot!(error, @"Bad latitude `91`. Latitude must be contained between -90 and 90 degrees.");
+    let error = index.geo_nearest(&rtxn, [45.0, -181.0], 10, None).unwrap_err();
+    insta::assert_snapshot!(error, @"Bad longitude `-181`. Longitude must be contained between -180 and 180 degrees.");
+}
+
+#[test]
+fn geo_bounding_box_from_rtree() {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|settings| {
+            settings.set_filterable_fields(vec![FilterableAttributesRule::Field(
+                RESERVED_GEO_FIELD_NAME.to_string(),
+            )]);
+        })
+        .unwrap();
+    index
+        .add_documents(documents!([
+            { "id": 0, "name": "Nàpiz' Milano", RESERVED_GEO_FIELD_NAME: { "lat": 45.4777599, "lng": 9.1967508 } },
+            { "id": 1, "name": "Artico Gelateria Tradizionale", RESERVED_GEO_FIELD_NAME: { "lat": 45.4632046, "lng": 9.1719421 } },
+            { "id": 2, "name": "Il Duomo", RESERVED_GEO_FIELD_NAME: { "lat": 45.4641013, "lng": 9.1897325 } },
+            { "id": 3, "name": "Gino Sorbillo", RESERVED_GEO_FIELD_NAME: { "lat": 40.8507327, "lng": 14.2554474 } },
+            { "id": 4, "name": "Fiji", RESERVED_GEO_FIELD_NAME: { "lat": -17.7134, "lng": 178.065 } },
+            { "id": 5, "name": "Samoa", RESERVED_GEO_FIELD_NAME: { "lat": -13.759, "lng": -172.1046 } },
+            { "id": 6, "name": "no location" },
+        ]))
+        .unwrap();
+
+    let rtxn = index.read_txn().unwrap();
+    assert_eq!(index.geo_point_count(&rtxn).unwrap(), 6);
+
+    let boxes = [
+        // around the Duomo
+        ([45.4641013, 9.1897325], [45.4641013, 9.1897325]),
+        // the center of Milan
+        ([45.47, 9.2], [45.46, 9.17]),
+        // all of Milan
+        ([45.48, 9.2], [45.46, 9.17]),
+        // from Milan to Naples
+        ([45.4777599, 14.2554474], [40.8507327, 9.1719421]),
+        // Italy
+        ([47.0, 19.0], [36.0, 6.0]),
+        // around the antimeridian
+        ([0.0, -170.0], [-20.0, 170.0]),
+        // the whole earth
+        ([90.0, 180.0], [-90.0, -180.0]),
+        // the poles
+        ([90.0, 180.0], [80.0, -180.0]),
+        // nowhere
+        ([10.0, 10.0], [0.0, 0.0]),
+    ];
+    for (top_right, bottom_left) in boxes {
+        let filter = format!(
+            "_geoBoundingBox([{}, {}], [{}, {}])",
+            top_right[0], top_right[1], bottom_left[0], bottom_left[1]
+        );
+        let mut search = index.search(&rtxn);
+        search.filter(Filter::from_str(&filter).unwrap().unwrap());
+        let expected = search.execute().unwrap().candidates;
+        let docids = index.geo_bounding_box(&rtxn, top_right, bottom_left).unwrap();
+        assert_eq!(docids, expected, "{filter}");
+    }
+    let docids = index.geo_bounding_box(&rtxn, [45.48, 9.2], [45.46, 9.17]).unwrap();
+    insta::assert_debug_snapshot!(docids, @"RoaringBitmap<[0, 1, 2]>");
+
+    let error = index.geo_bounding_box(&rtxn, [91.0, 9.0], [45.0, 9.0]).unwrap_err();
+    assert!(matches!(error, Error::UserError(crate::error::UserError::InvalidGeoPoint(_))));
+    insta::assert_snapshot!(error, @"Bad latitude `91`. Latitude must be contained between -90 and 90 degrees.");
+    let error = index.geo_bounding_box(&rtxn, [45.0, 9.0], [46.0, 9.0]).unwrap_err();
+    insta::assert_snapshot!(error, @"The top latitude `45` is below the bottom latitude `46`.");
+}
+
+#[test]
+fn facet_values_pagination() {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|settings| {
+            settings.set_filterable_fields(vec![
+                FilterableAttributesRule::Field(S("color")),
+                FilterableAttributesRule::Field(S("price")),
+            ]);
+        })
+        .unwrap();
+    index
+        .add_documents(documents!([
+            { "id": 0, "color": "Red", "price": 10 },
+            { "id": 1, "color": ["blue", "green"], "price": -2.5 },
+            { "id": 2, "color": "red", "price": 10 },
+            { "id": 3, "color": "yellow", "price": 3 },
+            { "id": 4, "color": "orange", "price": [100, 3] },
+            { "id": 5, "color": "blue" },
+        ]))
+        .unwrap();
+
+    let rtxn = index.read_txn().unwrap();
+
+    let all = index.facet_values(&rtxn, "color", None, usize::MAX).unwrap();
+    assert_eq!(
+        all,
+        vec![(S("blue"), 2), (S("green"), 1), (S("orange"), 1), (S("red"), 2), (S("yellow"), 1),]
+    );
+
+    // the pages follow each other without gaps nor overlaps
+    for limit in 1..=all.len() + 1 {
+        let mut pages = Vec::new();
+        let mut after: Option<String> = None;
+        loop {
+            let page = index.facet_values(&rtxn, "color", after.as_deref(), limit).unwrap();
+            assert!(page.len() <= limit);
+            match page.last() {
+                Some((value, _)) => after = Some(value.clone()),
+                None => break,
+            }
+            pages.extend(page);
+        }
+        assert_eq!(pages, all, "limit: {limit}");
+    }
+
+    let all = index.facet_number_values(&rtxn, "price", None, usize::MAX).unwrap();
+    assert_eq!(all, vec![(-2.5, 1), (3.0, 2), (10.0, 2), (100.0, 1)]);
+    for limit in 1..=all.len() + 1 {
+        let mut pages = Vec::new();
+        let mut after = None;
+        loop {
+            let page = index.facet_number_values(&rtxn, "price", after, limit).unwrap();
+            assert!(page.len() <= limit);
+            match page.last() {
+                Some((value, _)) => after = Some(*value),
+                None => break,
+            }
+            pages.extend(page);
+        }
+        assert_eq!(pages, all, "limit: {limit}");
+    }
+
+    assert!(index.facet_values(&rtxn, "color", Some("yellow"), 10).unwrap().is_empty());
+    assert!(index.facet_values(&rtxn, "price", None, 10).unwrap().is_empty());
+    assert!(index.facet_values(&rtxn, "unknown", None, 10).unwrap().is_empty());
+    assert!(index.facet_number_values(&rtxn, "color", None, 10).unwrap().is_empty());
+}
+
+#[test]
+fn facet_values_in_universe() {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|settings| {
+            settings.set_filterable_fields(vec![
+                FilterableAttributesRule::Field(S("color")),
+                FilterableAttributesRule::Field(S("price")),
+            ]);
+        })
+        .unwrap();
+    index
+        .add_documents(documents!([
+            { "id": 0, "color": "Red", "price": 10 },
+            { "id": 1, "color": ["blue", "green"], "price": -2.5 },
+            { "id": 2, "color": "red", "price": 10 },
+            { "id": 3, "color": "yellow", "price": 3 },
+            { "id": 4, "color": "orange", "price": [100, 3] },
+            { "id": 5, "color": "blue" },
+        ]))
+        .unwrap();
+
+    let rtxn = index.read_txn().unwrap();
+    let values = |universe: &RoaringBitmap| -> Vec<(String, Vec<u32>)> {
+        index
+            .facet_values_in_universe(&rtxn, "color", universe)
+            .unwrap()
+            .map(|result| result.map(|(value, docids)| (value, docids.into_iter().collect())))
+            .collect::<crate::Result<_>>()
+            .unwrap()
+    };
+
+    // the documents costing 10 or less
+    let filter = Filter::from_str("price <= 10").unwrap().unwrap();
+    let universe = filter.evaluate(&rtxn, &index).unwrap();
+    assert_eq!(universe, RoaringBitmap::from_iter([0, 1, 2, 3, 4]));
+    assert_eq!(
+        values(&universe),
+        vec![
+            (S("blue"), vec![1]),
+            (S("green"), vec![1]),
+            (S("orange"), vec![4]),
+            (S("red"), vec![0, 2]),
+            (S("yellow"), vec![3]),
+        ]
+    );
+
+    assert_eq!(
+        values(&RoaringBitmap::from_iter([2, 5])),
+        vec![(S("blue"), vec![5]), (S("red"), vec![2])]
+    );
+
+    // the values are streamed lazily
+    let universe = RoaringBitmap::from_iter([0, 1, 2, 3, 4, 5]);
+    let mut iter = index.facet_values_in_universe(&rtxn, "color", &universe).unwrap();
+    let (value, docids) = iter.next().unwrap().unwrap();
+    assert_eq!((value.as_str(), docids), ("blue", RoaringBitmap::from_iter([1, 5])));
+    drop(iter);
+
+    assert!(values(&RoaringBitmap::new()).is_empty());
+    assert!(values(&RoaringBitmap::from_iter([42])).is_empty());
+    assert!(index.facet_values_in_universe(&rtxn, "price", &universe).unwrap().next().is_none());
+    assert!(index.facet_values_in_universe(&rtxn, "unknown", &universe).unwrap().next().is_none());
+}
+
+#[test]
+fn facet_percentile_threshold() {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|settings| {
+            settings.set_filterable_fields(vec![
+                FilterableAttributesRule::Field(S("rating")),
+                FilterableAttributesRule::Field(S("color")),
+            ]);
+        })
+        .unwrap();
+    // the documents 0 to 99 are rated from 1 to 100, the document 100 is not rated
+    let mut documents: Vec<_> =
+        (0..100).map(|id| serde_json::json!({ "id": id, "rating": id + 1 })).collect();
+    documents.push(serde_json::json!({ "id": 100, "color": "blue" }));
+    index.add_documents(documents!(documents)).unwrap();
+
+    let rtxn = index.read_txn().unwrap();
+    let all = index.documents_ids(&rtxn).unwrap();
+    let threshold = |percentile: f64, universe: &RoaringBitmap| {
+        index.facet_percentile_threshold(&rtxn, "rating", percentile, universe).unwrap()
+    };
+
+    assert_eq!(threshold(90.0, &all), Some(90.0));
+    assert_eq!(threshold(50.0, &all), Some(50.0));
+    assert_eq!(threshold(12.5, &all), Some(13.0));
+    assert_eq!(threshold(0.0, &all), Some(1.0));
+    assert_eq!(threshold(100.0, &all), Some(100.0));
+
+    // the top 10% by rating
+    let threshold = threshold(90.0, &all).unwrap();
+    let filter = format!("rating > {threshold}");
+    let filter = Filter::from_str(&filter).unwrap().unwrap();
+    assert_eq!(filter.evaluate(&rtxn, &index).unwrap(), RoaringBitmap::from_iter(90..100));
+
+    // the percentiles are computed over the universe only
+    let universe = RoaringBitmap::from_iter((0..20).chain([100]));
+    assert_eq!(
+        index.facet_percentile_threshold(&rtxn, "rating", 50.0, &universe).unwrap(),
+        Some(10.0)
+    );
+    assert_eq!(
+        index
+            .facet_percentile_threshold(&rtxn, "rating", 50.0, &RoaringBitmap::from_iter([100]))
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        index.facet_percentile_threshold(&rtxn, "rating", 50.0, &RoaringBitmap::new()).unwrap(),
+        None
+    );
+    assert_eq!(index.facet_percentile_threshold(&rtxn, "color", 50.0, &all).unwrap(), None);
+    assert_eq!(index.facet_percentile_threshold(&rtxn, "unknown", 50.0, &all).unwrap(), None);
+
+    for percentile in [-1.0, 100.5, f64::NAN] {
+        let err = index.facet_percentile_threshold(&rtxn, "rating", percentile, &all).unwrap_err();
+        assert!(
+            matches!(err, Error::UserError(crate::error::UserError::InvalidPercentile(_))),
+            "{err:?}"
+        );
+    }
+}
+
 #[test]
 fn test_basic_geo_bounding_box() {
     let index = TempIndex::new();
@@ -1397,3 +1719,207 @@ fn vectors_are_never_indexed_as_searchable_or_filterable() {
         .unwrap();
     assert!(results.candidates.is_empty());
 }
+
+#[test]
+fn documents_changed_since() {
+    let index = TempIndex::new();
+
+    index
+        .add_documents(documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": 2, "name": "kevina" },
+        ]))
+        .unwrap();
+    let rtxn = index.read_txn().unwrap();
+    let first_version = index.documents_version(&rtxn).unwrap();
+    assert_eq!(first_version, 1);
+    drop(rtxn);
+
+    index
+        .add_documents(documents!([
+            { "id": 2, "name": "kevina", "age": 22 },
+            { "id": 3, "name": "benoit" },
+        ]))
+        .unwrap();
+
+    let rtxn = index.read_txn().unwrap();
+    let docid = |id: &str| index.external_documents_ids().get(&rtxn, id).unwrap().unwrap();
+    let second_version = index.documents_version(&rtxn).unwrap();
+    assert_eq!(second_version, 2);
+    assert_eq!(
+        index.documents_changed_since(&rtxn, first_version).unwrap(),
+        [docid("2"), docid("3")].into_iter().collect()
+    );
+    assert_eq!(
+        index.documents_changed_since(&rtxn, 0).unwrap(),
+        index.documents_ids(&rtxn).unwrap()
+    );
+    assert!(index.documents_changed_since(&rtxn, second_version).unwrap().is_empty());
+    drop(rtxn);
+
+    // reindexing the documents does not change them
+    index.update_settings(|settings| settings.set_searchable_fields(vec![S("name")])).unwrap();
+    let rtxn = index.read_txn().unwrap();
+    assert_eq!(index.documents_version(&rtxn).unwrap(), second_version);
+    drop(rtxn);
+
+    // the deleted documents are not changed documents and do not increase the version
+    index.delete_document("3");
+    let rtxn = index.read_txn().unwrap();
+    let docid = |id: &str| index.external_documents_ids().get(&rtxn, id).unwrap().unwrap();
+    assert_eq!(index.documents_version(&rtxn).unwrap(), second_version);
+    assert_eq!(
+        index.documents_changed_since(&rtxn, first_version).unwrap(),
+        [docid("2")].into_iter().collect()
+    );
+    drop(rtxn);
+
+    // a document is only kept in the last version it changed in
+    index.add_documents(documents!([{ "id": 1, "name": "kevin", "age": 23 }])).unwrap();
+    let rtxn = index.read_txn().unwrap();
+    let docid = |id: &str| index.external_documents_ids().get(&rtxn, id).unwrap().unwrap();
+    let versions: Vec<_> = index.version_docids.iter(&rtxn).unwrap().map(Result::unwrap).collect();
+    assert_eq!(
+        versions,
+        [
+            (second_version, [docid("2")].into_iter().collect()),
+            (second_version + 1, [docid("1")].into_iter().collect()),
+        ]
+    );
+}
+
+#[test]
+fn missing_faceted_documents_ids() {
+    let index = TempIndex::new();
+    index
+        .update_settings(|settings| {
+            settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("age"))])
+        })
+        .unwrap();
+    index
+        .add_documents(documents!([
+            { "id": 0, "age": 12 },
+            { "id": 1, "age": null },
+            { "id": 2, "age": [] },
+            { "id": 3 },
+            { "id": 4, "name": "kevin" },
+        ]))
+        .unwrap();
+
+    let rtxn = index.read_txn().unwrap();
+    let age = index.fields_ids_map(&rtxn).unwrap().id("age").unwrap();
+    let docid = |id: &str| index.external_documents_ids().get(&rtxn, id).unwrap().unwrap();
+    let missing = index.missing_faceted_documents_ids(&rtxn, age).unwrap();
+    let exists = index.exists_faceted_documents_ids(&rtxn, age).unwrap();
+    assert_eq!(missing, [docid("3"), docid("4")].into_iter().collect());
+    assert!(missing.is_disjoint(&exists));
+    assert_eq!(missing | exists, index.documents_ids(&rtxn).unwrap());
+    drop(rtxn);
+
+    // the missing documents follow the updates and deletions
+    index.add_documents(documents!([{ "id": 3, "age": 5 }])).unwrap();
+    index.delete_document("4");
+    let rtxn = index.read_txn().unwrap();
+    assert!(index.missing_faceted_documents_ids(&rtxn, age).unwrap().is_empty());
+}
+
+#[test]
+fn field_presence() {
+    let index = TempIndex::new();
+    index
+        .update_settings(|settings| {
+            settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("age"))])
+        })
+        .unwrap();
+    index
+        .add_documents(documents!([
+            { "id": 0, "age": 12 },
+            { "id": 1, "age": null },
+            { "id": 2, "age": [] },
+            { "id": 3, "age": "" },
+            { "id": 4 },
+            { "id": 5, "name": "kevin" },
+        ]))
+        .unwrap();
+
+    let rtxn = index.read_txn().unwrap();
+    let age = index.fields_ids_map(&rtxn).unwrap().id("age").unwrap();
+    let docids = |ids: &[&str]| -> RoaringBitmap {
+        ids.iter()
+            .map(|id| index.external_documents_ids().get(&rtxn, id).unwrap().unwrap())
+            .collect()
+    };
+    let presence = index.field_presence(&rtxn, age).unwrap();
+    assert_eq!(presence.exists, docids(&["0", "1", "2", "3"]));
+    assert_eq!(presence.null, docids(&["1"]));
+    assert_eq!(presence.empty, docids(&["2", "3"]));
+    assert_eq!(presence.missing, docids(&["4", "5"]));
+
+    // the null and empty documents contain the field, the missing ones don't
+    assert!(presence.null.is_subset(&presence.exists));
+    assert!(presence.empty.is_subset(&presence.exists));
+    assert!(presence.null.is_disjoint(&presence.empty));
+    assert!(presence.missing.is_disjoint(&presence.exists));
+    assert_eq!(&presence.missing | &presence.exists, index.documents_ids(&rtxn).unwrap());
+    assert_eq!(presence.null, index.null_faceted_documents_ids(&rtxn, age).unwrap());
+    assert_eq!(presence.empty, index.empty_faceted_documents_ids(&rtxn, age).unwrap());
+    assert_eq!(presence.missing, index.missing_faceted_documents_ids(&rtxn, age).unwrap());
+}
+
+#[test]
+fn facet_cardinalities() {
+    let index = TempIndex::new();
+    index
+        .update_settings(|settings| {
+            settings.set_filterable_fields(vec![
+                FilterableAttributesRule::Field(S("description")),
+                FilterableAttributesRule::Field(S("category")),
+                FilterableAttributesRule::Field(S("price")),
+            ])
+        })
+        .unwrap();
+    let documents: Vec<_> = (0..200)
+        .map(|id| {
+            let category = ["books", "music", "games"][id % 3];
+            serde_json::json!({
+                "id": id,
+                "description": format!("a unique description number {id}"),
+                "category": category,
+                "price": id % 10,
+                "title": "not filterable",
+            })
+        })
+        .collect();
+    index.add_documents(documents!(documents)).unwrap();
+
+    let rtxn = index.read_txn().unwrap();
+    let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+    let id = |name: &str| fields_ids_map.id(name).unwrap();
+    let cardinalities = index.facet_cardinalities(&rtxn).unwrap();
+    assert_eq!(
+        cardinalities,
+        btreemap! { id("description") => 200, id("category") => 3, id("price") => 10 }
+    );
+    // no budget, no warning
+    assert!(index.facet_cardinality_warnings(&rtxn).unwrap().is_empty());
+    drop(rtxn);
+
+    index
+        .update_settings(|settings| {
+            settings.set_facet_cardinality_budget(50);
+        })
+        .unwrap();
+    let rtxn = index.read_txn().unwrap();
+    let warnings = index.facet_cardinality_warnings(&rtxn).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].field_id, id("description"));
+    assert_eq!(warnings[0].cardinality, 200);
+    insta::assert_snapshot!(warnings[0], @"The facet field `description` has 200 distinct values, more than the budget of 50. Check that the field is meant to be filterable.");
+    drop(rtxn);
+
+    // the deleted values are no longer counted
+    index.delete_documents((0..160).map(|id| id.to_string()).collect());
+    let rtxn = index.read_txn().unwrap();
+    assert_eq!(index.facet_cardinalities(&rtxn).unwrap()[&id("description")], 40);
+    assert!(index.facet_cardinality_warnings(&rtxn).unwrap().is_empty());
+}
@@ -31,23 +31,31 @@ impl<'t, 'i> ClearDocuments<'t, 'i> {
             word_prefix_docids,
             exact_word_prefix_docids,
             word_pair_proximity_docids,
+            word_triple_docids,
+            word_pair_frequency_docids,
             word_position_docids,
             word_fid_docids,
             field_id_word_count_docids,
             word_prefix_position_docids,
             word_prefix_fid_docids,
             facet_id_f64_docids,
+            facet_id_i64_docids,
             facet_id_string_docids,
             facet_id_normalized_string_strings,
+            facet_id_string_representatives,
             facet_id_string_fst,
             facet_id_exists_docids,
             facet_id_is_null_docids,
             facet_id_is_empty_docids,
+            facet_id_is_date_docids,
+            facet_id_is_true_docids,
+            facet_id_is_false_docids,
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
             vector_arroy,
             embedder_category_id: _,
             documents,
+            version_docids,
         } = self.index;
 
         let empty_roaring = RoaringBitmap::default();
@@ -77,17 +85,24 @@ impl<'t, 'i> ClearDocuments<'t, 'i> {
         word_prefix_docids.clear(self.wtxn)?;
         exact_word_prefix_docids.clear(self.wtxn)?;
         word_pair_proximity_docids.clear(self.wtxn)?;
+        word_triple_docids.clear(self.wtxn)?;
+        word_pair_frequency_docids.clear(self.wtxn)?;
         word_position_docids.clear(self.wtxn)?;
         word_fid_docids.clear(self.wtxn)?;
         field_id_word_count_docids.clear(self.wtxn)?;
         word_prefix_position_docids.clear(self.wtxn)?;
         word_prefix_fid_docids.clear(self.wtxn)?;
         facet_id_f64_docids.clear(self.wtxn)?;
+        facet_id_i64_docids.clear(self.wtxn)?;
         facet_id_normalized_string_strings.clear(self.wtxn)?;
+        facet_id_string_representatives.clear(self.wtxn)?;
         facet_id_string_fst.clear(self.wtxn)?;
         facet_id_exists_docids.clear(self.wtxn)?;
         facet_id_is_null_docids.clear(self.wtxn)?;
         facet_id_is_empty_docids.clear(self.wtxn)?;
+        facet_id_is_date_docids.clear(self.wtxn)?;
+        facet_id_is_true_docids.clear(self.wtxn)?;
+        facet_id_is_false_docids.clear(self.wtxn)?;
         facet_id_string_docids.clear(self.wtxn)?;
         field_id_docid_facet_f64s.clear(self.wtxn)?;
         field_id_docid_facet_strings.clear(self.wtxn)?;
@@ -95,6 +110,8 @@ impl<'t, 'i> ClearDocuments<'t, 'i> {
         vector_arroy.clear(self.wtxn)?;
 
         documents.clear(self.wtxn)?;
+        // the documents version itself is kept so that it keeps increasing
+        version_docids.clear(self.wtxn)?;
 
         Ok(number_of_documents)
     }
@@ -140,11 +157,19 @@ mod tests {
         assert!(index.word_docids.is_empty(&rtxn).unwrap());
         assert!(index.word_prefix_docids.is_empty(&rtxn).unwrap());
         assert!(index.word_pair_proximity_docids.is_empty(&rtxn).unwrap());
+        assert!(index.word_triple_docids.is_empty(&rtxn).unwrap());
+        assert!(index.word_pair_frequency_docids.is_empty(&rtxn).unwrap());
         assert!(index.field_id_word_count_docids.is_empty(&rtxn).unwrap());
         assert!(index.facet_id_f64_docids.is_empty(&rtxn).unwrap());
+        assert!(index.facet_id_i64_docids.is_empty(&rtxn).unwrap());
+        assert!(index.facet_id_is_date_docids.is_empty(&rtxn).unwrap());
+        assert!(index.facet_id_is_true_docids.is_empty(&rtxn).unwrap());
+        assert!(index.facet_id_is_false_docids.is_empty(&rtxn).unwrap());
         assert!(index.facet_id_string_docids.is_empty(&rtxn).unwrap());
+        assert!(index.facet_id_string_representatives.is_empty(&rtxn).unwrap());
         assert!(index.field_id_docid_facet_f64s.is_empty(&rtxn).unwrap());
         assert!(index.field_id_docid_facet_strings.is_empty(&rtxn).unwrap());
         assert!(index.documents.is_empty(&rtxn).unwrap());
+        assert!(index.version_docids.is_empty(&rtxn).unwrap());
     }
 }
@@ -183,6 +183,10 @@ impl<'i> FacetsUpdate<'i> {
             incremental_update.execute(wtxn)?;
         }
 
+        if self.facet_type == FacetType::String {
+            compute_facet_representatives(wtxn, self.index)?;
+        }
+
         if !self.index.facet_search(wtxn)? {
             // If facet search is disabled, we don't need to compute facet search databases.
             // We clear the facet search databases.
@@ -295,6 +299,111 @@ fn index_facet_search(
     Ok(())
 }
 
+/// Stores the original string representing each of the normalized `values` of the string facet
+/// field in the facet distribution, as chosen by the [`FacetRepresentative`] setting.
+///
+/// Nothing is stored for [`FacetRepresentative::FirstSeen`], the original of any document
+/// having the value represents it without looking at the other originals.
+pub fn update_facet_representatives<'a>(
+    wtxn: &mut heed::RwTxn<'_>,
+    index: &Index,
+    field_id: FieldId,
+    values: impl IntoIterator<Item = &'a str>,
+) -> Result<()> {
+    let representative = index.facet_representative(wtxn)?;
+    if representative.is_first_seen() {
+        return Ok(());
+    }
+
+    for normalized in values {
+        let key = FacetGroupKey { field_id, level: 0, left_bound: normalized };
+        let docids = match index.facet_id_string_docids.get(wtxn, &key)? {
+            Some(group) => group.bitmap,
+            None => RoaringBitmap::new(),
+        };
+
+        let mut originals = Vec::new();
+        for docid in docids {
+            let key = (field_id, docid, normalized);
+            if let Some(original) = index.field_id_docid_facet_strings.get(wtxn, &key)? {
+                originals.push(original);
+            }
+        }
+
+        match representative.choose(originals).map(str::to_owned) {
+            Some(original) => index.facet_id_string_representatives.put(
+                wtxn,
+                &(field_id, normalized),
+                &original,
+            )?,
+            None => {
+                index.facet_id_string_representatives.delete(wtxn, &(field_id, normalized))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Recomputes the representatives of all the values of the string facet fields,
+/// see [`update_facet_representatives`].
+pub fn compute_facet_representatives(wtxn: &mut heed::RwTxn<'_>, index: &Index) -> Result<()> {
+    index.facet_id_string_representatives.clear(wtxn)?;
+    if index.facet_representative(wtxn)?.is_first_seen() {
+        return Ok(());
+    }
+
+    let mut values: Vec<(FieldId, String)> = Vec::new();
+    for result in index.facet_id_string_docids.remap_data_type::<DecodeIgnore>().iter(wtxn)? {
+        let (FacetGroupKey { field_id, level, left_bound }, ()) = result?;
+        if level == 0 {
+            values.push((field_id, left_bound.to_owned()));
+        }
+    }
+
+    for (field_id, value) in &values {
+        update_facet_representatives(wtxn, index, *field_id, Some(value.as_str()))?;
+    }
+
+    Ok(())
+}
+
+/// Recomputes the representatives of all the values of the given string facet field,
+/// see [`update_facet_representatives`].
+pub fn compute_field_facet_representatives(
+    wtxn: &mut heed::RwTxn<'_>,
+    index: &Index,
+    field_id: FieldId,
+) -> Result<()> {
+    let mut iter = index
+        .facet_id_string_representatives
+        .remap_types::<Bytes, DecodeIgnore>()
+        .prefix_iter_mut(wtxn, &field_id.to_be_bytes())?;
+    while iter.next().transpose()?.is_some() {
+        // safety: we don't keep references from inside the LMDB database.
+        unsafe { iter.del_current()? };
+    }
+    drop(iter);
+
+    if index.facet_representative(wtxn)?.is_first_seen() {
+        return Ok(());
+    }
+
+    let left = FacetGroupKey { field_id, level: 0, left_bound: "" };
+    let right = FacetGroupKey { field_id, level: 1, left_bound: "" };
+    let mut values = Vec::new();
+    for result in index
+        .facet_id_string_docids
+        .remap_data_type::<DecodeIgnore>()
+        .range(wtxn, &(left..right))?
+    {
+        let (FacetGroupKey { left_bound, .. }, ()) = result?;
+        values.push(left_bound.to_owned());
+    }
+
+    update_facet_representatives(wtxn, index, field_id, values.iter().map(String::as_str))
+}
+
 /// Clear all the levels greater than 0 for given field ids.
 pub fn clear_facet_levels<'a, I>(
     wtxn: &mut heed::RwTxn<'_>,
@@ -328,6 +437,21 @@ pub fn clear_facet_levels_based_on_settings_diff(
     Ok(())
 }
 
+/// Logs a warning for the faceted fields, among the `modified` ones, having more distinct values
+/// than the facet cardinality budget of the index.
+pub(crate) fn warn_facet_cardinalities(
+    rtxn: &heed::RoTxn<'_>,
+    index: &Index,
+    modified: impl Fn(FieldId) -> bool,
+) -> Result<()> {
+    for warning in index.facet_cardinality_warnings(rtxn)? {
+        if modified(warning.field_id) {
+            tracing::warn!(target: "indexing::facet", field = %warning.field, "{warning}");
+        }
+    }
+    Ok(())
+}
+
 fn facet_levels_field_ids<B>(settings: &InnerIndexSettings) -> B
 where
     B: FromIterator<FieldId>,
@@ -0,0 +1,306 @@
+use std::io;
+use std::sync::Arc;
+
+use obkv::KvReaderU16;
+
+use super::helpers::try_split_array_at;
+use crate::error::SerializationError;
+use crate::index::db_name::DOCID_WORD_POSITIONS;
+use crate::update::del_add::{DelAdd, KvReaderDelAdd};
+use crate::{DocumentId, FieldId, Result};
+
+/// The words of a field of a document, with their positions, as found in a
+/// docid_word_positions chunk.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WordPositionsEntry<'a> {
+    pub document_id: DocumentId,
+    pub fid: FieldId,
+    /// The positions and words removed from the field, sorted by position.
+    pub deletion: Vec<(u16, &'a str)>,
+    /// The positions and words added to the field, sorted by position.
+    pub addition: Vec<(u16, &'a str)>,
+}
+
+impl<'a> WordPositionsEntry<'a> {
+    /// Decodes a docid_word_positions entry, borrowing its words from the value
+    /// and reusing the allocations of the `recycled` entry.
+    fn decode(key: &[u8], value: &'a [u8], recycled: WordPositionsEntry<'_>) -> Result<Self> {
+        let (document_id_bytes, fid_bytes) = try_split_array_at(key)
+            .ok_or(SerializationError::Decoding { db_name: Some(DOCID_WORD_POSITIONS) })?;
+        let (fid_bytes, _) = try_split_array_at(fid_bytes)
+            .ok_or(SerializationError::Decoding { db_name: Some(DOCID_WORD_POSITIONS) })?;
+
+        let mut entry = recycled.recycle();
+        entry.document_id = u32::from_be_bytes(document_id_bytes);
+        entry.fid = u16::from_be_bytes(fid_bytes);
+
+        let del_add_reader = KvReaderDelAdd::from_slice(value);
+        for (side, words) in
+            [(DelAdd::Deletion, &mut entry.deletion), (DelAdd::Addition, &mut entry.addition)]
+        {
+            if let Some(positions) = del_add_reader.get(side) {
+                for (position, word) in KvReaderU16::from_slice(positions).iter() {
+                    words.push((position, std::str::from_utf8(word)?));
+                }
+            }
+        }
+
+        Ok(entry)
+    }
+
+    /// Empties the entry, keeping the allocations of its words for another entry.
+    fn recycle<'b>(self) -> WordPositionsEntry<'b> {
+        // the words are never yielded, only the allocation is reused
+        let reuse = |mut words: Vec<(u16, &str)>| -> Vec<(u16, &'b str)> {
+            words.clear();
+            words.into_iter().map(|_| unreachable!()).collect()
+        };
+        WordPositionsEntry {
+            document_id: self.document_id,
+            fid: self.fid,
+            deletion: reuse(self.deletion),
+            addition: reuse(self.addition),
+        }
+    }
+}
+
+/// A [`WordPositionsEntry`] owning its words.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OwnedWordPositionsEntry {
+    pub document_id: DocumentId,
+    pub fid: FieldId,
+    pub deletion: Vec<(u16, String)>,
+    pub addition: Vec<(u16, String)>,
+}
+
+/// The entries of a docid_word_positions chunk decoded once, cheap to clone
+/// and to share between the extractors.
+#[derive(Debug, Default, Clone)]
+pub struct CachedDocidWordPositions {
+    entries: Arc<[OwnedWordPositionsEntry]>,
+}
+
+impl CachedDocidWordPositions {
+    /// Reads and decodes all the entries of the chunk.
+    pub fn decode<R: io::Read + io::Seek>(docid_word_positions: grenad::Reader<R>) -> Result<Self> {
+        let mut entries = Vec::new();
+        let mut cursor = docid_word_positions.into_cursor()?;
+        while let Some((key, value)) = cursor.move_on_next()? {
+            let entry = WordPositionsEntry::decode(key, value, WordPositionsEntry::default())?;
+            let owned = |words: Vec<(u16, &str)>| {
+                words.into_iter().map(|(position, word)| (position, word.to_string())).collect()
+            };
+            entries.push(OwnedWordPositionsEntry {
+                document_id: entry.document_id,
+                fid: entry.fid,
+                deletion: owned(entry.deletion),
+                addition: owned(entry.addition),
+            });
+        }
+        Ok(Self { entries: entries.into() })
+    }
+
+    pub fn entries(&self) -> &[OwnedWordPositionsEntry] {
+        &self.entries
+    }
+}
+
+/// The docid_word_positions given to the word and proximity extractors.
+///
+/// The extractors usually read the grenad chunk on their own, but settings-only reindexes
+/// decode it once and share the decoded entries between them.
+#[derive(Clone)]
+pub enum DocidWordPositions<R> {
+    Chunk(grenad::Reader<R>),
+    Cached(CachedDocidWordPositions),
+}
+
+impl<R: io::Read + io::Seek> DocidWordPositions<R> {
+    /// Calls `f` on every entry, in the order of the chunk.
+    pub fn for_each(self, mut f: impl FnMut(&WordPositionsEntry<'_>) -> Result<()>) -> Result<()> {
+        let mut recycled = WordPositionsEntry::default();
+        match self {
+            DocidWordPositions::Chunk(reader) => {
+                let mut cursor = reader.into_cursor()?;
+                while let Some((key, value)) = cursor.move_on_next()? {
+                    let entry = WordPositionsEntry::decode(key, value, recycled)?;
+                    f(&entry)?;
+                    recycled = entry.recycle();
+                }
+                Ok(())
+            }
+            DocidWordPositions::Cached(cached) => {
+                for owned in cached.entries() {
+                    let mut entry = recycled.recycle();
+                    entry.document_id = owned.document_id;
+                    entry.fid = owned.fid;
+                    for (words, owned_words) in [
+                        (&mut entry.deletion, &owned.deletion),
+                        (&mut entry.addition, &owned.addition),
+                    ] {
+                        words.extend(
+                            owned_words.iter().map(|(position, word)| (*position, word.as_str())),
+                        );
+                    }
+                    f(&entry)?;
+                    recycled = entry;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<R> From<grenad::Reader<R>> for DocidWordPositions<R> {
+    fn from(reader: grenad::Reader<R>) -> Self {
+        DocidWordPositions::Chunk(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs::File;
+    use std::io::{BufReader, Read, Seek, SeekFrom};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use big_s::S;
+    use obkv::KvWriterU16;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::del_add::KvWriterDelAdd;
+    use crate::update::index_documents::extract::extract_word_docids::extract_word_docids;
+    use crate::update::index_documents::extract::extract_word_pair_proximity_docids::extract_word_pair_proximity_docids;
+    use crate::update::index_documents::helpers::GrenadParameters;
+    use crate::update::settings::{InnerIndexSettings, InnerIndexSettingsDiff};
+
+    /// A docid_word_positions chunk counting the bytes read from it.
+    #[derive(Clone)]
+    struct CountingCursor {
+        inner: io::Cursor<Arc<[u8]>>,
+        read: Arc<AtomicUsize>,
+    }
+
+    impl Read for CountingCursor {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.inner.read(buf)?;
+            self.read.fetch_add(read, Ordering::Relaxed);
+            Ok(read)
+        }
+    }
+
+    impl Seek for CountingCursor {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    fn docid_word_positions(
+        entries: &[(DocumentId, FieldId, &str, &str)],
+    ) -> (grenad::Reader<CountingCursor>, Arc<AtomicUsize>) {
+        let mut writer = grenad::Writer::memory();
+        for (docid, fid, deleted, added) in entries {
+            let mut key = docid.to_be_bytes().to_vec();
+            key.extend_from_slice(&fid.to_be_bytes());
+
+            let mut value = KvWriterDelAdd::memory();
+            for (side, text) in [(DelAdd::Deletion, deleted), (DelAdd::Addition, added)] {
+                if text.is_empty() {
+                    continue;
+                }
+                let mut positions = KvWriterU16::memory();
+                for (position, word) in text.split(' ').enumerate() {
+                    positions.insert(position as u16, word.as_bytes()).unwrap();
+                }
+                value.insert(side, positions.into_inner().unwrap()).unwrap();
+            }
+            writer.insert(key, value.into_inner().unwrap()).unwrap();
+        }
+        let bytes: Arc<[u8]> = writer.into_inner().unwrap().into();
+        let read = Arc::new(AtomicUsize::new(0));
+        let cursor = CountingCursor { inner: io::Cursor::new(bytes), read: read.clone() };
+        let reader = grenad::Reader::new(cursor).unwrap();
+        // only count the entries read, not the header read when opening the chunk
+        read.store(0, Ordering::Relaxed);
+        (reader, read)
+    }
+
+    fn read_all(reader: grenad::Reader<BufReader<File>>) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let mut entries = Vec::new();
+        let mut cursor = reader.into_cursor().unwrap();
+        while let Some((key, value)) = cursor.move_on_next().unwrap() {
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+        entries
+    }
+
+    #[test]
+    fn decoded_once_for_word_and_proximity_extractors() {
+        let index = TempIndex::new();
+        let rtxn = index.read_txn().unwrap();
+        let old = InnerIndexSettings::from_index(&index, &rtxn, None).unwrap();
+        let new = InnerIndexSettings::from_index(&index, &rtxn, None).unwrap();
+        let settings_diff = InnerIndexSettingsDiff::new(old, new, None, BTreeMap::new(), false);
+
+        let entries = [
+            (0, 0, "", "hello world"),
+            (0, 1, "", "rust is a language"),
+            (1, 0, "hello there", "hello world"),
+            (2, 1, "a language", ""),
+        ];
+        let extract = |input: DocidWordPositions<CountingCursor>| {
+            let (word_docids, _, word_fid_docids, _) =
+                extract_word_docids(input.clone(), GrenadParameters::default(), &settings_diff)
+                    .unwrap();
+            let proximities = extract_word_pair_proximity_docids(
+                input,
+                GrenadParameters::default(),
+                &settings_diff,
+            )
+            .unwrap();
+            (read_all(word_docids), read_all(word_fid_docids), read_all(proximities))
+        };
+
+        // each extractor reads the chunk on its own
+        let (chunk, chunk_read) = docid_word_positions(&entries);
+        let expected = extract(DocidWordPositions::Chunk(chunk));
+        let read_by_both = chunk_read.load(Ordering::Relaxed);
+
+        let (chunk, cached_read) = docid_word_positions(&entries);
+        let cached = CachedDocidWordPositions::decode(chunk).unwrap();
+        let read_once = cached_read.load(Ordering::Relaxed);
+        assert_eq!(cached.entries().len(), entries.len());
+        assert_eq!(cached.entries()[1].addition[3], (3, S("language")));
+        assert_eq!(read_by_both, read_once * 2);
+
+        // the cached entries are shared without reading the chunk again
+        assert_eq!(extract(DocidWordPositions::Cached(cached)), expected);
+        assert_eq!(cached_read.load(Ordering::Relaxed), read_once);
+    }
+
+    #[test]
+    fn chunk_entries_borrow_their_words() {
+        let (chunk, _) =
+            docid_word_positions(&[(0, 0, "hello", "hello world"), (1, 0, "", "rust")]);
+        let mut cursor = chunk.into_cursor().unwrap();
+
+        let (key, value) = cursor.move_on_next().unwrap().unwrap();
+        let entry = WordPositionsEntry::decode(key, value, WordPositionsEntry::default()).unwrap();
+        assert_eq!(entry.deletion, [(0, "hello")]);
+        assert_eq!(entry.addition, [(0, "hello"), (1, "world")]);
+        let value_range = value.as_ptr_range();
+        for (_, word) in entry.deletion.iter().chain(&entry.addition) {
+            assert!(value_range.contains(&word.as_ptr()), "{word}");
+        }
+
+        // the next entry reuses the allocations of the previous one
+        let allocation = entry.addition.as_ptr() as usize;
+        let recycled = entry.recycle();
+        let (key, value) = cursor.move_on_next().unwrap().unwrap();
+        let entry = WordPositionsEntry::decode(key, value, recycled).unwrap();
+        assert_eq!((entry.document_id, entry.addition.as_slice()), (1, &[(0, "rust")][..]));
+        assert!(entry.deletion.is_empty());
+        assert_eq!(entry.addition.as_ptr() as usize, allocation);
+    }
+}
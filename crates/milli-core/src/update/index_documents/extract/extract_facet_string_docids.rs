@@ -136,7 +136,7 @@ fn extract_facet_string_docids_document_update<R: io::Read + io::Seek>(
         facet_string_docids_sorter.insert(&key_bytes, &buffer)?;
     }
 
-    let normalized = sorter_into_reader(normalized_facet_string_docids_sorter, indexer)?;
+    let normalized = sorter_into_reader(normalized_facet_string_docids_sorter, indexer.clone())?;
     sorter_into_reader(facet_string_docids_sorter, indexer).map(|s| (s, normalized))
 }
 
@@ -302,7 +302,7 @@ fn extract_facet_string_docids_settings<R: io::Read + io::Seek>(
         facet_string_docids_sorter.insert(&key_bytes, &buffer)?;
     }
 
-    let normalized = sorter_into_reader(normalized_facet_string_docids_sorter, indexer)?;
+    let normalized = sorter_into_reader(normalized_facet_string_docids_sorter, indexer.clone())?;
     sorter_into_reader(facet_string_docids_sorter, indexer).map(|s| (s, normalized))
 }
 
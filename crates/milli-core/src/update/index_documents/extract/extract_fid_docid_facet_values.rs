@@ -5,6 +5,7 @@ use std::io::{self, BufReader};
 use std::mem::size_of;
 
 use bytemuck::bytes_of;
+use charabia::Language;
 use grenad::Sorter;
 use heed::BytesEncode;
 use itertools::{merge_join_by, EitherOrBoth, Itertools};
@@ -15,7 +16,8 @@ use FilterableValues::{Empty, Null, Values};
 
 use super::helpers::{create_sorter, sorter_into_reader, GrenadParameters, KeepFirst};
 use crate::error::InternalError;
-use crate::facet::value_encoding::f64_into_bytes;
+use crate::facet::value_encoding::{canonicalize_zero, f64_into_bytes};
+use crate::heed_codec::facet::FieldIdI64Codec;
 use crate::update::del_add::{DelAdd, KvReaderDelAdd, KvWriterDelAdd};
 use crate::update::index_documents::{create_writer, writer_into_reader};
 use crate::update::settings::InnerIndexSettingsDiff;
@@ -31,6 +33,10 @@ pub struct ExtractedFacetValues {
     pub fid_facet_is_null_docids_chunk: grenad::Reader<BufReader<File>>,
     pub fid_facet_is_empty_docids_chunk: grenad::Reader<BufReader<File>>,
     pub fid_facet_exists_docids_chunk: grenad::Reader<BufReader<File>>,
+    pub fid_facet_i64_docids_chunk: grenad::Reader<BufReader<File>>,
+    pub fid_facet_is_date_docids_chunk: grenad::Reader<BufReader<File>>,
+    pub fid_facet_is_true_docids_chunk: grenad::Reader<BufReader<File>>,
+    pub fid_facet_is_false_docids_chunk: grenad::Reader<BufReader<File>>,
 }
 
 /// Extracts the facet values of each faceted field of each document.
@@ -70,6 +76,10 @@ pub fn extract_fid_docid_facet_values<R: io::Read + io::Seek>(
     let mut facet_exists_docids = BTreeMap::<FieldId, (RoaringBitmap, RoaringBitmap)>::new();
     let mut facet_is_null_docids = BTreeMap::<FieldId, (RoaringBitmap, RoaringBitmap)>::new();
     let mut facet_is_empty_docids = BTreeMap::<FieldId, (RoaringBitmap, RoaringBitmap)>::new();
+    let mut facet_is_date_docids = BTreeMap::<FieldId, (RoaringBitmap, RoaringBitmap)>::new();
+    let mut facet_is_true_docids = BTreeMap::<FieldId, (RoaringBitmap, RoaringBitmap)>::new();
+    let mut facet_is_false_docids = BTreeMap::<FieldId, (RoaringBitmap, RoaringBitmap)>::new();
+    let mut facet_i64_docids = BTreeMap::<(FieldId, i64), (RoaringBitmap, RoaringBitmap)>::new();
 
     // We create two buffers for mutable ref issues with closures.
     let mut numbers_key_buffer = Vec::new();
@@ -79,6 +89,12 @@ pub fn extract_fid_docid_facet_values<R: io::Read + io::Seek>(
         settings_diff.list_faceted_fields_from_fid_map(DelAdd::Deletion);
     let new_faceted_fids: BTreeSet<_> =
         settings_diff.list_faceted_fields_from_fid_map(DelAdd::Addition);
+    let old_integer_fids = settings_diff.list_integer_fields_from_fid_map(DelAdd::Deletion);
+    let new_integer_fids = settings_diff.list_integer_fields_from_fid_map(DelAdd::Addition);
+    let old_prefixable_fids =
+        settings_diff.list_prefixable_number_fields_from_fid_map(DelAdd::Deletion);
+    let new_prefixable_fids =
+        settings_diff.list_prefixable_number_fields_from_fid_map(DelAdd::Addition);
 
     if !settings_diff.settings_update_only || settings_diff.reindex_facets() {
         let mut cursor = obkv_documents.into_cursor()?;
@@ -115,6 +131,10 @@ pub fn extract_fid_docid_facet_values<R: io::Read + io::Seek>(
                         // during settings update, recompute the changing settings only unless a global change is detected.
                         if settings_diff.settings_update_only
                             && !settings_diff.global_facet_settings_changed()
+                            && old_integer_fids.contains(&field_id)
+                                == new_integer_fids.contains(&field_id)
+                            && old_prefixable_fids.contains(&field_id)
+                                == new_prefixable_fids.contains(&field_id)
                         {
                             continue;
                         }
@@ -157,6 +177,52 @@ pub fn extract_fid_docid_facet_values<R: io::Read + io::Seek>(
                         add_exists.insert(document);
                     }
 
+                    // The fields containing a valid date are kept to answer the `IS DATE` filters.
+                    let del_is_date = del_value.as_ref().is_some_and(contains_date_value);
+                    let add_is_date = add_value.as_ref().is_some_and(contains_date_value);
+                    if del_is_date || add_is_date {
+                        let (del, add) = facet_is_date_docids.entry(field_id).or_default();
+                        if del_is_date {
+                            del.insert(document);
+                        }
+                        if add_is_date {
+                            add.insert(document);
+                        }
+                    }
+
+                    // The fields containing a boolean are kept to answer the `IS TRUE` and
+                    // `IS FALSE` filters, their string facet values being shared with strings.
+                    for (boolean, facet_is_boolean_docids) in
+                        [(true, &mut facet_is_true_docids), (false, &mut facet_is_false_docids)]
+                    {
+                        let contains = |value: &Value| contains_boolean_value(value, boolean);
+                        let del_is_boolean = del_value.as_ref().is_some_and(contains);
+                        let add_is_boolean = add_value.as_ref().is_some_and(contains);
+                        if del_is_boolean || add_is_boolean {
+                            let (del, add) = facet_is_boolean_docids.entry(field_id).or_default();
+                            if del_is_boolean {
+                                del.insert(document);
+                            }
+                            if add_is_boolean {
+                                add.insert(document);
+                            }
+                        }
+                    }
+
+                    // The integer fields also store their integers exactly.
+                    if old_integer_fids.contains(&field_id) {
+                        for integer in del_value.iter().flat_map(extract_integer_values) {
+                            let (del, _) = facet_i64_docids.entry((field_id, integer)).or_default();
+                            del.insert(document);
+                        }
+                    }
+                    if new_integer_fids.contains(&field_id) {
+                        for integer in add_value.iter().flat_map(extract_integer_values) {
+                            let (_, add) = facet_i64_docids.entry((field_id, integer)).or_default();
+                            add.insert(document);
+                        }
+                    }
+
                     let del_geo_support = settings_diff
                         .old
                         .geo_fields_ids
@@ -165,10 +231,30 @@ pub fn extract_fid_docid_facet_values<R: io::Read + io::Seek>(
                         .new
                         .geo_fields_ids
                         .is_some_and(|(lat, lng)| field_id == lat || field_id == lng);
-                    let del_filterable_values =
-                        del_value.map(|value| extract_facet_values(&value, del_geo_support));
-                    let add_filterable_values =
-                        add_value.map(|value| extract_facet_values(&value, add_geo_support));
+                    let del_filterable_values = del_value.map(|value| {
+                        let values = extract_facet_values(
+                            &value,
+                            del_geo_support,
+                            settings_diff.old.facet_normalization_locale,
+                        );
+                        if old_prefixable_fids.contains(&field_id) {
+                            values.with_number_strings()
+                        } else {
+                            values
+                        }
+                    });
+                    let add_filterable_values = add_value.map(|value| {
+                        let values = extract_facet_values(
+                            &value,
+                            add_geo_support,
+                            settings_diff.new.facet_normalization_locale,
+                        );
+                        if new_prefixable_fids.contains(&field_id) {
+                            values.with_number_strings()
+                        } else {
+                            values
+                        }
+                    });
 
                     // Those closures are just here to simplify things a bit.
                     let mut insert_numbers_diff = |del_numbers, add_numbers| {
@@ -294,12 +380,64 @@ pub fn extract_fid_docid_facet_values<R: io::Read + io::Seek>(
     }
     let facet_is_empty_docids_reader = writer_into_reader(facet_is_empty_docids_writer)?;
 
+    let mut facet_is_date_docids_writer = create_writer(
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        tempfile::tempfile()?,
+    );
+    for (fid, (del_bitmap, add_bitmap)) in facet_is_date_docids.into_iter() {
+        deladd_obkv_cbo_roaring_bitmaps(&mut buffer, &del_bitmap, &add_bitmap)?;
+        facet_is_date_docids_writer.insert(fid.to_be_bytes(), &buffer)?;
+    }
+    let facet_is_date_docids_reader = writer_into_reader(facet_is_date_docids_writer)?;
+
+    let mut facet_is_true_docids_writer = create_writer(
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        tempfile::tempfile()?,
+    );
+    for (fid, (del_bitmap, add_bitmap)) in facet_is_true_docids.into_iter() {
+        deladd_obkv_cbo_roaring_bitmaps(&mut buffer, &del_bitmap, &add_bitmap)?;
+        facet_is_true_docids_writer.insert(fid.to_be_bytes(), &buffer)?;
+    }
+    let facet_is_true_docids_reader = writer_into_reader(facet_is_true_docids_writer)?;
+
+    let mut facet_is_false_docids_writer = create_writer(
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        tempfile::tempfile()?,
+    );
+    for (fid, (del_bitmap, add_bitmap)) in facet_is_false_docids.into_iter() {
+        deladd_obkv_cbo_roaring_bitmaps(&mut buffer, &del_bitmap, &add_bitmap)?;
+        facet_is_false_docids_writer.insert(fid.to_be_bytes(), &buffer)?;
+    }
+    let facet_is_false_docids_reader = writer_into_reader(facet_is_false_docids_writer)?;
+
+    let mut facet_i64_docids_writer = create_writer(
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        tempfile::tempfile()?,
+    );
+    for (key, (del_bitmap, add_bitmap)) in facet_i64_docids.into_iter() {
+        deladd_obkv_cbo_roaring_bitmaps(&mut buffer, &del_bitmap, &add_bitmap)?;
+        let key = FieldIdI64Codec::bytes_encode(&key).map_err(heed::Error::Encoding)?;
+        facet_i64_docids_writer.insert(key, &buffer)?;
+    }
+    let facet_i64_docids_reader = writer_into_reader(facet_i64_docids_writer)?;
+
     Ok(ExtractedFacetValues {
-        fid_docid_facet_numbers_chunk: sorter_into_reader(fid_docid_facet_numbers_sorter, indexer)?,
+        fid_docid_facet_numbers_chunk: sorter_into_reader(
+            fid_docid_facet_numbers_sorter,
+            indexer.clone(),
+        )?,
         fid_docid_facet_strings_chunk: sorter_into_reader(fid_docid_facet_strings_sorter, indexer)?,
         fid_facet_is_null_docids_chunk: facet_is_null_docids_reader,
         fid_facet_is_empty_docids_chunk: facet_is_empty_docids_reader,
         fid_facet_exists_docids_chunk: facet_exists_docids_reader,
+        fid_facet_i64_docids_chunk: facet_i64_docids_reader,
+        fid_facet_is_date_docids_chunk: facet_is_date_docids_reader,
+        fid_facet_is_true_docids_chunk: facet_is_true_docids_reader,
+        fid_facet_is_false_docids_chunk: facet_is_false_docids_reader,
     })
 }
 
@@ -512,21 +650,73 @@ enum FilterableValues {
     Values { numbers: Vec<f64>, strings: Vec<(String, String)> },
 }
 
+impl FilterableValues {
+    /// Also stores the numbers as strings, so that the string filters like `STARTS WITH`
+    /// match them.
+    fn with_number_strings(self) -> Self {
+        match self {
+            Values { numbers, mut strings } => {
+                strings.extend(numbers.iter().map(|number| {
+                    let string = number.to_string();
+                    (string.clone(), string)
+                }));
+                Values { numbers, strings }
+            }
+            otherwise => otherwise,
+        }
+    }
+}
+
+/// Extracts the integers of a JSON field, the floats like `1.0` are not integers.
+fn extract_integer_values(value: &Value) -> Vec<i64> {
+    match value {
+        Value::Number(number) => number.as_i64().into_iter().collect(),
+        Value::Array(values) => values.iter().filter_map(Value::as_i64).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns `true` if the JSON field is a valid date string or an array containing one.
+fn contains_date_value(value: &Value) -> bool {
+    match value {
+        Value::String(s) => crate::is_date_facet_value(s),
+        Value::Array(values) => values.iter().any(contains_date_value),
+        _ => false,
+    }
+}
+
+/// Returns `true` if the JSON field is the `boolean` or an array containing it.
+fn contains_boolean_value(value: &Value, boolean: bool) -> bool {
+    match value {
+        Value::Bool(b) => *b == boolean,
+        Value::Array(values) => values.iter().any(|value| contains_boolean_value(value, boolean)),
+        _ => false,
+    }
+}
+
 /// Extracts the facet values of a JSON field.
-fn extract_facet_values(value: &Value, geo_field: bool) -> FilterableValues {
+fn extract_facet_values(
+    value: &Value,
+    geo_field: bool,
+    locale: Option<Language>,
+) -> FilterableValues {
     fn inner_extract_facet_values(
         value: &Value,
         can_recurse: bool,
         output_numbers: &mut Vec<f64>,
         output_strings: &mut Vec<(String, String)>,
         geo_field: bool,
+        locale: Option<Language>,
     ) {
         match value {
             Value::Null => (),
-            Value::Bool(b) => output_strings.push((b.to_string(), b.to_string())),
+            Value::Bool(b) => {
+                let b = crate::boolean_facet_value(*b);
+                output_strings.push((b.to_string(), b.to_string()))
+            }
             Value::Number(number) => {
                 if let Some(float) = number.as_f64() {
-                    output_numbers.push(float);
+                    output_numbers.push(canonicalize_zero(float));
                 }
             }
             Value::String(original) => {
@@ -541,8 +731,8 @@ fn extract_facet_values(value: &Value, geo_field: bool) -> FilterableValues {
                         )
                     }
                 }
-                let normalized = crate::normalize_facet(original);
-                output_strings.push((normalized, original.clone()));
+                let normalized = crate::normalize_facet_with_locale(original, locale);
+                output_strings.push((normalized.into_owned(), original.clone()));
             }
             Value::Array(values) => {
                 if can_recurse {
@@ -553,6 +743,7 @@ fn extract_facet_values(value: &Value, geo_field: bool) -> FilterableValues {
                             output_numbers,
                             output_strings,
                             geo_field,
+                            locale,
                         );
                     }
                 }
@@ -569,7 +760,14 @@ fn extract_facet_values(value: &Value, geo_field: bool) -> FilterableValues {
         otherwise => {
             let mut numbers = Vec::new();
             let mut strings = Vec::new();
-            inner_extract_facet_values(otherwise, true, &mut numbers, &mut strings, geo_field);
+            inner_extract_facet_values(
+                otherwise,
+                true,
+                &mut numbers,
+                &mut strings,
+                geo_field,
+                locale,
+            );
             FilterableValues::Values { numbers, strings }
         }
     }
@@ -1,20 +1,20 @@
 use std::collections::BTreeSet;
 use std::fs::File;
-use std::io::{self, BufReader};
+use std::io::{self, BufReader, BufWriter};
 
 use heed::{BytesDecode, BytesEncode};
-use obkv::KvReaderU16;
+use rayon::prelude::*;
 use roaring::RoaringBitmap;
 
+use super::cached_docid_word_positions::DocidWordPositions;
 use super::helpers::{
-    create_sorter, create_writer, try_split_array_at, writer_into_reader, GrenadParameters,
-    MergeDeladdCboRoaringBitmaps,
+    as_cloneable_grenad, create_sorter, create_writer, writer_into_reader, FlushingSorter,
+    GrenadParameters, MergeDeladdCboRoaringBitmaps, SpilledChunk,
 };
 use crate::error::SerializationError;
 use crate::heed_codec::StrBEU16Codec;
-use crate::index::db_name::DOCID_WORD_POSITIONS;
+use crate::index::db_name::{DOCID_WORD_POSITIONS, WORD_DOCIDS, WORD_FIELD_ID_DOCIDS};
 use crate::update::del_add::{is_noop_del_add_obkv, DelAdd, KvReaderDelAdd, KvWriterDelAdd};
-use crate::update::index_documents::helpers::sorter_into_reader;
 use crate::update::settings::InnerIndexSettingsDiff;
 use crate::{CboRoaringBitmapCodec, DocumentId, FieldId, Result};
 
@@ -23,60 +23,83 @@ use crate::{CboRoaringBitmapCodec, DocumentId, FieldId, Result};
 /// Returns a grenad reader with the list of extracted words and
 /// documents ids from the given chunk of docid word positions.
 ///
-/// The first returned reader is the one for normal word_docids, and the second one is for
-/// exact_word_docids
+/// The first returned reader is the one for normal word_docids, the second one is for
+/// exact_word_docids, the third one is for word_fid_docids and the last one
+/// maps each word to the number of documents in which it is deleted and added.
+///
+/// The words are routed to the normal and exact readers in parallel, over partitions
+/// of the merged word_fid_docids entries, the output doesn't depend on the partitions.
+///
+/// When none of the documents deletes a word, like during a first import, the words are
+/// routed without looking for deletions.
 #[tracing::instrument(level = "trace", skip_all, target = "indexing::extract")]
 pub fn extract_word_docids<R: io::Read + io::Seek>(
-    docid_word_positions: grenad::Reader<R>,
+    docid_word_positions: impl Into<DocidWordPositions<R>>,
     indexer: GrenadParameters,
     settings_diff: &InnerIndexSettingsDiff,
-) -> Result<(
+) -> Result<WordDocidsReaders> {
+    extract_word_docids_in_partitions(
+        docid_word_positions,
+        indexer,
+        settings_diff,
+        Some(SECOND_PASS_PARTITION_ENTRIES),
+        true,
+    )
+}
+
+/// The minimum number of word_fid_docids entries routed by each of the parallel partitions
+/// of [`extract_word_docids`].
+const SECOND_PASS_PARTITION_ENTRIES: usize = 100_000;
+
+type WordDocidsReaders = (
     grenad::Reader<BufReader<File>>,
     grenad::Reader<BufReader<File>>,
     grenad::Reader<BufReader<File>>,
-)> {
+    grenad::Reader<BufReader<File>>,
+);
+
+/// Extracts the word docids like [`extract_word_docids`], routing the words in partitions
+/// of at least `partition_entries` entries, or sequentially when `None`.
+///
+/// The addition-only routing is used when `detect_additions_only` is set and no word is deleted.
+fn extract_word_docids_in_partitions<R: io::Read + io::Seek>(
+    docid_word_positions: impl Into<DocidWordPositions<R>>,
+    indexer: GrenadParameters,
+    settings_diff: &InnerIndexSettingsDiff,
+    partition_entries: Option<usize>,
+    detect_additions_only: bool,
+) -> Result<WordDocidsReaders> {
     let max_memory = indexer.max_memory_by_thread();
 
-    let mut word_fid_docids_sorter = create_sorter(
+    let mut word_fid_docids_sorter = FlushingSorter::new(
+        "word_fid_docids",
         grenad::SortAlgorithm::Unstable,
         MergeDeladdCboRoaringBitmaps,
-        indexer.chunk_compression_type,
-        indexer.chunk_compression_level,
-        indexer.max_nb_chunks,
+        indexer.clone(),
         max_memory.map(|m| m / 3),
-        true,
     );
     let mut key_buffer = Vec::new();
+    let mut value_buffer = Vec::new();
     let mut del_words = BTreeSet::new();
     let mut add_words = BTreeSet::new();
-    let mut cursor = docid_word_positions.into_cursor()?;
-    while let Some((key, value)) = cursor.move_on_next()? {
-        let (document_id_bytes, fid_bytes) = try_split_array_at(key)
-            .ok_or(SerializationError::Decoding { db_name: Some(DOCID_WORD_POSITIONS) })?;
-        let (fid_bytes, _) = try_split_array_at(fid_bytes)
-            .ok_or(SerializationError::Decoding { db_name: Some(DOCID_WORD_POSITIONS) })?;
-        let document_id = u32::from_be_bytes(document_id_bytes);
-        let fid = u16::from_be_bytes(fid_bytes);
-
-        let del_add_reader = KvReaderDelAdd::from_slice(value);
+    let mut additions_only = detect_additions_only;
+    docid_word_positions.into().for_each(|entry| {
         // extract all unique words to remove.
-        if let Some(deletion) = del_add_reader.get(DelAdd::Deletion) {
-            for (_pos, word) in KvReaderU16::from_slice(deletion).iter() {
-                del_words.insert(word.to_vec());
-            }
+        for (_pos, word) in &entry.deletion {
+            del_words.insert(word.as_bytes().to_vec());
         }
+        additions_only &= del_words.is_empty();
 
         // extract all unique additional words.
-        if let Some(addition) = del_add_reader.get(DelAdd::Addition) {
-            for (_pos, word) in KvReaderU16::from_slice(addition).iter() {
-                add_words.insert(word.to_vec());
-            }
+        for (_pos, word) in &entry.addition {
+            add_words.insert(word.as_bytes().to_vec());
         }
 
         words_into_sorter(
-            document_id,
-            fid,
+            entry.document_id,
+            entry.fid,
             &mut key_buffer,
+            &mut value_buffer,
             &del_words,
             &add_words,
             &mut word_fid_docids_sorter,
@@ -84,80 +107,359 @@ pub fn extract_word_docids<R: io::Read + io::Seek>(
 
         del_words.clear();
         add_words.clear();
-    }
+        Ok(())
+    })?;
 
-    let mut word_fid_docids_writer = create_writer(
-        indexer.chunk_compression_type,
-        indexer.chunk_compression_level,
-        tempfile::tempfile()?,
-    );
+    let mut iter = word_fid_docids_sorter.into_stream_merger_iter()?;
+    let output = match partition_entries {
+        Some(partition_entries) => second_pass_in_partitions(
+            iter,
+            indexer,
+            settings_diff,
+            partition_entries,
+            additions_only,
+        )?,
+        None => {
+            let mut second_pass =
+                SecondPass::new(&indexer, settings_diff, additions_only, max_memory)?;
+            while let Some((key, value)) = iter.next()? {
+                second_pass.route(key, value)?;
+            }
+            second_pass.finish()?
+        }
+    };
 
-    let mut word_docids_sorter = create_sorter(
-        grenad::SortAlgorithm::Unstable,
-        MergeDeladdCboRoaringBitmaps,
-        indexer.chunk_compression_type,
-        indexer.chunk_compression_level,
-        indexer.max_nb_chunks,
-        max_memory.map(|m| m / 3),
-        true,
-    );
+    Ok((
+        output.word_docids,
+        output.exact_word_docids,
+        output.word_fid_docids,
+        output.word_frequency,
+    ))
+}
 
-    let mut exact_word_docids_sorter = create_sorter(
-        grenad::SortAlgorithm::Unstable,
-        MergeDeladdCboRoaringBitmaps,
-        indexer.chunk_compression_type,
-        indexer.chunk_compression_level,
-        indexer.max_nb_chunks,
-        max_memory.map(|m| m / 3),
-        true,
-    );
+/// The readers of the second pass of [`extract_word_docids`].
+struct SecondPassOutput {
+    word_docids: grenad::Reader<BufReader<File>>,
+    exact_word_docids: grenad::Reader<BufReader<File>>,
+    word_fid_docids: grenad::Reader<BufReader<File>>,
+    word_frequency: grenad::Reader<BufReader<File>>,
+}
+
+/// Routes the merged word fid docids entries to the word docids and exact word docids sorters,
+/// and computes the frequency of each word.
+///
+/// The entries must be given in key order. Since the keys are sorted by word then by field id,
+/// all the entries of a word are contiguous.
+struct SecondPass<'a> {
+    settings_diff: &'a InnerIndexSettingsDiff,
+    /// The entries only contain additions, see [`SecondPass::route_additions`].
+    additions_only: bool,
+    word_fid_docids_writer: grenad::Writer<BufWriter<File>>,
+    word_docids_sorter: FlushingSorter<MergeDeladdCboRoaringBitmaps>,
+    exact_word_docids_sorter: FlushingSorter<MergeDeladdCboRoaringBitmaps>,
+    word_frequency_writer: grenad::Writer<BufWriter<File>>,
+    buffer: Vec<u8>,
+    current_word: String,
+    word_deletions: RoaringBitmap,
+    word_additions: RoaringBitmap,
+}
+
+impl<'a> SecondPass<'a> {
+    /// Creates a second pass whose sorters share the `max_memory`.
+    fn new(
+        indexer: &GrenadParameters,
+        settings_diff: &'a InnerIndexSettingsDiff,
+        additions_only: bool,
+        max_memory: Option<usize>,
+    ) -> Result<Self> {
+        let sorter = |sorter_name| {
+            FlushingSorter::new(
+                sorter_name,
+                grenad::SortAlgorithm::Unstable,
+                MergeDeladdCboRoaringBitmaps,
+                indexer.clone(),
+                max_memory.map(|m| m / 3),
+            )
+        };
+        let writer = || -> Result<_> {
+            Ok(create_writer(
+                indexer.chunk_compression_type,
+                indexer.chunk_compression_level,
+                tempfile::tempfile()?,
+            ))
+        };
+
+        Ok(SecondPass {
+            settings_diff,
+            additions_only,
+            word_fid_docids_writer: writer()?,
+            word_docids_sorter: sorter("word_docids"),
+            exact_word_docids_sorter: sorter("exact_word_docids"),
+            word_frequency_writer: writer()?,
+            buffer: Vec::new(),
+            current_word: String::new(),
+            word_deletions: RoaringBitmap::new(),
+            word_additions: RoaringBitmap::new(),
+        })
+    }
+
+    fn route(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        if self.additions_only {
+            return self.route_additions(key, value);
+        }
 
-    let mut iter = word_fid_docids_sorter.into_stream_merger_iter()?;
-    let mut buffer = Vec::new();
-    // NOTE: replacing sorters by bitmap merging is less efficient, so, use sorters.
-    while let Some((key, value)) = iter.next()? {
         // only keep the value if their is a change to apply in the DB.
         if !is_noop_del_add_obkv(KvReaderDelAdd::from_slice(value)) {
-            word_fid_docids_writer.insert(key, value)?;
+            self.word_fid_docids_writer.insert(key, value)?;
         }
 
-        let (w, fid) = StrBEU16Codec::bytes_decode(key)
-            .map_err(|_| SerializationError::Decoding { db_name: Some(DOCID_WORD_POSITIONS) })?;
+        let (w, fid) = self.start_word(key)?;
 
-        // merge all deletions
+        // NOTE: replacing sorters by bitmap merging is less efficient, so, use sorters.
+        let settings_diff = self.settings_diff;
+        // merge all deletions, the empty ones written when merging additions are skipped
+        // so that the readers are the same as the ones of the addition-only routing.
         let obkv = KvReaderDelAdd::from_slice(value);
-        if let Some(value) = obkv.get(DelAdd::Deletion) {
+        if let Some(value) = obkv.get(DelAdd::Deletion).filter(|value| !value.is_empty()) {
+            self.word_deletions |= CboRoaringBitmapCodec::deserialize_from(value)?;
             let delete_from_exact = settings_diff.old.exact_attributes.contains(&fid)
                 || settings_diff.old.disabled_typos_terms.is_exact(w);
-            buffer.clear();
-            let mut obkv = KvWriterDelAdd::new(&mut buffer);
+            self.buffer.clear();
+            let mut obkv = KvWriterDelAdd::new(&mut self.buffer);
             obkv.insert(DelAdd::Deletion, value)?;
             if delete_from_exact {
-                exact_word_docids_sorter.insert(w, obkv.into_inner().unwrap())?;
+                self.exact_word_docids_sorter.insert(w, obkv.into_inner().unwrap())?;
             } else {
-                word_docids_sorter.insert(w, obkv.into_inner().unwrap())?;
+                self.word_docids_sorter.insert(w, obkv.into_inner().unwrap())?;
             }
         }
         // merge all additions
         if let Some(value) = obkv.get(DelAdd::Addition) {
+            self.word_additions |= CboRoaringBitmapCodec::deserialize_from(value)?;
             let add_in_exact = settings_diff.new.exact_attributes.contains(&fid)
                 || settings_diff.new.disabled_typos_terms.is_exact(w);
-            buffer.clear();
-            let mut obkv = KvWriterDelAdd::new(&mut buffer);
+            self.buffer.clear();
+            let mut obkv = KvWriterDelAdd::new(&mut self.buffer);
             obkv.insert(DelAdd::Addition, value)?;
             if add_in_exact {
-                exact_word_docids_sorter.insert(w, obkv.into_inner().unwrap())?;
+                self.exact_word_docids_sorter.insert(w, obkv.into_inner().unwrap())?;
             } else {
-                word_docids_sorter.insert(w, obkv.into_inner().unwrap())?;
+                self.word_docids_sorter.insert(w, obkv.into_inner().unwrap())?;
             }
         }
+
+        Ok(())
     }
 
-    Ok((
-        sorter_into_reader(word_docids_sorter, indexer)?,
-        sorter_into_reader(exact_word_docids_sorter, indexer)?,
-        writer_into_reader(word_fid_docids_writer)?,
-    ))
+    /// Routes an entry like [`Self::route`] when the entries only contain additions,
+    /// which are never noops.
+    fn route_additions(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.word_fid_docids_writer.insert(key, value)?;
+
+        let (w, fid) = self.start_word(key)?;
+        // the merged entries can hold an empty deletion side, skipped like in `Self::route`
+        let Some(value) = KvReaderDelAdd::from_slice(value).get(DelAdd::Addition) else {
+            return Ok(());
+        };
+        self.word_additions |= CboRoaringBitmapCodec::deserialize_from(value)?;
+        let settings_diff = self.settings_diff;
+        let add_in_exact = settings_diff.new.exact_attributes.contains(&fid)
+            || settings_diff.new.disabled_typos_terms.is_exact(w);
+        self.buffer.clear();
+        let mut obkv = KvWriterDelAdd::new(&mut self.buffer);
+        obkv.insert(DelAdd::Addition, value)?;
+        if add_in_exact {
+            self.exact_word_docids_sorter.insert(w, obkv.into_inner().unwrap())?;
+        } else {
+            self.word_docids_sorter.insert(w, obkv.into_inner().unwrap())?;
+        }
+
+        Ok(())
+    }
+
+    /// Decodes the word and field id of the key, writing the frequency of the previous word
+    /// when the key starts a new word.
+    fn start_word<'k>(&mut self, key: &'k [u8]) -> Result<(&'k str, FieldId)> {
+        let (w, fid) = decode_word_fid(key)?;
+
+        if w != self.current_word {
+            word_frequency_into_writer(
+                &self.current_word,
+                &self.word_deletions,
+                &self.word_additions,
+                &mut self.word_frequency_writer,
+            )?;
+            self.current_word.clear();
+            self.current_word.push_str(w);
+            self.word_deletions.clear();
+            self.word_additions.clear();
+        }
+
+        Ok((w, fid))
+    }
+
+    fn finish(mut self) -> Result<SecondPassOutput> {
+        word_frequency_into_writer(
+            &self.current_word,
+            &self.word_deletions,
+            &self.word_additions,
+            &mut self.word_frequency_writer,
+        )?;
+
+        Ok(SecondPassOutput {
+            word_docids: self.word_docids_sorter.into_reader()?,
+            exact_word_docids: self.exact_word_docids_sorter.into_reader()?,
+            word_fid_docids: writer_into_reader(self.word_fid_docids_writer)?,
+            word_frequency: writer_into_reader(self.word_frequency_writer)?,
+        })
+    }
+}
+
+/// Decodes the word and field id of a word_fid_docids key.
+fn decode_word_fid(key: &[u8]) -> Result<(&str, FieldId)> {
+    StrBEU16Codec::bytes_decode(key)
+        .map_err(|_| SerializationError::Decoding { db_name: Some(WORD_FIELD_ID_DOCIDS) }.into())
+}
+
+/// Runs the second pass of [`extract_word_docids`] in parallel over partitions of the merged
+/// entries of at least `partition_entries` entries each.
+///
+/// The partitions are cut between two words, so that the outputs of the partitions contain
+/// disjoint and ordered words, and are concatenated into the same readers as the ones of a
+/// sequential pass over all the entries.
+///
+/// The first partition is routed while merging the entries, with the whole memory of the thread,
+/// the following ones are written to disk and routed in parallel, sharing the memory of the thread.
+fn second_pass_in_partitions(
+    mut iter: grenad::MergerIter<SpilledChunk, MergeDeladdCboRoaringBitmaps>,
+    indexer: GrenadParameters,
+    settings_diff: &InnerIndexSettingsDiff,
+    partition_entries: usize,
+    additions_only: bool,
+) -> Result<SecondPassOutput> {
+    let max_memory = indexer.max_memory_by_thread();
+    let mut first_pass = SecondPass::new(&indexer, settings_diff, additions_only, max_memory)?;
+    let mut merged_writer = None;
+    let mut partition_starts = Vec::new();
+    let mut entries = 0;
+    let mut previous_word = Vec::new();
+    while let Some((key, value)) = iter.next()? {
+        let (word, _fid) = decode_word_fid(key)?;
+        if entries >= partition_entries && word.as_bytes() != previous_word {
+            partition_starts.push(key.to_vec());
+            entries = 0;
+        }
+        entries += 1;
+        previous_word.clear();
+        previous_word.extend_from_slice(word.as_bytes());
+        if partition_starts.is_empty() {
+            first_pass.route(key, value)?;
+        } else {
+            let merged_writer = match &mut merged_writer {
+                Some(merged_writer) => merged_writer,
+                None => merged_writer.insert(create_writer(
+                    indexer.chunk_compression_type,
+                    indexer.chunk_compression_level,
+                    tempfile::tempfile()?,
+                )),
+            };
+            merged_writer.insert(key, value)?;
+        }
+    }
+    let first_output = first_pass.finish()?;
+    let Some(merged_writer) = merged_writer else {
+        return Ok(first_output);
+    };
+    let merged = writer_into_reader(merged_writer)?;
+    let merged = unsafe { as_cloneable_grenad(&merged)? };
+
+    let ends = partition_starts.iter().skip(1).map(Some).chain(std::iter::once(None));
+    let partitions: Vec<_> = partition_starts.iter().zip(ends).collect();
+    let max_memory = max_memory.map(|m| m / partitions.len());
+
+    let outputs = partitions
+        .into_par_iter()
+        .map(|(start, end)| {
+            let mut second_pass =
+                SecondPass::new(&indexer, settings_diff, additions_only, max_memory)?;
+            let mut cursor = merged.clone().into_cursor()?;
+            let mut entry = cursor.move_on_key_greater_than_or_equal_to(start)?;
+            while let Some((key, value)) = entry {
+                if end.is_some_and(|end| key >= end.as_slice()) {
+                    break;
+                }
+                second_pass.route(key, value)?;
+                entry = cursor.move_on_next()?;
+            }
+            second_pass.finish()
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let outputs: Vec<_> = std::iter::once(first_output).chain(outputs).collect();
+
+    let concat = |readers: &mut dyn Iterator<Item = grenad::Reader<BufReader<File>>>| {
+        let mut writer = create_writer(
+            indexer.chunk_compression_type,
+            indexer.chunk_compression_level,
+            tempfile::tempfile()?,
+        );
+        for reader in readers {
+            let mut cursor = reader.into_cursor()?;
+            while let Some((key, value)) = cursor.move_on_next()? {
+                writer.insert(key, value)?;
+            }
+        }
+        writer_into_reader(writer)
+    };
+
+    let mut word_docids = Vec::with_capacity(outputs.len());
+    let mut exact_word_docids = Vec::with_capacity(outputs.len());
+    let mut word_fid_docids = Vec::with_capacity(outputs.len());
+    let mut word_frequency = Vec::with_capacity(outputs.len());
+    for output in outputs {
+        word_docids.push(output.word_docids);
+        exact_word_docids.push(output.exact_word_docids);
+        word_fid_docids.push(output.word_fid_docids);
+        word_frequency.push(output.word_frequency);
+    }
+
+    Ok(SecondPassOutput {
+        word_docids: concat(&mut word_docids.into_iter())?,
+        exact_word_docids: concat(&mut exact_word_docids.into_iter())?,
+        word_fid_docids: concat(&mut word_fid_docids.into_iter())?,
+        word_frequency: concat(&mut word_frequency.into_iter())?,
+    })
+}
+
+/// Writes the number of documents in which the word is deleted and added
+/// as big-endian `u64`s in a `DelAdd` obkv.
+///
+/// Documents that are both deleted and added don't change the frequency of the word
+/// and are therefore not counted, a word without any change is not written at all.
+fn word_frequency_into_writer<W>(
+    word: &str,
+    deletions: &RoaringBitmap,
+    additions: &RoaringBitmap,
+    writer: &mut grenad::Writer<W>,
+) -> Result<()>
+where
+    W: std::io::Write,
+{
+    let deleted = deletions.difference_len(additions);
+    let added = additions.difference_len(deletions);
+    if word.is_empty() || (deleted == 0 && added == 0) {
+        return Ok(());
+    }
+
+    let mut obkv = KvWriterDelAdd::memory();
+    if deleted != 0 {
+        obkv.insert(DelAdd::Deletion, deleted.to_be_bytes())?;
+    }
+    if added != 0 {
+        obkv.insert(DelAdd::Addition, added.to_be_bytes())?;
+    }
+    writer.insert(word.as_bytes(), obkv.into_inner().unwrap())?;
+
+    Ok(())
 }
 
 #[tracing::instrument(level = "trace", skip_all, target = "indexing::extract")]
@@ -165,17 +467,17 @@ fn words_into_sorter(
     document_id: DocumentId,
     fid: FieldId,
     key_buffer: &mut Vec<u8>,
+    value_buffer: &mut Vec<u8>,
     del_words: &BTreeSet<Vec<u8>>,
     add_words: &BTreeSet<Vec<u8>>,
-    word_fid_docids_sorter: &mut grenad::Sorter<MergeDeladdCboRoaringBitmaps>,
+    word_fid_docids_sorter: &mut FlushingSorter<MergeDeladdCboRoaringBitmaps>,
 ) -> Result<()> {
     use itertools::merge_join_by;
     use itertools::EitherOrBoth::{Both, Left, Right};
 
-    let mut buffer = Vec::new();
     for eob in merge_join_by(del_words.iter(), add_words.iter(), |d, a| d.cmp(a)) {
-        buffer.clear();
-        let mut value_writer = KvWriterDelAdd::new(&mut buffer);
+        value_buffer.clear();
+        let mut value_writer = KvWriterDelAdd::new(&mut *value_buffer);
         let word_bytes = match eob {
             Left(word_bytes) => {
                 value_writer.insert(DelAdd::Deletion, document_id.to_ne_bytes()).unwrap();
@@ -202,6 +504,70 @@ fn words_into_sorter(
     Ok(())
 }
 
+/// Builds a word docids reader from the `(word, deletions, additions)` computed by an
+/// external source, ready to be merged like the one returned by [`extract_word_docids`].
+///
+/// The entries don't need to be sorted and a word can appear in multiple entries,
+/// its deletions and additions are merged before being written. Words for which the same
+/// documents are deleted and added are not written.
+pub fn build_word_docids_reader<I, S>(
+    entries: I,
+    indexer: GrenadParameters,
+) -> Result<grenad::Reader<BufReader<File>>>
+where
+    I: IntoIterator<Item = (S, RoaringBitmap, RoaringBitmap)>,
+    S: AsRef<str>,
+{
+    let mut sorter = create_sorter(
+        grenad::SortAlgorithm::Unstable,
+        MergeDeladdCboRoaringBitmaps,
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        indexer.max_nb_chunks,
+        indexer.max_memory_by_thread(),
+        true,
+    );
+
+    let mut buffer = Vec::new();
+    for (word, deletions, additions) in entries {
+        buffer.clear();
+        let mut obkv = KvWriterDelAdd::new(&mut buffer);
+        for (side, docids) in [(DelAdd::Deletion, &deletions), (DelAdd::Addition, &additions)] {
+            if !docids.is_empty() {
+                obkv.insert(
+                    side,
+                    CboRoaringBitmapCodec::bytes_encode(docids)
+                        .map_err(|_| SerializationError::Encoding { db_name: Some(WORD_DOCIDS) })?,
+                )?;
+            }
+        }
+        sorter.insert(word.as_ref(), obkv.into_inner().unwrap())?;
+    }
+
+    let mut writer = create_writer(
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        tempfile::tempfile()?,
+    );
+    let mut iter = sorter.into_stream_merger_iter()?;
+    while let Some((word, value)) = iter.next()? {
+        let word = std::str::from_utf8(word)
+            .map_err(|_| SerializationError::Decoding { db_name: Some(WORD_DOCIDS) })?;
+        let obkv = KvReaderDelAdd::from_slice(value);
+        let deletions = match obkv.get(DelAdd::Deletion) {
+            Some(value) => CboRoaringBitmapCodec::deserialize_from(value)?,
+            None => RoaringBitmap::new(),
+        };
+        let additions = match obkv.get(DelAdd::Addition) {
+            Some(value) => CboRoaringBitmapCodec::deserialize_from(value)?,
+            None => RoaringBitmap::new(),
+        };
+        docids_into_writers(word, &deletions, &additions, &mut writer)?;
+    }
+
+    writer_into_reader(writer)
+}
+
 #[tracing::instrument(level = "trace", skip_all, target = "indexing::extract")]
 fn docids_into_writers<W>(
     word: &str,
@@ -243,3 +609,420 @@ where
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use big_s::S;
+    use obkv::KvWriterU16;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::settings::InnerIndexSettings;
+    use crate::update::SorterSpillObserver;
+
+    fn settings_diff(index: &TempIndex) -> InnerIndexSettingsDiff {
+        let rtxn = index.read_txn().unwrap();
+        let old = InnerIndexSettings::from_index(index, &rtxn, None).unwrap();
+        let new = InnerIndexSettings::from_index(index, &rtxn, None).unwrap();
+        InnerIndexSettingsDiff::new(old, new, None, BTreeMap::new(), false)
+    }
+
+    /// Builds a docid_word_positions reader from `(docid, fid, deleted text, added text)` entries.
+    fn docid_word_positions(
+        entries: &[(DocumentId, FieldId, &str, &str)],
+    ) -> grenad::Reader<BufReader<File>> {
+        let mut writer =
+            create_writer(grenad::CompressionType::None, None, tempfile::tempfile().unwrap());
+        for (docid, fid, deleted, added) in entries {
+            let mut key = docid.to_be_bytes().to_vec();
+            key.extend_from_slice(&fid.to_be_bytes());
+
+            let mut value = KvWriterDelAdd::memory();
+            for (side, text) in [(DelAdd::Deletion, deleted), (DelAdd::Addition, added)] {
+                if text.is_empty() {
+                    continue;
+                }
+                let mut positions = KvWriterU16::memory();
+                for (position, word) in text.split(' ').enumerate() {
+                    positions.insert(position as u16, word.as_bytes()).unwrap();
+                }
+                value.insert(side, positions.into_inner().unwrap()).unwrap();
+            }
+            writer.insert(key, value.into_inner().unwrap()).unwrap();
+        }
+        writer_into_reader(writer).unwrap()
+    }
+
+    fn read_frequencies(reader: grenad::Reader<BufReader<File>>) -> BTreeMap<String, (u64, u64)> {
+        let mut frequencies = BTreeMap::new();
+        let mut cursor = reader.into_cursor().unwrap();
+        while let Some((word, value)) = cursor.move_on_next().unwrap() {
+            let obkv = KvReaderDelAdd::from_slice(value);
+            let count = |side| {
+                obkv.get(side)
+                    .map_or(0, |bytes: &[u8]| u64::from_be_bytes(bytes.try_into().unwrap()))
+            };
+            frequencies.insert(
+                String::from_utf8(word.to_vec()).unwrap(),
+                (count(DelAdd::Deletion), count(DelAdd::Addition)),
+            );
+        }
+        frequencies
+    }
+
+    #[test]
+    fn word_frequencies_match_docids_cardinalities() {
+        let index = TempIndex::new();
+        let settings_diff = settings_diff(&index);
+
+        let input = docid_word_positions(&[
+            (0, 0, "", "hello world"),
+            (1, 0, "", "hello hello"),
+            (1, 1, "", "world hello"),
+            (2, 0, "", "rust"),
+            (3, 1, "", "hello"),
+        ]);
+        let (word_docids, exact_word_docids, _, word_frequency) =
+            extract_word_docids(input, GrenadParameters::default(), &settings_diff).unwrap();
+        assert!(exact_word_docids.is_empty());
+
+        let frequencies = read_frequencies(word_frequency);
+        let mut cursor = word_docids.into_cursor().unwrap();
+        let mut words = 0;
+        while let Some((word, value)) = cursor.move_on_next().unwrap() {
+            let additions = KvReaderDelAdd::from_slice(value).get(DelAdd::Addition).unwrap();
+            let docids = CboRoaringBitmapCodec::deserialize_from(additions).unwrap();
+            let word = std::str::from_utf8(word).unwrap();
+            assert_eq!(frequencies[word], (0, docids.len()), "{word}");
+            words += 1;
+        }
+        assert_eq!(words, frequencies.len());
+        assert_eq!(frequencies["hello"], (0, 3));
+        assert_eq!(frequencies["world"], (0, 2));
+        assert_eq!(frequencies["rust"], (0, 1));
+    }
+
+    #[test]
+    fn word_frequencies_decrement_on_deletion() {
+        let index = TempIndex::new();
+        let settings_diff = settings_diff(&index);
+
+        let input = docid_word_positions(&[
+            // document 1 is deleted
+            (1, 0, "hello hello", ""),
+            (1, 1, "world hello", ""),
+            // document 2 is updated without changing its words
+            (2, 0, "rust", "rust"),
+            // document 3 replaces a word
+            (3, 1, "hello", "world"),
+        ]);
+        let (_, _, _, word_frequency) =
+            extract_word_docids(input, GrenadParameters::default(), &settings_diff).unwrap();
+
+        let frequencies = read_frequencies(word_frequency);
+        assert_eq!(frequencies.get("hello"), Some(&(2, 0)));
+        assert_eq!(frequencies.get("world"), Some(&(1, 1)));
+        assert_eq!(frequencies.get("rust"), None);
+    }
+
+    type DelAddDocids = (Option<RoaringBitmap>, Option<RoaringBitmap>);
+
+    fn read_docids(reader: grenad::Reader<BufReader<File>>) -> BTreeMap<String, DelAddDocids> {
+        let mut docids = BTreeMap::new();
+        let mut cursor = reader.into_cursor().unwrap();
+        while let Some((word, value)) = cursor.move_on_next().unwrap() {
+            let obkv = KvReaderDelAdd::from_slice(value);
+            let side =
+                |side| obkv.get(side).map(|v| CboRoaringBitmapCodec::deserialize_from(v).unwrap());
+            docids.insert(
+                String::from_utf8(word.to_vec()).unwrap(),
+                (side(DelAdd::Deletion), side(DelAdd::Addition)),
+            );
+        }
+        docids
+    }
+
+    #[test]
+    fn words_into_sorter_reuses_value_buffer() {
+        let words = |text: &str| -> BTreeSet<Vec<u8>> {
+            text.split(' ').filter(|w| !w.is_empty()).map(|w| w.as_bytes().to_vec()).collect()
+        };
+        let documents: Vec<_> = (0..1000)
+            .map(|docid| {
+                (docid, words(if docid % 3 == 0 { "kiwi" } else { "" }), words("apple kiwi"))
+            })
+            .collect();
+        let sorter = || {
+            FlushingSorter::new(
+                "word_docids",
+                grenad::SortAlgorithm::Unstable,
+                MergeDeladdCboRoaringBitmaps,
+                GrenadParameters::default(),
+                None,
+            )
+        };
+
+        // a fresh value buffer for every document
+        let mut expected = sorter();
+        for (docid, del_words, add_words) in &documents {
+            let (mut key_buffer, mut value_buffer) = (Vec::new(), Vec::new());
+            words_into_sorter(
+                *docid,
+                0,
+                &mut key_buffer,
+                &mut value_buffer,
+                del_words,
+                add_words,
+                &mut expected,
+            )
+            .unwrap();
+        }
+
+        // a single value buffer, starting dirty, reused across the documents
+        let mut sorter = sorter();
+        let (mut key_buffer, mut value_buffer) = (Vec::new(), vec![42; 64]);
+        let allocation = value_buffer.as_ptr();
+        for (docid, del_words, add_words) in &documents {
+            words_into_sorter(
+                *docid,
+                0,
+                &mut key_buffer,
+                &mut value_buffer,
+                del_words,
+                add_words,
+                &mut sorter,
+            )
+            .unwrap();
+        }
+        // the buffer was large enough from the start and never reallocated
+        assert_eq!(value_buffer.as_ptr(), allocation);
+
+        let read = |sorter: FlushingSorter<MergeDeladdCboRoaringBitmaps>| {
+            read_docids(sorter.into_reader().unwrap())
+        };
+        let (expected, docids) = (read(expected), read(sorter));
+        assert_eq!(docids, expected);
+        assert_eq!(docids.len(), 2);
+    }
+
+    #[test]
+    fn flushed_sorters_give_the_same_output() {
+        let index = TempIndex::new();
+        let settings_diff = settings_diff(&index);
+
+        let text = |seed: usize| -> String {
+            (0..40).map(|i| format!("w{}", (i * 7 + seed * 3) % 29)).collect::<Vec<_>>().join(" ")
+        };
+        let texts: Vec<_> = (0..30).map(text).collect();
+        let mut entries = Vec::new();
+        for docid in 0..10 {
+            entries.push((docid, 0, "", texts[docid as usize].as_str()));
+            entries.push((
+                docid,
+                1,
+                texts[docid as usize + 10].as_str(),
+                texts[docid as usize + 20].as_str(),
+            ));
+        }
+
+        let extract = |indexer: GrenadParameters| {
+            let (word_docids, exact_word_docids, word_fid_docids, word_frequency) =
+                extract_word_docids(docid_word_positions(&entries), indexer, &settings_diff)
+                    .unwrap();
+            (
+                read_docids(word_docids),
+                read_docids(exact_word_docids),
+                read_docids(word_fid_docids),
+                read_frequencies(word_frequency),
+            )
+        };
+
+        // the number of times the sorters spilled to disk
+        let spills = Arc::new(AtomicUsize::new(0));
+        let observer = spills.clone();
+        let observer: SorterSpillObserver = Arc::new(move |_sorter_name, _spilled_bytes| {
+            observer.fetch_add(1, Ordering::Relaxed);
+        });
+        let indexer = |max_sorter_entries, max_nb_chunks| GrenadParameters {
+            max_sorter_entries,
+            max_nb_chunks,
+            sorter_spill_observer: Some(observer.clone()),
+            ..Default::default()
+        };
+
+        let expected = extract(indexer(None, None));
+        assert_eq!(spills.swap(0, Ordering::Relaxed), 0);
+        assert!(!expected.0.is_empty());
+
+        // the flushed chunks are merged when there are more than `max_nb_chunks` of them
+        for (max_sorter_entries, max_nb_chunks) in
+            [(1, None), (7, None), (100, None), (1, Some(1)), (7, Some(2))]
+        {
+            let output = extract(indexer(Some(max_sorter_entries), max_nb_chunks));
+            assert!(spills.swap(0, Ordering::Relaxed) > 1, "{max_sorter_entries}");
+            assert_eq!(output, expected, "{max_sorter_entries} {max_nb_chunks:?}");
+        }
+    }
+
+    #[test]
+    fn partitioned_second_pass_gives_the_same_readers() {
+        use std::io::{Read, Seek};
+
+        let index = TempIndex::new();
+        let rtxn = index.read_txn().unwrap();
+        let mut old = InnerIndexSettings::from_index(&index, &rtxn, None).unwrap();
+        let mut new = InnerIndexSettings::from_index(&index, &rtxn, None).unwrap();
+        // the words of the field 1 move to the exact word docids
+        new.exact_attributes.insert(1);
+        old.exact_attributes.insert(2);
+        let settings_diff = InnerIndexSettingsDiff::new(old, new, None, BTreeMap::new(), false);
+
+        let text = |seed: usize| -> String {
+            (0..40).map(|i| format!("w{}", (i * 7 + seed * 3) % 53)).collect::<Vec<_>>().join(" ")
+        };
+        let texts: Vec<_> = (0..60).map(text).collect();
+        let mut entries = Vec::new();
+        for docid in 0..20 {
+            let docid_index = docid as usize;
+            entries.push((docid, 0, "", texts[docid_index].as_str()));
+            entries.push((docid, 1, texts[docid_index + 20].as_str(), texts[docid_index].as_str()));
+            entries.push((docid, 2, texts[docid_index + 40].as_str(), ""));
+        }
+
+        let bytes = |reader: grenad::Reader<BufReader<File>>| {
+            let mut file = reader.get_ref().get_ref().try_clone().unwrap();
+            file.rewind().unwrap();
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes).unwrap();
+            bytes
+        };
+        let extract = |partition_entries| {
+            let (word_docids, exact_word_docids, word_fid_docids, word_frequency) =
+                extract_word_docids_in_partitions(
+                    docid_word_positions(&entries),
+                    GrenadParameters::default(),
+                    &settings_diff,
+                    partition_entries,
+                    true,
+                )
+                .unwrap();
+            [
+                bytes(word_docids),
+                bytes(exact_word_docids),
+                bytes(word_fid_docids),
+                bytes(word_frequency),
+            ]
+        };
+
+        let expected = extract(None);
+        for bytes in &expected {
+            assert!(!grenad::Reader::new(std::io::Cursor::new(bytes)).unwrap().is_empty());
+        }
+        for partition_entries in [1, 2, 7, 50, 100_000] {
+            let output = extract(Some(partition_entries));
+            assert_eq!(output, expected, "{partition_entries}");
+        }
+    }
+
+    #[test]
+    fn additions_only_routing_gives_the_same_readers() {
+        use std::io::{Read, Seek};
+
+        let index = TempIndex::new();
+        let rtxn = index.read_txn().unwrap();
+        let old = InnerIndexSettings::from_index(&index, &rtxn, None).unwrap();
+        let mut new = InnerIndexSettings::from_index(&index, &rtxn, None).unwrap();
+        // the words of the field 1 go to the exact word docids
+        new.exact_attributes.insert(1);
+        let settings_diff = InnerIndexSettingsDiff::new(old, new, None, BTreeMap::new(), false);
+
+        let text = |seed: usize| -> String {
+            (0..30).map(|i| format!("w{}", (i * 5 + seed * 3) % 41)).collect::<Vec<_>>().join(" ")
+        };
+        let texts: Vec<_> = (0..40).map(text).collect();
+        let mut entries = Vec::new();
+        for docid in 0..20 {
+            let docid_index = docid as usize;
+            entries.push((docid, 0, "", texts[docid_index].as_str()));
+            entries.push((docid, 1, "", texts[docid_index + 20].as_str()));
+        }
+
+        let bytes = |reader: grenad::Reader<BufReader<File>>| {
+            let mut file = reader.get_ref().get_ref().try_clone().unwrap();
+            file.rewind().unwrap();
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes).unwrap();
+            bytes
+        };
+        let extract = |partition_entries, detect_additions_only| {
+            let (word_docids, exact_word_docids, word_fid_docids, word_frequency) =
+                extract_word_docids_in_partitions(
+                    docid_word_positions(&entries),
+                    GrenadParameters::default(),
+                    &settings_diff,
+                    partition_entries,
+                    detect_additions_only,
+                )
+                .unwrap();
+            (
+                bytes(word_docids),
+                bytes(exact_word_docids),
+                bytes(word_fid_docids),
+                bytes(word_frequency),
+            )
+        };
+
+        let expected = extract(None, false);
+        assert!(!expected.0.is_empty() && !expected.1.is_empty());
+        for partition_entries in [None, Some(1), Some(7), Some(100_000)] {
+            let output = extract(partition_entries, true);
+            assert_eq!(output, expected, "{partition_entries:?}");
+        }
+    }
+
+    #[test]
+    fn build_word_docids_reader_elides_noops() {
+        let bitmap = |docids: &[u32]| RoaringBitmap::from_iter(docids.iter().copied());
+        let entries = vec![
+            ("kiwi", bitmap(&[1]), bitmap(&[1, 2])),
+            // the same documents are deleted and added once the entries are merged
+            ("apple", bitmap(&[1, 2]), bitmap(&[1, 2, 5])),
+            ("apple", bitmap(&[5]), bitmap(&[])),
+            ("banana", bitmap(&[3]), bitmap(&[])),
+            ("pear", bitmap(&[]), bitmap(&[])),
+            ("cherry", bitmap(&[4, 6]), bitmap(&[6])),
+            ("banana", bitmap(&[]), bitmap(&[7])),
+        ];
+
+        // the internal path writes the merged deletions and additions of each word
+        let mut merged = BTreeMap::<&str, (RoaringBitmap, RoaringBitmap)>::new();
+        for (word, deletions, additions) in &entries {
+            let entry = merged.entry(word).or_default();
+            entry.0 |= deletions;
+            entry.1 |= additions;
+        }
+        let mut writer =
+            create_writer(grenad::CompressionType::None, None, tempfile::tempfile().unwrap());
+        for (word, (deletions, additions)) in &merged {
+            docids_into_writers(word, deletions, additions, &mut writer).unwrap();
+        }
+        let expected = read_docids(writer_into_reader(writer).unwrap());
+
+        let reader = build_word_docids_reader(entries, GrenadParameters::default()).unwrap();
+        let docids = read_docids(reader);
+        assert_eq!(docids, expected);
+
+        assert_eq!(
+            docids,
+            BTreeMap::from_iter([
+                (S("banana"), (Some(bitmap(&[3])), Some(bitmap(&[7])))),
+                (S("cherry"), (Some(bitmap(&[4, 6])), Some(bitmap(&[6])))),
+                (S("kiwi"), (None, Some(bitmap(&[1, 2])))),
+            ])
+        );
+    }
+}
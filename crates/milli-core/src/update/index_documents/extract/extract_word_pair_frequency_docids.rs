@@ -0,0 +1,237 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+
+use super::cached_docid_word_positions::DocidWordPositions;
+use super::helpers::{
+    create_sorter, create_writer, sorter_into_reader, writer_into_reader, GrenadParameters,
+    MergeDeladdCboRoaringBitmaps,
+};
+use crate::proximity::{index_proximity, MAX_WORD_PAIR_FREQUENCY};
+use crate::update::del_add::{DelAdd, KvWriterDelAdd};
+use crate::update::settings::InnerIndexSettingsDiff;
+use crate::{DocumentId, Result};
+
+/// Extracts the number of times a word directly follows another one in each document,
+/// and the documents ids where each of these numbers is reached.
+///
+/// Returns a grenad reader with the list of extracted word pairs frequencies and
+/// documents ids from the given chunk of docid word positions.
+#[tracing::instrument(level = "trace", skip_all, target = "indexing::extract")]
+pub fn extract_word_pair_frequency_docids<R: io::Read + io::Seek>(
+    docid_word_positions: impl Into<DocidWordPositions<R>>,
+    indexer: GrenadParameters,
+    settings_diff: &InnerIndexSettingsDiff,
+) -> Result<grenad::Reader<BufReader<File>>> {
+    let any_deletion = settings_diff.old.record_word_pair_frequencies;
+    let any_addition = settings_diff.new.record_word_pair_frequencies;
+
+    // early return if the data shouldn't be deleted nor created.
+    if (settings_diff.settings_update_only && !settings_diff.reindex_word_pair_frequencies())
+        || !(any_deletion || any_addition)
+    {
+        let writer = create_writer(
+            indexer.chunk_compression_type,
+            indexer.chunk_compression_level,
+            tempfile::tempfile()?,
+        );
+        return writer_into_reader(writer);
+    }
+
+    let mut word_pair_frequency_docids_sorter = create_sorter(
+        grenad::SortAlgorithm::Unstable,
+        MergeDeladdCboRoaringBitmaps,
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        indexer.max_nb_chunks,
+        indexer.max_memory_by_thread(),
+        true,
+    );
+
+    let mut del_word_pair_frequencies = BTreeMap::new();
+    let mut add_word_pair_frequencies = BTreeMap::new();
+    let mut current_document_id = None;
+
+    docid_word_positions.into().for_each(|entry| {
+        let document_id = entry.document_id;
+
+        // if we change document, we fill the sorter
+        if let Some(current_document_id) = current_document_id.filter(|id| *id != document_id) {
+            document_word_pair_frequencies_into_sorter(
+                current_document_id,
+                &del_word_pair_frequencies,
+                &add_word_pair_frequencies,
+                &mut word_pair_frequency_docids_sorter,
+            )?;
+            del_word_pair_frequencies.clear();
+            add_word_pair_frequencies.clear();
+        }
+
+        current_document_id = Some(document_id);
+
+        if any_deletion {
+            word_positions_into_word_pair_frequencies(
+                &entry.deletion,
+                &mut del_word_pair_frequencies,
+            );
+        }
+        if any_addition {
+            word_positions_into_word_pair_frequencies(
+                &entry.addition,
+                &mut add_word_pair_frequencies,
+            );
+        }
+
+        Ok(())
+    })?;
+
+    if let Some(document_id) = current_document_id {
+        document_word_pair_frequencies_into_sorter(
+            document_id,
+            &del_word_pair_frequencies,
+            &add_word_pair_frequencies,
+            &mut word_pair_frequency_docids_sorter,
+        )?;
+    }
+
+    sorter_into_reader(word_pair_frequency_docids_sorter, indexer)
+}
+
+/// Counts the pairs of words of a field where the second word is at a proximity of 1
+/// from the first one, the counts stop at [`MAX_WORD_PAIR_FREQUENCY`].
+fn word_positions_into_word_pair_frequencies(
+    word_positions: &[(u16, &str)],
+    word_pair_frequencies: &mut BTreeMap<(String, String), u8>,
+) {
+    for window in word_positions.windows(2) {
+        let [(p1, w1), (p2, w2)] = window else { unreachable!() };
+        if index_proximity(*p1 as u32, *p2 as u32) == 1 {
+            let frequency =
+                word_pair_frequencies.entry((w1.to_string(), w2.to_string())).or_insert(0);
+            *frequency = frequency.saturating_add(1).min(MAX_WORD_PAIR_FREQUENCY);
+        }
+    }
+}
+
+fn document_word_pair_frequencies_into_sorter(
+    document_id: DocumentId,
+    del_word_pair_frequencies: &BTreeMap<(String, String), u8>,
+    add_word_pair_frequencies: &BTreeMap<(String, String), u8>,
+    word_pair_frequency_docids_sorter: &mut grenad::Sorter<MergeDeladdCboRoaringBitmaps>,
+) -> Result<()> {
+    use itertools::merge_join_by;
+    use itertools::EitherOrBoth::{Both, Left, Right};
+
+    // the frequency is part of the key, a pair whose frequency changed is deleted
+    // from its previous frequency and added to the new one.
+    let into_keys =
+        |frequencies: &BTreeMap<(String, String), u8>| -> BTreeSet<(u8, String, String)> {
+            frequencies
+                .iter()
+                .map(|((w1, w2), frequency)| (*frequency, w1.clone(), w2.clone()))
+                .collect()
+        };
+    let del_keys = into_keys(del_word_pair_frequencies);
+    let add_keys = into_keys(add_word_pair_frequencies);
+
+    let mut buffer = Vec::new();
+    let mut key_buffer = Vec::new();
+    for eob in merge_join_by(del_keys.iter(), add_keys.iter(), |d, a| d.cmp(a)) {
+        buffer.clear();
+        let mut value_writer = KvWriterDelAdd::new(&mut buffer);
+        let (frequency, w1, w2) = match eob {
+            Left(key) => {
+                value_writer.insert(DelAdd::Deletion, document_id.to_ne_bytes()).unwrap();
+                key
+            }
+            Right(key) => {
+                value_writer.insert(DelAdd::Addition, document_id.to_ne_bytes()).unwrap();
+                key
+            }
+            Both(key, _) => {
+                value_writer.insert(DelAdd::Deletion, document_id.to_ne_bytes()).unwrap();
+                value_writer.insert(DelAdd::Addition, document_id.to_ne_bytes()).unwrap();
+                key
+            }
+        };
+
+        key_buffer.clear();
+        key_buffer.push(*frequency);
+        key_buffer.extend_from_slice(w1.as_bytes());
+        key_buffer.push(0);
+        key_buffer.extend_from_slice(w2.as_bytes());
+
+        word_pair_frequency_docids_sorter
+            .insert(&key_buffer, value_writer.into_inner().unwrap())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use crate::index::tests::TempIndex;
+    use crate::proximity::MAX_WORD_PAIR_FREQUENCY;
+    use crate::Index;
+
+    fn word_pair_frequencies(index: &Index) -> Vec<(u8, String, String, Vec<u32>)> {
+        let rtxn = index.read_txn().unwrap();
+        index
+            .word_pair_frequency_docids
+            .iter(&rtxn)
+            .unwrap()
+            .map(|entry| {
+                let ((frequency, w1, w2), docids) = entry.unwrap();
+                (frequency, w1.to_string(), w2.to_string(), docids.into_iter().collect())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn word_pair_frequencies_only_recorded_when_enabled() {
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "new york and new york" },
+            ]))
+            .unwrap();
+        assert!(word_pair_frequencies(&index).is_empty());
+
+        // enabling the setting reindexes the documents
+        index.update_settings(|settings| settings.set_record_word_pair_frequencies(true)).unwrap();
+        assert_eq!(
+            word_pair_frequencies(&index),
+            vec![
+                (1, S("and"), S("new"), vec![0]),
+                (1, S("york"), S("and"), vec![0]),
+                (2, S("new"), S("york"), vec![0]),
+            ]
+        );
+
+        // the occurrences of all the fields are counted, but never across two fields
+        let repeated = vec!["new york"; 20].join(" ");
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "new york", "body": "new york" },
+                { "id": 1, "title": repeated },
+            ]))
+            .unwrap();
+        assert_eq!(
+            word_pair_frequencies(&index),
+            vec![
+                (2, S("new"), S("york"), vec![0]),
+                (MAX_WORD_PAIR_FREQUENCY, S("new"), S("york"), vec![1]),
+                (MAX_WORD_PAIR_FREQUENCY, S("york"), S("new"), vec![1]),
+            ]
+        );
+
+        index.delete_documents(vec![S("1")]);
+        assert_eq!(word_pair_frequencies(&index), vec![(2, S("new"), S("york"), vec![0])]);
+
+        index.update_settings(|settings| settings.set_record_word_pair_frequencies(false)).unwrap();
+        assert!(word_pair_frequencies(&index).is_empty());
+    }
+}
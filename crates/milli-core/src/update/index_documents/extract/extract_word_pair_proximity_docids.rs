@@ -1,28 +1,44 @@
+use std::borrow::Cow;
 use std::collections::{BTreeMap, VecDeque};
 use std::fs::File;
 use std::io::BufReader;
 use std::{cmp, io};
 
-use obkv::KvReaderU16;
+use grenad::MergeFunction;
 
+use super::cached_docid_word_positions::DocidWordPositions;
 use super::helpers::{
-    create_sorter, create_writer, try_split_array_at, writer_into_reader, GrenadParameters,
-    MergeDeladdCboRoaringBitmaps,
+    create_observed_sorter, create_writer, writer_into_reader, GrenadParameters,
+    MergeDeladdCboRoaringBitmaps, ObservedSorter,
 };
-use crate::error::SerializationError;
-use crate::index::db_name::DOCID_WORD_POSITIONS;
 use crate::proximity::{index_proximity, ProximityPrecision, MAX_DISTANCE};
 use crate::update::del_add::{DelAdd, KvReaderDelAdd, KvWriterDelAdd};
 use crate::update::settings::InnerIndexSettingsDiff;
 use crate::{DocumentId, Result};
 
+/// The weights of the memory given to the sorters of the proximities from 1 to `MAX_DISTANCE - 1`.
+///
+/// The pairs of near words are far more numerous than the pairs of distant words.
+const PROXIMITY_SORTERS_MEMORY_WEIGHTS: [usize; MAX_DISTANCE as usize - 1] = [3, 2, 1];
+
+/// Splits the memory between the sorters of each proximity according to
+/// [`PROXIMITY_SORTERS_MEMORY_WEIGHTS`].
+///
+/// A `1 / MAX_DISTANCE` share of the memory is kept for the sorter of the word pairs of
+/// the documents with too many word pairs.
+fn proximity_sorters_memory(max_memory: usize) -> [usize; MAX_DISTANCE as usize - 1] {
+    let sorters_memory = max_memory / MAX_DISTANCE as usize * (MAX_DISTANCE as usize - 1);
+    let total_weight: usize = PROXIMITY_SORTERS_MEMORY_WEIGHTS.iter().sum();
+    PROXIMITY_SORTERS_MEMORY_WEIGHTS.map(|weight| sorters_memory / total_weight * weight)
+}
+
 /// Extracts the best proximity between pairs of words and the documents ids where this pair appear.
 ///
 /// Returns a grenad reader with the list of extracted word pairs proximities and
 /// documents ids from the given chunk of docid word positions.
 #[tracing::instrument(level = "trace", skip_all, target = "indexing::extract")]
 pub fn extract_word_pair_proximity_docids<R: io::Read + io::Seek>(
-    docid_word_positions: grenad::Reader<R>,
+    docid_word_positions: impl Into<DocidWordPositions<R>>,
     indexer: GrenadParameters,
     settings_diff: &InnerIndexSettingsDiff,
 ) -> Result<grenad::Reader<BufReader<File>>> {
@@ -38,18 +54,21 @@ pub fn extract_word_pair_proximity_docids<R: io::Read + io::Seek>(
 
     let any_deletion = settings_diff.old.proximity_precision == ProximityPrecision::ByWord;
     let any_addition = settings_diff.new.proximity_precision == ProximityPrecision::ByWord;
+    // the window is drained at the end of every field, so skipping the words
+    // of an excluded field never pairs them with the words of another field
+    let del_excluded_fields = &settings_diff.old.proximity_excluded_fields;
+    let add_excluded_fields = &settings_diff.new.proximity_excluded_fields;
 
     let max_memory = indexer.max_memory_by_thread();
-    let mut word_pair_proximity_docids_sorters: Vec<_> = (1..MAX_DISTANCE)
-        .map(|_| {
-            create_sorter(
+    let sorters_memory = max_memory.map(proximity_sorters_memory);
+    let mut word_pair_proximity_docids_sorters: Vec<_> = (0..MAX_DISTANCE as usize - 1)
+        .map(|i| {
+            create_observed_sorter(
+                "word_pair_proximity_docids",
                 grenad::SortAlgorithm::Unstable,
                 MergeDeladdCboRoaringBitmaps,
-                indexer.chunk_compression_type,
-                indexer.chunk_compression_level,
-                indexer.max_nb_chunks,
-                max_memory.map(|m| m / MAX_DISTANCE as usize),
-                true,
+                indexer.clone(),
+                sorters_memory.map(|memory| memory[i]),
             )
         })
         .collect();
@@ -60,13 +79,15 @@ pub fn extract_word_pair_proximity_docids<R: io::Read + io::Seek>(
         VecDeque::with_capacity(MAX_DISTANCE as usize);
     let mut del_word_pair_proximity = BTreeMap::new();
     let mut add_word_pair_proximity = BTreeMap::new();
+    // the word pairs moved out of memory for the current document
+    let mut document_word_pair_proximity = None;
     let mut current_document_id = None;
+    // reused by every document moved into the sorters
+    let mut buffer = Vec::new();
+    let mut key_buffer = Vec::new();
 
-    let mut cursor = docid_word_positions.into_cursor()?;
-    while let Some((key, value)) = cursor.move_on_next()? {
-        let (document_id_bytes, _fid_bytes) = try_split_array_at(key)
-            .ok_or(SerializationError::Decoding { db_name: Some(DOCID_WORD_POSITIONS) })?;
-        let document_id = u32::from_be_bytes(document_id_bytes);
+    docid_word_positions.into().for_each(|entry| {
+        let document_id = entry.document_id;
 
         // if we change document, we fill the sorter
         if current_document_id.is_some_and(|id| id != document_id) {
@@ -74,12 +95,28 @@ pub fn extract_word_pair_proximity_docids<R: io::Read + io::Seek>(
             let span = tracing::trace_span!(target: "indexing::details", "document_into_sorter");
             let _entered = span.enter();
 
-            document_word_positions_into_sorter(
-                current_document_id.unwrap(),
-                &del_word_pair_proximity,
-                &add_word_pair_proximity,
-                &mut word_pair_proximity_docids_sorters,
-            )?;
+            match document_word_pair_proximity.take() {
+                Some(mut document_sorter) => {
+                    word_pair_proximity_into_document_sorter(
+                        &del_word_pair_proximity,
+                        &add_word_pair_proximity,
+                        &mut document_sorter,
+                    )?;
+                    document_sorter_into_sorters(
+                        current_document_id.unwrap(),
+                        document_sorter,
+                        &mut word_pair_proximity_docids_sorters,
+                    )?;
+                }
+                None => document_word_positions_into_sorter(
+                    current_document_id.unwrap(),
+                    &del_word_pair_proximity,
+                    &add_word_pair_proximity,
+                    &mut word_pair_proximity_docids_sorters,
+                    &mut buffer,
+                    &mut key_buffer,
+                )?,
+            }
             del_word_pair_proximity.clear();
             add_word_pair_proximity.clear();
         }
@@ -88,67 +125,61 @@ pub fn extract_word_pair_proximity_docids<R: io::Read + io::Seek>(
 
         let (del, add): (Result<_>, Result<_>) = rayon::join(
             || {
-                if !any_deletion {
+                if !any_deletion || del_excluded_fields.contains(&entry.fid) {
                     return Ok(());
                 }
 
                 // deletions
-                if let Some(deletion) = KvReaderDelAdd::from_slice(value).get(DelAdd::Deletion) {
-                    for (position, word) in KvReaderU16::from_slice(deletion).iter() {
-                        // drain the proximity window until the head word is considered close to the word we are inserting.
-                        while del_word_positions.front().is_some_and(|(_w, p)| {
-                            index_proximity(*p as u32, position as u32) >= MAX_DISTANCE
-                        }) {
-                            word_positions_into_word_pair_proximity(
-                                &mut del_word_positions,
-                                &mut del_word_pair_proximity,
-                            )?;
-                        }
-
-                        // insert the new word.
-                        let word = std::str::from_utf8(word)?;
-                        del_word_positions.push_back((word.to_string(), position));
-                    }
-
-                    while !del_word_positions.is_empty() {
+                for (position, word) in &entry.deletion {
+                    // drain the proximity window until the head word is considered close to the word we are inserting.
+                    while del_word_positions.front().is_some_and(|(_w, p)| {
+                        index_proximity(*p as u32, *position as u32) >= MAX_DISTANCE
+                    }) {
                         word_positions_into_word_pair_proximity(
                             &mut del_word_positions,
                             &mut del_word_pair_proximity,
                         )?;
                     }
+
+                    // insert the new word.
+                    del_word_positions.push_back((word.to_string(), *position));
+                }
+
+                while !del_word_positions.is_empty() {
+                    word_positions_into_word_pair_proximity(
+                        &mut del_word_positions,
+                        &mut del_word_pair_proximity,
+                    )?;
                 }
 
                 Ok(())
             },
             || {
-                if !any_addition {
+                if !any_addition || add_excluded_fields.contains(&entry.fid) {
                     return Ok(());
                 }
 
                 // additions
-                if let Some(addition) = KvReaderDelAdd::from_slice(value).get(DelAdd::Addition) {
-                    for (position, word) in KvReaderU16::from_slice(addition).iter() {
-                        // drain the proximity window until the head word is considered close to the word we are inserting.
-                        while add_word_positions.front().is_some_and(|(_w, p)| {
-                            index_proximity(*p as u32, position as u32) >= MAX_DISTANCE
-                        }) {
-                            word_positions_into_word_pair_proximity(
-                                &mut add_word_positions,
-                                &mut add_word_pair_proximity,
-                            )?;
-                        }
-
-                        // insert the new word.
-                        let word = std::str::from_utf8(word)?;
-                        add_word_positions.push_back((word.to_string(), position));
-                    }
-
-                    while !add_word_positions.is_empty() {
+                for (position, word) in &entry.addition {
+                    // drain the proximity window until the head word is considered close to the word we are inserting.
+                    while add_word_positions.front().is_some_and(|(_w, p)| {
+                        index_proximity(*p as u32, *position as u32) >= MAX_DISTANCE
+                    }) {
                         word_positions_into_word_pair_proximity(
                             &mut add_word_positions,
                             &mut add_word_pair_proximity,
                         )?;
                     }
+
+                    // insert the new word.
+                    add_word_positions.push_back((word.to_string(), *position));
+                }
+
+                while !add_word_positions.is_empty() {
+                    word_positions_into_word_pair_proximity(
+                        &mut add_word_positions,
+                        &mut add_word_pair_proximity,
+                    )?;
                 }
 
                 Ok(())
@@ -157,19 +188,60 @@ pub fn extract_word_pair_proximity_docids<R: io::Read + io::Seek>(
 
         del?;
         add?;
-    }
+
+        // a huge document can have too many word pairs to be kept in memory, we move them
+        // into a sorter that only keeps the best proximity of the pairs seen multiple times.
+        if indexer.max_word_pairs_per_document.is_some_and(|max_word_pairs| {
+            del_word_pair_proximity.len() + add_word_pair_proximity.len() > max_word_pairs
+        }) {
+            let document_sorter = document_word_pair_proximity.get_or_insert_with(|| {
+                create_observed_sorter(
+                    "document_word_pair_proximity",
+                    grenad::SortAlgorithm::Unstable,
+                    MergeDeladdMinProximity,
+                    indexer.clone(),
+                    max_memory.map(|m| m / MAX_DISTANCE as usize),
+                )
+            });
+            word_pair_proximity_into_document_sorter(
+                &del_word_pair_proximity,
+                &add_word_pair_proximity,
+                document_sorter,
+            )?;
+            del_word_pair_proximity.clear();
+            add_word_pair_proximity.clear();
+        }
+
+        Ok(())
+    })?;
 
     if let Some(document_id) = current_document_id {
         // FIXME: span inside of a hot loop might degrade performance and create big reports
         let span = tracing::trace_span!(target: "indexing::details", "final_document_into_sorter");
         let _entered = span.enter();
 
-        document_word_positions_into_sorter(
-            document_id,
-            &del_word_pair_proximity,
-            &add_word_pair_proximity,
-            &mut word_pair_proximity_docids_sorters,
-        )?;
+        match document_word_pair_proximity {
+            Some(mut document_sorter) => {
+                word_pair_proximity_into_document_sorter(
+                    &del_word_pair_proximity,
+                    &add_word_pair_proximity,
+                    &mut document_sorter,
+                )?;
+                document_sorter_into_sorters(
+                    document_id,
+                    document_sorter,
+                    &mut word_pair_proximity_docids_sorters,
+                )?;
+            }
+            None => document_word_positions_into_sorter(
+                document_id,
+                &del_word_pair_proximity,
+                &add_word_pair_proximity,
+                &mut word_pair_proximity_docids_sorters,
+                &mut buffer,
+                &mut key_buffer,
+            )?,
+        }
     }
     {
         // FIXME: span inside of a hot loop might degrade performance and create big reports
@@ -194,25 +266,31 @@ pub fn extract_word_pair_proximity_docids<R: io::Read + io::Seek>(
 ///
 /// This list is used by the engine to calculate the documents containing words that are
 /// close to each other.
+///
+/// The `buffer` and `key_buffer` are cleared before use, they are given by the caller
+/// to be reused across the documents.
 fn document_word_positions_into_sorter(
     document_id: DocumentId,
     del_word_pair_proximity: &BTreeMap<(String, String), u8>,
     add_word_pair_proximity: &BTreeMap<(String, String), u8>,
-    word_pair_proximity_docids_sorters: &mut [grenad::Sorter<MergeDeladdCboRoaringBitmaps>],
+    word_pair_proximity_docids_sorters: &mut [ObservedSorter<MergeDeladdCboRoaringBitmaps>],
+    buffer: &mut Vec<u8>,
+    key_buffer: &mut Vec<u8>,
 ) -> Result<()> {
     use itertools::merge_join_by;
     use itertools::EitherOrBoth::{Both, Left, Right};
 
-    let mut buffer = Vec::new();
-    let mut key_buffer = Vec::new();
     for eob in
         merge_join_by(del_word_pair_proximity.iter(), add_word_pair_proximity.iter(), |d, a| {
             d.cmp(a)
         })
     {
         buffer.clear();
-        let mut value_writer = KvWriterDelAdd::new(&mut buffer);
+        let mut value_writer = KvWriterDelAdd::new(&mut *buffer);
         let ((w1, w2), prox) = match eob {
+            // The pair may be missing from the database, when the previous version of the
+            // document was never fully indexed for example, the extractors can't know it.
+            // Such deletions are dropped when the entries are written into the database.
             Left(key_value) => {
                 value_writer.insert(DelAdd::Deletion, document_id.to_ne_bytes()).unwrap();
                 key_value
@@ -235,12 +313,137 @@ fn document_word_positions_into_sorter(
         key_buffer.extend_from_slice(w2.as_bytes());
 
         word_pair_proximity_docids_sorters[*prox as usize - 1]
-            .insert(&key_buffer, value_writer.into_inner().unwrap())?;
+            .insert(&*key_buffer, value_writer.into_inner().unwrap())?;
     }
 
     Ok(())
 }
 
+/// Moves the word pairs proximities of a document into a sorter where the keys are
+/// the word pairs and the values the proximities of the pairs on both DelAdd sides.
+fn word_pair_proximity_into_document_sorter(
+    del_word_pair_proximity: &BTreeMap<(String, String), u8>,
+    add_word_pair_proximity: &BTreeMap<(String, String), u8>,
+    document_sorter: &mut ObservedSorter<MergeDeladdMinProximity>,
+) -> Result<()> {
+    use itertools::merge_join_by;
+    use itertools::EitherOrBoth::{Both, Left, Right};
+
+    let mut buffer = Vec::new();
+    let mut key_buffer = Vec::new();
+    for eob in
+        merge_join_by(del_word_pair_proximity.iter(), add_word_pair_proximity.iter(), |d, a| {
+            d.0.cmp(a.0)
+        })
+    {
+        buffer.clear();
+        let mut value_writer = KvWriterDelAdd::new(&mut buffer);
+        let (w1, w2) = match eob {
+            Left(((w1, w2), del_prox)) => {
+                value_writer.insert(DelAdd::Deletion, [*del_prox]).unwrap();
+                (w1, w2)
+            }
+            Right(((w1, w2), add_prox)) => {
+                value_writer.insert(DelAdd::Addition, [*add_prox]).unwrap();
+                (w1, w2)
+            }
+            Both(((w1, w2), del_prox), (_, add_prox)) => {
+                value_writer.insert(DelAdd::Deletion, [*del_prox]).unwrap();
+                value_writer.insert(DelAdd::Addition, [*add_prox]).unwrap();
+                (w1, w2)
+            }
+        };
+
+        key_buffer.clear();
+        key_buffer.extend_from_slice(w1.as_bytes());
+        key_buffer.push(0);
+        key_buffer.extend_from_slice(w2.as_bytes());
+
+        document_sorter.insert(&key_buffer, value_writer.into_inner().unwrap())?;
+    }
+
+    Ok(())
+}
+
+/// Fills the word pair proximity sorters with the best proximities of a document
+/// whose word pairs were moved out of memory.
+fn document_sorter_into_sorters(
+    document_id: DocumentId,
+    document_sorter: ObservedSorter<MergeDeladdMinProximity>,
+    word_pair_proximity_docids_sorters: &mut [ObservedSorter<MergeDeladdCboRoaringBitmaps>],
+) -> Result<()> {
+    let mut buffer = Vec::new();
+    let mut key_buffer = Vec::new();
+    let mut iter = document_sorter.into_stream_merger_iter()?;
+    while let Some((word_pair, value)) = iter.next()? {
+        let value = KvReaderDelAdd::from_slice(value);
+        let del_prox = value.get(DelAdd::Deletion).map(|prox| prox[0]);
+        let add_prox = value.get(DelAdd::Addition).map(|prox| prox[0]);
+
+        // a pair with the same proximity on both sides is a single entry
+        let entries = match (del_prox, add_prox) {
+            (Some(del_prox), Some(add_prox)) if del_prox == add_prox => {
+                [Some((del_prox, &[DelAdd::Deletion, DelAdd::Addition][..])), None]
+            }
+            (del_prox, add_prox) => [
+                del_prox.map(|prox| (prox, &[DelAdd::Deletion][..])),
+                add_prox.map(|prox| (prox, &[DelAdd::Addition][..])),
+            ],
+        };
+
+        for (prox, sides) in entries.into_iter().flatten() {
+            buffer.clear();
+            let mut value_writer = KvWriterDelAdd::new(&mut buffer);
+            for side in sides {
+                value_writer.insert(*side, document_id.to_ne_bytes()).unwrap();
+            }
+
+            key_buffer.clear();
+            key_buffer.push(prox);
+            key_buffer.extend_from_slice(word_pair);
+
+            word_pair_proximity_docids_sorters[prox as usize - 1]
+                .insert(&key_buffer, value_writer.into_inner().unwrap())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Keeps the lowest proximity on both sides of the DelAdd obkvs of a word pair.
+struct MergeDeladdMinProximity;
+
+impl MergeFunction for MergeDeladdMinProximity {
+    type Error = crate::Error;
+
+    fn merge<'a>(&self, _key: &[u8], values: &[Cow<'a, [u8]>]) -> Result<Cow<'a, [u8]>> {
+        if values.len() == 1 {
+            return Ok(values[0].clone());
+        }
+
+        let mut del_prox: Option<u8> = None;
+        let mut add_prox: Option<u8> = None;
+        for value in values {
+            let obkv = KvReaderDelAdd::from_slice(value);
+            if let Some(prox) = obkv.get(DelAdd::Deletion) {
+                del_prox = Some(del_prox.map_or(prox[0], |p| cmp::min(p, prox[0])));
+            }
+            if let Some(prox) = obkv.get(DelAdd::Addition) {
+                add_prox = Some(add_prox.map_or(prox[0], |p| cmp::min(p, prox[0])));
+            }
+        }
+
+        let mut output_deladd_obkv = KvWriterDelAdd::memory();
+        if let Some(prox) = del_prox {
+            output_deladd_obkv.insert(DelAdd::Deletion, [prox])?;
+        }
+        if let Some(prox) = add_prox {
+            output_deladd_obkv.insert(DelAdd::Addition, [prox])?;
+        }
+        output_deladd_obkv.into_inner().map(Cow::from).map_err(Into::into)
+    }
+}
+
 fn word_positions_into_word_pair_proximity(
     word_positions: &mut VecDeque<(String, u16)>,
     word_pair_proximity: &mut BTreeMap<(String, String), u8>,
@@ -259,3 +462,337 @@ fn word_positions_into_word_pair_proximity(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    use big_s::S;
+    use obkv::KvWriterU16;
+    use roaring::RoaringBitmap;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::index_documents::typed_chunk::{write_typed_chunk_into_index, TypedChunk};
+    use crate::update::settings::InnerIndexSettings;
+    use crate::FieldId;
+
+    #[test]
+    fn proximity_sorters_memory_favors_near_proximities() {
+        for max_memory in [0, 7, 1000, 1024 * 1024 * 1024, usize::MAX] {
+            let memory = proximity_sorters_memory(max_memory);
+            let document_sorter_memory = max_memory / MAX_DISTANCE as usize;
+            assert!(memory.iter().sum::<usize>() + document_sorter_memory <= max_memory);
+            assert!(memory.windows(2).all(|w| w[0] >= w[1]), "{memory:?}");
+        }
+
+        let memory = proximity_sorters_memory(1024 * 1024 * 1024);
+        assert!(memory[0] > 1024 * 1024 * 1024 / MAX_DISTANCE as usize);
+        assert!(memory.windows(2).all(|w| w[0] > w[1]), "{memory:?}");
+    }
+
+    fn settings_diff(index: &TempIndex) -> InnerIndexSettingsDiff {
+        let rtxn = index.read_txn().unwrap();
+        let old = InnerIndexSettings::from_index(index, &rtxn, None).unwrap();
+        let new = InnerIndexSettings::from_index(index, &rtxn, None).unwrap();
+        InnerIndexSettingsDiff::new(old, new, None, BTreeMap::new(), false)
+    }
+
+    /// Builds a docid_word_positions reader from `(docid, fid, deleted words, added words)` entries.
+    fn docid_word_positions(
+        entries: &[(DocumentId, FieldId, Vec<String>, Vec<String>)],
+    ) -> grenad::Reader<BufReader<File>> {
+        let mut writer =
+            create_writer(grenad::CompressionType::None, None, tempfile::tempfile().unwrap());
+        for (docid, fid, deleted, added) in entries {
+            let mut key = docid.to_be_bytes().to_vec();
+            key.extend_from_slice(&fid.to_be_bytes());
+
+            let mut value = KvWriterDelAdd::memory();
+            for (side, words) in [(DelAdd::Deletion, deleted), (DelAdd::Addition, added)] {
+                if words.is_empty() {
+                    continue;
+                }
+                let mut positions = KvWriterU16::memory();
+                for (position, word) in words.iter().enumerate() {
+                    positions.insert(position as u16, word.as_bytes()).unwrap();
+                }
+                value.insert(side, positions.into_inner().unwrap()).unwrap();
+            }
+            writer.insert(key, value.into_inner().unwrap()).unwrap();
+        }
+        writer_into_reader(writer).unwrap()
+    }
+
+    fn read_all(reader: grenad::Reader<BufReader<File>>) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let mut entries = Vec::new();
+        let mut cursor = reader.into_cursor().unwrap();
+        while let Some((key, value)) = cursor.move_on_next().unwrap() {
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+        entries
+    }
+
+    #[test]
+    fn huge_document_word_pairs_out_of_memory() {
+        let index = TempIndex::new();
+        let settings_diff = settings_diff(&index);
+
+        // a small vocabulary makes the same pairs appear with different proximities
+        let words = |seed: usize, count: usize| -> Vec<String> {
+            (0..count).map(|i| format!("w{}", (i * 7 + seed * 3 + i / 5) % 23)).collect()
+        };
+        let mut entries = Vec::new();
+        for fid in 0..20 {
+            entries.push((0, fid, Vec::new(), words(fid as usize, 500)));
+        }
+        entries.push((1, 0, words(1, 10), words(1, 10)));
+        for fid in 0..5 {
+            entries.push((2, fid, words(fid as usize, 300), words(fid as usize + 1, 300)));
+        }
+        entries.push((3, 0, words(4, 50), Vec::new()));
+        // the best proximity of a pair is only found in a later field
+        let text = |text: &str| text.split(' ').map(String::from).collect::<Vec<_>>();
+        entries.push((4, 0, text("rust is a language"), text("rust is not a language")));
+        entries.push((4, 1, text("a rust language"), text("rust language")));
+
+        let expected = extract_word_pair_proximity_docids(
+            docid_word_positions(&entries),
+            GrenadParameters::default(),
+            &settings_diff,
+        )
+        .unwrap();
+        let expected = read_all(expected);
+        assert!(!expected.is_empty());
+
+        for max_word_pairs_per_document in [0, 10, 1000] {
+            let indexer = GrenadParameters {
+                max_word_pairs_per_document: Some(max_word_pairs_per_document),
+                ..Default::default()
+            };
+            let output = extract_word_pair_proximity_docids(
+                docid_word_positions(&entries),
+                indexer,
+                &settings_diff,
+            )
+            .unwrap();
+            assert_eq!(read_all(output), expected, "{max_word_pairs_per_document}");
+        }
+    }
+
+    #[test]
+    fn spill_observer_called_on_tiny_memory() {
+        let index = TempIndex::new();
+        let settings_diff = settings_diff(&index);
+
+        // enough distinct word pairs to exceed the minimum memory of a grenad sorter
+        let words = |docid: usize| -> Vec<String> {
+            (0..200).map(|i| format!("word{}", (docid * 7919 + i * 104_729) % 1000)).collect()
+        };
+        let entries: Vec<_> =
+            (0..1500).map(|docid| (docid as DocumentId, 0, Vec::new(), words(docid))).collect();
+
+        let extract = |max_memory| {
+            // the names of the sorters that spilled to disk and the number of bytes they wrote
+            let spills = Arc::new(Mutex::new(Vec::new()));
+            let observer = spills.clone();
+            let indexer = GrenadParameters {
+                max_memory,
+                sorter_spill_observer: Some(Arc::new(move |sorter_name: &str, spilled_bytes| {
+                    observer.lock().unwrap().push((sorter_name.to_string(), spilled_bytes))
+                })),
+                ..Default::default()
+            };
+            let output = extract_word_pair_proximity_docids(
+                docid_word_positions(&entries),
+                indexer,
+                &settings_diff,
+            )
+            .unwrap();
+            let spills = std::mem::take(&mut *spills.lock().unwrap());
+            (read_all(output), spills)
+        };
+
+        let (expected, spills) = extract(None);
+        assert!(spills.is_empty(), "{spills:?}");
+
+        let (output, spills) = extract(Some(0));
+        assert!(!spills.is_empty());
+        for (sorter_name, spilled_bytes) in spills {
+            assert_eq!(sorter_name, "word_pair_proximity_docids");
+            assert!(spilled_bytes > 0);
+        }
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn reused_buffers_match_fresh_buffers() {
+        let sorters = || -> Vec<_> {
+            (0..MAX_DISTANCE as usize - 1)
+                .map(|_| {
+                    create_observed_sorter(
+                        "word_pair_proximity_docids",
+                        grenad::SortAlgorithm::Unstable,
+                        MergeDeladdCboRoaringBitmaps,
+                        GrenadParameters::default(),
+                        None,
+                    )
+                })
+                .collect()
+        };
+        let into_entries = |sorters: Vec<ObservedSorter<MergeDeladdCboRoaringBitmaps>>| {
+            let mut writer =
+                create_writer(grenad::CompressionType::None, None, tempfile::tempfile().unwrap());
+            for sorter in sorters {
+                sorter.write_into_stream_writer(&mut writer).unwrap();
+            }
+            read_all(writer_into_reader(writer).unwrap())
+        };
+        let pairs = |pairs: &[(&str, &str, u8)]| -> BTreeMap<(String, String), u8> {
+            pairs.iter().map(|(w1, w2, prox)| ((w1.to_string(), w2.to_string()), *prox)).collect()
+        };
+
+        // the longest keys come first to leave stale bytes in the buffers
+        let documents = [
+            (0, pairs(&[("internationalization", "localization", 2)]), pairs(&[])),
+            (1, pairs(&[("a", "b", 1)]), pairs(&[("a", "b", 1), ("b", "c", 3)])),
+            (2, pairs(&[]), pairs(&[("hello", "world", 1), ("world", "hello", 2)])),
+        ];
+
+        let mut fresh_sorters = sorters();
+        for (docid, del, add) in &documents {
+            document_word_positions_into_sorter(
+                *docid,
+                del,
+                add,
+                &mut fresh_sorters,
+                &mut Vec::new(),
+                &mut Vec::new(),
+            )
+            .unwrap();
+        }
+
+        let mut reused_sorters = sorters();
+        let mut buffer = Vec::new();
+        let mut key_buffer = Vec::new();
+        for (docid, del, add) in &documents {
+            document_word_positions_into_sorter(
+                *docid,
+                del,
+                add,
+                &mut reused_sorters,
+                &mut buffer,
+                &mut key_buffer,
+            )
+            .unwrap();
+        }
+        // the first document sized the buffers for the following ones
+        assert!(key_buffer.capacity() >= "internationalization".len() + "localization".len() + 2);
+
+        assert_eq!(into_entries(reused_sorters), into_entries(fresh_sorters));
+    }
+
+    #[test]
+    fn deleted_word_pairs_leave_no_empty_entry() {
+        let index = TempIndex::new();
+        index.add_documents(documents!([{ "id": 0, "title": "hello world" }])).unwrap();
+        let settings_diff = settings_diff(&index);
+
+        let rtxn = index.read_txn().unwrap();
+        let title = index.fields_ids_map(&rtxn).unwrap().id("title").unwrap();
+        drop(rtxn);
+
+        let text = |text: &str| text.split(' ').map(String::from).collect::<Vec<_>>();
+        // the second document was never indexed, its pairs are missing from the database
+        let entries = [
+            (0, title, text("hello world"), text("hello there")),
+            (1, title, text("ghost pair"), Vec::new()),
+        ];
+        let chunk = extract_word_pair_proximity_docids(
+            docid_word_positions(&entries),
+            GrenadParameters::default(),
+            &settings_diff,
+        )
+        .unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        write_typed_chunk_into_index(
+            &mut wtxn,
+            &index,
+            &settings_diff,
+            vec![TypedChunk::WordPairProximityDocids(chunk)],
+            &mut RoaringBitmap::new(),
+        )
+        .unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let pairs: Vec<_> = index
+            .word_pair_proximity_docids
+            .iter(&rtxn)
+            .unwrap()
+            .map(|entry| {
+                let ((proximity, left, right), docids) = entry.unwrap();
+                (proximity, left.to_string(), right.to_string(), docids)
+            })
+            .collect();
+        assert_eq!(pairs, vec![(1, S("hello"), S("there"), RoaringBitmap::from_iter([0]))]);
+    }
+
+    #[test]
+    fn proximity_excluded_fields_record_no_word_pair() {
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "hello world", "body": "a very long body" },
+            ]))
+            .unwrap();
+
+        let word_pairs = |index: &TempIndex| -> Vec<(u8, String, String, Vec<u32>)> {
+            let rtxn = index.read_txn().unwrap();
+            index
+                .word_pair_proximity_docids
+                .iter(&rtxn)
+                .unwrap()
+                .map(|entry| {
+                    let ((proximity, left, right), docids) = entry.unwrap();
+                    (proximity, left.to_string(), right.to_string(), docids.into_iter().collect())
+                })
+                .filter(|(proximity, ..)| *proximity == 1)
+                .collect()
+        };
+        let rtxn = index.read_txn().unwrap();
+        let body = index.fields_ids_map(&rtxn).unwrap().id("body").unwrap();
+        drop(rtxn);
+        assert_eq!(word_pairs(&index).len(), 4);
+
+        // excluding a field reindexes the documents
+        index
+            .update_settings(|settings| {
+                settings.set_proximity_excluded_fields([body].into_iter().collect())
+            })
+            .unwrap();
+        assert_eq!(word_pairs(&index), vec![(1, S("hello"), S("world"), vec![0])]);
+
+        // the new documents skip the excluded field too
+        index
+            .add_documents(documents!([
+                { "id": 1, "title": "hello world", "body": "another long body" },
+            ]))
+            .unwrap();
+        assert_eq!(word_pairs(&index), vec![(1, S("hello"), S("world"), vec![0, 1])]);
+
+        index.update_settings(|settings| settings.reset_proximity_excluded_fields()).unwrap();
+        assert_eq!(
+            word_pairs(&index),
+            vec![
+                (1, S("a"), S("very"), vec![0]),
+                (1, S("another"), S("long"), vec![1]),
+                (1, S("hello"), S("world"), vec![0, 1]),
+                (1, S("long"), S("body"), vec![0, 1]),
+                (1, S("very"), S("long"), vec![0]),
+            ]
+        );
+    }
+}
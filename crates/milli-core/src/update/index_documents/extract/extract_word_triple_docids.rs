@@ -0,0 +1,225 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+
+use super::cached_docid_word_positions::DocidWordPositions;
+use super::helpers::{
+    create_sorter, create_writer, sorter_into_reader, writer_into_reader, GrenadParameters,
+    MergeDeladdCboRoaringBitmaps,
+};
+use crate::proximity::index_proximity;
+use crate::update::del_add::{DelAdd, KvWriterDelAdd};
+use crate::update::settings::InnerIndexSettingsDiff;
+use crate::{DocumentId, Result};
+
+/// Extracts the triples of words following each other and the documents ids where they appear.
+///
+/// Returns a grenad reader with the list of extracted word triples and
+/// documents ids from the given chunk of docid word positions.
+#[tracing::instrument(level = "trace", skip_all, target = "indexing::extract")]
+pub fn extract_word_triple_docids<R: io::Read + io::Seek>(
+    docid_word_positions: impl Into<DocidWordPositions<R>>,
+    indexer: GrenadParameters,
+    settings_diff: &InnerIndexSettingsDiff,
+) -> Result<grenad::Reader<BufReader<File>>> {
+    let any_deletion = settings_diff.old.record_word_triples;
+    let any_addition = settings_diff.new.record_word_triples;
+
+    // early return if the data shouldn't be deleted nor created.
+    if (settings_diff.settings_update_only && !settings_diff.reindex_word_triples())
+        || !(any_deletion || any_addition)
+    {
+        let writer = create_writer(
+            indexer.chunk_compression_type,
+            indexer.chunk_compression_level,
+            tempfile::tempfile()?,
+        );
+        return writer_into_reader(writer);
+    }
+
+    let mut word_triple_docids_sorter = create_sorter(
+        grenad::SortAlgorithm::Unstable,
+        MergeDeladdCboRoaringBitmaps,
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        indexer.max_nb_chunks,
+        indexer.max_memory_by_thread(),
+        true,
+    );
+
+    let mut del_word_triples = BTreeSet::new();
+    let mut add_word_triples = BTreeSet::new();
+    let mut current_document_id = None;
+
+    docid_word_positions.into().for_each(|entry| {
+        let document_id = entry.document_id;
+
+        // if we change document, we fill the sorter
+        if let Some(current_document_id) = current_document_id.filter(|id| *id != document_id) {
+            document_word_triples_into_sorter(
+                current_document_id,
+                &del_word_triples,
+                &add_word_triples,
+                &mut word_triple_docids_sorter,
+            )?;
+            del_word_triples.clear();
+            add_word_triples.clear();
+        }
+
+        current_document_id = Some(document_id);
+
+        if any_deletion {
+            word_positions_into_word_triples(&entry.deletion, &mut del_word_triples);
+        }
+        if any_addition {
+            word_positions_into_word_triples(&entry.addition, &mut add_word_triples);
+        }
+
+        Ok(())
+    })?;
+
+    if let Some(document_id) = current_document_id {
+        document_word_triples_into_sorter(
+            document_id,
+            &del_word_triples,
+            &add_word_triples,
+            &mut word_triple_docids_sorter,
+        )?;
+    }
+
+    sorter_into_reader(word_triple_docids_sorter, indexer)
+}
+
+/// Collects the triples of words of a field where each word is at a proximity of 1
+/// from the previous one.
+fn word_positions_into_word_triples(
+    word_positions: &[(u16, &str)],
+    word_triples: &mut BTreeSet<(String, String, String)>,
+) {
+    for window in word_positions.windows(3) {
+        let [(p1, w1), (p2, w2), (p3, w3)] = window else { unreachable!() };
+        if index_proximity(*p1 as u32, *p2 as u32) == 1
+            && index_proximity(*p2 as u32, *p3 as u32) == 1
+        {
+            word_triples.insert((w1.to_string(), w2.to_string(), w3.to_string()));
+        }
+    }
+}
+
+fn document_word_triples_into_sorter(
+    document_id: DocumentId,
+    del_word_triples: &BTreeSet<(String, String, String)>,
+    add_word_triples: &BTreeSet<(String, String, String)>,
+    word_triple_docids_sorter: &mut grenad::Sorter<MergeDeladdCboRoaringBitmaps>,
+) -> Result<()> {
+    use itertools::merge_join_by;
+    use itertools::EitherOrBoth::{Both, Left, Right};
+
+    let mut buffer = Vec::new();
+    let mut key_buffer = Vec::new();
+    for eob in merge_join_by(del_word_triples.iter(), add_word_triples.iter(), |d, a| d.cmp(a)) {
+        buffer.clear();
+        let mut value_writer = KvWriterDelAdd::new(&mut buffer);
+        let (w1, w2, w3) = match eob {
+            Left(triple) => {
+                value_writer.insert(DelAdd::Deletion, document_id.to_ne_bytes()).unwrap();
+                triple
+            }
+            Right(triple) => {
+                value_writer.insert(DelAdd::Addition, document_id.to_ne_bytes()).unwrap();
+                triple
+            }
+            Both(triple, _) => {
+                value_writer.insert(DelAdd::Deletion, document_id.to_ne_bytes()).unwrap();
+                value_writer.insert(DelAdd::Addition, document_id.to_ne_bytes()).unwrap();
+                triple
+            }
+        };
+
+        key_buffer.clear();
+        key_buffer.extend_from_slice(w1.as_bytes());
+        key_buffer.push(0);
+        key_buffer.extend_from_slice(w2.as_bytes());
+        key_buffer.push(0);
+        key_buffer.extend_from_slice(w3.as_bytes());
+
+        word_triple_docids_sorter.insert(&key_buffer, value_writer.into_inner().unwrap())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use crate::index::tests::TempIndex;
+    use crate::Index;
+
+    fn word_triples(index: &Index) -> Vec<(String, String, String, Vec<u32>)> {
+        let rtxn = index.read_txn().unwrap();
+        index
+            .word_triple_docids
+            .iter(&rtxn)
+            .unwrap()
+            .map(|entry| {
+                let ((w1, w2, w3), docids) = entry.unwrap();
+                (w1.to_string(), w2.to_string(), w3.to_string(), docids.into_iter().collect())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn word_triples_only_recorded_when_enabled() {
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick brown fox" },
+            ]))
+            .unwrap();
+        assert!(word_triples(&index).is_empty());
+
+        // enabling the setting reindexes the documents
+        index.update_settings(|settings| settings.set_record_word_triples(true)).unwrap();
+        let expected = vec![
+            (S("quick"), S("brown"), S("fox"), vec![0]),
+            (S("the"), S("quick"), S("brown"), vec![0]),
+        ];
+        assert_eq!(word_triples(&index), expected);
+
+        // the triples never span a hard separator nor two fields
+        index
+            .add_documents(documents!([
+                { "id": 1, "title": "the quick. brown fox", "body": "quick brown" },
+            ]))
+            .unwrap();
+        assert_eq!(word_triples(&index), expected);
+
+        index
+            .add_documents(documents!([
+                { "id": 1, "title": "a quick brown fox" },
+            ]))
+            .unwrap();
+        assert_eq!(
+            word_triples(&index),
+            vec![
+                (S("a"), S("quick"), S("brown"), vec![1]),
+                (S("quick"), S("brown"), S("fox"), vec![0, 1]),
+                (S("the"), S("quick"), S("brown"), vec![0]),
+            ]
+        );
+
+        index.delete_documents(vec![S("0")]);
+        assert_eq!(
+            word_triples(&index),
+            vec![
+                (S("a"), S("quick"), S("brown"), vec![1]),
+                (S("quick"), S("brown"), S("fox"), vec![1]),
+            ]
+        );
+
+        index.update_settings(|settings| settings.set_record_word_triples(false)).unwrap();
+        assert!(word_triples(&index).is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+mod cached_docid_word_positions;
 mod extract_docid_word_positions;
 mod extract_facet_number_docids;
 mod extract_facet_string_docids;
@@ -6,8 +7,10 @@ mod extract_fid_word_count_docids;
 mod extract_geo_points;
 mod extract_vector_points;
 mod extract_word_docids;
+mod extract_word_pair_frequency_docids;
 mod extract_word_pair_proximity_docids;
 mod extract_word_position_docids;
+mod extract_word_triple_docids;
 
 use std::fs::File;
 use std::io::BufReader;
@@ -16,6 +19,7 @@ use std::sync::{Arc, OnceLock};
 use crossbeam_channel::Sender;
 use rayon::prelude::*;
 
+use self::cached_docid_word_positions::{CachedDocidWordPositions, DocidWordPositions};
 use self::extract_docid_word_positions::extract_docid_word_positions;
 use self::extract_facet_number_docids::extract_facet_number_docids;
 use self::extract_facet_string_docids::extract_facet_string_docids;
@@ -25,9 +29,12 @@ use self::extract_geo_points::extract_geo_points;
 use self::extract_vector_points::{
     extract_embeddings, extract_vector_points, ExtractedVectorPoints,
 };
+pub use self::extract_word_docids::build_word_docids_reader;
 use self::extract_word_docids::extract_word_docids;
+use self::extract_word_pair_frequency_docids::extract_word_pair_frequency_docids;
 use self::extract_word_pair_proximity_docids::extract_word_pair_proximity_docids;
 use self::extract_word_position_docids::extract_word_position_docids;
+use self::extract_word_triple_docids::extract_word_triple_docids;
 use super::helpers::{as_cloneable_grenad, CursorClonableMmap, GrenadParameters};
 use super::{helpers, TypedChunk};
 use crate::index::IndexEmbeddingConfig;
@@ -57,7 +64,7 @@ pub(crate) fn data_from_obkv_documents(
                 .map(|original_documents_chunk| {
                     send_original_documents_data(
                         original_documents_chunk,
-                        indexer,
+                        indexer.clone(),
                         lmdb_writer_sx.clone(),
                         embedders_configs.clone(),
                         settings_diff.clone(),
@@ -72,7 +79,7 @@ pub(crate) fn data_from_obkv_documents(
                 .map(|flattened_obkv_chunks| {
                     send_and_extract_flattened_documents_data(
                         flattened_obkv_chunks,
-                        indexer,
+                        indexer.clone(),
                         lmdb_writer_sx.clone(),
                         primary_key_id,
                         settings_diff.clone(),
@@ -85,25 +92,37 @@ pub(crate) fn data_from_obkv_documents(
                         (ref fid_docid_facet_numbers_chunk, ref fid_docid_facet_strings_chunk),
                     )) = result
                     {
-                        run_extraction_task::<_, _, grenad::Reader<BufReader<File>>>(
+                        // settings-only reindexes decode the word positions once for both the
+                        // word and the proximity extractors instead of once per extractor.
+                        let docid_word_positions = if settings_diff.settings_update_only {
+                            DocidWordPositions::Cached(CachedDocidWordPositions::decode(
+                                docid_word_positions_chunk.clone(),
+                            )?)
+                        } else {
+                            DocidWordPositions::Chunk(docid_word_positions_chunk.clone())
+                        };
+
+                        run_extraction_task::<_, _, _, grenad::Reader<BufReader<File>>>(
                             docid_word_positions_chunk.clone(),
-                            indexer,
+                            indexer.clone(),
                             settings_diff.clone(),
                             lmdb_writer_sx.clone(),
                             extract_fid_word_count_docids,
                             TypedChunk::FieldIdWordCountDocids,
                         );
                         run_extraction_task::<
+                            _,
                             _,
                             _,
                             (
                                 grenad::Reader<BufReader<File>>,
                                 grenad::Reader<BufReader<File>>,
                                 grenad::Reader<BufReader<File>>,
+                                grenad::Reader<BufReader<File>>,
                             ),
                         >(
-                            docid_word_positions_chunk.clone(),
-                            indexer,
+                            docid_word_positions.clone(),
+                            indexer.clone(),
                             settings_diff.clone(),
                             lmdb_writer_sx.clone(),
                             extract_word_docids,
@@ -111,18 +130,20 @@ pub(crate) fn data_from_obkv_documents(
                                 word_docids_reader,
                                 exact_word_docids_reader,
                                 word_fid_docids_reader,
+                                word_frequency_reader,
                             )| {
                                 TypedChunk::WordDocids {
                                     word_docids_reader,
                                     exact_word_docids_reader,
                                     word_fid_docids_reader,
+                                    word_frequency_reader,
                                 }
                             },
                         );
 
-                        run_extraction_task::<_, _, grenad::Reader<BufReader<File>>>(
+                        run_extraction_task::<_, _, _, grenad::Reader<BufReader<File>>>(
                             docid_word_positions_chunk.clone(),
-                            indexer,
+                            indexer.clone(),
                             settings_diff.clone(),
                             lmdb_writer_sx.clone(),
                             extract_word_position_docids,
@@ -130,30 +151,49 @@ pub(crate) fn data_from_obkv_documents(
                         );
 
                         run_extraction_task::<
+                            _,
                             _,
                             _,
                             (grenad::Reader<BufReader<File>>, grenad::Reader<BufReader<File>>),
                         >(
                             fid_docid_facet_strings_chunk.clone(),
-                            indexer,
+                            indexer.clone(),
                             settings_diff.clone(),
                             lmdb_writer_sx.clone(),
                             extract_facet_string_docids,
                             TypedChunk::FieldIdFacetStringDocids,
                         );
 
-                        run_extraction_task::<_, _, grenad::Reader<BufReader<File>>>(
+                        run_extraction_task::<_, _, _, grenad::Reader<BufReader<File>>>(
                             fid_docid_facet_numbers_chunk.clone(),
-                            indexer,
+                            indexer.clone(),
                             settings_diff.clone(),
                             lmdb_writer_sx.clone(),
                             extract_facet_number_docids,
                             TypedChunk::FieldIdFacetNumberDocids,
                         );
 
-                        run_extraction_task::<_, _, grenad::Reader<BufReader<File>>>(
-                            docid_word_positions_chunk.clone(),
-                            indexer,
+                        run_extraction_task::<_, _, _, grenad::Reader<BufReader<File>>>(
+                            docid_word_positions.clone(),
+                            indexer.clone(),
+                            settings_diff.clone(),
+                            lmdb_writer_sx.clone(),
+                            extract_word_triple_docids,
+                            TypedChunk::WordTripleDocids,
+                        );
+
+                        run_extraction_task::<_, _, _, grenad::Reader<BufReader<File>>>(
+                            docid_word_positions.clone(),
+                            indexer.clone(),
+                            settings_diff.clone(),
+                            lmdb_writer_sx.clone(),
+                            extract_word_pair_frequency_docids,
+                            TypedChunk::WordPairFrequencyDocids,
+                        );
+
+                        run_extraction_task::<_, _, _, grenad::Reader<BufReader<File>>>(
+                            docid_word_positions,
+                            indexer.clone(),
                             settings_diff.clone(),
                             lmdb_writer_sx.clone(),
                             extract_word_pair_proximity_docids,
@@ -174,22 +214,16 @@ pub(crate) fn data_from_obkv_documents(
 /// Generated grenad chunks are merged using the merge_fn.
 /// The result of merged chunks is serialized as TypedChunk using the serialize_fn
 /// and sent into lmdb_writer_sx.
-fn run_extraction_task<FE, FS, M>(
-    chunk: grenad::Reader<CursorClonableMmap>,
+fn run_extraction_task<I, FE, FS, M>(
+    chunk: I,
     indexer: GrenadParameters,
     settings_diff: Arc<InnerIndexSettingsDiff>,
     lmdb_writer_sx: Sender<Result<TypedChunk>>,
     extract_fn: FE,
     serialize_fn: FS,
 ) where
-    FE: Fn(
-            grenad::Reader<CursorClonableMmap>,
-            GrenadParameters,
-            &InnerIndexSettingsDiff,
-        ) -> Result<M>
-        + Sync
-        + Send
-        + 'static,
+    I: Send + 'static,
+    FE: Fn(I, GrenadParameters, &InnerIndexSettingsDiff) -> Result<M> + Sync + Send + 'static,
     FS: Fn(M) -> TypedChunk + Sync + Send + 'static,
     M: Send,
 {
@@ -248,7 +282,7 @@ fn send_original_documents_data(
         rayon::spawn(move || {
             match extract_vector_points(
                 original_documents_chunk.clone(),
-                indexer,
+                indexer.clone(),
                 &embedders_configs,
                 &settings_diff,
                 &possible_embedding_mistakes,
@@ -266,7 +300,7 @@ fn send_original_documents_data(
                     {
                         let embeddings = match extract_embeddings(
                             prompts,
-                            indexer,
+                            indexer.clone(),
                             embedder.clone(),
                             &embedder_name,
                             &possible_embedding_mistakes,
@@ -332,10 +366,15 @@ fn send_and_extract_flattened_documents_data(
     if settings_diff.run_geo_indexing() {
         let documents_chunk_cloned = flattened_documents_chunk.clone();
         let lmdb_writer_sx_cloned = lmdb_writer_sx.clone();
+        let indexer_cloned = indexer.clone();
         let settings_diff = settings_diff.clone();
         rayon::spawn(move || {
-            let result =
-                extract_geo_points(documents_chunk_cloned, indexer, primary_key_id, &settings_diff);
+            let result = extract_geo_points(
+                documents_chunk_cloned,
+                indexer_cloned,
+                primary_key_id,
+                &settings_diff,
+            );
             let _ = match result {
                 Ok(geo_points) => lmdb_writer_sx_cloned.send(Ok(TypedChunk::GeoPoints(geo_points))),
                 Err(error) => lmdb_writer_sx_cloned.send(Err(error)),
@@ -348,7 +387,7 @@ fn send_and_extract_flattened_documents_data(
             || {
                 let docid_word_positions_chunk = extract_docid_word_positions(
                     flattened_documents_chunk.clone(),
-                    indexer,
+                    indexer.clone(),
                     &settings_diff,
                     max_positions_per_attributes,
                 )?;
@@ -366,9 +405,13 @@ fn send_and_extract_flattened_documents_data(
                     fid_facet_is_null_docids_chunk,
                     fid_facet_is_empty_docids_chunk,
                     fid_facet_exists_docids_chunk,
+                    fid_facet_i64_docids_chunk,
+                    fid_facet_is_date_docids_chunk,
+                    fid_facet_is_true_docids_chunk,
+                    fid_facet_is_false_docids_chunk,
                 } = extract_fid_docid_facet_values(
                     flattened_documents_chunk.clone(),
-                    indexer,
+                    indexer.clone(),
                     &settings_diff,
                 )?;
 
@@ -398,6 +441,19 @@ fn send_and_extract_flattened_documents_data(
                 let _ = lmdb_writer_sx
                     .send(Ok(TypedChunk::FieldIdFacetExistsDocids(fid_facet_exists_docids_chunk)));
 
+                let _ = lmdb_writer_sx
+                    .send(Ok(TypedChunk::FieldIdFacetI64Docids(fid_facet_i64_docids_chunk)));
+
+                let _ = lmdb_writer_sx
+                    .send(Ok(TypedChunk::FieldIdFacetIsDateDocids(fid_facet_is_date_docids_chunk)));
+
+                let _ = lmdb_writer_sx
+                    .send(Ok(TypedChunk::FieldIdFacetIsTrueDocids(fid_facet_is_true_docids_chunk)));
+
+                let _ = lmdb_writer_sx.send(Ok(TypedChunk::FieldIdFacetIsFalseDocids(
+                    fid_facet_is_false_docids_chunk,
+                )));
+
                 Ok((fid_docid_facet_numbers_chunk, fid_docid_facet_strings_chunk))
             },
         );
@@ -1,10 +1,14 @@
+use std::fmt;
 use std::fs::File;
-use std::io::{self, BufReader, BufWriter, Seek};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
-use grenad::{CompressionType, MergeFunction, Sorter};
+use grenad::{ChunkCreator, CompressionType, MergeFunction, Sorter};
 use heed::types::Bytes;
 
 use super::ClonableMmap;
+use crate::error::UserError;
 use crate::update::index_documents::valid_lmdb_key;
 use crate::Result;
 
@@ -12,6 +16,9 @@ use crate::Result;
 /// that there is one grenad sorter by thread.
 const MAX_GRENAD_SORTER_USAGE: usize = 500 * 1024 * 1024; // 500 MiB
 
+/// The maximum number of chunks of a grenad sorter when it is not specified.
+const DEFAULT_MAX_NB_CHUNKS: usize = 25;
+
 pub type CursorClonableMmap = io::Cursor<ClonableMmap>;
 
 pub fn create_writer<R: io::Write>(
@@ -39,6 +46,55 @@ pub fn create_sorter<MF: MergeFunction>(
     max_memory: Option<usize>,
     sort_in_parallel: bool,
 ) -> grenad::Sorter<MF> {
+    sorter_builder(
+        sort_algorithm,
+        merge,
+        chunk_compression_type,
+        chunk_compression_level,
+        max_nb_chunks,
+        max_memory,
+        sort_in_parallel,
+    )
+    .build()
+}
+
+/// Creates a grenad sorter like [`create_sorter`] that calls the
+/// [`GrenadParameters::sorter_spill_observer`] with its name each time it spills to disk.
+pub fn create_observed_sorter<MF: MergeFunction>(
+    sorter_name: &'static str,
+    sort_algorithm: grenad::SortAlgorithm,
+    merge: MF,
+    indexer: GrenadParameters,
+    max_memory: Option<usize>,
+) -> ObservedSorter<MF> {
+    let observer = SpillObserver {
+        sorter_name,
+        observer: indexer.sorter_spill_observer.clone(),
+        spilling: Arc::new(AtomicBool::new(true)),
+    };
+    let sorter = sorter_builder(
+        sort_algorithm,
+        merge,
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        indexer.max_nb_chunks,
+        max_memory,
+        true,
+    )
+    .chunk_creator(observer.clone())
+    .build();
+    ObservedSorter { sorter, spilling: observer.spilling }
+}
+
+fn sorter_builder<MF>(
+    sort_algorithm: grenad::SortAlgorithm,
+    merge: MF,
+    chunk_compression_type: grenad::CompressionType,
+    chunk_compression_level: Option<u32>,
+    max_nb_chunks: Option<usize>,
+    max_memory: Option<usize>,
+    sort_in_parallel: bool,
+) -> grenad::SorterBuilder<MF, grenad::DefaultChunkCreator> {
     let mut builder = grenad::Sorter::builder(merge);
     builder.chunk_compression_type(chunk_compression_type);
     if let Some(level) = chunk_compression_level {
@@ -53,7 +109,7 @@ pub fn create_sorter<MF: MergeFunction>(
     }
     builder.sort_algorithm(sort_algorithm);
     builder.sort_in_parallel(sort_in_parallel);
-    builder.build()
+    builder
 }
 
 #[tracing::instrument(level = "trace", skip_all, target = "indexing::grenad")]
@@ -75,6 +131,258 @@ where
     writer_into_reader(writer)
 }
 
+/// Called with the name of a sorter and the number of bytes it wrote to disk,
+/// each time its entries are spilled out of memory, from any of the extraction threads.
+pub type SorterSpillObserver = Arc<dyn Fn(&str, u64) + Send + Sync>;
+
+/// A grenad sorter that reports its spills to a [`SorterSpillObserver`], the entries
+/// written to disk when the sorter is consumed are not considered a spill.
+pub struct ObservedSorter<MF> {
+    sorter: Sorter<MF, SpillObserver>,
+    spilling: Arc<AtomicBool>,
+}
+
+impl<MF: MergeFunction> ObservedSorter<MF> {
+    pub fn insert<K, V>(&mut self, key: K, val: V) -> grenad::Result<(), MF::Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.sorter.insert(key, val)
+    }
+
+    /// Writes the entries to disk, reporting them as a spill.
+    fn spill_into_reader_cursors(
+        self,
+    ) -> grenad::Result<Vec<grenad::ReaderCursor<SpilledChunk>>, MF::Error> {
+        self.sorter.into_reader_cursors()
+    }
+
+    pub fn into_reader_cursors(
+        self,
+    ) -> grenad::Result<Vec<grenad::ReaderCursor<SpilledChunk>>, MF::Error> {
+        self.spilling.store(false, Ordering::Relaxed);
+        self.sorter.into_reader_cursors()
+    }
+
+    pub fn into_stream_merger_iter(
+        self,
+    ) -> grenad::Result<grenad::MergerIter<SpilledChunk, MF>, MF::Error> {
+        self.spilling.store(false, Ordering::Relaxed);
+        self.sorter.into_stream_merger_iter()
+    }
+
+    pub fn write_into_stream_writer<W: io::Write>(
+        self,
+        writer: &mut grenad::Writer<W>,
+    ) -> grenad::Result<(), MF::Error> {
+        self.spilling.store(false, Ordering::Relaxed);
+        self.sorter.write_into_stream_writer(writer)
+    }
+}
+
+/// Creates the temporary files of an [`ObservedSorter`].
+#[derive(Clone)]
+pub struct SpillObserver {
+    sorter_name: &'static str,
+    observer: Option<SorterSpillObserver>,
+    /// Whether the chunks written by the sorter are spills, `false` once it is consumed.
+    spilling: Arc<AtomicBool>,
+}
+
+impl ChunkCreator for SpillObserver {
+    type Chunk = SpilledChunk;
+    type Error = io::Error;
+
+    fn create(&self) -> io::Result<SpilledChunk> {
+        let file = tempfile::tempfile()?;
+        Ok(SpilledChunk { file, observer: self.clone(), written: 0 })
+    }
+}
+
+/// A temporary file of an [`ObservedSorter`], the bytes written since the last flush
+/// are reported to the observer when the sorter flushes the chunk.
+pub struct SpilledChunk {
+    file: File,
+    observer: SpillObserver,
+    written: u64,
+}
+
+impl Write for SpilledChunk {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let written = std::mem::take(&mut self.written);
+        let SpillObserver { sorter_name, observer, spilling } = &self.observer;
+        if let Some(observer) = observer {
+            if written > 0 && spilling.load(Ordering::Relaxed) {
+                observer(sorter_name, written);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Read for SpilledChunk {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Seek for SpilledChunk {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+/// A grenad sorter that also writes its entries to disk every
+/// [`GrenadParameters::max_sorter_entries`] insertions, not only when its memory is full.
+///
+/// Like the chunks of a grenad sorter, the flushed chunks are merged into a single one once there
+/// are more than [`GrenadParameters::max_nb_chunks`] of them.
+pub struct FlushingSorter<MF> {
+    sorter_name: &'static str,
+    sorter: ObservedSorter<MF>,
+    sort_algorithm: grenad::SortAlgorithm,
+    merge: MF,
+    indexer: GrenadParameters,
+    max_memory: Option<usize>,
+    entries: usize,
+    flushed: Vec<grenad::ReaderCursor<SpilledChunk>>,
+}
+
+impl<MF: MergeFunction + Clone> FlushingSorter<MF> {
+    pub fn new(
+        sorter_name: &'static str,
+        sort_algorithm: grenad::SortAlgorithm,
+        merge: MF,
+        indexer: GrenadParameters,
+        max_memory: Option<usize>,
+    ) -> Self {
+        let sorter = create_observed_sorter(
+            sorter_name,
+            sort_algorithm,
+            merge.clone(),
+            indexer.clone(),
+            max_memory,
+        );
+        Self {
+            sorter_name,
+            sorter,
+            sort_algorithm,
+            merge,
+            indexer,
+            max_memory,
+            entries: 0,
+            flushed: Vec::new(),
+        }
+    }
+
+    pub fn insert<K, V>(&mut self, key: K, val: V) -> grenad::Result<(), MF::Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.sorter.insert(key, val)?;
+        self.entries += 1;
+        if self.indexer.max_sorter_entries.is_some_and(|max_entries| self.entries >= max_entries) {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the entries inserted so far to disk and starts a new sorter for the next ones.
+    pub fn flush(&mut self) -> grenad::Result<(), MF::Error> {
+        if self.entries == 0 {
+            return Ok(());
+        }
+        let sorter = create_observed_sorter(
+            self.sorter_name,
+            self.sort_algorithm,
+            self.merge.clone(),
+            self.indexer.clone(),
+            self.max_memory,
+        );
+        let sorter = std::mem::replace(&mut self.sorter, sorter);
+        self.flushed.extend(sorter.spill_into_reader_cursors()?);
+        self.entries = 0;
+        let max_nb_chunks = self.indexer.max_nb_chunks.unwrap_or(DEFAULT_MAX_NB_CHUNKS).max(1);
+        if self.flushed.len() > max_nb_chunks {
+            self.merge_flushed()?;
+        }
+        Ok(())
+    }
+
+    /// Merges the flushed chunks into a single one, the merge is not reported as a spill.
+    fn merge_flushed(&mut self) -> grenad::Result<(), MF::Error> {
+        let chunk = SpillObserver {
+            sorter_name: self.sorter_name,
+            observer: self.indexer.sorter_spill_observer.clone(),
+            spilling: Arc::new(AtomicBool::new(false)),
+        }
+        .create()?;
+        let mut writer = create_writer(
+            self.indexer.chunk_compression_type,
+            self.indexer.chunk_compression_level,
+            chunk,
+        );
+        let mut builder = grenad::MergerBuilder::new(self.merge.clone());
+        builder.extend(self.flushed.drain(..));
+        let mut iter = builder.build().into_stream_merger_iter().map_err(infallible_merge_error)?;
+        while let Some((key, value)) = iter.next()? {
+            writer.insert(key, value)?;
+        }
+        let mut chunk = writer.into_inner()?.into_inner().map_err(|err| err.into_error())?;
+        chunk.rewind()?;
+        let cursor = grenad::Reader::new(chunk).and_then(grenad::Reader::into_cursor);
+        self.flushed.push(cursor.map_err(infallible_merge_error)?);
+        Ok(())
+    }
+
+    /// Consumes this sorter and outputs a stream of the merged entries in key-order,
+    /// including the ones written to disk.
+    pub fn into_stream_merger_iter(self) -> Result<grenad::MergerIter<SpilledChunk, MF>>
+    where
+        crate::Error: From<MF::Error>,
+    {
+        let mut builder = grenad::MergerBuilder::new(self.merge);
+        builder.extend(self.flushed);
+        builder.extend(self.sorter.into_reader_cursors()?);
+        Ok(builder.build().into_stream_merger_iter()?)
+    }
+
+    pub fn into_reader(self) -> Result<grenad::Reader<BufReader<File>>>
+    where
+        crate::Error: From<MF::Error>,
+    {
+        let mut writer = create_writer(
+            self.indexer.chunk_compression_type,
+            self.indexer.chunk_compression_level,
+            tempfile::tempfile()?,
+        );
+        let mut iter = self.into_stream_merger_iter()?;
+        while let Some((key, value)) = iter.next()? {
+            writer.insert(key, value)?;
+        }
+        writer_into_reader(writer)
+    }
+}
+
+/// Converts a grenad error that can't be a merge error into one of another merge function.
+fn infallible_merge_error<U>(error: grenad::Error) -> grenad::Error<U> {
+    match error {
+        grenad::Error::Io(error) => grenad::Error::Io(error),
+        grenad::Error::Merge(error) => match error {},
+        grenad::Error::InvalidCompressionType => grenad::Error::InvalidCompressionType,
+        grenad::Error::InvalidFormatVersion => grenad::Error::InvalidFormatVersion,
+    }
+}
+
 pub fn writer_into_reader(
     writer: grenad::Writer<BufWriter<File>>,
 ) -> Result<grenad::Reader<BufReader<File>>> {
@@ -95,12 +403,43 @@ pub unsafe fn as_cloneable_grenad(
     Ok(reader)
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Clone)]
 pub struct GrenadParameters {
     pub chunk_compression_type: CompressionType,
     pub chunk_compression_level: Option<u32>,
     pub max_memory: Option<usize>,
     pub max_nb_chunks: Option<usize>,
+    /// The number of word pairs of a single document above which the word pair proximities
+    /// extracted so far are moved out of memory, `None` keeps all of them in memory.
+    pub max_word_pairs_per_document: Option<usize>,
+    /// The number of entries inserted in a [`FlushingSorter`] above which they are written
+    /// to disk, `None` only writes them when the memory of the sorter is full.
+    pub max_sorter_entries: Option<usize>,
+    /// Called each time an observed sorter of the extraction writes its entries to disk.
+    pub sorter_spill_observer: Option<SorterSpillObserver>,
+}
+
+impl fmt::Debug for GrenadParameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let GrenadParameters {
+            chunk_compression_type,
+            chunk_compression_level,
+            max_memory,
+            max_nb_chunks,
+            max_word_pairs_per_document,
+            max_sorter_entries,
+            sorter_spill_observer,
+        } = self;
+        f.debug_struct("GrenadParameters")
+            .field("chunk_compression_type", chunk_compression_type)
+            .field("chunk_compression_level", chunk_compression_level)
+            .field("max_memory", max_memory)
+            .field("max_nb_chunks", max_nb_chunks)
+            .field("max_word_pairs_per_document", max_word_pairs_per_document)
+            .field("max_sorter_entries", max_sorter_entries)
+            .field("sorter_spill_observer", &sorter_spill_observer.as_ref().map(|_| "Fn"))
+            .finish()
+    }
 }
 
 impl Default for GrenadParameters {
@@ -110,11 +449,53 @@ impl Default for GrenadParameters {
             chunk_compression_level: None,
             max_memory: None,
             max_nb_chunks: None,
+            max_word_pairs_per_document: None,
+            max_sorter_entries: None,
+            sorter_spill_observer: None,
         }
     }
 }
 
 impl GrenadParameters {
+    /// Creates the default parameters with the given chunk compression,
+    /// returning an error if the compression level doesn't suit the compression type.
+    pub fn with_compression(
+        chunk_compression_type: CompressionType,
+        chunk_compression_level: Option<u32>,
+    ) -> Result<Self> {
+        let parameters =
+            Self { chunk_compression_type, chunk_compression_level, ..Default::default() };
+        parameters.validate()?;
+        Ok(parameters)
+    }
+
+    /// Returns an error if the chunk compression level is out of the range supported by
+    /// the chunk compression type, or if the compression type doesn't use a level at all.
+    pub fn validate(&self) -> Result<()> {
+        let Some(level) = self.chunk_compression_level else { return Ok(()) };
+        let compression_type = self.chunk_compression_type;
+        let (min, max) = match compression_type {
+            CompressionType::Zlib => (0, 9),
+            // zero selects the default level of zstd
+            CompressionType::Zstd => (0, 22),
+            CompressionType::None
+            | CompressionType::Snappy
+            | CompressionType::SnappyPre05
+            | CompressionType::Lz4 => {
+                return Err(UserError::ChunkCompressionLevelUnsupported {
+                    compression_type,
+                    level,
+                })?
+            }
+        };
+
+        if (min..=max).contains(&level) {
+            Ok(())
+        } else {
+            Err(UserError::InvalidChunkCompressionLevel { compression_type, level, min, max })?
+        }
+    }
+
     /// This function use the number of threads in the current threadpool to compute the value.
     ///
     /// This should be called inside of a rayon thread pool,
@@ -211,3 +592,45 @@ where
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use grenad::CompressionType;
+
+    use super::GrenadParameters;
+
+    #[test]
+    fn compression_level_validation() {
+        for (compression_type, level) in [
+            (CompressionType::None, None),
+            (CompressionType::Snappy, None),
+            (CompressionType::Lz4, None),
+            (CompressionType::Zlib, None),
+            (CompressionType::Zlib, Some(0)),
+            (CompressionType::Zlib, Some(9)),
+            (CompressionType::Zstd, Some(0)),
+            (CompressionType::Zstd, Some(22)),
+        ] {
+            GrenadParameters::with_compression(compression_type, level).unwrap();
+        }
+
+        let error =
+            GrenadParameters::with_compression(CompressionType::Zlib, Some(10)).unwrap_err();
+        insta::assert_snapshot!(error, @"The `Zlib` chunk compression level must be between `0` and `9` but `10` was given.");
+        let error =
+            GrenadParameters::with_compression(CompressionType::Zstd, Some(23)).unwrap_err();
+        insta::assert_snapshot!(error, @"The `Zstd` chunk compression level must be between `0` and `22` but `23` was given.");
+        let error = GrenadParameters::with_compression(CompressionType::Lz4, Some(1)).unwrap_err();
+        insta::assert_snapshot!(error, @"The `Lz4` chunk compression doesn't use a compression level but the level `1` was given.");
+        let error = GrenadParameters::with_compression(CompressionType::None, Some(0)).unwrap_err();
+        insta::assert_snapshot!(error, @"The `None` chunk compression doesn't use a compression level but the level `0` was given.");
+
+        // the parameters built field by field are validated the same way
+        let parameters = GrenadParameters {
+            chunk_compression_type: CompressionType::Snappy,
+            chunk_compression_level: Some(3),
+            ..Default::default()
+        };
+        parameters.validate().unwrap_err();
+    }
+}
@@ -208,6 +208,7 @@ impl MergeFunction for MergeCboRoaringBitmaps {
 
 /// Do a union of CboRoaringBitmaps on both sides of a DelAdd obkv
 /// separately and outputs a new DelAdd with both unions.
+#[derive(Debug, Clone, Copy)]
 pub struct MergeDeladdCboRoaringBitmaps;
 
 impl MergeFunction for MergeDeladdCboRoaringBitmaps {
@@ -298,6 +299,38 @@ impl MergeFunction for MergeDeladdBtreesetString {
     }
 }
 
+/// Sums the big-endian `u64` counts on both sides of a DelAdd obkv
+/// separately and outputs a new DelAdd with both sums.
+pub struct MergeDeladdCounts;
+
+impl MergeFunction for MergeDeladdCounts {
+    type Error = crate::Error;
+
+    fn merge<'a>(&self, _key: &[u8], values: &[Cow<'a, [u8]>]) -> Result<Cow<'a, [u8]>> {
+        if values.len() == 1 {
+            Ok(values[0].clone())
+        } else {
+            let mut deletions = 0u64;
+            let mut additions = 0u64;
+            for value in values {
+                let obkv = KvReaderDelAdd::from_slice(value);
+                let count = |bytes: &[u8]| u64::from_be_bytes(bytes.try_into().unwrap());
+                deletions += obkv.get(DelAdd::Deletion).map_or(0, count);
+                additions += obkv.get(DelAdd::Addition).map_or(0, count);
+            }
+
+            let mut output_deladd_obkv = KvWriterDelAdd::memory();
+            if deletions != 0 {
+                output_deladd_obkv.insert(DelAdd::Deletion, deletions.to_be_bytes())?;
+            }
+            if additions != 0 {
+                output_deladd_obkv.insert(DelAdd::Addition, additions.to_be_bytes())?;
+            }
+            output_deladd_obkv.into_inner().map(Cow::from).map_err(Into::into)
+        }
+    }
+}
+
 /// Used when trying to merge readers, but you don't actually care about the values.
 pub struct MergeIgnoreValues;
 
@@ -24,9 +24,10 @@ use tracing::debug;
 use typed_chunk::{write_typed_chunk_into_index, ChunkAccumulator, TypedChunk};
 
 pub use self::enrich::{extract_finite_float_from_value, DocumentId};
+pub use self::extract::build_word_docids_reader;
 pub use self::helpers::*;
 pub use self::transform::{Transform, TransformOutput};
-use super::facet::clear_facet_levels_based_on_settings_diff;
+use super::facet::{clear_facet_levels_based_on_settings_diff, warn_facet_cardinalities};
 use super::new::StdResult;
 use crate::database_stats::DatabaseStats;
 use crate::documents::{obkv_to_object, DocumentsBatchReader};
@@ -261,7 +262,11 @@ where
             chunk_compression_level: self.indexer_config.chunk_compression_level,
             max_memory: self.indexer_config.max_memory,
             max_nb_chunks: self.indexer_config.max_nb_chunks, // default value, may be chosen.
+            max_word_pairs_per_document: self.indexer_config.max_word_pairs_per_document,
+            max_sorter_entries: self.indexer_config.max_sorter_entries,
+            sorter_spill_observer: self.indexer_config.sorter_spill_observer.clone(),
         };
+        pool_params.validate()?;
         let documents_chunk_size = match self.indexer_config.documents_chunk_size {
             Some(chunk_size) => chunk_size,
             None => {
@@ -319,7 +324,7 @@ where
                     // split obkv file into several chunks
                     let original_chunk_iter = match original_documents {
                         Some(original_documents) => {
-                            grenad_obkv_into_chunks(original_documents,pool_params,documents_chunk_size).map(either::Left)
+                            grenad_obkv_into_chunks(original_documents, pool_params.clone(), documents_chunk_size).map(either::Left)
                         },
                         None => Ok(either::Right(iter::empty())),
                     };
@@ -327,7 +332,7 @@ where
                     // split obkv file into several chunks
                     let flattened_chunk_iter = match flattened_documents {
                         Some(flattened_documents) => {
-                            grenad_obkv_into_chunks(flattened_documents, pool_params, documents_chunk_size).map(either::Left)
+                            grenad_obkv_into_chunks(flattened_documents, pool_params.clone(), documents_chunk_size).map(either::Left)
                         },
                         None => Ok(either::Right(iter::empty())),
                     };
@@ -400,6 +405,7 @@ where
                                     word_docids_reader,
                                     exact_word_docids_reader,
                                     word_fid_docids_reader,
+                                    word_frequency_reader,
                                 } => {
                                     let cloneable_chunk =
                                         unsafe { as_cloneable_grenad(&word_docids_reader)? };
@@ -426,6 +432,7 @@ where
                                         word_docids_reader,
                                         exact_word_docids_reader,
                                         word_fid_docids_reader,
+                                        word_frequency_reader,
                                     }
                                 }
                                 TypedChunk::WordPositionDocids(chunk) => {
@@ -479,6 +486,7 @@ where
             // Update the stats of the documents database when there is a document update.
             let stats = DatabaseStats::new(self.index.documents.remap_data_type(), self.wtxn)?;
             self.index.put_documents_stats(self.wtxn, stats)?;
+            self.index.put_documents_version(self.wtxn, &modified_docids)?;
         }
         // We write the field distribution into the main database
         self.index.put_field_distribution(self.wtxn, &field_distribution)?;
@@ -535,6 +543,8 @@ where
             .map_err(InternalError::from)??;
         }
 
+        warn_facet_cardinalities(self.wtxn, self.index, |_| true)?;
+
         self.execute_prefix_databases(
             word_docids.map(MergerBuilder::build),
             exact_word_docids.map(MergerBuilder::build),
@@ -2781,6 +2791,11 @@ mod tests {
                         response: Setting::NotSet,
                         distribution: Setting::NotSet,
                         headers: Setting::NotSet,
+                        batch_encoding: Setting::NotSet,
+                        preprocess: Setting::NotSet,
+                        distance: Setting::NotSet,
+                        search_prompt_prefix: Setting::NotSet,
+                        index_prompt_prefix: Setting::NotSet,
                         search_embedder: Setting::NotSet,
                         indexing_embedder: Setting::NotSet,
                         binary_quantized: Setting::NotSet,
@@ -3577,4 +3592,43 @@ mod tests {
         let crate::SearchResult { documents_ids, .. } = s.execute().unwrap();
         insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0]");
     }
+
+    #[test]
+    fn words_fst_forgets_the_words_of_removed_searchable_fields() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key(S("id"));
+                settings.set_searchable_fields(vec![S("title"), S("tag")]);
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "hello world", "tag": "rust" },
+                { "id": 1, "title": "hello", "tag": "world" }
+            ]))
+            .unwrap();
+
+        let txn = index.read_txn().unwrap();
+        let words = index.words_fst(&txn).unwrap().into_stream().into_strs().unwrap();
+        insta::assert_snapshot!(format!("{words:?}"), @r###"["hello", "rust", "world"]"###);
+        drop(txn);
+
+        // the settings update reindexes the words with the frequencies of the old pipeline
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title")]);
+            })
+            .unwrap();
+
+        db_snap!(index, word_docids, @r###"
+        hello            [0, 1, ]
+        world            [0, ]
+        "###);
+        let txn = index.read_txn().unwrap();
+        let words = index.words_fst(&txn).unwrap().into_stream().into_strs().unwrap();
+        insta::assert_snapshot!(format!("{words:?}"), @r###"["hello", "world"]"###);
+    }
 }
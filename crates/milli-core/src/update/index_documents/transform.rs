@@ -670,11 +670,11 @@ impl<'a, 'i> Transform<'a, 'i> {
         // Always keep the primary key.
         let is_primary_key = |id: FieldId| -> bool { settings_diff.primary_key_id == Some(id) };
 
-        // If only a faceted field has been added, keep only this field.
-        let facet_fids_changed = settings_diff.facet_fids_changed();
+        // If only the faceted fields or the global facet settings changed, keep only the faceted fields.
+        let reindex_facets = settings_diff.reindex_facets();
 
         let necessary_faceted_field = |id: FieldId| -> Option<DelAddOperation> {
-            if facet_fids_changed {
+            if reindex_facets {
                 let field_name = settings_diff.new.fields_ids_map.name(id).unwrap();
                 // if the faceted fields changed, we need to keep all the field that are
                 // faceted in the old or new settings.
@@ -944,11 +944,16 @@ impl<'a, 'i> Transform<'a, 'i> {
             chunk_compression_level: self.indexer_settings.chunk_compression_level,
             max_memory: self.indexer_settings.max_memory,
             max_nb_chunks: self.indexer_settings.max_nb_chunks, // default value, may be chosen.
+            max_word_pairs_per_document: self.indexer_settings.max_word_pairs_per_document,
+            max_sorter_entries: self.indexer_settings.max_sorter_entries,
+            sorter_spill_observer: self.indexer_settings.sorter_spill_observer.clone(),
         };
 
         // Once we have written all the documents, we merge everything into a Reader.
         let flattened_documents = match flattened_sorter {
-            Some(flattened_sorter) => Some(sorter_into_reader(flattened_sorter, grenad_params)?),
+            Some(flattened_sorter) => {
+                Some(sorter_into_reader(flattened_sorter, grenad_params.clone())?)
+            }
             None => None,
         };
         let original_documents = match original_sorter {
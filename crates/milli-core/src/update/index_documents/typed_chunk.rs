@@ -5,7 +5,7 @@ use std::io::{self, BufReader};
 
 use bytemuck::allocation::pod_collect_to_vec;
 use grenad::{MergeFunction, Merger, MergerBuilder};
-use heed::types::Bytes;
+use heed::types::{Bytes, DecodeIgnore};
 use heed::{BytesDecode, RwTxn};
 use obkv::{KvReader, KvWriter};
 use roaring::RoaringBitmap;
@@ -13,7 +13,7 @@ use roaring::RoaringBitmap;
 use super::helpers::{
     self, merge_deladd_cbo_roaring_bitmaps_into_cbo_roaring_bitmap, valid_lmdb_key,
     CursorClonableMmap, KeepFirst, MergeDeladdBtreesetString, MergeDeladdCboRoaringBitmaps,
-    MergeIgnoreValues,
+    MergeDeladdCounts, MergeIgnoreValues,
 };
 use crate::external_documents_ids::{DocumentOperation, DocumentOperationKind};
 use crate::facet::FacetType;
@@ -75,14 +75,21 @@ pub(crate) enum TypedChunk {
         word_docids_reader: grenad::Reader<BufReader<File>>,
         exact_word_docids_reader: grenad::Reader<BufReader<File>>,
         word_fid_docids_reader: grenad::Reader<BufReader<File>>,
+        word_frequency_reader: grenad::Reader<BufReader<File>>,
     },
     WordPositionDocids(grenad::Reader<BufReader<File>>),
     WordPairProximityDocids(grenad::Reader<BufReader<File>>),
+    WordTripleDocids(grenad::Reader<BufReader<File>>),
+    WordPairFrequencyDocids(grenad::Reader<BufReader<File>>),
     FieldIdFacetStringDocids((grenad::Reader<BufReader<File>>, grenad::Reader<BufReader<File>>)),
     FieldIdFacetNumberDocids(grenad::Reader<BufReader<File>>),
     FieldIdFacetExistsDocids(grenad::Reader<BufReader<File>>),
     FieldIdFacetIsNullDocids(grenad::Reader<BufReader<File>>),
     FieldIdFacetIsEmptyDocids(grenad::Reader<BufReader<File>>),
+    FieldIdFacetI64Docids(grenad::Reader<BufReader<File>>),
+    FieldIdFacetIsDateDocids(grenad::Reader<BufReader<File>>),
+    FieldIdFacetIsTrueDocids(grenad::Reader<BufReader<File>>),
+    FieldIdFacetIsFalseDocids(grenad::Reader<BufReader<File>>),
     GeoPoints(grenad::Reader<BufReader<File>>),
     VectorPoints {
         remove_vectors: grenad::Reader<BufReader<File>>,
@@ -106,11 +113,17 @@ impl TypedChunk {
             | (WordDocids { .. }, WordDocids { .. })
             | (WordPositionDocids(_), WordPositionDocids(_))
             | (WordPairProximityDocids(_), WordPairProximityDocids(_))
+            | (WordTripleDocids(_), WordTripleDocids(_))
+            | (WordPairFrequencyDocids(_), WordPairFrequencyDocids(_))
             | (FieldIdFacetStringDocids(_), FieldIdFacetStringDocids(_))
             | (FieldIdFacetNumberDocids(_), FieldIdFacetNumberDocids(_))
             | (FieldIdFacetExistsDocids(_), FieldIdFacetExistsDocids(_))
             | (FieldIdFacetIsNullDocids(_), FieldIdFacetIsNullDocids(_))
             | (FieldIdFacetIsEmptyDocids(_), FieldIdFacetIsEmptyDocids(_))
+            | (FieldIdFacetI64Docids(_), FieldIdFacetI64Docids(_))
+            | (FieldIdFacetIsDateDocids(_), FieldIdFacetIsDateDocids(_))
+            | (FieldIdFacetIsTrueDocids(_), FieldIdFacetIsTrueDocids(_))
+            | (FieldIdFacetIsFalseDocids(_), FieldIdFacetIsFalseDocids(_))
             | (GeoPoints(_), GeoPoints(_)) => true,
             (
                 VectorPoints { embedder_name: left, expected_dimension: left_dim, .. },
@@ -263,11 +276,13 @@ pub(crate) fn write_typed_chunk_into_index(
             let mut exact_word_docids_builder = MergerBuilder::new(MergeDeladdCboRoaringBitmaps);
             let mut word_fid_docids_builder = MergerBuilder::new(MergeDeladdCboRoaringBitmaps);
             let mut fst_merger_builder = MergerBuilder::new(MergeIgnoreValues);
+            let mut word_frequency_builder = MergerBuilder::new(MergeDeladdCounts);
             for typed_chunk in typed_chunks {
                 let TypedChunk::WordDocids {
                     word_docids_reader,
                     exact_word_docids_reader,
                     word_fid_docids_reader,
+                    word_frequency_reader,
                 } = typed_chunk
                 else {
                     unreachable!();
@@ -278,6 +293,7 @@ pub(crate) fn write_typed_chunk_into_index(
                 exact_word_docids_builder.push(exact_word_docids_reader.into_cursor()?);
                 word_fid_docids_builder.push(word_fid_docids_reader.into_cursor()?);
                 fst_merger_builder.push(clonable_word_docids.into_cursor()?);
+                word_frequency_builder.push(word_frequency_reader.into_cursor()?);
             }
 
             let word_docids_merger = word_docids_builder.build();
@@ -310,6 +326,8 @@ pub(crate) fn write_typed_chunk_into_index(
             // create fst from word docids
             let fst_merger = fst_merger_builder.build();
             let fst = merge_word_docids_reader_into_fst(fst_merger)?;
+            let word_frequency_merger = word_frequency_builder.build();
+            let removed_words = removed_words_into_fst(word_frequency_merger, index, wtxn)?;
             let db_fst = index.words_fst(wtxn)?;
 
             // merge new fst with database fst
@@ -317,6 +335,12 @@ pub(crate) fn write_typed_chunk_into_index(
             let mut builder = fst::SetBuilder::memory();
             builder.extend_stream(union_stream)?;
             let fst = builder.into_set();
+
+            // remove the words that are no longer in any document
+            let difference_stream = fst.op().add(removed_words.stream()).difference();
+            let mut builder = fst::SetBuilder::memory();
+            builder.extend_stream(difference_stream)?;
+            let fst = builder.into_set();
             index.put_words_fst(wtxn, &fst)?;
             is_merged_database = true;
         }
@@ -472,6 +496,101 @@ pub(crate) fn write_typed_chunk_into_index(
             )?;
             is_merged_database = true;
         }
+        TypedChunk::FieldIdFacetIsDateDocids(_) => {
+            let span =
+                tracing::trace_span!(target: "indexing::write_db", "field_id_facet_is_date_docids");
+            let _entered = span.enter();
+
+            let mut builder = MergerBuilder::new(MergeDeladdCboRoaringBitmaps);
+            for typed_chunk in typed_chunks {
+                let TypedChunk::FieldIdFacetIsDateDocids(chunk) = typed_chunk else {
+                    unreachable!();
+                };
+
+                builder.push(chunk.into_cursor()?);
+            }
+            let merger = builder.build();
+
+            write_entries_into_database(
+                merger,
+                &index.facet_id_is_date_docids,
+                wtxn,
+                deladd_serialize_add_side,
+                merge_deladd_cbo_roaring_bitmaps_into_cbo_roaring_bitmap,
+            )?;
+            is_merged_database = true;
+        }
+        TypedChunk::FieldIdFacetIsTrueDocids(_) => {
+            let span =
+                tracing::trace_span!(target: "indexing::write_db", "field_id_facet_is_true_docids");
+            let _entered = span.enter();
+
+            let mut builder = MergerBuilder::new(MergeDeladdCboRoaringBitmaps);
+            for typed_chunk in typed_chunks {
+                let TypedChunk::FieldIdFacetIsTrueDocids(chunk) = typed_chunk else {
+                    unreachable!();
+                };
+
+                builder.push(chunk.into_cursor()?);
+            }
+            let merger = builder.build();
+
+            write_entries_into_database(
+                merger,
+                &index.facet_id_is_true_docids,
+                wtxn,
+                deladd_serialize_add_side,
+                merge_deladd_cbo_roaring_bitmaps_into_cbo_roaring_bitmap,
+            )?;
+            is_merged_database = true;
+        }
+        TypedChunk::FieldIdFacetIsFalseDocids(_) => {
+            let span = tracing::trace_span!(target: "indexing::write_db", "field_id_facet_is_false_docids");
+            let _entered = span.enter();
+
+            let mut builder = MergerBuilder::new(MergeDeladdCboRoaringBitmaps);
+            for typed_chunk in typed_chunks {
+                let TypedChunk::FieldIdFacetIsFalseDocids(chunk) = typed_chunk else {
+                    unreachable!();
+                };
+
+                builder.push(chunk.into_cursor()?);
+            }
+            let merger = builder.build();
+
+            write_entries_into_database(
+                merger,
+                &index.facet_id_is_false_docids,
+                wtxn,
+                deladd_serialize_add_side,
+                merge_deladd_cbo_roaring_bitmaps_into_cbo_roaring_bitmap,
+            )?;
+            is_merged_database = true;
+        }
+        TypedChunk::FieldIdFacetI64Docids(_) => {
+            let span =
+                tracing::trace_span!(target: "indexing::write_db", "field_id_facet_i64_docids");
+            let _entered = span.enter();
+
+            let mut builder = MergerBuilder::new(MergeDeladdCboRoaringBitmaps);
+            for typed_chunk in typed_chunks {
+                let TypedChunk::FieldIdFacetI64Docids(chunk) = typed_chunk else {
+                    unreachable!();
+                };
+
+                builder.push(chunk.into_cursor()?);
+            }
+            let merger = builder.build();
+
+            write_entries_into_database(
+                merger,
+                &index.facet_id_i64_docids,
+                wtxn,
+                deladd_serialize_add_side,
+                merge_deladd_cbo_roaring_bitmaps_into_cbo_roaring_bitmap,
+            )?;
+            is_merged_database = true;
+        }
         TypedChunk::WordPairProximityDocids(_) => {
             let span =
                 tracing::trace_span!(target: "indexing::write_db", "word_pair_proximity_docids");
@@ -505,6 +624,53 @@ pub(crate) fn write_typed_chunk_into_index(
 
             is_merged_database = true;
         }
+        TypedChunk::WordTripleDocids(_) => {
+            let span = tracing::trace_span!(target: "indexing::write_db", "word_triple_docids");
+            let _entered = span.enter();
+
+            let mut builder = MergerBuilder::new(MergeDeladdCboRoaringBitmaps);
+            for typed_chunk in typed_chunks {
+                let TypedChunk::WordTripleDocids(chunk) = typed_chunk else {
+                    unreachable!();
+                };
+
+                builder.push(chunk.into_cursor()?);
+            }
+            let merger = builder.build();
+
+            write_entries_into_database(
+                merger,
+                &index.word_triple_docids,
+                wtxn,
+                deladd_serialize_add_side,
+                merge_deladd_cbo_roaring_bitmaps_into_cbo_roaring_bitmap,
+            )?;
+            is_merged_database = true;
+        }
+        TypedChunk::WordPairFrequencyDocids(_) => {
+            let span =
+                tracing::trace_span!(target: "indexing::write_db", "word_pair_frequency_docids");
+            let _entered = span.enter();
+
+            let mut builder = MergerBuilder::new(MergeDeladdCboRoaringBitmaps);
+            for typed_chunk in typed_chunks {
+                let TypedChunk::WordPairFrequencyDocids(chunk) = typed_chunk else {
+                    unreachable!();
+                };
+
+                builder.push(chunk.into_cursor()?);
+            }
+            let merger = builder.build();
+
+            write_entries_into_database(
+                merger,
+                &index.word_pair_frequency_docids,
+                wtxn,
+                deladd_serialize_add_side,
+                merge_deladd_cbo_roaring_bitmaps_into_cbo_roaring_bitmap,
+            )?;
+            is_merged_database = true;
+        }
         TypedChunk::FieldIdDocidFacetNumbers(_) => {
             let span =
                 tracing::trace_span!(target: "indexing::write_db", "field_id_docid_facet_numbers");
@@ -758,6 +924,31 @@ where
     Ok(builder.into_set())
 }
 
+/// Returns the words whose number of documents only decreased
+/// and which are no longer in the word docids database.
+fn removed_words_into_fst<MF>(
+    word_frequency_merger: Merger<BufReader<File>, MF>,
+    index: &Index,
+    wtxn: &RwTxn<'_>,
+) -> Result<fst::Set<Vec<u8>>>
+where
+    MF: MergeFunction,
+    crate::Error: From<MF::Error>,
+{
+    let word_docids = index.word_docids.remap_types::<Bytes, DecodeIgnore>();
+    let mut iter = word_frequency_merger.into_stream_merger_iter()?;
+    let mut builder = fst::SetBuilder::memory();
+
+    while let Some((word, frequency)) = iter.next()? {
+        let frequency = KvReaderDelAdd::from_slice(frequency);
+        if frequency.get(DelAdd::Addition).is_none() && word_docids.get(wtxn, word)?.is_none() {
+            builder.insert(word)?;
+        }
+    }
+
+    Ok(builder.into_set())
+}
+
 /// Write provided entries in database using serialize_value function.
 /// merge_values function is used if an entry already exist in the database.
 #[tracing::instrument(level = "trace", skip_all, target = "indexing::write_db")]
@@ -784,7 +975,9 @@ where
             buffer.clear();
             let value = match database.get(wtxn, key)? {
                 Some(prev_value) => merge_values(value, prev_value, &mut buffer)?,
-                None => Some(serialize_value(value, &mut buffer)?),
+                // a deletion of an entry missing from the database has nothing to remove,
+                // it must not be written as an empty entry
+                None => Some(serialize_value(value, &mut buffer)?).filter(|v| !v.is_empty()),
             };
             match value {
                 Some(value) => database.put(wtxn, key, value)?,
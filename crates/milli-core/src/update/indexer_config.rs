@@ -1,9 +1,10 @@
+use std::fmt;
+
 use grenad::CompressionType;
 
-use super::GrenadParameters;
+use super::{GrenadParameters, SorterSpillObserver};
 use crate::thread_pool_no_abort::ThreadPoolNoAbort;
 
-#[derive(Debug)]
 pub struct IndexerConfig {
     pub log_every_n: Option<usize>,
     pub max_nb_chunks: Option<usize>,
@@ -14,6 +15,9 @@ pub struct IndexerConfig {
     pub thread_pool: Option<ThreadPoolNoAbort>,
     pub max_positions_per_attributes: Option<u32>,
     pub skip_index_budget: bool,
+    pub max_word_pairs_per_document: Option<usize>,
+    pub max_sorter_entries: Option<usize>,
+    pub sorter_spill_observer: Option<SorterSpillObserver>,
 }
 
 impl IndexerConfig {
@@ -23,10 +27,46 @@ impl IndexerConfig {
             chunk_compression_level: self.chunk_compression_level,
             max_memory: self.max_memory,
             max_nb_chunks: self.max_nb_chunks,
+            max_word_pairs_per_document: self.max_word_pairs_per_document,
+            max_sorter_entries: self.max_sorter_entries,
+            sorter_spill_observer: self.sorter_spill_observer.clone(),
         }
     }
 }
 
+impl fmt::Debug for IndexerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let IndexerConfig {
+            log_every_n,
+            max_nb_chunks,
+            documents_chunk_size,
+            max_memory,
+            chunk_compression_type,
+            chunk_compression_level,
+            thread_pool,
+            max_positions_per_attributes,
+            skip_index_budget,
+            max_word_pairs_per_document,
+            max_sorter_entries,
+            sorter_spill_observer,
+        } = self;
+        f.debug_struct("IndexerConfig")
+            .field("log_every_n", log_every_n)
+            .field("max_nb_chunks", max_nb_chunks)
+            .field("documents_chunk_size", documents_chunk_size)
+            .field("max_memory", max_memory)
+            .field("chunk_compression_type", chunk_compression_type)
+            .field("chunk_compression_level", chunk_compression_level)
+            .field("thread_pool", thread_pool)
+            .field("max_positions_per_attributes", max_positions_per_attributes)
+            .field("skip_index_budget", skip_index_budget)
+            .field("max_word_pairs_per_document", max_word_pairs_per_document)
+            .field("max_sorter_entries", max_sorter_entries)
+            .field("sorter_spill_observer", &sorter_spill_observer.as_ref().map(|_| "Fn"))
+            .finish()
+    }
+}
+
 impl Default for IndexerConfig {
     fn default() -> Self {
         Self {
@@ -39,6 +79,9 @@ impl Default for IndexerConfig {
             thread_pool: None,
             max_positions_per_attributes: None,
             skip_index_budget: false,
+            max_word_pairs_per_document: None,
+            max_sorter_entries: None,
+            sorter_spill_observer: None,
         }
     }
 }
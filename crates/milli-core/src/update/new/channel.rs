@@ -390,11 +390,17 @@ pub enum Database {
     WordDocids,
     WordFidDocids,
     WordPairProximityDocids,
+    WordTripleDocids,
+    WordPairFrequencyDocids,
     WordPositionDocids,
     FacetIdIsNullDocids,
     FacetIdIsEmptyDocids,
     FacetIdExistsDocids,
     FacetIdF64Docids,
+    FacetIdI64Docids,
+    FacetIdIsDateDocids,
+    FacetIdIsTrueDocids,
+    FacetIdIsFalseDocids,
     FacetIdStringDocids,
     FieldIdDocidFacetStrings,
     FieldIdDocidFacetF64s,
@@ -412,10 +418,16 @@ impl Database {
             Database::WordPositionDocids => index.word_position_docids.remap_types(),
             Database::FidWordCountDocids => index.field_id_word_count_docids.remap_types(),
             Database::WordPairProximityDocids => index.word_pair_proximity_docids.remap_types(),
+            Database::WordTripleDocids => index.word_triple_docids.remap_types(),
+            Database::WordPairFrequencyDocids => index.word_pair_frequency_docids.remap_types(),
             Database::FacetIdIsNullDocids => index.facet_id_is_null_docids.remap_types(),
             Database::FacetIdIsEmptyDocids => index.facet_id_is_empty_docids.remap_types(),
             Database::FacetIdExistsDocids => index.facet_id_exists_docids.remap_types(),
             Database::FacetIdF64Docids => index.facet_id_f64_docids.remap_types(),
+            Database::FacetIdI64Docids => index.facet_id_i64_docids.remap_types(),
+            Database::FacetIdIsDateDocids => index.facet_id_is_date_docids.remap_types(),
+            Database::FacetIdIsTrueDocids => index.facet_id_is_true_docids.remap_types(),
+            Database::FacetIdIsFalseDocids => index.facet_id_is_false_docids.remap_types(),
             Database::FacetIdStringDocids => index.facet_id_string_docids.remap_types(),
             Database::FieldIdDocidFacetStrings => index.field_id_docid_facet_strings.remap_types(),
             Database::FieldIdDocidFacetF64s => index.field_id_docid_facet_f64s.remap_types(),
@@ -433,10 +445,16 @@ impl Database {
             Database::WordPositionDocids => db_name::WORD_POSITION_DOCIDS,
             Database::FidWordCountDocids => db_name::FIELD_ID_WORD_COUNT_DOCIDS,
             Database::WordPairProximityDocids => db_name::WORD_PAIR_PROXIMITY_DOCIDS,
+            Database::WordTripleDocids => db_name::WORD_TRIPLE_DOCIDS,
+            Database::WordPairFrequencyDocids => db_name::WORD_PAIR_FREQUENCY_DOCIDS,
             Database::FacetIdIsNullDocids => db_name::FACET_ID_IS_NULL_DOCIDS,
             Database::FacetIdIsEmptyDocids => db_name::FACET_ID_IS_EMPTY_DOCIDS,
             Database::FacetIdExistsDocids => db_name::FACET_ID_EXISTS_DOCIDS,
             Database::FacetIdF64Docids => db_name::FACET_ID_F64_DOCIDS,
+            Database::FacetIdI64Docids => db_name::FACET_ID_I64_DOCIDS,
+            Database::FacetIdIsDateDocids => db_name::FACET_ID_IS_DATE_DOCIDS,
+            Database::FacetIdIsTrueDocids => db_name::FACET_ID_IS_TRUE_DOCIDS,
+            Database::FacetIdIsFalseDocids => db_name::FACET_ID_IS_FALSE_DOCIDS,
             Database::FacetIdStringDocids => db_name::FACET_ID_STRING_DOCIDS,
             Database::FieldIdDocidFacetStrings => db_name::FIELD_ID_DOCID_FACET_STRINGS,
             Database::FieldIdDocidFacetF64s => db_name::FIELD_ID_DOCID_FACET_F64S,
@@ -452,6 +470,10 @@ impl From<FacetKind> for Database {
             FacetKind::Null => Database::FacetIdIsNullDocids,
             FacetKind::Empty => Database::FacetIdIsEmptyDocids,
             FacetKind::Exists => Database::FacetIdExistsDocids,
+            FacetKind::Integer => Database::FacetIdI64Docids,
+            FacetKind::Date => Database::FacetIdIsDateDocids,
+            FacetKind::True => Database::FacetIdIsTrueDocids,
+            FacetKind::False => Database::FacetIdIsFalseDocids,
         }
     }
 }
@@ -752,6 +774,8 @@ pub enum FidWordCountDocids {}
 pub enum WordDocids {}
 pub enum WordFidDocids {}
 pub enum WordPairProximityDocids {}
+pub enum WordTripleDocids {}
+pub enum WordPairFrequencyDocids {}
 pub enum WordPositionDocids {}
 
 pub trait DatabaseType {
@@ -778,6 +802,14 @@ impl DatabaseType for WordPairProximityDocids {
     const DATABASE: Database = Database::WordPairProximityDocids;
 }
 
+impl DatabaseType for WordTripleDocids {
+    const DATABASE: Database = Database::WordTripleDocids;
+}
+
+impl DatabaseType for WordPairFrequencyDocids {
+    const DATABASE: Database = Database::WordPairFrequencyDocids;
+}
+
 impl DatabaseType for WordPositionDocids {
     const DATABASE: Database = Database::WordPositionDocids;
 }
@@ -831,7 +863,13 @@ impl FacetDocidsSender<'_, '_> {
             // We must take the facet group size into account
             // when we serialize strings and numbers.
             FacetKind::Number | FacetKind::String => value_length + 1,
-            FacetKind::Null | FacetKind::Empty | FacetKind::Exists => value_length,
+            FacetKind::Null
+            | FacetKind::Empty
+            | FacetKind::Exists
+            | FacetKind::Integer
+            | FacetKind::Date
+            | FacetKind::True
+            | FacetKind::False => value_length,
         };
         let key_length = key.len().try_into().ok().and_then(NonZeroU16::new).ok_or_else(|| {
             InternalError::StorePut {
@@ -857,7 +895,13 @@ impl FacetDocidsSender<'_, '_> {
                         *first = 1;
                         remaining
                     }
-                    FacetKind::Null | FacetKind::Empty | FacetKind::Exists => value_out,
+                    FacetKind::Null
+                    | FacetKind::Empty
+                    | FacetKind::Exists
+                    | FacetKind::Integer
+                    | FacetKind::Date
+                    | FacetKind::True
+                    | FacetKind::False => value_out,
                 };
 
                 CboRoaringBitmapCodec::serialize_into_writer(bitmap, value_out)?;
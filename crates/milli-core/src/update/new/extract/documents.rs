@@ -2,6 +2,7 @@ use std::cell::RefCell;
 
 use bumpalo::Bump;
 use hashbrown::HashMap;
+use roaring::RoaringBitmap;
 
 use super::DelAddRoaringBitmap;
 use crate::constants::RESERVED_GEO_FIELD_NAME;
@@ -28,6 +29,8 @@ impl<'a, 'b> DocumentsExtractor<'a, 'b> {
 #[derive(Default)]
 pub struct DocumentExtractorData {
     pub docids_delta: DelAddRoaringBitmap,
+    /// The documents that were already in the index and are updated.
+    pub updated_docids: RoaringBitmap,
     pub field_distribution_delta: HashMap<String, i64>,
 }
 
@@ -124,6 +127,7 @@ impl<'extractor> Extractor<'extractor> for DocumentsExtractor<'_, '_> {
                         &mut new_fields_ids_map,
                         &mut document_buffer,
                     )?;
+                    document_extractor_data.updated_docids.insert(docid);
                     self.document_sender.uncompressed(docid, external_docid, content).unwrap();
                 }
                 DocumentChange::Insertion(insertion) => {
@@ -4,6 +4,7 @@ use std::ops::DerefMut as _;
 
 use bumpalo::collections::Vec as BVec;
 use bumpalo::Bump;
+use charabia::Language;
 use hashbrown::HashMap;
 use serde_json::Value;
 
@@ -12,7 +13,7 @@ use super::facet_document::extract_document_facets;
 use super::FacetKind;
 use crate::fields_ids_map::metadata::Metadata;
 use crate::filterable_attributes_rules::match_faceted_field;
-use crate::heed_codec::facet::OrderedF64Codec;
+use crate::heed_codec::facet::{FieldIdI64Codec, OrderedF64Codec};
 use crate::update::del_add::DelAdd;
 use crate::update::new::channel::FieldIdDocidFacetSender;
 use crate::update::new::extract::perm_json_p;
@@ -35,6 +36,7 @@ pub struct FacetedExtractorData<'a, 'b> {
     asc_desc_fields: &'a HashSet<String>,
     distinct_field: &'a Option<String>,
     is_geo_enabled: bool,
+    facet_normalization_locale: Option<Language>,
 }
 
 impl<'extractor> Extractor<'extractor> for FacetedExtractorData<'_, '_> {
@@ -62,6 +64,7 @@ impl<'extractor> Extractor<'extractor> for FacetedExtractorData<'_, '_> {
                 self.asc_desc_fields,
                 self.distinct_field,
                 self.is_geo_enabled,
+                self.facet_normalization_locale,
                 change,
                 self.sender,
             )?
@@ -81,6 +84,7 @@ impl FacetedDocidsExtractor {
         asc_desc_fields: &HashSet<String>,
         distinct_field: &Option<String>,
         is_geo_enabled: bool,
+        facet_normalization_locale: Option<Language>,
         document_change: DocumentChange,
         sender: &FieldIdDocidFacetSender,
     ) -> Result<()> {
@@ -88,7 +92,8 @@ impl FacetedDocidsExtractor {
         let rtxn = &context.rtxn;
         let mut new_fields_ids_map = context.new_fields_ids_map.borrow_mut_or_yield();
         let mut cached_sorter = context.data.borrow_mut_or_yield();
-        let mut del_add_facet_value = DelAddFacetValue::new(&context.doc_alloc);
+        let mut del_add_facet_value =
+            DelAddFacetValue::new(&context.doc_alloc, facet_normalization_locale);
         let docid = document_change.docid();
         let res = match document_change {
             DocumentChange::Deletion(inner) => extract_document_facets(
@@ -252,6 +257,32 @@ impl FacetedDocidsExtractor {
             // Number
             // key: fid - level - orderedf64 - originalf64
             Value::Number(number) => {
+                // Integer
+                // key: fid - orderedi64
+                if let Some(integer) = number.as_i64().filter(|_| features.is_integer()) {
+                    buffer.clear();
+                    buffer.push(FacetKind::Integer as u8);
+                    buffer.extend_from_slice(&fid.to_be_bytes());
+                    buffer.extend_from_slice(&FieldIdI64Codec::ordered_bytes(integer));
+                    cache_fn(cached_sorter, &buffer, docid)?;
+                }
+
+                // Number as a string
+                // key: fid - level - number_string
+                if let Some(float) = number.as_f64().filter(|_| features.is_prefixable_numbers()) {
+                    let s = float.to_string();
+                    let mut string = BVec::new_in(doc_alloc);
+                    string.extend_from_slice(s.as_bytes());
+                    facet_fn(del_add_facet_value, fid, string, FacetKind::String);
+
+                    buffer.clear();
+                    buffer.push(FacetKind::String as u8);
+                    buffer.extend_from_slice(&fid.to_be_bytes());
+                    buffer.push(0); // level 0
+                    buffer.extend_from_slice(s.as_bytes());
+                    cache_fn(cached_sorter, &buffer, docid)?;
+                }
+
                 let mut ordered = [0u8; 16];
                 if number
                     .as_f64()
@@ -275,11 +306,20 @@ impl FacetedDocidsExtractor {
             // String
             // key: fid - level - truncated_string
             Value::String(s) if !s.is_empty() => {
+                // Date
+                // key: fid
+                if crate::is_date_facet_value(s) {
+                    buffer.clear();
+                    buffer.push(FacetKind::Date as u8);
+                    buffer.extend_from_slice(&fid.to_be_bytes());
+                    cache_fn(cached_sorter, &buffer, docid)?;
+                }
+
                 let mut string = BVec::new_in(doc_alloc);
                 string.extend_from_slice(s.as_bytes());
                 facet_fn(del_add_facet_value, fid, string, FacetKind::String);
 
-                let normalized = crate::normalize_facet(s);
+                let normalized = crate::normalize_facet_with_locale(s, del_add_facet_value.locale);
                 let truncated = truncate_str(&normalized);
                 buffer.clear();
                 buffer.push(FacetKind::String as u8);
@@ -290,7 +330,14 @@ impl FacetedDocidsExtractor {
             }
             // Bool is handled as a string
             Value::Bool(b) => {
-                let b = if *b { "true" } else { "false" };
+                // True or False
+                // key: fid
+                buffer.clear();
+                buffer.push(if *b { FacetKind::True } else { FacetKind::False } as u8);
+                buffer.extend_from_slice(&fid.to_be_bytes());
+                cache_fn(cached_sorter, &buffer, docid)?;
+
+                let b = crate::boolean_facet_value(*b);
                 let mut string = BVec::new_in(doc_alloc);
                 string.extend_from_slice(b.as_bytes());
                 facet_fn(del_add_facet_value, fid, string, FacetKind::String);
@@ -357,11 +404,17 @@ struct DelAddFacetValue<'doc> {
     >,
     f64s: HashMap<(FieldId, BVec<'doc, u8>), DelAdd, hashbrown::DefaultHashBuilder, &'doc Bump>,
     doc_alloc: &'doc Bump,
+    locale: Option<Language>,
 }
 
 impl<'doc> DelAddFacetValue<'doc> {
-    fn new(doc_alloc: &'doc Bump) -> Self {
-        Self { strings: HashMap::new_in(doc_alloc), f64s: HashMap::new_in(doc_alloc), doc_alloc }
+    fn new(doc_alloc: &'doc Bump, locale: Option<Language>) -> Self {
+        Self {
+            strings: HashMap::new_in(doc_alloc),
+            f64s: HashMap::new_in(doc_alloc),
+            doc_alloc,
+            locale,
+        }
     }
 
     fn insert_add(&mut self, fid: FieldId, value: BVec<'doc, u8>, kind: FacetKind) {
@@ -376,7 +429,7 @@ impl<'doc> DelAddFacetValue<'doc> {
             }
             FacetKind::String => {
                 if let Ok(s) = std::str::from_utf8(&value) {
-                    let normalized = crate::normalize_facet(s);
+                    let normalized = crate::normalize_facet_with_locale(s, self.locale);
                     let truncated = self.doc_alloc.alloc_str(truncate_str(&normalized));
                     self.strings.insert((fid, truncated), Some(value));
                 }
@@ -397,7 +450,7 @@ impl<'doc> DelAddFacetValue<'doc> {
             }
             FacetKind::String => {
                 if let Ok(s) = std::str::from_utf8(&value) {
-                    let normalized = crate::normalize_facet(s);
+                    let normalized = crate::normalize_facet_with_locale(s, self.locale);
                     let truncated = self.doc_alloc.alloc_str(truncate_str(&normalized));
                     self.strings.insert((fid, truncated), None);
                 }
@@ -470,6 +523,7 @@ impl FacetedDocidsExtractor {
         let asc_desc_fields = index.asc_desc_fields(&rtxn)?;
         let distinct_field = index.distinct_field(&rtxn)?.map(|s| s.to_string());
         let is_geo_enabled = index.is_geo_enabled(&rtxn)?;
+        let facet_normalization_locale = index.facet_normalization_locale(&rtxn)?;
         let datastore = ThreadLocal::new();
 
         {
@@ -486,6 +540,7 @@ impl FacetedDocidsExtractor {
                 asc_desc_fields: &asc_desc_fields,
                 distinct_field: &distinct_field,
                 is_geo_enabled,
+                facet_normalization_locale,
             };
             extract(
                 document_changes,
@@ -11,6 +11,10 @@ pub enum FacetKind {
     Null = 2,
     Empty = 3,
     Exists,
+    Integer = 5,
+    Date = 6,
+    True = 7,
+    False = 8,
 }
 
 impl From<u8> for FacetKind {
@@ -21,6 +25,10 @@ impl From<u8> for FacetKind {
             2 => Self::Null,
             3 => Self::Empty,
             4 => Self::Exists,
+            5 => Self::Integer,
+            6 => Self::Date,
+            7 => Self::True,
+            8 => Self::False,
             _ => unreachable!(),
         }
     }
@@ -1,12 +1,12 @@
 use std::cell::RefCell;
-use std::collections::VecDeque;
+use std::collections::{BTreeSet, VecDeque};
 use std::rc::Rc;
 
 use bumpalo::Bump;
 
 use super::match_searchable_field;
 use super::tokenize_document::{tokenizer_builder, DocumentTokenizer};
-use crate::proximity::{index_proximity, MAX_DISTANCE};
+use crate::proximity::{index_proximity, MAX_DISTANCE, MAX_WORD_PAIR_FREQUENCY};
 use crate::update::new::document::Document;
 use crate::update::new::extract::cache::BalancedCaches;
 use crate::update::new::indexer::document_changes::{
@@ -23,6 +23,8 @@ pub struct WordPairProximityDocidsExtractorData<'a> {
     searchable_attributes: Option<Vec<&'a str>>,
     max_memory_by_thread: Option<usize>,
     buckets: usize,
+    record: WindowRecord,
+    excluded_fields: BTreeSet<FieldId>,
 }
 
 impl<'extractor> Extractor<'extractor> for WordPairProximityDocidsExtractorData<'_> {
@@ -47,6 +49,8 @@ impl<'extractor> Extractor<'extractor> for WordPairProximityDocidsExtractorData<
                 context,
                 &self.tokenizer,
                 self.searchable_attributes.as_deref(),
+                self.record,
+                &self.excluded_fields,
                 change,
             )?;
         }
@@ -63,6 +67,74 @@ impl WordPairProximityDocidsExtractor {
         extractor_allocs: &'extractor mut ThreadLocal<FullySend<Bump>>,
         step: IndexingStep,
     ) -> Result<Vec<BalancedCaches<'extractor>>>
+    where
+        MSP: Fn() -> bool + Sync,
+    {
+        Self::run(
+            document_changes,
+            indexing_context,
+            extractor_allocs,
+            step,
+            WindowRecord::PairProximities,
+        )
+    }
+
+    /// Extracts the triples of words following each other, instead of the word pairs proximities.
+    pub fn run_triple_extraction<
+        'pl,
+        'fid,
+        'indexer,
+        'index,
+        'extractor,
+        DC: DocumentChanges<'pl>,
+        MSP,
+    >(
+        document_changes: &DC,
+        indexing_context: IndexingContext<'fid, 'indexer, 'index, MSP>,
+        extractor_allocs: &'extractor mut ThreadLocal<FullySend<Bump>>,
+        step: IndexingStep,
+    ) -> Result<Vec<BalancedCaches<'extractor>>>
+    where
+        MSP: Fn() -> bool + Sync,
+    {
+        Self::run(document_changes, indexing_context, extractor_allocs, step, WindowRecord::Triples)
+    }
+
+    /// Extracts the number of times a word directly follows another one,
+    /// instead of the word pairs proximities.
+    pub fn run_frequency_extraction<
+        'pl,
+        'fid,
+        'indexer,
+        'index,
+        'extractor,
+        DC: DocumentChanges<'pl>,
+        MSP,
+    >(
+        document_changes: &DC,
+        indexing_context: IndexingContext<'fid, 'indexer, 'index, MSP>,
+        extractor_allocs: &'extractor mut ThreadLocal<FullySend<Bump>>,
+        step: IndexingStep,
+    ) -> Result<Vec<BalancedCaches<'extractor>>>
+    where
+        MSP: Fn() -> bool + Sync,
+    {
+        Self::run(
+            document_changes,
+            indexing_context,
+            extractor_allocs,
+            step,
+            WindowRecord::PairFrequencies,
+        )
+    }
+
+    fn run<'pl, 'fid, 'indexer, 'index, 'extractor, DC: DocumentChanges<'pl>, MSP>(
+        document_changes: &DC,
+        indexing_context: IndexingContext<'fid, 'indexer, 'index, MSP>,
+        extractor_allocs: &'extractor mut ThreadLocal<FullySend<Bump>>,
+        step: IndexingStep,
+        record: WindowRecord,
+    ) -> Result<Vec<BalancedCaches<'extractor>>>
     where
         MSP: Fn() -> bool + Sync,
     {
@@ -88,11 +160,20 @@ impl WordPairProximityDocidsExtractor {
             localized_attributes_rules: &localized_attributes_rules,
             max_positions_per_attributes: MAX_POSITION_PER_ATTRIBUTE,
         };
+        // only the word pair proximities skip the excluded fields
+        let excluded_fields = match record {
+            WindowRecord::PairProximities => {
+                indexing_context.index.proximity_excluded_fields(&rtxn)?
+            }
+            WindowRecord::PairFrequencies | WindowRecord::Triples => BTreeSet::new(),
+        };
         let extractor_data = WordPairProximityDocidsExtractorData {
             tokenizer: document_tokenizer,
             searchable_attributes: indexing_context.index.user_defined_searchable_fields(&rtxn)?,
             max_memory_by_thread: indexing_context.grenad_parameters.max_memory_by_thread(),
             buckets: rayon::current_num_threads(),
+            record,
+            excluded_fields,
         };
         let datastore = ThreadLocal::new();
         {
@@ -119,6 +200,8 @@ impl WordPairProximityDocidsExtractor {
         context: &DocumentChangeContext<RefCell<BalancedCaches>>,
         document_tokenizer: &DocumentTokenizer,
         searchable_attributes: Option<&[&str]>,
+        record: WindowRecord,
+        excluded_fields: &BTreeSet<FieldId>,
         document_change: DocumentChange,
     ) -> Result<()> {
         let doc_alloc = &context.doc_alloc;
@@ -127,8 +210,8 @@ impl WordPairProximityDocidsExtractor {
         let rtxn = &context.rtxn;
 
         let mut key_buffer = bumpalo::collections::Vec::new_in(doc_alloc);
-        let mut del_word_pair_proximity = bumpalo::collections::Vec::new_in(doc_alloc);
-        let mut add_word_pair_proximity = bumpalo::collections::Vec::new_in(doc_alloc);
+        let mut del_window_keys = bumpalo::collections::Vec::new_in(doc_alloc);
+        let mut add_window_keys = bumpalo::collections::Vec::new_in(doc_alloc);
 
         let mut new_fields_ids_map = context.new_fields_ids_map.borrow_mut_or_yield();
         let new_fields_ids_map = &mut *new_fields_ids_map;
@@ -149,9 +232,9 @@ impl WordPairProximityDocidsExtractor {
                     document_tokenizer,
                     new_fields_ids_map,
                     &mut word_positions,
-                    &mut |(w1, w2), prox| {
-                        del_word_pair_proximity.push(((w1, w2), prox));
-                    },
+                    record,
+                    excluded_fields,
+                    &mut |key| del_window_keys.push(key),
                 )?;
             }
             DocumentChange::Update(inner) => {
@@ -172,9 +255,9 @@ impl WordPairProximityDocidsExtractor {
                     document_tokenizer,
                     new_fields_ids_map,
                     &mut word_positions,
-                    &mut |(w1, w2), prox| {
-                        del_word_pair_proximity.push(((w1, w2), prox));
-                    },
+                    record,
+                    excluded_fields,
+                    &mut |key| del_window_keys.push(key),
                 )?;
                 let document = inner.merged(rtxn, index, context.db_fields_ids_map)?;
                 process_document_tokens(
@@ -182,9 +265,9 @@ impl WordPairProximityDocidsExtractor {
                     document_tokenizer,
                     new_fields_ids_map,
                     &mut word_positions,
-                    &mut |(w1, w2), prox| {
-                        add_word_pair_proximity.push(((w1, w2), prox));
-                    },
+                    record,
+                    excluded_fields,
+                    &mut |key| add_window_keys.push(key),
                 )?;
             }
             DocumentChange::Insertion(inner) => {
@@ -194,63 +277,162 @@ impl WordPairProximityDocidsExtractor {
                     document_tokenizer,
                     new_fields_ids_map,
                     &mut word_positions,
-                    &mut |(w1, w2), prox| {
-                        add_word_pair_proximity.push(((w1, w2), prox));
-                    },
+                    record,
+                    excluded_fields,
+                    &mut |key| add_window_keys.push(key),
                 )?;
             }
         }
 
-        del_word_pair_proximity.sort_unstable();
-        del_word_pair_proximity.dedup_by(|(k1, _), (k2, _)| k1 == k2);
-        for ((w1, w2), prox) in del_word_pair_proximity.iter() {
-            let key = build_key(*prox, w1, w2, &mut key_buffer);
+        del_window_keys.sort_unstable();
+        del_window_keys.dedup_by(|key, previous| key.merge_into(previous));
+        for window_key in del_window_keys.iter() {
+            let key = window_key.build_key(&mut key_buffer);
             cached_sorter.insert_del_u32(key, docid)?;
         }
 
-        add_word_pair_proximity.sort_unstable();
-        add_word_pair_proximity.dedup_by(|(k1, _), (k2, _)| k1 == k2);
-        for ((w1, w2), prox) in add_word_pair_proximity.iter() {
-            let key = build_key(*prox, w1, w2, &mut key_buffer);
+        add_window_keys.sort_unstable();
+        add_window_keys.dedup_by(|key, previous| key.merge_into(previous));
+        for window_key in add_window_keys.iter() {
+            let key = window_key.build_key(&mut key_buffer);
             cached_sorter.insert_add_u32(key, docid)?;
         }
         Ok(())
     }
 }
 
-fn build_key<'a>(
-    prox: u8,
-    w1: &str,
-    w2: &str,
-    key_buffer: &'a mut bumpalo::collections::Vec<u8>,
-) -> &'a [u8] {
-    key_buffer.clear();
-    key_buffer.push(prox);
-    key_buffer.extend_from_slice(w1.as_bytes());
-    key_buffer.push(0);
-    key_buffer.extend_from_slice(w2.as_bytes());
-    key_buffer.as_slice()
+/// What is recorded from the window of word positions.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WindowRecord {
+    /// The best proximity of the pairs of words.
+    PairProximities,
+    /// The number of times a word directly follows another one.
+    PairFrequencies,
+    /// The triples of words following each other.
+    Triples,
+}
+
+/// A key extracted from the window of word positions.
+///
+/// The pairs are ordered by words then by proximity, so that the best proximity
+/// of a pair comes first once sorted.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum WindowKey {
+    Pair((Rc<str>, Rc<str>), u8),
+    Frequency((Rc<str>, Rc<str>), u8),
+    Triple(Rc<str>, Rc<str>, Rc<str>),
+}
+
+impl WindowKey {
+    /// Merges this key into the previous sorted one when both are about the same words,
+    /// returns `false` if they must be kept apart.
+    fn merge_into(&self, previous: &mut Self) -> bool {
+        match (self, previous) {
+            (WindowKey::Pair(words, _), WindowKey::Pair(previous_words, _)) => {
+                words == previous_words
+            }
+            (
+                WindowKey::Frequency(words, frequency),
+                WindowKey::Frequency(previous_words, previous_frequency),
+            ) => {
+                if words != previous_words {
+                    return false;
+                }
+                *previous_frequency =
+                    previous_frequency.saturating_add(*frequency).min(MAX_WORD_PAIR_FREQUENCY);
+                true
+            }
+            (this, previous) => this == previous,
+        }
+    }
+
+    fn build_key<'a>(&self, key_buffer: &'a mut bumpalo::collections::Vec<u8>) -> &'a [u8] {
+        key_buffer.clear();
+        match self {
+            WindowKey::Pair((w1, w2), prox) | WindowKey::Frequency((w1, w2), prox) => {
+                key_buffer.push(*prox);
+                key_buffer.extend_from_slice(w1.as_bytes());
+                key_buffer.push(0);
+                key_buffer.extend_from_slice(w2.as_bytes());
+            }
+            WindowKey::Triple(w1, w2, w3) => {
+                key_buffer.extend_from_slice(w1.as_bytes());
+                key_buffer.push(0);
+                key_buffer.extend_from_slice(w2.as_bytes());
+                key_buffer.push(0);
+                key_buffer.extend_from_slice(w3.as_bytes());
+            }
+        }
+        key_buffer.as_slice()
+    }
 }
 
 fn word_positions_into_word_pair_proximity(
     word_positions: &mut VecDeque<(Rc<str>, u16)>,
-    word_pair_proximity: &mut impl FnMut((Rc<str>, Rc<str>), u8),
+    window_key: &mut impl FnMut(WindowKey),
 ) {
     let (head_word, head_position) = word_positions.pop_front().unwrap();
     for (word, position) in word_positions.iter() {
         let prox = index_proximity(head_position as u32, *position as u32) as u8;
         if prox > 0 && prox < MAX_DISTANCE as u8 {
-            word_pair_proximity((head_word.clone(), word.clone()), prox);
+            window_key(WindowKey::Pair((head_word.clone(), word.clone()), prox));
+        }
+    }
+}
+
+/// Pops the head word and emits the triple it starts when the two next words
+/// are each at a proximity of 1 from the previous one.
+fn word_positions_into_word_triples(
+    word_positions: &mut VecDeque<(Rc<str>, u16)>,
+    window_key: &mut impl FnMut(WindowKey),
+) {
+    let (head_word, head_position) = word_positions.pop_front().unwrap();
+    if let (Some((w2, p2)), Some((w3, p3))) = (word_positions.front(), word_positions.get(1)) {
+        if index_proximity(head_position as u32, *p2 as u32) == 1
+            && index_proximity(*p2 as u32, *p3 as u32) == 1
+        {
+            window_key(WindowKey::Triple(head_word, w2.clone(), w3.clone()));
+        }
+    }
+}
+
+/// Pops the head word and emits a single occurrence of the pair it starts
+/// when the next word is at a proximity of 1.
+fn word_positions_into_word_pair_frequency(
+    word_positions: &mut VecDeque<(Rc<str>, u16)>,
+    window_key: &mut impl FnMut(WindowKey),
+) {
+    let (head_word, head_position) = word_positions.pop_front().unwrap();
+    if let Some((word, position)) = word_positions.front() {
+        if index_proximity(head_position as u32, *position as u32) == 1 {
+            window_key(WindowKey::Frequency((head_word, word.clone()), 1));
         }
     }
 }
 
+fn pop_word_positions_head(
+    word_positions: &mut VecDeque<(Rc<str>, u16)>,
+    record: WindowRecord,
+    window_key: &mut impl FnMut(WindowKey),
+) {
+    match record {
+        WindowRecord::PairProximities => {
+            word_positions_into_word_pair_proximity(word_positions, window_key)
+        }
+        WindowRecord::PairFrequencies => {
+            word_positions_into_word_pair_frequency(word_positions, window_key)
+        }
+        WindowRecord::Triples => word_positions_into_word_triples(word_positions, window_key),
+    }
+}
+
 fn drain_word_positions(
     word_positions: &mut VecDeque<(Rc<str>, u16)>,
-    word_pair_proximity: &mut impl FnMut((Rc<str>, Rc<str>), u8),
+    record: WindowRecord,
+    window_key: &mut impl FnMut(WindowKey),
 ) {
     while !word_positions.is_empty() {
-        word_positions_into_word_pair_proximity(word_positions, word_pair_proximity);
+        pop_word_positions_head(word_positions, record, window_key);
     }
 }
 
@@ -259,20 +441,25 @@ fn process_document_tokens<'doc>(
     document_tokenizer: &DocumentTokenizer,
     fields_ids_map: &mut GlobalFieldsIdsMap,
     word_positions: &mut VecDeque<(Rc<str>, u16)>,
-    word_pair_proximity: &mut impl FnMut((Rc<str>, Rc<str>), u8),
+    record: WindowRecord,
+    excluded_fields: &BTreeSet<FieldId>,
+    window_key: &mut impl FnMut(WindowKey),
 ) -> Result<()> {
     let mut field_id = None;
     let mut token_fn = |_fname: &str, fid: FieldId, pos: u16, word: &str| {
         if field_id != Some(fid) {
             field_id = Some(fid);
-            drain_word_positions(word_positions, word_pair_proximity);
+            drain_word_positions(word_positions, record, window_key);
+        }
+        if excluded_fields.contains(&fid) {
+            return Ok(());
         }
         // drain the proximity window until the head word is considered close to the word we are inserting.
         while word_positions
             .front()
             .is_some_and(|(_w, p)| index_proximity(*p as u32, pos as u32) >= MAX_DISTANCE)
         {
-            word_positions_into_word_pair_proximity(word_positions, word_pair_proximity);
+            pop_word_positions_head(word_positions, record, window_key);
         }
 
         // insert the new word.
@@ -281,6 +468,6 @@ fn process_document_tokens<'doc>(
     };
     document_tokenizer.tokenize_document(document, fields_ids_map, &mut token_fn)?;
 
-    drain_word_positions(word_positions, word_pair_proximity);
+    drain_word_positions(word_positions, record, window_key);
     Ok(())
 }
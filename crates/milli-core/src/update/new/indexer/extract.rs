@@ -73,6 +73,7 @@ where
                 *current = current.saturating_add_signed(delta);
             }
             document_extractor_data.docids_delta.apply_to(document_ids, modified_docids);
+            *modified_docids |= document_extractor_data.updated_docids;
         }
 
         field_distribution.retain(|_, v| *v != 0);
@@ -235,6 +236,68 @@ where
         }
     }
 
+    // run the triples extraction only if the index records them,
+    // this works only if the settings didn't change during this transaction.
+    if index.record_word_triples(&rtxn)? {
+        let caches = {
+            let span =
+                tracing::trace_span!(target: "indexing::documents::extract", "word_triple_docids");
+            let _entered = span.enter();
+
+            WordPairProximityDocidsExtractor::run_triple_extraction(
+                document_changes,
+                indexing_context,
+                extractor_allocs,
+                IndexingStep::ExtractingWordProximity,
+            )?
+        };
+
+        {
+            let span =
+                tracing::trace_span!(target: "indexing::documents::merge", "word_triple_docids");
+            let _entered = span.enter();
+            indexing_context.progress.update_progress(IndexingStep::MergingWordProximity);
+
+            merge_and_send_docids(
+                caches,
+                index.word_triple_docids.remap_types(),
+                index,
+                extractor_sender.docids::<WordTripleDocids>(),
+                &indexing_context.must_stop_processing,
+            )?;
+        }
+    }
+
+    // run the word pair frequencies extraction only if the index records them,
+    // this works only if the settings didn't change during this transaction.
+    if index.record_word_pair_frequencies(&rtxn)? {
+        let caches = {
+            let span = tracing::trace_span!(target: "indexing::documents::extract", "word_pair_frequency_docids");
+            let _entered = span.enter();
+
+            WordPairProximityDocidsExtractor::run_frequency_extraction(
+                document_changes,
+                indexing_context,
+                extractor_allocs,
+                IndexingStep::ExtractingWordProximity,
+            )?
+        };
+
+        {
+            let span = tracing::trace_span!(target: "indexing::documents::merge", "word_pair_frequency_docids");
+            let _entered = span.enter();
+            indexing_context.progress.update_progress(IndexingStep::MergingWordProximity);
+
+            merge_and_send_docids(
+                caches,
+                index.word_pair_frequency_docids.remap_types(),
+                index,
+                extractor_sender.docids::<WordPairFrequencyDocids>(),
+                &indexing_context.must_stop_processing,
+            )?;
+        }
+    }
+
     'vectors: {
         if index_embeddings.is_empty() {
             break 'vectors;
@@ -278,7 +341,8 @@ where
     }
 
     'geo: {
-        let Some(extractor) = GeoExtractor::new(&rtxn, index, *indexing_context.grenad_parameters)?
+        let Some(extractor) =
+            GeoExtractor::new(&rtxn, index, indexing_context.grenad_parameters.clone())?
         else {
             break 'geo;
         };
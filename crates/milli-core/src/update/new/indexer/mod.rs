@@ -60,6 +60,8 @@ where
     DC: DocumentChanges<'pl>,
     MSP: Fn() -> bool + Sync,
 {
+    grenad_parameters.validate()?;
+
     let mut bbbuffers = Vec::new();
     let finished_extraction = AtomicBool::new(false);
 
@@ -85,7 +87,7 @@ where
         (
             GrenadParameters {
                 max_memory: Some(minimum_total_extractors_capacity),
-                ..grenad_parameters
+                ..grenad_parameters.clone()
             },
             minimum_total_bbbuffer_capacity,
         ), // 100 MiB by thread by default
@@ -236,6 +238,7 @@ where
         field_distribution,
         document_ids,
     )?;
+    index.put_documents_version(wtxn, &modified_docids)?;
 
     Ok(congestion)
 }
@@ -10,6 +10,9 @@ use crate::index::main_key::{WORDS_FST_KEY, WORDS_PREFIXES_FST_KEY};
 use crate::progress::Progress;
 use crate::update::del_add::DelAdd;
 use crate::update::facet::new_incremental::FacetsUpdateIncremental;
+use crate::update::facet::{
+    compute_field_facet_representatives, update_facet_representatives, warn_facet_cardinalities,
+};
 use crate::update::facet::{FACET_GROUP_SIZE, FACET_MAX_GROUP_SIZE, FACET_MIN_LEVEL_SIZE};
 use crate::update::new::facet_search_builder::FacetSearchBuilder;
 use crate::update::new::merger::FacetFieldIdDelta;
@@ -34,6 +37,7 @@ where
 {
     let index = indexing_context.index;
     indexing_context.progress.update_progress(IndexingStep::PostProcessingFacets);
+    let modified_facet_fields = facet_field_ids_delta.modified_field_ids();
     compute_facet_level_database(
         index,
         wtxn,
@@ -41,6 +45,9 @@ where
         &mut global_fields_ids_map,
         indexing_context.progress,
     )?;
+    if !modified_facet_fields.is_empty() {
+        warn_facet_cardinalities(wtxn, index, |fid| modified_facet_fields.contains(&fid))?;
+    }
     compute_facet_search_database(index, wtxn, global_fields_ids_map, indexing_context.progress)?;
     indexing_context.progress.update_progress(IndexingStep::PostProcessingWords);
     if let Some(prefix_delta) = compute_word_fst(index, wtxn, indexing_context.progress)? {
@@ -221,6 +228,16 @@ fn compute_facet_level_database(
     deltas.sort_by_key(|(_, delta)| if let FacetFieldIdDelta::Bulk = delta { 0 } else { 1 });
 
     for (fid, delta) in deltas {
+        match &delta {
+            FacetFieldIdDelta::Bulk => compute_field_facet_representatives(wtxn, index, fid)?,
+            FacetFieldIdDelta::Incremental(delta_data) => {
+                let values = delta_data
+                    .iter()
+                    .filter_map(|change| std::str::from_utf8(&change.facet_value).ok());
+                update_facet_representatives(wtxn, index, fid, values)?
+            }
+        }
+
         // skip field ids that should not be facet leveled
         let Some(metadata) = global_fields_ids_map.metadata(fid) else {
             continue;
@@ -1,4 +1,5 @@
 use std::cell::RefCell;
+use std::collections::BTreeSet;
 
 use hashbrown::HashMap;
 use heed::types::Bytes;
@@ -265,6 +266,15 @@ impl FacetFieldIdsDelta {
         self.modified_facet_string_ids.drain()
     }
 
+    /// The field ids of the string and number facets that have been modified.
+    pub fn modified_field_ids(&self) -> BTreeSet<FieldId> {
+        self.modified_facet_string_ids
+            .keys()
+            .chain(self.modified_facet_number_ids.keys())
+            .copied()
+            .collect()
+    }
+
     pub fn consume_facet_number_delta(
         &mut self,
     ) -> impl Iterator<Item = (FieldId, FacetFieldIdDelta)> + '_ {
@@ -3,8 +3,9 @@ use std::convert::TryInto;
 use std::num::NonZeroUsize;
 use std::result::Result as StdResult;
 use std::sync::Arc;
+use std::time::Instant;
 
-use charabia::{Normalize, Tokenizer, TokenizerBuilder};
+use charabia::{Language, Normalize, Tokenizer, TokenizerBuilder};
 use deserr::{DeserializeError, Deserr};
 use itertools::{merge_join_by, EitherOrBoth, Itertools};
 use roaring::RoaringBitmap;
@@ -12,6 +13,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use time::OffsetDateTime;
 
 use super::del_add::{DelAdd, DelAddOperation};
+use super::facet::compute_facet_representatives;
 use super::index_documents::{IndexDocumentsConfig, Transform};
 use super::IndexerConfig;
 use crate::attribute_patterns::PatternMatch;
@@ -19,8 +21,9 @@ use crate::constants::RESERVED_GEO_FIELD_NAME;
 use crate::criterion::Criterion;
 use crate::disabled_typos_terms::DisabledTyposTerms;
 use crate::error::UserError;
+use crate::facet::FacetRepresentative;
 use crate::fields_ids_map::metadata::{FieldIdMapWithMetadata, MetadataBuilder};
-use crate::filterable_attributes_rules::match_faceted_field;
+use crate::filterable_attributes_rules::{match_faceted_field, FilterableAttributesFeatures};
 use crate::index::{
     IndexEmbeddingConfig, PrefixSearch, DEFAULT_MIN_WORD_LEN_ONE_TYPO,
     DEFAULT_MIN_WORD_LEN_TWO_TYPOS,
@@ -178,13 +181,22 @@ pub struct Settings<'a, 't, 'i> {
     exact_attributes: Setting<HashSet<String>>,
     max_values_per_facet: Setting<usize>,
     sort_facet_values_by: Setting<OrderByMap>,
+    /// Polygons of `[latitude, longitude]` vertices, used by the `_geoInRegion` filter.
+    geo_regions: Setting<BTreeMap<String, Vec<[f64; 2]>>>,
     pagination_max_total_hits: Setting<usize>,
     proximity_precision: Setting<ProximityPrecision>,
     embedder_settings: Setting<BTreeMap<String, Setting<EmbeddingSettings>>>,
     search_cutoff: Setting<u64>,
+    filter_scan_limit: Setting<u64>,
+    facet_cardinality_budget: Setting<u64>,
     localized_attributes_rules: Setting<Vec<LocalizedAttributesRule>>,
     prefix_search: Setting<PrefixSearch>,
     facet_search: Setting<bool>,
+    record_word_triples: Setting<bool>,
+    record_word_pair_frequencies: Setting<bool>,
+    proximity_excluded_fields: Setting<BTreeSet<FieldId>>,
+    facet_normalization_locale: Setting<Language>,
+    facet_representative: Setting<FacetRepresentative>,
 }
 
 impl<'a, 't, 'i> Settings<'a, 't, 'i> {
@@ -216,13 +228,21 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
             exact_attributes: Setting::NotSet,
             max_values_per_facet: Setting::NotSet,
             sort_facet_values_by: Setting::NotSet,
+            geo_regions: Setting::NotSet,
             pagination_max_total_hits: Setting::NotSet,
             proximity_precision: Setting::NotSet,
             embedder_settings: Setting::NotSet,
             search_cutoff: Setting::NotSet,
+            filter_scan_limit: Setting::NotSet,
+            facet_cardinality_budget: Setting::NotSet,
             localized_attributes_rules: Setting::NotSet,
             prefix_search: Setting::NotSet,
             facet_search: Setting::NotSet,
+            record_word_triples: Setting::NotSet,
+            record_word_pair_frequencies: Setting::NotSet,
+            proximity_excluded_fields: Setting::NotSet,
+            facet_normalization_locale: Setting::NotSet,
+            facet_representative: Setting::NotSet,
             indexer_config,
         }
     }
@@ -397,6 +417,14 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         self.sort_facet_values_by = Setting::Reset;
     }
 
+    pub fn set_geo_regions(&mut self, value: BTreeMap<String, Vec<[f64; 2]>>) {
+        self.geo_regions = Setting::Set(value);
+    }
+
+    pub fn reset_geo_regions(&mut self) {
+        self.geo_regions = Setting::Reset;
+    }
+
     pub fn set_pagination_max_total_hits(&mut self, value: usize) {
         self.pagination_max_total_hits = Setting::Set(value);
     }
@@ -429,6 +457,22 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         self.search_cutoff = Setting::Reset;
     }
 
+    pub fn set_filter_scan_limit(&mut self, value: u64) {
+        self.filter_scan_limit = Setting::Set(value);
+    }
+
+    pub fn reset_filter_scan_limit(&mut self) {
+        self.filter_scan_limit = Setting::Reset;
+    }
+
+    pub fn set_facet_cardinality_budget(&mut self, value: u64) {
+        self.facet_cardinality_budget = Setting::Set(value);
+    }
+
+    pub fn reset_facet_cardinality_budget(&mut self) {
+        self.facet_cardinality_budget = Setting::Reset;
+    }
+
     pub fn set_localized_attributes_rules(&mut self, value: Vec<LocalizedAttributesRule>) {
         self.localized_attributes_rules = Setting::Set(value);
     }
@@ -453,6 +497,47 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         self.facet_search = Setting::Reset;
     }
 
+    pub fn set_record_word_triples(&mut self, value: bool) {
+        self.record_word_triples = Setting::Set(value);
+    }
+
+    pub fn reset_record_word_triples(&mut self) {
+        self.record_word_triples = Setting::Reset;
+    }
+
+    pub fn set_record_word_pair_frequencies(&mut self, value: bool) {
+        self.record_word_pair_frequencies = Setting::Set(value);
+    }
+
+    pub fn reset_record_word_pair_frequencies(&mut self) {
+        self.record_word_pair_frequencies = Setting::Reset;
+    }
+
+    pub fn set_proximity_excluded_fields(&mut self, fields: BTreeSet<FieldId>) {
+        self.proximity_excluded_fields =
+            if fields.is_empty() { Setting::Reset } else { Setting::Set(fields) };
+    }
+
+    pub fn reset_proximity_excluded_fields(&mut self) {
+        self.proximity_excluded_fields = Setting::Reset;
+    }
+
+    pub fn set_facet_normalization_locale(&mut self, value: Language) {
+        self.facet_normalization_locale = Setting::Set(value);
+    }
+
+    pub fn reset_facet_normalization_locale(&mut self) {
+        self.facet_normalization_locale = Setting::Reset;
+    }
+
+    pub fn set_facet_representative(&mut self, value: FacetRepresentative) {
+        self.facet_representative = Setting::Set(value);
+    }
+
+    pub fn reset_facet_representative(&mut self) {
+        self.facet_representative = Setting::Reset;
+    }
+
     #[tracing::instrument(
         level = "trace"
         skip(self, progress_callback, should_abort, settings_diff),
@@ -955,6 +1040,44 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         Ok(())
     }
 
+    fn update_geo_regions(&mut self) -> Result<()> {
+        match self.geo_regions.as_ref() {
+            Setting::Set(regions) => {
+                for (name, polygon) in regions {
+                    if polygon.len() < 3 {
+                        return Err(UserError::InvalidGeoRegion {
+                            name: name.clone(),
+                            reason: format!(
+                                "a region needs at least 3 points but only {} were given",
+                                polygon.len()
+                            ),
+                        }
+                        .into());
+                    }
+                    let invalid_point = polygon.iter().find(|[lat, lng]| {
+                        !(-90.0..=90.0).contains(lat) || !(-180.0..=180.0).contains(lng)
+                    });
+                    if let Some([lat, lng]) = invalid_point {
+                        return Err(UserError::InvalidGeoRegion {
+                            name: name.clone(),
+                            reason: format!(
+                                "the point `[{lat}, {lng}]` is not a valid `[latitude, longitude]` pair"
+                            ),
+                        }
+                        .into());
+                    }
+                }
+                self.index.put_geo_regions(self.wtxn, regions)?;
+            }
+            Setting::Reset => {
+                self.index.delete_geo_regions(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+
+        Ok(())
+    }
+
     fn update_pagination_max_total_hits(&mut self) -> Result<()> {
         match self.pagination_max_total_hits {
             Setting::Set(max) => {
@@ -1023,6 +1146,96 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         Ok(changed)
     }
 
+    fn update_record_word_triples(&mut self) -> Result<bool> {
+        let changed = match self.record_word_triples {
+            Setting::Set(new) => {
+                let old = self.index.record_word_triples(self.wtxn)?;
+                if old == new {
+                    false
+                } else {
+                    self.index.put_record_word_triples(self.wtxn, new)?;
+                    true
+                }
+            }
+            Setting::Reset => self.index.delete_record_word_triples(self.wtxn)?,
+            Setting::NotSet => false,
+        };
+
+        Ok(changed)
+    }
+
+    fn update_record_word_pair_frequencies(&mut self) -> Result<bool> {
+        let changed = match self.record_word_pair_frequencies {
+            Setting::Set(new) => {
+                let old = self.index.record_word_pair_frequencies(self.wtxn)?;
+                if old == new {
+                    false
+                } else {
+                    self.index.put_record_word_pair_frequencies(self.wtxn, new)?;
+                    true
+                }
+            }
+            Setting::Reset => self.index.delete_record_word_pair_frequencies(self.wtxn)?,
+            Setting::NotSet => false,
+        };
+
+        Ok(changed)
+    }
+
+    fn update_proximity_excluded_fields(&mut self) -> Result<bool> {
+        let changed = match self.proximity_excluded_fields {
+            Setting::Set(ref new) => {
+                let old = self.index.proximity_excluded_fields(self.wtxn)?;
+                if &old == new {
+                    false
+                } else {
+                    self.index.put_proximity_excluded_fields(self.wtxn, new)?;
+                    true
+                }
+            }
+            Setting::Reset => self.index.delete_proximity_excluded_fields(self.wtxn)?,
+            Setting::NotSet => false,
+        };
+
+        Ok(changed)
+    }
+
+    fn update_facet_normalization_locale(&mut self) -> Result<bool> {
+        let changed = match self.facet_normalization_locale {
+            Setting::Set(new) => {
+                let old = self.index.facet_normalization_locale(self.wtxn)?;
+                if old == Some(new) {
+                    false
+                } else {
+                    self.index.put_facet_normalization_locale(self.wtxn, new)?;
+                    true
+                }
+            }
+            Setting::Reset => self.index.delete_facet_normalization_locale(self.wtxn)?,
+            Setting::NotSet => false,
+        };
+
+        Ok(changed)
+    }
+
+    fn update_facet_representative(&mut self) -> Result<bool> {
+        let changed = match self.facet_representative {
+            Setting::Set(new) => {
+                let old = self.index.facet_representative(self.wtxn)?;
+                if old == new {
+                    false
+                } else {
+                    self.index.put_facet_representative(self.wtxn, new)?;
+                    true
+                }
+            }
+            Setting::Reset => self.index.delete_facet_representative(self.wtxn)?,
+            Setting::NotSet => false,
+        };
+
+        Ok(changed)
+    }
+
     fn update_embedding_configs(&mut self) -> Result<BTreeMap<String, EmbedderAction>> {
         match std::mem::take(&mut self.embedder_settings) {
             Setting::Set(configs) => self.update_embedding_configs_set(configs),
@@ -1222,6 +1435,30 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         Ok(changed)
     }
 
+    fn update_filter_scan_limit(&mut self) -> Result<()> {
+        match self.filter_scan_limit {
+            Setting::Set(limit) => self.index.put_filter_scan_limit(self.wtxn, limit)?,
+            Setting::Reset => {
+                self.index.delete_filter_scan_limit(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+
+        Ok(())
+    }
+
+    fn update_facet_cardinality_budget(&mut self) -> Result<()> {
+        match self.facet_cardinality_budget {
+            Setting::Set(budget) => self.index.put_facet_cardinality_budget(self.wtxn, budget)?,
+            Setting::Reset => {
+                self.index.delete_facet_cardinality_budget(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+
+        Ok(())
+    }
+
     fn update_localized_attributes_rules(&mut self) -> Result<()> {
         match &self.localized_attributes_rules {
             Setting::Set(new) => {
@@ -1258,8 +1495,11 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         self.update_exact_words()?;
         self.update_max_values_per_facet()?;
         self.update_sort_facet_values_by()?;
+        self.update_geo_regions()?;
         self.update_pagination_max_total_hits()?;
         self.update_search_cutoff()?;
+        self.update_filter_scan_limit()?;
+        self.update_facet_cardinality_budget()?;
 
         // could trigger re-indexing
         self.update_filterable()?;
@@ -1274,6 +1514,11 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         self.update_proximity_precision()?;
         self.update_prefix_search()?;
         self.update_facet_search()?;
+        self.update_record_word_triples()?;
+        self.update_record_word_pair_frequencies()?;
+        self.update_proximity_excluded_fields()?;
+        self.update_facet_normalization_locale()?;
+        let facet_representative_changed = self.update_facet_representative()?;
         self.update_localized_attributes_rules()?;
         self.update_disabled_typos_terms()?;
 
@@ -1299,6 +1544,10 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
             self.reindex(&progress_callback, &should_abort, inner_settings_diff)?;
         }
 
+        if facet_representative_changed {
+            compute_facet_representatives(self.wtxn, self.index)?;
+        }
+
         Ok(())
     }
 }
@@ -1314,7 +1563,8 @@ pub struct InnerIndexSettingsDiff {
     pub(crate) only_additional_fields: Option<HashSet<String>>,
 
     // Cache the check to see if all the stop_words, allowed_separators, dictionary,
-    // exact_attributes, proximity_precision are different.
+    // exact_attributes, proximity_precision, record_word_triples,
+    // record_word_pair_frequencies, proximity_excluded_fields are different.
     pub(crate) cache_reindex_searchable_without_user_defined: bool,
     // Cache the check to see if the user_defined_searchables are different.
     pub(crate) cache_user_defined_searchables: bool,
@@ -1354,6 +1604,10 @@ impl InnerIndexSettingsDiff {
                 || old_settings.allowed_separators != new_settings.allowed_separators
                 || old_settings.dictionary != new_settings.dictionary
                 || old_settings.proximity_precision != new_settings.proximity_precision
+                || old_settings.record_word_triples != new_settings.record_word_triples
+                || old_settings.record_word_pair_frequencies
+                    != new_settings.record_word_pair_frequencies
+                || old_settings.proximity_excluded_fields != new_settings.proximity_excluded_fields
                 || old_settings.prefix_search != new_settings.prefix_search
                 || old_settings.localized_attributes_rules
                     != new_settings.localized_attributes_rules
@@ -1439,7 +1693,23 @@ impl InnerIndexSettingsDiff {
         (self.cache_reindex_searchable_without_user_defined || self.cache_user_defined_searchables)
         // and if any settings needs the proximity database created
             && (self.old.proximity_precision == ProximityPrecision::ByAttribute
-                || self.new.proximity_precision == ProximityPrecision::ByAttribute)
+                || self.new.proximity_precision == ProximityPrecision::ByAttribute
+                // or the pairs of the excluded fields removed or restored
+                || self.old.proximity_excluded_fields != self.new.proximity_excluded_fields)
+    }
+
+    pub fn reindex_word_triples(&self) -> bool {
+        // if any searchable settings force the reindexing
+        (self.cache_reindex_searchable_without_user_defined || self.cache_user_defined_searchables)
+        // and if any settings needs the word triples database
+            && (self.old.record_word_triples || self.new.record_word_triples)
+    }
+
+    pub fn reindex_word_pair_frequencies(&self) -> bool {
+        // if any searchable settings force the reindexing
+        (self.cache_reindex_searchable_without_user_defined || self.cache_user_defined_searchables)
+        // and if any settings needs the word pair frequencies database
+            && (self.old.record_word_pair_frequencies || self.new.record_word_pair_frequencies)
     }
 
     pub fn reindex_searchable_id(&self, id: FieldId) -> Option<DelAddOperation> {
@@ -1476,6 +1746,43 @@ impl InnerIndexSettingsDiff {
             .collect()
     }
 
+    /// List the faceted fields storing their integers exactly from the inner fid map.
+    pub fn list_integer_fields_from_fid_map(&self, del_add: DelAdd) -> BTreeSet<FieldId> {
+        self.list_faceted_fields_with_feature(del_add, FilterableAttributesFeatures::is_integer)
+    }
+
+    /// List the faceted fields also storing their numbers as strings from the inner fid map.
+    pub fn list_prefixable_number_fields_from_fid_map(&self, del_add: DelAdd) -> BTreeSet<FieldId> {
+        self.list_faceted_fields_with_feature(
+            del_add,
+            FilterableAttributesFeatures::is_prefixable_numbers,
+        )
+    }
+
+    fn list_faceted_fields_with_feature(
+        &self,
+        del_add: DelAdd,
+        feature: fn(&FilterableAttributesFeatures) -> bool,
+    ) -> BTreeSet<FieldId> {
+        let settings = match del_add {
+            DelAdd::Deletion => &self.old,
+            DelAdd::Addition => &self.new,
+        };
+
+        settings
+            .fields_ids_map
+            .iter_id_metadata()
+            .filter(|(_, metadata)| {
+                metadata.is_faceted(&settings.filterable_attributes_rules)
+                    && feature(
+                        &metadata
+                            .filterable_attributes_features(&settings.filterable_attributes_rules),
+                    )
+            })
+            .map(|(id, _)| id)
+            .collect()
+    }
+
     pub fn facet_fids_changed(&self) -> bool {
         for eob in merge_join_by(
             self.old.fields_ids_map.iter().filter(|(_, _, metadata)| {
@@ -1514,6 +1821,21 @@ impl InnerIndexSettingsDiff {
                     if old_facet_level_database != new_facet_level_database {
                         return true;
                     }
+
+                    // Check if the integers of the field are stored exactly in the old and new settings.
+                    // If there is a difference, we need to reindex the integer facet database.
+                    if old_filterable_features.is_integer() != new_filterable_features.is_integer()
+                    {
+                        return true;
+                    }
+
+                    // Check if the numbers of the field are stored as strings in the old and new settings.
+                    // If there is a difference, we need to reindex the string facet database.
+                    if old_filterable_features.is_prefixable_numbers()
+                        != new_filterable_features.is_prefixable_numbers()
+                    {
+                        return true;
+                    }
                 }
             }
         }
@@ -1524,6 +1846,7 @@ impl InnerIndexSettingsDiff {
     pub fn global_facet_settings_changed(&self) -> bool {
         self.old.localized_attributes_rules != self.new.localized_attributes_rules
             || self.old.facet_search != self.new.facet_search
+            || self.old.facet_normalization_locale != self.new.facet_normalization_locale
     }
 
     pub fn reindex_facets(&self) -> bool {
@@ -1563,6 +1886,10 @@ pub(crate) struct InnerIndexSettings {
     pub geo_fields_ids: Option<(FieldId, FieldId)>,
     pub prefix_search: PrefixSearch,
     pub facet_search: bool,
+    pub record_word_triples: bool,
+    pub record_word_pair_frequencies: bool,
+    pub proximity_excluded_fields: BTreeSet<FieldId>,
+    pub facet_normalization_locale: Option<Language>,
 }
 
 impl InnerIndexSettings {
@@ -1584,6 +1911,10 @@ impl InnerIndexSettings {
         };
         let prefix_search = index.prefix_search(rtxn)?.unwrap_or_default();
         let facet_search = index.facet_search(rtxn)?;
+        let record_word_triples = index.record_word_triples(rtxn)?;
+        let record_word_pair_frequencies = index.record_word_pair_frequencies(rtxn)?;
+        let proximity_excluded_fields = index.proximity_excluded_fields(rtxn)?;
+        let facet_normalization_locale = index.facet_normalization_locale(rtxn)?;
         let geo_fields_ids = match fields_ids_map.id(RESERVED_GEO_FIELD_NAME) {
             Some(_) if index.is_geo_enabled(rtxn)? => {
                 // if `_geo` is faceted then we get the `lat` and `lng`
@@ -1624,6 +1955,10 @@ impl InnerIndexSettings {
             geo_fields_ids,
             prefix_search,
             facet_search,
+            record_word_triples,
+            record_word_pair_frequencies,
+            proximity_excluded_fields,
+            facet_normalization_locale,
             disabled_typos_terms,
         })
     }
@@ -1671,13 +2006,21 @@ fn embedders(embedding_configs: Vec<IndexEmbeddingConfig>) -> Result<EmbeddingCo
                  config: EmbeddingConfig { embedder_options, prompt, quantized },
                  ..
              }| {
+                let prompt = embedder_options
+                    .document_prompt(prompt)
+                    .map_err(crate::vector::Error::from)
+                    .map_err(crate::Error::from)?;
                 let prompt = Arc::new(prompt.try_into().map_err(crate::Error::from)?);
 
                 let embedder = Arc::new(
                     // cache_cap: no cache needed for indexing purposes
-                    Embedder::new(embedder_options.clone(), 0)
-                        .map_err(crate::vector::Error::from)
-                        .map_err(crate::Error::from)?,
+                    Embedder::new_with_deadline(
+                        embedder_options.clone(),
+                        0,
+                        Instant::now() + crate::vector::COMPOSITE_CHECK_TIMEOUT,
+                    )
+                    .map_err(crate::vector::Error::from)
+                    .map_err(crate::Error::from)?,
                 );
                 Ok((name, (embedder, prompt, quantized.unwrap_or_default())))
             },
@@ -1738,6 +2081,11 @@ pub fn validate_embedding_settings(
         mut indexing_embedder,
         distribution,
         headers,
+        batch_encoding,
+        preprocess,
+        distance,
+        search_prompt_prefix,
+        index_prompt_prefix,
         binary_quantized: binary_quantize,
     } = settings;
 
@@ -1759,8 +2107,11 @@ pub fn validate_embedding_settings(
     }
 
     if let Some(request) = request.as_ref().set() {
-        let request = crate::vector::rest::Request::new(request.to_owned())
-            .map_err(|error| crate::UserError::VectorEmbeddingError(error.into()))?;
+        let request = crate::vector::rest::Request::new(
+            request.to_owned(),
+            batch_encoding.as_ref().set().copied().unwrap_or_default(),
+        )
+        .map_err(|error| crate::UserError::VectorEmbeddingError(error.into()))?;
         if let Some(response) = response.as_ref().set() {
             crate::vector::rest::Response::new(response.to_owned(), &request)
                 .map_err(|error| crate::UserError::VectorEmbeddingError(error.into()))?;
@@ -1785,6 +2136,11 @@ pub fn validate_embedding_settings(
             indexing_embedder,
             distribution,
             headers,
+            batch_encoding,
+            preprocess,
+            distance,
+            search_prompt_prefix,
+            index_prompt_prefix,
             binary_quantized: binary_quantize,
         }));
     };
@@ -1803,9 +2159,15 @@ pub fn validate_embedding_settings(
         &document_template,
         &document_template_max_bytes,
         &headers,
+        &batch_encoding,
+        &preprocess,
+        &distance,
+        &search_prompt_prefix,
+        &index_prompt_prefix,
         &search_embedder,
         &indexing_embedder,
         &binary_quantize,
+        &Setting::NotSet,
         &distribution,
     )?;
     match inferred_source {
@@ -1881,9 +2243,15 @@ pub fn validate_embedding_settings(
                         &embedder.document_template,
                         &embedder.document_template_max_bytes,
                         &embedder.headers,
+                        &embedder.batch_encoding,
+                        &Setting::NotSet,
+                        &Setting::NotSet,
+                        &Setting::NotSet,
+                        &Setting::NotSet,
                         &search_embedder,
                         &indexing_embedder,
                         &embedder.binary_quantized,
+                        &embedder.normalize,
                         &embedder.distribution,
                     )?;
                 } else {
@@ -1936,9 +2304,15 @@ pub fn validate_embedding_settings(
                         &embedder.document_template,
                         &embedder.document_template_max_bytes,
                         &embedder.headers,
+                        &embedder.batch_encoding,
+                        &Setting::NotSet,
+                        &Setting::NotSet,
+                        &Setting::NotSet,
+                        &Setting::NotSet,
                         &search_embedder,
                         &indexing_embedder,
                         &embedder.binary_quantized,
+                        &embedder.normalize,
                         &embedder.distribution,
                     )?;
                 } else {
@@ -1969,6 +2343,11 @@ pub fn validate_embedding_settings(
         indexing_embedder,
         distribution,
         headers,
+        batch_encoding,
+        preprocess,
+        distance,
+        search_prompt_prefix,
+        index_prompt_prefix,
         binary_quantized: binary_quantize,
     }))
 }
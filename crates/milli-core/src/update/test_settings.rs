@@ -889,13 +889,21 @@ fn test_correct_settings_init() {
                 exact_attributes,
                 max_values_per_facet,
                 sort_facet_values_by,
+                geo_regions,
                 pagination_max_total_hits,
                 proximity_precision,
                 embedder_settings,
                 search_cutoff,
+                filter_scan_limit,
+                facet_cardinality_budget,
                 localized_attributes_rules,
                 prefix_search,
                 facet_search,
+                record_word_triples,
+                record_word_pair_frequencies,
+                proximity_excluded_fields,
+                facet_normalization_locale,
+                facet_representative,
                 disable_on_numbers,
             } = settings;
             assert!(matches!(searchable_fields, Setting::NotSet));
@@ -917,13 +925,21 @@ fn test_correct_settings_init() {
             assert!(matches!(exact_attributes, Setting::NotSet));
             assert!(matches!(max_values_per_facet, Setting::NotSet));
             assert!(matches!(sort_facet_values_by, Setting::NotSet));
+            assert!(matches!(geo_regions, Setting::NotSet));
             assert!(matches!(pagination_max_total_hits, Setting::NotSet));
             assert!(matches!(proximity_precision, Setting::NotSet));
             assert!(matches!(embedder_settings, Setting::NotSet));
             assert!(matches!(search_cutoff, Setting::NotSet));
+            assert!(matches!(filter_scan_limit, Setting::NotSet));
+            assert!(matches!(facet_cardinality_budget, Setting::NotSet));
             assert!(matches!(localized_attributes_rules, Setting::NotSet));
             assert!(matches!(prefix_search, Setting::NotSet));
             assert!(matches!(facet_search, Setting::NotSet));
+            assert!(matches!(record_word_triples, Setting::NotSet));
+            assert!(matches!(record_word_pair_frequencies, Setting::NotSet));
+            assert!(matches!(proximity_excluded_fields, Setting::NotSet));
+            assert!(matches!(facet_normalization_locale, Setting::NotSet));
+            assert!(matches!(facet_representative, Setting::NotSet));
             assert!(matches!(disable_on_numbers, Setting::NotSet));
         })
         .unwrap();
@@ -6,7 +6,7 @@ use heed::RwTxn;
 use v1_12::{V1_12_3_To_V1_13_0, V1_12_To_V1_12_3};
 use v1_13::{V1_13_0_To_V1_13_1, V1_13_1_To_Latest_V1_13};
 use v1_14::Latest_V1_13_To_Latest_V1_14;
-use v1_15::Latest_V1_14_To_Latest_V1_15;
+use v1_15::{Latest_V1_14_To_Latest_V1_15, V1_15_1_To_V1_15_2};
 
 use crate::constants::{VERSION_MAJOR, VERSION_MINOR, VERSION_PATCH};
 use crate::progress::{Progress, VariableNameStep};
@@ -43,6 +43,7 @@ where
         &V1_13_1_To_Latest_V1_13 {},
         &Latest_V1_13_To_Latest_V1_14 {},
         &Latest_V1_14_To_Latest_V1_15 {},
+        &V1_15_1_To_V1_15_2 {},
         // This is the last upgrade function, it will be called when the index is up to date.
         // any other upgrade function should be added before this one.
         &ToCurrentNoOp {},
@@ -54,8 +55,9 @@ where
         (1, 13, 0) => 2,
         (1, 13, _) => 4,
         (1, 14, _) => 5,
+        (1, 15, 0..=1) => 6,
         // We must handle the current version in the match because in case of a failure some index may have been upgraded but not other.
-        (1, 15, _) => 6,
+        (1, 15, _) => 7,
         (major, minor, patch) => {
             return Err(InternalError::CannotUpgradeToVersion(major, minor, patch).into())
         }
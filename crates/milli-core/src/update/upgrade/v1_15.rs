@@ -1,9 +1,19 @@
+use std::collections::BTreeMap;
+use std::mem::size_of;
+
+use heed::types::Bytes;
 use heed::RwTxn;
+use roaring::RoaringBitmap;
+use serde_json::Value;
 
 use super::UpgradeIndex;
+use crate::facet::FacetType;
+use crate::heed_codec::facet::{FacetGroupKey, FacetGroupKeyCodec, FacetGroupValue};
+use crate::heed_codec::BytesRefCodec;
 use crate::progress::Progress;
 use crate::update::new::indexer::recompute_word_fst_from_word_docids_database;
-use crate::{make_enum_progress, Index, Result};
+use crate::update::FacetsUpdateBulk;
+use crate::{make_enum_progress, DocumentId, FieldId, Index, Result};
 
 #[allow(non_camel_case_types)]
 pub(super) struct Latest_V1_14_To_Latest_V1_15();
@@ -33,3 +43,345 @@ impl UpgradeIndex for Latest_V1_14_To_Latest_V1_15 {
         (1, 15, 0)
     }
 }
+
+#[allow(non_camel_case_types)]
+pub(super) struct V1_15_1_To_V1_15_2();
+
+impl UpgradeIndex for V1_15_1_To_V1_15_2 {
+    fn upgrade(
+        &self,
+        wtxn: &mut RwTxn,
+        index: &Index,
+        _original: (u32, u32, u32),
+        progress: Progress,
+    ) -> Result<bool> {
+        // The negative zero is now stored under the same numeric facet value as the zero.
+        make_enum_progress! {
+            enum NegativeZero {
+                MergeFacetValues,
+                RecomputeFacetLevels,
+            }
+        };
+        // The documents with a date string facet value are now stored for the `IS DATE` filter.
+        make_enum_progress! {
+            enum IsDate {
+                ComputeDateDocids,
+            }
+        };
+        // The documents with a boolean facet value are now stored for the `IS TRUE` and
+        // `IS FALSE` filters.
+        make_enum_progress! {
+            enum IsBoolean {
+                ComputeBooleanDocids,
+            }
+        };
+
+        progress.update_progress(NegativeZero::MergeFacetValues);
+        let field_ids = canonicalize_negative_zero_facet_values(wtxn, index)?;
+
+        progress.update_progress(NegativeZero::RecomputeFacetLevels);
+        if !field_ids.is_empty() {
+            FacetsUpdateBulk::new_not_updating_level_0(index, field_ids, FacetType::Number)
+                .execute(wtxn)?;
+        }
+
+        progress.update_progress(IsDate::ComputeDateDocids);
+        compute_facet_is_date_docids(wtxn, index)?;
+
+        progress.update_progress(IsBoolean::ComputeBooleanDocids);
+        compute_facet_is_boolean_docids(wtxn, index)?;
+
+        Ok(false)
+    }
+
+    fn target_version(&self) -> (u32, u32, u32) {
+        (1, 15, 2)
+    }
+}
+
+/// Moves the documents of the negative zero numeric facet values under the zero ones
+/// and returns the ids of the fields whose facet levels must be recomputed.
+fn canonicalize_negative_zero_facet_values(
+    wtxn: &mut RwTxn,
+    index: &Index,
+) -> Result<Vec<FieldId>> {
+    // The ordered f64 keys are the sortable bytes followed by the big endian float,
+    // only the latter differs between the two zeros.
+    const NEGATIVE_ZERO: [u8; 8] = (-0.0_f64).to_be_bytes();
+    const ZERO: [u8; 8] = 0.0_f64.to_be_bytes();
+    let is_negative_zero = |bytes: &[u8]| bytes.len() == 16 && bytes[8..] == NEGATIVE_ZERO;
+    let canonical = |bytes: &[u8]| [&bytes[..8], &ZERO[..]].concat();
+
+    let db = index.facet_id_f64_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>();
+    let mut negative_zeros = Vec::new();
+    for result in db.iter(wtxn)? {
+        let (key, value) = result?;
+        if key.level == 0 && is_negative_zero(key.left_bound) {
+            negative_zeros.push((key.field_id, key.left_bound.to_vec(), value.bitmap));
+        }
+    }
+
+    let mut field_ids = Vec::new();
+    for (field_id, left_bound, bitmap) in negative_zeros {
+        db.delete(wtxn, &FacetGroupKey { field_id, level: 0, left_bound: &left_bound[..] })?;
+        let left_bound = canonical(&left_bound);
+        let key = FacetGroupKey { field_id, level: 0, left_bound: &left_bound[..] };
+        let bitmap = match db.get(wtxn, &key)? {
+            Some(value) => value.bitmap | bitmap,
+            None => bitmap,
+        };
+        db.put(wtxn, &key, &FacetGroupValue { size: 1, bitmap })?;
+        field_ids.push(field_id);
+    }
+
+    // The field id and docid precede the ordered f64 in the keys of this database.
+    let prefix_len = size_of::<FieldId>() + size_of::<DocumentId>();
+    let db = index.field_id_docid_facet_f64s.remap_key_type::<Bytes>();
+    let mut keys = Vec::new();
+    for result in db.iter(wtxn)? {
+        let (key, ()) = result?;
+        if is_negative_zero(&key[prefix_len..]) {
+            keys.push(key.to_vec());
+        }
+    }
+
+    for key in keys {
+        db.delete(wtxn, &key)?;
+        let key = [&key[..prefix_len], &canonical(&key[prefix_len..])[..]].concat();
+        db.put(wtxn, &key, &())?;
+    }
+
+    field_ids.dedup();
+    Ok(field_ids)
+}
+
+/// Fills the database of the documents having a date string facet value from their originals.
+fn compute_facet_is_date_docids(wtxn: &mut RwTxn, index: &Index) -> Result<()> {
+    let mut date_docids = BTreeMap::<FieldId, RoaringBitmap>::new();
+    for result in index.field_id_docid_facet_strings.iter(wtxn)? {
+        let ((field_id, docid, _), original) = result?;
+        if crate::is_date_facet_value(original) {
+            date_docids.entry(field_id).or_default().insert(docid);
+        }
+    }
+
+    index.facet_id_is_date_docids.clear(wtxn)?;
+    for (field_id, docids) in date_docids {
+        index.facet_id_is_date_docids.put(wtxn, &field_id, &docids)?;
+    }
+
+    Ok(())
+}
+
+/// Fills the databases of the documents having a boolean facet value from their documents.
+///
+/// The booleans share their string facet values with the `"true"` and `"false"` strings, only
+/// the documents indexed under these values are read back to tell them apart.
+fn compute_facet_is_boolean_docids(wtxn: &mut RwTxn, index: &Index) -> Result<()> {
+    let fields_ids_map = index.fields_ids_map(wtxn)?;
+    let mut candidates = BTreeMap::<DocumentId, Vec<FieldId>>::new();
+    for field_id in fields_ids_map.ids() {
+        for value in [true, false] {
+            let left_bound = crate::boolean_facet_value(value);
+            let key = FacetGroupKey { field_id, level: 0, left_bound };
+            if let Some(value) = index.facet_id_string_docids.get(wtxn, &key)? {
+                for docid in value.bitmap {
+                    candidates.entry(docid).or_default().push(field_id);
+                }
+            }
+        }
+    }
+
+    let mut true_docids = BTreeMap::<FieldId, RoaringBitmap>::new();
+    let mut false_docids = BTreeMap::<FieldId, RoaringBitmap>::new();
+    for (docid, mut field_ids) in candidates {
+        let document = index.document(wtxn, docid)?;
+        let document = crate::all_obkv_to_json(document, &fields_ids_map)?;
+        // the flattened document holds the dotted fields, be they nested or not
+        let document = crate::flatten_serde_json::flatten(&document);
+        field_ids.dedup();
+        for field_id in field_ids {
+            let Some(value) = fields_ids_map.name(field_id).and_then(|name| document.get(name))
+            else {
+                continue;
+            };
+            if contains_boolean_value(value, true) {
+                true_docids.entry(field_id).or_default().insert(docid);
+            }
+            if contains_boolean_value(value, false) {
+                false_docids.entry(field_id).or_default().insert(docid);
+            }
+        }
+    }
+
+    index.facet_id_is_true_docids.clear(wtxn)?;
+    for (field_id, docids) in true_docids {
+        index.facet_id_is_true_docids.put(wtxn, &field_id, &docids)?;
+    }
+    index.facet_id_is_false_docids.clear(wtxn)?;
+    for (field_id, docids) in false_docids {
+        index.facet_id_is_false_docids.put(wtxn, &field_id, &docids)?;
+    }
+
+    Ok(())
+}
+
+/// Returns `true` if the JSON field is the `boolean` or an array containing it.
+fn contains_boolean_value(value: &Value, boolean: bool) -> bool {
+    match value {
+        Value::Bool(b) => *b == boolean,
+        Value::Array(values) => values.iter().any(|value| contains_boolean_value(value, boolean)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use heed::types::Bytes;
+    use roaring::RoaringBitmap;
+
+    use crate::constants::{VERSION_MAJOR, VERSION_MINOR, VERSION_PATCH};
+    use crate::facet::value_encoding::f64_into_bytes;
+    use crate::heed_codec::facet::{FacetGroupKey, FacetGroupKeyCodec, FacetGroupValue};
+    use crate::heed_codec::BytesRefCodec;
+    use crate::index::tests::TempIndex;
+    use crate::progress::Progress;
+    use crate::update::upgrade::upgrade;
+    use crate::{Filter, FilterableAttributesRule};
+
+    fn evaluate(index: &TempIndex, filter: &str) -> RoaringBitmap {
+        let rtxn = index.read_txn().unwrap();
+        Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, index).unwrap()
+    }
+
+    /// Upgrades the index as if it had been written by v1.15.1.
+    fn upgrade_from_v1_15_1(index: &TempIndex) {
+        let mut wtxn = index.write_txn().unwrap();
+        index.put_version(&mut wtxn, (1, 15, 1)).unwrap();
+        upgrade(&mut wtxn, index, (1, 15, 1), || false, Progress::default()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let version = index.get_version(&rtxn).unwrap();
+        assert_eq!(version, Some((VERSION_MAJOR, VERSION_MINOR, VERSION_PATCH)));
+    }
+
+    #[test]
+    fn negative_zero_from_v1_15_1() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![FilterableAttributesRule::Field(S("price"))])
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 0 },
+                { "id": 1, "price": -0.0 },
+                { "id": 2, "price": 1 },
+            ]))
+            .unwrap();
+
+        // v1.15.1 stored the negative zero of the document 1 under its own facet value
+        let mut wtxn = index.write_txn().unwrap();
+        let field_id = index.fields_ids_map(&wtxn).unwrap().id("price").unwrap();
+        let sortable_zero = f64_into_bytes(0.0).unwrap();
+        let zero = [&sortable_zero[..], &0.0_f64.to_be_bytes()].concat();
+        let negative_zero = [&sortable_zero[..], &(-0.0_f64).to_be_bytes()].concat();
+        let db = index.facet_id_f64_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>();
+        let key = |left_bound| FacetGroupKey { field_id, level: 0, left_bound };
+        let value = |docid| FacetGroupValue { size: 1, bitmap: RoaringBitmap::from_iter([docid]) };
+        db.put(&mut wtxn, &key(&zero[..]), &value(0)).unwrap();
+        db.put(&mut wtxn, &key(&negative_zero[..]), &value(1)).unwrap();
+        let docid_db = index.field_id_docid_facet_f64s.remap_key_type::<Bytes>();
+        let docid_key =
+            |value: &[u8]| [&field_id.to_be_bytes()[..], &1_u32.to_be_bytes(), value].concat();
+        docid_db.delete(&mut wtxn, &docid_key(&zero[..])).unwrap();
+        docid_db.put(&mut wtxn, &docid_key(&negative_zero[..]), &()).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(evaluate(&index, "price = 0"), RoaringBitmap::from_iter([0]));
+
+        upgrade_from_v1_15_1(&index);
+
+        assert_eq!(evaluate(&index, "price = 0"), RoaringBitmap::from_iter([0, 1]));
+        assert_eq!(evaluate(&index, "price = -0.0"), RoaringBitmap::from_iter([0, 1]));
+        assert_eq!(evaluate(&index, "price >= 0"), RoaringBitmap::from_iter([0, 1, 2]));
+        assert_eq!(evaluate(&index, "price < 0"), RoaringBitmap::new());
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(
+            db.get(&rtxn, &key(&negative_zero[..])).unwrap().map(|value| value.bitmap),
+            None
+        );
+        assert!(docid_db.get(&rtxn, &docid_key(&zero[..])).unwrap().is_some());
+        assert!(docid_db.get(&rtxn, &docid_key(&negative_zero[..])).unwrap().is_none());
+    }
+
+    #[test]
+    fn date_docids_from_v1_15_1() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings
+                    .set_filterable_fields(vec![FilterableAttributesRule::Field(S("created_at"))])
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "created_at": "2024-03-17" },
+                { "id": 1, "created_at": "2024-03-17T10:00:00Z" },
+                { "id": 2, "created_at": "yesterday" },
+                { "id": 3, "created_at": ["soon", "2024-12-25"] },
+                { "id": 4 },
+            ]))
+            .unwrap();
+
+        // v1.15.1 did not store the documents with a date
+        let mut wtxn = index.write_txn().unwrap();
+        index.facet_id_is_date_docids.clear(&mut wtxn).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(evaluate(&index, "created_at IS DATE"), RoaringBitmap::new());
+
+        upgrade_from_v1_15_1(&index);
+
+        assert_eq!(evaluate(&index, "created_at IS DATE"), RoaringBitmap::from_iter([0, 1, 3]));
+        assert_eq!(evaluate(&index, "created_at IS NOT DATE"), RoaringBitmap::from_iter([2, 4]));
+    }
+
+    #[test]
+    fn boolean_docids_from_v1_15_1() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(vec![
+                    FilterableAttributesRule::Field(S("active")),
+                    FilterableAttributesRule::Field(S("user.verified")),
+                ])
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "active": true },
+                { "id": 1, "active": [false, "true"] },
+                { "id": 2, "active": "false" },
+                { "id": 3, "user": [{ "verified": true }, { "verified": "false" }] },
+                { "id": 4, "user.verified": false },
+            ]))
+            .unwrap();
+
+        // v1.15.1 did not store the documents with a boolean
+        let mut wtxn = index.write_txn().unwrap();
+        index.facet_id_is_true_docids.clear(&mut wtxn).unwrap();
+        index.facet_id_is_false_docids.clear(&mut wtxn).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(evaluate(&index, "active IS TRUE"), RoaringBitmap::new());
+
+        upgrade_from_v1_15_1(&index);
+
+        assert_eq!(evaluate(&index, "active IS TRUE"), RoaringBitmap::from_iter([0]));
+        assert_eq!(evaluate(&index, "active IS FALSE"), RoaringBitmap::from_iter([1]));
+        assert_eq!(evaluate(&index, "user.verified IS TRUE"), RoaringBitmap::from_iter([3]));
+        assert_eq!(evaluate(&index, "user.verified IS FALSE"), RoaringBitmap::from_iter([4]));
+    }
+}
@@ -1,16 +1,44 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
 use std::time::Instant;
 
 use arroy::Distance;
 
 use super::error::CompositeEmbedderContainsHuggingFace;
+use super::settings::EmbedderSource;
 use super::{
-    hf, manual, ollama, openai, rest, DistributionShift, EmbedError, Embedding, EmbeddingCache,
-    NewEmbedderError,
+    embed_labeled_chunks, hf, manual, ollama, openai, rest, CacheOutcome, DistributionShift,
+    EmbedError, Embedding, EmbeddingCache, FieldLabel, NewEmbedderError,
 };
-use crate::ThreadPoolNoAbort;
+use crate::prompt::Prompt;
+use crate::{Object, ThreadPoolNoAbort};
 
 #[derive(Debug)]
-pub enum SubEmbedder {
+pub struct SubEmbedder {
+    kind: SubEmbedderKind,
+    /// L2-normalize the embeddings returned by the embedder.
+    normalize: bool,
+    /// Preprocess the texts before embedding them or looking them up in the cache.
+    preprocess: TextPreprocess,
+    /// Embed the identical texts of the chunks only once in [`SubEmbedder::embed_index`].
+    deduplicate_texts: bool,
+}
+
+/// What [`SubEmbedder::validate_template`] found rendering and embedding a sample document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemplateReport {
+    /// The size of the rendered text, after its truncation.
+    pub rendered_bytes: usize,
+    /// Whether the rendered text exceeded the `documentTemplateMaxBytes` and was truncated.
+    pub truncated: bool,
+    /// The number of dimensions of the embedding of the rendered text.
+    pub embedding_dims: usize,
+}
+
+#[derive(Debug)]
+pub enum SubEmbedderKind {
     /// An embedder based on running local models, fetched from the Hugging Face Hub.
     HuggingFace(hf::Embedder),
     /// An embedder based on making embedding queries against the OpenAI API.
@@ -42,66 +70,549 @@ impl SubEmbedderOptions {
             SubEmbedderOptions::Rest(embedder_options) => embedder_options.distribution,
         }
     }
+
+    /// Whether the embeddings returned by the embedder must be L2-normalized.
+    pub fn normalize(&self) -> bool {
+        match self {
+            SubEmbedderOptions::HuggingFace(embedder_options) => embedder_options.normalize,
+            SubEmbedderOptions::OpenAi(embedder_options) => embedder_options.normalize,
+            SubEmbedderOptions::Ollama(embedder_options) => embedder_options.normalize,
+            SubEmbedderOptions::UserProvided(embedder_options) => embedder_options.normalize,
+            SubEmbedderOptions::Rest(embedder_options) => embedder_options.normalize,
+        }
+    }
+
+    pub fn set_normalize(&mut self, normalize: bool) {
+        match self {
+            SubEmbedderOptions::HuggingFace(embedder_options) => {
+                embedder_options.normalize = normalize
+            }
+            SubEmbedderOptions::OpenAi(embedder_options) => embedder_options.normalize = normalize,
+            SubEmbedderOptions::Ollama(embedder_options) => embedder_options.normalize = normalize,
+            SubEmbedderOptions::UserProvided(embedder_options) => {
+                embedder_options.normalize = normalize
+            }
+            SubEmbedderOptions::Rest(embedder_options) => embedder_options.normalize = normalize,
+        }
+    }
+
+    /// Starts building the options of a sub-embedder of the `source`.
+    ///
+    /// The mandatory parameters are the `model` of the `ollama` source, the `dimensions` of
+    /// the `userProvided` source and the `url`, `request` and `response` of the `rest` source.
+    pub fn builder(source: EmbedderSource) -> SubEmbedderOptionsBuilder {
+        SubEmbedderOptionsBuilder {
+            source,
+            model: None,
+            revision: None,
+            pooling: Default::default(),
+            api_key: None,
+            url: None,
+            dimensions: None,
+            distribution: None,
+            request: None,
+            response: None,
+            headers: BTreeMap::new(),
+            batch_encoding: Default::default(),
+            normalize: false,
+        }
+    }
+}
+
+/// The normalizations applied to the texts by a sub-embedder before embedding them,
+/// so that the texts differing only by their case or spacing share their embedding
+/// and their entry in the cache.
+///
+/// The normalizations are combined with `|`, as in `TextPreprocess::TRIM | TextPreprocess::LOWERCASE`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Hash,
+    PartialEq,
+    Eq,
+    serde::Deserialize,
+    serde::Serialize,
+    utoipa::ToSchema,
+    deserr::Deserr,
+)]
+#[deserr(rename_all = camelCase, deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct TextPreprocess {
+    /// Remove the whitespace at the start and end of the text.
+    #[serde(default)]
+    #[deserr(default)]
+    pub trim: bool,
+    /// Lowercase the text.
+    #[serde(default)]
+    #[deserr(default)]
+    pub lowercase: bool,
+    /// Replace every run of whitespace with a single space.
+    #[serde(default)]
+    #[deserr(default)]
+    pub collapse_whitespace: bool,
+}
+
+impl TextPreprocess {
+    pub const NONE: Self = Self { trim: false, lowercase: false, collapse_whitespace: false };
+    pub const TRIM: Self = Self { trim: true, ..Self::NONE };
+    pub const LOWERCASE: Self = Self { lowercase: true, ..Self::NONE };
+    pub const COLLAPSE_WHITESPACE: Self = Self { collapse_whitespace: true, ..Self::NONE };
+
+    /// Whether no normalization is enabled.
+    pub fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    /// Returns the preprocessed `text`, borrowed when no normalization is enabled.
+    pub fn apply<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        if *self == Self::NONE {
+            return Cow::Borrowed(text);
+        }
+
+        let mut text = if self.trim { text.trim() } else { text };
+        let collapsed;
+        if self.collapse_whitespace {
+            let mut output = String::with_capacity(text.len());
+            let mut in_whitespace = false;
+            for c in text.chars() {
+                if c.is_whitespace() {
+                    if !in_whitespace {
+                        output.push(' ');
+                    }
+                    in_whitespace = true;
+                } else {
+                    output.push(c);
+                    in_whitespace = false;
+                }
+            }
+            collapsed = output;
+            text = &collapsed;
+        }
+        if self.lowercase {
+            Cow::Owned(text.to_lowercase())
+        } else {
+            Cow::Owned(text.to_owned())
+        }
+    }
 }
 
+impl std::ops::BitOr for TextPreprocess {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self {
+            trim: self.trim || other.trim,
+            lowercase: self.lowercase || other.lowercase,
+            collapse_whitespace: self.collapse_whitespace || other.collapse_whitespace,
+        }
+    }
+}
+
+/// Assembles [`SubEmbedderOptions`], checking that the mandatory parameters of the source are set.
+///
+/// The parameters that do not apply to the source are ignored.
+#[derive(Debug, Clone)]
+pub struct SubEmbedderOptionsBuilder {
+    source: EmbedderSource,
+    model: Option<String>,
+    revision: Option<String>,
+    pooling: hf::OverridePooling,
+    api_key: Option<String>,
+    url: Option<String>,
+    dimensions: Option<usize>,
+    distribution: Option<DistributionShift>,
+    request: Option<serde_json::Value>,
+    response: Option<serde_json::Value>,
+    headers: BTreeMap<String, String>,
+    batch_encoding: rest::BatchEncoding,
+    normalize: bool,
+}
+
+impl SubEmbedderOptionsBuilder {
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn revision(mut self, revision: impl Into<String>) -> Self {
+        self.revision = Some(revision.into());
+        self
+    }
+
+    pub fn pooling(mut self, pooling: hf::OverridePooling) -> Self {
+        self.pooling = pooling;
+        self
+    }
+
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    pub fn dimensions(mut self, dimensions: usize) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    pub fn distribution(mut self, distribution: DistributionShift) -> Self {
+        self.distribution = Some(distribution);
+        self
+    }
+
+    pub fn request(mut self, request: serde_json::Value) -> Self {
+        self.request = Some(request);
+        self
+    }
+
+    pub fn response(mut self, response: serde_json::Value) -> Self {
+        self.response = Some(response);
+        self
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn batch_encoding(mut self, batch_encoding: rest::BatchEncoding) -> Self {
+        self.batch_encoding = batch_encoding;
+        self
+    }
+
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Returns the options, or an error listing all the mandatory parameters that are missing.
+    ///
+    /// The `composite` source is rejected as composite embedders can't be nested.
+    pub fn build(self) -> Result<SubEmbedderOptions, NewEmbedderError> {
+        let mut missing = Vec::new();
+        let mandatory: &[(&str, bool)] = match self.source {
+            EmbedderSource::Ollama => &[("model", self.model.is_some())],
+            EmbedderSource::UserProvided => &[("dimensions", self.dimensions.is_some())],
+            EmbedderSource::Rest => &[
+                ("url", self.url.is_some()),
+                ("request", self.request.is_some()),
+                ("response", self.response.is_some()),
+            ],
+            EmbedderSource::HuggingFace | EmbedderSource::OpenAi => &[],
+            EmbedderSource::Composite => return Err(NewEmbedderError::nested_composite()),
+        };
+        for (parameter, is_set) in mandatory {
+            if !is_set {
+                missing.push(*parameter);
+            }
+        }
+        if !missing.is_empty() {
+            return Err(NewEmbedderError::missing_parameters(self.source, missing));
+        }
+
+        Ok(match self.source {
+            EmbedderSource::HuggingFace => {
+                let default = hf::EmbedderOptions::default();
+                // the default revision only applies to the default model
+                let (model, revision) = match self.model {
+                    Some(model) => (model, self.revision),
+                    None => (default.model, self.revision.or(default.revision)),
+                };
+                SubEmbedderOptions::HuggingFace(hf::EmbedderOptions {
+                    model,
+                    revision,
+                    distribution: self.distribution,
+                    pooling: self.pooling,
+                    normalize: self.normalize,
+                })
+            }
+            EmbedderSource::OpenAi => {
+                let embedding_model = match self.model {
+                    Some(model) => openai::EmbeddingModel::from_name(&model)
+                        .ok_or_else(|| NewEmbedderError::unknown_openai_model(model))?,
+                    None => Default::default(),
+                };
+                SubEmbedderOptions::OpenAi(openai::EmbedderOptions {
+                    url: self.url,
+                    api_key: self.api_key,
+                    embedding_model,
+                    dimensions: self.dimensions,
+                    distribution: self.distribution,
+                    normalize: self.normalize,
+                })
+            }
+            EmbedderSource::Ollama => SubEmbedderOptions::Ollama(ollama::EmbedderOptions {
+                embedding_model: self.model.unwrap_or_default(),
+                url: self.url,
+                api_key: self.api_key,
+                distribution: self.distribution,
+                dimensions: self.dimensions,
+                normalize: self.normalize,
+            }),
+            EmbedderSource::UserProvided => {
+                SubEmbedderOptions::UserProvided(manual::EmbedderOptions {
+                    dimensions: self.dimensions.unwrap_or_default(),
+                    distribution: self.distribution,
+                    normalize: self.normalize,
+                })
+            }
+            EmbedderSource::Rest => SubEmbedderOptions::Rest(rest::EmbedderOptions {
+                api_key: self.api_key,
+                distribution: self.distribution,
+                dimensions: self.dimensions,
+                url: self.url.unwrap_or_default(),
+                request: self.request.unwrap_or_default(),
+                response: self.response.unwrap_or_default(),
+                headers: self.headers,
+                normalize: self.normalize,
+                batch_encoding: self.batch_encoding,
+            }),
+            EmbedderSource::Composite => unreachable!("rejected above"),
+        })
+    }
+}
+
+/// The text embedded by [`SubEmbedder::health_check`].
+const HEALTH_CHECK_TEXT: &str = "health check";
+
+/// Embeds the queries with a search embedder and the documents with an indexing embedder.
+///
+/// Both embedders produce a single vector per text: the arroy index of an embedder only stores
+/// vectors of its dimensions, and the search compares one query vector to them.
 #[derive(Debug)]
 pub struct Embedder {
-    pub(super) search: SubEmbedder,
-    pub(super) index: SubEmbedder,
+    /// Shared with `index` when both embedders have the same options.
+    pub(super) search: Arc<SubEmbedder>,
+    pub(super) index: Arc<SubEmbedder>,
+    /// Prepended to the queries before embedding them.
+    search_prompt_prefix: Option<String>,
+    /// Prepended to the rendered documents before embedding them.
+    index_prompt_prefix: Option<String>,
+}
+
+/// The distance metric the index uses to compare embeddings.
+///
+/// The embeddings of the search and indexing embedders are compared with this metric
+/// when checking that the two embedders are compatible.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Hash,
+    PartialEq,
+    Eq,
+    serde::Deserialize,
+    serde::Serialize,
+    utoipa::ToSchema,
+    deserr::Deserr,
+)]
+#[deserr(rename_all = camelCase, deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub enum DistanceMetric {
+    #[default]
+    Cosine,
+    Euclidean,
+    Manhattan,
+    DotProduct,
+    BinaryQuantizedCosine,
+    BinaryQuantizedEuclidean,
+    BinaryQuantizedManhattan,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub struct EmbedderOptions {
     pub search: SubEmbedderOptions,
     pub index: SubEmbedderOptions,
+    /// The distance metric used to check that the search and indexing embedders are similar.
+    #[serde(default)]
+    pub distance: DistanceMetric,
+    /// Prepended to the queries before embedding them, e.g. `query: ` for the E5 models.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_prompt_prefix: Option<String>,
+    /// Prepended to the rendered documents before embedding them, e.g. `passage: ` for the E5 models.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_prompt_prefix: Option<String>,
+    /// Preprocess the texts before embedding them, at search and at indexing.
+    #[serde(default, skip_serializing_if = "TextPreprocess::is_none")]
+    pub preprocess: TextPreprocess,
+    /// Whether the queries are binary quantized before being compared to the documents.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub search_binary_quantized: bool,
+    /// Whether the embeddings of the documents are binary quantized in the index.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub index_binary_quantized: bool,
+}
+
+impl EmbedderOptions {
+    /// The maximum number of bytes of the rendered document template once the
+    /// `documentTemplateMaxBytes` are shared with the index prompt prefix.
+    ///
+    /// Fails when one of the prefixes leaves no room for the text it is prepended to.
+    pub fn document_max_bytes(
+        &self,
+        max_bytes: Option<NonZeroUsize>,
+    ) -> Result<Option<NonZeroUsize>, NewEmbedderError> {
+        let Some(max_bytes) = max_bytes else { return Ok(None) };
+        for (side, prefix) in
+            [("search", &self.search_prompt_prefix), ("index", &self.index_prompt_prefix)]
+        {
+            let len = prefix.as_ref().map_or(0, String::len);
+            if len >= max_bytes.get() {
+                return Err(NewEmbedderError::composite_prompt_prefix_too_long(
+                    side,
+                    len,
+                    max_bytes.get(),
+                ));
+            }
+        }
+        let index_len = self.index_prompt_prefix.as_ref().map_or(0, String::len);
+        Ok(NonZeroUsize::new(max_bytes.get() - index_len))
+    }
+}
+
+type SimilarityCheck = fn(
+    Vec<Embedding>,
+    Vec<Embedding>,
+    CompositeEmbedderContainsHuggingFace,
+) -> Result<(), NewEmbedderError>;
+
+impl DistanceMetric {
+    /// The similarity check comparing embeddings with the arroy distance of this metric.
+    fn similarity_check(self) -> Result<SimilarityCheck, NewEmbedderError> {
+        // the dot product is not a distance and binary quantized embeddings lose too much
+        // precision to tell whether the original embeddings are similar
+        match self {
+            DistanceMetric::Cosine => Ok(check_similarity::<arroy::distances::Cosine>),
+            DistanceMetric::Euclidean => Ok(check_similarity::<arroy::distances::Euclidean>),
+            DistanceMetric::Manhattan => Ok(check_similarity::<arroy::distances::Manhattan>),
+            DistanceMetric::DotProduct
+            | DistanceMetric::BinaryQuantizedCosine
+            | DistanceMetric::BinaryQuantizedEuclidean
+            | DistanceMetric::BinaryQuantizedManhattan => {
+                Err(NewEmbedderError::composite_distance_unsupported(self))
+            }
+        }
+    }
+}
+
+/// The similarity check of the search and indexing embedders given whether their embeddings
+/// are binary quantized.
+///
+/// The binary quantized embeddings of the index only keep the sign of each dimension, so the
+/// queries are compared to them by their signs whether they are quantized or not. Quantizing
+/// the queries of an index keeping the full-precision embeddings is rejected.
+fn quantized_similarity_check(
+    distance: DistanceMetric,
+    search_binary_quantized: bool,
+    index_binary_quantized: bool,
+) -> Result<SimilarityCheck, NewEmbedderError> {
+    match (search_binary_quantized, index_binary_quantized) {
+        (false, false) => distance.similarity_check(),
+        (_, true) => Ok(check_quantized_similarity),
+        (true, false) => Err(NewEmbedderError::composite_search_quantization_unsupported()),
+    }
 }
 
 impl Embedder {
-    pub fn new(
-        EmbedderOptions { search, index }: EmbedderOptions,
+    pub fn new(options: EmbedderOptions, cache_cap: usize) -> Result<Self, NewEmbedderError> {
+        Self::build(options, cache_cap, None)
+    }
+
+    /// Like [`Self::new`], but fails when the test embeddings checking the similarity
+    /// of the search and indexing embedders are not computed before the `deadline`.
+    pub fn new_with_deadline(
+        options: EmbedderOptions,
         cache_cap: usize,
+        deadline: Instant,
     ) -> Result<Self, NewEmbedderError> {
-        let search = SubEmbedder::new(search, cache_cap)?;
-        // cache is only used at search
-        let index = SubEmbedder::new(index, 0)?;
-
-        // check dimensions
-        if search.dimensions() != index.dimensions() {
-            return Err(NewEmbedderError::composite_dimensions_mismatch(
-                search.dimensions(),
-                index.dimensions(),
-            ));
+        Self::build(options, cache_cap, Some(deadline))
+    }
+
+    fn build(
+        EmbedderOptions {
+            search,
+            index,
+            distance,
+            search_prompt_prefix,
+            index_prompt_prefix,
+            preprocess,
+            search_binary_quantized,
+            index_binary_quantized,
+        }: EmbedderOptions,
+        cache_cap: usize,
+        deadline: Option<Instant>,
+    ) -> Result<Self, NewEmbedderError> {
+        let check_similarity =
+            quantized_similarity_check(distance, search_binary_quantized, index_binary_quantized)?;
+        let (search, index) =
+            new_pair(search, index, preprocess, check_similarity, cache_cap, deadline)?;
+
+        Ok(Self { search, index, search_prompt_prefix, index_prompt_prefix })
+    }
+
+    /// Returns the query as embedded by the search embedder, prefixed then preprocessed.
+    pub fn search_text<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        match &self.search_prompt_prefix {
+            Some(prefix) => {
+                Cow::Owned(self.search.preprocess(&format!("{prefix}{text}")).into_owned())
+            }
+            None => self.search.preprocess(text),
         }
-        // check similarity
-        let search_embeddings = search
-            .embed(
-                vec![
-                    "test".into(),
-                    "a brave dog".into(),
-                    "This is a sample text. It is meant to compare similarity.".into(),
-                ],
-                None,
-            )
-            .map_err(|error| NewEmbedderError::composite_test_embedding_failed(error, "search"))?;
-
-        let index_embeddings = index
-            .embed(
-                vec![
-                    "test".into(),
-                    "a brave dog".into(),
-                    "This is a sample text. It is meant to compare similarity.".into(),
-                ],
-                None,
-            )
-            .map_err(|error| {
-                NewEmbedderError::composite_test_embedding_failed(error, "indexing")
-            })?;
+    }
 
-        let hint = configuration_hint(&search, &index);
+    fn index_texts(&self, texts: Vec<String>) -> Vec<String> {
+        match &self.index_prompt_prefix {
+            Some(prefix) => texts.into_iter().map(|text| format!("{prefix}{text}")).collect(),
+            None => texts,
+        }
+    }
 
-        check_similarity(search_embeddings, index_embeddings, hint)?;
+    /// Embeds the chunks of rendered documents with the indexing embedder, prefixed
+    /// with the index prompt prefix.
+    pub fn embed_index(
+        &self,
+        text_chunks: Vec<Vec<String>>,
+        threads: &ThreadPoolNoAbort,
+    ) -> std::result::Result<Vec<Vec<Embedding>>, EmbedError> {
+        let text_chunks = if self.index_prompt_prefix.is_some() {
+            text_chunks.into_iter().map(|texts| self.index_texts(texts)).collect()
+        } else {
+            text_chunks
+        };
+        self.index.embed_index(text_chunks, threads)
+    }
 
-        Ok(Self { search, index })
+    pub fn embed_index_labeled(
+        &self,
+        labeled_chunks: Vec<(FieldLabel, Vec<String>)>,
+        threads: &ThreadPoolNoAbort,
+    ) -> std::result::Result<Vec<(FieldLabel, Vec<Embedding>)>, EmbedError> {
+        embed_labeled_chunks(labeled_chunks, self.dimensions(), |text_chunks| {
+            self.embed_index(text_chunks, threads)
+        })
+    }
+
+    /// Non-owning variant of [`Self::embed_index`].
+    pub fn embed_index_ref(
+        &self,
+        texts: &[&str],
+        threads: &ThreadPoolNoAbort,
+    ) -> std::result::Result<Vec<Embedding>, EmbedError> {
+        match &self.index_prompt_prefix {
+            Some(prefix) => {
+                let prefixed: Vec<_> = texts.iter().map(|text| format!("{prefix}{text}")).collect();
+                let texts: Vec<&str> = prefixed.iter().map(String::as_str).collect();
+                self.index.embed_index_ref(&texts, threads)
+            }
+            None => self.index.embed_index_ref(texts, threads),
+        }
     }
 
     /// Indicates the dimensions of a single embedding produced by the embedder.
@@ -110,6 +621,30 @@ impl Embedder {
         self.index.dimensions()
     }
 
+    /// Checks that the search and indexing embedders can reach their provider,
+    /// see [`SubEmbedder::health_check`].
+    ///
+    /// An embedder shared by the search and the indexing is only checked once.
+    pub fn health_check(&self, deadline: Option<Instant>) -> Result<(), EmbedError> {
+        self.search.health_check(deadline)?;
+        if !Arc::ptr_eq(&self.search, &self.index) {
+            self.index.health_check(deadline)?;
+        }
+        Ok(())
+    }
+
+    /// Prepares a vector provided by the user to be indexed, so that it can be compared
+    /// with the embeddings of the queries produced by the search embedder.
+    pub fn index_user_vector(&self, mut vector: Vec<f32>) -> Result<Embedding, EmbedError> {
+        if vector.len() != self.search.dimensions() {
+            return Err(EmbedError::unexpected_dimension(self.search.dimensions(), vector.len()));
+        }
+        if self.index.normalize {
+            l2_normalize(&mut vector);
+        }
+        Ok(vector)
+    }
+
     /// An optional distribution used to apply an affine transformation to the similarity score of a document.
     pub fn distribution(&self) -> Option<DistributionShift> {
         // 3 cases here:
@@ -120,30 +655,141 @@ impl Embedder {
     }
 }
 
+/// Builds the search and indexing embedders of a vector, checking that they produce
+/// embeddings of the same dimensions and similar enough to be compared.
+fn new_pair(
+    search: SubEmbedderOptions,
+    index: SubEmbedderOptions,
+    preprocess: TextPreprocess,
+    check_similarity: SimilarityCheck,
+    cache_cap: usize,
+    deadline: Option<Instant>,
+) -> Result<(Arc<SubEmbedder>, Arc<SubEmbedder>), NewEmbedderError> {
+    // the same options build the same model, there is nothing to check
+    if search == index {
+        // the cache is only used at search, the indexing methods never look it up
+        let embedder = SubEmbedder::new(search, cache_cap)?.with_preprocess(preprocess);
+        let embedder = Arc::new(embedder);
+        return Ok((embedder.clone(), embedder));
+    }
+
+    let search = Arc::new(SubEmbedder::new(search, cache_cap)?.with_preprocess(preprocess));
+    // cache is only used at search
+    let index = Arc::new(SubEmbedder::new(index, 0)?.with_preprocess(preprocess));
+
+    // check dimensions
+    if search.dimensions() != index.dimensions() {
+        return Err(NewEmbedderError::composite_dimensions_mismatch(
+            search.dimensions(),
+            index.dimensions(),
+        ));
+    }
+    // the user-provided embeddings can't be compared to the embeddings of the search
+    // embedder, making them similar is left to the user
+    if let SubEmbedderKind::UserProvided(_) = &index.kind {
+        return Ok((search, index));
+    }
+
+    // check similarity
+    let test_embeddings = |embedder: &Arc<SubEmbedder>, failing_embedder| {
+        let texts = vec![
+            "test".into(),
+            "a brave dog".into(),
+            "This is a sample text. It is meant to compare similarity.".into(),
+        ];
+        let embeddings = match deadline {
+            // by chunks, as the embedders taking a single text per request reject a batch
+            None => embedder.embed_auto_batched(texts, None),
+            // the local models never look at the deadline, the embeddings are waited for
+            // on another thread that is left to finish in the background once it elapsed
+            Some(deadline) => {
+                let (sender, receiver) = std::sync::mpsc::channel();
+                let embedder = embedder.clone();
+                std::thread::spawn(move || {
+                    let _ = sender.send(embedder.embed_auto_batched(texts, Some(deadline)));
+                });
+                match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    // the remote embedders give up at the deadline with a network error
+                    Ok(embeddings) if Instant::now() <= deadline => embeddings,
+                    _ => {
+                        return Err(NewEmbedderError::composite_test_embedding_timeout(
+                            failing_embedder,
+                        ))
+                    }
+                }
+            }
+        };
+        embeddings.map_err(|error| {
+            NewEmbedderError::composite_test_embedding_failed(error, failing_embedder)
+        })
+    };
+    let search_embeddings = test_embeddings(&search, "search")?;
+    let index_embeddings = test_embeddings(&index, "indexing")?;
+
+    let hint = configuration_hint(&search, &index);
+
+    check_similarity(search_embeddings, index_embeddings, hint)?;
+
+    Ok((search, index))
+}
+
 impl SubEmbedder {
     pub fn new(
         options: SubEmbedderOptions,
         cache_cap: usize,
     ) -> std::result::Result<Self, NewEmbedderError> {
-        Ok(match options {
+        let normalize = options.normalize();
+        let kind = match options {
             SubEmbedderOptions::HuggingFace(options) => {
-                Self::HuggingFace(hf::Embedder::new(options, cache_cap)?)
+                SubEmbedderKind::HuggingFace(hf::Embedder::new(options, cache_cap)?)
             }
             SubEmbedderOptions::OpenAi(options) => {
-                Self::OpenAi(openai::Embedder::new(options, cache_cap)?)
+                SubEmbedderKind::OpenAi(openai::Embedder::new(options, cache_cap)?)
             }
             SubEmbedderOptions::Ollama(options) => {
-                Self::Ollama(ollama::Embedder::new(options, cache_cap)?)
+                SubEmbedderKind::Ollama(ollama::Embedder::new(options, cache_cap)?)
             }
             SubEmbedderOptions::UserProvided(options) => {
-                Self::UserProvided(manual::Embedder::new(options))
+                SubEmbedderKind::UserProvided(manual::Embedder::new(options))
             }
-            SubEmbedderOptions::Rest(options) => Self::Rest(rest::Embedder::new(
+            SubEmbedderOptions::Rest(options) => SubEmbedderKind::Rest(rest::Embedder::new(
                 options,
                 cache_cap,
                 rest::ConfigurationSource::User,
             )?),
-        })
+        };
+        // the remote embedders are billed by request, the local ones are not worth the hashing
+        let deduplicate_texts = matches!(
+            kind,
+            SubEmbedderKind::OpenAi(_) | SubEmbedderKind::Ollama(_) | SubEmbedderKind::Rest(_)
+        );
+        Ok(Self { kind, normalize, preprocess: TextPreprocess::NONE, deduplicate_texts })
+    }
+
+    /// Sets the normalizations applied to the texts before embedding them or looking them up
+    /// in the cache, none by default.
+    pub fn with_preprocess(mut self, preprocess: TextPreprocess) -> Self {
+        self.preprocess = preprocess;
+        self
+    }
+
+    /// Sets whether [`Self::embed_index`] embeds the identical texts of the chunks only once,
+    /// which is enabled by default for the embedders making requests to a remote server.
+    pub fn with_deduplicated_texts(mut self, deduplicate_texts: bool) -> Self {
+        self.deduplicate_texts = deduplicate_texts;
+        self
+    }
+
+    /// Returns the `text` preprocessed like the texts embedded by this embedder.
+    pub fn preprocess<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        self.preprocess.apply(text)
+    }
+
+    fn preprocess_all(&self, texts: Vec<String>) -> Vec<String> {
+        if self.preprocess == TextPreprocess::NONE {
+            return texts;
+        }
+        texts.iter().map(|text| self.preprocess(text).into_owned()).collect()
     }
 
     pub fn embed(
@@ -151,13 +797,39 @@ impl SubEmbedder {
         texts: Vec<String>,
         deadline: Option<Instant>,
     ) -> std::result::Result<Vec<Embedding>, EmbedError> {
-        match self {
-            SubEmbedder::HuggingFace(embedder) => embedder.embed(texts),
-            SubEmbedder::OpenAi(embedder) => embedder.embed(&texts, deadline),
-            SubEmbedder::Ollama(embedder) => embedder.embed(&texts, deadline),
-            SubEmbedder::UserProvided(embedder) => embedder.embed(&texts),
-            SubEmbedder::Rest(embedder) => embedder.embed(texts, deadline),
+        let texts = self.preprocess_all(texts);
+        let mut embeddings = match &self.kind {
+            SubEmbedderKind::HuggingFace(embedder) => embedder.embed(texts),
+            SubEmbedderKind::OpenAi(embedder) => embedder.embed(&texts, deadline),
+            SubEmbedderKind::Ollama(embedder) => embedder.embed(&texts, deadline),
+            SubEmbedderKind::UserProvided(embedder) => embedder.embed(&texts),
+            SubEmbedderKind::Rest(embedder) => embedder.embed(texts, deadline),
+        }?;
+        self.normalize_all(&mut embeddings);
+        Ok(embeddings)
+    }
+
+    /// Embeds each token of the texts rather than one pooled embedding per text,
+    /// for the late-interaction models comparing the texts token by token.
+    ///
+    /// Only the Hugging Face embedders produce token embeddings, the other sources
+    /// return an [`EmbedError::unsupported`] error.
+    pub fn embed_tokens(
+        &self,
+        texts: Vec<String>,
+    ) -> std::result::Result<Vec<Vec<Embedding>>, EmbedError> {
+        let texts = self.preprocess_all(texts);
+        let mut embeddings = match &self.kind {
+            SubEmbedderKind::HuggingFace(embedder) => embedder.embed_tokens(texts),
+            SubEmbedderKind::OpenAi(_) => Err(EmbedError::unsupported("openAi")),
+            SubEmbedderKind::Ollama(_) => Err(EmbedError::unsupported("ollama")),
+            SubEmbedderKind::UserProvided(_) => Err(EmbedError::unsupported("userProvided")),
+            SubEmbedderKind::Rest(_) => Err(EmbedError::unsupported("rest")),
+        }?;
+        for token_embeddings in &mut embeddings {
+            self.normalize_all(token_embeddings);
         }
+        Ok(embeddings)
     }
 
     pub fn embed_one(
@@ -165,37 +837,184 @@ impl SubEmbedder {
         text: &str,
         deadline: Option<Instant>,
     ) -> std::result::Result<Embedding, EmbedError> {
-        match self {
-            SubEmbedder::HuggingFace(embedder) => embedder.embed_one(text),
-            SubEmbedder::OpenAi(embedder) => {
+        self.embed_one_preprocessed(&self.preprocess(text), deadline)
+    }
+
+    fn embed_one_preprocessed(
+        &self,
+        text: &str,
+        deadline: Option<Instant>,
+    ) -> std::result::Result<Embedding, EmbedError> {
+        let mut embedding = match &self.kind {
+            SubEmbedderKind::HuggingFace(embedder) => embedder.embed_one(text),
+            SubEmbedderKind::OpenAi(embedder) => {
                 embedder.embed(&[text], deadline)?.pop().ok_or_else(EmbedError::missing_embedding)
             }
-            SubEmbedder::Ollama(embedder) => {
+            SubEmbedderKind::Ollama(embedder) => {
                 embedder.embed(&[text], deadline)?.pop().ok_or_else(EmbedError::missing_embedding)
             }
-            SubEmbedder::UserProvided(embedder) => embedder.embed_one(text),
-            SubEmbedder::Rest(embedder) => embedder
+            SubEmbedderKind::UserProvided(embedder) => embedder.embed_one(text),
+            SubEmbedderKind::Rest(embedder) => embedder
                 .embed_ref(&[text], deadline)?
                 .pop()
                 .ok_or_else(EmbedError::missing_embedding),
+        }?;
+        if self.normalize {
+            l2_normalize(&mut embedding);
+        }
+        Ok(embedding)
+    }
+
+    /// Like [`Self::embed_one`], but looks up the embedding in the cache first
+    /// and reports whether it was found there.
+    ///
+    /// The embedders without a cache always report a [`CacheOutcome::Miss`].
+    pub fn embed_one_cached(
+        &self,
+        text: &str,
+        deadline: Option<Instant>,
+    ) -> std::result::Result<(Embedding, CacheOutcome), EmbedError> {
+        let text = self.preprocess(text);
+        if let Some(embedding) = self.cache().and_then(|cache| cache.get(&text)) {
+            tracing::trace!(%text, "embedding found in cache");
+            return Ok((embedding, CacheOutcome::Hit));
+        }
+
+        let embedding = self.embed_one_preprocessed(&text, deadline)?;
+        if let Some(cache) = self.cache() {
+            cache.put(text.into_owned(), embedding.clone());
+        }
+        Ok((embedding, CacheOutcome::Miss))
+    }
+
+    /// Checks that the embedder can reach its provider, for the readiness probes.
+    ///
+    /// Embeds a short constant text without looking at the cache, so that every check makes
+    /// a request, and returns the error of the provider classified by its [`EmbedError::fault`].
+    /// The user provided embedders have no provider to reach and are always healthy.
+    pub fn health_check(&self, deadline: Option<Instant>) -> std::result::Result<(), EmbedError> {
+        if let SubEmbedderKind::UserProvided(_) = self.kind {
+            return Ok(());
+        }
+
+        let embedding = self.embed_one_preprocessed(HEALTH_CHECK_TEXT, deadline)?;
+        if embedding.len() != self.dimensions() {
+            return Err(EmbedError::unexpected_dimension(self.dimensions(), embedding.len()));
         }
+        Ok(())
+    }
+
+    /// Renders the `prompt` for the `sample_doc` and embeds the rendered text once,
+    /// to check that a document template suits the documents before indexing them.
+    pub fn validate_template(
+        &self,
+        prompt: &Prompt,
+        sample_doc: &Object,
+        deadline: Option<Instant>,
+    ) -> crate::Result<TemplateReport> {
+        let (rendered, truncated) = prompt.render_object(sample_doc)?;
+        let embedding = self.embed_one(&rendered, deadline).map_err(crate::vector::Error::from)?;
+        Ok(TemplateReport {
+            rendered_bytes: rendered.len(),
+            truncated,
+            embedding_dims: embedding.len(),
+        })
+    }
+
+    /// Embeds the texts like [`Self::embed`], by chunks of at most
+    /// [`Self::prompt_count_in_chunk_hint`] texts so that no request exceeds the batch size
+    /// the embedder expects.
+    ///
+    /// The embeddings are returned in the order of the texts.
+    pub fn embed_auto_batched(
+        &self,
+        texts: Vec<String>,
+        deadline: Option<Instant>,
+    ) -> std::result::Result<Vec<Embedding>, EmbedError> {
+        embed_by_chunks(texts, self.prompt_count_in_chunk_hint(), |chunk| {
+            self.embed(chunk, deadline)
+        })
     }
 
     /// Embed multiple chunks of texts.
     ///
-    /// Each chunk is composed of one or multiple texts.
+    /// Each chunk is composed of one or multiple texts. The identical texts are embedded
+    /// only once unless disabled with [`Self::with_deduplicated_texts`].
     pub fn embed_index(
         &self,
         text_chunks: Vec<Vec<String>>,
         threads: &ThreadPoolNoAbort,
     ) -> std::result::Result<Vec<Vec<Embedding>>, EmbedError> {
-        match self {
-            SubEmbedder::HuggingFace(embedder) => embedder.embed_index(text_chunks),
-            SubEmbedder::OpenAi(embedder) => embedder.embed_index(text_chunks, threads),
-            SubEmbedder::Ollama(embedder) => embedder.embed_index(text_chunks, threads),
-            SubEmbedder::UserProvided(embedder) => embedder.embed_index(text_chunks),
-            SubEmbedder::Rest(embedder) => embedder.embed_index(text_chunks, threads),
+        // preprocessed first, so that the texts differing only by their spacing are deduplicated
+        let text_chunks = if self.preprocess == TextPreprocess::NONE {
+            text_chunks
+        } else {
+            text_chunks.into_iter().map(|texts| self.preprocess_all(texts)).collect()
+        };
+        if self.deduplicate_texts {
+            embed_deduplicated(text_chunks, self.prompt_count_in_chunk_hint(), |text_chunks| {
+                self.embed_index_chunks(text_chunks, threads)
+            })
+        } else {
+            self.embed_index_chunks(text_chunks, threads)
+        }
+    }
+
+    fn embed_index_chunks(
+        &self,
+        text_chunks: Vec<Vec<String>>,
+        threads: &ThreadPoolNoAbort,
+    ) -> std::result::Result<Vec<Vec<Embedding>>, EmbedError> {
+        let mut embeddings = match &self.kind {
+            SubEmbedderKind::HuggingFace(embedder) => embedder.embed_index(text_chunks),
+            SubEmbedderKind::OpenAi(embedder) => embedder.embed_index(text_chunks, threads),
+            SubEmbedderKind::Ollama(embedder) => embedder.embed_index(text_chunks, threads),
+            SubEmbedderKind::UserProvided(embedder) => embedder.embed_index(text_chunks),
+            SubEmbedderKind::Rest(embedder) => embedder.embed_index(text_chunks, threads),
+        }?;
+        for embeddings in &mut embeddings {
+            self.normalize_all(embeddings);
         }
+        Ok(embeddings)
+    }
+
+    /// Embeds the chunks like [`Self::embed_index`], passing the embeddings of every chunk
+    /// to `callback` along with the index of the chunk, in the order of the chunks.
+    ///
+    /// The chunks are embedded by windows of [`Self::chunk_count_hint`] chunks, so that only
+    /// the embeddings of a single window are held in memory before being handed to `callback`.
+    pub fn embed_index_streaming<E>(
+        &self,
+        text_chunks: Vec<Vec<String>>,
+        threads: &ThreadPoolNoAbort,
+        mut callback: impl FnMut(usize, Vec<Embedding>) -> std::result::Result<(), E>,
+    ) -> std::result::Result<(), E>
+    where
+        E: From<EmbedError>,
+    {
+        let window_size = self.chunk_count_hint().max(1);
+        let mut text_chunks = text_chunks.into_iter();
+        let mut chunk_index = 0;
+        loop {
+            let window: Vec<_> = text_chunks.by_ref().take(window_size).collect();
+            if window.is_empty() {
+                return Ok(());
+            }
+            for embeddings in self.embed_index(window, threads)? {
+                callback(chunk_index, embeddings)?;
+                chunk_index += 1;
+            }
+        }
+    }
+
+    pub fn embed_index_labeled(
+        &self,
+        labeled_chunks: Vec<(FieldLabel, Vec<String>)>,
+        threads: &ThreadPoolNoAbort,
+    ) -> std::result::Result<Vec<(FieldLabel, Vec<Embedding>)>, EmbedError> {
+        embed_labeled_chunks(labeled_chunks, self.dimensions(), |text_chunks| {
+            self.embed_index(text_chunks, threads)
+        })
     }
 
     /// Non-owning variant of [`Self::embed_index`].
@@ -204,81 +1023,135 @@ impl SubEmbedder {
         texts: &[&str],
         threads: &ThreadPoolNoAbort,
     ) -> std::result::Result<Vec<Embedding>, EmbedError> {
-        match self {
-            SubEmbedder::HuggingFace(embedder) => embedder.embed_index_ref(texts),
-            SubEmbedder::OpenAi(embedder) => embedder.embed_index_ref(texts, threads),
-            SubEmbedder::Ollama(embedder) => embedder.embed_index_ref(texts, threads),
-            SubEmbedder::UserProvided(embedder) => embedder.embed_index_ref(texts),
-            SubEmbedder::Rest(embedder) => embedder.embed_index_ref(texts, threads),
+        let preprocessed: Vec<_> = texts.iter().map(|text| self.preprocess(text)).collect();
+        let texts: Vec<&str> = preprocessed.iter().map(AsRef::as_ref).collect();
+        let texts = texts.as_slice();
+        let mut embeddings = match &self.kind {
+            SubEmbedderKind::HuggingFace(embedder) => embedder.embed_index_ref(texts),
+            SubEmbedderKind::OpenAi(embedder) => embedder.embed_index_ref(texts, threads),
+            SubEmbedderKind::Ollama(embedder) => embedder.embed_index_ref(texts, threads),
+            SubEmbedderKind::UserProvided(embedder) => embedder.embed_index_ref(texts),
+            SubEmbedderKind::Rest(embedder) => embedder.embed_index_ref(texts, threads),
+        }?;
+        self.normalize_all(&mut embeddings);
+        Ok(embeddings)
+    }
+
+    fn normalize_all(&self, embeddings: &mut [Embedding]) {
+        if self.normalize {
+            embeddings.iter_mut().for_each(l2_normalize);
         }
     }
 
     /// Indicates the preferred number of chunks to pass to [`Self::embed_chunks`]
     pub fn chunk_count_hint(&self) -> usize {
-        match self {
-            SubEmbedder::HuggingFace(embedder) => embedder.chunk_count_hint(),
-            SubEmbedder::OpenAi(embedder) => embedder.chunk_count_hint(),
-            SubEmbedder::Ollama(embedder) => embedder.chunk_count_hint(),
-            SubEmbedder::UserProvided(_) => 100,
-            SubEmbedder::Rest(embedder) => embedder.chunk_count_hint(),
+        match &self.kind {
+            SubEmbedderKind::HuggingFace(embedder) => embedder.chunk_count_hint(),
+            SubEmbedderKind::OpenAi(embedder) => embedder.chunk_count_hint(),
+            SubEmbedderKind::Ollama(embedder) => embedder.chunk_count_hint(),
+            SubEmbedderKind::UserProvided(_) => 100,
+            SubEmbedderKind::Rest(embedder) => embedder.chunk_count_hint(),
         }
     }
 
     /// Indicates the preferred number of texts in a single chunk passed to [`Self::embed`]
     pub fn prompt_count_in_chunk_hint(&self) -> usize {
-        match self {
-            SubEmbedder::HuggingFace(embedder) => embedder.prompt_count_in_chunk_hint(),
-            SubEmbedder::OpenAi(embedder) => embedder.prompt_count_in_chunk_hint(),
-            SubEmbedder::Ollama(embedder) => embedder.prompt_count_in_chunk_hint(),
-            SubEmbedder::UserProvided(_) => 1,
-            SubEmbedder::Rest(embedder) => embedder.prompt_count_in_chunk_hint(),
+        match &self.kind {
+            SubEmbedderKind::HuggingFace(embedder) => embedder.prompt_count_in_chunk_hint(),
+            SubEmbedderKind::OpenAi(embedder) => embedder.prompt_count_in_chunk_hint(),
+            SubEmbedderKind::Ollama(embedder) => embedder.prompt_count_in_chunk_hint(),
+            SubEmbedderKind::UserProvided(_) => 1,
+            SubEmbedderKind::Rest(embedder) => embedder.prompt_count_in_chunk_hint(),
         }
     }
 
     pub fn uses_document_template(&self) -> bool {
-        match self {
-            SubEmbedder::HuggingFace(_)
-            | SubEmbedder::OpenAi(_)
-            | SubEmbedder::Ollama(_)
-            | SubEmbedder::Rest(_) => true,
-            SubEmbedder::UserProvided(_) => false,
+        match &self.kind {
+            SubEmbedderKind::HuggingFace(_)
+            | SubEmbedderKind::OpenAi(_)
+            | SubEmbedderKind::Ollama(_)
+            | SubEmbedderKind::Rest(_) => true,
+            SubEmbedderKind::UserProvided(_) => false,
         }
     }
 
     /// Indicates the dimensions of a single embedding produced by the embedder.
     pub fn dimensions(&self) -> usize {
-        match self {
-            SubEmbedder::HuggingFace(embedder) => embedder.dimensions(),
-            SubEmbedder::OpenAi(embedder) => embedder.dimensions(),
-            SubEmbedder::Ollama(embedder) => embedder.dimensions(),
-            SubEmbedder::UserProvided(embedder) => embedder.dimensions(),
-            SubEmbedder::Rest(embedder) => embedder.dimensions(),
+        match &self.kind {
+            SubEmbedderKind::HuggingFace(embedder) => embedder.dimensions(),
+            SubEmbedderKind::OpenAi(embedder) => embedder.dimensions(),
+            SubEmbedderKind::Ollama(embedder) => embedder.dimensions(),
+            SubEmbedderKind::UserProvided(embedder) => embedder.dimensions(),
+            SubEmbedderKind::Rest(embedder) => embedder.dimensions(),
         }
     }
 
     /// An optional distribution used to apply an affine transformation to the similarity score of a document.
     pub fn distribution(&self) -> Option<DistributionShift> {
-        match self {
-            SubEmbedder::HuggingFace(embedder) => embedder.distribution(),
-            SubEmbedder::OpenAi(embedder) => embedder.distribution(),
-            SubEmbedder::Ollama(embedder) => embedder.distribution(),
-            SubEmbedder::UserProvided(embedder) => embedder.distribution(),
-            SubEmbedder::Rest(embedder) => embedder.distribution(),
+        match &self.kind {
+            SubEmbedderKind::HuggingFace(embedder) => embedder.distribution(),
+            SubEmbedderKind::OpenAi(embedder) => embedder.distribution(),
+            SubEmbedderKind::Ollama(embedder) => embedder.distribution(),
+            SubEmbedderKind::UserProvided(embedder) => embedder.distribution(),
+            SubEmbedderKind::Rest(embedder) => embedder.distribution(),
         }
     }
 
     pub(super) fn cache(&self) -> Option<&EmbeddingCache> {
-        match self {
-            SubEmbedder::HuggingFace(embedder) => Some(embedder.cache()),
-            SubEmbedder::OpenAi(embedder) => Some(embedder.cache()),
-            SubEmbedder::UserProvided(_) => None,
-            SubEmbedder::Ollama(embedder) => Some(embedder.cache()),
-            SubEmbedder::Rest(embedder) => Some(embedder.cache()),
+        match &self.kind {
+            SubEmbedderKind::HuggingFace(embedder) => Some(embedder.cache()),
+            SubEmbedderKind::OpenAi(embedder) => Some(embedder.cache()),
+            SubEmbedderKind::UserProvided(_) => None,
+            SubEmbedderKind::Ollama(embedder) => Some(embedder.cache()),
+            SubEmbedderKind::Rest(embedder) => Some(embedder.cache()),
         }
     }
 }
 
-fn check_similarity(
+/// A distance the embeddings of the search and indexing embedders can be compared with.
+trait SimilarityDistance: Distance {
+    /// The metric of the distance, reported in the errors.
+    const METRIC: DistanceMetric;
+    /// The maximum distance between the embeddings of similar embedders.
+    const MAX_DISTANCE: f32;
+
+    /// The magnitude of the embedding the distance is divided by, making it independent
+    /// of the scale of the embeddings.
+    fn magnitude(embedding: &[f32]) -> f32;
+}
+
+impl SimilarityDistance for arroy::distances::Cosine {
+    const METRIC: DistanceMetric = DistanceMetric::Cosine;
+    const MAX_DISTANCE: f32 = super::MAX_COMPOSITE_DISTANCE;
+
+    fn magnitude(_embedding: &[f32]) -> f32 {
+        // the cosine distance ignores the norms already
+        1.0
+    }
+}
+
+impl SimilarityDistance for arroy::distances::Euclidean {
+    const METRIC: DistanceMetric = DistanceMetric::Euclidean;
+    // the euclidean distance of unit embeddings at the maximum cosine distance
+    const MAX_DISTANCE: f32 = 0.15;
+
+    fn magnitude(embedding: &[f32]) -> f32 {
+        embedding.iter().map(|value| value * value).sum::<f32>().sqrt()
+    }
+}
+
+impl SimilarityDistance for arroy::distances::Manhattan {
+    const METRIC: DistanceMetric = DistanceMetric::Manhattan;
+    const MAX_DISTANCE: f32 = 0.15;
+
+    fn magnitude(embedding: &[f32]) -> f32 {
+        embedding.iter().map(|value| value.abs()).sum()
+    }
+}
+
+/// Fails when the distance between two paired embeddings, relative to the magnitude of the
+/// largest one, exceeds the maximum distance of the metric.
+fn check_similarity<D: SimilarityDistance>(
     left: Vec<Embedding>,
     right: Vec<Embedding>,
     hint: CompositeEmbedderContainsHuggingFace,
@@ -288,36 +1161,1377 @@ fn check_similarity(
     }
 
     for (left, right) in left.into_iter().zip(right) {
+        let dimensions = left.len();
+        let magnitude = D::magnitude(&left).max(D::magnitude(&right));
         let left = arroy::internals::UnalignedVector::from_slice(&left);
         let right = arroy::internals::UnalignedVector::from_slice(&right);
-        let left = arroy::internals::Leaf {
-            header: arroy::distances::Cosine::new_header(&left),
-            vector: left,
-        };
-        let right = arroy::internals::Leaf {
-            header: arroy::distances::Cosine::new_header(&right),
-            vector: right,
-        };
+        let left = arroy::internals::Leaf { header: D::new_header(&left), vector: left };
+        let right = arroy::internals::Leaf { header: D::new_header(&right), vector: right };
 
-        let distance = arroy::distances::Cosine::built_distance(&left, &right);
+        let mut distance = D::normalized_distance(D::built_distance(&left, &right), dimensions);
+        // two null embeddings are at a null distance
+        if magnitude > f32::EPSILON {
+            distance /= magnitude;
+        }
 
-        if distance > super::MAX_COMPOSITE_DISTANCE {
-            return Err(NewEmbedderError::composite_embedding_value_mismatch(distance, hint));
+        if distance > D::MAX_DISTANCE {
+            return Err(NewEmbedderError::composite_embedding_value_mismatch(
+                D::METRIC,
+                distance,
+                D::MAX_DISTANCE,
+                hint,
+            ));
         }
     }
     Ok(())
 }
 
+/// Compares the signs of the embeddings, the only part of them kept by the binary quantization.
+fn check_quantized_similarity(
+    search: Vec<Embedding>,
+    index: Vec<Embedding>,
+    hint: CompositeEmbedderContainsHuggingFace,
+) -> Result<(), NewEmbedderError> {
+    check_similarity::<arroy::distances::Cosine>(
+        search.into_iter().map(binary_quantize).collect(),
+        index.into_iter().map(binary_quantize).collect(),
+        hint,
+    )
+}
+
+/// Maps the positive values of the embedding to `1.0` and the other ones to `-1.0`.
+fn binary_quantize(embedding: Embedding) -> Embedding {
+    embedding.into_iter().map(|value| if value > 0.0 { 1.0 } else { -1.0 }).collect()
+}
+
+/// Scales the embedding to a unit L2 norm, leaving null embeddings untouched.
+fn l2_normalize(embedding: &mut Embedding) {
+    let norm = embedding.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm > f32::EPSILON {
+        embedding.iter_mut().for_each(|value| *value /= norm);
+    }
+}
+
+/// Calls `embed` on the successive chunks of at most `chunk_size` texts,
+/// concatenating their embeddings in the order of the texts.
+fn embed_by_chunks(
+    texts: Vec<String>,
+    chunk_size: usize,
+    mut embed: impl FnMut(Vec<String>) -> std::result::Result<Vec<Embedding>, EmbedError>,
+) -> std::result::Result<Vec<Embedding>, EmbedError> {
+    let chunk_size = chunk_size.max(1);
+    let mut embeddings = Vec::with_capacity(texts.len());
+    let mut texts = texts.into_iter();
+    loop {
+        let chunk: Vec<_> = texts.by_ref().take(chunk_size).collect();
+        if chunk.is_empty() {
+            return Ok(embeddings);
+        }
+        embeddings.extend(embed(chunk)?);
+    }
+}
+
+/// Calls `embed` on the distinct texts of the chunks, regrouped in chunks of at most
+/// `chunk_size` texts, then gives every text of the chunks the embedding of its distinct text.
+///
+/// The chunks are passed as they are when all of their texts are distinct.
+fn embed_deduplicated(
+    text_chunks: Vec<Vec<String>>,
+    chunk_size: usize,
+    embed: impl FnOnce(Vec<Vec<String>>) -> std::result::Result<Vec<Vec<Embedding>>, EmbedError>,
+) -> std::result::Result<Vec<Vec<Embedding>>, EmbedError> {
+    let mut distinct_texts = Vec::new();
+    let mut positions = Vec::with_capacity(text_chunks.len());
+    let mut text_count = 0;
+    {
+        let mut distinct_positions = HashMap::new();
+        for chunk in &text_chunks {
+            text_count += chunk.len();
+            let chunk_positions: Vec<usize> = chunk
+                .iter()
+                .map(|text| {
+                    *distinct_positions.entry(text.as_str()).or_insert_with(|| {
+                        distinct_texts.push(text.clone());
+                        distinct_texts.len() - 1
+                    })
+                })
+                .collect();
+            positions.push(chunk_positions);
+        }
+    }
+
+    if distinct_texts.len() == text_count {
+        return embed(text_chunks);
+    }
+
+    let chunk_size = chunk_size.max(1);
+    let mut distinct_chunks = Vec::new();
+    let mut distinct_texts = distinct_texts.into_iter();
+    loop {
+        let chunk: Vec<_> = distinct_texts.by_ref().take(chunk_size).collect();
+        if chunk.is_empty() {
+            break;
+        }
+        distinct_chunks.push(chunk);
+    }
+
+    let embeddings: Vec<Embedding> = embed(distinct_chunks)?.into_iter().flatten().collect();
+    positions
+        .into_iter()
+        .map(|chunk_positions| {
+            chunk_positions
+                .into_iter()
+                .map(|position| {
+                    embeddings.get(position).cloned().ok_or_else(EmbedError::missing_embedding)
+                })
+                .collect()
+        })
+        .collect()
+}
+
 fn configuration_hint(
     search: &SubEmbedder,
     index: &SubEmbedder,
 ) -> CompositeEmbedderContainsHuggingFace {
-    match (search, index) {
-        (SubEmbedder::HuggingFace(_), SubEmbedder::HuggingFace(_)) => {
+    match (&search.kind, &index.kind) {
+        (SubEmbedderKind::HuggingFace(_), SubEmbedderKind::HuggingFace(_)) => {
             CompositeEmbedderContainsHuggingFace::Both
         }
-        (SubEmbedder::HuggingFace(_), _) => CompositeEmbedderContainsHuggingFace::Search,
-        (_, SubEmbedder::HuggingFace(_)) => CompositeEmbedderContainsHuggingFace::Indexing,
+        (SubEmbedderKind::HuggingFace(_), _) => CompositeEmbedderContainsHuggingFace::Search,
+        (_, SubEmbedderKind::HuggingFace(_)) => CompositeEmbedderContainsHuggingFace::Indexing,
         _ => CompositeEmbedderContainsHuggingFace::None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use arroy::distances::{Cosine, Euclidean, Manhattan};
+    use big_s::S;
+
+    use super::{
+        check_similarity, l2_normalize, CompositeEmbedderContainsHuggingFace, DistanceMetric,
+        Embedder, EmbedderOptions, SubEmbedder, SubEmbedderOptions, TemplateReport, TextPreprocess,
+    };
+    use crate::error::FaultSource;
+    use crate::vector::error::EmbedErrorKind;
+    use crate::vector::settings::EmbedderSource;
+    use crate::vector::{manual, rest};
+
+    #[test]
+    fn similarity_cosine() {
+        let hint = CompositeEmbedderContainsHuggingFace::None;
+
+        // the norm of the embeddings doesn't matter to the cosine distance
+        let left = vec![vec![1.0, 0.0], vec![0.5, 0.5]];
+        let right = vec![vec![2.0, 0.0], vec![3.0, 3.0]];
+        check_similarity::<Cosine>(left, right, hint).unwrap();
+
+        let left = vec![vec![1.0, 0.0]];
+        let right = vec![vec![0.0, 1.0]];
+        check_similarity::<Cosine>(left, right, hint).unwrap_err();
+    }
+
+    #[test]
+    fn similarity_euclidean() {
+        let hint = CompositeEmbedderContainsHuggingFace::None;
+
+        let left = vec![vec![1.0, 0.0], vec![0.5, 0.5]];
+        let right = vec![vec![1.0, 0.0], vec![0.5, 0.501]];
+        check_similarity::<Euclidean>(left, right, hint).unwrap();
+
+        // the distance is relative to the norm of the embeddings, large embeddings with
+        // a small angle between them are similar
+        let left = vec![vec![100.0, 0.0]];
+        let right = vec![vec![100.0, 5.0]];
+        check_similarity::<Euclidean>(left, right, hint).unwrap();
+
+        // embeddings pointing in the same direction are still far apart
+        let left = vec![vec![1.0, 0.0]];
+        let right = vec![vec![2.0, 0.0]];
+        let error = check_similarity::<Euclidean>(left, right, hint).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "user error: error while generating test embeddings.\n  - the embeddings produced at search time and indexing time are not similar enough.\n  - `Euclidean` distance 0.50\n  - Meilisearch requires a maximum distance of 0.15.\n  - Note: check that both embedders produce similar embeddings."
+        );
+    }
+
+    #[test]
+    fn similarity_manhattan() {
+        let hint = CompositeEmbedderContainsHuggingFace::None;
+
+        let left = vec![vec![10.0, 10.0, 0.0]];
+        let right = vec![vec![10.0, 9.0, 1.0]];
+        check_similarity::<Manhattan>(left, right, hint).unwrap();
+
+        let left = vec![vec![1.0, 1.0, 0.0]];
+        let right = vec![vec![1.0, 0.0, 1.0]];
+        check_similarity::<Manhattan>(left, right, hint).unwrap_err();
+    }
+
+    #[test]
+    fn normalize_embeddings() {
+        let mut embedding = vec![3.0, 4.0];
+        l2_normalize(&mut embedding);
+        assert_eq!(embedding, vec![0.6, 0.8]);
+
+        // null embeddings have no direction to keep
+        let mut embedding = vec![0.0, 0.0];
+        l2_normalize(&mut embedding);
+        assert_eq!(embedding, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn normalize_toggle() {
+        let options = |normalize| {
+            SubEmbedderOptions::UserProvided(manual::EmbedderOptions {
+                dimensions: 2,
+                distribution: None,
+                normalize,
+            })
+        };
+
+        let embedder = SubEmbedder::new(options(true), 0).unwrap();
+        let mut embeddings = vec![vec![3.0, 4.0], vec![0.0, 2.0]];
+        embedder.normalize_all(&mut embeddings);
+        assert_eq!(embeddings, vec![vec![0.6, 0.8], vec![0.0, 1.0]]);
+
+        let embedder = SubEmbedder::new(options(false), 0).unwrap();
+        let mut embeddings = vec![vec![3.0, 4.0], vec![0.0, 2.0]];
+        embedder.normalize_all(&mut embeddings);
+        assert_eq!(embeddings, vec![vec![3.0, 4.0], vec![0.0, 2.0]]);
+    }
+
+    #[test]
+    fn token_embeddings_unsupported() {
+        let embedder = SubEmbedder::new(
+            SubEmbedderOptions::UserProvided(manual::EmbedderOptions {
+                dimensions: 2,
+                distribution: None,
+                normalize: false,
+            }),
+            0,
+        )
+        .unwrap();
+
+        let error = embedder.embed_tokens(vec![S("hello")]).unwrap_err();
+        assert!(matches!(error.kind, EmbedErrorKind::UnsupportedTokenEmbeddings("userProvided")));
+        assert!(matches!(error.fault, FaultSource::User));
+        assert!(
+            error
+                .kind
+                .to_string()
+                .starts_with("`userProvided` embedders cannot produce token embeddings"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn index_user_vector() {
+        let options = |normalize| EmbedderOptions {
+            // never reached, the search embedder is not called at construction time
+            // when the embeddings are provided by the user at indexing time
+            search: SubEmbedderOptions::Rest(rest::EmbedderOptions {
+                api_key: None,
+                distribution: None,
+                dimensions: Some(2),
+                url: "http://localhost:1/embed".to_string(),
+                request: serde_json::json!({ "input": "{{text}}" }),
+                response: serde_json::json!({ "embedding": "{{embedding}}" }),
+                headers: Default::default(),
+                normalize: false,
+                batch_encoding: Default::default(),
+            }),
+            index: SubEmbedderOptions::UserProvided(manual::EmbedderOptions {
+                dimensions: 2,
+                distribution: None,
+                normalize,
+            }),
+            distance: DistanceMetric::Cosine,
+            search_prompt_prefix: None,
+            index_prompt_prefix: None,
+            preprocess: Default::default(),
+            search_binary_quantized: false,
+            index_binary_quantized: false,
+        };
+
+        let embedder = Embedder::new(options(false), 0).unwrap();
+        assert_eq!(embedder.index_user_vector(vec![3.0, 4.0]).unwrap(), vec![3.0, 4.0]);
+        let error = embedder.index_user_vector(vec![3.0, 4.0, 5.0]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "runtime error: was expecting embeddings of dimension `2`, got embeddings of dimensions `3`"
+        );
+
+        let embedder = Embedder::new(options(true), 0).unwrap();
+        assert_eq!(embedder.index_user_vector(vec![3.0, 4.0]).unwrap(), vec![0.6, 0.8]);
+    }
+
+    #[test]
+    fn similarity_check_metric() {
+        let hint = CompositeEmbedderContainsHuggingFace::None;
+        // same direction, different norms
+        let left = || vec![vec![1.0, 0.0]];
+        let right = || vec![vec![2.0, 0.0]];
+
+        let check = DistanceMetric::Cosine.similarity_check().unwrap();
+        check(left(), right(), hint).unwrap();
+        let check = DistanceMetric::Euclidean.similarity_check().unwrap();
+        check(left(), right(), hint).unwrap_err();
+        let check = DistanceMetric::Manhattan.similarity_check().unwrap();
+        check(left(), right(), hint).unwrap_err();
+
+        // close points, different directions, are far apart relative to their norms
+        let left = || vec![vec![0.001, 0.0]];
+        let right = || vec![vec![0.0, 0.001]];
+
+        for distance in
+            [DistanceMetric::Cosine, DistanceMetric::Euclidean, DistanceMetric::Manhattan]
+        {
+            let check = distance.similarity_check().unwrap();
+            check(left(), right(), hint).unwrap_err();
+        }
+
+        for distance in [
+            DistanceMetric::DotProduct,
+            DistanceMetric::BinaryQuantizedCosine,
+            DistanceMetric::BinaryQuantizedEuclidean,
+            DistanceMetric::BinaryQuantizedManhattan,
+        ] {
+            distance.similarity_check().unwrap_err();
+        }
+    }
+
+    #[test]
+    fn distance_defaults_to_cosine() {
+        let index = serde_json::json!({
+            "UserProvided": { "dimensions": 2, "distribution": null }
+        });
+        let options: EmbedderOptions =
+            serde_json::from_value(serde_json::json!({ "search": index, "index": index })).unwrap();
+        assert_eq!(options.distance, DistanceMetric::Cosine);
+
+        let options: EmbedderOptions = serde_json::from_value(serde_json::json!({
+            "search": index,
+            "index": index,
+            "distance": "euclidean",
+        }))
+        .unwrap();
+        assert_eq!(options.distance, DistanceMetric::Euclidean);
+    }
+
+    /// Serves the `embedding` to every request, counting the requests.
+    fn embedding_server(
+        embedding: serde_json::Value,
+    ) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        embedding_server_with(move |_| embedding.clone())
+    }
+
+    /// Like [`embedding_server`], computing the embedding from the body of each request.
+    fn embedding_server_with(
+        embedding: impl Fn(serde_json::Value) -> serde_json::Value + Send + 'static,
+    ) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use crate::vector::test_server::{self, Response};
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let url = test_server::serve(move |request| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let request = serde_json::from_slice(&request.body).unwrap_or_default();
+            Response::ok(serde_json::json!({ "embedding": embedding(request) }))
+        });
+        (url, requests)
+    }
+
+    #[test]
+    fn embed_one_cached() {
+        use std::sync::atomic::Ordering;
+
+        use crate::vector::CacheOutcome;
+
+        let (url, requests) = embedding_server(serde_json::json!([3.0, 4.0]));
+        let options = SubEmbedderOptions::Rest(rest::EmbedderOptions {
+            api_key: None,
+            distribution: None,
+            dimensions: Some(2),
+            url,
+            request: serde_json::json!({ "input": "{{text}}" }),
+            response: serde_json::json!({ "embedding": "{{embedding}}" }),
+            headers: Default::default(),
+            normalize: true,
+            batch_encoding: Default::default(),
+        });
+        let embedder = SubEmbedder::new(options, 10).unwrap();
+
+        let (first, outcome) = embedder.embed_one_cached("kefir", None).unwrap();
+        assert_eq!(outcome, CacheOutcome::Miss);
+        assert_eq!(first, vec![0.6, 0.8]);
+        let (second, outcome) = embedder.embed_one_cached("kefir", None).unwrap();
+        assert_eq!(outcome, CacheOutcome::Hit);
+        assert_eq!(second, first);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let (_, outcome) = embedder.embed_one_cached("echo", None).unwrap();
+        assert_eq!(outcome, CacheOutcome::Miss);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // the user-provided embedder has no cache
+        let options = SubEmbedderOptions::UserProvided(manual::EmbedderOptions {
+            dimensions: 2,
+            distribution: None,
+            normalize: false,
+        });
+        let embedder = SubEmbedder::new(options, 10).unwrap();
+        embedder.embed_one_cached("kefir", None).unwrap_err();
+        assert!(embedder.cache().is_none());
+    }
+
+    #[test]
+    fn health_check() {
+        use std::sync::atomic::Ordering;
+
+        let rest_options = |url: &str| {
+            SubEmbedderOptions::Rest(rest::EmbedderOptions {
+                api_key: None,
+                distribution: None,
+                dimensions: Some(2),
+                url: url.to_string(),
+                request: serde_json::json!({ "input": "{{text}}" }),
+                response: serde_json::json!({ "embedding": "{{embedding}}" }),
+                headers: Default::default(),
+                normalize: false,
+                batch_encoding: Default::default(),
+            })
+        };
+        let deadline = || Some(std::time::Instant::now() + std::time::Duration::from_millis(200));
+
+        // the user-provided embedders have nothing to reach
+        let embedder = SubEmbedder::new(
+            SubEmbedderOptions::UserProvided(manual::EmbedderOptions {
+                dimensions: 2,
+                distribution: None,
+                normalize: false,
+            }),
+            0,
+        )
+        .unwrap();
+        embedder.health_check(None).unwrap();
+
+        // every check makes a request, the cache is not used
+        let (url, requests) = embedding_server_with(|_| serde_json::json!([1.0, 2.0]));
+        let embedder = SubEmbedder::new(rest_options(&url), 10).unwrap();
+        embedder.health_check(deadline()).unwrap();
+        embedder.health_check(deadline()).unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // the embedder shared by the search and the indexing is checked once
+        let options = EmbedderOptions {
+            search: rest_options(&url),
+            index: rest_options(&url),
+            distance: DistanceMetric::Cosine,
+            search_prompt_prefix: None,
+            index_prompt_prefix: None,
+            preprocess: Default::default(),
+            search_binary_quantized: false,
+            index_binary_quantized: false,
+        };
+        let embedder = Embedder::new(options, 10).unwrap();
+        embedder.health_check(deadline()).unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        // an unexpected number of dimensions is reported
+        let (url, _) = embedding_server_with(|_| serde_json::json!([1.0, 2.0, 3.0]));
+        let embedder = SubEmbedder::new(rest_options(&url), 10).unwrap();
+        let error = embedder.health_check(deadline()).unwrap_err();
+        assert!(matches!(error.kind, EmbedErrorKind::UnexpectedDimension(2, 3)), "{error}");
+
+        // nothing listens on the port of a closed listener anymore
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/embed", listener.local_addr().unwrap());
+        drop(listener);
+        let embedder = SubEmbedder::new(rest_options(&url), 10).unwrap();
+        let error = embedder.health_check(deadline()).unwrap_err();
+        assert!(matches!(error.kind, EmbedErrorKind::RestNetwork(_)), "{error}");
+        assert!(matches!(error.fault, FaultSource::Runtime));
+    }
+
+    #[test]
+    fn new_with_deadline() {
+        use std::time::{Duration, Instant};
+
+        use crate::vector::error::NewEmbedderErrorKind;
+
+        let batch_options = |url: String| {
+            SubEmbedderOptions::Rest(rest::EmbedderOptions {
+                api_key: None,
+                distribution: None,
+                dimensions: Some(2),
+                url,
+                request: serde_json::json!({ "input": ["{{text}}", "{{..}}"] }),
+                response: serde_json::json!({ "embedding": ["{{embedding}}", "{{..}}"] }),
+                headers: Default::default(),
+                normalize: false,
+                batch_encoding: Default::default(),
+            })
+        };
+        let server = |delay: Duration| {
+            let (url, _) = embedding_server_with(move |request| {
+                std::thread::sleep(delay);
+                let count = request["input"].as_array().map_or(0, Vec::len);
+                serde_json::json!(vec![[1.0, 2.0]; count])
+            });
+            url
+        };
+        let options = |search_url, index_url| EmbedderOptions {
+            search: batch_options(search_url),
+            index: batch_options(index_url),
+            distance: DistanceMetric::Cosine,
+            search_prompt_prefix: None,
+            index_prompt_prefix: None,
+            preprocess: Default::default(),
+            search_binary_quantized: false,
+            index_binary_quantized: false,
+        };
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        Embedder::new_with_deadline(
+            options(server(Duration::ZERO), server(Duration::ZERO)),
+            0,
+            deadline,
+        )
+        .unwrap();
+
+        // the search embedder answers long after the deadline, which is not waited for
+        let deadline = Instant::now() + Duration::from_millis(100);
+        let error = Embedder::new_with_deadline(
+            options(server(Duration::from_secs(10)), server(Duration::ZERO)),
+            0,
+            deadline,
+        )
+        .unwrap_err();
+        assert!(deadline.elapsed() < Duration::from_secs(5));
+        assert!(
+            matches!(
+                error.kind,
+                NewEmbedderErrorKind::CompositeTestEmbeddingTimeout { failing_embedder: "search" }
+            ),
+            "{error}"
+        );
+        assert!(matches!(error.fault, FaultSource::Runtime));
+
+        // without a deadline the slow embedder is waited for
+        Embedder::new(options(server(Duration::from_millis(300)), server(Duration::ZERO)), 0)
+            .unwrap();
+    }
+
+    #[test]
+    fn text_preprocess() {
+        let all =
+            TextPreprocess::TRIM | TextPreprocess::LOWERCASE | TextPreprocess::COLLAPSE_WHITESPACE;
+        assert_eq!(all.apply(" Hello  World "), "hello world");
+        assert_eq!(all.apply("hello world"), "hello world");
+        assert_eq!(TextPreprocess::TRIM.apply(" Hello  World "), "Hello  World");
+        assert_eq!(TextPreprocess::COLLAPSE_WHITESPACE.apply(" Hello \t World "), " Hello World ");
+        assert_eq!(TextPreprocess::LOWERCASE.apply(" Hello  World "), " hello  world ");
+        assert!(matches!(TextPreprocess::NONE.apply(" Hello "), Cow::Borrowed(" Hello ")));
+    }
+
+    #[test]
+    fn preprocessed_texts_share_their_embedding() {
+        use std::sync::atomic::Ordering;
+
+        use crate::vector::CacheOutcome;
+
+        // a single text per request, embedded as its length
+        let (url, requests) = embedding_server_with(|request| {
+            let length = request["input"].as_str().unwrap().len() as f32;
+            serde_json::json!([length, 1.0])
+        });
+        let options = || {
+            SubEmbedderOptions::Rest(rest::EmbedderOptions {
+                api_key: None,
+                distribution: None,
+                dimensions: Some(2),
+                url: url.clone(),
+                request: serde_json::json!({ "input": "{{text}}" }),
+                response: serde_json::json!({ "embedding": "{{embedding}}" }),
+                headers: Default::default(),
+                normalize: false,
+                batch_encoding: Default::default(),
+            })
+        };
+        let all =
+            TextPreprocess::TRIM | TextPreprocess::LOWERCASE | TextPreprocess::COLLAPSE_WHITESPACE;
+        let threads = crate::ThreadPoolNoAbortBuilder::new().build().unwrap();
+
+        let embedder = SubEmbedder::new(options(), 10).unwrap().with_preprocess(all);
+        requests.store(0, Ordering::SeqCst);
+        let (first, outcome) = embedder.embed_one_cached(" Hello  World ", None).unwrap();
+        assert_eq!(outcome, CacheOutcome::Miss);
+        let (second, outcome) = embedder.embed_one_cached("hello world", None).unwrap();
+        assert_eq!(outcome, CacheOutcome::Hit);
+        assert_eq!(first, second);
+        assert_eq!(first, vec![11.0, 1.0]);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let text_chunks = vec![vec![S(" Hello  World ")], vec![S("hello world")]];
+        requests.store(0, Ordering::SeqCst);
+        let embeddings = embedder.embed_index(text_chunks.clone(), &threads).unwrap();
+        assert_eq!(embeddings, vec![vec![vec![11.0, 1.0]], vec![vec![11.0, 1.0]]]);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert_eq!(embedder.embed(vec![S("  HELLO WORLD")], None).unwrap(), vec![vec![11.0, 1.0]]);
+
+        // the texts are embedded as is without preprocessing
+        let embedder = SubEmbedder::new(options(), 10).unwrap();
+        let (_, outcome) = embedder.embed_one_cached(" Hello  World ", None).unwrap();
+        assert_eq!(outcome, CacheOutcome::Miss);
+        let (embedding, outcome) = embedder.embed_one_cached("hello world", None).unwrap();
+        assert_eq!(outcome, CacheOutcome::Miss);
+        assert_eq!(embedding, vec![11.0, 1.0]);
+        let embeddings = embedder.embed_index(text_chunks, &threads).unwrap();
+        assert_eq!(embeddings, vec![vec![vec![14.0, 1.0]], vec![vec![11.0, 1.0]]]);
+    }
+
+    #[test]
+    fn embed_index_streaming() {
+        use std::sync::atomic::Ordering;
+
+        use crate::vector::EmbedError;
+
+        let (url, requests) = embedding_server(serde_json::json!([[3.0, 4.0], [0.0, 2.0]]));
+        let options = SubEmbedderOptions::Rest(rest::EmbedderOptions {
+            api_key: None,
+            distribution: None,
+            dimensions: Some(2),
+            url,
+            request: serde_json::json!({ "input": ["{{text}}", "{{..}}"] }),
+            response: serde_json::json!({ "embedding": ["{{embedding}}", "{{..}}"] }),
+            headers: Default::default(),
+            normalize: true,
+            batch_encoding: Default::default(),
+        });
+        let embedder = SubEmbedder::new(options, 0).unwrap();
+        let threads = crate::ThreadPoolNoAbortBuilder::new().build().unwrap();
+
+        // more chunks than a single window
+        let chunk_count = embedder.chunk_count_hint() * 2 + 3;
+        let text_chunks: Vec<_> =
+            (0..chunk_count).map(|i| vec![format!("kefir {i}"), format!("echo {i}")]).collect();
+
+        let mut received = Vec::new();
+        embedder
+            .embed_index_streaming(text_chunks, &threads, |index, embeddings| {
+                assert_eq!(embeddings, vec![vec![0.6, 0.8], vec![0.0, 1.0]]);
+                received.push(index);
+                Ok::<_, EmbedError>(())
+            })
+            .unwrap();
+        assert_eq!(received, (0..chunk_count).collect::<Vec<_>>());
+        assert_eq!(requests.load(Ordering::SeqCst), chunk_count);
+    }
+
+    #[test]
+    fn embed_index_deduplicates_texts() {
+        use std::sync::atomic::Ordering;
+
+        // a single text per request, embedded as its length
+        let (url, requests) = embedding_server_with(|request| {
+            let length = request["input"].as_str().unwrap().len() as f32;
+            serde_json::json!([length, 1.0])
+        });
+        let options = SubEmbedderOptions::Rest(rest::EmbedderOptions {
+            api_key: None,
+            distribution: None,
+            dimensions: Some(2),
+            url,
+            request: serde_json::json!({ "input": "{{text}}" }),
+            response: serde_json::json!({ "embedding": "{{embedding}}" }),
+            headers: Default::default(),
+            normalize: false,
+            batch_encoding: Default::default(),
+        });
+        let threads = crate::ThreadPoolNoAbortBuilder::new().build().unwrap();
+
+        let texts = ["a", "kefir", "a description shared by many documents"];
+        let text_chunks: Vec<_> = (0..30).map(|i| vec![texts[i % 7 % 3].to_string()]).collect();
+        let expected: Vec<_> =
+            text_chunks.iter().map(|chunk| vec![vec![chunk[0].len() as f32, 1.0]]).collect();
+
+        let embedder = SubEmbedder::new(options.clone(), 0).unwrap();
+        requests.store(0, Ordering::SeqCst);
+        assert_eq!(embedder.embed_index(text_chunks.clone(), &threads).unwrap(), expected);
+        assert_eq!(requests.load(Ordering::SeqCst), texts.len());
+
+        // every text is embedded once deduplication is disabled
+        let embedder = SubEmbedder::new(options, 0).unwrap().with_deduplicated_texts(false);
+        requests.store(0, Ordering::SeqCst);
+        assert_eq!(embedder.embed_index(text_chunks.clone(), &threads).unwrap(), expected);
+        assert_eq!(requests.load(Ordering::SeqCst), text_chunks.len());
+    }
+
+    #[test]
+    fn embed_deduplicated() {
+        let mut calls = Vec::new();
+        let text_chunks = vec![vec![S("a"), S("b"), S("a")], vec![S("c")], vec![S("b"), S("b")]];
+        let embeddings = super::embed_deduplicated(text_chunks, 2, |chunks| {
+            calls.push(chunks.clone());
+            Ok(chunks
+                .iter()
+                .map(|chunk| chunk.iter().map(|text| vec![text.as_bytes()[0] as f32]).collect())
+                .collect())
+        })
+        .unwrap();
+        assert_eq!(calls, vec![vec![vec![S("a"), S("b")], vec![S("c")]]]);
+        let (a, b, c) = (vec![97.0], vec![98.0], vec![99.0]);
+        assert_eq!(embeddings, vec![vec![a.clone(), b.clone(), a], vec![c], vec![b.clone(), b]]);
+
+        // the chunks of distinct texts keep their shape
+        let text_chunks = vec![vec![S("a"), S("b"), S("c")], vec![S("d")]];
+        super::embed_deduplicated(text_chunks.clone(), 2, |chunks| {
+            assert_eq!(chunks, text_chunks);
+            Ok(vec![vec![vec![0.0]; 3], vec![vec![0.0]]])
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn embed_by_chunks() {
+        // a mock embedder expecting at most 2 texts per request
+        let mut calls = Vec::new();
+        let texts: Vec<_> = (0..5).map(|i| format!("text {i}")).collect();
+        let embeddings = super::embed_by_chunks(texts, 2, |chunk| {
+            calls.push(chunk.clone());
+            Ok(chunk.iter().map(|text| vec![text[5..].parse().unwrap()]).collect())
+        })
+        .unwrap();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[2], vec![S("text 4")]);
+        assert_eq!(embeddings, vec![vec![0.0], vec![1.0], vec![2.0], vec![3.0], vec![4.0]]);
+
+        let embeddings = super::embed_by_chunks(Vec::new(), 2, |_| unreachable!()).unwrap();
+        assert!(embeddings.is_empty());
+    }
+
+    #[test]
+    fn embed_auto_batched() {
+        use std::sync::atomic::Ordering;
+
+        // a single text per request
+        let (url, requests) = embedding_server(serde_json::json!([3.0, 4.0]));
+        let options = SubEmbedderOptions::Rest(rest::EmbedderOptions {
+            api_key: None,
+            distribution: None,
+            dimensions: Some(2),
+            url,
+            request: serde_json::json!({ "input": "{{text}}" }),
+            response: serde_json::json!({ "embedding": "{{embedding}}" }),
+            headers: Default::default(),
+            normalize: true,
+            batch_encoding: Default::default(),
+        });
+        let embedder = SubEmbedder::new(options, 0).unwrap();
+        assert_eq!(embedder.prompt_count_in_chunk_hint(), 1);
+
+        let texts = vec![S("kefir"), S("echo"), S("intel")];
+        let embeddings = embedder.embed_auto_batched(texts, None).unwrap();
+        assert_eq!(embeddings, vec![vec![0.6, 0.8]; 3]);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn identical_options_share_the_embedder() {
+        use std::sync::atomic::Ordering;
+        use std::sync::Arc;
+
+        let (url, requests) = embedding_server(serde_json::json!([3.0, 4.0]));
+        let sub_options = |url: &str| {
+            SubEmbedderOptions::Rest(rest::EmbedderOptions {
+                api_key: None,
+                distribution: None,
+                dimensions: Some(2),
+                url: url.to_string(),
+                request: serde_json::json!({ "input": "{{text}}" }),
+                response: serde_json::json!({ "embedding": "{{embedding}}" }),
+                headers: Default::default(),
+                normalize: false,
+                batch_encoding: Default::default(),
+            })
+        };
+        let options = EmbedderOptions {
+            search: sub_options(&url),
+            index: sub_options(&url),
+            distance: DistanceMetric::Cosine,
+            search_prompt_prefix: None,
+            index_prompt_prefix: None,
+            preprocess: Default::default(),
+            search_binary_quantized: false,
+            index_binary_quantized: false,
+        };
+
+        let embedder = Embedder::new(options, 10).unwrap();
+        assert!(Arc::ptr_eq(&embedder.search, &embedder.index));
+        // no similarity check between the embedder and itself
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+        assert!(embedder.search.cache().is_some());
+
+        // different options still build both embedders
+        let options = EmbedderOptions {
+            search: sub_options(&url),
+            index: SubEmbedderOptions::UserProvided(manual::EmbedderOptions {
+                dimensions: 2,
+                distribution: None,
+                normalize: false,
+            }),
+            distance: DistanceMetric::Cosine,
+            search_prompt_prefix: None,
+            index_prompt_prefix: None,
+            preprocess: Default::default(),
+            search_binary_quantized: false,
+            index_binary_quantized: false,
+        };
+        let embedder = Embedder::new(options, 10).unwrap();
+        assert!(!Arc::ptr_eq(&embedder.search, &embedder.index));
+    }
+
+    #[test]
+    fn asymmetric_binary_quantization() {
+        use crate::vector::error::NewEmbedderErrorKind;
+
+        let sub_options = |url: String| {
+            SubEmbedderOptions::Rest(rest::EmbedderOptions {
+                api_key: None,
+                distribution: None,
+                dimensions: Some(3),
+                url,
+                request: serde_json::json!({ "input": "{{text}}" }),
+                response: serde_json::json!({ "embedding": "{{embedding}}" }),
+                headers: Default::default(),
+                normalize: false,
+                batch_encoding: Default::default(),
+            })
+        };
+        let options = |index: serde_json::Value, search_quantized, index_quantized| {
+            let (search_url, _) = embedding_server(serde_json::json!([0.8, 0.1, -0.5]));
+            let (index_url, _) = embedding_server(index);
+            EmbedderOptions {
+                search: sub_options(search_url),
+                index: sub_options(index_url),
+                distance: DistanceMetric::Cosine,
+                search_prompt_prefix: None,
+                index_prompt_prefix: None,
+                preprocess: Default::default(),
+                search_binary_quantized: search_quantized,
+                index_binary_quantized: index_quantized,
+            }
+        };
+
+        // the embeddings have the same signs but are far apart in full precision
+        let same_signs = serde_json::json!([0.1, 0.9, -0.2]);
+        let error = Embedder::new(options(same_signs.clone(), false, false), 0).unwrap_err();
+        assert!(matches!(error.kind, NewEmbedderErrorKind::CompositeEmbeddingValueMismatch { .. }));
+        Embedder::new(options(same_signs.clone(), false, true), 0).unwrap();
+        Embedder::new(options(same_signs.clone(), true, true), 0).unwrap();
+
+        // the quantized queries can't be compared to full-precision documents
+        let error = Embedder::new(options(same_signs, true, false), 0).unwrap_err();
+        assert!(matches!(error.kind, NewEmbedderErrorKind::CompositeSearchQuantizationUnsupported));
+
+        // the signs of incompatible models still differ
+        let flipped_signs = serde_json::json!([-0.8, 0.1, 0.5]);
+        let error = Embedder::new(options(flipped_signs, false, true), 0).unwrap_err();
+        assert!(matches!(error.kind, NewEmbedderErrorKind::CompositeEmbeddingValueMismatch { .. }));
+
+        // the quantization is not serialized when disabled
+        let options = options(serde_json::json!([0.1, 0.9, -0.2]), false, true);
+        let value = serde_json::to_value(&options).unwrap();
+        assert_eq!(value.get("search_binary_quantized"), None);
+        assert_eq!(value.get("index_binary_quantized"), Some(&serde_json::json!(true)));
+        let options: EmbedderOptions = serde_json::from_value(value).unwrap();
+        assert!(!options.search_binary_quantized);
+        assert!(options.index_binary_quantized);
+    }
+
+    #[test]
+    fn search_binary_quantized_settings() {
+        use crate::update::Setting;
+        use crate::vector::settings::{EmbeddingSettings, SubEmbeddingSettings};
+        use crate::vector::EmbeddingConfig;
+
+        let sub_settings = |binary_quantized| SubEmbeddingSettings {
+            source: Setting::Set(EmbedderSource::UserProvided),
+            dimensions: Setting::Set(3),
+            binary_quantized,
+            ..Default::default()
+        };
+        let settings = EmbeddingSettings {
+            source: Setting::Set(EmbedderSource::Composite),
+            binary_quantized: Setting::Set(true),
+            search_embedder: Setting::Set(sub_settings(Setting::Set(true))),
+            indexing_embedder: Setting::Set(sub_settings(Setting::NotSet)),
+            ..Default::default()
+        };
+
+        let config = EmbeddingConfig::from(settings);
+        let crate::vector::EmbedderOptions::Composite(options) = &config.embedder_options else {
+            panic!("expected a composite embedder, got {:?}", config.embedder_options)
+        };
+        assert!(options.search_binary_quantized);
+        assert!(options.index_binary_quantized);
+
+        // the quantization of the queries is returned in the search embedder
+        let settings = EmbeddingSettings::from(config);
+        let Setting::Set(search_embedder) = settings.search_embedder else {
+            panic!("expected a search embedder")
+        };
+        assert_eq!(search_embedder.binary_quantized, Setting::Set(true));
+        let Setting::Set(indexing_embedder) = settings.indexing_embedder else {
+            panic!("expected an indexing embedder")
+        };
+        assert_eq!(indexing_embedder.binary_quantized, Setting::NotSet);
+    }
+
+    #[test]
+    fn preprocess_settings() {
+        use crate::update::settings::validate_embedding_settings;
+        use crate::update::Setting;
+        use crate::vector::settings::{
+            EmbeddingSettings, ReindexAction, SettingsDiff, SubEmbeddingSettings,
+        };
+        use crate::vector::EmbeddingConfig;
+
+        let sub_settings = || SubEmbeddingSettings {
+            source: Setting::Set(EmbedderSource::Rest),
+            url: Setting::Set(S("http://localhost:1/embed")),
+            request: Setting::Set(serde_json::json!({ "input": "{{text}}" })),
+            response: Setting::Set(serde_json::json!({ "embedding": "{{embedding}}" })),
+            ..Default::default()
+        };
+        let settings = |preprocess| EmbeddingSettings {
+            source: Setting::Set(EmbedderSource::Composite),
+            search_embedder: Setting::Set(sub_settings()),
+            indexing_embedder: Setting::Set(sub_settings()),
+            preprocess,
+            ..Default::default()
+        };
+        let all =
+            TextPreprocess::TRIM | TextPreprocess::LOWERCASE | TextPreprocess::COLLAPSE_WHITESPACE;
+
+        validate_embedding_settings(Setting::Set(settings(Setting::Set(all))), "default").unwrap();
+        let config = EmbeddingConfig::from(settings(Setting::Set(all)));
+        let crate::vector::EmbedderOptions::Composite(options) = &config.embedder_options else {
+            panic!("expected a composite embedder, got {:?}", config.embedder_options)
+        };
+        assert_eq!(options.preprocess, all);
+        assert_eq!(EmbeddingSettings::from(config).preprocess, Setting::Set(all));
+        let config = EmbeddingConfig::from(settings(Setting::NotSet));
+        assert_eq!(EmbeddingSettings::from(config).preprocess, Setting::NotSet);
+
+        // the embeddings of the documents change with their texts
+        let diff = SettingsDiff::from_settings(
+            "default",
+            settings(Setting::NotSet),
+            Setting::Set(EmbeddingSettings {
+                preprocess: Setting::Set(TextPreprocess::LOWERCASE),
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+        assert!(matches!(diff, SettingsDiff::Reindex { action: ReindexAction::FullReindex, .. }));
+
+        // only the composite embedders preprocess their texts
+        let error = validate_embedding_settings(
+            Setting::Set(EmbeddingSettings {
+                source: Setting::Set(EmbedderSource::UserProvided),
+                dimensions: Setting::Set(3),
+                preprocess: Setting::Set(all),
+                ..Default::default()
+            }),
+            "default",
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("Field `preprocess` unavailable"), "{error}");
+    }
+
+    #[test]
+    fn distance_settings() {
+        use crate::update::settings::validate_embedding_settings;
+        use crate::update::Setting;
+        use crate::vector::settings::{EmbeddingSettings, SettingsDiff, SubEmbeddingSettings};
+        use crate::vector::EmbeddingConfig;
+
+        let sub_settings = || SubEmbeddingSettings {
+            source: Setting::Set(EmbedderSource::Rest),
+            url: Setting::Set(S("http://localhost:1/embed")),
+            request: Setting::Set(serde_json::json!({ "input": "{{text}}" })),
+            response: Setting::Set(serde_json::json!({ "embedding": "{{embedding}}" })),
+            ..Default::default()
+        };
+        let settings = |distance| EmbeddingSettings {
+            source: Setting::Set(EmbedderSource::Composite),
+            search_embedder: Setting::Set(sub_settings()),
+            indexing_embedder: Setting::Set(sub_settings()),
+            distance,
+            ..Default::default()
+        };
+
+        let euclidean = Setting::Set(DistanceMetric::Euclidean);
+        validate_embedding_settings(Setting::Set(settings(euclidean)), "default").unwrap();
+        let config = EmbeddingConfig::from(settings(euclidean));
+        let crate::vector::EmbedderOptions::Composite(options) = &config.embedder_options else {
+            panic!("expected a composite embedder, got {:?}", config.embedder_options)
+        };
+        assert_eq!(options.distance, DistanceMetric::Euclidean);
+        assert_eq!(EmbeddingSettings::from(config).distance, euclidean);
+        let config = EmbeddingConfig::from(settings(Setting::NotSet));
+        let crate::vector::EmbedderOptions::Composite(options) = &config.embedder_options else {
+            panic!("expected a composite embedder, got {:?}", config.embedder_options)
+        };
+        assert_eq!(options.distance, DistanceMetric::Cosine);
+        assert_eq!(EmbeddingSettings::from(config).distance, Setting::NotSet);
+
+        // the embeddings of the documents don't depend on the metric checking them
+        let diff = SettingsDiff::from_settings(
+            "default",
+            settings(Setting::NotSet),
+            Setting::Set(EmbeddingSettings { distance: euclidean, ..Default::default() }),
+        )
+        .unwrap();
+        assert!(matches!(diff, SettingsDiff::UpdateWithoutReindex { .. }));
+
+        let value = serde_json::json!({ "source": "composite", "distance": "manhattan" });
+        let settings: EmbeddingSettings =
+            deserr::deserialize::<_, _, deserr::errors::JsonError>(value).unwrap();
+        assert_eq!(settings.distance, Setting::Set(DistanceMetric::Manhattan));
+
+        // only the composite embedders compare the embeddings of two embedders
+        let error = validate_embedding_settings(
+            Setting::Set(EmbeddingSettings {
+                source: Setting::Set(EmbedderSource::UserProvided),
+                dimensions: Setting::Set(3),
+                distance: euclidean,
+                ..Default::default()
+            }),
+            "default",
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("Field `distance` unavailable"), "{error}");
+    }
+
+    #[test]
+    fn prompt_prefix_settings() {
+        use crate::update::Setting;
+        use crate::vector::settings::{
+            EmbeddingSettings, ReindexAction, SettingsDiff, SubEmbeddingSettings,
+        };
+        use crate::vector::EmbeddingConfig;
+
+        let sub_settings = || SubEmbeddingSettings {
+            source: Setting::Set(EmbedderSource::Rest),
+            url: Setting::Set(S("http://localhost:1/embed")),
+            request: Setting::Set(serde_json::json!({ "input": "{{text}}" })),
+            response: Setting::Set(serde_json::json!({ "embedding": "{{embedding}}" })),
+            ..Default::default()
+        };
+        let settings = EmbeddingSettings {
+            source: Setting::Set(EmbedderSource::Composite),
+            search_embedder: Setting::Set(sub_settings()),
+            indexing_embedder: Setting::Set(sub_settings()),
+            search_prompt_prefix: Setting::Set(S("query: ")),
+            index_prompt_prefix: Setting::Set(S("passage: ")),
+            ..Default::default()
+        };
+
+        let config = EmbeddingConfig::from(settings.clone());
+        let crate::vector::EmbedderOptions::Composite(options) = &config.embedder_options else {
+            panic!("expected a composite embedder, got {:?}", config.embedder_options)
+        };
+        assert_eq!(options.search_prompt_prefix.as_deref(), Some("query: "));
+        assert_eq!(options.index_prompt_prefix.as_deref(), Some("passage: "));
+        let returned = EmbeddingSettings::from(config);
+        assert_eq!(returned.search_prompt_prefix, Setting::Set(S("query: ")));
+        assert_eq!(returned.index_prompt_prefix, Setting::Set(S("passage: ")));
+
+        // only the texts embedded at indexing depend on the index prompt prefix
+        let diff =
+            |new| SettingsDiff::from_settings("default", settings.clone(), Setting::Set(new));
+        let search =
+            diff(EmbeddingSettings { search_prompt_prefix: Setting::Reset, ..Default::default() })
+                .unwrap();
+        assert!(matches!(search, SettingsDiff::UpdateWithoutReindex { .. }));
+        let index = diff(EmbeddingSettings {
+            index_prompt_prefix: Setting::Set(S("document: ")),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(matches!(index, SettingsDiff::Reindex { action: ReindexAction::FullReindex, .. }));
+    }
+
+    #[test]
+    fn normalize_settings() {
+        use crate::update::settings::validate_embedding_settings;
+        use crate::update::Setting;
+        use crate::vector::settings::{
+            EmbeddingSettings, ReindexAction, SettingsDiff, SubEmbeddingSettings,
+        };
+        use crate::vector::EmbeddingConfig;
+
+        let sub_settings = |normalize| SubEmbeddingSettings {
+            source: Setting::Set(EmbedderSource::Rest),
+            url: Setting::Set(S("http://localhost:1/embed")),
+            request: Setting::Set(serde_json::json!({ "input": "{{text}}" })),
+            response: Setting::Set(serde_json::json!({ "embedding": "{{embedding}}" })),
+            normalize,
+            ..Default::default()
+        };
+        let settings = EmbeddingSettings {
+            source: Setting::Set(EmbedderSource::Composite),
+            search_embedder: Setting::Set(sub_settings(Setting::Set(true))),
+            indexing_embedder: Setting::Set(sub_settings(Setting::NotSet)),
+            ..Default::default()
+        };
+
+        validate_embedding_settings(Setting::Set(settings.clone()), "default").unwrap();
+        let config = EmbeddingConfig::from(settings.clone());
+        let crate::vector::EmbedderOptions::Composite(options) = &config.embedder_options else {
+            panic!("expected a composite embedder, got {:?}", config.embedder_options)
+        };
+        assert!(options.search.normalize());
+        assert!(!options.index.normalize());
+        let returned = EmbeddingSettings::from(config);
+        let Setting::Set(search_embedder) = returned.search_embedder else {
+            panic!("expected a search embedder")
+        };
+        assert_eq!(search_embedder.normalize, Setting::Set(true));
+        let Setting::Set(indexing_embedder) = returned.indexing_embedder else {
+            panic!("expected an indexing embedder")
+        };
+        assert_eq!(indexing_embedder.normalize, Setting::NotSet);
+
+        // only the embeddings of the documents depend on the normalization of the indexing embedder
+        let diff = |search_embedder, indexing_embedder| {
+            SettingsDiff::from_settings(
+                "default",
+                settings.clone(),
+                Setting::Set(EmbeddingSettings {
+                    search_embedder,
+                    indexing_embedder,
+                    ..Default::default()
+                }),
+            )
+            .unwrap()
+        };
+        let search = diff(Setting::Set(sub_settings(Setting::Set(false))), Setting::NotSet);
+        assert!(matches!(search, SettingsDiff::UpdateWithoutReindex { .. }));
+        let index = diff(Setting::NotSet, Setting::Set(sub_settings(Setting::Set(true))));
+        assert!(matches!(index, SettingsDiff::Reindex { action: ReindexAction::FullReindex, .. }));
+    }
+
+    #[test]
+    fn builder_checks_mandatory_parameters() {
+        let request = serde_json::json!({ "input": "{{text}}" });
+        let response = serde_json::json!({ "embedding": "{{embedding}}" });
+
+        let error = SubEmbedderOptions::builder(EmbedderSource::Rest).build().unwrap_err();
+        assert_eq!(
+            error.kind.to_string(),
+            "missing mandatory parameters for a `rest` embedder.\n  - Missing `url`, `request`, `response`"
+        );
+        let error = SubEmbedderOptions::builder(EmbedderSource::Rest)
+            .url("http://localhost:1/embed")
+            .response(response.clone())
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error.kind.to_string(),
+            "missing mandatory parameters for a `rest` embedder.\n  - Missing `request`"
+        );
+        let error = SubEmbedderOptions::builder(EmbedderSource::UserProvided).build().unwrap_err();
+        assert!(error.kind.to_string().ends_with("Missing `dimensions`"), "{error}");
+        let error =
+            SubEmbedderOptions::builder(EmbedderSource::OpenAi).model("gpt").build().unwrap_err();
+        assert!(error.kind.to_string().starts_with("unknown OpenAI model `gpt`"), "{error}");
+
+        let options = SubEmbedderOptions::builder(EmbedderSource::Rest)
+            .url("http://localhost:1/embed")
+            .request(request.clone())
+            .response(response.clone())
+            .dimensions(2)
+            .header("Authorization", "Bearer token")
+            .build()
+            .unwrap();
+        let SubEmbedderOptions::Rest(options) = options else { panic!("{options:?}") };
+        assert_eq!(options.url, "http://localhost:1/embed");
+        assert_eq!(options.request, request);
+        assert_eq!(options.response, response);
+        assert_eq!(options.dimensions, Some(2));
+        assert_eq!(options.headers.get("Authorization").unwrap(), "Bearer token");
+
+        assert!(SubEmbedderOptions::builder(EmbedderSource::HuggingFace).build().is_ok());
+        assert!(SubEmbedderOptions::builder(EmbedderSource::OpenAi).build().is_ok());
+        assert!(SubEmbedderOptions::builder(EmbedderSource::Ollama)
+            .model("nomic-embed-text")
+            .build()
+            .is_ok());
+        let error = SubEmbedderOptions::builder(EmbedderSource::Composite).build().unwrap_err();
+        assert!(
+            error.kind.to_string().starts_with("composite embedders can't be nested"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn validate_template() {
+        use std::num::NonZeroUsize;
+
+        use crate::prompt::Prompt;
+
+        let (url, requests) = embedding_server(serde_json::json!([3.0, 4.0]));
+        let options = SubEmbedderOptions::Rest(rest::EmbedderOptions {
+            api_key: None,
+            distribution: None,
+            dimensions: Some(2),
+            url,
+            request: serde_json::json!({ "input": "{{text}}" }),
+            response: serde_json::json!({ "embedding": "{{embedding}}" }),
+            headers: Default::default(),
+            normalize: false,
+            batch_encoding: Default::default(),
+        });
+        let embedder = SubEmbedder::new(options, 0).unwrap();
+        let sample_doc = serde_json::json!({ "title": "kefir", "overview": "a good boy" });
+        let sample_doc = sample_doc.as_object().unwrap();
+
+        let prompt = Prompt::new(
+            S("{{doc.title}}: {{doc.overview}}"),
+            Some(NonZeroUsize::new(100).unwrap()),
+        )
+        .unwrap();
+        let report = embedder.validate_template(&prompt, sample_doc, None).unwrap();
+        assert_eq!(
+            report,
+            TemplateReport { rendered_bytes: 17, truncated: false, embedding_dims: 2 }
+        );
+
+        let prompt =
+            Prompt::new(S("{{doc.title}}: {{doc.overview}}"), Some(NonZeroUsize::new(8).unwrap()))
+                .unwrap();
+        let report = embedder.validate_template(&prompt, sample_doc, None).unwrap();
+        assert_eq!(
+            report,
+            TemplateReport { rendered_bytes: 8, truncated: true, embedding_dims: 2 }
+        );
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // the template must only use the fields of the document
+        let prompt = Prompt::new(S("{{doc.title}}: {{doc.price.amount}}"), None).unwrap();
+        embedder.validate_template(&prompt, sample_doc, None).unwrap_err();
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn prompt_prefixes() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::ThreadPoolNoAbortBuilder;
+
+        let inputs = Arc::new(Mutex::new(Vec::new()));
+        let recorded = inputs.clone();
+        let (url, _) = embedding_server_with(move |request| {
+            recorded.lock().unwrap().push(request["input"].as_str().unwrap().to_string());
+            serde_json::json!([3.0, 4.0])
+        });
+        let rest_options = SubEmbedderOptions::Rest(rest::EmbedderOptions {
+            api_key: None,
+            distribution: None,
+            dimensions: Some(2),
+            url,
+            request: serde_json::json!({ "input": "{{text}}" }),
+            response: serde_json::json!({ "embedding": "{{embedding}}" }),
+            headers: Default::default(),
+            normalize: false,
+            batch_encoding: Default::default(),
+        });
+        let options = EmbedderOptions {
+            search: rest_options.clone(),
+            index: rest_options,
+            distance: DistanceMetric::Cosine,
+            search_prompt_prefix: Some(S("query: ")),
+            index_prompt_prefix: Some(S("passage: ")),
+            preprocess: Default::default(),
+            search_binary_quantized: false,
+            index_binary_quantized: false,
+        };
+        let embedder = crate::vector::Embedder::Composite(Embedder::new(options, 0).unwrap());
+        let threads = ThreadPoolNoAbortBuilder::new().num_threads(1).build().unwrap();
+
+        embedder.embed_search("hello", None).unwrap();
+        assert_eq!(inputs.lock().unwrap().drain(..).collect::<Vec<_>>(), vec![S("query: hello")]);
+
+        embedder.embed_index(vec![vec![S("kefir")]], &threads).unwrap();
+        assert_eq!(inputs.lock().unwrap().drain(..).collect::<Vec<_>>(), vec![S("passage: kefir")]);
+
+        embedder.embed_index_ref(&["echo"], &threads).unwrap();
+        assert_eq!(inputs.lock().unwrap().drain(..).collect::<Vec<_>>(), vec![S("passage: echo")]);
+    }
+
+    #[test]
+    fn prompt_prefixes_share_the_template_max_bytes() {
+        use std::num::NonZeroUsize;
+
+        use crate::prompt::PromptData;
+
+        let manual_options =
+            manual::EmbedderOptions { dimensions: 2, distribution: None, normalize: false };
+        let user_provided = SubEmbedderOptions::UserProvided(manual_options.clone());
+        let options = |search_prompt_prefix: Option<&str>, index_prompt_prefix: Option<&str>| {
+            crate::vector::EmbedderOptions::Composite(EmbedderOptions {
+                search: user_provided.clone(),
+                index: user_provided.clone(),
+                distance: DistanceMetric::Cosine,
+                search_prompt_prefix: search_prompt_prefix.map(String::from),
+                index_prompt_prefix: index_prompt_prefix.map(String::from),
+                preprocess: Default::default(),
+                search_binary_quantized: false,
+                index_binary_quantized: false,
+            })
+        };
+        let prompt = |max_bytes| PromptData {
+            template: S("{{doc.title}}"),
+            max_bytes: NonZeroUsize::new(max_bytes),
+        };
+
+        let adjusted = options(None, None).document_prompt(prompt(400)).unwrap();
+        assert_eq!(adjusted.max_bytes, NonZeroUsize::new(400));
+        let adjusted =
+            options(Some("query: "), Some("passage: ")).document_prompt(prompt(400)).unwrap();
+        assert_eq!(adjusted.max_bytes, NonZeroUsize::new(391));
+
+        // a prefix must leave room for the text it is prepended to
+        let error = options(Some("query: "), None).document_prompt(prompt(7)).unwrap_err();
+        assert!(
+            error
+                .kind
+                .to_string()
+                .starts_with("the search prompt prefix of the composite embedder is 7 bytes long"),
+            "{error}"
+        );
+        let error = options(None, Some("passage: ")).document_prompt(prompt(9)).unwrap_err();
+        assert!(
+            error
+                .kind
+                .to_string()
+                .starts_with("the index prompt prefix of the composite embedder is 9 bytes long"),
+            "{error}"
+        );
+
+        // the other embedders keep their template untouched
+        let adjusted = crate::vector::EmbedderOptions::UserProvided(manual_options)
+            .document_prompt(prompt(7))
+            .unwrap();
+        assert_eq!(adjusted.max_bytes, NonZeroUsize::new(7));
+    }
+}
@@ -4,9 +4,10 @@ use std::path::PathBuf;
 use bumpalo::Bump;
 use hf_hub::api::sync::ApiError;
 
+use super::composite::DistanceMetric;
 use super::parsed_vectors::ParsedVectorsDiff;
 use super::rest::ConfigurationSource;
-use super::MAX_COMPOSITE_DISTANCE;
+use super::settings::EmbedderSource;
 use crate::error::FaultSource;
 use crate::update::new::vector_document::VectorDocument;
 use crate::{FieldDistribution, PanicCatched};
@@ -99,6 +100,8 @@ pub enum EmbedErrorKind {
     UnexpectedDimension(usize, usize),
     #[error("no embedding was produced")]
     MissingEmbedding,
+    #[error("`{0}` embedders cannot produce token embeddings\n  - Hint: token embeddings are only produced by `huggingFace` embedders")]
+    UnsupportedTokenEmbeddings(&'static str),
     #[error(transparent)]
     PanicInThreadPool(#[from] PanicCatched),
 }
@@ -197,7 +200,7 @@ impl EmbedError {
         Self { kind: EmbedErrorKind::RestNetwork(transport), fault: FaultSource::Runtime }
     }
 
-    pub(crate) fn rest_unexpected_dimension(expected: usize, got: usize) -> EmbedError {
+    pub(crate) fn unexpected_dimension(expected: usize, got: usize) -> EmbedError {
         Self {
             kind: EmbedErrorKind::UnexpectedDimension(expected, got),
             fault: FaultSource::Runtime,
@@ -207,6 +210,10 @@ impl EmbedError {
         Self { kind: EmbedErrorKind::MissingEmbedding, fault: FaultSource::Undecided }
     }
 
+    pub(crate) fn unsupported(source: &'static str) -> EmbedError {
+        Self { kind: EmbedErrorKind::UnsupportedTokenEmbeddings(source), fault: FaultSource::User }
+    }
+
     pub(crate) fn rest_extraction_error(error: String) -> EmbedError {
         Self { kind: EmbedErrorKind::RestExtractionError(error), fault: FaultSource::Runtime }
     }
@@ -360,6 +367,15 @@ impl NewEmbedderError {
         }
     }
 
+    pub(crate) fn composite_test_embedding_timeout(
+        failing_embedder: &'static str,
+    ) -> NewEmbedderError {
+        Self {
+            kind: NewEmbedderErrorKind::CompositeTestEmbeddingTimeout { failing_embedder },
+            fault: FaultSource::Runtime,
+        }
+    }
+
     pub(crate) fn composite_embedding_count_mismatch(
         search_count: usize,
         index_count: usize,
@@ -374,14 +390,64 @@ impl NewEmbedderError {
     }
 
     pub(crate) fn composite_embedding_value_mismatch(
+        metric: DistanceMetric,
         distance: f32,
+        max_distance: f32,
         hint: CompositeEmbedderContainsHuggingFace,
     ) -> NewEmbedderError {
         Self {
-            kind: NewEmbedderErrorKind::CompositeEmbeddingValueMismatch { distance, hint },
+            kind: NewEmbedderErrorKind::CompositeEmbeddingValueMismatch {
+                metric,
+                distance,
+                max_distance,
+                hint,
+            },
+            fault: FaultSource::User,
+        }
+    }
+
+    pub(crate) fn composite_distance_unsupported(distance: DistanceMetric) -> NewEmbedderError {
+        Self {
+            kind: NewEmbedderErrorKind::CompositeDistanceUnsupported(distance),
+            fault: FaultSource::User,
+        }
+    }
+
+    pub(crate) fn composite_search_quantization_unsupported() -> NewEmbedderError {
+        Self {
+            kind: NewEmbedderErrorKind::CompositeSearchQuantizationUnsupported,
             fault: FaultSource::User,
         }
     }
+
+    pub(crate) fn composite_prompt_prefix_too_long(
+        side: &'static str,
+        len: usize,
+        max_bytes: usize,
+    ) -> NewEmbedderError {
+        Self {
+            kind: NewEmbedderErrorKind::CompositePromptPrefixTooLong { side, len, max_bytes },
+            fault: FaultSource::User,
+        }
+    }
+
+    pub(crate) fn missing_parameters(
+        embedder_source: EmbedderSource,
+        missing: Vec<&'static str>,
+    ) -> NewEmbedderError {
+        Self {
+            kind: NewEmbedderErrorKind::MissingParameters { embedder_source, missing },
+            fault: FaultSource::User,
+        }
+    }
+
+    pub(crate) fn unknown_openai_model(model: String) -> NewEmbedderError {
+        Self { kind: NewEmbedderErrorKind::UnknownOpenAiModel { model }, fault: FaultSource::User }
+    }
+
+    pub(crate) fn nested_composite() -> NewEmbedderError {
+        Self { kind: NewEmbedderErrorKind::NestedComposite, fault: FaultSource::User }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -495,10 +561,29 @@ pub enum NewEmbedderErrorKind {
     CompositeDimensionsMismatch { search_dimensions: usize, index_dimensions: usize },
     #[error("error while generating test embeddings.\n  - could not generate test embedding with embedder at {failing_embedder} time.\n  - Embedding failed with {inner}")]
     CompositeTestEmbeddingFailed { inner: EmbedError, failing_embedder: &'static str },
+    #[error("the deadline elapsed before the test embeddings of the {failing_embedder} embedder were computed.\n  - Note: the search and indexing embedders of a composite embedder must answer before the deadline to check their similarity.")]
+    CompositeTestEmbeddingTimeout { failing_embedder: &'static str },
     #[error("error while generating test embeddings.\n  - the number of generated embeddings differs.\n  - {search_count} embeddings for the search time embedder.\n  - {index_count} embeddings for the indexing time embedder.")]
     CompositeEmbeddingCountMismatch { search_count: usize, index_count: usize },
-    #[error("error while generating test embeddings.\n  - the embeddings produced at search time and indexing time are not similar enough.\n  - angular distance {distance:.2}\n  - Meilisearch requires a maximum distance of {MAX_COMPOSITE_DISTANCE}.\n  - Note: check that both embedders produce similar embeddings.{hint}")]
-    CompositeEmbeddingValueMismatch { distance: f32, hint: CompositeEmbedderContainsHuggingFace },
+    #[error("error while generating test embeddings.\n  - the embeddings produced at search time and indexing time are not similar enough.\n  - `{metric:?}` distance {distance:.2}\n  - Meilisearch requires a maximum distance of {max_distance}.\n  - Note: check that both embedders produce similar embeddings.{hint}")]
+    CompositeEmbeddingValueMismatch {
+        metric: DistanceMetric,
+        distance: f32,
+        max_distance: f32,
+        hint: CompositeEmbedderContainsHuggingFace,
+    },
+    #[error("unsupported distance metric for composite embedders.\n  - Got `{0:?}`\n  - Note: composite embedders can only be checked with the `Cosine`, `Euclidean` or `Manhattan` distances.")]
+    CompositeDistanceUnsupported(DistanceMetric),
+    #[error("unsupported binary quantization for composite embedders.\n  - Note: the queries can only be binary quantized when the embeddings of the documents are binary quantized too.")]
+    CompositeSearchQuantizationUnsupported,
+    #[error("the {side} prompt prefix of the composite embedder is {len} bytes long, which leaves no room for the document template.\n  - Note: the prefixes must be shorter than `documentTemplateMaxBytes` ({max_bytes} bytes).")]
+    CompositePromptPrefixTooLong { side: &'static str, len: usize, max_bytes: usize },
+    #[error("missing mandatory parameters for a `{embedder_source}` embedder.\n  - Missing `{}`", .missing.join("`, `"))]
+    MissingParameters { embedder_source: EmbedderSource, missing: Vec<&'static str> },
+    #[error("unknown OpenAI model `{model}`.\n  - Note: the supported models are `{}`.", super::openai::EmbeddingModel::supported_models().join("`, `"))]
+    UnknownOpenAiModel { model: String },
+    #[error("composite embedders can't be nested.\n  - Note: the sub-embedders of a composite embedder can't use the `composite` source.")]
+    NestedComposite,
 }
 
 pub struct PossibleEmbeddingMistakes {
@@ -36,6 +36,9 @@ pub struct EmbedderOptions {
     pub distribution: Option<DistributionShift>,
     #[serde(default)]
     pub pooling: OverridePooling,
+    /// L2-normalize the embeddings, only applied when used by a composite embedder.
+    #[serde(default)]
+    pub normalize: bool,
 }
 
 #[derive(
@@ -67,6 +70,7 @@ impl EmbedderOptions {
             revision: Some("617ca489d9e86b49b8167676d8220688b99db36e".into()),
             distribution: None,
             pooling: OverridePooling::UseModel,
+            normalize: false,
         }
     }
 }
@@ -311,7 +315,8 @@ impl Embedder {
         embeddings.get_on_dim(1, n_tokens - 1).map_err(EmbedError::tensor_value)
     }
 
-    pub fn embed_one(&self, text: &str) -> std::result::Result<Embedding, EmbedError> {
+    /// Runs the model on the tokens of the text, returning a tensor of shape `(1, n_tokens, hidden_size)`.
+    fn forward(&self, text: &str) -> Result<Tensor, EmbedError> {
         let tokens = self.tokenizer.encode(text, true).map_err(EmbedError::tokenize)?;
         let token_ids = tokens.get_ids();
         let token_ids = if token_ids.len() > 512 { &token_ids[..512] } else { token_ids };
@@ -319,10 +324,11 @@ impl Embedder {
             Tensor::new(token_ids, &self.model.device).map_err(EmbedError::tensor_shape)?;
         let token_ids = Tensor::stack(&[token_ids], 0).map_err(EmbedError::tensor_shape)?;
         let token_type_ids = token_ids.zeros_like().map_err(EmbedError::tensor_shape)?;
-        let embeddings = self
-            .model
-            .forward(&token_ids, &token_type_ids, None)
-            .map_err(EmbedError::model_forward)?;
+        self.model.forward(&token_ids, &token_type_ids, None).map_err(EmbedError::model_forward)
+    }
+
+    pub fn embed_one(&self, text: &str) -> std::result::Result<Embedding, EmbedError> {
+        let embeddings = self.forward(text)?;
 
         let embedding = Self::pooling(embeddings, self.pooling)?;
 
@@ -331,6 +337,23 @@ impl Embedder {
         Ok(embedding)
     }
 
+    /// Embeds each token of the text without pooling them, as late-interaction models expect.
+    ///
+    /// Returns one embedding per token, including the special tokens added by the tokenizer,
+    /// and at most 512 of them like the pooled embeddings.
+    pub fn embed_tokens_one(&self, text: &str) -> std::result::Result<Vec<Embedding>, EmbedError> {
+        let embeddings = self.forward(text)?;
+        let embeddings = embeddings.squeeze(0).map_err(EmbedError::tensor_shape)?;
+        embeddings.to_vec2().map_err(EmbedError::tensor_shape)
+    }
+
+    pub fn embed_tokens(
+        &self,
+        texts: Vec<String>,
+    ) -> std::result::Result<Vec<Vec<Embedding>>, EmbedError> {
+        texts.into_iter().map(|text| self.embed_tokens_one(&text)).collect()
+    }
+
     pub fn embed_index(
         &self,
         text_chunks: Vec<Vec<String>>,
@@ -371,3 +394,81 @@ impl Embedder {
         &self.cache
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use candle_core::Device;
+    use candle_nn::VarBuilder;
+    use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+    use tokenizers::models::wordlevel::WordLevel;
+    use tokenizers::pre_tokenizers::whitespace::Whitespace;
+    use tokenizers::Tokenizer;
+
+    use super::{Embedder, EmbedderOptions, Pooling};
+    use crate::vector::EmbeddingCache;
+
+    const HIDDEN_SIZE: usize = 8;
+
+    /// A tiny BERT model with zeroed weights and a tokenizer splitting on whitespaces,
+    /// so that the tests don't depend on fetching a model from the Hugging Face Hub.
+    fn tiny_embedder() -> Embedder {
+        let vocab: HashMap<String, u32> = ["[UNK]", "the", "quick", "brown", "fox"]
+            .into_iter()
+            .enumerate()
+            .map(|(id, word)| (word.to_string(), id as u32))
+            .collect();
+        let model = WordLevel::builder().vocab(vocab).unk_token("[UNK]".into()).build().unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Whitespace);
+
+        let config = Config {
+            vocab_size: 5,
+            hidden_size: HIDDEN_SIZE,
+            num_hidden_layers: 1,
+            num_attention_heads: 2,
+            intermediate_size: 16,
+            ..Default::default()
+        };
+        let vb = VarBuilder::zeros(DTYPE, &Device::Cpu);
+        let model = BertModel::load(vb, &config).unwrap();
+
+        Embedder {
+            model,
+            tokenizer,
+            options: EmbedderOptions::new(),
+            dimensions: HIDDEN_SIZE,
+            pooling: Pooling::Mean,
+            cache: EmbeddingCache::new(0),
+        }
+    }
+
+    #[test]
+    fn token_embeddings_shape() {
+        let embedder = tiny_embedder();
+        let texts =
+            vec!["the quick brown fox".to_string(), "fox".to_string(), "the unknown".into()];
+
+        let embeddings = embedder.embed_tokens(texts.clone()).unwrap();
+        assert_eq!(embeddings.len(), texts.len());
+        for (text, token_embeddings) in texts.iter().zip(&embeddings) {
+            let token_count = embedder.tokenizer.encode(text.as_str(), true).unwrap().len();
+            assert_eq!(token_embeddings.len(), token_count, "{text}");
+            assert!(token_embeddings.iter().all(|embedding| embedding.len() == HIDDEN_SIZE));
+        }
+        assert_eq!(embeddings.iter().map(Vec::len).collect::<Vec<_>>(), [4, 1, 2]);
+
+        // the pooled embedding keeps the dimensions of the token embeddings
+        assert_eq!(embedder.embed_one("the quick brown fox").unwrap().len(), HIDDEN_SIZE);
+    }
+
+    #[test]
+    fn token_embeddings_are_truncated() {
+        let embedder = tiny_embedder();
+        let text = "fox ".repeat(600);
+
+        let embeddings = embedder.embed_tokens_one(&text).unwrap();
+        assert_eq!(embeddings.len(), 512);
+    }
+}
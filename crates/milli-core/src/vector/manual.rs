@@ -12,6 +12,9 @@ pub struct Embedder {
 pub struct EmbedderOptions {
     pub dimensions: usize,
     pub distribution: Option<DistributionShift>,
+    /// L2-normalize the embeddings, only applied when used by a composite embedder.
+    #[serde(default)]
+    pub normalize: bool,
 }
 
 impl Embedder {
@@ -1,5 +1,9 @@
 use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{self, BufReader, BufWriter, Write};
 use std::num::NonZeroUsize;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
@@ -28,13 +32,21 @@ pub mod settings;
 
 pub mod ollama;
 pub mod rest;
+#[cfg(test)]
+mod test_server;
 
 pub use self::error::Error;
 
 pub type Embedding = Vec<f32>;
 
+/// The label of a group of chunks embedded together, typically the name of the embedded field.
+pub type FieldLabel = String;
+
 pub const REQUEST_PARALLELISM: usize = 40;
 pub const MAX_COMPOSITE_DISTANCE: f32 = 0.01;
+/// How long building a composite embedder waits for the embeddings checking its
+/// search and indexing embedders are similar.
+pub const COMPOSITE_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
 
 pub struct ArroyWrapper {
     quantized: bool,
@@ -557,8 +569,9 @@ pub enum Embedder {
     Composite(composite::Embedder),
 }
 
+/// A cache of the embeddings computed at search time, keyed by the embedded text.
 #[derive(Debug)]
-struct EmbeddingCache {
+pub struct EmbeddingCache {
     data: Option<Mutex<lru::LruCache<String, Embedding>>>,
 }
 
@@ -595,6 +608,110 @@ impl EmbeddingCache {
 
         cache.put(text, embedding);
     }
+
+    /// Writes the cached entries to the file at `path`, replacing it.
+    ///
+    /// The entries are written from the least to the most recently used so that loading them back
+    /// preserves their recency. The `options` fingerprint, see [`EmbedderOptions::fingerprint`],
+    /// identifies the options of the embedder that computed the embeddings.
+    pub fn persist(&self, path: &Path, options: u64) -> io::Result<()> {
+        let entries = match self.data.as_ref() {
+            Some(data) => {
+                let cache = data.lock().unwrap();
+                cache
+                    .iter()
+                    .rev()
+                    .map(|(text, embedding)| (text.clone(), embedding.clone()))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        let persisted =
+            PersistedEmbeddingCache { version: PersistedEmbeddingCache::VERSION, options, entries };
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(&mut writer, &persisted)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        writer.flush()
+    }
+
+    /// Creates a cache of capacity `cap` filled with the entries persisted at `path`.
+    ///
+    /// See [`Self::load_entries`] for the entries that are discarded.
+    pub fn load(path: &Path, cap: usize, dimensions: usize, options: u64) -> io::Result<Self> {
+        let cache = Self::new(cap);
+        cache.load_entries(path, dimensions, options)?;
+        Ok(cache)
+    }
+
+    /// Adds the entries persisted at `path` to the cache, returning the number of restored entries.
+    ///
+    /// The whole file is discarded when it was written by another version of the format or with
+    /// another `options` fingerprint, and so are the embeddings that do not have the expected
+    /// `dimensions`. Only the most recently used entries are kept when the file holds more entries
+    /// than the capacity of the cache.
+    pub fn load_entries(&self, path: &Path, dimensions: usize, options: u64) -> io::Result<usize> {
+        let Some(data) = self.data.as_ref() else {
+            return Ok(0);
+        };
+
+        let reader = BufReader::new(File::open(path)?);
+        let persisted: PersistedEmbeddingCache = bincode::deserialize_from(reader)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        if persisted.version != PersistedEmbeddingCache::VERSION {
+            tracing::warn!(
+                version = persisted.version,
+                "discarding an embedding cache persisted with another format version"
+            );
+            return Ok(0);
+        }
+        if persisted.options != options {
+            tracing::warn!("discarding an embedding cache persisted with other embedder options");
+            return Ok(0);
+        }
+
+        let mut cache = data.lock().unwrap();
+        let cap = cache.cap().get();
+        let restored = persisted
+            .entries
+            .into_iter()
+            .filter(|(text, embedding)| {
+                embedding.len() == dimensions && text.len() <= Self::MAX_TEXT_LEN
+            })
+            .collect::<Vec<_>>();
+        let skipped = restored.len().saturating_sub(cap);
+        let mut count = 0;
+        for (text, embedding) in restored.into_iter().skip(skipped) {
+            cache.put(text, embedding);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+/// The on-disk representation of an [`EmbeddingCache`].
+#[derive(Serialize, Deserialize)]
+struct PersistedEmbeddingCache {
+    version: u32,
+    /// The fingerprint of the options of the embedder that computed the embeddings.
+    options: u64,
+    /// The cached entries, from the least to the most recently used.
+    entries: Vec<(String, Embedding)>,
+}
+
+impl PersistedEmbeddingCache {
+    /// The version of the format, to bump whenever the representation changes.
+    const VERSION: u32 = 2;
+}
+
+/// Whether an embedding was found in the cache of the embedder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheOutcome {
+    /// The embedding was found in the cache.
+    Hit,
+    /// The embedding was computed by the embedder.
+    Miss,
 }
 
 /// Configuration for an embedder.
@@ -673,6 +790,32 @@ impl Default for EmbedderOptions {
     }
 }
 
+impl EmbedderOptions {
+    /// Adjusts the document template to the embedder, shortening its maximum number of bytes
+    /// by the index prompt prefix of the composite embedders.
+    pub fn document_prompt(
+        &self,
+        mut prompt: PromptData,
+    ) -> std::result::Result<PromptData, NewEmbedderError> {
+        if let EmbedderOptions::Composite(options) = self {
+            prompt.max_bytes = options.document_max_bytes(prompt.max_bytes)?;
+        }
+        Ok(prompt)
+    }
+
+    /// A hash of the options, stable across runs, telling whether the embeddings computed with
+    /// other options can be reused.
+    ///
+    /// Hashes the serialized options rather than using their [`Hash`], which skips the request
+    /// and response templates of the REST embedders, where the model is usually chosen.
+    pub fn fingerprint(&self) -> u64 {
+        let options = serde_json::to_vec(self).expect("the embedder options are serializable");
+        let mut hasher = fxhash::FxHasher64::default();
+        hasher.write(&options);
+        hasher.finish()
+    }
+}
+
 impl Embedder {
     /// Spawns a new embedder built from its options.
     pub fn new(
@@ -703,6 +846,52 @@ impl Embedder {
         })
     }
 
+    /// Like [`Self::new`], but fails when the embeddings checking a composite embedder
+    /// are not computed before the `deadline`.
+    pub fn new_with_deadline(
+        options: EmbedderOptions,
+        cache_cap: usize,
+        deadline: Instant,
+    ) -> std::result::Result<Self, NewEmbedderError> {
+        match options {
+            EmbedderOptions::Composite(options) => Ok(Self::Composite(
+                composite::Embedder::new_with_deadline(options, cache_cap, deadline)?,
+            )),
+            options => Self::new(options, cache_cap),
+        }
+    }
+
+    /// Spawns a new embedder whose cache is warmed up with the entries persisted at `cache_path`.
+    ///
+    /// Failing to read the persisted entries is not an error: the embedder then starts with an
+    /// empty cache. The entries persisted by an embedder with other options are discarded.
+    pub fn new_with_warm_cache(
+        options: EmbedderOptions,
+        cache_cap: usize,
+        cache_path: &Path,
+    ) -> std::result::Result<Self, NewEmbedderError> {
+        let fingerprint = options.fingerprint();
+        let embedder = Self::new(options, cache_cap)?;
+        if let Some(cache) = embedder.cache() {
+            match cache.load_entries(cache_path, embedder.dimensions(), fingerprint) {
+                Ok(count) => tracing::debug!(count, "embedding cache warmed up"),
+                Err(error) => tracing::warn!(%error, "could not warm up the embedding cache"),
+            }
+        }
+        Ok(embedder)
+    }
+
+    /// Persists the entries of the cache of the embedder to `path`, see [`Self::new_with_warm_cache`].
+    ///
+    /// The `options` must be the ones the embedder was built from. Does nothing for the embedders
+    /// without a cache.
+    pub fn persist_cache(&self, options: &EmbedderOptions, path: &Path) -> io::Result<()> {
+        match self.cache() {
+            Some(cache) => cache.persist(path, options.fingerprint()),
+            None => Ok(()),
+        }
+    }
+
     /// Embed in search context
 
     #[tracing::instrument(level = "debug", skip_all, target = "search")]
@@ -711,6 +900,12 @@ impl Embedder {
         text: &str,
         deadline: Option<Instant>,
     ) -> std::result::Result<Embedding, EmbedError> {
+        // the composite embedders cache their texts once preprocessed
+        let text = match self {
+            Embedder::Composite(embedder) => embedder.search_text(text),
+            _ => std::borrow::Cow::Borrowed(text),
+        };
+        let text = text.as_ref();
         if let Some(cache) = self.cache() {
             if let Some(embedding) = cache.get(text) {
                 tracing::trace!(text, "embedding found in cache");
@@ -754,7 +949,24 @@ impl Embedder {
             Embedder::Ollama(embedder) => embedder.embed_index(text_chunks, threads),
             Embedder::UserProvided(embedder) => embedder.embed_index(text_chunks),
             Embedder::Rest(embedder) => embedder.embed_index(text_chunks, threads),
-            Embedder::Composite(embedder) => embedder.index.embed_index(text_chunks, threads),
+            Embedder::Composite(embedder) => embedder.embed_index(text_chunks, threads),
+        }
+    }
+
+    /// Embed multiple chunks of texts, keeping the label of each chunk.
+    ///
+    /// Each label gets back the embeddings of its own chunk, so that the fields of a document
+    /// rendered with different prompts can be stored as separate vectors.
+    pub fn embed_index_labeled(
+        &self,
+        labeled_chunks: Vec<(FieldLabel, Vec<String>)>,
+        threads: &ThreadPoolNoAbort,
+    ) -> std::result::Result<Vec<(FieldLabel, Vec<Embedding>)>, EmbedError> {
+        match self {
+            Embedder::Composite(embedder) => embedder.embed_index_labeled(labeled_chunks, threads),
+            _ => embed_labeled_chunks(labeled_chunks, self.dimensions(), |text_chunks| {
+                self.embed_index(text_chunks, threads)
+            }),
         }
     }
 
@@ -770,7 +982,7 @@ impl Embedder {
             Embedder::Ollama(embedder) => embedder.embed_index_ref(texts, threads),
             Embedder::UserProvided(embedder) => embedder.embed_index_ref(texts),
             Embedder::Rest(embedder) => embedder.embed_index_ref(texts, threads),
-            Embedder::Composite(embedder) => embedder.index.embed_index_ref(texts, threads),
+            Embedder::Composite(embedder) => embedder.embed_index_ref(texts, threads),
         }
     }
 
@@ -979,3 +1191,166 @@ pub fn arroy_db_range_for_embedder(embedder_id: u8) -> impl Iterator<Item = u16>
 
     (0..=u8::MAX).map(move |k| embedder_id | (k as u16))
 }
+
+/// Embeds the chunks of all the labels at once with `embed_index`, then gives each label
+/// back the embeddings of its chunk, checking that they all have the expected dimensions.
+pub(crate) fn embed_labeled_chunks(
+    labeled_chunks: Vec<(FieldLabel, Vec<String>)>,
+    dimensions: usize,
+    embed_index: impl FnOnce(Vec<Vec<String>>) -> std::result::Result<Vec<Vec<Embedding>>, EmbedError>,
+) -> std::result::Result<Vec<(FieldLabel, Vec<Embedding>)>, EmbedError> {
+    let (labels, text_chunks): (Vec<_>, Vec<_>) = labeled_chunks.into_iter().unzip();
+    let chunk_lens: Vec<_> = text_chunks.iter().map(Vec::len).collect();
+    let embeddings = embed_index(text_chunks)?;
+
+    if embeddings.len() != labels.len()
+        || embeddings.iter().zip(&chunk_lens).any(|(embeddings, len)| embeddings.len() != *len)
+    {
+        return Err(EmbedError::missing_embedding());
+    }
+    if let Some(embedding) =
+        embeddings.iter().flatten().find(|embedding| embedding.len() != dimensions)
+    {
+        return Err(EmbedError::unexpected_dimension(dimensions, embedding.len()));
+    }
+
+    Ok(labels.into_iter().zip(embeddings).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        embed_labeled_chunks, rest, EmbedError, Embedder, EmbedderOptions, Embedding,
+        EmbeddingCache, PersistedEmbeddingCache,
+    };
+
+    fn fake_embed_index(text_chunks: Vec<Vec<String>>) -> Result<Vec<Vec<Embedding>>, EmbedError> {
+        Ok(text_chunks
+            .into_iter()
+            .map(|chunk| chunk.iter().map(|text| vec![text.len() as f32, 1.0]).collect())
+            .collect())
+    }
+
+    #[test]
+    fn labeled_chunks() {
+        let labeled_chunks = vec![
+            ("title".to_string(), vec!["a".to_string(), "bb".to_string()]),
+            ("overview".to_string(), vec!["cccc".to_string()]),
+        ];
+
+        let embeddings = embed_labeled_chunks(labeled_chunks, 2, fake_embed_index).unwrap();
+        assert_eq!(
+            embeddings,
+            vec![
+                ("title".to_string(), vec![vec![1.0, 1.0], vec![2.0, 1.0]]),
+                ("overview".to_string(), vec![vec![4.0, 1.0]]),
+            ]
+        );
+    }
+
+    #[test]
+    fn labeled_chunks_dimensions_mismatch() {
+        let labeled_chunks = vec![("title".to_string(), vec!["a".to_string()])];
+
+        let error = embed_labeled_chunks(labeled_chunks, 3, fake_embed_index).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "runtime error: was expecting embeddings of dimension `3`, got embeddings of dimensions `2`"
+        );
+    }
+
+    #[test]
+    fn persisted_cache_is_restored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("embedding-cache");
+
+        let cache = EmbeddingCache::new(3);
+        cache.put("evicted".to_string(), vec![0.0, 0.0]);
+        cache.put("first".to_string(), vec![1.0, 1.0]);
+        cache.put("wrong dimensions".to_string(), vec![2.0, 2.0, 2.0]);
+        cache.put("second".to_string(), vec![3.0, 3.0]);
+        // makes "first" the most recently used entry
+        assert!(cache.get("first").is_some());
+        cache.persist(&path, 42).unwrap();
+
+        let restored = EmbeddingCache::load(&path, 3, 2, 42).unwrap();
+        assert_eq!(restored.get("first"), Some(vec![1.0, 1.0]));
+        assert_eq!(restored.get("second"), Some(vec![3.0, 3.0]));
+        assert_eq!(restored.get("wrong dimensions"), None);
+        assert_eq!(restored.get("evicted"), None);
+
+        // only the most recently used entries fit in a smaller cache
+        let restored = EmbeddingCache::load(&path, 1, 2, 42).unwrap();
+        assert_eq!(restored.get("first"), Some(vec![1.0, 1.0]));
+        assert_eq!(restored.get("second"), None);
+    }
+
+    #[test]
+    fn persisted_cache_of_another_version_is_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("embedding-cache");
+
+        let persisted = PersistedEmbeddingCache {
+            version: PersistedEmbeddingCache::VERSION + 1,
+            options: 42,
+            entries: vec![("text".to_string(), vec![1.0, 1.0])],
+        };
+        std::fs::write(&path, bincode::serialize(&persisted).unwrap()).unwrap();
+
+        let cache = EmbeddingCache::new(3);
+        assert_eq!(cache.load_entries(&path, 2, 42).unwrap(), 0);
+        assert_eq!(cache.get("text"), None);
+    }
+
+    #[test]
+    fn persisted_cache_of_other_options_is_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("embedding-cache");
+
+        let cache = EmbeddingCache::new(3);
+        cache.put("text".to_string(), vec![1.0, 1.0]);
+        cache.persist(&path, 42).unwrap();
+
+        let cache = EmbeddingCache::new(3);
+        assert_eq!(cache.load_entries(&path, 2, 43).unwrap(), 0);
+        assert_eq!(cache.get("text"), None);
+    }
+
+    #[test]
+    fn embedder_with_warm_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("embedding-cache");
+        // never reached, the embeddings are all found in the cache
+        let options = EmbedderOptions::Rest(rest::EmbedderOptions {
+            api_key: None,
+            distribution: None,
+            dimensions: Some(2),
+            url: "http://localhost:1/embed".to_string(),
+            request: serde_json::json!({ "input": "{{text}}" }),
+            response: serde_json::json!({ "embedding": "{{embedding}}" }),
+            headers: Default::default(),
+            normalize: false,
+            batch_encoding: Default::default(),
+        });
+
+        // a missing file starts the embedder with an empty cache
+        let embedder = Embedder::new_with_warm_cache(options.clone(), 10, &path).unwrap();
+        embedder.cache().unwrap().put("hello".to_string(), vec![1.0, 2.0]);
+        embedder.persist_cache(&options, &path).unwrap();
+
+        let embedder = Embedder::new_with_warm_cache(options.clone(), 10, &path).unwrap();
+        assert_eq!(embedder.embed_search("hello", None).unwrap(), vec![1.0, 2.0]);
+
+        // the embeddings of another model are not reused, be it chosen by the url or the request
+        let EmbedderOptions::Rest(rest_options) = options else { unreachable!() };
+        let mut other_url = rest_options.clone();
+        other_url.url = "http://localhost:1/other-model/embed".to_string();
+        let mut other_request = rest_options;
+        other_request.request = serde_json::json!({ "input": "{{text}}", "model": "other-model" });
+        for other_options in [other_url, other_request] {
+            let other_options = EmbedderOptions::Rest(other_options);
+            let embedder = Embedder::new_with_warm_cache(other_options, 10, &path).unwrap();
+            assert_eq!(embedder.cache().unwrap().get("hello"), None);
+        }
+    }
+}
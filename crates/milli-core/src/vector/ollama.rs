@@ -22,6 +22,9 @@ pub struct EmbedderOptions {
     pub api_key: Option<String>,
     pub distribution: Option<DistributionShift>,
     pub dimensions: Option<usize>,
+    /// L2-normalize the embeddings, only applied when used by a composite embedder.
+    #[serde(default)]
+    pub normalize: bool,
 }
 
 impl EmbedderOptions {
@@ -36,6 +39,7 @@ impl EmbedderOptions {
             url,
             distribution: None,
             dimensions,
+            normalize: false,
         }
     }
 
@@ -70,6 +74,8 @@ impl EmbedderOptions {
             request,
             response,
             headers: Default::default(),
+            normalize: false,
+            batch_encoding: Default::default(),
         })
     }
 }
@@ -20,6 +20,9 @@ pub struct EmbedderOptions {
     pub embedding_model: EmbeddingModel,
     pub dimensions: Option<usize>,
     pub distribution: Option<DistributionShift>,
+    /// L2-normalize the embeddings, only applied when used by a composite embedder.
+    #[serde(default)]
+    pub normalize: bool,
 }
 
 impl EmbedderOptions {
@@ -159,6 +162,7 @@ impl EmbedderOptions {
             dimensions: None,
             distribution: None,
             url: None,
+            normalize: false,
         }
     }
 }
@@ -190,6 +194,7 @@ impl Embedder {
                 api_key: (!api_key.is_empty()).then(|| api_key.clone()),
                 distribution: None,
                 dimensions: Some(options.dimensions()),
+                normalize: false,
                 url,
                 request: options.request(),
                 response: serde_json::json!({
@@ -200,6 +205,7 @@ impl Embedder {
                     ]
                 }),
                 headers: Default::default(),
+                batch_encoding: Default::default(),
             },
             cache_cap,
             super::rest::ConfigurationSource::OpenAi,
@@ -323,6 +329,11 @@ impl Embedder {
     pub(super) fn cache(&self) -> &EmbeddingCache {
         self.rest_embedder.cache()
     }
+
+    /// The rate limit announced by OpenAI in its last response with rate limit headers.
+    pub fn rate_limit_state(&self) -> Option<super::rest::RateLimitState> {
+        self.rest_embedder.rate_limit_state()
+    }
 }
 
 impl fmt::Debug for Embedder {
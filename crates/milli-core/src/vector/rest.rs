@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
-use std::time::Instant;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use deserr::Deserr;
 use rand::Rng;
@@ -19,6 +20,8 @@ use crate::ThreadPoolNoAbort;
 pub struct Retry {
     pub error: EmbedError,
     strategy: RetryStrategy,
+    /// The delay asked by the provider in a `Retry-After` header.
+    retry_after: Option<Duration>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,27 +40,37 @@ pub enum RetryStrategy {
 
 impl Retry {
     pub fn give_up(error: EmbedError) -> Self {
-        Self { error, strategy: RetryStrategy::GiveUp }
+        Self { error, strategy: RetryStrategy::GiveUp, retry_after: None }
     }
 
     pub fn retry_later(error: EmbedError) -> Self {
-        Self { error, strategy: RetryStrategy::Retry }
+        Self { error, strategy: RetryStrategy::Retry, retry_after: None }
     }
 
     pub fn retry_tokenized(error: EmbedError) -> Self {
-        Self { error, strategy: RetryStrategy::RetryTokenized }
+        Self { error, strategy: RetryStrategy::RetryTokenized, retry_after: None }
     }
 
     pub fn rate_limited(error: EmbedError) -> Self {
-        Self { error, strategy: RetryStrategy::RetryAfterRateLimit }
+        Self { error, strategy: RetryStrategy::RetryAfterRateLimit, retry_after: None }
+    }
+
+    /// Waits for the delay asked by the provider instead of the delay of the strategy,
+    /// unless the strategy is to give up.
+    pub fn with_retry_after(mut self, retry_after: Option<Duration>) -> Self {
+        self.retry_after = retry_after;
+        self
     }
 
     pub fn into_duration(self, attempt: u32) -> Result<std::time::Duration, EmbedError> {
-        match self.strategy {
-            RetryStrategy::GiveUp => Err(self.error),
-            RetryStrategy::Retry => Ok(std::time::Duration::from_millis((10u64).pow(attempt))),
-            RetryStrategy::RetryTokenized => Ok(std::time::Duration::from_millis(1)),
-            RetryStrategy::RetryAfterRateLimit => {
+        match (self.strategy, self.retry_after) {
+            (RetryStrategy::GiveUp, _) => Err(self.error),
+            (_, Some(retry_after)) => Ok(retry_after),
+            (RetryStrategy::Retry, None) => {
+                Ok(std::time::Duration::from_millis((10u64).pow(attempt)))
+            }
+            (RetryStrategy::RetryTokenized, None) => Ok(std::time::Duration::from_millis(1)),
+            (RetryStrategy::RetryAfterRateLimit, None) => {
                 Ok(std::time::Duration::from_millis(100 + 10u64.pow(attempt)))
             }
         }
@@ -72,6 +85,40 @@ impl Retry {
     }
 }
 
+/// The rate limit announced by the provider in the headers of its last response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitState {
+    /// The delay to wait before the next request, from the `Retry-After` header.
+    pub retry_after: Option<Duration>,
+    /// The number of requests left before being rate limited, from the
+    /// `X-RateLimit-Remaining` header, or the `X-RateLimit-Remaining-Requests` header of OpenAI.
+    pub remaining: Option<u64>,
+}
+
+impl RateLimitState {
+    /// Reads the rate limit headers of the response, if it has any.
+    fn from_response(response: &ureq::Response) -> Option<Self> {
+        let retry_after = response.header("Retry-After").and_then(parse_retry_after);
+        let remaining = response
+            .header("X-RateLimit-Remaining")
+            .or_else(|| response.header("X-RateLimit-Remaining-Requests"))
+            .and_then(|remaining| remaining.trim().parse().ok());
+        (retry_after.is_some() || remaining.is_some()).then_some(Self { retry_after, remaining })
+    }
+}
+
+/// Parses a `Retry-After` header, either a number of seconds or an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date =
+        time::OffsetDateTime::parse(value, &time::format_description::well_known::Rfc2822).ok()?;
+    // a date in the past means that the request can be retried right away
+    Some((date - time::OffsetDateTime::now_utc()).try_into().unwrap_or_default())
+}
+
 #[derive(Debug)]
 pub struct Embedder {
     data: EmbedderData,
@@ -84,12 +131,14 @@ pub struct Embedder {
 #[derive(Debug)]
 struct EmbedderData {
     client: ureq::Agent,
-    bearer: Option<String>,
+    /// Sent with every request, see [`request_headers`].
     headers: BTreeMap<String, String>,
     url: String,
     request: Request,
     response: Response,
     configuration_source: ConfigurationSource,
+    /// The rate limit announced in the last response with rate limit headers.
+    rate_limit: Mutex<Option<RateLimitState>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -101,6 +150,12 @@ pub struct EmbedderOptions {
     pub request: serde_json::Value,
     pub response: serde_json::Value,
     pub headers: BTreeMap<String, String>,
+    /// L2-normalize the embeddings, only applied when used by a composite embedder.
+    #[serde(default)]
+    pub normalize: bool,
+    /// How the texts of a batch are sent in the body of a request.
+    #[serde(default)]
+    pub batch_encoding: BatchEncoding,
 }
 
 impl std::hash::Hash for EmbedderOptions {
@@ -109,12 +164,38 @@ impl std::hash::Hash for EmbedderOptions {
         self.distribution.hash(state);
         self.dimensions.hash(state);
         self.url.hash(state);
+        self.normalize.hash(state);
+        self.batch_encoding.hash(state);
         // skip hashing the request and response
         // collisions in regular usage should be minimal,
         // and the list is limited to 256 values anyway
     }
 }
 
+/// How the texts of a batch are encoded in the body of a request.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Deserialize,
+    Serialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Deserr,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+#[deserr(rename_all = camelCase, deny_unknown_fields)]
+pub enum BatchEncoding {
+    /// A single JSON value, the `request` template with all the texts of the batch injected.
+    #[default]
+    JsonArray,
+    /// Newline-delimited JSON, the `request` template with one text of the batch injected per line.
+    Ndjson,
+}
+
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash, Deserr)]
 #[serde(rename_all = "camelCase")]
 #[deserr(rename_all = camelCase, deny_unknown_fields)]
@@ -129,25 +210,25 @@ impl Embedder {
         cache_cap: usize,
         configuration_source: ConfigurationSource,
     ) -> Result<Self, NewEmbedderError> {
-        let bearer = options.api_key.as_deref().map(|api_key| format!("Bearer {api_key}"));
-
         let client = ureq::AgentBuilder::new()
             .max_idle_connections(REQUEST_PARALLELISM * 2)
             .max_idle_connections_per_host(REQUEST_PARALLELISM * 2)
             .timeout(std::time::Duration::from_secs(30))
             .build();
 
-        let request = Request::new(options.request)?;
+        let request = Request::new(options.request, options.batch_encoding)?;
         let response = Response::new(options.response, &request)?;
+        let headers =
+            request_headers(options.api_key.as_deref(), request.content_type(), options.headers);
 
         let data = EmbedderData {
             client,
-            bearer,
             url: options.url,
             request,
             response,
             configuration_source,
-            headers: options.headers,
+            headers,
+            rate_limit: Mutex::new(None),
         };
 
         let dimensions = if let Some(dimensions) = options.dimensions {
@@ -269,6 +350,32 @@ impl Embedder {
     pub(super) fn cache(&self) -> &EmbeddingCache {
         &self.cache
     }
+
+    /// The rate limit announced by the provider in the last response with rate limit headers,
+    /// so that the callers can adapt the rate of their requests.
+    pub fn rate_limit_state(&self) -> Option<RateLimitState> {
+        *self.data.rate_limit.lock().unwrap()
+    }
+}
+
+/// Merges the `user` headers over the default headers: the `Content-Type` of the request
+/// and, when there is an API key, its `Authorization` bearer.
+///
+/// The header names are case-insensitive, a user header replaces the default header
+/// of the same name whatever its case.
+fn request_headers(
+    api_key: Option<&str>,
+    content_type: &str,
+    user: BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut headers = BTreeMap::new();
+    headers.insert("Content-Type".to_string(), content_type.to_string());
+    if let Some(api_key) = api_key {
+        headers.insert("Authorization".to_string(), format!("Bearer {api_key}"));
+    }
+    headers.retain(|name, _| !user.keys().any(|user| user.eq_ignore_ascii_case(name)));
+    headers.extend(user);
+    headers
 }
 
 fn infer_dimensions(data: &EmbedderData) -> Result<usize, NewEmbedderError> {
@@ -288,21 +395,16 @@ fn embed<S>(
 where
     S: Serialize,
 {
-    let request = data.client.post(&data.url);
-    let request = if let Some(bearer) = &data.bearer {
-        request.set("Authorization", bearer)
-    } else {
-        request
-    };
-    let mut request = request.set("Content-Type", "application/json");
+    let mut request = data.client.post(&data.url);
     for (header, value) in &data.headers {
         request = request.set(header.as_str(), value.as_str());
     }
 
-    let body = data.request.inject_texts(inputs);
+    let body = data.request.encode_texts(inputs);
 
     for attempt in 0..10 {
-        let response = request.clone().send_json(&body);
+        let response = with_deadline(request.clone(), deadline).send_bytes(&body);
+        record_rate_limit(data, &response);
         let result = check_response(response, data.configuration_source).and_then(|response| {
             response_to_embedding(response, data, expected_count, expected_dimension)
         });
@@ -328,15 +430,16 @@ where
 
         let retry_duration = retry_duration.min(std::time::Duration::from_secs(60)); // don't wait more than a minute
 
-        // randomly up to double the retry duration
+        // randomly up to double the retry duration, a `Retry-After` can ask for no delay at all
         let retry_duration = retry_duration
-            + rand::thread_rng().gen_range(std::time::Duration::ZERO..retry_duration);
+            + rand::thread_rng().gen_range(std::time::Duration::ZERO..=retry_duration);
 
         tracing::warn!("Attempt #{}, retrying after {}ms.", attempt, retry_duration.as_millis());
         std::thread::sleep(retry_duration);
     }
 
-    let response = request.send_json(&body);
+    let response = with_deadline(request, deadline).send_bytes(&body);
+    record_rate_limit(data, &response);
     let result = check_response(response, data.configuration_source);
     result.map_err(Retry::into_error).and_then(|response| {
         response_to_embedding(response, data, expected_count, expected_dimension)
@@ -344,6 +447,25 @@ where
     })
 }
 
+/// Bounds the duration of the request to the time left before the `deadline`,
+/// in place of the timeout of the agent.
+fn with_deadline(request: ureq::Request, deadline: Option<Instant>) -> ureq::Request {
+    match deadline {
+        Some(deadline) => request.timeout(deadline.saturating_duration_since(Instant::now())),
+        None => request,
+    }
+}
+
+fn record_rate_limit(data: &EmbedderData, response: &Result<ureq::Response, ureq::Error>) {
+    let response = match response {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        Err(ureq::Error::Transport(_)) => return,
+    };
+    if let Some(state) = RateLimitState::from_response(response) {
+        *data.rate_limit.lock().unwrap() = Some(state);
+    }
+}
+
 fn check_response(
     response: Result<ureq::Response, ureq::Error>,
     configuration_source: ConfigurationSource,
@@ -351,8 +473,9 @@ fn check_response(
     match response {
         Ok(response) => Ok(response),
         Err(ureq::Error::Status(code, response)) => {
+            let retry_after = response.header("Retry-After").and_then(parse_retry_after);
             let error_response: Option<String> = response.into_string().ok();
-            Err(match code {
+            let retry = match code {
                 401 => Retry::give_up(EmbedError::rest_unauthorized(
                     error_response,
                     configuration_source,
@@ -369,7 +492,8 @@ fn check_response(
                     Retry::give_up(EmbedError::rest_other_status_code(code, error_response))
                 }
                 _ => Retry::retry_later(EmbedError::rest_other_status_code(code, error_response)),
-            })
+            };
+            Err(retry.with_retry_after(retry_after))
         }
         Err(ureq::Error::Transport(transport)) => {
             Err(Retry::retry_later(EmbedError::rest_network(transport)))
@@ -400,7 +524,7 @@ fn response_to_embedding(
     if let Some(dimensions) = expected_dimensions {
         for embedding in &embeddings {
             if embedding.len() != dimensions {
-                return Err(Retry::give_up(EmbedError::rest_unexpected_dimension(
+                return Err(Retry::give_up(EmbedError::unexpected_dimension(
                     dimensions,
                     embedding.len(),
                 )));
@@ -418,10 +542,14 @@ pub(super) const REPEAT_PLACEHOLDER: &str = "{{..}}";
 #[derive(Debug)]
 pub struct Request {
     template: ValueTemplate,
+    batch_encoding: BatchEncoding,
 }
 
 impl Request {
-    pub fn new(template: serde_json::Value) -> Result<Self, NewEmbedderError> {
+    pub fn new(
+        template: serde_json::Value,
+        batch_encoding: BatchEncoding,
+    ) -> Result<Self, NewEmbedderError> {
         let template = match ValueTemplate::new(template, REQUEST_PLACEHOLDER, REPEAT_PLACEHOLDER) {
             Ok(template) => template,
             Err(error) => {
@@ -431,17 +559,40 @@ impl Request {
             }
         };
 
-        Ok(Self { template })
+        Ok(Self { template, batch_encoding })
     }
 
     fn input_type(&self) -> InputType {
-        if self.template.has_array_value() {
+        // every line of a newline-delimited body is a text of the batch
+        if self.template.has_array_value() || self.batch_encoding == BatchEncoding::Ndjson {
             InputType::TextArray
         } else {
             InputType::Text
         }
     }
 
+    fn content_type(&self) -> &'static str {
+        match self.batch_encoding {
+            BatchEncoding::JsonArray => "application/json",
+            BatchEncoding::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    /// Returns the body of the request embedding the texts, encoded according to the batch encoding.
+    pub fn encode_texts<S: Serialize>(&self, texts: &[S]) -> Vec<u8> {
+        match self.batch_encoding {
+            BatchEncoding::JsonArray => serde_json::to_vec(&self.inject_texts(texts)).unwrap(),
+            BatchEncoding::Ndjson => {
+                let mut body = Vec::new();
+                for text in texts {
+                    serde_json::to_writer(&mut body, &self.inject_texts([text])).unwrap();
+                    body.push(b'\n');
+                }
+                body
+            }
+        }
+    }
+
     pub fn inject_texts<S: Serialize>(
         &self,
         texts: impl IntoIterator<Item = S>,
@@ -467,7 +618,7 @@ impl Response {
             }
         };
 
-        match (template.has_array_value(), request.template.has_array_value()) {
+        match (template.has_array_value(), request.input_type() == InputType::TextArray) {
             (true, true) | (false, false) => Ok(Self {template}),
             (true, false) => Err(NewEmbedderError::rest_could_not_parse_template("in `response`: `response` has multiple embeddings, but `request` has only one text to embed".to_string())),
             (false, true) => Err(NewEmbedderError::rest_could_not_parse_template("in `response`: `response` has a single embedding, but `request` has multiple texts to embed".to_string())),
@@ -491,3 +642,264 @@ impl Response {
         Ok(embeddings)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use super::{
+        parse_retry_after, request_headers, BatchEncoding, ConfigurationSource, Embedder,
+        EmbedderOptions, RateLimitState,
+    };
+    use crate::vector::test_server::{self, Response};
+
+    /// Serves the `response` to every request, recording their content type and body.
+    fn recording_server(
+        response: serde_json::Value,
+    ) -> (String, Arc<Mutex<Vec<(String, String)>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let url = test_server::serve(move |request| {
+            let body = String::from_utf8(request.body).unwrap();
+            recorded.lock().unwrap().push((request.content_type, body));
+            Response::ok(response.clone())
+        });
+        (url, requests)
+    }
+
+    fn rest_embedder(
+        url: String,
+        request: serde_json::Value,
+        batch_encoding: BatchEncoding,
+    ) -> Embedder {
+        let options = EmbedderOptions {
+            api_key: None,
+            distribution: None,
+            dimensions: Some(2),
+            url,
+            request,
+            response: serde_json::json!({ "data": ["{{embedding}}", "{{..}}"] }),
+            headers: Default::default(),
+            normalize: false,
+            batch_encoding,
+        };
+        Embedder::new(options, 0, ConfigurationSource::User).unwrap()
+    }
+
+    #[test]
+    fn batch_encoding() {
+        let (url, requests) =
+            recording_server(serde_json::json!({ "data": [[1.0, 0.0], [0.0, 1.0]] }));
+        let texts = vec!["kefir".to_string(), "echo".to_string()];
+
+        let request = serde_json::json!({ "input": ["{{text}}", "{{..}}"] });
+        let embedder = rest_embedder(url.clone(), request, BatchEncoding::JsonArray);
+        let embeddings = embedder.embed(texts.clone(), None).unwrap();
+        assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        let (content_type, body) = requests.lock().unwrap().pop().unwrap();
+        assert_eq!(content_type, "application/json");
+        assert_eq!(body, r#"{"input":["kefir","echo"]}"#);
+
+        // every line of the body is a request for a single text
+        let request = serde_json::json!({ "input": "{{text}}", "model": "small" });
+        let embedder = rest_embedder(url, request, BatchEncoding::Ndjson);
+        assert_eq!(embedder.prompt_count_in_chunk_hint(), 10);
+        let embeddings = embedder.embed(texts, None).unwrap();
+        assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        let (content_type, body) = requests.lock().unwrap().pop().unwrap();
+        assert_eq!(content_type, "application/x-ndjson");
+        assert_eq!(
+            body,
+            "{\"input\":\"kefir\",\"model\":\"small\"}\n{\"input\":\"echo\",\"model\":\"small\"}\n"
+        );
+    }
+
+    #[test]
+    fn default_headers() {
+        use std::collections::BTreeMap;
+
+        let headers = |api_key: Option<&str>, user: &[(&str, &str)]| {
+            let user = user.iter().map(|(name, value)| (name.to_string(), value.to_string()));
+            request_headers(api_key, "application/json", user.collect())
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+        let header = |name: &str, value: &str| (name.to_string(), value.to_string());
+
+        assert_eq!(headers(None, &[]), vec![header("Content-Type", "application/json")]);
+        assert_eq!(
+            headers(Some("sk-123"), &[]),
+            vec![
+                header("Authorization", "Bearer sk-123"),
+                header("Content-Type", "application/json"),
+            ]
+        );
+
+        // the user headers win, whatever the case of their name
+        assert_eq!(
+            headers(Some("sk-123"), &[("content-type", "text/plain"), ("X-Trace", "on")]),
+            vec![
+                header("Authorization", "Bearer sk-123"),
+                header("X-Trace", "on"),
+                header("content-type", "text/plain"),
+            ]
+        );
+        assert_eq!(
+            headers(Some("sk-123"), &[("Authorization", "Token abc")]),
+            vec![header("Authorization", "Token abc"), header("Content-Type", "application/json")]
+        );
+
+        // the user content type is sent in place of the default one
+        let (url, requests) = recording_server(serde_json::json!({ "embedding": [1.0, 0.0] }));
+        let options = EmbedderOptions {
+            api_key: Some("sk-123".to_string()),
+            distribution: None,
+            dimensions: Some(2),
+            url,
+            request: serde_json::json!({ "input": "{{text}}" }),
+            response: serde_json::json!({ "embedding": "{{embedding}}" }),
+            headers: BTreeMap::from([header("Content-Type", "application/vnd.embed+json")]),
+            normalize: false,
+            batch_encoding: Default::default(),
+        };
+        let embedder = Embedder::new(options, 0, ConfigurationSource::User).unwrap();
+        assert_eq!(embedder.data.headers["Authorization"], "Bearer sk-123");
+        embedder.embed(vec!["kefir".to_string()], None).unwrap();
+        let (content_type, _) = requests.lock().unwrap().pop().unwrap();
+        assert_eq!(content_type, "application/vnd.embed+json");
+    }
+
+    #[test]
+    fn batch_encoding_defaults_to_json_array() {
+        let options: EmbedderOptions = serde_json::from_value(serde_json::json!({
+            "api_key": null,
+            "distribution": null,
+            "dimensions": 2,
+            "url": "http://localhost:1/embed",
+            "request": { "input": "{{text}}" },
+            "response": { "embedding": "{{embedding}}" },
+            "headers": {},
+        }))
+        .unwrap();
+        assert_eq!(options.batch_encoding, BatchEncoding::JsonArray);
+    }
+
+    #[test]
+    fn batch_encoding_settings() {
+        use crate::update::settings::validate_embedding_settings;
+        use crate::update::Setting;
+        use crate::vector::settings::{EmbedderSource, EmbeddingSettings};
+        use crate::vector::EmbeddingConfig;
+
+        let settings = |batch_encoding, response| EmbeddingSettings {
+            source: Setting::Set(EmbedderSource::Rest),
+            url: Setting::Set("http://localhost:1/embed".to_string()),
+            request: Setting::Set(serde_json::json!({ "input": "{{text}}" })),
+            response: Setting::Set(response),
+            batch_encoding,
+            ..Default::default()
+        };
+        let single = serde_json::json!({ "embedding": "{{embedding}}" });
+        let multiple = serde_json::json!({ "data": ["{{embedding}}", "{{..}}"] });
+
+        // every line of a newline-delimited body is a text to embed
+        let ndjson = Setting::Set(BatchEncoding::Ndjson);
+        validate_embedding_settings(Setting::Set(settings(ndjson, multiple.clone())), "default")
+            .unwrap();
+        let error =
+            validate_embedding_settings(Setting::Set(settings(ndjson, single.clone())), "default")
+                .unwrap_err();
+        assert!(error.to_string().contains("`response` has a single embedding"), "{error}");
+        validate_embedding_settings(Setting::Set(settings(Setting::NotSet, single)), "default")
+            .unwrap();
+
+        let config = EmbeddingConfig::from(settings(ndjson, multiple));
+        let crate::vector::EmbedderOptions::Rest(options) = &config.embedder_options else {
+            panic!("expected a rest embedder, got {:?}", config.embedder_options)
+        };
+        assert_eq!(options.batch_encoding, BatchEncoding::Ndjson);
+        assert_eq!(EmbeddingSettings::from(config).batch_encoding, ndjson);
+    }
+
+    /// Serves the scripted status, headers and bodies in order, the last one to the
+    /// requests in excess.
+    fn scripted_server(
+        responses: Vec<(u16, Vec<(&'static str, &'static str)>, serde_json::Value)>,
+    ) -> String {
+        let mut index = 0;
+        test_server::serve(move |_request| {
+            let (status, headers, body) = responses[index.min(responses.len() - 1)].clone();
+            index += 1;
+            Response { status, headers, body }
+        })
+    }
+
+    #[test]
+    fn rate_limit_headers() {
+        let embeddings = serde_json::json!({ "data": [[1.0, 0.0]] });
+        let url = scripted_server(vec![
+            (200, vec![("X-RateLimit-Remaining", "42")], embeddings.clone()),
+            (200, vec![], embeddings.clone()),
+            (200, vec![("x-ratelimit-remaining-requests", "41")], embeddings),
+        ]);
+        let request = serde_json::json!({ "input": ["{{text}}", "{{..}}"] });
+        let embedder = rest_embedder(url, request, BatchEncoding::JsonArray);
+        assert_eq!(embedder.rate_limit_state(), None);
+
+        embedder.embed(vec!["kefir".to_string()], None).unwrap();
+        let expected = RateLimitState { retry_after: None, remaining: Some(42) };
+        assert_eq!(embedder.rate_limit_state(), Some(expected));
+
+        // a response without rate limit headers keeps the last announced rate limit
+        embedder.embed(vec!["kefir".to_string()], None).unwrap();
+        assert_eq!(embedder.rate_limit_state(), Some(expected));
+
+        // the OpenAI header is read too
+        embedder.embed(vec!["kefir".to_string()], None).unwrap();
+        let expected = RateLimitState { retry_after: None, remaining: Some(41) };
+        assert_eq!(embedder.rate_limit_state(), Some(expected));
+    }
+
+    #[test]
+    fn retry_after_delays_the_retry() {
+        let url = scripted_server(vec![
+            (
+                429,
+                vec![("Retry-After", "1"), ("X-RateLimit-Remaining", "0")],
+                serde_json::json!({ "error": "slow down" }),
+            ),
+            (
+                200,
+                vec![("X-RateLimit-Remaining", "9")],
+                serde_json::json!({ "data": [[1.0, 0.0]] }),
+            ),
+        ]);
+        let request = serde_json::json!({ "input": ["{{text}}", "{{..}}"] });
+        let embedder = rest_embedder(url, request, BatchEncoding::JsonArray);
+
+        let start = Instant::now();
+        let embeddings = embedder.embed(vec!["kefir".to_string()], None).unwrap();
+        assert_eq!(embeddings, vec![vec![1.0, 0.0]]);
+        // without the header, the first retry after a rate limit waits less than 250ms
+        assert!(start.elapsed() >= Duration::from_secs(1), "{:?}", start.elapsed());
+        assert_eq!(
+            embedder.rate_limit_state(),
+            Some(RateLimitState { retry_after: None, remaining: Some(9) })
+        );
+    }
+
+    #[test]
+    fn retry_after_formats() {
+        assert_eq!(parse_retry_after("3"), Some(Duration::from_secs(3)));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::ZERO));
+        // a date in the past doesn't delay the retry
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT"), Some(Duration::ZERO));
+        let in_a_minute = time::OffsetDateTime::now_utc() + time::Duration::minutes(1);
+        let in_a_minute =
+            in_a_minute.format(&time::format_description::well_known::Rfc2822).unwrap();
+        let retry_after = parse_retry_after(&in_a_minute).unwrap();
+        assert!(retry_after > Duration::from_secs(50) && retry_after <= Duration::from_secs(60));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
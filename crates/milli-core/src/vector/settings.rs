@@ -6,8 +6,9 @@ use roaring::RoaringBitmap;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use super::composite::SubEmbedderOptions;
+use super::composite::{DistanceMetric, SubEmbedderOptions, TextPreprocess};
 use super::hf::OverridePooling;
+use super::rest::BatchEncoding;
 use super::{ollama, openai, DistributionShift, EmbedderOptions};
 use crate::prompt::{default_max_bytes, PromptData};
 use crate::update::Setting;
@@ -266,6 +267,92 @@ pub struct EmbeddingSettings {
     ///
     /// - 🌱 Changing the value of this parameter never regenerates embeddings
     pub headers: Setting<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    #[schema(value_type = Option<BatchEncoding>)]
+    /// How the texts of a batch are encoded in the body of the requests sent to the remote embedder.
+    ///
+    /// # Availability
+    ///
+    /// - This parameter is available for source `rest`
+    ///
+    /// # 🔄 Reindexing
+    ///
+    /// - 🏗️ Changing the value of this parameter always regenerates embeddings
+    ///
+    /// # Defaults
+    ///
+    /// - Defaults to `jsonArray`
+    pub batch_encoding: Setting<BatchEncoding>,
+
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    #[schema(value_type = Option<TextPreprocess>)]
+    /// Normalizations applied to the texts before embedding them, so that the texts differing
+    /// only by their case or spacing share their embedding.
+    ///
+    /// # Availability
+    ///
+    /// - This parameter is available for source `composite`, it applies to both the search
+    ///   and the indexing embedders
+    ///
+    /// # 🔄 Reindexing
+    ///
+    /// - 🏗️ Changing the value of this parameter always regenerates embeddings
+    ///
+    /// # Defaults
+    ///
+    /// - Defaults to no normalization
+    pub preprocess: Setting<TextPreprocess>,
+
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    #[schema(value_type = Option<DistanceMetric>)]
+    /// The distance metric comparing the embeddings of the search and indexing embedders
+    /// to check that they are similar.
+    ///
+    /// # Availability
+    ///
+    /// - This parameter is available for source `composite`
+    ///
+    /// # 🔄 Reindexing
+    ///
+    /// - 🌱 Changing the value of this parameter never regenerates embeddings
+    ///
+    /// # Defaults
+    ///
+    /// - Defaults to `cosine`
+    pub distance: Setting<DistanceMetric>,
+
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    #[schema(value_type = Option<String>)]
+    /// Text prepended to the queries before embedding them, e.g. `query: ` for the E5 models.
+    ///
+    /// # Availability
+    ///
+    /// - This parameter is available for source `composite`
+    ///
+    /// # 🔄 Reindexing
+    ///
+    /// - 🌱 Changing the value of this parameter never regenerates embeddings
+    pub search_prompt_prefix: Setting<String>,
+
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    #[schema(value_type = Option<String>)]
+    /// Text prepended to the rendered documents before embedding them, e.g. `passage: ` for the E5 models.
+    ///
+    /// The prefix counts in the `documentTemplateMaxBytes` of the indexing embedder.
+    ///
+    /// # Availability
+    ///
+    /// - This parameter is available for source `composite`
+    ///
+    /// # 🔄 Reindexing
+    ///
+    /// - 🏗️ Changing the value of this parameter always regenerates embeddings
+    pub index_prompt_prefix: Setting<String>,
 
     #[serde(default, skip_serializing_if = "Setting::is_not_set")]
     #[deserr(default)]
@@ -518,6 +605,61 @@ pub struct SubEmbeddingSettings {
     ///
     /// - 🌱 Changing the value of this parameter never regenerates embeddings
     pub headers: Setting<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    #[schema(value_type = Option<BatchEncoding>)]
+    /// How the texts of a batch are encoded in the body of the requests sent to the remote embedder.
+    ///
+    /// # Availability
+    ///
+    /// - This parameter is available for source `rest`
+    ///
+    /// # 🔄 Reindexing
+    ///
+    /// - 🏗️ Changing the value of this parameter always regenerates embeddings
+    ///
+    /// # Defaults
+    ///
+    /// - Defaults to `jsonArray`
+    pub batch_encoding: Setting<BatchEncoding>,
+
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    #[schema(value_type = Option<bool>)]
+    /// Whether the queries are binary quantized before being compared to the documents.
+    ///
+    /// # Availability
+    ///
+    /// - This parameter is only available in the `searchEmbedder` of a `composite` embedder whose
+    ///   embeddings are binary quantized
+    ///
+    /// # 🔄 Reindexing
+    ///
+    /// - 🌱 Changing the value of this parameter never regenerates embeddings
+    ///
+    /// # Defaults
+    ///
+    /// - Defaults to `false`
+    pub binary_quantized: Setting<bool>,
+
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    #[schema(value_type = Option<bool>)]
+    /// Whether the embeddings produced by this embedder are L2-normalized.
+    ///
+    /// # Availability
+    ///
+    /// - This parameter is only available in the `searchEmbedder` and `indexingEmbedder` of a `composite` embedder
+    ///
+    /// # 🔄 Reindexing
+    ///
+    /// - 🏗️ When modified in the `indexingEmbedder`, embeddings are always regenerated
+    /// - 🌱 When modified in the `searchEmbedder`, embeddings are never regenerated
+    ///
+    /// # Defaults
+    ///
+    /// - Defaults to `false`
+    pub normalize: Setting<bool>,
 
     // The following fields are provided for the sake of improving error handling
     // They should always be set to `NotSet`, otherwise an error will be returned
@@ -526,11 +668,6 @@ pub struct SubEmbeddingSettings {
     #[schema(ignore)]
     pub distribution: Setting<DistributionShift>,
 
-    #[serde(default, skip_serializing)]
-    #[deserr(default)]
-    #[schema(ignore)]
-    pub binary_quantized: Setting<bool>,
-
     #[serde(default, skip_serializing)]
     #[deserr(default)]
     #[schema(ignore)]
@@ -628,7 +765,12 @@ impl SettingsDiff {
                     mut indexing_embedder,
                     mut distribution,
                     mut headers,
+                    mut batch_encoding,
                     mut document_template_max_bytes,
+                    mut preprocess,
+                    mut distance,
+                    mut search_prompt_prefix,
+                    mut index_prompt_prefix,
                     binary_quantized: mut binary_quantize,
                 } = old;
 
@@ -647,7 +789,12 @@ impl SettingsDiff {
                     indexing_embedder: new_indexing_embedder,
                     distribution: new_distribution,
                     headers: new_headers,
+                    batch_encoding: new_batch_encoding,
                     document_template_max_bytes: new_document_template_max_bytes,
+                    preprocess: new_preprocess,
+                    distance: new_distance,
+                    search_prompt_prefix: new_search_prompt_prefix,
+                    index_prompt_prefix: new_index_prompt_prefix,
                     binary_quantized: new_binary_quantize,
                 } = new;
 
@@ -675,6 +822,7 @@ impl SettingsDiff {
                     &mut request,
                     &mut response,
                     &mut headers,
+                    &mut batch_encoding,
                     new_source,
                     new_model,
                     new_revision,
@@ -687,10 +835,23 @@ impl SettingsDiff {
                     new_request,
                     new_response,
                     new_headers,
+                    new_batch_encoding,
                 );
 
                 let binary_quantize_changed = binary_quantize.apply(new_binary_quantize);
 
+                // the texts of both the search and the indexing embedders are preprocessed
+                if preprocess.apply(new_preprocess) {
+                    ReindexAction::push_action(&mut reindex_action, ReindexAction::FullReindex);
+                }
+                // the prefix is prepended to the rendered documents, the prompts don't change
+                if index_prompt_prefix.apply(new_index_prompt_prefix) {
+                    ReindexAction::push_action(&mut reindex_action, ReindexAction::FullReindex);
+                }
+                search_prompt_prefix.apply(new_search_prompt_prefix);
+                // only used to check the similarity of the search and indexing embedders
+                distance.apply(new_distance);
+
                 // changes to the *search* embedder never triggers any reindexing
                 search_embedder.apply(new_search_embedder);
                 indexing_embedder = Self::from_sub_settings(
@@ -716,7 +877,12 @@ impl SettingsDiff {
                     indexing_embedder,
                     distribution,
                     headers,
+                    batch_encoding,
                     document_template_max_bytes,
+                    preprocess,
+                    distance,
+                    search_prompt_prefix,
+                    index_prompt_prefix,
                     binary_quantized: binary_quantize,
                 };
 
@@ -760,9 +926,11 @@ impl SettingsDiff {
                     mut request,
                     mut response,
                     mut headers,
+                    mut batch_encoding,
+                    mut binary_quantized,
+                    mut normalize,
                     // phony settings
                     mut distribution,
-                    mut binary_quantized,
                     mut search_embedder,
                     mut indexing_embedder,
                 }) = sub_embedder
@@ -785,8 +953,10 @@ impl SettingsDiff {
                     request: new_request,
                     response: new_response,
                     headers: new_headers,
+                    batch_encoding: new_batch_encoding,
                     distribution: new_distribution,
                     binary_quantized: new_binary_quantized,
+                    normalize: new_normalize,
                     search_embedder: new_search_embedder,
                     indexing_embedder: new_indexing_embedder,
                 } = new_sub_embedder;
@@ -805,6 +975,7 @@ impl SettingsDiff {
                     &mut request,
                     &mut response,
                     &mut headers,
+                    &mut batch_encoding,
                     new_source,
                     new_model,
                     new_revision,
@@ -817,11 +988,17 @@ impl SettingsDiff {
                     new_request,
                     new_response,
                     new_headers,
+                    new_batch_encoding,
                 );
 
+                // the quantization of the queries never triggers any reindexing
+                binary_quantized.apply(new_binary_quantized);
+                if normalize.apply(new_normalize) {
+                    ReindexAction::push_action(reindex_action, ReindexAction::FullReindex);
+                }
+
                 // update phony settings, it is always an error to have them set.
                 distribution.apply(new_distribution);
-                binary_quantized.apply(new_binary_quantized);
                 search_embedder.apply(new_search_embedder);
                 indexing_embedder.apply(new_indexing_embedder);
 
@@ -837,9 +1014,11 @@ impl SettingsDiff {
                     request,
                     response,
                     headers,
+                    batch_encoding,
                     document_template_max_bytes,
                     distribution,
                     binary_quantized,
+                    normalize,
                     search_embedder,
                     indexing_embedder,
                 };
@@ -866,6 +1045,7 @@ impl SettingsDiff {
         request: &mut Setting<serde_json::Value>,
         response: &mut Setting<serde_json::Value>,
         headers: &mut Setting<BTreeMap<String, String>>,
+        batch_encoding: &mut Setting<BatchEncoding>,
         new_source: Setting<EmbedderSource>,
         new_model: Setting<String>,
         new_revision: Setting<String>,
@@ -878,6 +1058,7 @@ impl SettingsDiff {
         new_request: Setting<serde_json::Value>,
         new_response: Setting<serde_json::Value>,
         new_headers: Setting<BTreeMap<String, String>>,
+        new_batch_encoding: Setting<BatchEncoding>,
     ) {
         // **Warning**: do not use short-circuiting || here, we want all these operations applied
         if source.apply(new_source) {
@@ -895,6 +1076,7 @@ impl SettingsDiff {
                 document_template,
                 document_template_max_bytes,
                 headers,
+                batch_encoding,
                 // send dummy values, the source cannot recursively be composite
                 &mut Setting::NotSet,
                 &mut Setting::NotSet,
@@ -935,6 +1117,9 @@ impl SettingsDiff {
         if response.apply(new_response) {
             ReindexAction::push_action(reindex_action, ReindexAction::FullReindex);
         }
+        if batch_encoding.apply(new_batch_encoding) {
+            ReindexAction::push_action(reindex_action, ReindexAction::FullReindex);
+        }
         if document_template.apply(new_document_template) {
             ReindexAction::push_action(reindex_action, ReindexAction::RegeneratePrompts);
         }
@@ -981,6 +1166,7 @@ fn apply_default_for_source(
     document_template: &mut Setting<String>,
     document_template_max_bytes: &mut Setting<usize>,
     headers: &mut Setting<BTreeMap<String, String>>,
+    batch_encoding: &mut Setting<BatchEncoding>,
     search_embedder: &mut Setting<SubEmbeddingSettings>,
     indexing_embedder: &mut Setting<SubEmbeddingSettings>,
 ) {
@@ -994,6 +1180,7 @@ fn apply_default_for_source(
             *request = Setting::NotSet;
             *response = Setting::NotSet;
             *headers = Setting::NotSet;
+            *batch_encoding = Setting::NotSet;
             *search_embedder = Setting::NotSet;
             *indexing_embedder = Setting::NotSet;
         }
@@ -1006,6 +1193,7 @@ fn apply_default_for_source(
             *request = Setting::NotSet;
             *response = Setting::NotSet;
             *headers = Setting::NotSet;
+            *batch_encoding = Setting::NotSet;
             *search_embedder = Setting::NotSet;
             *indexing_embedder = Setting::NotSet;
         }
@@ -1018,6 +1206,7 @@ fn apply_default_for_source(
             *request = Setting::NotSet;
             *response = Setting::NotSet;
             *headers = Setting::NotSet;
+            *batch_encoding = Setting::NotSet;
             *search_embedder = Setting::NotSet;
             *indexing_embedder = Setting::NotSet;
         }
@@ -1030,6 +1219,7 @@ fn apply_default_for_source(
             *request = Setting::Reset;
             *response = Setting::Reset;
             *headers = Setting::Reset;
+            *batch_encoding = Setting::Reset;
             *search_embedder = Setting::NotSet;
             *indexing_embedder = Setting::NotSet;
         }
@@ -1044,6 +1234,7 @@ fn apply_default_for_source(
             *document_template = Setting::NotSet;
             *document_template_max_bytes = Setting::NotSet;
             *headers = Setting::NotSet;
+            *batch_encoding = Setting::NotSet;
             *search_embedder = Setting::NotSet;
             *indexing_embedder = Setting::NotSet;
         }
@@ -1058,6 +1249,7 @@ fn apply_default_for_source(
             *document_template = Setting::NotSet;
             *document_template_max_bytes = Setting::NotSet;
             *headers = Setting::NotSet;
+            *batch_encoding = Setting::NotSet;
             *search_embedder = Setting::Reset;
             *indexing_embedder = Setting::Reset;
         }
@@ -1122,10 +1314,16 @@ pub enum MetaEmbeddingSetting {
     Request,
     Response,
     Headers,
+    BatchEncoding,
+    Preprocess,
+    Distance,
+    SearchPromptPrefix,
+    IndexPromptPrefix,
     SearchEmbedder,
     IndexingEmbedder,
     Distribution,
     BinaryQuantized,
+    Normalize,
 }
 
 impl MetaEmbeddingSetting {
@@ -1144,10 +1342,16 @@ impl MetaEmbeddingSetting {
             Request => "request",
             Response => "response",
             Headers => "headers",
+            BatchEncoding => "batchEncoding",
+            Preprocess => "preprocess",
+            Distance => "distance",
+            SearchPromptPrefix => "searchPromptPrefix",
+            IndexPromptPrefix => "indexPromptPrefix",
             SearchEmbedder => "searchEmbedder",
             IndexingEmbedder => "indexingEmbedder",
             Distribution => "distribution",
             BinaryQuantized => "binaryQuantized",
+            Normalize => "normalize",
         }
     }
 }
@@ -1169,9 +1373,15 @@ impl EmbeddingSettings {
         document_template: &Setting<String>,
         document_template_max_bytes: &Setting<usize>,
         headers: &Setting<BTreeMap<String, String>>,
+        batch_encoding: &Setting<BatchEncoding>,
+        preprocess: &Setting<TextPreprocess>,
+        distance: &Setting<DistanceMetric>,
+        search_prompt_prefix: &Setting<String>,
+        index_prompt_prefix: &Setting<String>,
         search_embedder: &Setting<SubEmbeddingSettings>,
         indexing_embedder: &Setting<SubEmbeddingSettings>,
         binary_quantized: &Setting<bool>,
+        normalize: &Setting<bool>,
         distribution: &Setting<DistributionShift>,
     ) -> Result<(), UserError> {
         Self::check_setting(embedder_name, source, MetaEmbeddingSetting::Model, context, model)?;
@@ -1233,6 +1443,41 @@ impl EmbeddingSettings {
             context,
             headers,
         )?;
+        Self::check_setting(
+            embedder_name,
+            source,
+            MetaEmbeddingSetting::BatchEncoding,
+            context,
+            batch_encoding,
+        )?;
+        Self::check_setting(
+            embedder_name,
+            source,
+            MetaEmbeddingSetting::Preprocess,
+            context,
+            preprocess,
+        )?;
+        Self::check_setting(
+            embedder_name,
+            source,
+            MetaEmbeddingSetting::Distance,
+            context,
+            distance,
+        )?;
+        Self::check_setting(
+            embedder_name,
+            source,
+            MetaEmbeddingSetting::SearchPromptPrefix,
+            context,
+            search_prompt_prefix,
+        )?;
+        Self::check_setting(
+            embedder_name,
+            source,
+            MetaEmbeddingSetting::IndexPromptPrefix,
+            context,
+            index_prompt_prefix,
+        )?;
         Self::check_setting(
             embedder_name,
             source,
@@ -1254,6 +1499,13 @@ impl EmbeddingSettings {
             context,
             binary_quantized,
         )?;
+        Self::check_setting(
+            embedder_name,
+            source,
+            MetaEmbeddingSetting::Normalize,
+            context,
+            normalize,
+        )?;
         Self::check_setting(
             embedder_name,
             source,
@@ -1321,8 +1573,22 @@ impl EmbeddingSettings {
         use NestingContext::*;
         match (source, field, context) {
             (_, Distribution | BinaryQuantized, NotNested) => FieldStatus::Allowed,
+            // the quantization of the queries, checked against the index when building the embedder
+            (_, BinaryQuantized, Search) => FieldStatus::Allowed,
             (_, Distribution | BinaryQuantized, _) => FieldStatus::Disallowed,
             (_, DocumentTemplate | DocumentTemplateMaxBytes, Search) => FieldStatus::Disallowed,
+            // applies to both the search and the indexing embedders of a composite embedder
+            (
+                Composite,
+                Preprocess | Distance | SearchPromptPrefix | IndexPromptPrefix,
+                NotNested,
+            ) => FieldStatus::Allowed,
+            (_, Preprocess | Distance | SearchPromptPrefix | IndexPromptPrefix, _) => {
+                FieldStatus::Disallowed
+            }
+            // only applied to the embeddings of the sub-embedders of a composite embedder
+            (_, Normalize, Search | Indexing) => FieldStatus::Allowed,
+            (_, Normalize, NotNested) => FieldStatus::Disallowed,
             (
                 OpenAi,
                 Source
@@ -1336,7 +1602,7 @@ impl EmbeddingSettings {
             ) => FieldStatus::Allowed,
             (
                 OpenAi,
-                Revision | Pooling | Request | Response | Headers | SearchEmbedder
+                Revision | Pooling | Request | Response | Headers | BatchEncoding | SearchEmbedder
                 | IndexingEmbedder,
                 _,
             ) => FieldStatus::Disallowed,
@@ -1347,8 +1613,8 @@ impl EmbeddingSettings {
             ) => FieldStatus::Allowed,
             (
                 HuggingFace,
-                ApiKey | Dimensions | Url | Request | Response | Headers | SearchEmbedder
-                | IndexingEmbedder,
+                ApiKey | Dimensions | Url | Request | Response | Headers | BatchEncoding
+                | SearchEmbedder | IndexingEmbedder,
                 _,
             ) => FieldStatus::Disallowed,
             (Ollama, Model, _) => FieldStatus::Mandatory,
@@ -1359,7 +1625,7 @@ impl EmbeddingSettings {
             ) => FieldStatus::Allowed,
             (
                 Ollama,
-                Revision | Pooling | Request | Response | Headers | SearchEmbedder
+                Revision | Pooling | Request | Response | Headers | BatchEncoding | SearchEmbedder
                 | IndexingEmbedder,
                 _,
             ) => FieldStatus::Disallowed,
@@ -1377,6 +1643,7 @@ impl EmbeddingSettings {
                 | Request
                 | Response
                 | Headers
+                | BatchEncoding
                 | SearchEmbedder
                 | IndexingEmbedder,
                 _,
@@ -1389,7 +1656,8 @@ impl EmbeddingSettings {
                 | Dimensions
                 | DocumentTemplate
                 | DocumentTemplateMaxBytes
-                | Headers,
+                | Headers
+                | BatchEncoding,
                 _,
             ) => FieldStatus::Allowed,
             (Rest, Model | Revision | Pooling | SearchEmbedder | IndexingEmbedder, _) => {
@@ -1409,7 +1677,8 @@ impl EmbeddingSettings {
                 | Url
                 | Request
                 | Response
-                | Headers,
+                | Headers
+                | BatchEncoding,
                 _,
             ) => FieldStatus::Disallowed,
         }
@@ -1503,11 +1772,12 @@ impl std::fmt::Display for EmbedderSource {
 impl EmbeddingSettings {
     fn from_hugging_face(
         super::hf::EmbedderOptions {
-        model,
-        revision,
-        distribution,
-        pooling,
-    }: super::hf::EmbedderOptions,
+            model,
+            revision,
+            distribution,
+            pooling,
+            normalize: _,
+        }: super::hf::EmbedderOptions,
         document_template: Setting<String>,
         document_template_max_bytes: Setting<usize>,
         quantized: Option<bool>,
@@ -1525,6 +1795,11 @@ impl EmbeddingSettings {
             request: Setting::NotSet,
             response: Setting::NotSet,
             headers: Setting::NotSet,
+            batch_encoding: Setting::NotSet,
+            preprocess: Setting::NotSet,
+            distance: Setting::NotSet,
+            search_prompt_prefix: Setting::NotSet,
+            index_prompt_prefix: Setting::NotSet,
             search_embedder: Setting::NotSet,
             indexing_embedder: Setting::NotSet,
             distribution: Setting::some_or_not_set(distribution),
@@ -1539,6 +1814,7 @@ impl EmbeddingSettings {
             embedding_model,
             dimensions,
             distribution,
+            normalize: _,
         }: super::openai::EmbedderOptions,
         document_template: Setting<String>,
         document_template_max_bytes: Setting<usize>,
@@ -1557,6 +1833,11 @@ impl EmbeddingSettings {
             request: Setting::NotSet,
             response: Setting::NotSet,
             headers: Setting::NotSet,
+            batch_encoding: Setting::NotSet,
+            preprocess: Setting::NotSet,
+            distance: Setting::NotSet,
+            search_prompt_prefix: Setting::NotSet,
+            index_prompt_prefix: Setting::NotSet,
             search_embedder: Setting::NotSet,
             indexing_embedder: Setting::NotSet,
             distribution: Setting::some_or_not_set(distribution),
@@ -1566,11 +1847,12 @@ impl EmbeddingSettings {
 
     fn from_ollama(
         super::ollama::EmbedderOptions {
-          embedding_model,
-          url,
-          api_key,
-          distribution,
-          dimensions,
+            embedding_model,
+            url,
+            api_key,
+            distribution,
+            dimensions,
+            normalize: _,
         }: super::ollama::EmbedderOptions,
         document_template: Setting<String>,
         document_template_max_bytes: Setting<usize>,
@@ -1589,6 +1871,11 @@ impl EmbeddingSettings {
             request: Setting::NotSet,
             response: Setting::NotSet,
             headers: Setting::NotSet,
+            batch_encoding: Setting::NotSet,
+            preprocess: Setting::NotSet,
+            distance: Setting::NotSet,
+            search_prompt_prefix: Setting::NotSet,
+            index_prompt_prefix: Setting::NotSet,
             search_embedder: Setting::NotSet,
             indexing_embedder: Setting::NotSet,
             distribution: Setting::some_or_not_set(distribution),
@@ -1597,7 +1884,7 @@ impl EmbeddingSettings {
     }
 
     fn from_user_provided(
-        super::manual::EmbedderOptions { dimensions, distribution }: super::manual::EmbedderOptions,
+        super::manual::EmbedderOptions { dimensions, distribution, normalize: _ }: super::manual::EmbedderOptions,
         quantized: Option<bool>,
     ) -> Self {
         Self {
@@ -1613,6 +1900,11 @@ impl EmbeddingSettings {
             request: Setting::NotSet,
             response: Setting::NotSet,
             headers: Setting::NotSet,
+            batch_encoding: Setting::NotSet,
+            preprocess: Setting::NotSet,
+            distance: Setting::NotSet,
+            search_prompt_prefix: Setting::NotSet,
+            index_prompt_prefix: Setting::NotSet,
             search_embedder: Setting::NotSet,
             indexing_embedder: Setting::NotSet,
             distribution: Setting::some_or_not_set(distribution),
@@ -1629,6 +1921,8 @@ impl EmbeddingSettings {
             response,
             distribution,
             headers,
+            normalize: _,
+            batch_encoding,
         }: super::rest::EmbedderOptions,
         document_template: Setting<String>,
         document_template_max_bytes: Setting<usize>,
@@ -1648,6 +1942,11 @@ impl EmbeddingSettings {
             response: Setting::Set(response),
             distribution: Setting::some_or_not_set(distribution),
             headers: Setting::Set(headers),
+            batch_encoding: Setting::Set(batch_encoding),
+            preprocess: Setting::NotSet,
+            distance: Setting::NotSet,
+            search_prompt_prefix: Setting::NotSet,
+            index_prompt_prefix: Setting::NotSet,
             search_embedder: Setting::NotSet,
             indexing_embedder: Setting::NotSet,
             binary_quantized: Setting::some_or_not_set(quantized),
@@ -1691,6 +1990,12 @@ impl From<EmbeddingConfig> for EmbeddingSettings {
             super::EmbedderOptions::Composite(super::composite::EmbedderOptions {
                 search,
                 index,
+                distance,
+                search_prompt_prefix,
+                index_prompt_prefix,
+                preprocess,
+                search_binary_quantized,
+                index_binary_quantized: _,
             }) => Self {
                 source: Setting::Set(EmbedderSource::Composite),
                 model: Setting::NotSet,
@@ -1705,12 +2010,20 @@ impl From<EmbeddingConfig> for EmbeddingSettings {
                 request: Setting::NotSet,
                 response: Setting::NotSet,
                 headers: Setting::NotSet,
+                batch_encoding: Setting::NotSet,
+                preprocess: Setting::some_or_not_set((!preprocess.is_none()).then_some(preprocess)),
+                distance: Setting::some_or_not_set(
+                    (distance != super::composite::DistanceMetric::default()).then_some(distance),
+                ),
+                search_prompt_prefix: Setting::some_or_not_set(search_prompt_prefix),
+                index_prompt_prefix: Setting::some_or_not_set(index_prompt_prefix),
                 distribution: Setting::some_or_not_set(search.distribution()),
-                search_embedder: Setting::Set(SubEmbeddingSettings::from_options(
-                    search,
-                    Setting::NotSet,
-                    Setting::NotSet,
-                )),
+                search_embedder: Setting::Set(SubEmbeddingSettings {
+                    binary_quantized: Setting::some_or_not_set(
+                        search_binary_quantized.then_some(true),
+                    ),
+                    ..SubEmbeddingSettings::from_options(search, Setting::NotSet, Setting::NotSet)
+                }),
                 indexing_embedder: Setting::Set(SubEmbeddingSettings::from_options(
                     index,
                     Setting::Set(prompt.template),
@@ -1727,6 +2040,7 @@ impl SubEmbeddingSettings {
         document_template: Setting<String>,
         document_template_max_bytes: Setting<usize>,
     ) -> Self {
+        let normalize = Setting::some_or_not_set(options.normalize().then_some(true));
         let settings = match options {
             SubEmbedderOptions::HuggingFace(embedder_options) => {
                 EmbeddingSettings::from_hugging_face(
@@ -1758,7 +2072,7 @@ impl SubEmbeddingSettings {
                 None,
             ),
         };
-        settings.into()
+        Self { normalize, ..settings.into() }
     }
 }
 
@@ -1777,6 +2091,11 @@ impl From<EmbeddingSettings> for SubEmbeddingSettings {
             request,
             response,
             headers,
+            batch_encoding,
+            preprocess: _,
+            distance: _,
+            search_prompt_prefix: _,
+            index_prompt_prefix: _,
             binary_quantized: _,
             search_embedder: _,
             indexing_embedder: _,
@@ -1795,8 +2114,10 @@ impl From<EmbeddingSettings> for SubEmbeddingSettings {
             request,
             response,
             headers,
+            batch_encoding,
             distribution: Setting::NotSet,
             binary_quantized: Setting::NotSet,
+            normalize: Setting::NotSet,
             search_embedder: Setting::NotSet,
             indexing_embedder: Setting::NotSet,
         }
@@ -1820,6 +2141,11 @@ impl From<EmbeddingSettings> for EmbeddingConfig {
             response,
             distribution,
             headers,
+            batch_encoding,
+            preprocess,
+            distance,
+            search_prompt_prefix,
+            index_prompt_prefix,
             binary_quantized,
             search_embedder,
             mut indexing_embedder,
@@ -1870,21 +2196,28 @@ impl From<EmbeddingSettings> for EmbeddingConfig {
                     request.set().unwrap(),
                     response.set().unwrap(),
                     headers,
+                    batch_encoding,
                     dimensions,
                     distribution,
                 )
                 .into(),
                 EmbedderSource::Composite => {
+                    let search_embedder = search_embedder.set().unwrap();
+                    let search_binary_quantized =
+                        search_embedder.binary_quantized.set().unwrap_or_default();
                     super::EmbedderOptions::Composite(super::composite::EmbedderOptions {
                         // it is important to give the distribution to the search here, as this is from where we'll retrieve it
-                        search: SubEmbedderOptions::from_settings(
-                            search_embedder.set().unwrap(),
-                            distribution,
-                        ),
+                        search: SubEmbedderOptions::from_settings(search_embedder, distribution),
                         index: SubEmbedderOptions::from_settings(
                             indexing_embedder.set().unwrap(),
                             Setting::NotSet,
                         ),
+                        distance: distance.set().unwrap_or_default(),
+                        search_prompt_prefix: search_prompt_prefix.set(),
+                        index_prompt_prefix: index_prompt_prefix.set(),
+                        preprocess: preprocess.set().unwrap_or_default(),
+                        search_binary_quantized,
+                        index_binary_quantized: this.quantized.unwrap_or_default(),
                     })
                 }
             };
@@ -1913,14 +2246,17 @@ impl SubEmbedderOptions {
             request,
             response,
             headers,
+            batch_encoding,
+            // retrieved by the composite options
+            binary_quantized: _,
+            normalize,
             // phony parameters
             distribution: _,
-            binary_quantized: _,
             search_embedder: _,
             indexing_embedder: _,
         } = settings;
 
-        match source.set().unwrap() {
+        let mut options = match source.set().unwrap() {
             EmbedderSource::OpenAi => Self::openai(model, url, api_key, dimensions, distribution),
             EmbedderSource::HuggingFace => {
                 Self::hugging_face(model, revision, pooling, distribution)
@@ -1935,11 +2271,16 @@ impl SubEmbedderOptions {
                 request.set().unwrap(),
                 response.set().unwrap(),
                 headers,
+                batch_encoding,
                 dimensions,
                 distribution,
             ),
             EmbedderSource::Composite => panic!("nested composite embedders"),
+        };
+        if let Some(normalize) = normalize.set() {
+            options.set_normalize(normalize);
         }
+        options
     }
 
     fn openai(
@@ -1996,14 +2337,17 @@ impl SubEmbedderOptions {
         Self::UserProvided(super::manual::EmbedderOptions {
             dimensions,
             distribution: distribution.set(),
+            normalize: false,
         })
     }
+    #[allow(clippy::too_many_arguments)]
     fn rest(
         url: String,
         api_key: Setting<String>,
         request: serde_json::Value,
         response: serde_json::Value,
         headers: Setting<BTreeMap<String, String>>,
+        batch_encoding: Setting<BatchEncoding>,
         dimensions: Setting<usize>,
         distribution: Setting<DistributionShift>,
     ) -> Self {
@@ -2015,6 +2359,8 @@ impl SubEmbedderOptions {
             response,
             distribution: distribution.set(),
             headers: headers.set().unwrap_or_default(),
+            normalize: false,
+            batch_encoding: batch_encoding.set().unwrap_or_default(),
         })
     }
     fn ollama(
@@ -0,0 +1,65 @@
+//! A minimal HTTP server answering the requests of the REST embedders in the tests.
+
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// A request received by the server.
+pub struct Request {
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+/// A response sent by the server, always with a JSON body.
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(&'static str, &'static str)>,
+    pub body: serde_json::Value,
+}
+
+impl Response {
+    /// A `200` response without additional headers.
+    pub fn ok(body: serde_json::Value) -> Self {
+        Response { status: 200, headers: Vec::new(), body }
+    }
+}
+
+/// Serves the response returned by `respond` to each request, in the order they are received.
+///
+/// Returns the url of the `/embed` route of the server, which runs until the end of the tests.
+pub fn serve(mut respond: impl FnMut(Request) -> Response + Send + 'static) -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/embed", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let mut content_length = 0;
+            let mut content_type = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    } else if name.eq_ignore_ascii_case("content-type") {
+                        content_type = value.trim().to_string();
+                    }
+                }
+                if line.trim().is_empty() {
+                    break;
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let Response { status, headers, body } = respond(Request { content_type, body });
+            let body = body.to_string();
+            let headers: String =
+                headers.iter().flat_map(|(name, value)| [*name, ": ", *value, "\r\n"]).collect();
+            let response = format!(
+                "HTTP/1.1 {status} Status\r\nContent-Type: application/json\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+        }
+    });
+    url
+}